@@ -1,7 +1,7 @@
 use criterion::{Criterion, criterion_group, criterion_main};
-use media_analyzer::MediaAnalyzer;
+use media_analyzer::{AnalyzerPool, MediaAnalyzer};
 use std::hint::black_box;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use tokio::runtime::Runtime;
 
 fn bench(c: &mut Criterion) {
@@ -28,6 +28,61 @@ fn bench(c: &mut Criterion) {
             });
         });
     });
+
+    // Every analysis above was of the same file, so its location was searched at most once.
+    assert!(media_analyzer.geocode_cache_len() <= 1);
+
+    c.bench_function("media_analyzer.reverse_geocode (cached)", |b| {
+        b.iter(|| media_analyzer.reverse_geocode(black_box(40.8209), black_box(14.4228)));
+    });
+
+    let uncached_analyzer = rt.block_on(async {
+        MediaAnalyzer::builder()
+            .geocode_cache_size(0)
+            .build()
+            .await
+            .unwrap()
+    });
+    c.bench_function("media_analyzer.reverse_geocode (uncached)", |b| {
+        b.iter(|| uncached_analyzer.reverse_geocode(black_box(40.8209), black_box(14.4228)));
+    });
+
+    c.bench_function("media_analyzer.time_info", |b| {
+        b.iter(|| {
+            let _ = media_analyzer.time_info(black_box(image_path)).unwrap();
+        });
+    });
+
+    c.bench_function("media_analyzer.probe", |b| {
+        b.iter(|| {
+            let _ = media_analyzer.probe(black_box(image_path)).unwrap();
+        });
+    });
+
+    // Every file in the assets directory, 20 times over.
+    let assets: Vec<PathBuf> = walkdir::WalkDir::new("./assets")
+        .into_iter()
+        .filter_map(Result::ok)
+        .filter(|entry| entry.file_type().is_file())
+        .map(walkdir::DirEntry::into_path)
+        .collect();
+    let batch: Vec<&PathBuf> = std::iter::repeat_n(&assets, 20).flatten().collect();
+    let mut group = c.benchmark_group("analyzer_pool.analyze_many (assets x20)");
+    group.sample_size(10);
+    for size in [1, 4] {
+        let pool = rt.block_on(async {
+            let analyzer = MediaAnalyzer::builder()
+                .weather(false)
+                .build()
+                .await
+                .unwrap();
+            AnalyzerPool::new(size, analyzer).unwrap()
+        });
+        group.bench_function(format!("pool size {size}"), |b| {
+            b.iter(|| rt.block_on(pool.analyze_many(black_box(&batch), 8)));
+        });
+    }
+    group.finish();
 }
 
 criterion_group!(benches, bench);
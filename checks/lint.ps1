@@ -65,6 +65,10 @@ try {
     if ($LASTEXITCODE -ne 0) {
         $checksPassed = $false
     }
+    cargo clippy --all-targets --features metrics -- -D warnings
+    if ($LASTEXITCODE -ne 0) {
+        $checksPassed = $false
+    }
 
     # Documentation check
     Write-Host "`n=== Checking documentation ===" -ForegroundColor Cyan
@@ -0,0 +1,16 @@
+use media_analyzer::{MediaAnalyzer, MediaAnalyzerError, web};
+use std::sync::Arc;
+
+/// Serves `POST /analyze` on port 3000. Try it with:
+/// `curl -F file=@assets/sunset.jpg http://localhost:3000/analyze`
+#[tokio::main]
+async fn main() -> Result<(), MediaAnalyzerError> {
+    let analyzer = MediaAnalyzer::builder().build().await?;
+    let app = web::router(Arc::new(analyzer));
+
+    let listener = tokio::net::TcpListener::bind("0.0.0.0:3000").await?;
+    println!("Listening on http://{}", listener.local_addr()?);
+    axum::serve(listener, app).await?;
+
+    Ok(())
+}
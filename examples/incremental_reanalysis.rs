@@ -0,0 +1,38 @@
+use media_analyzer::{MediaAnalyzer, MediaAnalyzerError, read_ndjson, write_ndjson};
+use std::fs::File;
+use std::io::{BufReader, BufWriter};
+use std::path::Path;
+
+/// Re-analyzes only the stored results that are stale after a crate upgrade or a configuration
+/// change, keeping the others as they are.
+#[tokio::main]
+async fn main() -> Result<(), MediaAnalyzerError> {
+    let stored_path = Path::new("results.ndjson");
+    let analyzer = MediaAnalyzer::builder().build().await?;
+    let policy = analyzer.staleness_policy();
+
+    let stored: Vec<_> = read_ndjson(BufReader::new(File::open(stored_path)?))
+        .collect::<Result<_, _>>()
+        .expect("stored results are valid");
+    let mut results = Vec::with_capacity(stored.len());
+    let mut reanalyzed = 0;
+    for result in stored {
+        if !result.is_stale(&policy) {
+            results.push(result);
+            continue;
+        }
+        // The path exiftool read the file from when it was first analyzed.
+        let source = result.exif["SourceFile"].as_str().map(str::to_string);
+        match source {
+            Some(source) => {
+                results.push(analyzer.analyze_media(Path::new(&source)).await?);
+                reanalyzed += 1;
+            }
+            None => results.push(result),
+        }
+    }
+
+    write_ndjson(&results, BufWriter::new(File::create(stored_path)?))?;
+    println!("Re-analyzed {reanalyzed} of {} results", results.len());
+    Ok(())
+}
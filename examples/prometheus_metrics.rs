@@ -0,0 +1,27 @@
+//! Analyzes a few files and prints the metrics they produced in the Prometheus text format.
+//! A server would instead serve `handle.render()` on a `/metrics` endpoint.
+//!
+//! Run with `cargo run --example prometheus_metrics --features metrics`.
+
+use media_analyzer::MediaAnalyzer;
+use metrics_exporter_prometheus::PrometheusBuilder;
+use std::path::Path;
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let handle = PrometheusBuilder::new().install_recorder()?;
+    let analyzer = MediaAnalyzer::builder().build().await?;
+
+    for path in [
+        "assets/sunset.jpg",
+        "assets/tent.jpg",
+        "assets/text_file.txt",
+    ] {
+        if let Err(e) = analyzer.analyze_media(Path::new(path)).await {
+            eprintln!("{path}: {e}");
+        }
+    }
+
+    println!("{}", handle.render());
+    Ok(())
+}
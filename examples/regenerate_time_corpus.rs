@@ -0,0 +1,42 @@
+//! Regenerates the expectations of the time corpus in `tests/time_corpus` from the current time
+//! logic. Without `--write` it only lists the cases that would change.
+//!
+//! Run with `cargo run --example regenerate_time_corpus -- --write`, and review the diff of
+//! the expectations before committing it.
+
+#[path = "../tests/support/time_corpus.rs"]
+mod time_corpus;
+
+use serde_json::Value;
+use std::fs;
+use time_corpus::{CORPUS_DIR, load_cases, orphaned_expectations};
+
+fn main() -> std::io::Result<()> {
+    let write = std::env::args().any(|arg| arg == "--write");
+    let cases = load_cases();
+    let mut changed = 0;
+    for case in &cases {
+        let actual = case.resolve();
+        let expected: Option<Value> = fs::read_to_string(case.expected_path())
+            .ok()
+            .and_then(|text| serde_json::from_str(&text).ok());
+        if expected.as_ref() == Some(&actual) {
+            continue;
+        }
+        changed += 1;
+        println!("{}: {}", case.name, case.description);
+        if write {
+            let json = serde_json::to_string_pretty(&actual).expect("JSON values serialize");
+            fs::write(case.expected_path(), json + "\n")?;
+        }
+    }
+    for orphan in orphaned_expectations(&cases) {
+        println!("{orphan} has no case in {CORPUS_DIR}");
+    }
+    match (changed, write) {
+        (0, _) => println!("All {} expectations are up to date.", cases.len()),
+        (_, true) => println!("Wrote {changed} of {} expectations.", cases.len()),
+        (_, false) => println!("{changed} expectations would change, rerun with --write."),
+    }
+    Ok(())
+}
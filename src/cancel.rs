@@ -0,0 +1,72 @@
+//! Cooperative cancellation of analyses, see
+//! [`MediaAnalyzer::analyze_media_cancellable`](crate::MediaAnalyzer::analyze_media_cancellable).
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use tokio::sync::Notify;
+
+/// Cancels the analyses it was passed to, from another task or thread, like the cancel button of
+/// an import. Clones share their state: cancelling one cancels all.
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken {
+    inner: Arc<Inner>,
+}
+
+#[derive(Debug, Default)]
+struct Inner {
+    cancelled: AtomicBool,
+    notify: Notify,
+}
+
+impl CancellationToken {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Cancels the analyses using this token. Analyses that start later fail right away.
+    pub fn cancel(&self) {
+        self.inner.cancelled.store(true, Ordering::SeqCst);
+        self.inner.notify.notify_waiters();
+    }
+
+    #[must_use]
+    pub fn is_cancelled(&self) -> bool {
+        self.inner.cancelled.load(Ordering::SeqCst)
+    }
+
+    /// Completes once the token is cancelled.
+    pub async fn cancelled(&self) {
+        loop {
+            // Registered before the flag is read, so a `cancel` in between isn't missed.
+            let notified = self.inner.notify.notified();
+            if self.is_cancelled() {
+                return;
+            }
+            notified.await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_cancel_wakes_waiters_and_clones() {
+        let token = CancellationToken::new();
+        let clone = token.clone();
+        let waiter = tokio::spawn(async move { clone.cancelled().await });
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert!(!token.is_cancelled());
+
+        token.cancel();
+        tokio::time::timeout(Duration::from_secs(1), waiter)
+            .await
+            .unwrap()
+            .unwrap();
+        // Already cancelled: completes right away.
+        token.cancelled().await;
+    }
+}
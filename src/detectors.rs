@@ -0,0 +1,82 @@
+//! The detections of the analyzer as pure functions over `exiftool` JSON, for metadata that was
+//! read elsewhere. None of them run `exiftool` or touch the file system.
+//!
+//! # Stability
+//!
+//! The signatures of these functions follow semver like the rest of the crate. What they detect
+//! is not frozen: rules are added and tuned between releases, which bumps
+//! [`LogicRevisions::tags`](crate::LogicRevisions), or `time` for [`resolve_time`]. Their output types are `#[non_exhaustive]`,
+//! so new fields and evidence variants are not breaking changes.
+//!
+//! The JSON is expected in the shape of `exiftool -j -n -g2`, but tags are found in any group
+//! and at the top level too.
+
+use crate::ExifData;
+use crate::features::filename::FilenameCandidates;
+use crate::features::gps::get_gps_coordinates;
+use crate::tags::drive_mode::get_drive_mode;
+use crate::time::error::TimeError;
+use crate::time::get_time_info;
+use crate::time::structs::{TimeInfo, TimeOptions};
+use std::path::Path;
+
+pub use crate::features::filename::FilenameKind;
+pub use crate::features::pano::{
+    get_pano_view_info, pano_viewer_evidence, spherical_video_evidence, stereo_pano_evidence,
+};
+pub use crate::tags::burst::BurstEvidence;
+pub use crate::tags::hdr::{HdrDetection, HdrEvidence, VideoTransfer, detect_hdr_type};
+pub use crate::tags::logic::extract_features;
+
+/// Finds out whether a photo is part of a burst, from burst tags, a continuous drive sequence
+/// in the maker notes, or a `_BURST` filename. `path` is only used for its filename.
+///
+/// ```
+/// use media_analyzer::ExifData;
+/// use media_analyzer::detectors::{BurstEvidence, find_burst};
+/// use serde_json::json;
+/// use std::path::Path;
+///
+/// let exif = ExifData::new(json!({
+///     "Image": { "BurstUUID": "5A1C2D3E-0F1B-4C5D-9E8F-112233445566" }
+/// }));
+/// let burst = find_burst(Path::new("IMG_0042.HEIC"), &exif).unwrap();
+/// assert!(matches!(burst, BurstEvidence::ExifTag { tag: "BurstUUID", .. }));
+/// assert_eq!(
+///     burst.burst_id().as_deref(),
+///     Some("5A1C2D3E-0F1B-4C5D-9E8F-112233445566")
+/// );
+///
+/// let exif = ExifData::new(json!({}));
+/// assert!(find_burst(Path::new("IMG_0042.HEIC"), &exif).is_none());
+/// ```
+#[must_use]
+pub fn find_burst(path: &Path, exif: &ExifData) -> Option<BurstEvidence> {
+    let filenames = FilenameCandidates::new(path, exif);
+    crate::tags::burst::burst_evidence(exif, &get_drive_mode(exif), &filenames)
+}
+
+/// Resolves the capture time like [`MediaAnalyzer::time_info`](crate::MediaAnalyzer::time_info),
+/// with the GPS coordinates in the tags but without hooks.
+///
+/// ```
+/// use media_analyzer::detectors::resolve_time;
+/// use media_analyzer::{ExifData, TimeOptions};
+/// use serde_json::json;
+///
+/// let exif = ExifData::new(json!({
+///     "Time": {
+///         "DateTimeOriginal": "2024:06:01 10:00:00",
+///         "OffsetTimeOriginal": "+02:00"
+///     }
+/// }));
+/// let time = resolve_time(&exif, &TimeOptions::default()).unwrap();
+/// assert_eq!(time.utc_rfc3339().as_deref(), Some("2024-06-01T08:00:00Z"));
+/// ```
+///
+/// # Errors
+///
+/// [`TimeError::Extraction`] when no usable time is found in any source.
+pub fn resolve_time(exif: &ExifData, options: &TimeOptions) -> Result<TimeInfo, TimeError> {
+    get_time_info(exif, get_gps_coordinates(exif).as_ref(), options)
+}
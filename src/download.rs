@@ -0,0 +1,316 @@
+//! Streams media from a URL, such as a presigned object storage URL, to a temporary file.
+
+use crate::MediaAnalyzerError;
+use reqwest::Response;
+use reqwest::header::CONTENT_DISPOSITION;
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+use std::time::Duration;
+
+/// The default maximum size of a download: 2GiB.
+pub const DEFAULT_MAX_DOWNLOAD_BYTES: u64 = 2 * 1024 * 1024 * 1024;
+/// The file name used when neither the response nor the URL has one.
+const FALLBACK_FILENAME: &str = "download";
+/// How long connecting to a server may take.
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+/// How long a response may stall between two reads.
+const READ_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// The client for downloads, which gives up on servers that stop responding.
+pub fn client() -> reqwest::Result<reqwest::Client> {
+    reqwest::Client::builder()
+        .connect_timeout(CONNECT_TIMEOUT)
+        .read_timeout(READ_TIMEOUT)
+        .build()
+}
+
+/// `url` without its query string and fragment, which hold the signature of a presigned URL,
+/// so it can be logged.
+fn redact_url(url: &str) -> String {
+    match reqwest::Url::parse(url) {
+        Ok(mut parsed) => {
+            parsed.set_query(None);
+            parsed.set_fragment(None);
+            parsed.to_string()
+        }
+        Err(_) => url.split(['?', '#']).next().unwrap_or_default().to_string(),
+    }
+}
+
+fn download_error(url: &str, status: Option<u16>, reason: String) -> MediaAnalyzerError {
+    MediaAnalyzerError::Download {
+        url: redact_url(url),
+        status,
+        reason,
+    }
+}
+
+/// The message of a request error, without the URL it carries.
+fn request_error(url: &str, error: reqwest::Error) -> MediaAnalyzerError {
+    let status = error.status().map(|status| status.as_u16());
+    download_error(url, status, error.without_url().to_string())
+}
+
+/// Requests `url`, failing when the server responds with anything but success.
+pub async fn get(client: &reqwest::Client, url: &str) -> Result<Response, MediaAnalyzerError> {
+    let response = client
+        .get(url)
+        .send()
+        .await
+        .map_err(|e| request_error(url, e))?;
+    let status = response.status();
+    if !status.is_success() {
+        return Err(download_error(
+            url,
+            Some(status.as_u16()),
+            status.to_string(),
+        ));
+    }
+    Ok(response)
+}
+
+/// Decodes `%XX` escapes. Invalid escapes are kept as they are.
+fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = (bytes[i] == b'%')
+            .then(|| bytes.get(i + 1..i + 3))
+            .flatten()
+            .and_then(|hex| u8::from_str_radix(std::str::from_utf8(hex).ok()?, 16).ok());
+        if let Some(byte) = escaped {
+            decoded.push(byte);
+            i += 3;
+        } else {
+            decoded.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// The file name in a `Content-Disposition` header. The RFC 5987 `filename*` wins over
+/// `filename`.
+fn content_disposition_filename(header: &str) -> Option<String> {
+    let params: Vec<(&str, &str)> = header
+        .split(';')
+        .filter_map(|param| param.trim().split_once('='))
+        .map(|(name, value)| (name.trim(), value.trim()))
+        .collect();
+    let find = |wanted: &str| {
+        params
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(wanted))
+            .map(|(_, value)| *value)
+    };
+    let extended = find("filename*").and_then(|value| {
+        // charset'language'encoded-name
+        let (_, encoded) = value.rsplit_once('\'')?;
+        Some(percent_decode(encoded))
+    });
+    extended
+        .or_else(|| find("filename").map(|value| value.trim_matches('"').to_string()))
+        .filter(|name| !name.is_empty())
+}
+
+/// The last segment of the URL's path, without the query string that presigned URLs carry.
+fn url_filename(url: &reqwest::Url) -> Option<String> {
+    let segment = url.path_segments()?.next_back()?;
+    Some(percent_decode(segment)).filter(|name| !name.is_empty())
+}
+
+/// The name of the downloaded file: from the `Content-Disposition` header, or else from the
+/// URL's path.
+pub fn response_filename(response: &Response) -> String {
+    response
+        .headers()
+        .get(CONTENT_DISPOSITION)
+        .and_then(|header| header.to_str().ok())
+        .and_then(content_disposition_filename)
+        .or_else(|| url_filename(response.url()))
+        .unwrap_or_else(|| FALLBACK_FILENAME.to_string())
+}
+
+/// Streams the body of `response` to `path`, failing as soon as it is known to be larger than
+/// `max_bytes`. Returns the number of bytes written.
+pub async fn write_body(
+    mut response: Response,
+    path: &Path,
+    max_bytes: u64,
+) -> Result<u64, MediaAnalyzerError> {
+    let url = response.url().to_string();
+    let status = Some(response.status().as_u16());
+    let too_large = || {
+        download_error(
+            &url,
+            status,
+            format!("the body is larger than the maximum of {max_bytes} bytes"),
+        )
+    };
+    if response
+        .content_length()
+        .is_some_and(|length| length > max_bytes)
+    {
+        return Err(too_large());
+    }
+    let mut file = File::create(path)?;
+    let mut written = 0;
+    while let Some(chunk) = response.chunk().await.map_err(|e| request_error(&url, e))? {
+        written += chunk.len() as u64;
+        if written > max_bytes {
+            return Err(too_large());
+        }
+        file.write_all(&chunk)?;
+    }
+    file.flush()?;
+    Ok(written)
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader};
+    use std::net::TcpListener;
+    use std::thread;
+
+    /// Serves `body` with the extra `headers` to every request on a local port, returning the
+    /// server's URL. Without a `Content-Length`, the body is ended by closing the connection.
+    pub fn serve(body: Vec<u8>, headers: &'static str, content_length: bool) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        thread::spawn(move || {
+            for mut stream in listener.incoming().flatten() {
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut request_line = String::new();
+                reader.read_line(&mut request_line).unwrap();
+                let mut line = String::new();
+                while reader.read_line(&mut line).unwrap_or(0) > 2 {
+                    line.clear();
+                }
+                let (status, body) = if request_line.contains("/missing") {
+                    ("404 Not Found", &[][..])
+                } else {
+                    ("200 OK", &body[..])
+                };
+                let length = if content_length {
+                    format!("Content-Length: {}\r\n", body.len())
+                } else {
+                    String::new()
+                };
+                let head =
+                    format!("HTTP/1.1 {status}\r\n{headers}{length}Connection: close\r\n\r\n");
+                let _ = stream.write_all(head.as_bytes());
+                let _ = stream.write_all(body);
+            }
+        });
+        format!("http://{address}")
+    }
+
+    fn temp_path() -> std::path::PathBuf {
+        std::env::temp_dir().join(format!(
+            "media_analyzer_download_{:016x}",
+            rand::random::<u64>()
+        ))
+    }
+
+    #[test]
+    fn test_content_disposition_filename() {
+        assert_eq!(
+            content_disposition_filename(r#"attachment; filename="PXL_0001.NIGHT.jpg""#),
+            Some("PXL_0001.NIGHT.jpg".to_string())
+        );
+        assert_eq!(
+            content_disposition_filename(
+                r#"attachment; filename="fallback.jpg"; filename*=UTF-8''IMG%200001%20%C3%A9.jpg"#
+            ),
+            Some("IMG 0001 é.jpg".to_string())
+        );
+        assert_eq!(content_disposition_filename("inline"), None);
+        assert_eq!(content_disposition_filename(r#"inline; filename="""#), None);
+    }
+
+    #[test]
+    fn test_url_filename() {
+        let url = reqwest::Url::parse(
+            "https://bucket.s3.amazonaws.com/photos/IMG%200001_BURST01.jpg?X-Amz-Signature=abc",
+        )
+        .unwrap();
+        assert_eq!(url_filename(&url), Some("IMG 0001_BURST01.jpg".to_string()));
+        let url = reqwest::Url::parse("https://example.com/").unwrap();
+        assert_eq!(url_filename(&url), None);
+        assert_eq!(percent_decode("100%zz%4"), "100%zz%4");
+    }
+
+    #[tokio::test]
+    async fn test_download_is_streamed_to_a_file() {
+        let body = b"not really a jpeg".to_vec();
+        let url = serve(
+            body.clone(),
+            "Content-Disposition: attachment; filename=\"sunset.jpg\"\r\n",
+            true,
+        );
+        let client = reqwest::Client::new();
+        let response = get(&client, &format!("{url}/object?signature=1"))
+            .await
+            .unwrap();
+        assert_eq!(response_filename(&response), "sunset.jpg");
+
+        let path = temp_path();
+        let written = write_body(response, &path, 1024).await.unwrap();
+        assert_eq!(written, body.len() as u64);
+        assert_eq!(std::fs::read(&path).unwrap(), body);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_large_downloads_are_rejected() {
+        let client = reqwest::Client::new();
+        for content_length in [true, false] {
+            let url = serve(vec![0; 4096], "", content_length);
+            let response = get(&client, &format!("{url}/big.mp4")).await.unwrap();
+            let path = temp_path();
+            let result = write_body(response, &path, 1000).await;
+            assert!(
+                matches!(
+                    result,
+                    Err(MediaAnalyzerError::Download { status: Some(200), ref reason, .. })
+                        if reason.contains("maximum of 1000 bytes")
+                ),
+                "{result:?}"
+            );
+            let _ = std::fs::remove_file(path);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_error_status_is_reported() {
+        let url = serve(Vec::new(), "", true);
+        let result = get(
+            &client().unwrap(),
+            &format!("{url}/missing?X-Amz-Signature=secret"),
+        )
+        .await;
+        assert!(
+            matches!(
+                result,
+                Err(MediaAnalyzerError::Download {
+                    status: Some(404),
+                    ref url,
+                    ..
+                }) if !url.contains("secret")
+            ),
+            "{result:?}"
+        );
+    }
+
+    #[test]
+    fn test_redact_url() {
+        assert_eq!(
+            redact_url("https://bucket.s3.amazonaws.com/a%20b.jpg?X-Amz-Signature=abc#top"),
+            "https://bucket.s3.amazonaws.com/a%20b.jpg"
+        );
+        assert_eq!(redact_url("not a url?token=abc"), "not a url");
+    }
+}
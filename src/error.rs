@@ -1,3 +1,5 @@
+use std::path::PathBuf;
+use std::time::Duration;
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -23,6 +25,67 @@ pub enum MediaAnalyzerError {
     #[error("Weather service (Meteostat) initialization failed")]
     Meteostat(#[from] meteostat::MeteostatError),
 
+    #[error("The location of the embedded {0} image is unknown")]
+    AuxImageNotLocated(String),
+
     #[error("No thumbnail frames were provided to generate a data URL")]
     NoThumbnail,
+
+    #[error("The file is empty: {0}")]
+    EmptyFile(PathBuf),
+
+    /// The analyzer was built with options that contradict each other.
+    #[error("Invalid configuration: {0}")]
+    InvalidConfiguration(String),
+
+    #[error("Unsupported file {path}: {reason}")]
+    UnsupportedFile { path: PathBuf, reason: String },
+
+    /// Downloading media from a URL failed. `url` is without its query string, which holds the
+    /// signature of a presigned URL. `status` is the HTTP status code, when the server responded.
+    #[error("Downloading {url} failed: {reason}")]
+    Download {
+        url: String,
+        status: Option<u16>,
+        reason: String,
+    },
+
+    /// The analysis took longer than the analyzer's `analysis_timeout`. `stage` is the stage
+    /// that was running: `download`, `exiftool`, `metadata` or `weather`.
+    #[error("Analysis timed out after {elapsed:?} in stage {stage:?}")]
+    Timeout {
+        elapsed: Duration,
+        stage: Option<String>,
+    },
+
+    /// The analysis was cancelled through its [`CancellationToken`](crate::CancellationToken).
+    /// `stage` is the stage that was running, like for [`MediaAnalyzerError::Timeout`], and
+    /// `None` when the token was cancelled before the analysis started.
+    #[error("Analysis cancelled in stage {stage:?}")]
+    Cancelled { stage: Option<String> },
+}
+
+impl MediaAnalyzerError {
+    /// The variant as a short snake case name, like `metadata` or `timeout`, for grouping
+    /// errors, e.g. in metrics.
+    #[must_use]
+    pub const fn kind(&self) -> &'static str {
+        match self {
+            Self::Exiftool(_) => "exiftool",
+            Self::Io(_) => "io",
+            Self::Time(_) => "time",
+            Self::Metadata(_) => "metadata",
+            Self::DataUrl(_) => "data_url",
+            Self::Weather(_) => "weather",
+            Self::Meteostat(_) => "meteostat",
+            Self::AuxImageNotLocated(_) => "aux_image_not_located",
+            Self::NoThumbnail => "no_thumbnail",
+            Self::EmptyFile(_) => "empty_file",
+            Self::InvalidConfiguration(_) => "invalid_configuration",
+            Self::UnsupportedFile { .. } => "unsupported_file",
+            Self::Download { .. } => "download",
+            Self::Timeout { .. } => "timeout",
+            Self::Cancelled { .. } => "cancelled",
+        }
+    }
 }
@@ -1,39 +1,133 @@
 #![allow(clippy::cast_sign_loss)]
 use crate::features::error::MetadataError;
+use crate::features::stack::sub_document_number;
 use serde::{Deserialize, Serialize};
 use serde_json::{Map, Value};
 use std::ops::Deref;
+use std::panic::Location;
+use std::sync::{Arc, Mutex, PoisonError};
+
+/// The number of characters of a value kept in [`ParseDiagnostic::raw_preview`].
+const RAW_PREVIEW_CHARS: usize = 80;
+
+/// A tag that was present, but couldn't be converted to the type a detection asked for.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ParseDiagnostic {
+    /// The module that read the tag, like `tags::motion`.
+    pub module: String,
+    pub tag: String,
+    /// The requested type: `f64`, `u64`, `i64`, `u32`, `string` or `bool`.
+    pub expected: String,
+    /// The JSON type of the value: `null`, `bool`, `number`, `string`, `array` or `object`.
+    pub actual_json_type: String,
+    /// The value as JSON, cut off after [`RAW_PREVIEW_CHARS`] characters.
+    pub raw_preview: String,
+}
 
 /// A newtype wrapper around the raw exiftool JSON output.
 ///
 /// Provides ergonomic, typed accessors for common EXIF field patterns.
 /// Supports both flat (`-n`) and grouped (`-g2 -n`) JSON structures.
+///
+/// Numeric accessors are lenient: they also accept numeric strings, and take the first element
+/// of an array, as exiftool reports e.g. `ISO` of bracketed shots as one. With
+/// [`ExifData::with_parse_diagnostics`], a tag that is present but fails conversion anyway is
+/// recorded as a [`ParseDiagnostic`].
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct ExifData(Value);
+#[serde(transparent)]
+pub struct ExifData {
+    value: Value,
+    #[serde(skip)]
+    diagnostics: Option<Arc<Mutex<Vec<ParseDiagnostic>>>>,
+}
 
 impl ExifData {
     #[must_use]
     pub const fn new(value: Value) -> Self {
-        Self(value)
+        Self {
+            value,
+            diagnostics: None,
+        }
+    }
+
+    /// Records failed conversions from now on, see [`ExifData::take_parse_diagnostics`]. Clones
+    /// share the recorded diagnostics.
+    #[must_use]
+    pub fn with_parse_diagnostics(mut self) -> Self {
+        self.diagnostics.get_or_insert_with(Arc::default);
+        self
+    }
+
+    /// The failed conversions recorded so far, `None` when they aren't being recorded.
+    #[must_use]
+    pub fn take_parse_diagnostics(&self) -> Option<Vec<ParseDiagnostic>> {
+        let diagnostics = self.diagnostics.as_ref()?;
+        Some(std::mem::take(
+            &mut *diagnostics.lock().unwrap_or_else(PoisonError::into_inner),
+        ))
     }
 
     /// Access the underlying JSON value.
     #[must_use]
     pub const fn inner(&self) -> &Value {
-        &self.0
+        &self.value
     }
 
+    /// Takes the underlying JSON value.
+    #[must_use]
+    pub fn into_inner(self) -> Value {
+        self.value
+    }
+
+    /// Converts a present tag, recording a diagnostic for the caller when that fails.
+    #[track_caller]
+    fn convert<'a, T>(
+        &self,
+        key: &str,
+        val: Option<&'a Value>,
+        expected: &str,
+        conversion: impl FnOnce(&'a Value) -> Option<T>,
+    ) -> Option<T> {
+        let val = val?;
+        let converted = conversion(val);
+        if converted.is_none()
+            && let Some(diagnostics) = &self.diagnostics
+        {
+            let diagnostic = ParseDiagnostic {
+                module: caller_module(Location::caller().file()),
+                tag: key.to_string(),
+                expected: expected.to_string(),
+                actual_json_type: json_type(val).to_string(),
+                raw_preview: raw_preview(val),
+            };
+            let mut diagnostics = diagnostics.lock().unwrap_or_else(PoisonError::into_inner);
+            if !diagnostics.iter().any(|d| {
+                d.module == diagnostic.module && d.tag == diagnostic.tag && d.expected == expected
+            }) {
+                diagnostics.push(diagnostic);
+            }
+        }
+        converted
+    }
+
+    /// Finds a tag at the top level or in any group. The sub-documents of `exiftool -ee -g3`
+    /// output (`Doc1`, `Doc2`, ...) are searched last, so the primary image's tags win.
     fn find_value(&self, key: &str) -> Option<&Value> {
-        if let Some(val) = self.0.get(key) {
+        if let Some(val) = self.value.get(key) {
             return Some(val);
         }
-        let obj = self.0.as_object()?;
-        for group_val in obj.values() {
-            if let Some(val) = group_val.as_object().and_then(|g| g.get(key)) {
-                return Some(val);
-            }
-        }
-        None
+        let obj = self.value.as_object()?;
+        let in_groups = |sub_documents: bool| {
+            obj.iter()
+                .filter(move |(group, _)| sub_document_number(group).is_some() == sub_documents)
+                .find_map(|(_, group_val)| group_val.as_object()?.get(key))
+        };
+        in_groups(false).or_else(|| in_groups(true))
+    }
+
+    fn find_group_value(&self, group: &str, key: &str) -> Option<&Value> {
+        self.value.get(group)?.get(key)
     }
 
     fn key_matches(key: &str, target: &str) -> bool {
@@ -53,32 +147,34 @@ impl ExifData {
     // --- Flat accessors (for -n output) ---
 
     #[must_use]
+    #[track_caller]
     pub fn get_f64(&self, key: &str) -> Option<f64> {
-        let val = self.find_value(key)?;
-        if let Some(n) = val.as_f64() {
-            return Some(n);
-        }
-        val.as_str().and_then(|s| s.parse().ok())
+        self.convert(key, self.find_value(key), "f64", parse_f64)
     }
 
+    #[track_caller]
     pub fn get_u64(&self, key: &str) -> Option<u64> {
-        self.find_value(key).and_then(Value::as_u64)
+        self.convert(key, self.find_value(key), "u64", parse_u64)
     }
 
+    #[track_caller]
     pub fn get_i64(&self, key: &str) -> Option<i64> {
-        self.find_value(key).and_then(Value::as_i64)
+        self.convert(key, self.find_value(key), "i64", parse_i64)
     }
 
+    #[track_caller]
     pub fn get_str(&self, key: &str) -> Option<&str> {
-        self.find_value(key).and_then(Value::as_str)
+        self.convert(key, self.find_value(key), "string", Value::as_str)
     }
 
+    #[track_caller]
     pub fn get_string(&self, key: &str) -> Option<String> {
         self.get_str(key).map(str::to_owned)
     }
 
+    #[track_caller]
     pub fn get_bool(&self, key: &str) -> Option<bool> {
-        self.find_value(key).and_then(parse_bool)
+        self.convert(key, self.find_value(key), "bool", parse_bool)
     }
 
     #[must_use]
@@ -86,8 +182,33 @@ impl ExifData {
         self.find_value(key)
     }
 
+    /// The names of all tags, at the top level and in any group.
+    #[must_use]
+    pub fn tag_names(&self) -> Vec<&str> {
+        let Some(obj) = self.value.as_object() else {
+            return Vec::new();
+        };
+        obj.iter()
+            .flat_map(|(key, val)| match val.as_object() {
+                Some(group) => group.keys().map(String::as_str).collect(),
+                None => vec![key.as_str()],
+            })
+            .collect()
+    }
+
+    /// The names of the tags in `group`, empty when there is no such group.
+    #[must_use]
+    pub fn group_tag_names(&self, group: &str) -> Vec<&str> {
+        self.value
+            .get(group)
+            .and_then(Value::as_object)
+            .map(|tags| tags.keys().map(String::as_str).collect())
+            .unwrap_or_default()
+    }
+
     /// # Errors
     /// * If field is missing or not u64
+    #[track_caller]
     pub fn require_u64(&self, key: &str) -> Result<u64, MetadataError> {
         self.get_u64(key)
             .ok_or_else(|| MetadataError::MissingRequiredField(key.to_string()))
@@ -95,6 +216,7 @@ impl ExifData {
 
     /// # Errors
     /// * If field is or not a string
+    #[track_caller]
     pub fn require_string(&self, key: &str) -> Result<String, MetadataError> {
         self.get_string(key)
             .ok_or_else(|| MetadataError::MissingRequiredField(key.to_string()))
@@ -103,26 +225,28 @@ impl ExifData {
     // --- Grouped accessors (for -g2 output) ---
 
     #[must_use]
+    #[track_caller]
     pub fn group_str(&self, group: &str, key: &str) -> Option<&str> {
-        self.0.get(group)?.get(key)?.as_str()
+        self.convert(
+            key,
+            self.find_group_value(group, key),
+            "string",
+            Value::as_str,
+        )
     }
 
     #[must_use]
+    #[track_caller]
     pub fn group_u32(&self, group: &str, key: &str) -> Option<u32> {
-        self.0
-            .get(group)?
-            .get(key)?
-            .as_u64()
-            .and_then(|n| u32::try_from(n).ok())
+        self.convert(key, self.find_group_value(group, key), "u32", |val| {
+            parse_u64(val).and_then(|n| u32::try_from(n).ok())
+        })
     }
 
     #[must_use]
+    #[track_caller]
     pub fn group_f64(&self, group: &str, key: &str) -> Option<f64> {
-        let val = self.0.get(group)?.get(key)?;
-        if let Some(n) = val.as_f64() {
-            return Some(n);
-        }
-        val.as_str().and_then(|s| s.parse().ok())
+        self.convert(key, self.find_group_value(group, key), "f64", parse_f64)
     }
 
     // --- Convenience ---
@@ -131,7 +255,7 @@ impl ExifData {
     #[must_use]
     pub fn get_ignoring_case(&self, tag_name: &str) -> Option<&Value> {
         let target = tag_name.to_lowercase();
-        let root = self.0.as_object()?;
+        let root = self.value.as_object()?;
 
         if let Some(val) = Self::search_object(root, &target) {
             return Some(val);
@@ -148,35 +272,31 @@ impl ExifData {
     }
 
     #[must_use]
+    #[track_caller]
     pub fn get_f64_ignoring_case(&self, tag_name: &str) -> Option<f64> {
-        let val = self.get_ignoring_case(tag_name)?;
-        if let Some(n) = val.as_f64() {
-            return Some(n);
-        }
-        val.as_str().and_then(|s| s.parse().ok())
+        self.convert(tag_name, self.get_ignoring_case(tag_name), "f64", parse_f64)
     }
 
     #[must_use]
+    #[track_caller]
     pub fn get_u64_ignoring_case(&self, tag_name: &str) -> Option<u64> {
-        let val = self.get_ignoring_case(tag_name)?;
-        if let Some(n) = val.as_u64() {
-            return Some(n);
-        }
-        if let Some(n) = val.as_f64() {
-            return Some(n as u64);
-        }
-        val.as_str().and_then(|s| {
-            s.parse::<u64>()
-                .ok()
-                .or_else(|| s.parse::<f64>().ok().map(|f| f as u64))
+        self.convert(tag_name, self.get_ignoring_case(tag_name), "u64", |val| {
+            parse_u64(val).or_else(|| parse_f64(val).map(|f| f as u64))
         })
     }
 
+    #[track_caller]
     pub fn get_bool_ignoring_case(&self, tag_name: &str) -> Option<bool> {
-        self.get_ignoring_case(tag_name).and_then(parse_bool)
+        self.convert(
+            tag_name,
+            self.get_ignoring_case(tag_name),
+            "bool",
+            parse_bool,
+        )
     }
 
     #[must_use]
+    #[track_caller]
     pub fn is_video(&self) -> bool {
         self.group_str("Other", "MIMEType")
             .or_else(|| self.get_str("MIMEType"))
@@ -188,16 +308,73 @@ impl Deref for ExifData {
     type Target = Value;
 
     fn deref(&self) -> &Value {
-        &self.0
+        &self.value
     }
 }
 
+/// The module path of a source file, like `tags::motion` for `src/tags/motion.rs`.
+fn caller_module(file: &str) -> String {
+    let path = file.replace('\\', "/");
+    // Relative in a workspace build, absolute when built as a dependency.
+    let path = path.rsplit_once("/src/").map_or_else(
+        || path.strip_prefix("src/").unwrap_or(&path),
+        |(_, path)| path,
+    );
+    let path = path.strip_suffix(".rs").unwrap_or(path);
+    let path = path.strip_suffix("/mod").unwrap_or(path);
+    path.replace('/', "::")
+}
+
+const fn json_type(val: &Value) -> &'static str {
+    match val {
+        Value::Null => "null",
+        Value::Bool(_) => "bool",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+fn raw_preview(val: &Value) -> String {
+    let raw = val.to_string();
+    match raw.char_indices().nth(RAW_PREVIEW_CHARS) {
+        Some((end, _)) => format!("{}…", &raw[..end]),
+        None => raw,
+    }
+}
+
+/// The first element of an array, or the value itself.
+fn scalar(val: &Value) -> &Value {
+    val.as_array()
+        .and_then(|items| items.first())
+        .unwrap_or(val)
+}
+
+fn parse_f64(val: &Value) -> Option<f64> {
+    let val = scalar(val);
+    val.as_f64()
+        .or_else(|| val.as_str().and_then(|s| s.trim().parse().ok()))
+}
+
+fn parse_u64(val: &Value) -> Option<u64> {
+    let val = scalar(val);
+    val.as_u64()
+        .or_else(|| val.as_str().and_then(|s| s.trim().parse().ok()))
+}
+
+fn parse_i64(val: &Value) -> Option<i64> {
+    let val = scalar(val);
+    val.as_i64()
+        .or_else(|| val.as_str().and_then(|s| s.trim().parse().ok()))
+}
+
 fn parse_bool(val: &Value) -> Option<bool> {
     if let Some(b) = val.as_bool() {
         return Some(b);
     }
     if let Some(s) = val.as_str() {
-        let s_lower = s.to_lowercase();
+        let s_lower = s.trim().to_lowercase();
         if s_lower == "true" || s_lower == "1" || s_lower == "yes" {
             return Some(true);
         }
@@ -262,4 +439,75 @@ mod tests {
         }));
         assert_eq!(exif.get_bool_ignoring_case("UsePanoramaViewer"), Some(true));
     }
+
+    #[test]
+    fn numeric_access_is_lenient() {
+        let exif = ExifData::new(json!({
+            "ISO": [400, 800, 1600],
+            "Rotation": "90",
+            "MotionPhoto": "1"
+        }));
+        assert_eq!(exif.get_u64("ISO"), Some(400));
+        assert_eq!(exif.get_f64("ISO"), Some(400.0));
+        assert_eq!(exif.get_u64("Rotation"), Some(90));
+        assert_eq!(exif.get_i64("Rotation"), Some(90));
+        assert_eq!(exif.get_bool("MotionPhoto"), Some(true));
+    }
+
+    #[test]
+    fn parse_diagnostics_record_failed_conversions() {
+        let exif = ExifData::new(json!({
+            "Camera": { "ISO": { "Value": 100 }, "Make": "Canon" }
+        }));
+        assert_eq!(exif.take_parse_diagnostics(), None);
+
+        let exif = exif.with_parse_diagnostics();
+        assert_eq!(exif.get_u64("ISO"), None);
+        assert_eq!(exif.get_u64("ISO"), None);
+        assert_eq!(exif.get_u64("Make"), None);
+        assert_eq!(exif.get_str("Make"), Some("Canon"));
+        assert_eq!(exif.get_u64("FNumber"), None);
+        let diagnostics = exif.take_parse_diagnostics().unwrap();
+        assert_eq!(
+            diagnostics,
+            [
+                ParseDiagnostic {
+                    module: "exif_data".to_string(),
+                    tag: "ISO".to_string(),
+                    expected: "u64".to_string(),
+                    actual_json_type: "object".to_string(),
+                    raw_preview: r#"{"Value":100}"#.to_string(),
+                },
+                ParseDiagnostic {
+                    module: "exif_data".to_string(),
+                    tag: "Make".to_string(),
+                    expected: "u64".to_string(),
+                    actual_json_type: "string".to_string(),
+                    raw_preview: r#""Canon""#.to_string(),
+                },
+            ]
+        );
+        assert_eq!(exif.take_parse_diagnostics(), Some(Vec::new()));
+    }
+
+    #[test]
+    fn raw_preview_is_cut_off() {
+        let preview = raw_preview(&json!("x".repeat(200)));
+        assert_eq!(preview.chars().count(), RAW_PREVIEW_CHARS + 1);
+        assert!(preview.ends_with('…'));
+        assert_eq!(caller_module("src/tags/mod.rs"), "tags");
+        assert_eq!(caller_module("src/features/gps.rs"), "features::gps");
+        assert_eq!(
+            caller_module(
+                "/home/user/.cargo/registry/src/index.crates.io-1949cf8c6b5b557f/media_analyzer-0.11.0/src/tags/mod.rs"
+            ),
+            "tags"
+        );
+        assert_eq!(
+            caller_module(
+                r"C:\Users\user\.cargo\registry\src\index\media_analyzer-0.11.0\src\features\gps.rs"
+            ),
+            "features::gps"
+        );
+    }
 }
@@ -0,0 +1,269 @@
+//! The `exiftool` process shared by an analyzer and the analyzers derived from it, which can be
+//! killed and replaced when it gets stuck on a file.
+//!
+//! `exiftool` runs in stay-open mode: it reads the arguments of each command from its stdin, one
+//! per line, and ends the output of each command with `{ready}`. It is started here rather than
+//! through [`exiftool::ExifTool`], which doesn't give out its [`Child`], so a process that hangs
+//! in the middle of a command can be killed.
+
+use exiftool::ExifToolError;
+use serde_json::Value;
+use std::io::{BufRead, BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Child, ChildStdin, ChildStdout, Command, Stdio};
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// How long to wait for error lines, which can arrive on stderr just after the output.
+const STDERR_TIMEOUT: Duration = Duration::from_millis(2);
+const STDERR_POLL_INTERVAL: Duration = Duration::from_millis(1);
+
+pub struct ExifToolProcess {
+    executable: Option<PathBuf>,
+    current: Mutex<Arc<StayOpen>>,
+}
+
+/// A running `exiftool -stay_open True -@ -`.
+pub struct StayOpen {
+    /// Held for the length of a command.
+    pipes: Mutex<Pipes>,
+    /// Separate from the pipes, so the process can be killed while a command waits for it.
+    child: Mutex<Child>,
+}
+
+struct Pipes {
+    stdin: BufWriter<ChildStdin>,
+    stdout: BufReader<ChildStdout>,
+    stderr: Receiver<String>,
+}
+
+impl StayOpen {
+    fn start(executable: &Path) -> Result<Self, ExifToolError> {
+        let mut command = Command::new(executable);
+        command
+            .args(["-stay_open", "True", "-@", "-"])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+        #[cfg(windows)]
+        {
+            // Don't open a console window for it.
+            use std::os::windows::process::CommandExt;
+            const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+            command.creation_flags(CREATE_NO_WINDOW);
+        }
+        let mut child = command.spawn().map_err(ExifToolError::ExifToolNotFound)?;
+        let (Some(stdin), Some(stdout), Some(stderr)) =
+            (child.stdin.take(), child.stdout.take(), child.stderr.take())
+        else {
+            let _ = child.kill();
+            return Err(std::io::Error::other("exiftool's pipes could not be captured").into());
+        };
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            for line in BufReader::new(stderr).lines().map_while(Result::ok) {
+                if sender.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+        Ok(Self {
+            pipes: Mutex::new(Pipes {
+                stdin: BufWriter::new(stdin),
+                stdout: BufReader::new(stdout),
+                stderr: receiver,
+            }),
+            child: Mutex::new(child),
+        })
+    }
+
+    /// Runs `exiftool` with `args` and returns its output.
+    ///
+    /// # Errors
+    /// * [`ExifToolError::FileNotFound`] or [`ExifToolError::ExifToolProcess`]: `exiftool`
+    ///   reported an error.
+    /// * [`ExifToolError::ProcessTerminated`]: The process ended, e.g. because it was killed.
+    /// * [`ExifToolError::Io`]: Talking to the process failed.
+    pub fn execute_raw(&self, args: &[&str]) -> Result<Vec<u8>, ExifToolError> {
+        let mut pipes = self.pipes.lock().unwrap_or_else(PoisonError::into_inner);
+        // Errors left over from an earlier command.
+        while pipes.stderr.try_recv().is_ok() {}
+        for arg in args {
+            writeln!(pipes.stdin, "{arg}")?;
+        }
+        writeln!(pipes.stdin, "-execute")?;
+        pipes.stdin.flush()?;
+        let output = pipes.read_until_ready();
+        let errors = pipes.drain_stderr();
+        drop(pipes);
+
+        let output = output?.ok_or(ExifToolError::ProcessTerminated)?;
+        let command_args = args.join(" ");
+        if let Some(path) = errors
+            .iter()
+            .find_map(|line| line.strip_prefix("Error: File not found - "))
+        {
+            return Err(ExifToolError::FileNotFound {
+                path: PathBuf::from(path.trim()),
+                command_args,
+            });
+        }
+        if let Some(message) = errors.iter().find(|line| line.contains("Error:")) {
+            return Err(ExifToolError::ExifToolProcess {
+                message: message.clone(),
+                std_err: errors.join("\n"),
+                command_args,
+            });
+        }
+        Ok(output)
+    }
+
+    /// Runs `exiftool` with `args` and returns the lines of its output.
+    pub fn execute_lines(&self, args: &[&str]) -> Result<Vec<String>, ExifToolError> {
+        let output = String::from_utf8(self.execute_raw(args)?)?;
+        Ok(output.lines().map(String::from).collect())
+    }
+
+    /// The JSON object `exiftool -json` outputs for `file`.
+    pub fn json(&self, file: &Path, extra_args: &[&str]) -> Result<Value, ExifToolError> {
+        let path = file.to_string_lossy();
+        let mut args = vec!["-json"];
+        args.extend_from_slice(extra_args);
+        args.push(&path);
+        let unexpected = || ExifToolError::UnexpectedFormat {
+            path: path.to_string(),
+            command_args: args.join(" "),
+        };
+        let output = self.execute_raw(&args)?;
+        if output.is_empty() {
+            return Err(unexpected());
+        }
+        match serde_json::from_slice(&output)? {
+            Value::Array(objects) => objects.into_iter().next().ok_or_else(unexpected),
+            _ => Err(unexpected()),
+        }
+    }
+
+    /// The binary value of `tag` in `file`, like an embedded image.
+    pub fn read_tag_binary(&self, file: &Path, tag: &str) -> Result<Vec<u8>, ExifToolError> {
+        let bytes = self.execute_raw(&[&file.to_string_lossy(), "-b", &format!("-{tag}")])?;
+        if bytes.is_empty() {
+            return Err(ExifToolError::TagNotFound {
+                path: file.to_path_buf(),
+                tag: tag.to_string(),
+            });
+        }
+        Ok(bytes)
+    }
+
+    /// Kills the process, which ends a command in flight with an error.
+    fn kill(&self) {
+        let mut child = self.child.lock().unwrap_or_else(PoisonError::into_inner);
+        if let Err(e) = child.kill() {
+            log::warn!("Could not kill exiftool: {e}");
+        }
+    }
+}
+
+impl Pipes {
+    /// The output up to the `{ready}` marker, or `None` when the process ended before it.
+    fn read_until_ready(&mut self) -> Result<Option<Vec<u8>>, ExifToolError> {
+        let mut output = Vec::new();
+        let mut chunk = [0; 4096];
+        loop {
+            let read = self.stdout.read(&mut chunk)?;
+            if read == 0 {
+                return Ok(None);
+            }
+            output.extend_from_slice(&chunk[..read]);
+            for marker in [&b"{ready}\n"[..], b"{ready}\r\n"] {
+                if let Some(end) = output
+                    .windows(marker.len())
+                    .position(|window| window == marker)
+                {
+                    output.truncate(end);
+                    return Ok(Some(output));
+                }
+            }
+        }
+    }
+
+    /// The lines written to stderr for the last command.
+    fn drain_stderr(&self) -> Vec<String> {
+        let mut lines = Vec::new();
+        let started = Instant::now();
+        loop {
+            match self.stderr.try_recv() {
+                Ok(line) => lines.push(line),
+                Err(TryRecvError::Empty)
+                    if lines.is_empty() && started.elapsed() < STDERR_TIMEOUT =>
+                {
+                    thread::sleep(STDERR_POLL_INTERVAL);
+                }
+                Err(_) => return lines,
+            }
+        }
+    }
+}
+
+impl Drop for StayOpen {
+    fn drop(&mut self) {
+        let pipes = self.pipes.get_mut().unwrap_or_else(PoisonError::into_inner);
+        let _ = writeln!(pipes.stdin, "-stay_open\nFalse\n-execute");
+        let _ = pipes.stdin.flush();
+        let child = self.child.get_mut().unwrap_or_else(PoisonError::into_inner);
+        let _ = child.kill();
+        let _ = child.wait();
+    }
+}
+
+impl ExifToolProcess {
+    /// Starts `exiftool` from `executable`, or from the PATH when it is `None`.
+    pub fn start(executable: Option<&Path>) -> Result<Self, ExifToolError> {
+        let start = StayOpen::start(executable.unwrap_or_else(|| Path::new("exiftool")))?;
+        Ok(Self {
+            current: Mutex::new(Arc::new(start)),
+            executable: executable.map(Path::to_path_buf),
+        })
+    }
+
+    /// Starts another process from the same executable, for an analyzer that shouldn't wait
+    /// behind this one's calls.
+    pub fn start_another(&self) -> Result<Self, ExifToolError> {
+        Self::start(self.executable.as_deref())
+    }
+
+    /// The running process. Calls hold on to it, so a restart doesn't affect calls in flight.
+    pub fn current(&self) -> Arc<StayOpen> {
+        Arc::clone(&self.current.lock().unwrap_or_else(PoisonError::into_inner))
+    }
+
+    /// Kills `stuck` and replaces it with a fresh process, unless that already happened, so
+    /// later calls don't queue behind it. The kill ends the call `stuck` is hanging in.
+    pub fn restart(&self, stuck: &Arc<StayOpen>) -> Result<(), ExifToolError> {
+        let mut current = self.current.lock().unwrap_or_else(PoisonError::into_inner);
+        if Arc::ptr_eq(&current, stuck) {
+            stuck.kill();
+            *current = Arc::new(StayOpen::start(
+                self.executable
+                    .as_deref()
+                    .unwrap_or_else(|| Path::new("exiftool")),
+            )?);
+        }
+        drop(current);
+        Ok(())
+    }
+
+    /// Replaces the running process when it no longer answers, e.g. because it crashed.
+    /// Returns whether it was replaced.
+    pub fn restart_if_dead(&self) -> Result<bool, ExifToolError> {
+        let current = self.current();
+        if current.execute_lines(&["-ver"]).is_ok() {
+            return Ok(false);
+        }
+        self.restart(&current)?;
+        Ok(true)
+    }
+}
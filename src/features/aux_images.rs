@@ -0,0 +1,356 @@
+//! Auxiliary images embedded next to the primary image: gain maps, depth maps, portrait mattes
+//! and large thumbnails.
+//!
+//! They are listed from three sources, in order of preference:
+//! * The Google container directory (`DirectoryItemSemantic`/`DirectoryItemLength`), used by
+//!   Pixel phones and Ultra HDR JPEGs. Items are stored back to back at the end of the file.
+//! * The CIPA Multi-Picture Format (MPF) image list (`MPImageType`/`MPImageStart`/`MPImageLength`).
+//! * HEIC auxiliary items (`AuxiliaryImageType`), used by iPhones. Their location isn't known.
+
+use crate::ExifData;
+use crate::MediaAnalyzerError;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+
+pub const SEMANTIC_GAIN_MAP: &str = "GainMap";
+pub const SEMANTIC_DEPTH: &str = "Depth";
+pub const SEMANTIC_MATTE: &str = "Matte";
+pub const SEMANTIC_THUMBNAIL: &str = "Thumbnail";
+
+/// How many bytes at the start of a JPEG to search for the MPF header.
+const MPF_SEARCH_BYTES: u64 = 256 * 1024;
+const MPF_SIGNATURE: &[u8] = b"MPF\0";
+
+/// An auxiliary image embedded in the file.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuxImageInfo {
+    /// What the image is, e.g. `GainMap`, `Depth`, `Matte` or `Thumbnail`.
+    pub semantic: String,
+    #[serde(default)]
+    pub mime: Option<String>,
+    #[serde(default)]
+    pub length_bytes: Option<u64>,
+    /// Where the image starts, counted from the start of the file.
+    #[serde(default)]
+    pub offset_bytes: Option<u64>,
+    /// The exiftool tag to extract the image with (`exiftool -b -<tag>`), when known.
+    #[serde(default)]
+    pub exiftool_tag: Option<String>,
+}
+
+/// Reads a tag that exiftool reports as a list, or as a single value when there is only one.
+fn list_values<'a>(exif: &'a ExifData, tag: &str) -> Vec<&'a Value> {
+    match exif.get_value(tag) {
+        Some(Value::Array(values)) => values.iter().collect(),
+        Some(value) => vec![value],
+        None => Vec::new(),
+    }
+}
+
+fn value_as_u64(value: &Value) -> Option<u64> {
+    value
+        .as_u64()
+        .or_else(|| value.as_str().and_then(|s| s.trim().parse().ok()))
+}
+
+/// Aligns `values` with the last items of a list of `len` items. exiftool flattens the container
+/// directory per attribute, and the primary item, which always comes first, has no length.
+fn right_aligned<T: Copy>(values: &[T], len: usize, index: usize) -> Option<T> {
+    let skipped = len.checked_sub(values.len())?;
+    index
+        .checked_sub(skipped)
+        .and_then(|i| values.get(i).copied())
+}
+
+fn normalize_container_semantic(semantic: &str) -> String {
+    match semantic.to_lowercase().as_str() {
+        "gainmap" => SEMANTIC_GAIN_MAP.to_string(),
+        "depth" => SEMANTIC_DEPTH.to_string(),
+        "matte" | "portraitmatte" => SEMANTIC_MATTE.to_string(),
+        _ => semantic.to_string(),
+    }
+}
+
+fn container_directory_images(exif: &ExifData) -> Vec<AuxImageInfo> {
+    let semantics: Vec<&str> = list_values(exif, "DirectoryItemSemantic")
+        .into_iter()
+        .filter_map(Value::as_str)
+        .collect();
+    let mimes: Vec<&str> = list_values(exif, "DirectoryItemMime")
+        .into_iter()
+        .filter_map(Value::as_str)
+        .collect();
+    let lengths: Vec<u64> = list_values(exif, "DirectoryItemLength")
+        .into_iter()
+        .filter_map(value_as_u64)
+        .collect();
+    let count = semantics.len();
+    let file_size = exif.get_u64("FileSize");
+
+    // Secondary items are stored back to back at the end of the file, so their offsets follow
+    // from the file size and the lengths of the items after them.
+    let mut end = file_size;
+    let mut images: Vec<AuxImageInfo> = semantics
+        .iter()
+        .enumerate()
+        .rev()
+        .filter(|(_, semantic)| !semantic.eq_ignore_ascii_case("Primary"))
+        .map(|(index, semantic)| {
+            let length_bytes = right_aligned(&lengths, count, index);
+            let offset_bytes = match (end, length_bytes) {
+                (Some(e), Some(length)) => e.checked_sub(length),
+                _ => None,
+            };
+            end = offset_bytes;
+            AuxImageInfo {
+                semantic: normalize_container_semantic(semantic),
+                mime: right_aligned(&mimes, count, index).map(str::to_string),
+                length_bytes,
+                offset_bytes,
+                exiftool_tag: None,
+            }
+        })
+        .collect();
+    images.reverse();
+    images
+}
+
+/// Maps an MPF image type code (as reported by `exiftool -n`) to a semantic.
+fn mpf_semantic(image_type: u64) -> Option<String> {
+    Some(
+        match image_type & 0x00FF_FFFF {
+            0x03_0000 => return None, // The baseline primary image.
+            0x01_0001 | 0x01_0002 => SEMANTIC_THUMBNAIL,
+            0x02_0001 => "Panorama",
+            0x02_0002 => "Disparity",
+            0x02_0003 => "MultiAngle",
+            _ => "Undefined",
+        }
+        .to_string(),
+    )
+}
+
+/// Finds where MPF offsets are counted from: the TIFF header right after the "MPF\0" signature.
+fn find_mpf_base_offset(path: &Path) -> Option<u64> {
+    let mut head = Vec::new();
+    File::open(path)
+        .ok()?
+        .take(MPF_SEARCH_BYTES)
+        .read_to_end(&mut head)
+        .ok()?;
+    let position = head
+        .windows(MPF_SIGNATURE.len())
+        .position(|window| window == MPF_SIGNATURE)?;
+    u64::try_from(position + MPF_SIGNATURE.len()).ok()
+}
+
+fn mpf_images(path: &Path, exif: &ExifData) -> Vec<AuxImageInfo> {
+    let types: Vec<u64> = list_values(exif, "MPImageType")
+        .into_iter()
+        .filter_map(value_as_u64)
+        .collect();
+    if types.is_empty() {
+        return Vec::new();
+    }
+    let starts: Vec<u64> = list_values(exif, "MPImageStart")
+        .into_iter()
+        .filter_map(value_as_u64)
+        .collect();
+    let lengths: Vec<u64> = list_values(exif, "MPImageLength")
+        .into_iter()
+        .filter_map(value_as_u64)
+        .collect();
+    let base_offset = find_mpf_base_offset(path);
+
+    types
+        .iter()
+        .enumerate()
+        .filter_map(|(index, image_type)| {
+            let semantic = mpf_semantic(*image_type)?;
+            // Offsets are only reliable when exiftool listed every image, not just one of them.
+            let start = (starts.len() == types.len())
+                .then(|| starts[index])
+                .filter(|start| *start > 0);
+            Some(AuxImageInfo {
+                semantic,
+                mime: Some("image/jpeg".to_string()),
+                length_bytes: (lengths.len() == types.len()).then(|| lengths[index]),
+                offset_bytes: base_offset.zip(start).map(|(base, start)| base + start),
+                exiftool_tag: Some(format!("MPImage{}", index + 1)),
+            })
+        })
+        .collect()
+}
+
+fn heic_semantic(aux_type: &str) -> String {
+    let lower = aux_type.to_lowercase();
+    if lower.contains("gainmap") {
+        SEMANTIC_GAIN_MAP.to_string()
+    } else if lower.contains("matte") {
+        SEMANTIC_MATTE.to_string()
+    } else if lower.contains("depth") || lower.contains("disparity") || lower.ends_with("auxid:2") {
+        SEMANTIC_DEPTH.to_string()
+    } else if lower.ends_with("auxid:1") {
+        "Alpha".to_string()
+    } else {
+        aux_type.to_string()
+    }
+}
+
+fn heic_aux_images(exif: &ExifData) -> Vec<AuxImageInfo> {
+    list_values(exif, "AuxiliaryImageType")
+        .into_iter()
+        .filter_map(Value::as_str)
+        .map(|aux_type| AuxImageInfo {
+            semantic: heic_semantic(aux_type),
+            mime: None,
+            length_bytes: None,
+            offset_bytes: None,
+            exiftool_tag: None,
+        })
+        .collect()
+}
+
+/// Lists the auxiliary images embedded in the file. The container directory and the MPF list
+/// usually describe the same images, so MPF is only used when there is no container directory.
+pub fn get_aux_images(path: &Path, exif: &ExifData) -> Vec<AuxImageInfo> {
+    let mut images = container_directory_images(exif);
+    if images.is_empty() {
+        images = mpf_images(path, exif);
+    }
+    images.extend(heic_aux_images(exif));
+    images
+}
+
+/// Copies an auxiliary image out of `path` into `out` using its recorded offset and length,
+/// returning the number of bytes written. See [`MediaAnalyzer::extract_aux_image`](crate::MediaAnalyzer::extract_aux_image)
+/// for images without a known location.
+///
+/// # Errors
+///
+/// * [`MediaAnalyzerError::AuxImageNotLocated`] when the offset or length isn't known.
+/// * [`MediaAnalyzerError::Io`] when the file can't be read, or is shorter than expected.
+pub fn copy_aux_image(
+    path: &Path,
+    info: &AuxImageInfo,
+    out: &mut impl Write,
+) -> Result<u64, MediaAnalyzerError> {
+    let (Some(offset), Some(length)) = (info.offset_bytes, info.length_bytes) else {
+        return Err(MediaAnalyzerError::AuxImageNotLocated(
+            info.semantic.clone(),
+        ));
+    };
+    let mut file = File::open(path)?;
+    file.seek(SeekFrom::Start(offset))?;
+    let copied = io::copy(&mut file.take(length), out)?;
+    if copied < length {
+        return Err(io::Error::new(
+            io::ErrorKind::UnexpectedEof,
+            format!(
+                "{} ends before the end of the {}",
+                path.display(),
+                info.semantic
+            ),
+        )
+        .into());
+    }
+    Ok(copied)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const HDR_JPG_SIZE: u64 = 2_814_103;
+    const HDR_GAIN_MAP_LENGTH: u64 = 13_750;
+
+    /// The container directory of `assets/hdr.jpg`, as exiftool reports it.
+    fn hdr_jpg_exif() -> ExifData {
+        ExifData::new(json!({
+            "Other": { "FileSize": HDR_JPG_SIZE, "MIMEType": "image/jpeg" },
+            "Image": {
+                "DirectoryItemMime": ["image/jpeg", "image/jpeg"],
+                "DirectoryItemSemantic": ["Primary", "GainMap"],
+                "DirectoryItemLength": HDR_GAIN_MAP_LENGTH,
+                "MPImageType": 0
+            }
+        }))
+    }
+
+    #[test]
+    fn test_container_directory_gain_map() {
+        let path = Path::new("assets/hdr.jpg");
+        let images = get_aux_images(path, &hdr_jpg_exif());
+        assert_eq!(
+            images,
+            vec![AuxImageInfo {
+                semantic: SEMANTIC_GAIN_MAP.to_string(),
+                mime: Some("image/jpeg".to_string()),
+                length_bytes: Some(HDR_GAIN_MAP_LENGTH),
+                offset_bytes: Some(HDR_JPG_SIZE - HDR_GAIN_MAP_LENGTH),
+                exiftool_tag: None,
+            }]
+        );
+
+        let mut gain_map = Vec::new();
+        let written = copy_aux_image(path, &images[0], &mut gain_map).unwrap();
+        assert_eq!(written, HDR_GAIN_MAP_LENGTH);
+        assert_eq!(&gain_map[..2], &[0xFF, 0xD8], "gain map should be a JPEG");
+    }
+
+    #[test]
+    fn test_mpf_multi_picture_file() {
+        // A JPEG-like file with the "MPF\0" signature at byte 100, so offsets count from 104.
+        let mut bytes = vec![0u8; 1000];
+        bytes[100..104].copy_from_slice(MPF_SIGNATURE);
+        bytes[604..606].copy_from_slice(&[0xFF, 0xD8]);
+        let path = std::env::temp_dir().join("media_analyzer_mpf_fixture.jpg");
+        std::fs::write(&path, &bytes).unwrap();
+
+        let exif = ExifData::new(json!({
+            "MPImageType": [0x03_0000, 0x01_0001, 0x02_0002],
+            "MPImageStart": [0, 500, 700],
+            "MPImageLength": [480, 100, 50]
+        }));
+        let images = get_aux_images(&path, &exif);
+        assert_eq!(images.len(), 2);
+        assert_eq!(images[0].semantic, SEMANTIC_THUMBNAIL);
+        assert_eq!(images[0].offset_bytes, Some(604));
+        assert_eq!(images[0].length_bytes, Some(100));
+        assert_eq!(images[0].exiftool_tag.as_deref(), Some("MPImage2"));
+        assert_eq!(images[1].semantic, "Disparity");
+        assert_eq!(images[1].offset_bytes, Some(804));
+
+        let mut thumbnail = Vec::new();
+        copy_aux_image(&path, &images[0], &mut thumbnail).unwrap();
+        assert_eq!(&thumbnail[..2], &[0xFF, 0xD8]);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_heic_aux_images_have_no_location() {
+        let exif = ExifData::new(json!({
+            "AuxiliaryImageType": [
+                "urn:com:apple:photo:2020:aux:hdrgainmap",
+                "urn:com:apple:photo:2018:aux:portraiteffectsmatte",
+                "urn:mpeg:hevc:2015:auxid:2"
+            ]
+        }));
+        let images = get_aux_images(Path::new("IMG_0001.HEIC"), &exif);
+        let semantics: Vec<&str> = images.iter().map(|i| i.semantic.as_str()).collect();
+        assert_eq!(
+            semantics,
+            [SEMANTIC_GAIN_MAP, SEMANTIC_MATTE, SEMANTIC_DEPTH]
+        );
+        let result = copy_aux_image(Path::new("IMG_0001.HEIC"), &images[0], &mut Vec::new());
+        assert!(matches!(
+            result,
+            Err(MediaAnalyzerError::AuxImageNotLocated(_))
+        ));
+    }
+}
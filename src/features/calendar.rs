@@ -0,0 +1,362 @@
+//! Whether a photo was taken on a weekend or a public holiday, in the country it was taken in.
+//!
+//! `holidays.tsv` holds the national public holidays of 29 countries whose holidays all follow
+//! fixed rules. Its columns are `cc`, `rule`, `name` and optionally `years`, without a header.
+//! A rule is a date (`12-25`), days from Easter Sunday (`easter+39`), the nth weekday of a
+//! month (`11-thu#4`, or `05-mon#-1` for the last), or the first weekday on or after a date
+//! (`06-20>sat`). `years` limits a holiday to a range like `2021-` or `-2013`. Countries with
+//! holidays from a lunar calendar aren't in it, and regional holidays are left out.
+
+use crate::features::gps::GpsInfo;
+use crate::time::structs::TimeInfo;
+use chrono::{Datelike, Days, NaiveDate, Weekday};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::LazyLock;
+
+static HOLIDAYS: &str = include_str!("holidays.tsv");
+
+/// The bundled holidays, parsed on first use.
+static BUNDLED_HOLIDAYS: LazyLock<HolidayTable> = LazyLock::new(|| HolidayTable::parse(HOLIDAYS));
+
+/// The calendar day a photo was taken on, in the local time of where it was taken.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CalendarContext {
+    /// The local date is a Saturday or a Sunday.
+    pub is_weekend: bool,
+    /// The name of the public holiday on the local date. `None` on other days, and in
+    /// countries the [`HolidayProvider`] has no holidays for.
+    #[serde(default)]
+    pub holiday: Option<String>,
+}
+
+/// A source of public holidays.
+pub trait HolidayProvider: Send + Sync {
+    /// The name of the public holiday on `date` in the country with the ISO 3166-1 alpha-2
+    /// `country_code`. `None` on other days, and for countries without data, rather than a
+    /// guess.
+    fn holiday(&self, country_code: &str, date: NaiveDate) -> Option<String>;
+}
+
+/// The national public holidays compiled into the crate, see the [module](self) docs for the
+/// countries it covers.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct BundledHolidays;
+
+impl HolidayProvider for BundledHolidays {
+    fn holiday(&self, country_code: &str, date: NaiveDate) -> Option<String> {
+        BUNDLED_HOLIDAYS.holiday(country_code, date)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Rule {
+    Date {
+        month: u32,
+        day: u32,
+    },
+    Easter {
+        offset_days: i64,
+    },
+    /// The `n`th `weekday` of the month, counted from the end when negative.
+    NthWeekday {
+        month: u32,
+        weekday: Weekday,
+        n: i8,
+    },
+    OnOrAfter {
+        month: u32,
+        day: u32,
+        weekday: Weekday,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Holiday {
+    rule: Rule,
+    name: String,
+    first_year: Option<i32>,
+    last_year: Option<i32>,
+}
+
+/// Easter Sunday in the Gregorian calendar, with the anonymous Gregorian algorithm.
+#[allow(clippy::many_single_char_names)]
+fn easter_sunday(year: i32) -> Option<NaiveDate> {
+    let a = year % 19;
+    let b = year / 100;
+    let c = year % 100;
+    let d = b / 4;
+    let e = b % 4;
+    let f = (b + 8) / 25;
+    let g = (b - f + 1) / 3;
+    let h = (19 * a + b - d - g + 15) % 30;
+    let i = c / 4;
+    let k = c % 4;
+    let l = (32 + 2 * e + 2 * i - h - k) % 7;
+    let m = (a + 11 * h + 22 * l) / 451;
+    let month = (h + l - 7 * m + 114) / 31;
+    let day = (h + l - 7 * m + 114) % 31 + 1;
+    NaiveDate::from_ymd_opt(year, month.try_into().ok()?, day.try_into().ok()?)
+}
+
+fn last_weekday_of_month(year: i32, month: u32, weekday: Weekday) -> Option<NaiveDate> {
+    let next_month = if month == 12 {
+        NaiveDate::from_ymd_opt(year + 1, 1, 1)
+    } else {
+        NaiveDate::from_ymd_opt(year, month + 1, 1)
+    }?;
+    let last_day = next_month.pred_opt()?;
+    let days_back =
+        (7 + last_day.weekday().num_days_from_monday() - weekday.num_days_from_monday()) % 7;
+    last_day.checked_sub_days(Days::new(days_back.into()))
+}
+
+impl Rule {
+    /// Parses `12-25`, `easter+39`, `11-thu#4` or `06-20>sat`.
+    fn parse(rule: &str) -> Option<Self> {
+        if let Some(offset) = rule.strip_prefix("easter") {
+            let offset_days = match offset {
+                "" => 0,
+                offset => offset.strip_prefix('+').unwrap_or(offset).parse().ok()?,
+            };
+            return Some(Self::Easter { offset_days });
+        }
+        let (month, rest) = rule.split_once('-')?;
+        let month = month.parse().ok()?;
+        if let Some((weekday, n)) = rest.split_once('#') {
+            return Some(Self::NthWeekday {
+                month,
+                weekday: weekday.parse().ok()?,
+                n: n.parse().ok()?,
+            });
+        }
+        if let Some((day, weekday)) = rest.split_once('>') {
+            return Some(Self::OnOrAfter {
+                month,
+                day: day.parse().ok()?,
+                weekday: weekday.parse().ok()?,
+            });
+        }
+        Some(Self::Date {
+            month,
+            day: rest.parse().ok()?,
+        })
+    }
+
+    /// The date of the holiday in `year`.
+    fn date_in(self, year: i32) -> Option<NaiveDate> {
+        match self {
+            Self::Date { month, day } => NaiveDate::from_ymd_opt(year, month, day),
+            Self::Easter { offset_days } => {
+                let easter = easter_sunday(year)?;
+                let days = Days::new(offset_days.unsigned_abs());
+                if offset_days < 0 {
+                    easter.checked_sub_days(days)
+                } else {
+                    easter.checked_add_days(days)
+                }
+            }
+            Self::NthWeekday { month, weekday, n } if n < 0 => {
+                let last = last_weekday_of_month(year, month, weekday)?;
+                last.checked_sub_days(Days::new(7 * u64::from((-n - 1).unsigned_abs())))
+            }
+            Self::NthWeekday { month, weekday, n } => {
+                NaiveDate::from_weekday_of_month_opt(year, month, weekday, n.unsigned_abs())
+            }
+            Self::OnOrAfter {
+                month,
+                day,
+                weekday,
+            } => {
+                let date = NaiveDate::from_ymd_opt(year, month, day)?;
+                let days_ahead = (7 + weekday.num_days_from_monday()
+                    - date.weekday().num_days_from_monday())
+                    % 7;
+                date.checked_add_days(Days::new(days_ahead.into()))
+            }
+        }
+    }
+}
+
+/// Holidays by country code.
+struct HolidayTable {
+    countries: HashMap<String, Vec<Holiday>>,
+}
+
+impl HolidayTable {
+    /// Parses tab separated `cc rule name [years]` lines, skipping malformed ones.
+    fn parse(data: &str) -> Self {
+        let mut countries: HashMap<String, Vec<Holiday>> = HashMap::new();
+        for line in data.lines() {
+            let mut columns = line.split('\t');
+            let (Some(country_code), Some(rule), Some(name)) =
+                (columns.next(), columns.next(), columns.next())
+            else {
+                continue;
+            };
+            let Some(rule) = Rule::parse(rule) else {
+                continue;
+            };
+            let (first_year, last_year) = match columns.next().map(|years| years.split_once('-')) {
+                None => (None, None),
+                Some(Some((first, last))) => (first.parse().ok(), last.parse().ok()),
+                Some(None) => continue,
+            };
+            countries
+                .entry(country_code.to_string())
+                .or_default()
+                .push(Holiday {
+                    rule,
+                    name: name.to_string(),
+                    first_year,
+                    last_year,
+                });
+        }
+        Self { countries }
+    }
+
+    fn holiday(&self, country_code: &str, date: NaiveDate) -> Option<String> {
+        let year = date.year();
+        self.countries
+            .get(&country_code.to_uppercase())?
+            .iter()
+            .filter(|holiday| holiday.first_year.is_none_or(|first| year >= first))
+            .filter(|holiday| holiday.last_year.is_none_or(|last| year <= last))
+            .find(|holiday| holiday.rule.date_in(year) == Some(date))
+            .map(|holiday| holiday.name.clone())
+    }
+}
+
+/// Whether the photo was taken on a weekend or a public holiday. Both use the local date of the
+/// capture, so a Saturday morning in Tokyo is a weekend though it is Friday in UTC. The holiday
+/// needs the reverse geocoded country of `gps`.
+#[must_use]
+pub fn get_calendar_context(
+    time: &TimeInfo,
+    gps: Option<&GpsInfo>,
+    holidays: &dyn HolidayProvider,
+) -> CalendarContext {
+    let date = time.datetime_local.date();
+    let holiday = gps
+        .and_then(|gps| gps.location.as_ref())
+        .map(|location| location.country_code.as_str())
+        .filter(|country_code| !country_code.is_empty())
+        .and_then(|country_code| holidays.holiday(country_code, date));
+    CalendarContext {
+        is_weekend: matches!(date.weekday(), Weekday::Sat | Weekday::Sun),
+        holiday,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::features::gps::LocationName;
+    use crate::test_support::MediaMetadataFixture;
+
+    fn date(date: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(date, "%Y-%m-%d").unwrap()
+    }
+
+    /// The calendar context of a photo taken at `local_time` in the country.
+    fn context_at(local_time: &str, offset: &str, country_code: &str) -> CalendarContext {
+        let result = MediaMetadataFixture::photo()
+            .with_local_time(local_time)
+            .with_offset(offset)
+            .build();
+        let mut location = LocationName::unnamed(0.0, 0.0);
+        location.country_code = country_code.to_string();
+        let gps = GpsInfo {
+            location: Some(location),
+            ..MediaMetadataFixture::photo()
+                .with_gps(52.37, 4.89)
+                .build()
+                .gps
+                .unwrap()
+        };
+        get_calendar_context(&result.time, Some(&gps), &BundledHolidays)
+    }
+
+    #[test]
+    fn test_dataset_parses_completely() {
+        let holidays: usize = BUNDLED_HOLIDAYS.countries.values().map(Vec::len).sum();
+        assert_eq!(holidays, HOLIDAYS.lines().count());
+        assert_eq!(BUNDLED_HOLIDAYS.countries.len(), 29);
+    }
+
+    #[test]
+    fn test_saturday_uses_the_local_date() {
+        // Saturday morning in Tokyo is still Friday in UTC.
+        let context = context_at("2024-06-01T08:00:00", "+09:00", "JP");
+        assert!(context.is_weekend);
+
+        let friday = context_at("2024-05-31T23:30:00", "-07:00", "US");
+        assert!(!friday.is_weekend);
+        assert_eq!(friday.holiday, None);
+    }
+
+    #[test]
+    fn test_new_years_day_in_a_covered_country() {
+        let context = context_at("2025-01-01T00:15:00", "+01:00", "NL");
+        assert_eq!(
+            context,
+            CalendarContext {
+                is_weekend: false,
+                holiday: Some("New Year's Day".to_string()),
+            }
+        );
+    }
+
+    #[test]
+    fn test_uncovered_country_still_has_weekend() {
+        // Lunar New Year in Vietnam, which the bundled table doesn't cover.
+        let context = context_at("2024-02-10T12:00:00", "+07:00", "VN");
+        assert!(context.is_weekend);
+        assert_eq!(context.holiday, None);
+
+        let result = MediaMetadataFixture::photo().build();
+        let without_gps = get_calendar_context(&result.time, None, &BundledHolidays);
+        assert!(without_gps.is_weekend);
+        assert_eq!(without_gps.holiday, None);
+    }
+
+    #[test]
+    fn test_rules() {
+        let holiday =
+            |country_code: &str, day: &str| BundledHolidays.holiday(country_code, date(day));
+        assert_eq!(easter_sunday(2024), Some(date("2024-03-31")));
+        assert_eq!(easter_sunday(2025), Some(date("2025-04-20")));
+        assert_eq!(holiday("DE", "2025-04-18").as_deref(), Some("Good Friday"));
+        assert_eq!(
+            holiday("nl", "2025-05-29").as_deref(),
+            Some("Ascension Day")
+        );
+        assert_eq!(
+            holiday("US", "2024-11-28").as_deref(),
+            Some("Thanksgiving Day")
+        );
+        assert_eq!(holiday("US", "2024-05-27").as_deref(), Some("Memorial Day"));
+        assert_eq!(
+            holiday("GB", "2024-08-26").as_deref(),
+            Some("Summer Bank Holiday")
+        );
+        assert_eq!(holiday("CA", "2024-05-20").as_deref(), Some("Victoria Day"));
+        assert_eq!(
+            holiday("SE", "2024-06-22").as_deref(),
+            Some("Midsummer Day")
+        );
+        // King's Day replaced Queen's Day in 2014.
+        assert_eq!(holiday("NL", "2013-04-30").as_deref(), Some("Queen's Day"));
+        assert_eq!(holiday("NL", "2014-04-30"), None);
+        assert_eq!(holiday("US", "2020-06-19"), None);
+        assert_eq!(holiday("US", "2024-07-05"), None);
+    }
+
+    #[test]
+    fn test_malformed_lines_are_skipped() {
+        let table =
+            HolidayTable::parse("NL\t01-01\tA\nNL\tnot a rule\tB\nNL\t05-05\nNL\t12-25\tC\t2020\n");
+        assert_eq!(table.countries["NL"].len(), 1);
+    }
+}
@@ -0,0 +1,191 @@
+use serde::{Deserialize, Serialize};
+
+/// Which side of the device the camera module faces.
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum Facing {
+    Front,
+    Back,
+}
+
+/// The kind of camera module on a multi-camera phone.
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum ModuleKind {
+    UltraWide,
+    Wide,
+    Telephoto,
+    Periscope,
+    Macro,
+}
+
+/// The camera module of a multi-camera phone that took the shot.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct CameraModule {
+    #[serde(default)]
+    pub facing: Option<Facing>,
+    #[serde(default)]
+    pub kind: Option<ModuleKind>,
+}
+
+/// Makes of phones with multiple camera modules. Other makes (DSLRs, compacts) use
+/// interchangeable or zoom lenses, where a module classification makes no sense.
+const PHONE_MAKES: [&str; 10] = [
+    "apple", "google", "samsung", "huawei", "xiaomi", "oneplus", "oppo", "vivo", "motorola", "sony",
+];
+
+// 35mm-equivalent focal length buckets, in millimeters.
+const ULTRA_WIDE_MAX_MM: f64 = 18.0;
+const WIDE_MAX_MM: f64 = 35.0;
+const TELEPHOTO_MIN_MM: f64 = 50.0;
+const TELEPHOTO_MAX_MM: f64 = 85.0;
+
+fn is_phone(make: Option<&str>, model: Option<&str>) -> bool {
+    let make = make.unwrap_or_default().to_lowercase();
+    let model = model.unwrap_or_default().to_lowercase();
+    if make == "sony" {
+        // Sony also makes cameras; only its Xperia phones have camera modules.
+        return model.contains("xperia");
+    }
+    PHONE_MAKES.iter().any(|m| make.contains(m))
+}
+
+fn kind_from_focal_length(focal_length_35mm: f64) -> Option<ModuleKind> {
+    match focal_length_35mm {
+        f if f <= 0.0 => None,
+        f if f < ULTRA_WIDE_MAX_MM => Some(ModuleKind::UltraWide),
+        f if f <= WIDE_MAX_MM => Some(ModuleKind::Wide),
+        f if (TELEPHOTO_MIN_MM..=TELEPHOTO_MAX_MM).contains(&f) => Some(ModuleKind::Telephoto),
+        f if f > TELEPHOTO_MAX_MM => Some(ModuleKind::Periscope),
+        _ => None,
+    }
+}
+
+/// Parses Apple ("iPhone 14 Pro back triple camera 6.86mm f/1.78") and Samsung
+/// ("Samsung Galaxy S23 Ultra Rear Telephoto Camera") style lens descriptions.
+fn parse_lens_string(lens: &str) -> (Option<Facing>, Option<ModuleKind>) {
+    let lens = lens.to_lowercase();
+    let facing = if lens.contains("front") {
+        Some(Facing::Front)
+    } else if lens.contains("back") || lens.contains("rear") {
+        Some(Facing::Back)
+    } else {
+        None
+    };
+    let kind = if lens.contains("ultra wide") || lens.contains("ultrawide") {
+        Some(ModuleKind::UltraWide)
+    } else if lens.contains("periscope") {
+        Some(ModuleKind::Periscope)
+    } else if lens.contains("telephoto") {
+        Some(ModuleKind::Telephoto)
+    } else if lens.contains("macro") {
+        Some(ModuleKind::Macro)
+    } else {
+        None
+    };
+    (facing, kind)
+}
+
+/// Infers the camera module of a phone from its lens description and 35mm focal length.
+/// Returns `None` for non-phone cameras, or when nothing could be inferred.
+pub fn detect_camera_module(
+    make: Option<&str>,
+    model: Option<&str>,
+    lens: Option<&str>,
+    focal_length_35mm: Option<f64>,
+) -> Option<CameraModule> {
+    if !is_phone(make, model) {
+        return None;
+    }
+    let (facing, lens_kind) = lens.map(parse_lens_string).unwrap_or_default();
+    let kind = lens_kind.or_else(|| focal_length_35mm.and_then(kind_from_focal_length));
+    if facing.is_none() && kind.is_none() {
+        return None;
+    }
+    Some(CameraModule { facing, kind })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_iphone_ultra_wide_lens_string() {
+        let module = detect_camera_module(
+            Some("Apple"),
+            Some("iPhone 14 Pro"),
+            Some("iPhone 14 Pro back triple camera 2.22mm f/2.2"),
+            Some(13.0),
+        );
+        assert_eq!(
+            module,
+            Some(CameraModule {
+                facing: Some(Facing::Back),
+                kind: Some(ModuleKind::UltraWide),
+            })
+        );
+    }
+
+    #[test]
+    fn test_iphone_front_camera() {
+        let module = detect_camera_module(
+            Some("Apple"),
+            Some("iPhone 12"),
+            Some("iPhone 12 front camera 2.71mm f/2.2"),
+            Some(30.0),
+        )
+        .unwrap();
+        assert_eq!(module.facing, Some(Facing::Front));
+        assert_eq!(module.kind, Some(ModuleKind::Wide));
+    }
+
+    #[test]
+    fn test_samsung_lens_string() {
+        let module = detect_camera_module(
+            Some("samsung"),
+            Some("SM-S918B"),
+            Some("Samsung Galaxy S23 Ultra Rear Telephoto Camera"),
+            None,
+        )
+        .unwrap();
+        assert_eq!(module.facing, Some(Facing::Back));
+        assert_eq!(module.kind, Some(ModuleKind::Telephoto));
+    }
+
+    #[test]
+    fn test_pixel_tele_by_focal_length_only() {
+        let module = detect_camera_module(Some("Google"), Some("Pixel 8 Pro"), None, Some(77.0));
+        assert_eq!(
+            module,
+            Some(CameraModule {
+                facing: None,
+                kind: Some(ModuleKind::Telephoto),
+            })
+        );
+        let periscope =
+            detect_camera_module(Some("Google"), Some("Pixel 8 Pro"), None, Some(113.0));
+        assert_eq!(periscope.unwrap().kind, Some(ModuleKind::Periscope));
+    }
+
+    #[test]
+    fn test_dslr_has_no_module() {
+        let module = detect_camera_module(
+            Some("Canon"),
+            Some("Canon EOS R5"),
+            Some("RF24-70mm F2.8 L IS USM"),
+            Some(24.0),
+        );
+        assert!(module.is_none());
+    }
+
+    #[test]
+    fn test_focal_length_buckets() {
+        assert_eq!(kind_from_focal_length(17.9), Some(ModuleKind::UltraWide));
+        assert_eq!(kind_from_focal_length(18.0), Some(ModuleKind::Wide));
+        assert_eq!(kind_from_focal_length(35.0), Some(ModuleKind::Wide));
+        assert_eq!(kind_from_focal_length(42.0), None);
+        assert_eq!(kind_from_focal_length(85.0), Some(ModuleKind::Telephoto));
+        assert_eq!(kind_from_focal_length(85.1), Some(ModuleKind::Periscope));
+    }
+}
@@ -0,0 +1,111 @@
+//! A small reverse geocoding dataset for the analyzer's low-memory mode.
+//!
+//! `coarse_cities.tsv` is derived from the `reverse_geocoder` dataset by keeping, for every
+//! country, one place per 1° by 1° cell: the one closest to the center of the cell. Its columns
+//! are `lat`, `lon`, `name`, `admin1` and `cc`, without a header. The result is ~9,500 places
+//! instead of ~150,000, so a location resolves to a town up to ~100km away. The country is
+//! still right away from borders, but `admin2` is never known.
+
+use std::sync::LazyLock;
+
+static COARSE_CITIES: &str = include_str!("coarse_cities.tsv");
+
+/// The coarse dataset, parsed on first use.
+pub static COARSE_GEOCODER: LazyLock<CoarseGeocoder> =
+    LazyLock::new(|| CoarseGeocoder::parse(COARSE_CITIES));
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CoarsePlace {
+    pub latitude: f64,
+    pub longitude: f64,
+    pub name: String,
+    pub admin1: String,
+    pub country_code: String,
+    unit_vector: [f64; 3],
+}
+
+fn unit_vector(latitude: f64, longitude: f64) -> [f64; 3] {
+    let (latitude, longitude) = (latitude.to_radians(), longitude.to_radians());
+    [
+        latitude.cos() * longitude.cos(),
+        latitude.cos() * longitude.sin(),
+        latitude.sin(),
+    ]
+}
+
+fn dot([ax, ay, az]: [f64; 3], [bx, by, bz]: [f64; 3]) -> f64 {
+    ax.mul_add(bx, ay.mul_add(by, az * bz))
+}
+
+/// Nearest-place search over the coarse dataset, by linear scan. With this few places a scan
+/// takes microseconds, and it needs no index next to the places themselves.
+pub struct CoarseGeocoder {
+    places: Vec<CoarsePlace>,
+}
+
+impl CoarseGeocoder {
+    /// Parses tab separated `lat lon name admin1 cc` lines, skipping malformed ones.
+    fn parse(data: &str) -> Self {
+        let places = data
+            .lines()
+            .filter_map(|line| {
+                let mut columns = line.split('\t');
+                let latitude = columns.next()?.parse().ok()?;
+                let longitude = columns.next()?.parse().ok()?;
+                Some(CoarsePlace {
+                    latitude,
+                    longitude,
+                    name: columns.next()?.to_string(),
+                    admin1: columns.next()?.to_string(),
+                    country_code: columns.next()?.to_string(),
+                    unit_vector: unit_vector(latitude, longitude),
+                })
+            })
+            .collect();
+        Self { places }
+    }
+
+    /// The place closest to the coordinates, along the surface of the earth. `None` only for an
+    /// empty dataset, which the bundled one is not.
+    #[must_use]
+    pub fn search(&self, latitude: f64, longitude: f64) -> Option<&CoarsePlace> {
+        let point = unit_vector(latitude, longitude);
+        // The closest place has the smallest angle to the point, so the largest dot product.
+        self.places
+            .iter()
+            .max_by(|a, b| dot(a.unit_vector, point).total_cmp(&dot(b.unit_vector, point)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dataset_parses_completely() {
+        assert_eq!(COARSE_GEOCODER.places.len(), COARSE_CITIES.lines().count());
+    }
+
+    #[test]
+    fn test_search_finds_a_nearby_place_in_the_same_country() {
+        // Central Amsterdam, Madrid and Sydney.
+        for (latitude, longitude, country_code) in [
+            (52.379_189, 4.899_431, "NL"),
+            (40.4168, -3.7038, "ES"),
+            (-33.8688, 151.2093, "AU"),
+        ] {
+            let place = COARSE_GEOCODER.search(latitude, longitude).unwrap();
+            assert_eq!(place.country_code, country_code);
+            assert!((place.latitude - latitude).abs() < 1.0);
+            assert!((place.longitude - longitude).abs() < 1.0);
+        }
+    }
+
+    #[test]
+    fn test_malformed_lines_are_skipped() {
+        let geocoder = CoarseGeocoder::parse("52.0\t4.0\tA\tB\tNL\nnot a place\n51.0\t5.0\tC\n");
+        assert_eq!(geocoder.places.len(), 1);
+        assert_eq!(geocoder.search(0.0, 0.0).unwrap().name, "A");
+        assert!(CoarseGeocoder::parse("").search(0.0, 0.0).is_none());
+    }
+}
@@ -0,0 +1,245 @@
+//! A single 0-100 score for how much a result knows about its file, to find files worth a
+//! manual look in a library.
+//!
+//! Default weights, which sum to 100:
+//! * Dimensions: 5
+//! * Capture time: 25 at `High` confidence, 15 at `Medium`, 5 at `Low`
+//! * GPS coordinates: 20
+//! * A reverse geocoded place name: 10
+//! * A weather observation: 10
+//! * Camera make or model: 15
+//! * A thumbnail: 15, for an embedded thumbnail or a resolved video thumbnail timestamp
+//!
+//! The defaults are stable: changing them would change the scores of stored results.
+
+use crate::features::aux_images::SEMANTIC_THUMBNAIL;
+use crate::structs::MediaMetadata;
+use crate::time::structs::{CONFIDENCE_HIGH, CONFIDENCE_LOW, CONFIDENCE_MEDIUM};
+use serde::{Deserialize, Serialize};
+
+/// The points each part of a result is worth. The score is the share of the maximum, so the
+/// weights don't have to sum to 100.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CompletenessWeights {
+    pub dimensions: u8,
+    pub time_high: u8,
+    pub time_medium: u8,
+    pub time_low: u8,
+    pub gps: u8,
+    pub location: u8,
+    pub weather: u8,
+    pub camera: u8,
+    pub thumbnail: u8,
+}
+
+impl Default for CompletenessWeights {
+    fn default() -> Self {
+        Self {
+            dimensions: 5,
+            time_high: 25,
+            time_medium: 15,
+            time_low: 5,
+            gps: 20,
+            location: 10,
+            weather: 10,
+            camera: 15,
+            thumbnail: 15,
+        }
+    }
+}
+
+impl CompletenessWeights {
+    /// The points of a result that has everything.
+    const fn max_points(&self) -> u32 {
+        let time = if self.time_high >= self.time_medium && self.time_high >= self.time_low {
+            self.time_high
+        } else if self.time_medium >= self.time_low {
+            self.time_medium
+        } else {
+            self.time_low
+        };
+        self.dimensions as u32
+            + time as u32
+            + self.gps as u32
+            + self.location as u32
+            + self.weather as u32
+            + self.camera as u32
+            + self.thumbnail as u32
+    }
+}
+
+/// What a result has, behind its `completeness_score`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+#[allow(clippy::struct_excessive_bools)]
+pub struct CompletenessBreakdown {
+    pub has_dimensions: bool,
+    /// The points for the capture time, by its confidence.
+    pub time_confidence_points: u8,
+    pub has_gps: bool,
+    /// The coordinates were reverse geocoded to a named place.
+    pub has_location: bool,
+    /// A weather observation was found for the capture time and place.
+    pub has_weather: bool,
+    /// The camera make or model is known.
+    pub has_camera: bool,
+    /// The file has an embedded thumbnail, or a video thumbnail timestamp was resolved.
+    pub has_thumbnail: bool,
+}
+
+/// Scores how complete `result` is, from 0 to 100, and what the score is made of.
+///
+/// ```rust
+/// use media_analyzer::test_support::MediaMetadataFixture;
+/// use media_analyzer::{CompletenessWeights, score_completeness};
+///
+/// let result = MediaMetadataFixture::photo().build();
+/// let (score, breakdown) = score_completeness(&result, &CompletenessWeights::default());
+/// assert!(breakdown.has_camera && !breakdown.has_gps);
+/// assert!(score < 100);
+/// ```
+#[must_use]
+pub fn score_completeness(
+    result: &MediaMetadata,
+    weights: &CompletenessWeights,
+) -> (u8, CompletenessBreakdown) {
+    let location = result.gps.as_ref().and_then(|gps| gps.location.as_ref());
+    let breakdown = CompletenessBreakdown {
+        has_dimensions: result.basic.width > 0 && result.basic.height > 0,
+        time_confidence_points: match result.time.source_details.confidence.as_str() {
+            CONFIDENCE_HIGH => weights.time_high,
+            CONFIDENCE_MEDIUM => weights.time_medium,
+            CONFIDENCE_LOW => weights.time_low,
+            _ => 0,
+        },
+        has_gps: result.gps.is_some(),
+        has_location: location.is_some_and(|location| !location.name.is_empty()),
+        has_weather: result
+            .weather
+            .as_ref()
+            .is_some_and(|weather| weather.hourly.is_some()),
+        has_camera: result.camera.camera_make.is_some() || result.camera.camera_model.is_some(),
+        has_thumbnail: result.thumbnail_timestamp_s.is_some()
+            || result
+                .aux_images
+                .iter()
+                .any(|image| image.semantic == SEMANTIC_THUMBNAIL),
+    };
+    let earned = [
+        (breakdown.has_dimensions, weights.dimensions),
+        (true, breakdown.time_confidence_points),
+        (breakdown.has_gps, weights.gps),
+        (breakdown.has_location, weights.location),
+        (breakdown.has_weather, weights.weather),
+        (breakdown.has_camera, weights.camera),
+        (breakdown.has_thumbnail, weights.thumbnail),
+    ]
+    .into_iter()
+    .filter(|(has, _)| *has)
+    .map(|(_, points)| u32::from(points))
+    .sum::<u32>();
+    let max = weights.max_points();
+    if max == 0 {
+        return (0, breakdown);
+    }
+    // Rounded to the nearest point; `earned` is at most `max`, so this fits in a u8.
+    let score = u8::try_from((earned * 100 + max / 2) / max).unwrap_or(100);
+    (score, breakdown)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::features::aux_images::AuxImageInfo;
+    use crate::features::gps::LocationName;
+    use crate::test_support::MediaMetadataFixture;
+
+    fn thumbnail() -> AuxImageInfo {
+        AuxImageInfo {
+            semantic: SEMANTIC_THUMBNAIL.to_string(),
+            mime: Some("image/jpeg".to_string()),
+            length_bytes: Some(8000),
+            offset_bytes: None,
+            exiftool_tag: Some("ThumbnailImage".to_string()),
+        }
+    }
+
+    #[test]
+    fn test_default_weights_sum_to_100() {
+        assert_eq!(CompletenessWeights::default().max_points(), 100);
+    }
+
+    #[test]
+    fn test_complete_result_scores_high() {
+        let mut result = MediaMetadataFixture::sunset().build();
+        let gps = result.gps.as_mut().unwrap();
+        gps.location = Some(LocationName {
+            latitude: gps.latitude,
+            longitude: gps.longitude,
+            name: "Amsterdam".to_string(),
+            admin1: "North Holland".to_string(),
+            admin2: "Gemeente Amsterdam".to_string(),
+            country_code: "NL".to_string(),
+            country_name: Some("Netherlands".to_string()),
+        });
+        result.aux_images.push(thumbnail());
+
+        let (score, breakdown) = score_completeness(&result, &CompletenessWeights::default());
+        assert_eq!(
+            breakdown,
+            CompletenessBreakdown {
+                has_dimensions: true,
+                time_confidence_points: 25,
+                has_gps: true,
+                has_location: true,
+                has_weather: false,
+                has_camera: true,
+                has_thumbnail: true,
+            }
+        );
+        assert_eq!(score, 90);
+    }
+
+    #[test]
+    fn test_minimal_result_scores_low() {
+        let result = MediaMetadataFixture::from_exif(serde_json::json!({
+            "Other": { "MIMEType": "image/png", "FileSize": 120_000 },
+            "Image": { "ImageWidth": 640, "ImageHeight": 480 },
+            "Time": { "DateTimeOriginal": "2024:03:01 12:00:00" }
+        }))
+        .build();
+
+        let (score, breakdown) = score_completeness(&result, &CompletenessWeights::default());
+        assert!(breakdown.has_dimensions);
+        assert!(!breakdown.has_gps && !breakdown.has_camera && !breakdown.has_thumbnail);
+        assert!(score <= 30, "{score}");
+    }
+
+    #[test]
+    fn test_custom_weights_change_the_score() {
+        let result = MediaMetadataFixture::photo().build();
+        let (default_score, _) = score_completeness(&result, &CompletenessWeights::default());
+        let camera_only = CompletenessWeights {
+            dimensions: 0,
+            time_high: 0,
+            time_medium: 0,
+            time_low: 0,
+            gps: 0,
+            location: 0,
+            weather: 0,
+            camera: 1,
+            thumbnail: 0,
+        };
+        let (score, breakdown) = score_completeness(&result, &camera_only);
+        assert_eq!(score, 100);
+        assert_eq!(breakdown.time_confidence_points, 0);
+        assert!(score > default_score);
+
+        let nothing = CompletenessWeights {
+            camera: 0,
+            ..camera_only
+        };
+        assert_eq!(score_completeness(&result, &nothing).0, 0);
+    }
+}
@@ -0,0 +1,124 @@
+use crate::ExifData;
+use crate::features::metadata::CameraSettings;
+use crate::structs::MediaMetadata;
+use crate::time::structs::{CONFIDENCE_LOW, TimeInfo};
+use std::collections::HashMap;
+
+const SERIAL_NUMBER_TAGS: [&str; 3] = ["SerialNumber", "BodySerialNumber", "InternalSerialNumber"];
+
+/// The camera body's serial number, from the first serial number tag that is present.
+pub fn get_serial_number(exif: &ExifData) -> Option<String> {
+    SERIAL_NUMBER_TAGS.iter().find_map(|tag| {
+        exif.get_value(tag).and_then(|v| {
+            v.as_str()
+                .map(str::to_owned)
+                .or_else(|| v.as_u64().map(|n| n.to_string()))
+        })
+    })
+}
+
+/// Builds a key that identifies a single shot across re-exports of the same photo.
+///
+/// The key is `make|model|serial|local time (millisecond precision)`. It is `None` when the time
+/// confidence is low or the camera is unknown, because those keys would merge unrelated shots.
+pub fn get_dedup_key(camera: &CameraSettings, time: &TimeInfo) -> Option<String> {
+    if time.source_details.confidence == CONFIDENCE_LOW {
+        return None;
+    }
+    if camera.camera_make.is_none() && camera.camera_model.is_none() {
+        return None;
+    }
+    let serial = camera.camera_serial_number.as_deref().unwrap_or_default();
+
+    Some(format!(
+        "{}|{}|{}|{}",
+        camera.camera_make.as_deref().unwrap_or_default().trim(),
+        camera.camera_model.as_deref().unwrap_or_default().trim(),
+        serial.trim(),
+        time.datetime_local.format("%Y-%m-%dT%H:%M:%S%.3f"),
+    ))
+}
+
+/// Groups results by their dedup key, returning the indices into `results` for each key.
+/// Results without a key are left out.
+#[must_use]
+pub fn group_by_dedup_key(results: &[MediaMetadata]) -> HashMap<String, Vec<usize>> {
+    let mut groups: HashMap<String, Vec<usize>> = HashMap::new();
+    for (index, result) in results.iter().enumerate() {
+        if let Some(key) = &result.dedup_key {
+            groups.entry(key.clone()).or_default().push(index);
+        }
+    }
+    groups
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::features::metadata::get_metadata;
+    use crate::time::get_time_info;
+    use crate::time::structs::TimeOptions;
+    use serde_json::{Value, json};
+
+    fn key_for(value: Value) -> Option<String> {
+        let exif = ExifData::new(value);
+        let (_, camera) = get_metadata(&exif).unwrap();
+        let time = get_time_info(&exif, None, &TimeOptions::default()).unwrap();
+        get_dedup_key(&camera, &time)
+    }
+
+    fn fixture(width: u64) -> Value {
+        json!({
+            "Image": {
+                "ImageWidth": width, "ImageHeight": 3000, "MIMEType": "image/jpeg", "FileSize": 1024
+            },
+            "Camera": { "Make": "Canon", "Model": "EOS R5", "SerialNumber": "123456" },
+            "Time": {
+                "DateTimeOriginal": "2024:02:02 12:34:56",
+                "SubSecTimeOriginal": 123_456,
+                "OffsetTimeOriginal": "+01:00"
+            }
+        })
+    }
+
+    #[test]
+    fn test_resized_export_shares_key() {
+        let original = key_for(fixture(4000));
+        let resized = key_for(fixture(1000));
+        assert_eq!(
+            original,
+            Some("Canon|EOS R5|123456|2024-02-02T12:34:56.123".to_string())
+        );
+        assert_eq!(original, resized);
+    }
+
+    #[test]
+    fn test_low_confidence_time_has_no_key() {
+        let mut value = fixture(4000);
+        value["Time"]
+            .as_object_mut()
+            .unwrap()
+            .remove("OffsetTimeOriginal");
+        assert!(key_for(value).is_none());
+    }
+
+    #[test]
+    fn test_missing_serial_leaves_empty_segment() {
+        let mut value = fixture(4000);
+        value["Camera"]
+            .as_object_mut()
+            .unwrap()
+            .remove("SerialNumber");
+        assert_eq!(
+            key_for(value),
+            Some("Canon|EOS R5||2024-02-02T12:34:56.123".to_string())
+        );
+    }
+
+    #[test]
+    fn test_unknown_camera_has_no_key() {
+        let mut value = fixture(4000);
+        value.as_object_mut().unwrap().remove("Camera");
+        assert!(key_for(value).is_none());
+    }
+}
@@ -0,0 +1,146 @@
+//! How the device was held and moving at capture time, from Apple maker notes.
+//!
+//! `exiftool` describes Apple's `AccelerationVector` in units of g. As viewed from the front of
+//! the phone, positive X is toward the left side, positive Y toward the bottom and positive Z
+//! into the face of the phone, so along the view of the back camera.
+
+use crate::ExifData;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// The share of gravity along the Z axis above which the back camera counts as pointed down,
+/// within ~25 degrees of straight down.
+const POINTED_DOWN_MIN_Z: f64 = 0.9;
+
+/// The device's acceleration and the kind of capture, for stabilization hints: a photo taken
+/// while the phone moved fast is likely blurry, and one pointed straight down is likely a
+/// document or receipt.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct DeviceMotion {
+    /// The X, Y and Z acceleration in g, see the module docs for the axes.
+    #[serde(default)]
+    pub acceleration: Option<[f64; 3]>,
+    /// Apple's `ImageCaptureType`, like "Photo" or "Portrait".
+    #[serde(default)]
+    pub capture_type: Option<String>,
+    /// Whether the back camera pointed straight down, derived from the direction of gravity in
+    /// the acceleration vector.
+    #[serde(default)]
+    pub pointed_down: Option<bool>,
+}
+
+/// Parses a vector of three numbers from an array, or from the space-separated string older
+/// `exiftool` versions write.
+fn parse_vector(value: &Value) -> Option<[f64; 3]> {
+    let components: Vec<f64> = match value {
+        Value::Array(values) => values.iter().map(Value::as_f64).collect::<Option<_>>()?,
+        Value::String(text) => text
+            .split_whitespace()
+            .map(str::parse)
+            .collect::<Result<_, _>>()
+            .ok()?,
+        _ => return None,
+    };
+    let vector: [f64; 3] = components.try_into().ok()?;
+    vector.iter().all(|c| c.is_finite()).then_some(vector)
+}
+
+/// Whether gravity points along the view of the back camera. `None` for a zero vector.
+fn is_pointed_down([x, y, z]: [f64; 3]) -> Option<bool> {
+    let magnitude = x.hypot(y).hypot(z);
+    (magnitude > f64::EPSILON).then(|| z / magnitude >= POINTED_DOWN_MIN_Z)
+}
+
+/// Names Apple's numeric `ImageCaptureType` values, as `exiftool -n` reports them.
+fn capture_type_name(value: &Value) -> Option<String> {
+    if let Some(name) = value.as_str() {
+        return Some(name.to_string());
+    }
+    let name = match value.as_u64()? {
+        1 => "ProRAW",
+        2 => "Portrait",
+        10 => "Photo",
+        11 => "Manual Focus",
+        12 => "Scene",
+        other => return Some(other.to_string()),
+    };
+    Some(name.to_string())
+}
+
+/// Reads the device motion from Apple maker notes. `None` when there is neither an
+/// acceleration vector nor a capture type.
+pub fn get_device_motion(exif: &ExifData) -> Option<DeviceMotion> {
+    let acceleration = exif.get_value("AccelerationVector").and_then(parse_vector);
+    let capture_type = exif
+        .get_value("ImageCaptureType")
+        .and_then(capture_type_name);
+    if acceleration.is_none() && capture_type.is_none() {
+        return None;
+    }
+    Some(DeviceMotion {
+        acceleration,
+        capture_type,
+        pointed_down: acceleration.and_then(is_pointed_down),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_string_form() {
+        // iPhone 13, held upright in portrait, as older `exiftool` versions report it.
+        let exif = ExifData::new(json!({
+            "Camera": {
+                "AccelerationVector": "-0.0152 0.9837 0.1741",
+                "ImageCaptureType": 10
+            }
+        }));
+        let motion = get_device_motion(&exif).unwrap();
+        assert_eq!(motion.acceleration, Some([-0.0152, 0.9837, 0.1741]));
+        assert_eq!(motion.capture_type.as_deref(), Some("Photo"));
+        assert_eq!(motion.pointed_down, Some(false));
+    }
+
+    #[test]
+    fn test_array_form() {
+        let exif = ExifData::new(json!({
+            "Camera": {
+                "AccelerationVector": [0.9921, -0.0304, 0.0857],
+                "ImageCaptureType": "Portrait"
+            }
+        }));
+        let motion = get_device_motion(&exif).unwrap();
+        assert_eq!(motion.acceleration, Some([0.9921, -0.0304, 0.0857]));
+        assert_eq!(motion.capture_type.as_deref(), Some("Portrait"));
+        assert_eq!(motion.pointed_down, Some(false));
+    }
+
+    #[test]
+    fn test_face_down_capture_is_pointed_down() {
+        // A receipt on a table, shot from above with the back camera.
+        let exif = ExifData::new(json!({
+            "Camera": { "AccelerationVector": "0.0213 0.2012 0.9768" }
+        }));
+        let motion = get_device_motion(&exif).unwrap();
+        assert_eq!(motion.pointed_down, Some(true));
+        assert_eq!(motion.capture_type, None);
+
+        // Malformed vectors are ignored.
+        let exif = ExifData::new(json!({ "Camera": { "AccelerationVector": "0.1 0.2" } }));
+        assert_eq!(get_device_motion(&exif), None);
+        let exif = ExifData::new(json!({ "Camera": { "AccelerationVector": [0, 0, 0] } }));
+        assert_eq!(get_device_motion(&exif).unwrap().pointed_down, None);
+    }
+
+    #[test]
+    fn test_other_makers_orientation_is_not_a_capture_type() {
+        let exif = ExifData::new(json!({
+            "Camera": { "Make": "Canon", "CameraOrientation": 0 }
+        }));
+        assert_eq!(get_device_motion(&exif), None);
+    }
+}
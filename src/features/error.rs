@@ -26,4 +26,7 @@ pub enum WeatherError {
 
     #[error("Failed to calculate sun position")]
     SunCalculationError,
+
+    #[error("Weather provider request failed: {0}")]
+    Provider(String),
 }
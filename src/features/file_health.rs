@@ -0,0 +1,341 @@
+//! Cheap checks for files cut short by an interrupted copy or download, run before `exiftool`
+//! reads them. They take a `stat` and a read of at most [`TAIL_WINDOW_BYTES`] bytes.
+//!
+//! After `exiftool`, the size of a JPEG's image data is compared to the size its metadata
+//! claims, see [`decoded_dimension_mismatch`].
+
+use crate::MediaAnalyzerError;
+use crate::exif_data::ExifData;
+use crate::features::metadata::display_dimensions;
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{BufReader, Read, Seek, SeekFrom};
+use std::path::Path;
+
+/// Photos and videos with these extensions are suspected to be truncated below
+/// [`MIN_PLAUSIBLE_SIZE_BYTES`].
+const SIZE_CHECKED_EXTENSIONS: [&str; 12] = [
+    "jpg", "jpeg", "heic", "heif", "avif", "mp4", "mov", "m4v", "3gp", "mkv", "webm", "avi",
+];
+pub const MIN_PLAUSIBLE_SIZE_BYTES: u64 = 1024;
+/// How much of the end of a JPEG is read to find its EOI marker.
+pub const TAIL_WINDOW_BYTES: u64 = 4096;
+/// The last bytes of the padding-trimmed tail in which the EOI marker must appear. Some cameras
+/// write a few stray bytes after it.
+const EOI_SLACK_BYTES: usize = 8;
+
+const JPEG_SOI: [u8; 2] = [0xFF, 0xD8];
+const JPEG_EOI: [u8; 2] = [0xFF, 0xD9];
+/// The footer of the trailer Samsung phones append after the JPEG data.
+const SAMSUNG_TRAILER_FOOTER: &[u8] = b"SEFT";
+/// Start of scan: the entropy-coded image data follows, so no frame header comes after it.
+const JPEG_SOS: u8 = 0xDA;
+/// Claimed and actual sizes this many pixels apart still match. Some encoders write the size
+/// rounded up to whole 16 pixel blocks.
+const DIMENSION_TOLERANCE_PX: u64 = 16;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileHealth {
+    /// The file is smaller than [`MIN_PLAUSIBLE_SIZE_BYTES`] for its photo or video extension.
+    pub suspected_truncated: bool,
+    /// The file is a JPEG that doesn't end in an EOI marker, ignoring zero padding. Motion photos
+    /// and Samsung trailers are recognized, other data appended after the image is flagged too.
+    pub truncated_jpeg: bool,
+    /// EXIF dimensions do not match decoded image: the JPEG frame header has another size than
+    /// `ImageWidth`/`ImageHeight` or `ExifImageWidth`/`ExifImageHeight`, as left by some
+    /// recovery tools that save the embedded thumbnail under the original metadata.
+    #[serde(default)]
+    pub dimension_mismatch: bool,
+}
+
+fn is_size_checked(path: &Path) -> bool {
+    path.extension()
+        .and_then(|extension| extension.to_str())
+        .is_some_and(|extension| {
+            SIZE_CHECKED_EXTENSIONS
+                .iter()
+                .any(|checked| checked.eq_ignore_ascii_case(extension))
+        })
+}
+
+/// Whether the tail of a JPEG, up to [`TAIL_WINDOW_BYTES`] long, shows that it was cut short. A
+/// tail of only zero padding is inconclusive.
+fn jpeg_tail_is_truncated(tail: &[u8]) -> bool {
+    let end = tail
+        .iter()
+        .rposition(|&byte| byte != 0)
+        .map_or(0, |i| i + 1);
+    let tail = &tail[..end];
+    if tail.is_empty() || tail.ends_with(SAMSUNG_TRAILER_FOOTER) {
+        return false;
+    }
+    !tail[tail.len().saturating_sub(EOI_SLACK_BYTES)..]
+        .windows(JPEG_EOI.len())
+        .any(|window| window == JPEG_EOI)
+}
+
+fn truncated_jpeg(path: &Path, size: u64) -> std::io::Result<bool> {
+    let mut file = File::open(path)?;
+    let mut head = [0; 2];
+    file.read_exact(&mut head)?;
+    if head != JPEG_SOI {
+        return Ok(false);
+    }
+    let window = size.min(TAIL_WINDOW_BYTES);
+    file.seek(SeekFrom::Start(size - window))?;
+    let mut tail = Vec::new();
+    file.take(window).read_to_end(&mut tail)?;
+    Ok(jpeg_tail_is_truncated(&tail))
+}
+
+/// Whether a JPEG marker starts a frame header: SOF0-SOF15, except DHT, JPG and DAC, which
+/// share the range.
+const fn is_start_of_frame(marker: u8) -> bool {
+    matches!(marker, 0xC0..=0xCF) && !matches!(marker, 0xC4 | 0xC8 | 0xCC)
+}
+
+/// The width and height in the first frame header of a JPEG stream, or `None` when the stream
+/// isn't a JPEG or the image data starts before a frame header.
+fn jpeg_frame_size(mut reader: impl Read) -> std::io::Result<Option<(u64, u64)>> {
+    let mut bytes = [0; 2];
+    reader.read_exact(&mut bytes)?;
+    if bytes != JPEG_SOI {
+        return Ok(None);
+    }
+    loop {
+        let mut byte = [0; 1];
+        reader.read_exact(&mut byte)?;
+        if byte[0] != 0xFF {
+            return Ok(None);
+        }
+        // Any number of 0xFF fill bytes may come before the marker.
+        while byte[0] == 0xFF {
+            reader.read_exact(&mut byte)?;
+        }
+        let marker = byte[0];
+        // Markers without a segment.
+        if matches!(marker, 0x01 | 0xD0..=0xD7) {
+            continue;
+        }
+        if marker == JPEG_SOS {
+            return Ok(None);
+        }
+        reader.read_exact(&mut bytes)?;
+        let length = u64::from(u16::from_be_bytes(bytes)).saturating_sub(2);
+        if is_start_of_frame(marker) {
+            // Sample precision, then the height and width.
+            let mut header = [0; 5];
+            reader.read_exact(&mut header)?;
+            let height = u16::from_be_bytes([header[1], header[2]]);
+            let width = u16::from_be_bytes([header[3], header[4]]);
+            return Ok(Some((u64::from(width), u64::from(height))));
+        }
+        std::io::copy(&mut (&mut reader).take(length), &mut std::io::sink())?;
+    }
+}
+
+const fn dimensions_match(
+    (width, height): (u64, u64),
+    (actual_width, actual_height): (u64, u64),
+) -> bool {
+    width.abs_diff(actual_width) <= DIMENSION_TOLERANCE_PX
+        && height.abs_diff(actual_height) <= DIMENSION_TOLERANCE_PX
+}
+
+/// The actual size of a JPEG's image, as displayed, when the metadata claims another size.
+/// Either orientation of a claimed size matches, as some cameras write the EXIF size
+/// already rotated. Other formats and unreadable files are not checked.
+pub fn decoded_dimension_mismatch(path: &Path, exif: &ExifData) -> Option<(u64, u64)> {
+    let file = File::open(path).ok()?;
+    let (width, height) = jpeg_frame_size(BufReader::new(file)).ok().flatten()?;
+    let claims = [
+        exif.get_u64("ImageWidth").zip(exif.get_u64("ImageHeight")),
+        exif.get_u64("ExifImageWidth")
+            .zip(exif.get_u64("ExifImageHeight")),
+    ];
+    let mismatch = claims
+        .into_iter()
+        .flatten()
+        .any(|(claimed_width, claimed_height)| {
+            !dimensions_match((claimed_width, claimed_height), (width, height))
+                && !dimensions_match((claimed_height, claimed_width), (width, height))
+        });
+    mismatch.then(|| display_dimensions(width, height, exif))
+}
+
+/// Checks a file before analysis.
+///
+/// # Errors
+///
+/// * [`MediaAnalyzerError::EmptyFile`] when the file is zero bytes.
+/// * [`MediaAnalyzerError::UnsupportedFile`] when `strict` is set and the file is suspected to
+///   be truncated.
+/// * [`MediaAnalyzerError::Io`] when the file can't be read.
+pub fn check_file_health(path: &Path, strict: bool) -> Result<FileHealth, MediaAnalyzerError> {
+    let size = path.metadata()?.len();
+    if size == 0 {
+        return Err(MediaAnalyzerError::EmptyFile(path.to_path_buf()));
+    }
+    let suspected_truncated = size < MIN_PLAUSIBLE_SIZE_BYTES && is_size_checked(path);
+    if strict && suspected_truncated {
+        return Err(MediaAnalyzerError::UnsupportedFile {
+            path: path.to_path_buf(),
+            reason: format!("{size} bytes is too small for a complete file"),
+        });
+    }
+    Ok(FileHealth {
+        suspected_truncated,
+        truncated_jpeg: size >= JPEG_SOI.len() as u64 && truncated_jpeg(path, size)?,
+        // Checked against the metadata after `exiftool` ran.
+        dimension_mismatch: false,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::path::PathBuf;
+
+    /// Writes `bytes` to `name` in a new temporary directory.
+    fn temp_file(name: &str, bytes: &[u8]) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "media_analyzer_health_{:016x}",
+            rand::random::<u64>()
+        ));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(name);
+        fs::write(&path, bytes).unwrap();
+        path
+    }
+
+    fn jpeg(len: usize) -> Vec<u8> {
+        let mut bytes = vec![0xAB; len];
+        bytes[..2].copy_from_slice(&JPEG_SOI);
+        bytes[len - 2..].copy_from_slice(&JPEG_EOI);
+        bytes
+    }
+
+    #[test]
+    fn test_zero_byte_file() {
+        let path = temp_file("IMG_0001.jpg", &[]);
+        let result = check_file_health(&path, false);
+        assert!(matches!(result, Err(MediaAnalyzerError::EmptyFile(p)) if p == path));
+    }
+
+    #[test]
+    fn test_tiny_jpeg_is_suspected_truncated() {
+        let path = temp_file("IMG_0001.JPG", &jpeg(200));
+        let health = check_file_health(&path, false).unwrap();
+        assert!(health.suspected_truncated);
+        assert!(!health.truncated_jpeg);
+        assert!(matches!(
+            check_file_health(&path, true),
+            Err(MediaAnalyzerError::UnsupportedFile { .. })
+        ));
+
+        // Small files of other types are fine.
+        let path = temp_file("notes.txt", b"hello");
+        assert_eq!(
+            check_file_health(&path, true).unwrap(),
+            FileHealth::default()
+        );
+    }
+
+    #[test]
+    fn test_jpeg_without_eoi_is_truncated() {
+        let mut bytes = jpeg(10_000);
+        let path = temp_file("IMG_0001.jpg", &bytes);
+        assert_eq!(
+            check_file_health(&path, true).unwrap(),
+            FileHealth::default()
+        );
+
+        // Zero padding after the EOI marker is fine.
+        bytes.extend([0; 5000]);
+        let path = temp_file("IMG_0001.jpg", &bytes);
+        assert!(!check_file_health(&path, false).unwrap().truncated_jpeg);
+
+        let bytes = &jpeg(10_000)[..10_000 - 2];
+        let path = temp_file("IMG_0001.jpg", bytes);
+        let health = check_file_health(&path, false).unwrap();
+        assert!(health.truncated_jpeg);
+        assert!(!health.suspected_truncated);
+    }
+
+    #[test]
+    fn test_jpeg_tail() {
+        assert!(!jpeg_tail_is_truncated(&[0xAB, 0xFF, 0xD9]));
+        assert!(!jpeg_tail_is_truncated(&[
+            0xAB, 0xFF, 0xD9, 0xD9, 0xD9, 0, 0
+        ]));
+        assert!(!jpeg_tail_is_truncated(b"\xAB\xFF\xD9trailerSEFT"));
+        assert!(!jpeg_tail_is_truncated(&[0; 16]));
+        assert!(jpeg_tail_is_truncated(&[0xAB, 0xCD, 0xEF]));
+        assert!(jpeg_tail_is_truncated(b"\xFF\xD9 and a long trailer"));
+    }
+
+    /// A JPEG with an EXIF segment and a frame header of `width` by `height`.
+    fn jpeg_with_frame(width: u16, height: u16) -> Vec<u8> {
+        let mut bytes = JPEG_SOI.to_vec();
+        // An APP1 segment to skip, with a stray 0xFF fill byte before its marker.
+        bytes.extend([0xFF, 0xFF, 0xE1, 0x00, 0x08]);
+        bytes.extend(b"Exif\0\0");
+        // DHT shares the SOF marker range, but is not a frame header.
+        bytes.extend([0xFF, 0xC4, 0x00, 0x03, 0x00]);
+        bytes.extend([0xFF, 0xC0, 0x00, 0x11, 0x08]);
+        bytes.extend(height.to_be_bytes());
+        bytes.extend(width.to_be_bytes());
+        bytes.extend([0x03, 0x01, 0x22, 0x00, 0x02, 0x11, 0x01, 0x03, 0x11, 0x01]);
+        bytes.extend([0xFF, 0xDA, 0x00, 0x02, 0xAB, 0xCD]);
+        bytes.extend(JPEG_EOI);
+        bytes
+    }
+
+    #[test]
+    fn test_jpeg_frame_size() {
+        let size = jpeg_frame_size(jpeg_with_frame(160, 120).as_slice()).unwrap();
+        assert_eq!(size, Some((160, 120)));
+        assert_eq!(jpeg_frame_size(b"\x89PNG\r\n".as_slice()).unwrap(), None);
+        // Image data before any frame header.
+        let bytes = [0xFF, 0xD8, 0xFF, 0xDA, 0x00, 0x02, 0xFF, 0xD9];
+        assert_eq!(jpeg_frame_size(bytes.as_slice()).unwrap(), None);
+        assert!(jpeg_frame_size([0xFF, 0xD8, 0xFF].as_slice()).is_err());
+    }
+
+    #[test]
+    fn test_exif_claims_another_size() {
+        // A recovery tool saved the thumbnail under the metadata of the full photo.
+        let path = temp_file("IMG_0001.jpg", &jpeg_with_frame(160, 120));
+        let exif = ExifData::new(serde_json::json!({
+            "Image": {
+                "ImageWidth": 4000,
+                "ImageHeight": 3000,
+                "ExifImageWidth": 4000,
+                "ExifImageHeight": 3000,
+                "Orientation": 6
+            }
+        }));
+        assert_eq!(decoded_dimension_mismatch(&path, &exif), Some((120, 160)));
+    }
+
+    #[test]
+    fn test_matching_sizes_are_not_flagged() {
+        let path = temp_file("IMG_0001.jpg", &jpeg_with_frame(4000, 3000));
+        // Rotated, and rounded up to whole blocks.
+        let exif = ExifData::new(serde_json::json!({
+            "Image": {
+                "ImageWidth": 4000,
+                "ImageHeight": 3000,
+                "ExifImageWidth": 3008,
+                "ExifImageHeight": 4000
+            }
+        }));
+        assert_eq!(decoded_dimension_mismatch(&path, &exif), None);
+
+        // Other formats are not checked.
+        let path = temp_file("IMG_0001.png", b"\x89PNG\r\n\x1a\n");
+        assert_eq!(decoded_dimension_mismatch(&path, &exif), None);
+    }
+}
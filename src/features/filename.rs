@@ -0,0 +1,281 @@
+//! The file's current name and, when sync tools renamed it (`IMG_0001 (1).jpg`), its original
+//! name as preserved in the metadata. Filename-based detections try both.
+
+use crate::ExifData;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// Tags that may hold the file's original name, in order of preference. `ObjectName` is often
+/// a title rather than a file name, so it is only used when it has a file extension.
+const ORIGINAL_FILENAME_TAGS: [&str; 3] = ["PreservedFileName", "RawFileName", "ObjectName"];
+
+fn looks_like_filename(name: &str) -> bool {
+    Path::new(name)
+        .extension()
+        .is_some_and(|ext| !ext.is_empty() && ext.len() <= 5)
+}
+
+/// Reads the original file name from the metadata, with the tag it was found in.
+pub fn get_original_filename(exif: &ExifData) -> Option<(&'static str, &str)> {
+    ORIGINAL_FILENAME_TAGS.iter().find_map(|tag| {
+        let name = exif.get_str(tag)?.trim();
+        let usable = !name.is_empty() && (*tag != "ObjectName" || looks_like_filename(name));
+        usable.then_some((*tag, name))
+    })
+}
+
+/// Which of the file's names a filename-based detection matched.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum FilenameKind {
+    Current,
+    Original,
+}
+
+impl FilenameKind {
+    #[must_use]
+    pub const fn describe(self) -> &'static str {
+        match self {
+            Self::Current => "filename",
+            Self::Original => "original filename",
+        }
+    }
+}
+
+/// The lowercased names to run filename-based detections on: the current name, and the
+/// original name when it differs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FilenameCandidates {
+    current: String,
+    original: Option<String>,
+}
+
+impl FilenameCandidates {
+    pub fn new(path: &Path, exif: &ExifData) -> Self {
+        let current = path
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .to_lowercase();
+        let original = get_original_filename(exif)
+            .map(|(_, name)| name.to_lowercase())
+            .filter(|name| *name != current);
+        Self { current, original }
+    }
+
+    /// Runs `detect` on the current name, then on the original name, returning the first
+    /// detection and which name it came from.
+    pub fn find_map<T>(
+        &self,
+        mut detect: impl FnMut(&str) -> Option<T>,
+    ) -> Option<(T, FilenameKind)> {
+        detect(&self.current)
+            .map(|found| (found, FilenameKind::Current))
+            .or_else(|| {
+                let original = self.original.as_deref()?;
+                detect(original).map(|found| (found, FilenameKind::Original))
+            })
+    }
+}
+
+/// A tag for files whose name matches a regex, for naming conventions the built-in detections
+/// don't know, like a `_AER` suffix for drone shots. See the analyzer's `filename_tag_patterns`.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FilenameTagPattern {
+    /// The key in `features.filename_tags`.
+    pub tag_name: String,
+    /// A regex, matched against the lowercased file name and original file name.
+    pub pattern: String,
+    /// The capture group whose text is the value of the tag, like the number of a shot. The
+    /// value is `None` without one.
+    #[serde(default)]
+    pub capture_group_as_id: Option<usize>,
+}
+
+/// The compiled [`FilenameTagPattern`]s of an analyzer.
+#[derive(Debug, Clone, Default)]
+pub struct FilenameTagger {
+    patterns: Vec<(FilenameTagPattern, Regex)>,
+}
+
+impl FilenameTagger {
+    /// Compiles the patterns.
+    ///
+    /// # Errors
+    /// * A description of the first pattern with an empty tag name, an invalid regex, or a
+    ///   capture group the regex doesn't have.
+    pub fn new(patterns: Vec<FilenameTagPattern>) -> Result<Self, String> {
+        let patterns = patterns
+            .into_iter()
+            .map(|pattern| {
+                if pattern.tag_name.is_empty() {
+                    return Err(format!(
+                        "filename tag pattern `{}` has an empty tag name",
+                        pattern.pattern
+                    ));
+                }
+                let regex = Regex::new(&pattern.pattern).map_err(|e| {
+                    format!(
+                        "invalid regex `{}` for filename tag `{}`: {e}",
+                        pattern.pattern, pattern.tag_name
+                    )
+                })?;
+                if let Some(group) = pattern.capture_group_as_id
+                    && group >= regex.captures_len()
+                {
+                    return Err(format!(
+                        "regex `{}` for filename tag `{}` has no capture group {group}",
+                        pattern.pattern, pattern.tag_name
+                    ));
+                }
+                Ok((pattern, regex))
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self { patterns })
+    }
+
+    /// The patterns, as passed to [`FilenameTagger::new`].
+    pub fn patterns(&self) -> impl Iterator<Item = &FilenameTagPattern> {
+        self.patterns.iter().map(|(pattern, _)| pattern)
+    }
+
+    /// The tags whose pattern matches the current or the original name, with the captured id
+    /// and the name that matched. The first matching pattern of a tag name wins.
+    pub fn tags(
+        &self,
+        candidates: &FilenameCandidates,
+    ) -> BTreeMap<String, (Option<String>, FilenameKind)> {
+        let mut tags = BTreeMap::new();
+        for (pattern, regex) in &self.patterns {
+            if tags.contains_key(&pattern.tag_name) {
+                continue;
+            }
+            let found = candidates.find_map(|name| {
+                let captures = regex.captures(name)?;
+                Some(
+                    pattern
+                        .capture_group_as_id
+                        .and_then(|group| captures.get(group))
+                        .map(|id| id.as_str().to_string()),
+                )
+            });
+            if let Some(found) = found {
+                tags.insert(pattern.tag_name.clone(), found);
+            }
+        }
+        tags
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_original_filename_tag_priority() {
+        let exif = ExifData::new(json!({
+            "ObjectName": "IMG_0001.JPG",
+            "RawFileName": "DSC_0042.NEF"
+        }));
+        assert_eq!(
+            get_original_filename(&exif),
+            Some(("RawFileName", "DSC_0042.NEF"))
+        );
+    }
+
+    #[test]
+    fn test_object_name_title_is_not_a_filename() {
+        let exif = ExifData::new(json!({ "ObjectName": "Sunset at the beach" }));
+        assert_eq!(get_original_filename(&exif), None);
+    }
+
+    #[test]
+    fn test_candidates_prefer_current_name() {
+        let exif = ExifData::new(json!({ "PreservedFileName": "night_shot.jpg" }));
+        let candidates = FilenameCandidates::new(Path::new("IMG_0001 (1).jpg"), &exif);
+        let night = candidates.find_map(|name| name.contains("night").then_some(()));
+        assert_eq!(night, Some(((), FilenameKind::Original)));
+
+        let img = candidates.find_map(|name| name.starts_with("img").then_some(()));
+        assert_eq!(img, Some(((), FilenameKind::Current)));
+    }
+
+    fn pattern(
+        tag_name: &str,
+        pattern: &str,
+        capture_group_as_id: Option<usize>,
+    ) -> FilenameTagPattern {
+        FilenameTagPattern {
+            tag_name: tag_name.to_string(),
+            pattern: pattern.to_string(),
+            capture_group_as_id,
+        }
+    }
+
+    fn tags_of(
+        tagger: &FilenameTagger,
+        path: &str,
+        exif: &ExifData,
+    ) -> BTreeMap<String, (Option<String>, FilenameKind)> {
+        tagger.tags(&FilenameCandidates::new(Path::new(path), exif))
+    }
+
+    #[test]
+    fn test_plain_filename_tag_match() {
+        let tagger = FilenameTagger::new(vec![
+            pattern("drone", r"_aer\.", None),
+            pattern("underwater", r"_uw\.", None),
+        ])
+        .unwrap();
+        let exif = ExifData::new(json!({}));
+        let tags = tags_of(&tagger, "DJI_0042_AER.JPG", &exif);
+        assert_eq!(
+            tags,
+            BTreeMap::from([("drone".to_string(), (None, FilenameKind::Current))])
+        );
+        assert!(tags_of(&tagger, "IMG_0001.jpg", &exif).is_empty());
+
+        // A renamed copy is still tagged by its original name.
+        let renamed = ExifData::new(json!({ "PreservedFileName": "reef_UW.jpg" }));
+        assert_eq!(
+            tags_of(&tagger, "IMG_0001 (1).jpg", &renamed)["underwater"],
+            (None, FilenameKind::Original)
+        );
+    }
+
+    #[test]
+    fn test_capture_group_is_the_tag_value() {
+        let tagger = FilenameTagger::new(vec![
+            pattern("roll", r"^roll(\d+)_", Some(1)),
+            pattern("roll", r"roll", None),
+        ])
+        .unwrap();
+        let exif = ExifData::new(json!({}));
+        // Both patterns match, and the first one of a tag name wins.
+        assert_eq!(
+            tags_of(&tagger, "Roll042_frame12.tif", &exif)["roll"],
+            (Some("042".to_string()), FilenameKind::Current)
+        );
+        assert_eq!(tags_of(&tagger, "film_roll1_.tif", &exif)["roll"].0, None);
+    }
+
+    #[test]
+    fn test_invalid_patterns_are_rejected() {
+        assert_eq!(
+            FilenameTagger::new(vec![pattern("drone", "_aer(", None)])
+                .unwrap_err()
+                .lines()
+                .next(),
+            Some("invalid regex `_aer(` for filename tag `drone`: regex parse error:")
+        );
+        assert_eq!(
+            FilenameTagger::new(vec![pattern("roll", r"^roll\d+", Some(1))]).unwrap_err(),
+            "regex `^roll\\d+` for filename tag `roll` has no capture group 1"
+        );
+        assert!(FilenameTagger::new(vec![pattern("", "x", None)]).is_err());
+    }
+}
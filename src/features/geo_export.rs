@@ -0,0 +1,192 @@
+//! Conversions of GPS info to `GeoJSON` (RFC 7946) and map links.
+
+use crate::features::gps::GpsInfo;
+use crate::structs::MediaMetadata;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value, json};
+
+/// A web map service to link to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum MapProvider {
+    Google,
+    OpenStreetMap,
+    Apple,
+}
+
+/// The zoom level of `OpenStreetMap` links, at which streets are visible.
+const OSM_ZOOM: u8 = 16;
+
+impl GpsInfo {
+    /// A `GeoJSON` Feature with a Point geometry, in `[longitude, latitude]` order with the
+    /// altitude as a third coordinate when it is known.
+    ///
+    /// The properties are the location's `name`, `admin1`, `admin2`, `countryCode` and
+    /// `countryName`, when it was reverse geocoded. The fields of `properties` are added to them, and win when names clash.
+    /// A `properties` value that isn't an object is ignored, as `GeoJSON` properties must be one.
+    #[must_use]
+    pub fn to_geojson_feature(&self, properties: Option<Value>) -> Value {
+        let mut coordinates = vec![self.longitude, self.latitude];
+        coordinates.extend(self.altitude);
+        let mut feature_properties = Map::new();
+        if let Some(location) = &self.location {
+            for (key, value) in [
+                ("name", json!(location.name)),
+                ("admin1", json!(location.admin1)),
+                ("admin2", json!(location.admin2)),
+                ("countryCode", json!(location.country_code)),
+                ("countryName", json!(location.country_name)),
+            ] {
+                feature_properties.insert(key.to_string(), value);
+            }
+        }
+        if let Some(Value::Object(extra)) = properties {
+            feature_properties.extend(extra);
+        }
+        json!({
+            "type": "Feature",
+            "geometry": { "type": "Point", "coordinates": coordinates },
+            "properties": feature_properties,
+        })
+    }
+
+    /// A link that shows the location on `provider`'s map.
+    #[must_use]
+    pub fn maps_url(&self, provider: MapProvider) -> String {
+        let (latitude, longitude) = (self.latitude, self.longitude);
+        match provider {
+            MapProvider::Google => {
+                format!("https://www.google.com/maps/search/?api=1&query={latitude},{longitude}")
+            }
+            MapProvider::OpenStreetMap => format!(
+                "https://www.openstreetmap.org/?mlat={latitude}&mlon={longitude}#map={OSM_ZOOM}/{latitude}/{longitude}"
+            ),
+            MapProvider::Apple => format!("https://maps.apple.com/?ll={latitude},{longitude}"),
+        }
+    }
+}
+
+/// A `GeoJSON` `FeatureCollection` with a feature per geotagged result, see
+/// [`GpsInfo::to_geojson_feature`]. Results without GPS info are skipped.
+///
+/// Each feature also has the result's `hash`, its `captureTime` (local, with the offset when
+/// the timezone is known) and its `captureTimeUtc` (`null` when unknown).
+#[must_use]
+pub fn results_to_geojson_collection(results: &[MediaMetadata]) -> Value {
+    let features: Vec<Value> = results
+        .iter()
+        .filter_map(|result| {
+            let gps = result.gps.as_ref()?;
+            Some(gps.to_geojson_feature(Some(json!({
+                "hash": result.hash,
+                "captureTime": result.time.best_display_time(),
+                "captureTimeUtc": result.time.utc_rfc3339(),
+            }))))
+        })
+        .collect();
+    json!({ "type": "FeatureCollection", "features": features })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::features::gps::{CoordinatePrecision, LocationName};
+    use crate::test_support::MediaMetadataFixture;
+
+    fn amsterdam() -> GpsInfo {
+        GpsInfo {
+            latitude: 52.379_189,
+            longitude: 4.899_431,
+            altitude: Some(2.5),
+            location: Some(LocationName {
+                latitude: 52.374_03,
+                longitude: 4.889_69,
+                name: "Amsterdam".to_string(),
+                admin1: "North Holland".to_string(),
+                admin2: "Gemeente Amsterdam".to_string(),
+                country_code: "NL".to_string(),
+                country_name: Some("Netherlands".to_string()),
+            }),
+            image_direction: None,
+            image_direction_ref: None,
+            coordinate_precision: CoordinatePrecision::Exact,
+            satellites: None,
+            fix_mode: None,
+            measurement_in_progress: None,
+            fix_quality: None,
+            location_conflict: None,
+            timezone: None,
+            movement_from_previous: None,
+        }
+    }
+
+    fn result(hash: &str, gps: Option<GpsInfo>) -> MediaMetadata {
+        MediaMetadata {
+            gps,
+            ..MediaMetadataFixture::photo().with_hash(hash).build()
+        }
+    }
+
+    #[test]
+    fn test_feature_has_longitude_first() {
+        let feature = amsterdam().to_geojson_feature(Some(json!({ "name": "Home", "id": 7 })));
+        assert_eq!(feature["type"], "Feature");
+        assert_eq!(feature["geometry"]["type"], "Point");
+        assert_eq!(
+            feature["geometry"]["coordinates"],
+            json!([4.899_431, 52.379_189, 2.5])
+        );
+        let properties = &feature["properties"];
+        assert_eq!(properties["name"], "Home");
+        assert_eq!(properties["id"], 7);
+        assert_eq!(properties["countryCode"], "NL");
+
+        let mut gps = amsterdam();
+        gps.altitude = None;
+        let feature = gps.to_geojson_feature(Some(json!("not an object")));
+        assert_eq!(
+            feature["geometry"]["coordinates"],
+            json!([4.899_431, 52.379_189])
+        );
+        assert_eq!(feature["properties"]["name"], "Amsterdam");
+    }
+
+    #[test]
+    fn test_maps_urls() {
+        let gps = amsterdam();
+        assert_eq!(
+            gps.maps_url(MapProvider::Google),
+            "https://www.google.com/maps/search/?api=1&query=52.379189,4.899431"
+        );
+        assert_eq!(
+            gps.maps_url(MapProvider::OpenStreetMap),
+            "https://www.openstreetmap.org/?mlat=52.379189&mlon=4.899431#map=16/52.379189/4.899431"
+        );
+        assert_eq!(
+            gps.maps_url(MapProvider::Apple),
+            "https://maps.apple.com/?ll=52.379189,4.899431"
+        );
+    }
+
+    #[test]
+    fn test_collection_skips_results_without_gps() {
+        let results = vec![
+            result("a", Some(amsterdam())),
+            result("b", None),
+            result("c", Some(amsterdam())),
+        ];
+        let collection = results_to_geojson_collection(&results);
+        assert_eq!(collection["type"], "FeatureCollection");
+        let features = collection["features"].as_array().unwrap();
+        let hashes: Vec<&Value> = features.iter().map(|f| &f["properties"]["hash"]).collect();
+        assert_eq!(hashes, [&json!("a"), &json!("c")]);
+        assert_eq!(
+            features[0]["properties"]["captureTime"],
+            "2024-06-01T10:00:00+02:00"
+        );
+        assert_eq!(
+            features[0]["properties"]["captureTimeUtc"],
+            "2024-06-01T08:00:00Z"
+        );
+    }
+}
@@ -0,0 +1,298 @@
+use crate::features::coarse_geocode::{COARSE_GEOCODER, CoarseGeocoder};
+use crate::features::gps::LocationName;
+use lru::LruCache;
+use reverse_geocoder::ReverseGeocoder;
+use std::num::NonZeroUsize;
+use std::panic::{AssertUnwindSafe, catch_unwind};
+use std::sync::{Arc, Mutex, PoisonError};
+
+/// Default number of locations kept in the reverse geocoding cache.
+pub const DEFAULT_GEOCODE_CACHE_SIZE: usize = 10_000;
+
+/// Coordinates are rounded to this many decimals for the cache key. 0.001 degree is about 111m
+/// of latitude, and less of longitude away from the equator.
+const CACHE_KEY_SCALE: f64 = 1000.0;
+/// The largest latitude and longitude searched. The dateline and the poles themselves are moved
+/// just inside, as the geocoder's k-d tree has panicked on a longitude of exactly 180.
+const MAX_SEARCH_LATITUDE: f64 = 89.999_999;
+const MAX_SEARCH_LONGITUDE: f64 = 179.999_999;
+
+/// The dataset places are searched in.
+enum Dataset {
+    Full(Arc<ReverseGeocoder>),
+    Coarse(&'static CoarseGeocoder),
+}
+
+/// A [`ReverseGeocoder`] with an LRU cache in front, so bursts of photos taken at the same spot
+/// only search once.
+pub struct CachedGeocoder {
+    dataset: Dataset,
+    cache: Option<Mutex<LruCache<(i64, i64), LocationName>>>,
+}
+
+impl CachedGeocoder {
+    /// Creates the geocoder with room for `cache_size` locations. A size of 0 disables caching.
+    #[must_use]
+    pub fn new(cache_size: usize) -> Self {
+        Self::with_geocoder(Arc::new(ReverseGeocoder::new()), cache_size)
+    }
+
+    /// Like [`CachedGeocoder::new`], but shares an already loaded geocoder dataset.
+    #[must_use]
+    pub fn with_geocoder(geocoder: Arc<ReverseGeocoder>, cache_size: usize) -> Self {
+        Self::with_dataset(Dataset::Full(geocoder), cache_size)
+    }
+
+    /// Like [`CachedGeocoder::new`], but searches the much smaller coarse dataset, see
+    /// [`coarse_geocode`](crate::features::coarse_geocode) for what that costs in accuracy.
+    #[must_use]
+    pub fn coarse(cache_size: usize) -> Self {
+        Self::with_dataset(Dataset::Coarse(&COARSE_GEOCODER), cache_size)
+    }
+
+    fn with_dataset(dataset: Dataset, cache_size: usize) -> Self {
+        Self {
+            dataset,
+            cache: NonZeroUsize::new(cache_size).map(|size| Mutex::new(LruCache::new(size))),
+        }
+    }
+
+    #[allow(clippy::cast_possible_truncation)]
+    fn cache_key(latitude: f64, longitude: f64) -> (i64, i64) {
+        (
+            (latitude * CACHE_KEY_SCALE).round() as i64,
+            (longitude * CACHE_KEY_SCALE).round() as i64,
+        )
+    }
+
+    /// Finds the nearest named place to the coordinates.
+    pub fn reverse_geocode(&self, latitude: f64, longitude: f64) -> LocationName {
+        let Some(cache) = &self.cache else {
+            return self.search(latitude, longitude);
+        };
+        let key = Self::cache_key(latitude, longitude);
+        if let Some(location) = cache
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(&key)
+        {
+            return location.clone();
+        }
+        let location = self.search(latitude, longitude);
+        cache
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .put(key, location.clone());
+        location
+    }
+
+    /// The number of locations currently cached.
+    #[must_use]
+    pub fn cached_len(&self) -> usize {
+        self.cache.as_ref().map_or(0, |cache| {
+            cache.lock().unwrap_or_else(PoisonError::into_inner).len()
+        })
+    }
+
+    fn search(&self, latitude: f64, longitude: f64) -> LocationName {
+        match &self.dataset {
+            Dataset::Full(geocoder) => fail_soft(latitude, longitude, |latitude, longitude| {
+                let record = geocoder.search((latitude, longitude)).record;
+                LocationName {
+                    latitude: record.lat,
+                    longitude: record.lon,
+                    name: record.name.clone(),
+                    admin1: record.admin1.clone(),
+                    admin2: record.admin2.clone(),
+                    country_code: record.cc.clone(),
+                    country_name: country_name(&record.cc),
+                }
+            }),
+            Dataset::Coarse(geocoder) => {
+                let Some(place) = geocoder.search(latitude, longitude) else {
+                    return LocationName::unnamed(latitude, longitude);
+                };
+                LocationName {
+                    latitude: place.latitude,
+                    longitude: place.longitude,
+                    name: place.name.clone(),
+                    admin1: place.admin1.clone(),
+                    admin2: String::new(),
+                    country_code: place.country_code.clone(),
+                    country_name: country_name(&place.country_code),
+                }
+            }
+        }
+    }
+}
+
+/// Moves the dateline and the poles just inside the searched range.
+fn clamp_search_coordinates(latitude: f64, longitude: f64) -> (f64, f64) {
+    (
+        latitude.clamp(-MAX_SEARCH_LATITUDE, MAX_SEARCH_LATITUDE),
+        longitude.clamp(-MAX_SEARCH_LONGITUDE, MAX_SEARCH_LONGITUDE),
+    )
+}
+
+/// Runs `search` on the clamped coordinates. When it panics or finds no country, this logs a
+/// warning and falls back to a location with only the country of the nearest place in the
+/// coarse dataset, so one bad coordinate cannot take down a whole batch.
+fn fail_soft(
+    latitude: f64,
+    longitude: f64,
+    search: impl FnOnce(f64, f64) -> LocationName,
+) -> LocationName {
+    let (search_latitude, search_longitude) = clamp_search_coordinates(latitude, longitude);
+    // The search only reads the in-memory dataset, so nothing is left half-updated by a panic.
+    match catch_unwind(AssertUnwindSafe(|| {
+        search(search_latitude, search_longitude)
+    })) {
+        Ok(location) if !location.country_code.is_empty() => return location,
+        Ok(_) => log::warn!(
+            "Reverse geocoding {latitude}, {longitude} found no country, using the coarse country"
+        ),
+        Err(_) => log::warn!(
+            "Reverse geocoding {latitude}, {longitude} panicked, using the coarse country"
+        ),
+    }
+    country_only(latitude, longitude)
+}
+
+/// A location named only by the country of the nearest place in the coarse dataset.
+fn country_only(latitude: f64, longitude: f64) -> LocationName {
+    let (search_latitude, search_longitude) = clamp_search_coordinates(latitude, longitude);
+    let mut location = LocationName::unnamed(latitude, longitude);
+    if let Some(place) = COARSE_GEOCODER.search(search_latitude, search_longitude) {
+        location.country_code.clone_from(&place.country_code);
+        location.country_name = country_name(&place.country_code);
+    }
+    location
+}
+
+fn country_name(country_code: &str) -> Option<String> {
+    rust_iso3166::from_alpha2(country_code).map(|a| normalize_country_name(a.name))
+}
+
+fn normalize_country_name(name: &str) -> String {
+    match name {
+        "Netherlands (Kingdom of the)" => "The Netherlands".to_string(),
+        other => other.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_points_5km_apart_have_different_keys() {
+        // Roughly 5km north and 5km east of central Amsterdam.
+        let center = CachedGeocoder::cache_key(52.3792, 4.8994);
+        let north = CachedGeocoder::cache_key(52.4242, 4.8994);
+        let east = CachedGeocoder::cache_key(52.3792, 4.9731);
+        assert_ne!(center, north);
+        assert_ne!(center, east);
+    }
+
+    #[test]
+    fn test_points_meters_apart_share_a_key() {
+        assert_eq!(
+            CachedGeocoder::cache_key(52.379_21, 4.899_41),
+            CachedGeocoder::cache_key(52.379_19, 4.899_43)
+        );
+    }
+
+    #[test]
+    fn test_repeated_lookups_are_cached() {
+        let geocoder = CachedGeocoder::new(10);
+        let first = geocoder.reverse_geocode(52.379_189, 4.899_431);
+        let second = geocoder.reverse_geocode(52.379_19, 4.899_43);
+        assert_eq!(first, second);
+        assert_eq!(geocoder.cached_len(), 1);
+
+        // Leerdam is ~40km away and must not reuse Amsterdam's entry.
+        let leerdam = geocoder.reverse_geocode(51.8938, 5.0918);
+        assert_ne!(leerdam.name, first.name);
+        assert_eq!(geocoder.cached_len(), 2);
+    }
+
+    #[test]
+    fn test_zero_cache_size_disables_caching() {
+        let geocoder = CachedGeocoder::new(0);
+        let location = geocoder.reverse_geocode(52.379_189, 4.899_431);
+        assert_eq!(location.country_code, "NL");
+        assert_eq!(geocoder.cached_len(), 0);
+    }
+
+    #[test]
+    fn test_coarse_geocoder_only_loses_granularity() {
+        let full = CachedGeocoder::new(0);
+        let coarse = CachedGeocoder::coarse(0);
+        // Amsterdam, Leerdam, Madrid, Tokyo and New York, all well away from a border.
+        for (latitude, longitude) in [
+            (52.379_189, 4.899_431),
+            (51.8938, 5.0918),
+            (40.4168, -3.7038),
+            (35.6762, 139.6503),
+            (40.7128, -74.0060),
+        ] {
+            let full = full.reverse_geocode(latitude, longitude);
+            let coarse = coarse.reverse_geocode(latitude, longitude);
+            assert_eq!(coarse.country_code, full.country_code);
+            assert_eq!(coarse.country_name, full.country_name);
+            assert!(coarse.admin2.is_empty());
+            assert!((coarse.latitude - latitude).abs() < 1.0);
+            assert!((coarse.longitude - longitude).abs() < 1.0);
+        }
+    }
+
+    #[test]
+    fn test_dateline_and_poles_are_geocoded() {
+        let geocoder = CachedGeocoder::new(0);
+        // Exactly on the dateline in Fiji, and its other side.
+        for longitude in [180.0, -180.0] {
+            let location = geocoder.reverse_geocode(-16.5, longitude);
+            assert_eq!(location.country_code, "FJ", "at longitude {longitude}");
+        }
+        // The nearest places to the poles are far away, but there are some.
+        for latitude in [90.0, -90.0] {
+            let location = geocoder.reverse_geocode(latitude, 0.0);
+            assert!(!location.country_code.is_empty(), "at latitude {latitude}");
+        }
+    }
+
+    #[test]
+    fn test_normal_coordinates_are_searched_as_is() {
+        let location = fail_soft(52.379_189, 4.899_431, |latitude, longitude| {
+            assert!((latitude - 52.379_189).abs() < f64::EPSILON);
+            assert!((longitude - 4.899_431).abs() < f64::EPSILON);
+            LocationName {
+                name: "Amsterdam".to_string(),
+                country_code: "NL".to_string(),
+                ..LocationName::unnamed(latitude, longitude)
+            }
+        });
+        assert_eq!(location.name, "Amsterdam");
+
+        let location = CachedGeocoder::new(0).reverse_geocode(52.379_189, 4.899_431);
+        assert_eq!(location.name, "Amsterdam");
+        assert_eq!(location.admin1, "North Holland");
+    }
+
+    #[test]
+    fn test_panicking_search_falls_back_to_the_country() {
+        let location = fail_soft(52.379_189, 4.899_431, |_, _| panic!("k-d tree bug"));
+        assert_eq!(location.country_code, "NL");
+        assert_eq!(location.country_name, Some("The Netherlands".to_string()));
+        assert!(location.name.is_empty());
+        // The photo's coordinates are kept, not those of the coarse place.
+        assert!((location.latitude - 52.379_189).abs() < f64::EPSILON);
+        assert!((location.longitude - 4.899_431).abs() < f64::EPSILON);
+
+        // A result without a country is degenerate, too.
+        let location = fail_soft(40.4168, -3.7038, |latitude, longitude| {
+            LocationName::unnamed(latitude, longitude)
+        });
+        assert_eq!(location.country_code, "ES");
+    }
+}
@@ -1,6 +1,23 @@
 use crate::ExifData;
-use reverse_geocoder::ReverseGeocoder;
+use crate::features::geocode::CachedGeocoder;
+use crate::features::movement::Movement;
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// The revision of the GPS parsing and geocoding logic. Bump it when a change can alter a
+/// [`GpsInfo`](crate::GpsInfo).
+pub const GPS_LOGIC_REVISION: u32 = 6;
+/// Coordinates with at most this many decimals (~1km) are [`CoordinatePrecision::Approximate`].
+pub const APPROXIMATE_MAX_DECIMALS: usize = 2;
+/// A 3D fix needs at least 4 satellites; with fewer, the fix quality is
+/// [`GpsFixQuality::Poor`].
+const MIN_3D_FIX_SATELLITES: u8 = 4;
+/// The fewest satellites of a [`GpsFixQuality::Good`] fix.
+const GOOD_FIX_MIN_SATELLITES: u8 = 6;
+/// The highest dilution of precision of a [`GpsFixQuality::Good`] fix.
+const GOOD_FIX_MAX_DOP: f64 = 2.0;
+/// Fixes with a higher dilution of precision are [`GpsFixQuality::Poor`].
+const POOR_FIX_MIN_DOP: f64 = 5.0;
 
 #[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
 pub enum DirectionRef {
@@ -13,10 +30,178 @@ pub enum DirectionRef {
 pub struct GpsInfo {
     pub latitude: f64,
     pub longitude: f64,
+    #[serde(default)]
     pub altitude: Option<f64>,
-    pub location: LocationName,
+    /// The nearest named place. `None` when the coordinates were not reverse geocoded: with
+    /// `reverse_geocode` turned off, or for `VeryCoarse` coordinates.
+    #[serde(default)]
+    pub location: Option<LocationName>,
+    #[serde(default)]
     pub image_direction: Option<f64>,
+    #[serde(default)]
     pub image_direction_ref: Option<DirectionRef>,
+    /// How precisely the coordinates were recorded. A timezone looked up from coordinates that
+    /// aren't `Exact` gets a `Medium` confidence at most, and `VeryCoarse` coordinates are not
+    /// reverse geocoded.
+    #[serde(default)]
+    pub coordinate_precision: CoordinatePrecision,
+    /// The number of satellites used for the fix, from `GPSSatellites`.
+    #[serde(default)]
+    pub satellites: Option<u8>,
+    /// From `GPSMeasureMode`. The altitude of a 2D fix is dropped, unless the analyzer was built
+    /// with `trust_2d_altitude`.
+    #[serde(default)]
+    pub fix_mode: Option<GpsFixMode>,
+    /// From `GPSStatus`: `true` when the measurement was active, `false` when it was void.
+    #[serde(default)]
+    pub measurement_in_progress: Option<bool>,
+    /// A coarse judgement of the fix, from the fix mode, satellites and `GPSDOP`. `None` when
+    /// neither the satellites nor the dilution of precision are known.
+    #[serde(default)]
+    pub fix_quality: Option<GpsFixQuality>,
+    /// Whether the reverse-geocoded country differs from the country written in the file's
+    /// IPTC/XMP location. `None` when either is unknown.
+    #[serde(default)]
+    pub location_conflict: Option<bool>,
+    /// The IANA timezone at the coordinates, which zones the capture time. `Etc/GMT±N` at sea
+    /// with `nautical_timezone_fallback`. `None` when it was not looked up, as with
+    /// [`get_gps_info`] on its own.
+    #[serde(default)]
+    pub timezone: Option<String>,
+    /// The movement since the previously analyzed photo, with `track_movement` on.
+    #[serde(default)]
+    pub movement_from_previous: Option<Movement>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum GpsFixMode {
+    /// Latitude and longitude only; the altitude is not measured.
+    TwoD,
+    ThreeD,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum GpsFixQuality {
+    /// A 3D fix with at least 6 satellites and a dilution of precision of at most 2.
+    Good,
+    Fair,
+    /// A 2D fix, fewer than 4 satellites, or a dilution of precision over 5. The position may
+    /// be hundreds of meters off.
+    Poor,
+}
+
+/// Reads `GPSSatellites` when it is a count. The tag is free text, and some cameras list the
+/// satellite numbers instead.
+fn satellite_count(exif: &ExifData) -> Option<u8> {
+    match exif.get_value("GPSSatellites")? {
+        Value::Number(number) => number.as_u64(),
+        Value::String(text) => text.trim().parse().ok(),
+        _ => None,
+    }
+    .and_then(|count| u8::try_from(count).ok())
+}
+
+fn fix_mode(exif: &ExifData) -> Option<GpsFixMode> {
+    let mode = match exif.get_value("GPSMeasureMode")? {
+        Value::Number(number) => number.as_u64()?,
+        // Without `-n`, exiftool writes "2-Dimensional Measurement".
+        Value::String(text) => text.trim().chars().next()?.to_digit(10)?.into(),
+        _ => return None,
+    };
+    match mode {
+        2 => Some(GpsFixMode::TwoD),
+        3 => Some(GpsFixMode::ThreeD),
+        _ => None,
+    }
+}
+
+fn measurement_in_progress(exif: &ExifData) -> Option<bool> {
+    match exif.get_str("GPSStatus")?.trim() {
+        "A" => Some(true),
+        "V" => Some(false),
+        _ => None,
+    }
+}
+
+fn fix_quality(
+    fix_mode: Option<GpsFixMode>,
+    satellites: Option<u8>,
+    dop: Option<f64>,
+) -> Option<GpsFixQuality> {
+    let is_2d = fix_mode == Some(GpsFixMode::TwoD);
+    if satellites.is_none() && dop.is_none() {
+        return is_2d.then_some(GpsFixQuality::Poor);
+    }
+    let few_satellites = satellites.is_some_and(|count| count < MIN_3D_FIX_SATELLITES);
+    let imprecise = dop.is_some_and(|dop| dop > POOR_FIX_MIN_DOP);
+    if is_2d || few_satellites || imprecise {
+        return Some(GpsFixQuality::Poor);
+    }
+    let many_satellites = satellites.is_none_or(|count| count >= GOOD_FIX_MIN_SATELLITES);
+    let precise = dop.is_none_or(|dop| dop <= GOOD_FIX_MAX_DOP);
+    Some(if many_satellites && precise {
+        GpsFixQuality::Good
+    } else {
+        GpsFixQuality::Fair
+    })
+}
+
+/// Drops the altitude of a 2D fix, which didn't measure one: receivers write a stale or
+/// assumed value.
+pub fn drop_2d_fix_altitude(gps: &mut GpsInfo) {
+    if gps.fix_mode == Some(GpsFixMode::TwoD) {
+        gps.altitude = None;
+    }
+}
+
+/// How precisely GPS coordinates were recorded, judged from the decimals written in the file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum CoordinatePrecision {
+    #[default]
+    Exact,
+    /// Both coordinates have at most [`APPROXIMATE_MAX_DECIMALS`] decimals, so the location is
+    /// only known to within ~1km.
+    Approximate,
+    /// Both coordinates are whole or half degrees, as in privacy-stripped exports, so the
+    /// location is only known to within ~50km.
+    VeryCoarse,
+}
+
+/// The number of decimals written for a coordinate. Text keeps trailing zeros, which count as
+/// precision: "52.000000" is exact, 52 is not.
+fn written_decimals(value: &Value) -> Option<usize> {
+    let text = match value {
+        Value::Number(number) => number.to_string(),
+        Value::String(text) => text.trim().to_string(),
+        _ => return None,
+    };
+    if text.contains(['e', 'E']) {
+        return None;
+    }
+    let decimals = text.split_once('.').map_or("", |(_, decimals)| decimals);
+    decimals
+        .chars()
+        .all(|c| c.is_ascii_digit())
+        .then_some(decimals.len())
+}
+
+fn coordinate_precision(exif: &ExifData, latitude: f64, longitude: f64) -> CoordinatePrecision {
+    let decimals = ["GPSLatitude", "GPSLongitude"]
+        .map(|tag| exif.get_ignoring_case(tag).and_then(written_decimals));
+    let [Some(latitude_decimals), Some(longitude_decimals)] = decimals else {
+        return CoordinatePrecision::Exact;
+    };
+    let is_half_degree = |coordinate: f64| (coordinate * 2.0).fract() == 0.0;
+    if latitude_decimals.max(longitude_decimals) > APPROXIMATE_MAX_DECIMALS {
+        CoordinatePrecision::Exact
+    } else if is_half_degree(latitude) && is_half_degree(longitude) {
+        CoordinatePrecision::VeryCoarse
+    } else {
+        CoordinatePrecision::Approximate
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
@@ -25,22 +210,72 @@ pub struct LocationName {
     pub latitude: f64,
     pub longitude: f64,
     pub name: String,
+    /// The first-level administrative region, like a state or province. Empty for results
+    /// stored before it was recorded.
+    #[serde(default)]
     pub admin1: String,
+    /// The second-level administrative region, like a county or municipality.
+    #[serde(default)]
     pub admin2: String,
     pub country_code: String,
+    #[serde(default)]
     pub country_name: Option<String>,
 }
 
-pub fn get_gps_info(geocoder: &ReverseGeocoder, exif: &ExifData) -> Option<GpsInfo> {
-    let (Some(latitude), Some(longitude)) =
-        (exif.get_f64("GPSLatitude"), exif.get_f64("GPSLongitude"))
-    else {
-        return None;
-    };
+impl LocationName {
+    /// A location without a name, for coordinates that were not reverse geocoded.
+    pub(crate) const fn unnamed(latitude: f64, longitude: f64) -> Self {
+        Self {
+            latitude,
+            longitude,
+            name: String::new(),
+            admin1: String::new(),
+            admin2: String::new(),
+            country_code: String::new(),
+            country_name: None,
+        }
+    }
+}
+
+pub fn get_gps_info(geocoder: &CachedGeocoder, exif: &ExifData) -> Option<GpsInfo> {
+    let mut gps = get_gps_coordinates(exif)?;
+    // A place name for whole degrees would be a village that merely lies on the grid.
+    if gps.coordinate_precision != CoordinatePrecision::VeryCoarse {
+        gps.location = Some(geocoder.reverse_geocode(gps.latitude, gps.longitude));
+    }
+    Some(gps)
+}
+
+/// The QuickTime `GPSCoordinates` of the container, "latitude longitude [altitude]" with `-n`.
+/// 360 degree cameras like those of Insta360 and GoPro only write these, not the GPS tags.
+fn container_coordinates(exif: &ExifData) -> Option<(f64, f64, Option<f64>)> {
+    let mut values = exif
+        .get_str("GPSCoordinates")?
+        .split([' ', ','])
+        .filter(|value| !value.is_empty())
+        .map(str::parse::<f64>);
+    let latitude = values.next()?.ok()?;
+    let longitude = values.next()?.ok()?;
+    Some((latitude, longitude, values.next().and_then(Result::ok)))
+}
+
+/// Like [`get_gps_info`] without reverse geocoding: `location` is `None`.
+pub fn get_gps_coordinates(exif: &ExifData) -> Option<GpsInfo> {
+    let container = container_coordinates(exif);
+    let (latitude, longitude) = exif
+        .get_f64("GPSLatitude")
+        .zip(exif.get_f64("GPSLongitude"))
+        .or_else(|| container.map(|(latitude, longitude, _)| (latitude, longitude)))?;
     if latitude == 0.0 && longitude == 0.0 {
         return None;
     }
-    let altitude = extract_altitude(exif);
+    if !(-90.0..=90.0).contains(&latitude) || !(-180.0..=180.0).contains(&longitude) {
+        return None;
+    }
+    let altitude =
+        extract_altitude(exif).or_else(|| container.and_then(|(_, _, altitude)| altitude));
+    let satellites = satellite_count(exif);
+    let fix_mode = fix_mode(exif);
     let image_direction = exif.get_f64("GPSImgDirection");
     let image_direction_ref = exif.get_str("GPSImgDirectionRef").and_then(|s| match s {
         "T" => Some(DirectionRef::TrueNorth),
@@ -48,37 +283,24 @@ pub fn get_gps_info(geocoder: &ReverseGeocoder, exif: &ExifData) -> Option<GpsIn
         _ => None,
     });
 
-    let search_result = geocoder.search((latitude, longitude));
-    let country_info = rust_iso3166::from_alpha2(&search_result.record.cc);
-    let country_name = country_info.map(|a| normalize_country_name(a.name));
-    let record = search_result.record;
-    let location = LocationName {
-        latitude: record.lat,
-        longitude: record.lon,
-        name: record.name.clone(),
-        admin1: record.admin1.clone(),
-        admin2: record.admin2.clone(),
-        country_code: record.cc.clone(),
-        country_name,
-    };
-
     Some(GpsInfo {
         latitude,
         longitude,
         altitude,
-        location,
+        location: None,
         image_direction,
         image_direction_ref,
+        coordinate_precision: coordinate_precision(exif, latitude, longitude),
+        satellites,
+        fix_mode,
+        measurement_in_progress: measurement_in_progress(exif),
+        fix_quality: fix_quality(fix_mode, satellites, exif.get_f64("GPSDOP")),
+        location_conflict: None,
+        timezone: None,
+        movement_from_previous: None,
     })
 }
 
-fn normalize_country_name(name: &str) -> String {
-    match name {
-        "Netherlands (Kingdom of the)" => "The Netherlands".to_string(),
-        other => other.to_string(),
-    }
-}
-
 fn extract_altitude(exif: &ExifData) -> Option<f64> {
     let raw_alt = exif.group_f64("Location", "GPSAltitude");
 
@@ -113,12 +335,12 @@ fn extract_altitude(exif: &ExifData) -> Option<f64> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use reverse_geocoder::ReverseGeocoder;
+    use crate::features::geocode::DEFAULT_GEOCODE_CACHE_SIZE;
     use serde_json::json;
 
     #[tokio::test]
     async fn test_get_gps_info_with_full_data() {
-        let geocoder = ReverseGeocoder::new();
+        let geocoder = CachedGeocoder::new(DEFAULT_GEOCODE_CACHE_SIZE);
         // Simulate numeric exif data with all relevant GPS tags
         let exif = ExifData::new(json!({
             "GPSLatitude": 52.379_189,
@@ -142,16 +364,51 @@ mod tests {
         assert_eq!(gps_info.image_direction_ref, Some(DirectionRef::TrueNorth));
 
         // 3. Assert that the reverse geocoding worked as expected
-        let location = gps_info.location;
+        let location = gps_info.location.unwrap();
         assert_eq!(location.name, "Amsterdam");
         assert_eq!(location.admin1, "North Holland");
         assert_eq!(location.country_code, "NL");
         assert_eq!(location.country_name, Some("The Netherlands".to_string()));
     }
 
+    #[test]
+    fn test_coordinate_precision() {
+        let geocoder = CachedGeocoder::coarse(0);
+        let gps_for = |latitude: Value, longitude: Value| {
+            let exif = ExifData::new(json!({
+                "Location": { "GPSLatitude": latitude, "GPSLongitude": longitude }
+            }));
+            get_gps_info(&geocoder, &exif).unwrap()
+        };
+
+        // Whole degrees, as left by privacy-stripping exports, are kept but not geocoded.
+        let gps = gps_for(json!(52), json!(5));
+        assert_eq!(gps.coordinate_precision, CoordinatePrecision::VeryCoarse);
+        assert_eq!((gps.latitude, gps.longitude), (52.0, 5.0));
+        assert_eq!(gps.location, None);
+        let gps = gps_for(json!(52.5), json!(-4.0));
+        assert_eq!(gps.coordinate_precision, CoordinatePrecision::VeryCoarse);
+
+        let gps = gps_for(json!(52.38), json!(4.9));
+        assert_eq!(gps.coordinate_precision, CoordinatePrecision::Approximate);
+        assert_eq!(gps.location.unwrap().country_code, "NL");
+
+        let gps = gps_for(json!(52.379_189), json!(4.899_431));
+        assert_eq!(gps.coordinate_precision, CoordinatePrecision::Exact);
+        assert_eq!(gps.location.unwrap().country_code, "NL");
+        // One precise coordinate is enough, as the other may be round by chance.
+        let gps = gps_for(json!(52.0), json!(4.899_431));
+        assert_eq!(gps.coordinate_precision, CoordinatePrecision::Exact);
+        // Written as text, trailing zeros count as precision.
+        let gps = gps_for(json!("52.0"), json!("5.0"));
+        assert_eq!(gps.coordinate_precision, CoordinatePrecision::VeryCoarse);
+        let gps = gps_for(json!("52.000000"), json!("5.000000"));
+        assert_eq!(gps.coordinate_precision, CoordinatePrecision::Exact);
+    }
+
     #[tokio::test]
     async fn test_netherlands_country_name_normalization() {
-        let geocoder = ReverseGeocoder::new();
+        let geocoder = CachedGeocoder::new(DEFAULT_GEOCODE_CACHE_SIZE);
         // Coordinates for Leerdam, Netherlands
         let exif = ExifData::new(json!({
             "GPSLatitude": 51.8938,
@@ -167,17 +424,17 @@ mod tests {
 
         let gps_info = result.unwrap();
         // Ensure the geocoded region is indeed the Netherlands (NL)
-        assert_eq!(gps_info.location.country_code, "NL");
+        assert_eq!(gps_info.location.as_ref().unwrap().country_code, "NL");
         // Verify that country name is normalized correctly
         assert_eq!(
-            gps_info.location.country_name,
+            gps_info.location.unwrap().country_name,
             Some("The Netherlands".to_string())
         );
     }
 
     #[tokio::test]
     async fn test_get_gps_info_with_minimal_data() {
-        let geocoder = ReverseGeocoder::new();
+        let geocoder = CachedGeocoder::new(DEFAULT_GEOCODE_CACHE_SIZE);
         // Simulate numeric exif data with only the required tags
         let exif = ExifData::new(json!({
             "GPSLatitude": 40.7128,
@@ -200,13 +457,39 @@ mod tests {
         assert!(gps_info.image_direction_ref.is_none());
 
         // 4. Assert geocoding still worked
-        assert_eq!(gps_info.location.name, "New York City");
-        assert_eq!(gps_info.location.country_code, "US");
+        assert_eq!(gps_info.location.as_ref().unwrap().name, "New York City");
+        assert_eq!(gps_info.location.unwrap().country_code, "US");
+    }
+
+    #[test]
+    fn test_container_gps_coordinates() {
+        // A 360 degree video with only the QuickTime coordinates of the container.
+        let exif = ExifData::new(json!({
+            "Location": { "GPSCoordinates": "46.5583 7.9634 2061.4" }
+        }));
+        let gps = get_gps_coordinates(&exif).unwrap();
+        assert_eq!((gps.latitude, gps.longitude), (46.5583, 7.9634));
+        assert_eq!(gps.altitude, Some(2061.4));
+
+        // The GPS tags win over the container.
+        let exif = ExifData::new(json!({
+            "Location": {
+                "GPSLatitude": 52.379_189,
+                "GPSLongitude": 4.899_431,
+                "GPSCoordinates": "46.5583 7.9634"
+            }
+        }));
+        let gps = get_gps_coordinates(&exif).unwrap();
+        assert_eq!((gps.latitude, gps.longitude), (52.379_189, 4.899_431));
+        assert_eq!(gps.altitude, None);
+
+        let malformed = ExifData::new(json!({ "GPSCoordinates": "somewhere" }));
+        assert_eq!(get_gps_coordinates(&malformed), None);
     }
 
     #[tokio::test]
     async fn test_returns_none_if_latitude_is_missing() {
-        let geocoder = ReverseGeocoder::new();
+        let geocoder = CachedGeocoder::new(DEFAULT_GEOCODE_CACHE_SIZE);
         // Longitude is present, but latitude is missing
         let exif = ExifData::new(json!({
             "GPSLongitude": 4.899_431,
@@ -221,7 +504,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_returns_none_if_longitude_is_missing() {
-        let geocoder = ReverseGeocoder::new();
+        let geocoder = CachedGeocoder::new(DEFAULT_GEOCODE_CACHE_SIZE);
         // Latitude is present, but longitude is missing
         let exif = ExifData::new(json!({
             "GPSLatitude": 52.379_189,
@@ -234,9 +517,25 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn test_returns_none_for_out_of_range_coordinates() {
+        let geocoder = CachedGeocoder::new(DEFAULT_GEOCODE_CACHE_SIZE);
+        let exif = ExifData::new(json!({
+            "GPSLatitude": 123.0,
+            "GPSLongitude": 4.899_431,
+        }));
+        assert!(get_gps_info(&geocoder, &exif).is_none());
+
+        let exif = ExifData::new(json!({
+            "GPSLatitude": 52.0,
+            "GPSLongitude": 1e300,
+        }));
+        assert!(get_gps_info(&geocoder, &exif).is_none());
+    }
+
     #[tokio::test]
     async fn test_returns_none_for_empty_exif_data() {
-        let geocoder = ReverseGeocoder::new();
+        let geocoder = CachedGeocoder::new(DEFAULT_GEOCODE_CACHE_SIZE);
         let exif = ExifData::new(json!({})); // Empty JSON object
 
         let result = get_gps_info(&geocoder, &exif);
@@ -245,7 +544,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_gps_altitude_lg_g4_bug() {
-        let geocoder = ReverseGeocoder::new();
+        let geocoder = CachedGeocoder::new(DEFAULT_GEOCODE_CACHE_SIZE);
         // Simulate the grouped structure returned by exiftool -n -g2
         let exif = ExifData::new(json!({
             "Location": {
@@ -270,7 +569,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_gps_altitude_below_sea_level() {
-        let geocoder = ReverseGeocoder::new();
+        let geocoder = CachedGeocoder::new(DEFAULT_GEOCODE_CACHE_SIZE);
         // Simulate the grouped structure for actual below-sea-level values
         let exif = ExifData::new(json!({
             "Location": {
@@ -292,4 +591,75 @@ mod tests {
         // Altitude should correctly remain negative
         assert_eq!(gps_info.altitude, Some(-4.0));
     }
+
+    #[test]
+    fn test_2d_fix_with_few_satellites() {
+        let exif = ExifData::new(json!({
+            "Location": {
+                "GPSLatitude": 52.379_189,
+                "GPSLongitude": 4.899_431,
+                "GPSAltitude": 312,
+                "GPSSatellites": "03",
+                "GPSMeasureMode": "2",
+                "GPSStatus": "A"
+            }
+        }));
+        let mut gps = get_gps_coordinates(&exif).unwrap();
+        assert_eq!(gps.satellites, Some(3));
+        assert_eq!(gps.fix_mode, Some(GpsFixMode::TwoD));
+        assert_eq!(gps.measurement_in_progress, Some(true));
+        assert_eq!(gps.fix_quality, Some(GpsFixQuality::Poor));
+        assert_eq!(gps.altitude, Some(312.0));
+        drop_2d_fix_altitude(&mut gps);
+        assert_eq!(gps.altitude, None);
+    }
+
+    #[test]
+    fn test_3d_fix_with_many_satellites() {
+        let exif = ExifData::new(json!({
+            "Location": {
+                "GPSLatitude": 52.379_189,
+                "GPSLongitude": 4.899_431,
+                "GPSAltitude": 2,
+                "GPSSatellites": 9,
+                "GPSMeasureMode": 3,
+                "GPSDOP": 1.2,
+                "GPSStatus": "V"
+            }
+        }));
+        let mut gps = get_gps_coordinates(&exif).unwrap();
+        assert_eq!(gps.satellites, Some(9));
+        assert_eq!(gps.fix_mode, Some(GpsFixMode::ThreeD));
+        assert_eq!(gps.measurement_in_progress, Some(false));
+        assert_eq!(gps.fix_quality, Some(GpsFixQuality::Good));
+        drop_2d_fix_altitude(&mut gps);
+        assert_eq!(gps.altitude, Some(2.0));
+    }
+
+    #[test]
+    fn test_fix_quality() {
+        use GpsFixMode::{ThreeD, TwoD};
+        assert_eq!(fix_quality(None, None, None), None);
+        assert_eq!(fix_quality(Some(ThreeD), None, None), None);
+        assert_eq!(
+            fix_quality(Some(TwoD), None, None),
+            Some(GpsFixQuality::Poor)
+        );
+        assert_eq!(fix_quality(None, Some(5), None), Some(GpsFixQuality::Fair));
+        assert_eq!(
+            fix_quality(None, Some(12), Some(3.0)),
+            Some(GpsFixQuality::Fair)
+        );
+        assert_eq!(
+            fix_quality(None, None, Some(8.0)),
+            Some(GpsFixQuality::Poor)
+        );
+        assert_eq!(fix_quality(None, Some(7), None), Some(GpsFixQuality::Good));
+        // A list of satellite numbers is not a count.
+        let exif = ExifData::new(json!({ "Location": { "GPSSatellites": "07 12 19" } }));
+        assert_eq!(satellite_count(&exif), None);
+        let exif =
+            ExifData::new(json!({ "Location": { "GPSMeasureMode": "3-Dimensional Measurement" } }));
+        assert_eq!(fix_mode(&exif), Some(ThreeD));
+    }
 }
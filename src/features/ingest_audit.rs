@@ -0,0 +1,188 @@
+//! Checks for auditing a sync pipeline: how long after capture a file was last written, and
+//! whether its name and extension are still the ones the camera gave it.
+
+use crate::ExifData;
+use crate::features::metadata::CameraSettings;
+use crate::time::file_modify_time;
+use crate::time::structs::TimeInfo;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::sync::LazyLock;
+
+/// The file names cameras of each make write, by a lowercase substring of `Make`. Patterns are
+/// anchored at the start only, so suffixes like Pixel's `.MP` or `_PORTRAIT` and the ` (1)` of
+/// sync conflicts still match.
+const CAMERA_FILENAME_SCHEMES: [(&str, &str); 13] = [
+    ("apple", r"^IMG_E?\d{4}"),
+    (
+        "google",
+        r"^(?:PXL_\d{8}_\d{9}|(?:IMG|VID|MVIMG)_\d{8}_\d{6})",
+    ),
+    ("samsung", r"^\d{8}_\d{6}"),
+    ("huawei", r"^(?:IMG|VID)_\d{8}_\d{6}"),
+    ("xiaomi", r"^(?:IMG|VID)_\d{8}_\d{6}"),
+    ("oneplus", r"^(?:IMG|VID)_\d{8}_\d{6}"),
+    ("nikon", r"^(?:_?DSC_?|DSCN)\d{4}"),
+    ("sony", r"^(?:_?DSC\d{5}|C\d{4})"),
+    ("canon", r"^(?:IMG_|_MG_|MVI_)\d{4}"),
+    ("fujifilm", r"^_?DSCF\d{4}"),
+    ("dji", r"^DJI_\d{4}"),
+    ("olympus", r"^P[0-9A-C]\d{6}"),
+    ("panasonic", r"^P\d{7}"),
+];
+
+// The patterns are valid literals, so compiling them cannot fail.
+#[allow(clippy::unwrap_used)]
+static CAMERA_FILENAME_PATTERNS: LazyLock<Vec<(&str, Regex)>> = LazyLock::new(|| {
+    CAMERA_FILENAME_SCHEMES
+        .iter()
+        .map(|(make, pattern)| (*make, Regex::new(&format!("(?i){pattern}")).unwrap()))
+        .collect()
+});
+
+/// The extensions a file of each MIME type may have. Other MIME types are compared to
+/// `exiftool`'s `FileTypeExtension`.
+const MIME_EXTENSIONS: [(&str, &[&str]); 15] = [
+    ("image/jpeg", &["jpg", "jpeg", "jpe", "jfif"]),
+    ("image/png", &["png"]),
+    ("image/gif", &["gif"]),
+    ("image/webp", &["webp"]),
+    ("image/heic", &["heic", "heif", "hif"]),
+    ("image/heif", &["heif", "heic", "hif"]),
+    ("image/avif", &["avif"]),
+    ("image/tiff", &["tif", "tiff"]),
+    ("image/x-adobe-dng", &["dng"]),
+    ("video/mp4", &["mp4", "m4v"]),
+    ("video/quicktime", &["mov", "qt"]),
+    ("video/x-matroska", &["mkv"]),
+    ("video/webm", &["webm"]),
+    ("video/3gpp", &["3gp"]),
+    ("video/x-msvideo", &["avi"]),
+];
+
+/// When and under what name a file arrived where it was analyzed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IngestAudit {
+    /// Seconds from the UTC capture time to the file's modification time, which is usually when
+    /// it was copied to its current location. `None` without a UTC capture time or
+    /// `FileModifyDate`.
+    #[serde(default)]
+    pub capture_to_file_modify_seconds: Option<i64>,
+    /// Whether the file name follows the naming scheme of the camera's make, like `PXL_` for
+    /// Pixels. `None` for makes without a known scheme.
+    #[serde(default)]
+    pub filename_matches_camera_scheme: Option<bool>,
+    /// Whether the extension fits the file's actual type, so a HEIC renamed to `.jpg` doesn't.
+    /// `true` when the type is unknown.
+    pub file_extension_matches_mime: bool,
+}
+
+/// Whether `filename` follows the naming scheme of cameras by `make`.
+pub fn filename_matches_camera_scheme(make: &str, filename: &str) -> Option<bool> {
+    let make = make.to_lowercase();
+    let (_, pattern) = CAMERA_FILENAME_PATTERNS
+        .iter()
+        .find(|(scheme_make, _)| make.contains(scheme_make))?;
+    Some(pattern.is_match(filename))
+}
+
+/// Whether the extension of `path` fits the MIME type `exiftool` detected from the contents.
+fn extension_matches_mime(path: &Path, exif: &ExifData) -> bool {
+    let Some(mime) = exif.get_str("MIMEType") else {
+        return true;
+    };
+    let extension = path
+        .extension()
+        .map(|extension| extension.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    match MIME_EXTENSIONS.iter().find(|(known, _)| *known == mime) {
+        Some((_, extensions)) => extensions.contains(&extension.as_str()),
+        None => exif
+            .get_str("FileTypeExtension")
+            .is_none_or(|expected| expected.eq_ignore_ascii_case(&extension)),
+    }
+}
+
+pub fn get_ingest_audit(
+    path: &Path,
+    exif: &ExifData,
+    camera: &CameraSettings,
+    time: &TimeInfo,
+) -> IngestAudit {
+    let filename = path.file_name().unwrap_or_default().to_string_lossy();
+    IngestAudit {
+        capture_to_file_modify_seconds: time
+            .datetime_utc
+            .zip(file_modify_time(exif))
+            .map(|(captured, modified)| (modified.to_utc() - captured).num_seconds()),
+        filename_matches_camera_scheme: camera
+            .camera_make
+            .as_deref()
+            .and_then(|make| filename_matches_camera_scheme(make, &filename)),
+        file_extension_matches_mime: extension_matches_mime(path, exif),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::features::metadata::get_camera_settings;
+    use crate::time::get_time_info;
+    use crate::time::structs::TimeOptions;
+    use serde_json::json;
+
+    /// A Pixel photo as `exiftool -n -g2` reports it, copied to a NAS a day after capture.
+    fn pixel_exif() -> ExifData {
+        ExifData::new(json!({
+            "Other": { "MIMEType": "image/jpeg", "FileTypeExtension": "JPG" },
+            "Camera": { "Make": "Google", "Model": "Pixel 8" },
+            "Time": {
+                "DateTimeOriginal": "2024:06:01 18:15:02",
+                "OffsetTimeOriginal": "+02:00",
+                "FileModifyDate": "2024:06:02 20:15:32+02:00"
+            }
+        }))
+    }
+
+    fn audit(filename: &str, exif: &ExifData) -> IngestAudit {
+        let time = get_time_info(exif, None, &TimeOptions::default()).unwrap();
+        get_ingest_audit(Path::new(filename), exif, &get_camera_settings(exif), &time)
+    }
+
+    #[test]
+    fn test_pixel_name_matches() {
+        let audit = audit("PXL_20240601_161502123.MP.jpg", &pixel_exif());
+        assert_eq!(audit.filename_matches_camera_scheme, Some(true));
+        assert!(audit.file_extension_matches_mime);
+        assert_eq!(audit.capture_to_file_modify_seconds, Some(26 * 3600 + 30));
+    }
+
+    #[test]
+    fn test_renamed_pixel_photo() {
+        let renamed = audit("holiday_day1.jpg", &pixel_exif());
+        assert_eq!(renamed.filename_matches_camera_scheme, Some(false));
+        assert!(renamed.file_extension_matches_mime);
+
+        let wrong_extension = audit("PXL_20240601_161502123.heic", &pixel_exif());
+        assert_eq!(wrong_extension.filename_matches_camera_scheme, Some(true));
+        assert!(!wrong_extension.file_extension_matches_mime);
+    }
+
+    #[test]
+    fn test_unknown_make_has_no_scheme() {
+        assert_eq!(
+            filename_matches_camera_scheme("Hasselblad", "B0001234.3FR"),
+            None
+        );
+        assert_eq!(
+            filename_matches_camera_scheme("NIKON CORPORATION", "DSC_0042.NEF"),
+            Some(true)
+        );
+        assert_eq!(
+            filename_matches_camera_scheme("DJI", "DJI_20240601181502_0001_D.JPG"),
+            Some(true)
+        );
+    }
+}
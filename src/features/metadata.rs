@@ -1,7 +1,20 @@
 use crate::ExifData;
+use crate::features::camera_module::{CameraModule, detect_camera_module};
+use crate::features::dedup::get_serial_number;
+use crate::features::device_motion::{DeviceMotion, get_device_motion};
 use crate::features::error::MetadataError;
+use crate::features::filename::get_original_filename;
+use crate::features::mime::resolve_mime_type;
+use crate::features::orientation::{self, Orientation};
+use crate::features::stack::stack_image_count;
+use crate::features::stripped::is_metadata_stripped;
+use crate::notes::{
+    AnalysisNote, CODE_IMPLAUSIBLE_DURATION, CODE_MALFORMED_DURATION, MODULE_METADATA,
+};
+use crate::tags::fps::parse_fps;
+use crate::units::Seconds;
 use serde::{Deserialize, Serialize};
-use serde_json::Value;
+use serde_json::{Value, json};
 use std::mem;
 
 #[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
@@ -9,19 +22,69 @@ use std::mem;
 pub struct BasicMetadata {
     pub width: u64,
     pub height: u64,
+    /// Lowercase, with aliases like `image/x-heic` replaced by the registered type, see
+    /// [`normalize_mime_type`](crate::normalize_mime_type). `application/octet-stream` when
+    /// exiftool recognized the `file_type`, but reported no MIME type.
     pub mime_type: String,
-    pub duration: Option<f64>,
+    /// exiftool's `FileType`, like "HEIC" or "CR3".
+    #[serde(default)]
+    pub file_type: Option<String>,
+    /// The usual extension of the `file_type`, lowercase and without a dot, from exiftool's
+    /// `FileTypeExtension`.
+    #[serde(default)]
+    pub recommended_extension: Option<String>,
+    #[serde(default)]
+    pub duration: Option<Seconds>,
     pub size_bytes: u64,
-    pub orientation: Option<u64>,
+    /// `None` when the `Orientation` tag is missing or outside 1-8.
+    #[serde(default, deserialize_with = "orientation::deserialize_lenient")]
+    pub orientation: Option<Orientation>,
+    /// The `Orientation` tag as written, including invalid values.
+    #[serde(default)]
+    pub orientation_raw: Option<u64>,
+    /// Absolute difference in seconds between the longest and shortest of the container and
+    /// stream durations, when more than one is present.
+    #[serde(default)]
+    pub duration_mismatch: Option<f64>,
+    /// The JPEG quality (1-100) estimated by exiftool from the quantization tables.
+    #[serde(default)]
+    pub jpeg_quality_estimate: Option<u8>,
+    /// Whether the JPEG is progressive rather than baseline.
+    #[serde(default)]
+    pub is_progressive: Option<bool>,
+    /// The JPEG chroma subsampling, e.g. "4:2:0".
+    #[serde(default)]
+    pub chroma_subsampling: Option<String>,
+    /// The file's original name, from `PreservedFileName`, `RawFileName` or IPTC `ObjectName`.
+    #[serde(default)]
+    pub original_filename: Option<String>,
+    /// The size of the JPEG image data as displayed, when it differs from the `width` and
+    /// `height` the metadata claims, see [`FileHealth::dimension_mismatch`](crate::FileHealth::dimension_mismatch).
+    #[serde(default)]
+    pub actual_width: Option<u64>,
+    #[serde(default)]
+    pub actual_height: Option<u64>,
+    /// The number of full-size images in a multi-image file, like an exposure stack or an edit
+    /// kept with its original. `None` for single images. See
+    /// [`MediaAnalyzer::analyze_stack_members`](crate::MediaAnalyzer::analyze_stack_members).
+    #[serde(default)]
+    pub stack_image_count: Option<u32>,
+    /// Whether the metadata was stripped: no times but the file system's, no camera tags and no
+    /// maker notes. `Some(false)` tells a camera that didn't record something apart from a
+    /// stripped file. `None` for formats cameras don't write, like PNG.
+    #[serde(default)]
+    pub metadata_stripped: Option<bool>,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
 #[serde(rename_all = "camelCase")]
 pub enum FlashMode {
-    Unknown,
     CompulsoryFiring,
     CompulsorySuppression,
     Auto,
+    /// Also for modes written by later versions.
+    #[serde(other)]
+    Unknown,
 }
 
 impl FlashMode {
@@ -40,13 +103,15 @@ impl FlashMode {
 pub struct FlashInfo {
     pub fired: bool,
     pub mode: FlashMode,
+    #[serde(default)]
     pub return_detected: Option<bool>,
     pub red_eye_reduction: bool,
     pub flash_function_present: bool,
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
-#[serde(rename_all = "camelCase")]
+/// Everything `None` for a file without camera metadata, like a screenshot.
+#[derive(Debug, Clone, Default, Deserialize, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
 pub struct CameraSettings {
     pub iso: Option<u64>,
     pub exposure_time: Option<f64>,
@@ -54,6 +119,8 @@ pub struct CameraSettings {
     pub focal_length: Option<f64>,
     pub camera_make: Option<String>,
     pub camera_model: Option<String>,
+    /// The camera body's serial number, to tell apart cameras of the same model.
+    pub camera_serial_number: Option<String>,
     pub focal_length_in_35mm: Option<f64>,
     pub lens_make: Option<String>,
     pub lens_model: Option<String>,
@@ -61,8 +128,16 @@ pub struct CameraSettings {
     pub digital_zoom_ratio: Option<f64>,
     pub subject_distance: Option<f64>,
     pub exposure_compensation: Option<f64>,
+    /// On multi-camera phones, the camera module that took the shot.
+    pub camera_module: Option<CameraModule>,
+    /// How the device was held and moving at capture time, from Apple maker notes.
+    pub device_motion: Option<DeviceMotion>,
 }
 
+/// Durations longer than a week are treated as corrupt values.
+const MAX_SANE_DURATION_SECONDS: f64 = 7.0 * 24.0 * 3600.0;
+const DURATION_TAGS: [&str; 3] = ["Duration", "TrackDuration", "MediaDuration"];
+
 fn parse_duration(val: &Value) -> Option<f64> {
     if let Some(d) = val.as_f64() {
         return Some(d);
@@ -74,6 +149,109 @@ fn parse_duration(val: &Value) -> Option<f64> {
     })
 }
 
+/// Parses an exposure time in seconds from a number, a decimal string, a fraction string
+/// ("1/250"), or any of those with a trailing "s".
+fn parse_exposure_time(val: &Value) -> Option<f64> {
+    if let Some(s) = val.as_str() {
+        let trimmed = s.trim().trim_end_matches('s').trim_end();
+        return parse_fps(&Value::from(trimmed)).filter(|t| t.is_finite() && *t > 0.0);
+    }
+    val.as_f64()
+}
+
+fn is_sane_duration(seconds: f64) -> bool {
+    seconds.is_finite() && (0.0..=MAX_SANE_DURATION_SECONDS).contains(&seconds)
+}
+
+/// Notes for the duration tags that [`resolve_duration`] ignored, as they couldn't be parsed or
+/// were out of range.
+pub fn duration_notes(exif: &ExifData) -> Vec<AnalysisNote> {
+    DURATION_TAGS
+        .iter()
+        .filter_map(|tag| {
+            let value = exif.get_value(tag)?;
+            let note = match parse_duration(value) {
+                None => AnalysisNote::warning(
+                    MODULE_METADATA,
+                    CODE_MALFORMED_DURATION,
+                    format!("`{tag}` value {value} is not a duration"),
+                ),
+                Some(seconds) if !is_sane_duration(seconds) => AnalysisNote::warning(
+                    MODULE_METADATA,
+                    CODE_IMPLAUSIBLE_DURATION,
+                    format!("`{tag}` value {value} was ignored as corrupt"),
+                ),
+                Some(_) => return None,
+            };
+            Some(note.with_data(json!({ "tag": tag, "value": value })))
+        })
+        .collect()
+}
+
+/// Picks the most trustworthy duration (the longest sane value) out of the container and stream
+/// durations, and reports how far apart they are.
+fn resolve_duration(exif: &ExifData) -> (Option<f64>, Option<f64>) {
+    let durations: Vec<f64> = DURATION_TAGS
+        .iter()
+        .filter_map(|tag| exif.get_value(tag).and_then(parse_duration))
+        .filter(|d| is_sane_duration(*d))
+        .collect();
+    let longest = durations.iter().copied().reduce(f64::max);
+    let shortest = durations.iter().copied().reduce(f64::min);
+    let mismatch = match (longest, shortest) {
+        (Some(longest), Some(shortest)) if durations.len() > 1 => Some(longest - shortest),
+        _ => None,
+    };
+    (longest, mismatch)
+}
+
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+fn parse_jpeg_quality(val: &Value) -> Option<u8> {
+    let quality = val
+        .as_f64()
+        .or_else(|| val.as_str().and_then(|s| s.trim().parse().ok()))?;
+    (1.0..=100.0)
+        .contains(&quality)
+        .then(|| quality.round() as u8)
+}
+
+/// Parses `EncodingProcess`, either numeric (`-n`, the SOF marker index) or its description
+/// ("Progressive DCT, Huffman coding").
+fn parse_is_progressive(val: &Value) -> Option<bool> {
+    if let Some(process) = val.as_u64() {
+        // SOF2, SOF6, SOF10 and SOF14 are the progressive variants.
+        return Some(matches!(process, 2 | 6 | 10 | 14));
+    }
+    val.as_str()
+        .map(|s| s.to_lowercase().contains("progressive"))
+}
+
+/// Normalizes `YCbCrSubSampling` ("2 2" with `-n`, "YCbCr4:2:0 (2 2)" without) to "4:2:0".
+fn parse_chroma_subsampling(val: &Value) -> Option<String> {
+    let s = val.as_str()?;
+    if let Some(start) = s.find("4:") {
+        let ratio: String = s[start..]
+            .chars()
+            .take_while(|c| c.is_ascii_digit() || *c == ':')
+            .collect();
+        return Some(ratio);
+    }
+    let factors: Vec<u64> = s
+        .split_whitespace()
+        .filter_map(|p| p.parse().ok())
+        .collect();
+    let ratio = match factors.as_slice() {
+        [1, 1] => "4:4:4",
+        [1, 2] => "4:4:0",
+        [2, 1] => "4:2:2",
+        [2, 2] => "4:2:0",
+        [4, 1] => "4:1:1",
+        [4, 2] => "4:1:0",
+        _ => return None,
+    };
+    Some(ratio.to_string())
+}
+
 const fn parse_flash(raw: u64) -> FlashInfo {
     let fired = raw & 0x1 != 0;
     let return_bits = (raw >> 1) & 0x3;
@@ -103,53 +281,109 @@ const fn parse_flash(raw: u64) -> FlashInfo {
     }
 }
 
-pub fn get_metadata(exif: &ExifData) -> Result<(BasicMetadata, CameraSettings), MetadataError> {
-    let mut width = exif.require_u64("ImageWidth")?;
-    let mut height = exif.require_u64("ImageHeight")?;
-    let orientation = exif.get_u64("Orientation");
+/// The width and height as displayed, after the photo's `Orientation` or the video's `Rotation`.
+pub fn display_dimensions(mut width: u64, mut height: u64, exif: &ExifData) -> (u64, u64) {
     let is_video_rotated = exif
         .get_u64("Rotation")
         .is_some_and(|r| r == 90 || r == 270);
-    let is_photo_rotated = orientation.is_some_and(|o| (5..=8).contains(&o));
+    let is_photo_rotated = exif
+        .get_u64("Orientation")
+        .and_then(|o| Orientation::try_from(o).ok())
+        .is_some_and(Orientation::swaps_dimensions);
     if is_photo_rotated || is_video_rotated {
         // Swap width and height for 90 and 270-degree rotations
         mem::swap(&mut width, &mut height);
     }
+    (width, height)
+}
+
+/// The capture details of the photo or video. Unlike [`get_metadata`], no tags are required.
+pub fn get_camera_settings(exif: &ExifData) -> CameraSettings {
+    let camera_make = exif
+        .get_string("Make")
+        .or_else(|| exif.get_string("AndroidMake"));
+    let camera_model = exif
+        .get_string("Model")
+        .or_else(|| exif.get_string("AndroidModel"));
+    let lens_model = exif.get_string("LensModel");
+    let focal_length_in_35mm = exif.get_f64("FocalLengthIn35mmFormat");
+    let camera_module = detect_camera_module(
+        camera_make.as_deref(),
+        camera_model.as_deref(),
+        lens_model.as_deref().or_else(|| exif.get_str("LensID")),
+        focal_length_in_35mm,
+    );
+    CameraSettings {
+        iso: exif.get_u64("ISO"),
+        exposure_time: exif
+            .get_value("ExposureTime")
+            .and_then(parse_exposure_time)
+            // `exiftool` decodes the APEX value (2^-Tv) of `ShutterSpeedValue` to seconds.
+            .or_else(|| {
+                exif.get_value("ShutterSpeedValue")
+                    .and_then(parse_exposure_time)
+            }),
+        aperture: exif
+            .get_f64("FNumber")
+            .or_else(|| exif.get_f64("Aperture"))
+            .or_else(|| exif.get_f64("ApertureValue")),
+        focal_length: exif.get_f64("FocalLength"),
+        focal_length_in_35mm,
+        camera_make,
+        camera_model,
+        camera_serial_number: get_serial_number(exif),
+        lens_make: exif.get_string("LensMake"),
+        lens_model,
+        flash: exif
+            .get_value("Flash")
+            .and_then(|v| v.as_u64().map(parse_flash)),
+        digital_zoom_ratio: exif.get_f64("DigitalZoomRatio"),
+        subject_distance: exif.get_f64("SubjectDistance"),
+        exposure_compensation: exif
+            .get_f64("ExposureCompensation")
+            .or_else(|| exif.get_f64("ExposureBiasValue")),
+        camera_module,
+        device_motion: get_device_motion(exif),
+    }
+}
+
+pub fn get_metadata(exif: &ExifData) -> Result<(BasicMetadata, CameraSettings), MetadataError> {
+    let (width, height) = display_dimensions(
+        exif.require_u64("ImageWidth")?,
+        exif.require_u64("ImageHeight")?,
+        exif,
+    );
+    let orientation_raw = exif.get_u64("Orientation");
+    let orientation = orientation_raw.and_then(|o| Orientation::try_from(o).ok());
+    let (duration, duration_mismatch) = resolve_duration(exif);
+    let mime_type = resolve_mime_type(exif)?;
+    let metadata_stripped = is_metadata_stripped(exif, &mime_type);
+    let is_jpeg = mime_type == "image/jpeg";
+    let jpeg_value = |tag: &str| exif.get_value(tag).filter(|_| is_jpeg);
     Ok((
         BasicMetadata {
             width,
             height,
-            mime_type: exif.require_string("MIMEType")?,
             size_bytes: exif.require_u64("FileSize")?,
             orientation,
-            duration: exif.get_value("Duration").and_then(parse_duration),
-        },
-        CameraSettings {
-            iso: exif.get_u64("ISO"),
-            exposure_time: exif.get_f64("ExposureTime"),
-            aperture: exif
-                .get_f64("FNumber")
-                .or_else(|| exif.get_f64("Aperture"))
-                .or_else(|| exif.get_f64("ApertureValue")),
-            focal_length: exif.get_f64("FocalLength"),
-            focal_length_in_35mm: exif.get_f64("FocalLengthIn35mmFormat"),
-            camera_make: exif
-                .get_string("Make")
-                .or_else(|| exif.get_string("AndroidMake")),
-            camera_model: exif
-                .get_string("Model")
-                .or_else(|| exif.get_string("AndroidModel")),
-            lens_make: exif.get_string("LensMake"),
-            lens_model: exif.get_string("LensModel"),
-            flash: exif
-                .get_value("Flash")
-                .and_then(|v| v.as_u64().map(parse_flash)),
-            digital_zoom_ratio: exif.get_f64("DigitalZoomRatio"),
-            subject_distance: exif.get_f64("SubjectDistance"),
-            exposure_compensation: exif
-                .get_f64("ExposureCompensation")
-                .or_else(|| exif.get_f64("ExposureBiasValue")),
+            orientation_raw,
+            duration: duration.map(Seconds),
+            duration_mismatch,
+            jpeg_quality_estimate: jpeg_value("JPEGQualityEstimate").and_then(parse_jpeg_quality),
+            is_progressive: jpeg_value("EncodingProcess").and_then(parse_is_progressive),
+            chroma_subsampling: jpeg_value("YCbCrSubSampling").and_then(parse_chroma_subsampling),
+            original_filename: get_original_filename(exif).map(|(_, name)| name.to_string()),
+            mime_type,
+            file_type: exif.get_string("FileType"),
+            recommended_extension: exif
+                .get_str("FileTypeExtension")
+                .map(|extension| extension.trim_start_matches('.').to_lowercase()),
+            actual_width: None,
+            actual_height: None,
+            stack_image_count: stack_image_count(exif),
+            metadata_stripped,
         },
+        get_camera_settings(exif),
     ))
 }
 
@@ -202,6 +436,66 @@ mod tests {
         assert_eq!(capture_details.focal_length, None);
     }
 
+    #[test]
+    fn test_bracketed_iso_uses_first_shot() {
+        let exif_data = ExifData::new(json!({
+            "ImageWidth": 4000, "ImageHeight": 3000, "MIMEType": "image/jpeg", "FileSize": 1024,
+            "ISO": [200, 400, 800]
+        }));
+        let (_, capture_details) = get_metadata(&exif_data).unwrap();
+        assert_eq!(capture_details.iso, Some(200));
+    }
+
+    fn jpeg_details_for(tags: &Value) -> BasicMetadata {
+        let mut value = json!({
+            "ImageWidth": 4000, "ImageHeight": 3000, "MIMEType": "image/jpeg", "FileSize": 1024
+        });
+        for (k, v) in tags.as_object().unwrap() {
+            value[k] = v.clone();
+        }
+        get_metadata(&ExifData::new(value)).unwrap().0
+    }
+
+    #[test]
+    fn test_baseline_jpeg_420() {
+        let metadata = jpeg_details_for(&json!({
+            "JPEGQualityEstimate": 92,
+            "EncodingProcess": 0,
+            "YCbCrSubSampling": "2 2"
+        }));
+        assert_eq!(metadata.jpeg_quality_estimate, Some(92));
+        assert_eq!(metadata.is_progressive, Some(false));
+        assert_eq!(metadata.chroma_subsampling.as_deref(), Some("4:2:0"));
+    }
+
+    #[test]
+    fn test_progressive_jpeg_444() {
+        let metadata = jpeg_details_for(&json!({
+            "EncodingProcess": "Progressive DCT, Huffman coding",
+            "YCbCrSubSampling": "YCbCr4:4:4 (1 1)"
+        }));
+        assert!(metadata.jpeg_quality_estimate.is_none());
+        assert_eq!(metadata.is_progressive, Some(true));
+        assert_eq!(metadata.chroma_subsampling.as_deref(), Some("4:4:4"));
+
+        let numeric = jpeg_details_for(&json!({ "EncodingProcess": 2, "YCbCrSubSampling": "1 1" }));
+        assert_eq!(numeric.is_progressive, Some(true));
+        assert_eq!(numeric.chroma_subsampling.as_deref(), Some("4:4:4"));
+    }
+
+    #[test]
+    fn test_png_has_no_jpeg_details() {
+        let metadata = get_metadata(&ExifData::new(json!({
+            "ImageWidth": 800, "ImageHeight": 600, "MIMEType": "image/png", "FileSize": 1024,
+            "JPEGQualityEstimate": 90, "EncodingProcess": 0, "YCbCrSubSampling": "2 2"
+        })))
+        .unwrap()
+        .0;
+        assert!(metadata.jpeg_quality_estimate.is_none());
+        assert!(metadata.is_progressive.is_none());
+        assert!(metadata.chroma_subsampling.is_none());
+    }
+
     #[test]
     fn test_get_metadata_with_minimal_video_data() {
         // Simulate minimal EXIF from a video file
@@ -225,7 +519,7 @@ mod tests {
         assert_eq!(metadata.height, 1080);
         assert_eq!(metadata.mime_type, "video/mp4");
         assert_eq!(metadata.size_bytes, 15_728_640);
-        assert_eq!(metadata.duration, Some(10.53));
+        assert_eq!(metadata.duration, Some(Seconds(10.53)));
 
         // --- Assert CaptureDetails ---
         // All optional photo-specific fields should be None
@@ -250,7 +544,7 @@ mod tests {
             "Duration should be parsed from string"
         );
         // Use an epsilon for float comparison
-        assert!((metadata.duration.unwrap() - 5.874).abs() < 1e-9);
+        assert!((metadata.duration.unwrap().0 - 5.874).abs() < 1e-9);
     }
 
     #[test]
@@ -265,6 +559,109 @@ mod tests {
             metadata.duration.is_none(),
             "Malformed duration string should result in None"
         );
+        let notes = duration_notes(&exif_data);
+        assert_eq!(notes.len(), 1);
+        assert_eq!(notes[0].code, CODE_MALFORMED_DURATION);
+        assert_eq!(
+            notes[0].data,
+            Some(json!({ "tag": "Duration", "value": "5 seconds" }))
+        );
+    }
+
+    #[test]
+    fn test_duration_prefers_longest_stream_duration() {
+        let exif_data = ExifData::new(json!({
+            "ImageWidth": 1920, "ImageHeight": 1080, "MIMEType": "video/mp4", "FileSize": 1_000_000,
+            "Duration": 12.0,
+            "TrackDuration": 11.5,
+            "MediaDuration": 11.6
+        }));
+        let (metadata, _) = get_metadata(&exif_data).unwrap();
+        assert_eq!(metadata.duration, Some(Seconds(12.0)));
+        assert!((metadata.duration_mismatch.unwrap() - 0.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_duration_ignores_insane_values() {
+        let exif_data = ExifData::new(json!({
+            "ImageWidth": 1920, "ImageHeight": 1080, "MIMEType": "video/mp4", "FileSize": 1_000_000,
+            "Duration": 4_294_967_295.0_f64,
+            "TrackDuration": 8.0
+        }));
+        let (metadata, _) = get_metadata(&exif_data).unwrap();
+        assert_eq!(metadata.duration, Some(Seconds(8.0)));
+        assert!(metadata.duration_mismatch.is_none());
+        let codes: Vec<String> = duration_notes(&exif_data)
+            .into_iter()
+            .map(|note| note.code)
+            .collect();
+        assert_eq!(codes, vec![CODE_IMPLAUSIBLE_DURATION]);
+    }
+
+    mod exposure_time_tests {
+        use super::*;
+
+        fn exposure_for(value: &Value) -> Option<f64> {
+            let exif_data = ExifData::new(json!({
+                "ImageWidth": 100, "ImageHeight": 100, "MIMEType": "image/jpeg", "FileSize": 1024,
+                "ExposureTime": value
+            }));
+            get_metadata(&exif_data).unwrap().1.exposure_time
+        }
+
+        #[test]
+        fn test_parses_fraction_string() {
+            assert!((exposure_for(&json!("1/250")).unwrap() - 0.004).abs() < 1e-12);
+        }
+
+        #[test]
+        fn test_parses_decimal_string() {
+            assert_eq!(exposure_for(&json!("0.5")), Some(0.5));
+        }
+
+        #[test]
+        fn test_parses_whole_seconds_with_unit() {
+            assert_eq!(exposure_for(&json!("30")), Some(30.0));
+            assert_eq!(exposure_for(&json!("30 s")), Some(30.0));
+            assert!((exposure_for(&json!("1/60s")).unwrap() - 1.0 / 60.0).abs() < 1e-12);
+        }
+
+        #[test]
+        fn test_garbage_is_none() {
+            assert!(exposure_for(&json!("fast")).is_none());
+            assert!(exposure_for(&json!("1/0")).is_none());
+            assert!(exposure_for(&json!([1, 250])).is_none());
+        }
+
+        #[test]
+        fn test_falls_back_to_apex_shutter_speed() {
+            // A Tv of 8, as `exiftool -n` reports it.
+            let exif_data = ExifData::new(json!({
+                "ImageWidth": 100, "ImageHeight": 100, "MIMEType": "image/jpeg", "FileSize": 1024,
+                "ShutterSpeedValue": 0.003_906_25
+            }));
+            let (_, camera) = get_metadata(&exif_data).unwrap();
+            assert!((camera.exposure_time.unwrap() - 1.0 / 256.0).abs() < 1e-12);
+
+            // Without `-n`, it is a fraction.
+            let exif_data = ExifData::new(json!({
+                "ImageWidth": 100, "ImageHeight": 100, "MIMEType": "image/jpeg", "FileSize": 1024,
+                "ShutterSpeedValue": "1/256"
+            }));
+            let (_, camera) = get_metadata(&exif_data).unwrap();
+            assert!((camera.exposure_time.unwrap() - 1.0 / 256.0).abs() < 1e-12);
+        }
+
+        #[test]
+        fn test_exposure_time_is_preferred_over_apex() {
+            let exif_data = ExifData::new(json!({
+                "ImageWidth": 100, "ImageHeight": 100, "MIMEType": "image/jpeg", "FileSize": 1024,
+                "ExposureTime": "1/125",
+                "ShutterSpeedValue": 0.003_906_25
+            }));
+            let (_, camera) = get_metadata(&exif_data).unwrap();
+            assert!((camera.exposure_time.unwrap() - 0.008).abs() < 1e-12);
+        }
     }
 
     #[test]
@@ -274,7 +671,8 @@ mod tests {
         let numeric_exif = ExifData::new(et.json(file, &["-n", "-g2"])?);
         let (metadata, _) = get_metadata(&numeric_exif)?;
 
-        assert_eq!(metadata.orientation, Some(5));
+        assert_eq!(metadata.orientation, Some(Orientation::Transpose));
+        assert_eq!(metadata.orientation_raw, Some(5));
         assert_eq!(metadata.width, 1800);
         assert_eq!(metadata.height, 1200);
 
@@ -329,4 +727,23 @@ mod tests {
             "Should fail with specific error for missing MIMEType"
         );
     }
+
+    #[test]
+    fn test_raw_without_mime_type_uses_file_type() {
+        let exif_data = ExifData::new(json!({
+            "ImageWidth": 5472, "ImageHeight": 3648, "MIMEType": "", "FileSize": 28_311_552,
+            "FileType": "SRW", "FileTypeExtension": "SRW"
+        }));
+        let (metadata, _) = get_metadata(&exif_data).unwrap();
+        assert_eq!(metadata.mime_type, "application/octet-stream");
+        assert_eq!(metadata.file_type.as_deref(), Some("SRW"));
+        assert_eq!(metadata.recommended_extension.as_deref(), Some("srw"));
+
+        let heic = ExifData::new(json!({
+            "ImageWidth": 4032, "ImageHeight": 3024, "MIMEType": "image/x-heic", "FileSize": 1024
+        }));
+        let (metadata, _) = get_metadata(&heic).unwrap();
+        assert_eq!(metadata.mime_type, "image/heic");
+        assert_eq!(metadata.file_type, None);
+    }
 }
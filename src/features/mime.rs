@@ -0,0 +1,136 @@
+//! Normalizes the `MIMEType` reported by exiftool, so the same format always has the same type.
+
+use crate::ExifData;
+use crate::features::error::MetadataError;
+use crate::notes::{AnalysisNote, CODE_MISSING_MIME_TYPE, MODULE_METADATA};
+use serde_json::json;
+
+/// The MIME type of files exiftool knows the format of, but reports no MIME type for, like
+/// some raw formats.
+pub const UNKNOWN_MIME_TYPE: &str = "application/octet-stream";
+
+/// Nonstandard MIME types and the registered type of the same format, after lowercasing.
+/// Other types are kept as reported, including `video/quicktime`, which is the registered type
+/// of .mov files, and the `image/x-` types of raw formats, which have no registered type.
+pub const MIME_TYPE_ALIASES: [(&str, &str); 12] = [
+    ("image/x-heic", "image/heic"),
+    ("image/x-heif", "image/heif"),
+    ("image/x-avif", "image/avif"),
+    ("image/jpg", "image/jpeg"),
+    ("image/pjpeg", "image/jpeg"),
+    ("image/x-png", "image/png"),
+    ("image/x-ms-bmp", "image/bmp"),
+    ("image/x-bmp", "image/bmp"),
+    ("image/x-tiff", "image/tiff"),
+    ("image/x-webp", "image/webp"),
+    ("audio/x-wav", "audio/wav"),
+    ("video/x-mp4", "video/mp4"),
+];
+
+/// Lowercases `mime_type` and replaces an alias from [`MIME_TYPE_ALIASES`] with its registered
+/// type.
+///
+/// ```
+/// use media_analyzer::normalize_mime_type;
+///
+/// assert_eq!(normalize_mime_type("image/x-heic"), "image/heic");
+/// assert_eq!(normalize_mime_type(" Video/QuickTime"), "video/quicktime");
+/// assert_eq!(normalize_mime_type("image/x-samsung-srw"), "image/x-samsung-srw");
+/// ```
+#[must_use]
+pub fn normalize_mime_type(mime_type: &str) -> String {
+    let mime_type = mime_type.trim().to_lowercase();
+    MIME_TYPE_ALIASES
+        .iter()
+        .find(|(alias, _)| *alias == mime_type)
+        .map_or(mime_type, |(_, canonical)| (*canonical).to_string())
+}
+
+/// The normalized MIME type, or [`UNKNOWN_MIME_TYPE`] when it is missing or empty but exiftool
+/// recognized the `FileType`.
+///
+/// # Errors
+/// * If both the MIME type and the `FileType` are missing.
+pub fn resolve_mime_type(exif: &ExifData) -> Result<String, MetadataError> {
+    match exif
+        .get_str("MIMEType")
+        .filter(|mime| !mime.trim().is_empty())
+    {
+        Some(mime_type) => Ok(normalize_mime_type(mime_type)),
+        None if exif.get_str("FileType").is_some() => Ok(UNKNOWN_MIME_TYPE.to_string()),
+        None => Err(MetadataError::MissingRequiredField("MIMEType".to_string())),
+    }
+}
+
+/// A note when [`resolve_mime_type`] fell back on [`UNKNOWN_MIME_TYPE`].
+pub fn mime_type_notes(exif: &ExifData) -> Vec<AnalysisNote> {
+    let has_mime_type = exif
+        .get_str("MIMEType")
+        .is_some_and(|mime| !mime.trim().is_empty());
+    match exif.get_str("FileType") {
+        Some(file_type) if !has_mime_type => vec![
+            AnalysisNote::warning(
+                MODULE_METADATA,
+                CODE_MISSING_MIME_TYPE,
+                format!("no MIME type for file type {file_type}, using {UNKNOWN_MIME_TYPE}"),
+            )
+            .with_data(json!({ "fileType": file_type })),
+        ],
+        _ => Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_aliases_map_to_registered_types() {
+        assert_eq!(normalize_mime_type("image/x-heic"), "image/heic");
+        assert_eq!(normalize_mime_type("IMAGE/JPG"), "image/jpeg");
+        assert_eq!(normalize_mime_type("audio/x-wav"), "audio/wav");
+        for (alias, canonical) in MIME_TYPE_ALIASES {
+            assert_eq!(normalize_mime_type(alias), canonical);
+            // Registered types map to themselves.
+            assert_eq!(normalize_mime_type(canonical), canonical);
+        }
+    }
+
+    #[test]
+    fn test_unknown_types_pass_through() {
+        for mime_type in [
+            "image/x-samsung-srw",
+            "video/quicktime",
+            "image/x-canon-cr3",
+        ] {
+            assert_eq!(normalize_mime_type(mime_type), mime_type);
+        }
+        assert_eq!(normalize_mime_type("Image/X-Sony-ARW"), "image/x-sony-arw");
+    }
+
+    #[test]
+    fn test_empty_mime_type_with_file_type() {
+        let exif = ExifData::new(json!({ "MIMEType": "", "FileType": "SRW" }));
+        assert_eq!(resolve_mime_type(&exif).unwrap(), UNKNOWN_MIME_TYPE);
+        let notes = mime_type_notes(&exif);
+        assert_eq!(notes.len(), 1);
+        assert_eq!(notes[0].code, CODE_MISSING_MIME_TYPE);
+
+        let missing = ExifData::new(json!({ "FileType": "SRW" }));
+        assert_eq!(resolve_mime_type(&missing).unwrap(), UNKNOWN_MIME_TYPE);
+
+        let reported = ExifData::new(json!({ "MIMEType": "image/x-heic", "FileType": "HEIC" }));
+        assert_eq!(resolve_mime_type(&reported).unwrap(), "image/heic");
+        assert!(mime_type_notes(&reported).is_empty());
+    }
+
+    #[test]
+    fn test_empty_mime_type_without_file_type_fails() {
+        let exif = ExifData::new(json!({ "MIMEType": "" }));
+        assert!(matches!(
+            resolve_mime_type(&exif),
+            Err(MetadataError::MissingRequiredField(field)) if field == "MIMEType"
+        ));
+        assert!(mime_type_notes(&exif).is_empty());
+    }
+}
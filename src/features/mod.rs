@@ -1,6 +1,34 @@
+pub mod aux_images;
+pub mod calendar;
+pub mod camera_module;
+pub mod coarse_geocode;
+pub mod completeness;
+pub mod dedup;
+pub mod device_motion;
 pub mod error;
+pub mod file_health;
+pub mod filename;
+pub mod geo_export;
+pub mod geocode;
 pub mod gps;
 pub mod hashing;
+pub mod ingest_audit;
 pub mod metadata;
+pub mod mime;
+pub mod movement;
+#[cfg(feature = "weather-openmeteo")]
+pub mod open_meteo;
+pub mod ordering;
+pub mod orientation;
 pub mod pano;
+pub mod probe;
+pub mod quality;
+pub mod sessions;
+pub mod stack;
+pub mod stripped;
+pub mod thumbnail;
+pub mod time_inference;
+pub mod user_metadata;
+pub mod versions;
 pub mod weather;
+pub mod weather_provider;
@@ -0,0 +1,176 @@
+//! The distance, bearing and speed between consecutively analyzed photos, for travel timelines.
+//!
+//! An implied speed over a few hundred km/h between two photos means a flight, or photos that
+//! were not analyzed in capture order.
+
+use crate::features::gps::GpsInfo;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::sync::{Mutex, PoisonError};
+
+/// The mean radius of the earth, in km.
+const EARTH_RADIUS_KM: f64 = 6371.0;
+
+/// How far and in which direction the camera moved since the previously analyzed photo.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Movement {
+    /// The great-circle distance from the previous photo.
+    pub distance_km: f64,
+    /// The initial bearing from the previous photo, in degrees clockwise from true north.
+    pub bearing_deg: f64,
+    /// The seconds since the previous photo was taken. Negative when it was taken later, so the
+    /// photos were analyzed out of capture order.
+    pub elapsed_s: f64,
+    /// The distance over the time between the photos. `None` when they were taken at the same
+    /// second.
+    #[serde(default)]
+    pub implied_speed_kmh: Option<f64>,
+}
+
+impl Movement {
+    /// The movement from `from` to `to`, as latitude and longitude pairs in degrees.
+    #[must_use]
+    pub fn between(
+        from: (f64, f64),
+        from_time: DateTime<Utc>,
+        to: (f64, f64),
+        to_time: DateTime<Utc>,
+    ) -> Self {
+        let distance_km = haversine_km(from, to);
+        #[allow(clippy::cast_precision_loss)]
+        let elapsed_s = (to_time - from_time).num_milliseconds() as f64 / 1000.0;
+        Self {
+            distance_km,
+            bearing_deg: initial_bearing_deg(from, to),
+            elapsed_s,
+            implied_speed_kmh: (elapsed_s.abs() >= 1.0)
+                .then(|| distance_km / (elapsed_s.abs() / 3600.0)),
+        }
+    }
+}
+
+fn haversine_km((latitude1, longitude1): (f64, f64), (latitude2, longitude2): (f64, f64)) -> f64 {
+    let (phi1, phi2) = (latitude1.to_radians(), latitude2.to_radians());
+    let delta_phi = phi2 - phi1;
+    let delta_lambda = (longitude2 - longitude1).to_radians();
+    let a = (delta_phi / 2.0).sin().powi(2).mul_add(
+        1.0,
+        phi1.cos() * phi2.cos() * (delta_lambda / 2.0).sin().powi(2),
+    );
+    2.0 * EARTH_RADIUS_KM * a.sqrt().min(1.0).asin()
+}
+
+fn initial_bearing_deg(
+    (latitude1, longitude1): (f64, f64),
+    (latitude2, longitude2): (f64, f64),
+) -> f64 {
+    let (phi1, phi2) = (latitude1.to_radians(), latitude2.to_radians());
+    let delta_lambda = (longitude2 - longitude1).to_radians();
+    let y = delta_lambda.sin() * phi2.cos();
+    let x = phi1
+        .cos()
+        .mul_add(phi2.sin(), -(phi1.sin() * phi2.cos() * delta_lambda.cos()));
+    y.atan2(x).to_degrees().rem_euclid(360.0)
+}
+
+/// A latitude and longitude, and the UTC capture time there.
+type Sighting = ((f64, f64), DateTime<Utc>);
+
+/// Remembers the position and time of the last analyzed photo with both.
+#[derive(Debug, Default)]
+pub struct MovementTracker {
+    previous: Mutex<Option<Sighting>>,
+}
+
+impl MovementTracker {
+    /// The movement since the previously tracked photo, if any, and remembers this one. Photos
+    /// without a UTC capture time are not tracked.
+    pub fn track(&self, gps: &GpsInfo, datetime_utc: Option<DateTime<Utc>>) -> Option<Movement> {
+        let datetime_utc = datetime_utc?;
+        let position = (gps.latitude, gps.longitude);
+        let mut previous = self.previous.lock().unwrap_or_else(PoisonError::into_inner);
+        let movement = previous
+            .map(|(from, from_time)| Movement::between(from, from_time, position, datetime_utc));
+        *previous = Some((position, datetime_utc));
+        movement
+    }
+
+    /// Forgets the previously tracked photo, so the next one has no movement.
+    pub fn reset(&self) {
+        *self.previous.lock().unwrap_or_else(PoisonError::into_inner) = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ExifData;
+    use crate::features::gps::get_gps_coordinates;
+    use serde_json::json;
+
+    const AMSTERDAM: (f64, f64) = (52.3676, 4.9041);
+    const PARIS: (f64, f64) = (48.8566, 2.3522);
+
+    fn gps((latitude, longitude): (f64, f64)) -> GpsInfo {
+        get_gps_coordinates(&ExifData::new(json!({
+            "GPSLatitude": latitude,
+            "GPSLongitude": longitude
+        })))
+        .unwrap()
+    }
+
+    fn at(time: &str) -> DateTime<Utc> {
+        time.parse().unwrap()
+    }
+
+    #[test]
+    fn test_movement_between_cities() {
+        let movement = Movement::between(
+            AMSTERDAM,
+            at("2024-06-01T10:00:00Z"),
+            PARIS,
+            at("2024-06-01T11:00:00Z"),
+        );
+        assert!((movement.distance_km - 430.0).abs() < 5.0, "{movement:?}");
+        // Paris lies to the south-southwest of Amsterdam.
+        assert!(
+            (200.0..220.0).contains(&movement.bearing_deg),
+            "{movement:?}"
+        );
+        assert_eq!(movement.elapsed_s, 3600.0);
+        // Faster than any car: a flight.
+        assert!(movement.implied_speed_kmh.unwrap() > 200.0);
+    }
+
+    #[test]
+    fn test_tracker_sequence_and_reset() {
+        let tracker = MovementTracker::default();
+        assert_eq!(
+            tracker.track(&gps(AMSTERDAM), Some(at("2024-06-01T10:00:00Z"))),
+            None
+        );
+        // Without a UTC time, a photo is skipped and the previous one is kept.
+        assert_eq!(tracker.track(&gps(PARIS), None), None);
+
+        let walk = (52.3731, 4.8922);
+        let movement = tracker
+            .track(&gps(walk), Some(at("2024-06-01T10:15:00Z")))
+            .unwrap();
+        assert!((0.8..1.2).contains(&movement.distance_km), "{movement:?}");
+        let speed = movement.implied_speed_kmh.unwrap();
+        assert!((3.0..5.0).contains(&speed), "{speed}");
+
+        let same_second = tracker
+            .track(&gps(walk), Some(at("2024-06-01T10:15:00Z")))
+            .unwrap();
+        assert_eq!(same_second.distance_km, 0.0);
+        assert_eq!(same_second.implied_speed_kmh, None);
+
+        tracker.reset();
+        assert_eq!(
+            tracker.track(&gps(PARIS), Some(at("2024-06-01T12:00:00Z"))),
+            None
+        );
+    }
+}
@@ -0,0 +1,276 @@
+use crate::features::error::WeatherError;
+use crate::features::weather_provider::{
+    ObservationWithContext, WeatherObservation, WeatherProvider, required_dates,
+};
+use async_trait::async_trait;
+use chrono::{DateTime, NaiveDateTime, TimeDelta, Timelike, Utc};
+use meteostat::WeatherCondition;
+use serde::Deserialize;
+
+const ARCHIVE_URL: &str = "https://archive-api.open-meteo.com/v1/archive";
+const HOURLY_VARIABLES: &str = "temperature_2m,dew_point_2m,relative_humidity_2m,precipitation,\
+snow_depth,wind_direction_10m,wind_speed_10m,wind_gusts_10m,pressure_msl,sunshine_duration,weather_code";
+
+/// A provider backed by the Open-Meteo historical reanalysis API, which has global gridded
+/// coverage. Useful as a fallback where weather stations are sparse.
+pub struct OpenMeteoProvider {
+    client: reqwest::Client,
+    base_url: String,
+}
+
+impl Default for OpenMeteoProvider {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl OpenMeteoProvider {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::with_base_url(ARCHIVE_URL)
+    }
+
+    /// Uses a different archive endpoint, e.g. a self-hosted Open-Meteo instance.
+    #[must_use]
+    pub fn with_base_url(base_url: impl Into<String>) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            base_url: base_url.into(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct ArchiveResponse {
+    hourly: HourlyColumns,
+}
+
+#[derive(Debug, Deserialize)]
+struct HourlyColumns {
+    time: Vec<String>,
+    temperature_2m: Vec<Option<f64>>,
+    dew_point_2m: Vec<Option<f64>>,
+    relative_humidity_2m: Vec<Option<f64>>,
+    precipitation: Vec<Option<f64>>,
+    snow_depth: Vec<Option<f64>>,
+    wind_direction_10m: Vec<Option<f64>>,
+    wind_speed_10m: Vec<Option<f64>>,
+    wind_gusts_10m: Vec<Option<f64>>,
+    pressure_msl: Vec<Option<f64>>,
+    sunshine_duration: Vec<Option<f64>>,
+    weather_code: Vec<Option<f64>>,
+}
+
+/// Maps a WMO weather interpretation code to the closest Meteostat condition.
+const fn condition_from_wmo(code: u32) -> Option<WeatherCondition> {
+    Some(match code {
+        0 => WeatherCondition::Clear,
+        1 => WeatherCondition::Fair,
+        2 => WeatherCondition::Cloudy,
+        3 => WeatherCondition::Overcast,
+        45 => WeatherCondition::Fog,
+        48 => WeatherCondition::FreezingFog,
+        51 | 53 | 55 | 61 => WeatherCondition::LightRain,
+        63 => WeatherCondition::Rain,
+        65 => WeatherCondition::HeavyRain,
+        56 | 57 | 66 => WeatherCondition::FreezingRain,
+        67 => WeatherCondition::HeavyFreezingRain,
+        71 | 77 => WeatherCondition::LightSnowfall,
+        73 => WeatherCondition::Snowfall,
+        75 => WeatherCondition::HeavySnowfall,
+        80 | 81 => WeatherCondition::RainShower,
+        82 => WeatherCondition::HeavyRainShower,
+        85 => WeatherCondition::SnowShower,
+        86 => WeatherCondition::HeavySnowShower,
+        95 => WeatherCondition::Thunderstorm,
+        96 | 99 => WeatherCondition::HeavyThunderstorm,
+        _ => return None,
+    })
+}
+
+fn value_at(column: &[Option<f64>], index: usize) -> Option<f64> {
+    column.get(index).copied().flatten()
+}
+
+#[allow(clippy::cast_sign_loss)]
+fn observation_at(
+    hourly: &HourlyColumns,
+    datetime: DateTime<Utc>,
+) -> Result<WeatherObservation, WeatherError> {
+    let hour = datetime
+        .with_minute(0)
+        .and_then(|dt| dt.with_second(0))
+        .and_then(|dt| dt.with_nanosecond(0))
+        .ok_or(WeatherError::NoDataAvailable)?;
+    let index = hourly
+        .time
+        .iter()
+        .position(|t| {
+            NaiveDateTime::parse_from_str(t, "%Y-%m-%dT%H:%M")
+                .is_ok_and(|naive| naive.and_utc() == hour)
+        })
+        .ok_or(WeatherError::NoDataAvailable)?;
+
+    let temperature = value_at(&hourly.temperature_2m, index);
+    let weather_code = value_at(&hourly.weather_code, index);
+    if temperature.is_none() && weather_code.is_none() {
+        return Err(WeatherError::NoDataAvailable);
+    }
+
+    Ok(WeatherObservation {
+        datetime: hour,
+        temperature,
+        dew_point: value_at(&hourly.dew_point_2m, index),
+        relative_humidity: value_at(&hourly.relative_humidity_2m, index).map(|v| v.round() as i32),
+        precipitation: value_at(&hourly.precipitation, index),
+        // Open-Meteo reports snow depth in meters, Meteostat in millimeters.
+        snow: value_at(&hourly.snow_depth, index).map(|v| (v * 1000.0).round() as i32),
+        wind_direction: value_at(&hourly.wind_direction_10m, index).map(|v| v.round() as i32),
+        wind_speed: value_at(&hourly.wind_speed_10m, index),
+        peak_wind_gust: value_at(&hourly.wind_gusts_10m, index),
+        pressure: value_at(&hourly.pressure_msl, index),
+        // Open-Meteo reports sunshine duration in seconds, Meteostat in minutes.
+        sunshine_minutes: value_at(&hourly.sunshine_duration, index)
+            .map(|v| (v / 60.0).round() as i32),
+        condition: weather_code.and_then(|c| condition_from_wmo(c as u32)),
+    })
+}
+
+/// The observation at `datetime`, and the ones within `context_hours` of it on the fetched days.
+fn observation_with_context(
+    hourly: &HourlyColumns,
+    datetime: DateTime<Utc>,
+    context_hours: u8,
+) -> Result<ObservationWithContext, WeatherError> {
+    let observation = observation_at(hourly, datetime)?;
+    let context_hours = i64::from(context_hours);
+    let context = (-context_hours..=context_hours)
+        .filter_map(|offset| observation_at(hourly, datetime + TimeDelta::hours(offset)).ok())
+        .collect();
+    Ok((observation, context))
+}
+
+impl OpenMeteoProvider {
+    /// Fetches the hourly columns for the UTC days around `datetime`, see [`required_dates`].
+    async fn fetch_days(
+        &self,
+        latitude: f64,
+        longitude: f64,
+        datetime: DateTime<Utc>,
+        context_hours: u8,
+    ) -> Result<HourlyColumns, WeatherError> {
+        let (start_date, end_date) = required_dates(datetime, context_hours);
+        let response: ArchiveResponse = self
+            .client
+            .get(&self.base_url)
+            .query(&[
+                ("latitude", latitude.to_string()),
+                ("longitude", longitude.to_string()),
+                ("start_date", start_date.to_string()),
+                ("end_date", end_date.to_string()),
+                ("hourly", HOURLY_VARIABLES.to_string()),
+                ("timezone", "UTC".to_string()),
+            ])
+            .send()
+            .await
+            .and_then(reqwest::Response::error_for_status)
+            .map_err(|e| WeatherError::Provider(e.to_string()))?
+            .json()
+            .await
+            .map_err(|e| WeatherError::Provider(e.to_string()))?;
+        Ok(response.hourly)
+    }
+}
+
+#[async_trait]
+impl WeatherProvider for OpenMeteoProvider {
+    async fn hourly_at(
+        &self,
+        latitude: f64,
+        longitude: f64,
+        datetime: DateTime<Utc>,
+    ) -> Result<WeatherObservation, WeatherError> {
+        let hourly = self.fetch_days(latitude, longitude, datetime, 0).await?;
+        observation_at(&hourly, datetime)
+    }
+
+    /// Only the hours of the fetched UTC days are available as context.
+    async fn hourly_with_context(
+        &self,
+        latitude: f64,
+        longitude: f64,
+        datetime: DateTime<Utc>,
+        _radius_km: f64,
+        context_hours: u8,
+    ) -> Result<ObservationWithContext, WeatherError> {
+        let hourly = self
+            .fetch_days(latitude, longitude, datetime, context_hours)
+            .await?;
+        observation_with_context(&hourly, datetime, context_hours)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use serde_json::json;
+
+    fn sample_response() -> ArchiveResponse {
+        serde_json::from_value(json!({
+            "hourly": {
+                "time": ["2023-10-26T11:00", "2023-10-26T12:00"],
+                "temperature_2m": [12.1, 13.4],
+                "dew_point_2m": [8.0, 8.5],
+                "relative_humidity_2m": [77, 72],
+                "precipitation": [0.0, 0.2],
+                "snow_depth": [0.0, 0.05],
+                "wind_direction_10m": [200, 210],
+                "wind_speed_10m": [14.2, 15.0],
+                "wind_gusts_10m": [30.1, 31.0],
+                "pressure_msl": [1012.3, 1012.0],
+                "sunshine_duration": [3600.0, 1800.0],
+                "weather_code": [3, 61]
+            }
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_observation_at_picks_the_matching_hour() {
+        let response = sample_response();
+        let datetime = Utc.with_ymd_and_hms(2023, 10, 26, 12, 34, 0).unwrap();
+
+        let observation = observation_at(&response.hourly, datetime).unwrap();
+        assert_eq!(
+            observation.datetime,
+            Utc.with_ymd_and_hms(2023, 10, 26, 12, 0, 0).unwrap()
+        );
+        assert_eq!(observation.temperature, Some(13.4));
+        assert_eq!(observation.relative_humidity, Some(72));
+        assert_eq!(observation.snow, Some(50));
+        assert_eq!(observation.sunshine_minutes, Some(30));
+        assert_eq!(observation.condition, Some(WeatherCondition::LightRain));
+    }
+
+    #[test]
+    fn test_observation_at_missing_hour_is_no_data() {
+        let response = sample_response();
+        let datetime = Utc.with_ymd_and_hms(2023, 10, 26, 18, 0, 0).unwrap();
+
+        let result = observation_at(&response.hourly, datetime);
+        assert!(matches!(result, Err(WeatherError::NoDataAvailable)));
+    }
+
+    #[test]
+    fn test_context_is_limited_to_the_fetched_day() {
+        let response = sample_response();
+        let datetime = Utc.with_ymd_and_hms(2023, 10, 26, 12, 15, 0).unwrap();
+
+        let (observation, context) =
+            observation_with_context(&response.hourly, datetime, 3).unwrap();
+        assert_eq!(observation.temperature, Some(13.4));
+        let temperatures: Vec<_> = context.iter().map(|o| o.temperature).collect();
+        assert_eq!(temperatures, vec![Some(12.1), Some(13.4)]);
+    }
+}
@@ -0,0 +1,188 @@
+//! Orders a batch of results by capture time, deterministically, also within bursts whose frames
+//! share a timestamp down to the second.
+
+use crate::features::sessions::ordering_time;
+use crate::structs::MediaMetadata;
+use crate::time::structs::CONFIDENCE_LOW;
+use chrono::{DateTime, TimeDelta, Timelike, Utc};
+use serde_json::Value;
+
+/// What results are sorted by, in order of precedence.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+struct ChronologicalKey {
+    /// The capture time, with the `SubSecTimeOriginal` fraction when the time has none.
+    instant: DateTime<Utc>,
+    low_confidence: bool,
+    /// The bracket position, or the trailing number of the filename. Results without one come
+    /// last.
+    sequence: u64,
+    filename: String,
+}
+
+/// The current filename, falling back to the original one preserved in the metadata.
+pub fn filename(result: &MediaMetadata) -> String {
+    result
+        .exif
+        .pointer("/Other/FileName")
+        .and_then(Value::as_str)
+        .or(result.basic.original_filename.as_deref())
+        .unwrap_or_default()
+        .to_string()
+}
+
+/// The last run of digits in the file stem, like 3 in `IMG_0003.jpg` or `DSC_0412-3.jpg`.
+fn trailing_number(filename: &str) -> Option<u64> {
+    let stem = filename.rsplit_once('.').map_or(filename, |(stem, _)| stem);
+    let end = stem.rfind(|c: char| c.is_ascii_digit())? + 1;
+    let start = stem[..end]
+        .rfind(|c: char| !c.is_ascii_digit())
+        .map_or(0, |i| i + 1);
+    stem[start..end].parse().ok()
+}
+
+/// The `SubSecTimeOriginal` fraction, for times read from a tag without one. Exiftool writes
+/// it as a number unless it has leading zeros, like "045".
+fn subsec(exif: &Value) -> Option<TimeDelta> {
+    let digits = match exif.pointer("/Time/SubSecTimeOriginal")? {
+        Value::String(text) => text.trim().to_string(),
+        Value::Number(number) => number.to_string(),
+        _ => return None,
+    };
+    if digits.is_empty() || digits.len() > 9 || !digits.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    let nanos: i64 = format!("{digits:0<9}").parse().ok()?;
+    Some(TimeDelta::nanoseconds(nanos))
+}
+
+fn chronological_key(result: &MediaMetadata) -> ChronologicalKey {
+    let mut instant = ordering_time(&result.time);
+    if instant.nanosecond() == 0
+        && let Some(fraction) = subsec(&result.exif)
+    {
+        instant += fraction;
+    }
+    let filename = filename(result);
+    ChronologicalKey {
+        instant,
+        low_confidence: result.time.source_details.confidence == CONFIDENCE_LOW,
+        sequence: result
+            .features
+            .bracket_sequence
+            .map(u64::from)
+            .or_else(|| trailing_number(&filename))
+            .unwrap_or(u64::MAX),
+        filename,
+    }
+}
+
+/// Sorts results by capture time, in the same order on every run.
+///
+/// Results are ordered by their UTC time, or their local time as if it were UTC when the
+/// timezone is unknown, including sub-seconds. Results taken at the same instant are ordered by:
+/// 1. Confidence: `Low` confidence times (file dates, filenames) sort after `Medium` and
+///    `High` confidence times.
+/// 2. Sequence number: the position in an exposure bracket, or else the trailing number in the
+///    filename, so burst frames stay in shooting order.
+/// 3. Filename.
+///
+/// The sort is stable, so results that tie on all of these keep their relative order.
+pub fn sort_chronologically(results: &mut [MediaMetadata]) {
+    results.sort_by_cached_key(chronological_key);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::MediaMetadataFixture;
+    use serde_json::json;
+
+    /// A photo named `filename`, taken at `time` ("HH:MM:SS" on 2024-06-01) with extra `Time`
+    /// tags. Without an `OffsetTimeOriginal` in `time_tags`, its time has a low confidence.
+    fn photo(filename: &str, time: &str, time_tags: &Value) -> MediaMetadata {
+        let mut time_group = json!({ "DateTimeOriginal": format!("2024:06:01 {time}") });
+        time_group
+            .as_object_mut()
+            .unwrap()
+            .extend(time_tags.as_object().unwrap().clone());
+        MediaMetadataFixture::from_exif(json!({
+            "ImageWidth": 4000, "ImageHeight": 3000, "MIMEType": "image/jpeg", "FileSize": 1024,
+            "Make": "Canon", "Model": "EOS R5",
+            "Other": { "FileName": filename },
+            "Time": time_group
+        }))
+        .build()
+    }
+
+    fn filenames(results: &[MediaMetadata]) -> Vec<String> {
+        results.iter().map(filename).collect()
+    }
+
+    #[test]
+    fn test_burst_in_one_second_is_ordered_deterministically() {
+        let offset = json!({ "OffsetTimeOriginal": "+02:00" });
+        let burst: Vec<MediaMetadata> = (8..=12)
+            .map(|n| photo(&format!("IMG_{n:04}.jpg"), "12:00:00", &offset))
+            .collect();
+        let expected = filenames(&burst);
+
+        let mut orders = Vec::new();
+        for rotation in 0..burst.len() {
+            for reverse in [false, true] {
+                let mut shuffled = burst.clone();
+                shuffled.rotate_left(rotation);
+                if reverse {
+                    shuffled.reverse();
+                }
+                sort_chronologically(&mut shuffled);
+                orders.push(filenames(&shuffled));
+            }
+        }
+        assert!(orders.iter().all(|order| *order == expected), "{orders:?}");
+    }
+
+    #[test]
+    fn test_subseconds_and_confidence_break_ties() {
+        let offset = json!({ "OffsetTimeOriginal": "+02:00" });
+        let mut results = vec![
+            // Numbered before the others, but taken later in the second.
+            photo(
+                "IMG_0001.jpg",
+                "12:00:00",
+                &json!({ "OffsetTimeOriginal": "+02:00", "SubSecTimeOriginal": 5 }),
+            ),
+            photo(
+                "IMG_0002.jpg",
+                "12:00:00",
+                &json!({ "OffsetTimeOriginal": "+02:00", "SubSecTimeOriginal": "045" }),
+            ),
+            // The same instant without a timezone, read as UTC.
+            photo("IMG_0003.jpg", "10:00:00", &json!({})),
+            photo("IMG_0004.jpg", "12:00:00", &offset),
+            photo("IMG_0005.jpg", "11:59:59", &offset),
+        ];
+        sort_chronologically(&mut results);
+        assert_eq!(
+            filenames(&results),
+            vec![
+                "IMG_0005.jpg",
+                "IMG_0004.jpg",
+                "IMG_0003.jpg",
+                "IMG_0002.jpg",
+                "IMG_0001.jpg"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_trailing_number() {
+        assert_eq!(trailing_number("IMG_0003.jpg"), Some(3));
+        assert_eq!(trailing_number("DSC_0412-12.JPG"), Some(12));
+        assert_eq!(
+            trailing_number("PXL_20240601_120000123.jpg"),
+            Some(120_000_123)
+        );
+        assert_eq!(trailing_number("holiday.jpg"), None);
+        assert_eq!(trailing_number("2024"), Some(2024));
+    }
+}
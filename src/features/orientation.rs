@@ -0,0 +1,237 @@
+use serde::{Deserialize, Deserializer, Serialize};
+use thiserror::Error;
+
+/// How a photo must be transformed to display upright, from the EXIF `Orientation` tag (1-8).
+///
+/// Serializes as the EXIF number, so stored results stay compatible. Wrap it in
+/// [`OrientationName`] to serialize a readable name instead.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[serde(try_from = "u64", into = "u64")]
+pub enum Orientation {
+    Normal,
+    /// Mirrored left to right.
+    FlipH,
+    Rotate180,
+    /// Mirrored top to bottom.
+    FlipV,
+    /// Mirrored along the top-left to bottom-right diagonal.
+    Transpose,
+    Rotate90CW,
+    /// Mirrored along the top-right to bottom-left diagonal.
+    Transverse,
+    Rotate270CW,
+}
+
+/// An EXIF `Orientation` value outside 1-8.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Error)]
+#[error("invalid EXIF orientation {0}, expected 1-8")]
+pub struct InvalidOrientation(pub u64);
+
+impl Orientation {
+    /// The clockwise rotation to display the image upright, applied after a horizontal flip
+    /// when [`Orientation::is_flipped`]. EXIF describes `FlipV` as a flip plus 180°, and the
+    /// diagonal mirrors as a flip plus 90° or 270°.
+    #[must_use]
+    pub const fn rotation_degrees(self) -> u16 {
+        match self {
+            Self::Normal | Self::FlipH => 0,
+            Self::Rotate90CW | Self::Transverse => 90,
+            Self::Rotate180 | Self::FlipV => 180,
+            Self::Rotate270CW | Self::Transpose => 270,
+        }
+    }
+
+    /// Whether the image is mirrored, on top of any rotation.
+    #[must_use]
+    pub const fn is_flipped(self) -> bool {
+        matches!(
+            self,
+            Self::FlipH | Self::FlipV | Self::Transpose | Self::Transverse
+        )
+    }
+
+    /// Whether the displayed image's width is the stored image's height.
+    #[must_use]
+    pub const fn swaps_dimensions(self) -> bool {
+        self.rotation_degrees() % 180 == 90
+    }
+
+    /// The EXIF `Orientation` value.
+    #[must_use]
+    pub const fn exif_value(self) -> u64 {
+        match self {
+            Self::Normal => 1,
+            Self::FlipH => 2,
+            Self::Rotate180 => 3,
+            Self::FlipV => 4,
+            Self::Transpose => 5,
+            Self::Rotate90CW => 6,
+            Self::Transverse => 7,
+            Self::Rotate270CW => 8,
+        }
+    }
+}
+
+impl TryFrom<u64> for Orientation {
+    type Error = InvalidOrientation;
+
+    fn try_from(value: u64) -> Result<Self, Self::Error> {
+        Ok(match value {
+            1 => Self::Normal,
+            2 => Self::FlipH,
+            3 => Self::Rotate180,
+            4 => Self::FlipV,
+            5 => Self::Transpose,
+            6 => Self::Rotate90CW,
+            7 => Self::Transverse,
+            8 => Self::Rotate270CW,
+            _ => return Err(InvalidOrientation(value)),
+        })
+    }
+}
+
+impl From<Orientation> for u64 {
+    fn from(orientation: Orientation) -> Self {
+        orientation.exif_value()
+    }
+}
+
+/// Serializes an [`Orientation`] by name, e.g. `"rotate90CW"`, rather than by EXIF number.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(transparent)]
+pub struct OrientationName(#[serde(with = "by_name")] pub Orientation);
+
+mod by_name {
+    use super::Orientation;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    #[derive(Deserialize, Serialize)]
+    #[serde(rename_all = "camelCase")]
+    enum Name {
+        Normal,
+        FlipH,
+        Rotate180,
+        FlipV,
+        Transpose,
+        Rotate90CW,
+        Transverse,
+        Rotate270CW,
+    }
+
+    #[allow(clippy::trivially_copy_pass_by_ref)]
+    pub fn serialize<S: Serializer>(
+        orientation: &Orientation,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        let name = match orientation {
+            Orientation::Normal => Name::Normal,
+            Orientation::FlipH => Name::FlipH,
+            Orientation::Rotate180 => Name::Rotate180,
+            Orientation::FlipV => Name::FlipV,
+            Orientation::Transpose => Name::Transpose,
+            Orientation::Rotate90CW => Name::Rotate90CW,
+            Orientation::Transverse => Name::Transverse,
+            Orientation::Rotate270CW => Name::Rotate270CW,
+        };
+        name.serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Orientation, D::Error> {
+        Ok(match Name::deserialize(deserializer)? {
+            Name::Normal => Orientation::Normal,
+            Name::FlipH => Orientation::FlipH,
+            Name::Rotate180 => Orientation::Rotate180,
+            Name::FlipV => Orientation::FlipV,
+            Name::Transpose => Orientation::Transpose,
+            Name::Rotate90CW => Orientation::Rotate90CW,
+            Name::Transverse => Orientation::Transverse,
+            Name::Rotate270CW => Orientation::Rotate270CW,
+        })
+    }
+}
+
+/// Deserializes an optional orientation, reading values outside 1-8 (which older results may
+/// hold) as `None` instead of failing.
+pub fn deserialize_lenient<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<Orientation>, D::Error> {
+    let value = Option::<u64>::deserialize(deserializer)?;
+    Ok(value.and_then(|value| Orientation::try_from(value).ok()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const ALL: [(u64, Orientation, u16, bool, bool); 8] = [
+        (1, Orientation::Normal, 0, false, false),
+        (2, Orientation::FlipH, 0, true, false),
+        (3, Orientation::Rotate180, 180, false, false),
+        (4, Orientation::FlipV, 180, true, false),
+        (5, Orientation::Transpose, 270, true, true),
+        (6, Orientation::Rotate90CW, 90, false, true),
+        (7, Orientation::Transverse, 90, true, true),
+        (8, Orientation::Rotate270CW, 270, false, true),
+    ];
+
+    #[test]
+    fn test_all_eight_values() {
+        for (value, orientation, degrees, flipped, swaps) in ALL {
+            assert_eq!(Orientation::try_from(value), Ok(orientation));
+            assert_eq!(u64::from(orientation), value);
+            assert_eq!(orientation.rotation_degrees(), degrees, "{orientation:?}");
+            assert_eq!(orientation.is_flipped(), flipped, "{orientation:?}");
+            assert_eq!(orientation.swaps_dimensions(), swaps, "{orientation:?}");
+        }
+    }
+
+    #[derive(Deserialize)]
+    struct Stored {
+        #[serde(default, deserialize_with = "deserialize_lenient")]
+        orientation: Option<Orientation>,
+    }
+
+    #[test]
+    fn test_invalid_values() {
+        assert_eq!(Orientation::try_from(0), Err(InvalidOrientation(0)));
+        assert_eq!(Orientation::try_from(9), Err(InvalidOrientation(9)));
+        assert!(serde_json::from_value::<Orientation>(json!(0)).is_err());
+        assert!(serde_json::from_value::<Orientation>(json!(9)).is_err());
+
+        let read = |value| serde_json::from_value::<Stored>(value).unwrap().orientation;
+        assert_eq!(read(json!({ "orientation": 0 })), None);
+        assert_eq!(read(json!({ "orientation": null })), None);
+        assert_eq!(read(json!({})), None);
+        assert_eq!(
+            read(json!({ "orientation": 6 })),
+            Some(Orientation::Rotate90CW)
+        );
+    }
+
+    #[test]
+    fn test_numeric_serde_round_trip() {
+        for (value, orientation, ..) in ALL {
+            let serialized = serde_json::to_value(orientation).unwrap();
+            assert_eq!(serialized, json!(value));
+            assert_eq!(
+                serde_json::from_value::<Orientation>(serialized).unwrap(),
+                orientation
+            );
+        }
+    }
+
+    #[test]
+    fn test_name_serde_round_trip() {
+        let serialized = serde_json::to_value(OrientationName(Orientation::Rotate90CW)).unwrap();
+        assert_eq!(serialized, json!("rotate90CW"));
+        for (_, orientation, ..) in ALL {
+            let serialized = serde_json::to_value(OrientationName(orientation)).unwrap();
+            assert!(serialized.is_string());
+            let read: OrientationName = serde_json::from_value(serialized).unwrap();
+            assert_eq!(read.0, orientation);
+        }
+    }
+}
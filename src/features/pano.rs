@@ -1,40 +1,457 @@
 use crate::ExifData;
+use serde::{Deserialize, Serialize};
 
-pub fn should_use_pano_viewer(exif: &ExifData) -> bool {
-    if let Some(use_panorama_viewer) = exif.get_bool_ignoring_case("UsePanoramaViewer") {
-        return use_panorama_viewer;
+/// How far the width of an equirectangular panorama may be from twice its height, as a
+/// fraction of the width, when the projection is assumed from the dimensions.
+const EQUIRECTANGULAR_RATIO_TOLERANCE: f64 = 0.02;
+
+/// Projections of the raw lens images of 360 degree cameras, which have to be stitched before
+/// a viewer can show them.
+const UNSTITCHED_PROJECTIONS: [&str; 3] = ["fisheye", "dual-fisheye", "cubemap"];
+
+/// The GPano view of a photo sphere or panorama: how much of the full sphere or cylinder the
+/// image covers, and where.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct PanoViewInfo {
+    /// The `ProjectionType`, lowercased, like "equirectangular", "cylindrical" or
+    /// "dual-fisheye". Apps like Cardboard Camera and older Photo Sphere versions omit it.
+    pub projection_type: Option<String>,
+    /// `projection_type` was not written, and is "equirectangular" because the full panorama
+    /// is about twice as wide as it is high.
+    pub projection_assumed: bool,
+    pub full_pano_width: Option<u64>,
+    pub full_pano_height: Option<u64>,
+    pub cropped_width: Option<u64>,
+    pub cropped_height: Option<u64>,
+    /// The position of the image within the full panorama, in pixels from the left.
+    pub cropped_left: Option<u64>,
+    /// The position of the image within the full panorama, in pixels from the top.
+    pub cropped_top: Option<u64>,
+    /// The horizontal angle the image covers, out of 360 degrees.
+    pub horizontal_coverage_deg: Option<f64>,
+    /// The vertical angle the image covers, out of 180 degrees. Only known for equirectangular
+    /// panoramas.
+    pub vertical_coverage_deg: Option<f64>,
+    /// The lens images of a 360 degree camera that weren't stitched yet, with a fisheye,
+    /// dual-fisheye or cubemap projection or `Stitched` false. The panorama viewer can't show
+    /// them.
+    pub needs_stitching: bool,
+    /// The number of lenses the image was captured with, from the `SourceCount` of Google's
+    /// Spherical Video metadata or a dual-fisheye projection.
+    pub lens_count: Option<u8>,
+}
+
+/// Whether full panorama dimensions fit an equirectangular projection, which is twice as
+/// wide as it is high.
+#[allow(clippy::cast_precision_loss)]
+fn is_equirectangular_size(width: u64, height: u64) -> bool {
+    let width = width as f64;
+    height > 0 && (width - 2.0 * height as f64).abs() <= width * EQUIRECTANGULAR_RATIO_TOLERANCE
+}
+
+/// Whether the file holds the lens images of a 360 degree camera that weren't stitched yet.
+fn is_unstitched(exif: &ExifData, projection_type: Option<&str>) -> bool {
+    projection_type.is_some_and(|projection| UNSTITCHED_PROJECTIONS.contains(&projection))
+        || exif.get_bool_ignoring_case("Stitched") == Some(false)
+}
+
+/// Reads the GPano view of a panorama, or `None` when the file has neither a `ProjectionType`
+/// nor `CroppedArea` or `FullPano` dimensions.
+///
+/// ```
+/// use media_analyzer::ExifData;
+/// use media_analyzer::detectors::get_pano_view_info;
+/// use serde_json::json;
+///
+/// // A partial photo sphere from an app that doesn't write the projection type.
+/// let exif = ExifData::new(json!({
+///     "Other": {
+///         "UsePanoramaViewer": "True",
+///         "CroppedAreaImageWidthPixels": 4000,
+///         "CroppedAreaImageHeightPixels": 1000,
+///         "FullPanoWidthPixels": 8000,
+///         "FullPanoHeightPixels": 4000
+///     }
+/// }));
+/// let pano = get_pano_view_info(&exif).unwrap();
+/// assert_eq!(pano.projection_type.as_deref(), Some("equirectangular"));
+/// assert!(pano.projection_assumed);
+/// assert_eq!(pano.horizontal_coverage_deg, Some(180.0));
+/// assert_eq!(pano.vertical_coverage_deg, Some(45.0));
+/// ```
+#[must_use]
+#[allow(clippy::cast_precision_loss)]
+pub fn get_pano_view_info(exif: &ExifData) -> Option<PanoViewInfo> {
+    let written_projection = exif
+        .get_ignoring_case("ProjectionType")
+        .and_then(|v| v.as_str())
+        .map(str::to_lowercase);
+    let full_pano_width = exif.get_u64_ignoring_case("FullPanoWidthPixels");
+    let full_pano_height = exif.get_u64_ignoring_case("FullPanoHeightPixels");
+    let cropped_width = exif.get_u64_ignoring_case("CroppedAreaImageWidthPixels");
+    let cropped_height = exif.get_u64_ignoring_case("CroppedAreaImageHeightPixels");
+    let dimensions = [
+        full_pano_width,
+        full_pano_height,
+        cropped_width,
+        cropped_height,
+    ];
+    if written_projection.is_none() && dimensions.iter().all(Option::is_none) {
+        return None;
     }
 
-    // 3. Extract dimensions and projection metadata
-    let cropped_width = exif.get_f64_ignoring_case("CroppedAreaImageWidthPixels");
-    let full_width = exif.get_f64_ignoring_case("FullPanoWidthPixels");
-    let has_gpano_dims = cropped_width.is_some() || full_width.is_some();
+    let projection_assumed = written_projection.is_none()
+        && full_pano_width
+            .zip(full_pano_height)
+            .is_some_and(|(width, height)| is_equirectangular_size(width, height));
+    let projection_type =
+        written_projection.or_else(|| projection_assumed.then(|| "equirectangular".to_string()));
+    let coverage = |cropped: Option<u64>, full: Option<u64>, degrees: f64| {
+        cropped
+            .zip(full.filter(|full| *full > 0))
+            .map(|(cropped, full)| cropped as f64 / full as f64 * degrees)
+    };
+    let vertical_coverage_deg = coverage(cropped_height, full_pano_height, 180.0)
+        .filter(|_| projection_type.as_deref() == Some("equirectangular"));
+    let lens_count = exif
+        .get_u64_ignoring_case("SourceCount")
+        .and_then(|count| u8::try_from(count).ok())
+        .or_else(|| (projection_type.as_deref() == Some("dual-fisheye")).then_some(2));
+    Some(PanoViewInfo {
+        needs_stitching: is_unstitched(exif, projection_type.as_deref()),
+        lens_count,
+        horizontal_coverage_deg: coverage(cropped_width, full_pano_width, 360.0),
+        vertical_coverage_deg,
+        projection_type,
+        projection_assumed,
+        full_pano_width,
+        full_pano_height,
+        cropped_width,
+        cropped_height,
+        cropped_left: exif.get_u64_ignoring_case("CroppedAreaLeftPixels"),
+        cropped_top: exif.get_u64_ignoring_case("CroppedAreaTopPixels"),
+    })
+}
 
+/// Explains why a panorama is stereoscopic, like those of Cardboard Camera, which embed the
+/// right-eye image in the XMP `GImage:Data` of the left-eye panorama. `None` for other images.
+///
+/// ```
+/// use media_analyzer::ExifData;
+/// use media_analyzer::detectors::stereo_pano_evidence;
+/// use serde_json::json;
+///
+/// let cardboard = ExifData::new(json!({
+///     "Other": {
+///         "UsePanoramaViewer": true,
+///         "ImageMimeType": "image/jpeg",
+///         "ImageData": "(Binary data 1534232 bytes, use -b option to extract)"
+///     }
+/// }));
+/// assert_eq!(
+///     stereo_pano_evidence(&cardboard).as_deref(),
+///     Some("Right-eye image in GImage:Data of a panorama")
+/// );
+/// ```
+#[must_use]
+pub fn stereo_pano_evidence(exif: &ExifData) -> Option<String> {
+    // ExifTool names `GImage:Data` "ImageData".
+    let has_right_eye = exif.get_ignoring_case("ImageData").is_some();
+    let is_pano = pano_viewer_evidence(exif).is_some() || get_pano_view_info(exif).is_some();
+    (has_right_eye && is_pano).then(|| "Right-eye image in GImage:Data of a panorama".to_string())
+}
+
+/// Explains why the panorama viewer should be used, or `None` when it shouldn't, like for the
+/// unstitched lens images of a 360 degree camera.
+///
+/// ```
+/// use media_analyzer::ExifData;
+/// use media_analyzer::detectors::pano_viewer_evidence;
+/// use serde_json::json;
+///
+/// let photosphere = ExifData::new(json!({
+///     "Other": { "ProjectionType": "equirectangular" }
+/// }));
+/// assert_eq!(
+///     pano_viewer_evidence(&photosphere).as_deref(),
+///     Some("ProjectionType is equirectangular")
+/// );
+///
+/// // A wide, but not 360 degree, cylindrical panorama is shown as a flat image.
+/// let partial = ExifData::new(json!({
+///     "Other": {
+///         "ProjectionType": "cylindrical",
+///         "CroppedAreaImageWidthPixels": 4000,
+///         "FullPanoWidthPixels": 12000
+///     }
+/// }));
+/// assert_eq!(pano_viewer_evidence(&partial), None);
+/// ```
+#[must_use]
+pub fn pano_viewer_evidence(exif: &ExifData) -> Option<String> {
     let projection_type = exif
         .get_ignoring_case("ProjectionType")
         .and_then(|v| v.as_str())
         .map(str::to_lowercase);
+    if is_unstitched(exif, projection_type.as_deref()) {
+        return None;
+    }
+    if let Some(use_panorama_viewer) = exif.get_bool_ignoring_case("UsePanoramaViewer") {
+        return use_panorama_viewer.then(|| "UsePanoramaViewer is true".to_string());
+    }
+
+    // 3. Extract dimensions and projection metadata
+    let cropped_width = exif.get_f64_ignoring_case("CroppedAreaImageWidthPixels");
+    let full_width = exif.get_f64_ignoring_case("FullPanoWidthPixels");
+    let has_gpano_dims = cropped_width.is_some() || full_width.is_some();
 
     match projection_type.as_deref() {
-        Some("equirectangular") => true,
+        Some("equirectangular") => Some("ProjectionType is equirectangular".to_string()),
         Some("cylindrical") => {
             // Only use the panorama viewer for cylindrical views if they cover 360 degrees
-            if let (Some(cw), Some(fw)) = (cropped_width, full_width) {
-                if fw > 0.0 {
-                    let haov = (cw / fw) * 360.0;
-                    haov >= 359.0
-                } else {
-                    false
-                }
+            if let (Some(cw), Some(fw)) = (cropped_width, full_width)
+                && fw > 0.0
+            {
+                let haov = (cw / fw) * 360.0;
+                (haov >= 359.0).then(|| {
+                    format!("cylindrical projection covering {haov:.1} degrees horizontally")
+                })
             } else {
-                false
+                None
             }
         }
         None => {
             // When GPano tags are present but ProjectionType is omitted,
             // standard Google Photosphere specifications fallback to equirectangular.
-            has_gpano_dims
+            has_gpano_dims.then(|| {
+                "GPano dimensions without ProjectionType (assumed equirectangular)".to_string()
+            })
         }
-        _ => false,
+        _ => None,
+    }
+}
+
+/// Explains why a video is a 360 degree video, from Google's Spherical Video metadata or the
+/// same projection tags as photospheres. Videos are never shown in the panorama viewer, which
+/// is for images.
+///
+/// ```
+/// use media_analyzer::ExifData;
+/// use media_analyzer::detectors::spherical_video_evidence;
+/// use serde_json::json;
+///
+/// let video = ExifData::new(json!({
+///     "Other": { "MIMEType": "video/mp4" },
+///     "Video": { "Spherical": true, "ProjectionType": "equirectangular" }
+/// }));
+/// assert_eq!(
+///     spherical_video_evidence(&video).as_deref(),
+///     Some("Spherical is true")
+/// );
+/// ```
+#[must_use]
+pub fn spherical_video_evidence(exif: &ExifData) -> Option<String> {
+    if exif.get_bool_ignoring_case("Spherical") == Some(true) {
+        return Some("Spherical is true".to_string());
+    }
+    pano_viewer_evidence(exif)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::features::gps::get_gps_coordinates;
+    use crate::raw_exif::RawExif;
+    use serde_json::json;
+    use std::path::Path;
+
+    fn should_use_pano_viewer(exif: &ExifData) -> bool {
+        pano_viewer_evidence(exif).is_some()
+    }
+
+    #[test]
+    fn test_prefixed_gpano_keys_partial_cylindrical_pano() {
+        // A 120 degree cylindrical pano with exiftool's "XMP-GPano:" prefixed keys.
+        let exif = ExifData::new(json!({
+            "XMP-GPano:ProjectionType": "cylindrical",
+            "XMP-GPano:CroppedAreaImageWidthPixels": 4000,
+            "XMP-GPano:FullPanoWidthPixels": 12000
+        }));
+        assert!(!should_use_pano_viewer(&exif));
+    }
+
+    #[test]
+    fn test_prefixed_gpano_keys_full_cylindrical_pano() {
+        let exif = ExifData::new(json!({
+            "Other": {
+                "XMP-GPano:ProjectionType": "cylindrical",
+                "XMP-GPano:CroppedAreaImageWidthPixels": 12000,
+                "XMP-GPano:FullPanoWidthPixels": 12000
+            }
+        }));
+        assert!(should_use_pano_viewer(&exif));
+    }
+
+    #[test]
+    fn test_prefixed_use_panorama_viewer_wins() {
+        let exif = ExifData::new(json!({
+            "XMP-GPano:UsePanoramaViewer": false,
+            "XMP-GPano:ProjectionType": "equirectangular"
+        }));
+        assert!(!should_use_pano_viewer(&exif));
+    }
+
+    #[test]
+    fn test_bare_and_prefixed_keys_agree() {
+        let bare = ExifData::new(json!({
+            "CroppedAreaImageWidthPixels": 8000,
+            "FullPanoWidthPixels": 8000
+        }));
+        let prefixed = ExifData::new(json!({
+            "XMP-GPano:CroppedAreaImageWidthPixels": 8000,
+            "XMP-GPano:FullPanoWidthPixels": 8000
+        }));
+        assert!(should_use_pano_viewer(&bare));
+        assert_eq!(
+            should_use_pano_viewer(&bare),
+            should_use_pano_viewer(&prefixed)
+        );
+    }
+
+    #[test]
+    fn test_use_panorama_viewer_string_is_enough() {
+        // Third-party stitchers write only this tag, as a string.
+        let exif = ExifData::new(json!({ "XMP-GPano:UsePanoramaViewer": "True" }));
+        assert!(should_use_pano_viewer(&exif));
+        assert_eq!(get_pano_view_info(&exif), None);
+    }
+
+    #[test]
+    fn test_projectionless_partial_pano_is_assumed_equirectangular() {
+        let exif = ExifData::new(json!({
+            "Other": {
+                "XMP-GPano:UsePanoramaViewer": "True",
+                "XMP-GPano:CroppedAreaImageWidthPixels": 6000,
+                "XMP-GPano:CroppedAreaImageHeightPixels": 2000,
+                "XMP-GPano:CroppedAreaLeftPixels": 1000,
+                "XMP-GPano:CroppedAreaTopPixels": 1000,
+                "XMP-GPano:FullPanoWidthPixels": 8000,
+                "XMP-GPano:FullPanoHeightPixels": 4000
+            }
+        }));
+        let pano = get_pano_view_info(&exif).unwrap();
+        assert_eq!(pano.projection_type.as_deref(), Some("equirectangular"));
+        assert!(pano.projection_assumed);
+        assert_eq!(pano.cropped_left, Some(1000));
+        assert_eq!(pano.cropped_top, Some(1000));
+        assert_eq!(pano.horizontal_coverage_deg, Some(270.0));
+        assert_eq!(pano.vertical_coverage_deg, Some(90.0));
+        assert_eq!(stereo_pano_evidence(&exif), None);
+    }
+
+    #[test]
+    fn test_projection_is_not_assumed_for_other_ratios() {
+        let exif = ExifData::new(json!({
+            "CroppedAreaImageWidthPixels": 4000,
+            "FullPanoWidthPixels": 12000,
+            "FullPanoHeightPixels": 3000
+        }));
+        let pano = get_pano_view_info(&exif).unwrap();
+        assert_eq!(pano.projection_type, None);
+        assert!(!pano.projection_assumed);
+        assert_eq!(pano.horizontal_coverage_deg, Some(120.0));
+        assert_eq!(pano.vertical_coverage_deg, None);
+
+        let written = ExifData::new(json!({
+            "ProjectionType": "cylindrical",
+            "FullPanoWidthPixels": 8000,
+            "FullPanoHeightPixels": 4000
+        }));
+        let pano = get_pano_view_info(&written).unwrap();
+        assert_eq!(pano.projection_type.as_deref(), Some("cylindrical"));
+        assert!(!pano.projection_assumed);
+    }
+
+    #[test]
+    fn test_cardboard_camera_is_stereo() {
+        // The XMP of a Cardboard Camera photo, without ProjectionType.
+        let exif = ExifData::new(json!({
+            "XMP": {
+                "XMP-GPano:UsePanoramaViewer": true,
+                "XMP-GPano:CroppedAreaImageWidthPixels": 9184,
+                "XMP-GPano:CroppedAreaImageHeightPixels": 1652,
+                "XMP-GPano:CroppedAreaLeftPixels": 0,
+                "XMP-GPano:CroppedAreaTopPixels": 1762,
+                "XMP-GPano:FullPanoWidthPixels": 9184,
+                "XMP-GPano:FullPanoHeightPixels": 4592,
+                "XMP-GPano:InitialViewHeadingDegrees": 180,
+                "XMP-GImage:ImageMimeType": "image/jpeg",
+                "XMP-GImage:ImageData": "(Binary data 1534232 bytes, use -b option to extract)",
+                "XMP-GAudio:AudioMimeType": "audio/mp4a-latm",
+                "XMP-GAudio:AudioData": "(Binary data 91236 bytes, use -b option to extract)"
+            }
+        }));
+        assert!(should_use_pano_viewer(&exif));
+        assert!(stereo_pano_evidence(&exif).is_some());
+        let pano = get_pano_view_info(&exif).unwrap();
+        assert!(pano.projection_assumed);
+        assert_eq!(pano.horizontal_coverage_deg, Some(360.0));
+    }
+
+    fn load_fixture(name: &str) -> ExifData {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("assets/pano360")
+            .join(name);
+        ExifData::new(RawExif::load(&path).unwrap().grouped)
+    }
+
+    #[test]
+    fn test_insta360_dual_fisheye_needs_stitching() {
+        let exif = load_fixture("insta360_dual_fisheye.json");
+        let pano = get_pano_view_info(&exif).unwrap();
+        assert_eq!(pano.projection_type.as_deref(), Some("dual-fisheye"));
+        assert!(pano.needs_stitching);
+        assert_eq!(pano.lens_count, Some(2));
+        // Not a photosphere: the viewer can't show the lens images.
+        assert!(!should_use_pano_viewer(&exif));
+        assert!(spherical_video_evidence(&exif).is_some());
+        // The GPS position is only in the container.
+        let gps = get_gps_coordinates(&exif).unwrap();
+        assert_eq!((gps.latitude, gps.longitude), (52.3791, 4.8994));
+    }
+
+    #[test]
+    fn test_gopro_360_needs_stitching() {
+        let exif = load_fixture("gopro_max_360.json");
+        let pano = get_pano_view_info(&exif).unwrap();
+        assert_eq!(pano.projection_type.as_deref(), Some("fisheye"));
+        assert!(pano.needs_stitching);
+        assert_eq!(pano.lens_count, Some(2));
+        assert!(!should_use_pano_viewer(&exif));
+        let gps = get_gps_coordinates(&exif).unwrap();
+        assert_eq!(gps.altitude, Some(2061.4));
+    }
+
+    #[test]
+    fn test_unstitched_overrides_use_panorama_viewer() {
+        let exif = ExifData::new(json!({
+            "XMP-GPano:UsePanoramaViewer": "True",
+            "XMP-GSpherical:Stitched": "False",
+            "XMP-GSpherical:ProjectionType": "equirectangular"
+        }));
+        assert!(!should_use_pano_viewer(&exif));
+        let pano = get_pano_view_info(&exif).unwrap();
+        assert!(pano.needs_stitching);
+        assert_eq!(pano.lens_count, None);
+
+        let stitched = ExifData::new(json!({ "ProjectionType": "equirectangular" }));
+        assert!(!get_pano_view_info(&stitched).unwrap().needs_stitching);
+    }
+
+    #[test]
+    fn test_embedded_image_without_pano_is_not_stereo() {
+        let exif = ExifData::new(json!({ "XMP-GImage:ImageData": "(Binary data 10 bytes)" }));
+        assert_eq!(stereo_pano_evidence(&exif), None);
     }
 }
@@ -0,0 +1,95 @@
+use crate::ExifData;
+use crate::features::error::MetadataError;
+use crate::features::mime::resolve_mime_type;
+use serde::{Deserialize, Serialize};
+
+/// The only tags read by a probe, so exiftool can skip everything else.
+pub const PROBE_EXIFTOOL_ARGS: [&str; 7] = [
+    "-n",
+    "-FileSize",
+    "-MIMEType",
+    "-FileType",
+    "-ImageWidth",
+    "-ImageHeight",
+    "-Duration",
+];
+
+/// Basic file properties, cheap enough to route a file before running the full analysis.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProbeResult {
+    pub mime_type: String,
+    pub width: Option<u64>,
+    pub height: Option<u64>,
+    pub duration: Option<f64>,
+    pub is_video: bool,
+    pub file_size: u64,
+}
+
+/// Builds a [`ProbeResult`]. Unlike the full metadata extraction, only the MIME type and file
+/// size are required, so files without dimensions (e.g. text files) can still be probed.
+///
+/// # Errors
+/// * If `FileSize` is missing, or both `MIMEType` and `FileType` are, see
+///   [`resolve_mime_type`].
+pub fn get_probe_result(exif: &ExifData) -> Result<ProbeResult, MetadataError> {
+    let mime_type = resolve_mime_type(exif)?;
+    Ok(ProbeResult {
+        is_video: mime_type.starts_with("video/"),
+        mime_type,
+        width: exif.get_u64("ImageWidth"),
+        height: exif.get_u64("ImageHeight"),
+        duration: exif.get_f64("Duration"),
+        file_size: exif.require_u64("FileSize")?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::features::mime::UNKNOWN_MIME_TYPE;
+    use serde_json::json;
+
+    #[test]
+    fn test_probe_without_dimensions() {
+        let exif = ExifData::new(json!({ "MIMEType": "text/plain", "FileSize": 12 }));
+        let probe = get_probe_result(&exif).unwrap();
+        assert_eq!(probe.mime_type, "text/plain");
+        assert_eq!(probe.file_size, 12);
+        assert!(probe.width.is_none());
+        assert!(!probe.is_video);
+    }
+
+    #[test]
+    fn test_probe_video() {
+        let exif = ExifData::new(json!({
+            "MIMEType": "video/webm",
+            "FileSize": 1_000_000,
+            "ImageWidth": 1280,
+            "ImageHeight": 720,
+            "Duration": 5.72
+        }));
+        let probe = get_probe_result(&exif).unwrap();
+        assert!(probe.is_video);
+        assert_eq!(probe.width, Some(1280));
+        assert_eq!(probe.height, Some(720));
+        assert_eq!(probe.duration, Some(5.72));
+    }
+
+    #[test]
+    fn test_probe_of_a_type_without_mime_type() {
+        let exif = ExifData::new(json!({ "FileType": "DPX", "FileSize": 12 }));
+        let probe = get_probe_result(&exif).unwrap();
+        assert_eq!(probe.mime_type, UNKNOWN_MIME_TYPE);
+        assert!(!probe.is_video);
+    }
+
+    #[test]
+    fn test_probe_requires_mime_type() {
+        let exif = ExifData::new(json!({ "FileSize": 12 }));
+        assert!(matches!(
+            get_probe_result(&exif),
+            Err(MetadataError::MissingRequiredField(field)) if field == "MIMEType"
+        ));
+    }
+}
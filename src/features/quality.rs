@@ -0,0 +1,232 @@
+//! Cheap image quality hints derived from capture settings, without decoding any pixels.
+//!
+//! Thresholds:
+//! * [`HIGH_ISO_THRESHOLD`]: ISO at or above this value is likely to show visible sensor noise.
+//! * [`EXTREME_EXPOSURE_COMPENSATION_EV`]: absolute exposure compensation at or above this many EV
+//!   suggests the scene was difficult to meter (strong back light, snow, night).
+//! * [`DIGITAL_ZOOM_THRESHOLD`]: a `DigitalZoomRatio` above this value means the image was cropped
+//!   and upscaled enough to lose noticeable detail.
+//! * [`MAX_APERTURE_TOLERANCE`]: the aperture is considered "wide open" when it is within this many
+//!   stops of the lens' `MaxApertureValue`, allowing for the rounding of the f-numbers.
+//! * [`DURATION_MISMATCH_THRESHOLD_SECONDS`]: container and stream durations further apart than
+//!   this point at a corrupt or re-wrapped video.
+//! * Handheld exposures use the reciprocal rule: an exposure longer than `1 / focal_length_35mm`
+//!   seconds risks motion blur, unless the metadata hints that a tripod was used.
+
+use crate::ExifData;
+use crate::features::metadata::{BasicMetadata, CameraSettings};
+use crate::units::Seconds;
+use serde::{Deserialize, Serialize};
+
+pub const HIGH_ISO_THRESHOLD: u64 = 3200;
+pub const EXTREME_EXPOSURE_COMPENSATION_EV: f64 = 2.0;
+pub const DIGITAL_ZOOM_THRESHOLD: f64 = 1.2;
+pub const MAX_APERTURE_TOLERANCE: f64 = 0.1;
+pub const DURATION_MISMATCH_THRESHOLD_SECONDS: f64 = 1.0;
+
+/// A hint that the image may have a quality problem, based purely on its metadata.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase", rename_all_fields = "camelCase")]
+pub enum QualityHint {
+    HighIsoNoise {
+        iso: u64,
+    },
+    LongHandheldExposure {
+        exposure_time: f64,
+        focal_length_35mm: f64,
+    },
+    ExtremeExposureCompensation(f64),
+    LensAtMaxAperture,
+    DigitalZoomUsed(f64),
+    /// Container and stream durations disagree by this many seconds.
+    DurationMismatch(f64),
+    /// A video that reports a duration of zero, e.g. a wrapper around a single frame.
+    ZeroDurationVideo,
+}
+
+/// Tags that some cameras fill with a scene/shooting mode, which may mention a tripod.
+const TRIPOD_HINT_TAGS: [&str; 3] = ["SceneMode", "ShootingMode", "ImageStabilization"];
+
+fn has_tripod_hint(exif: &ExifData) -> bool {
+    TRIPOD_HINT_TAGS.iter().any(|tag| {
+        exif.get_str(tag)
+            .is_some_and(|s| s.to_lowercase().contains("tripod"))
+    })
+}
+
+/// The number of stops an f-number is from f/1.
+fn stops(f_number: f64) -> f64 {
+    2.0 * f_number.log2()
+}
+
+pub fn get_quality_hints(
+    basic: &BasicMetadata,
+    camera: &CameraSettings,
+    exif: &ExifData,
+) -> Vec<QualityHint> {
+    let mut hints = Vec::new();
+
+    if let Some(iso) = camera.iso
+        && iso >= HIGH_ISO_THRESHOLD
+    {
+        hints.push(QualityHint::HighIsoNoise { iso });
+    }
+
+    if let (Some(exposure_time), Some(focal_length_35mm)) =
+        (camera.exposure_time, camera.focal_length_in_35mm)
+        && focal_length_35mm > 0.0
+        && exposure_time > 1.0 / focal_length_35mm
+        && !has_tripod_hint(exif)
+    {
+        hints.push(QualityHint::LongHandheldExposure {
+            exposure_time,
+            focal_length_35mm,
+        });
+    }
+
+    if let Some(compensation) = camera.exposure_compensation
+        && compensation.abs() >= EXTREME_EXPOSURE_COMPENSATION_EV
+    {
+        hints.push(QualityHint::ExtremeExposureCompensation(compensation));
+    }
+
+    if let (Some(aperture), Some(max_aperture)) =
+        (camera.aperture, exif.get_f64("MaxApertureValue"))
+        && (stops(aperture) - stops(max_aperture)).abs() <= MAX_APERTURE_TOLERANCE
+    {
+        hints.push(QualityHint::LensAtMaxAperture);
+    }
+
+    if let Some(zoom) = camera.digital_zoom_ratio
+        && zoom > DIGITAL_ZOOM_THRESHOLD
+    {
+        hints.push(QualityHint::DigitalZoomUsed(zoom));
+    }
+
+    if let Some(mismatch) = basic.duration_mismatch
+        && mismatch > DURATION_MISMATCH_THRESHOLD_SECONDS
+    {
+        hints.push(QualityHint::DurationMismatch(mismatch));
+    }
+
+    if exif.is_video() && basic.duration == Some(Seconds(0.0)) {
+        hints.push(QualityHint::ZeroDurationVideo);
+    }
+
+    hints
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::features::metadata::get_metadata;
+    use serde_json::{Value, json};
+
+    fn hints_for(tags: Value) -> Vec<QualityHint> {
+        hints_for_mime(tags, "image/jpeg")
+    }
+
+    fn hints_for_mime(mut tags: Value, mime_type: &str) -> Vec<QualityHint> {
+        let base = json!({
+            "ImageWidth": 4000, "ImageHeight": 3000, "MIMEType": mime_type, "FileSize": 1024
+        });
+        for (k, v) in base.as_object().unwrap() {
+            tags[k] = v.clone();
+        }
+        let exif = ExifData::new(tags);
+        let (basic, camera) = get_metadata(&exif).unwrap();
+        get_quality_hints(&basic, &camera, &exif)
+    }
+
+    #[test]
+    fn test_high_iso_around_threshold() {
+        assert_eq!(
+            hints_for(json!({ "ISO": 3200 })),
+            vec![QualityHint::HighIsoNoise { iso: 3200 }]
+        );
+        assert!(hints_for(json!({ "ISO": 3199 })).is_empty());
+    }
+
+    #[test]
+    fn test_long_handheld_exposure_around_threshold() {
+        assert_eq!(
+            hints_for(json!({ "ExposureTime": 0.05, "FocalLengthIn35mmFormat": 50.0 })),
+            vec![QualityHint::LongHandheldExposure {
+                exposure_time: 0.05,
+                focal_length_35mm: 50.0
+            }]
+        );
+        assert!(
+            hints_for(json!({ "ExposureTime": 0.02, "FocalLengthIn35mmFormat": 50.0 })).is_empty()
+        );
+    }
+
+    #[test]
+    fn test_long_exposure_with_tripod_hint_is_ignored() {
+        let hints = hints_for(json!({
+            "ExposureTime": 2.0,
+            "FocalLengthIn35mmFormat": 24.0,
+            "SceneMode": "Tripod Night"
+        }));
+        assert!(hints.is_empty());
+    }
+
+    #[test]
+    fn test_extreme_exposure_compensation_around_threshold() {
+        assert_eq!(
+            hints_for(json!({ "ExposureCompensation": -2.0 })),
+            vec![QualityHint::ExtremeExposureCompensation(-2.0)]
+        );
+        assert!(hints_for(json!({ "ExposureCompensation": 1.7 })).is_empty());
+    }
+
+    #[test]
+    fn test_lens_at_max_aperture() {
+        assert_eq!(
+            hints_for(json!({ "FNumber": 1.68, "MaxApertureValue": 1.681_792_83 })),
+            vec![QualityHint::LensAtMaxAperture]
+        );
+        assert!(hints_for(json!({ "FNumber": 2.8, "MaxApertureValue": 1.8 })).is_empty());
+        // A difference in f-number weighs less at narrow apertures.
+        assert_eq!(
+            hints_for(json!({ "FNumber": 5.6, "MaxApertureValue": 5.7 })),
+            vec![QualityHint::LensAtMaxAperture]
+        );
+        assert!(hints_for(json!({ "FNumber": 1.4, "MaxApertureValue": 1.3 })).is_empty());
+    }
+
+    #[test]
+    fn test_digital_zoom_around_threshold() {
+        assert_eq!(
+            hints_for(json!({ "DigitalZoomRatio": 2.0 })),
+            vec![QualityHint::DigitalZoomUsed(2.0)]
+        );
+        assert!(hints_for(json!({ "DigitalZoomRatio": 1.2 })).is_empty());
+        // Many cameras write 0 when no digital zoom was used.
+        assert!(hints_for(json!({ "DigitalZoomRatio": 0 })).is_empty());
+    }
+
+    #[test]
+    fn test_zero_duration_video_wrapper() {
+        let hints = hints_for_mime(json!({ "Duration": 0 }), "video/mp4");
+        assert_eq!(hints, vec![QualityHint::ZeroDurationVideo]);
+    }
+
+    #[test]
+    fn test_small_duration_mismatch_is_not_flagged() {
+        let hints = hints_for_mime(
+            json!({ "Duration": 10.5, "TrackDuration": 10.0 }),
+            "video/mp4",
+        );
+        assert!(hints.is_empty());
+    }
+
+    #[test]
+    fn test_large_duration_mismatch_is_flagged() {
+        let hints = hints_for_mime(
+            json!({ "Duration": 20.0, "TrackDuration": 10.0, "MediaDuration": 10.0 }),
+            "video/mp4",
+        );
+        assert_eq!(hints, vec![QualityHint::DurationMismatch(10.0)]);
+    }
+}
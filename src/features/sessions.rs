@@ -0,0 +1,263 @@
+//! Groups a batch of results into capture sessions, such as a wedding or a hike: runs of photos
+//! from the same camera without long breaks in between.
+
+use crate::structs::MediaMetadata;
+use crate::time::structs::{CONFIDENCE_LOW, TimeInfo};
+use chrono::{DateTime, TimeDelta, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Photos taken by one camera, each within `max_gap` of the one before.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Session {
+    /// The time of the first photo. Times without a known timezone are treated as UTC.
+    pub start_utc: DateTime<Utc>,
+    /// The time of the last photo.
+    pub end_utc: DateTime<Utc>,
+    /// `make|model|serial`, with an empty serial when it is unknown.
+    pub camera_key: String,
+    /// Indices into the analyzed results, in time order.
+    pub member_indices: Vec<usize>,
+    pub photo_count: usize,
+    /// Whether any of the photos has a GPS location.
+    pub has_gps: bool,
+}
+
+/// The result of [`detect_sessions`].
+#[derive(Debug, Clone, PartialEq, Eq, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionDetection {
+    /// Sessions ordered by start time.
+    pub sessions: Vec<Session>,
+    /// Indices of results that are in no session: those without a known camera, and those with
+    /// a low confidence time unless `include_low_confidence` is set.
+    pub unsessioned: Vec<usize>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SessionOptions {
+    /// A longer gap between consecutive photos of a camera starts a new session.
+    pub max_gap: TimeDelta,
+    /// Low confidence times (file dates, filenames) are often far off, and would split or
+    /// stretch sessions they don't belong to. They are left out unless this is set.
+    pub include_low_confidence: bool,
+}
+
+impl SessionOptions {
+    #[must_use]
+    pub const fn new(max_gap: TimeDelta) -> Self {
+        Self {
+            max_gap,
+            include_low_confidence: false,
+        }
+    }
+}
+
+/// Identifies the camera of a result, `None` when neither make nor model is known.
+fn camera_key(result: &MediaMetadata) -> Option<String> {
+    let camera = &result.camera;
+    if camera.camera_make.is_none() && camera.camera_model.is_none() {
+        return None;
+    }
+    Some(format!(
+        "{}|{}|{}",
+        camera.camera_make.as_deref().unwrap_or_default().trim(),
+        camera.camera_model.as_deref().unwrap_or_default().trim(),
+        camera
+            .camera_serial_number
+            .as_deref()
+            .unwrap_or_default()
+            .trim(),
+    ))
+}
+
+/// The time to order results by: UTC when known, otherwise the local time as if it were UTC.
+pub fn ordering_time(time: &TimeInfo) -> DateTime<Utc> {
+    time.datetime_utc
+        .unwrap_or_else(|| time.datetime_local.and_utc())
+}
+
+/// Splits `results` into sessions per camera.
+///
+/// A new session starts whenever consecutive photos are more than `max_gap` apart. Low
+/// confidence times are left out, see [`detect_sessions_with_options`] to include them.
+#[must_use]
+pub fn detect_sessions(results: &[MediaMetadata], max_gap: TimeDelta) -> SessionDetection {
+    detect_sessions_with_options(results, &SessionOptions::new(max_gap))
+}
+
+/// Like [`detect_sessions`], with all options.
+#[must_use]
+pub fn detect_sessions_with_options(
+    results: &[MediaMetadata],
+    options: &SessionOptions,
+) -> SessionDetection {
+    let mut unsessioned = Vec::new();
+    let mut by_camera: BTreeMap<String, Vec<(DateTime<Utc>, usize)>> = BTreeMap::new();
+    for (index, result) in results.iter().enumerate() {
+        let low_confidence = result.time.source_details.confidence == CONFIDENCE_LOW;
+        match camera_key(result) {
+            Some(key) if options.include_low_confidence || !low_confidence => {
+                by_camera
+                    .entry(key)
+                    .or_default()
+                    .push((ordering_time(&result.time), index));
+            }
+            _ => unsessioned.push(index),
+        }
+    }
+
+    let mut sessions = Vec::new();
+    for (camera_key, mut photos) in by_camera {
+        photos.sort_unstable();
+        let mut current: Vec<(DateTime<Utc>, usize)> = Vec::new();
+        for photo in photos {
+            if let Some((last_time, _)) = current.last()
+                && photo.0 - *last_time > options.max_gap
+            {
+                sessions.push(to_session(&camera_key, &current, results));
+                current.clear();
+            }
+            current.push(photo);
+        }
+        if !current.is_empty() {
+            sessions.push(to_session(&camera_key, &current, results));
+        }
+    }
+    sessions.sort_by(|a, b| {
+        a.start_utc
+            .cmp(&b.start_utc)
+            .then_with(|| a.camera_key.cmp(&b.camera_key))
+    });
+
+    SessionDetection {
+        sessions,
+        unsessioned,
+    }
+}
+
+/// Builds a session from a non-empty, time ordered run of photos.
+fn to_session(
+    camera_key: &str,
+    photos: &[(DateTime<Utc>, usize)],
+    results: &[MediaMetadata],
+) -> Session {
+    let member_indices: Vec<usize> = photos.iter().map(|(_, index)| *index).collect();
+    Session {
+        start_utc: photos.first().map(|(time, _)| *time).unwrap_or_default(),
+        end_utc: photos.last().map(|(time, _)| *time).unwrap_or_default(),
+        camera_key: camera_key.to_string(),
+        photo_count: member_indices.len(),
+        has_gps: member_indices.iter().any(|i| results[*i].gps.is_some()),
+        member_indices,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::MediaMetadataFixture;
+    use serde_json::json;
+
+    /// A Canon taken by `model` at `time` ("HH:MM" on 2024-06-01) in UTC+02:00.
+    fn fixture(model: &str, time: &str) -> MediaMetadataFixture {
+        MediaMetadataFixture::photo()
+            .with_tag("Camera", "Model", model)
+            .with_local_time(&format!("2024-06-01T{time}:00"))
+    }
+
+    fn photo(model: &str, time: &str) -> MediaMetadata {
+        fixture(model, time).build()
+    }
+
+    fn indices(detection: &SessionDetection) -> Vec<Vec<usize>> {
+        detection
+            .sessions
+            .iter()
+            .map(|s| s.member_indices.clone())
+            .collect()
+    }
+
+    #[test]
+    fn test_interleaved_cameras_get_separate_sessions() {
+        let results = vec![
+            photo("EOS R5", "10:00"),
+            photo("EOS R6", "10:05"),
+            photo("EOS R5", "10:10"),
+            photo("EOS R6", "10:15"),
+            photo("EOS R5", "10:20"),
+        ];
+        let detection = detect_sessions(&results, TimeDelta::minutes(30));
+        assert_eq!(indices(&detection), vec![vec![0, 2, 4], vec![1, 3]]);
+        assert!(detection.unsessioned.is_empty());
+
+        let first = &detection.sessions[0];
+        assert_eq!(first.camera_key, "Canon|EOS R5|");
+        assert_eq!(first.photo_count, 3);
+        assert_eq!(first.start_utc.to_rfc3339(), "2024-06-01T08:00:00+00:00");
+        assert_eq!(first.end_utc.to_rfc3339(), "2024-06-01T08:20:00+00:00");
+        assert!(!first.has_gps);
+    }
+
+    #[test]
+    fn test_long_gap_splits_a_session() {
+        let results = vec![
+            photo("EOS R5", "14:00"),
+            photo("EOS R5", "09:00"),
+            photo("EOS R5", "09:20"),
+            photo("EOS R5", "14:25"),
+        ];
+        let detection = detect_sessions(&results, TimeDelta::minutes(30));
+        assert_eq!(indices(&detection), vec![vec![1, 2], vec![0, 3]]);
+
+        // A gap of exactly `max_gap` does not split.
+        let detection = detect_sessions(&results, TimeDelta::hours(4) + TimeDelta::minutes(40));
+        assert_eq!(indices(&detection), vec![vec![1, 2, 0, 3]]);
+    }
+
+    #[test]
+    fn test_serial_numbers_tell_cameras_apart() {
+        let mut results = vec![photo("EOS R5", "10:00"), photo("EOS R5", "10:01")];
+        results[1].camera.camera_serial_number = Some("123456".to_string());
+        let detection = detect_sessions(&results, TimeDelta::minutes(30));
+        assert_eq!(indices(&detection), vec![vec![0], vec![1]]);
+    }
+
+    #[test]
+    fn test_low_confidence_outliers_are_unsessioned_by_default() {
+        let outlier = fixture("EOS R5", "10:10").without_offset().build();
+        assert_eq!(outlier.time.source_details.confidence, CONFIDENCE_LOW);
+        let results = vec![photo("EOS R5", "10:00"), outlier, photo("EOS R5", "10:20")];
+
+        let detection = detect_sessions(&results, TimeDelta::minutes(30));
+        assert_eq!(indices(&detection), vec![vec![0, 2]]);
+        assert_eq!(detection.unsessioned, vec![1]);
+
+        let options = SessionOptions {
+            include_low_confidence: true,
+            ..SessionOptions::new(TimeDelta::hours(3))
+        };
+        let detection = detect_sessions_with_options(&results, &options);
+        assert_eq!(indices(&detection), vec![vec![0, 2, 1]]);
+        assert!(detection.unsessioned.is_empty());
+    }
+
+    #[test]
+    fn test_unknown_camera_and_gps() {
+        let with_gps = fixture("EOS R5", "10:00")
+            .with_gps(52.379_189, 4.899_431)
+            .build();
+        assert!(with_gps.gps.is_some());
+        let no_camera = MediaMetadataFixture::from_exif(json!({
+            "ImageWidth": 4000, "ImageHeight": 3000, "MIMEType": "image/png", "FileSize": 1024,
+            "Time": { "DateTimeOriginal": "2024:06:01 10:05:00", "OffsetTimeOriginal": "+02:00" }
+        }))
+        .build();
+
+        let detection = detect_sessions(&[with_gps, no_camera], TimeDelta::minutes(30));
+        assert_eq!(indices(&detection), vec![vec![0]]);
+        assert!(detection.sessions[0].has_gps);
+        assert_eq!(detection.unsessioned, vec![1]);
+    }
+}
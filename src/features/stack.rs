@@ -0,0 +1,179 @@
+//! Multi-image stacks: files holding several full-size images, like exposure stacks and
+//! before/after edits in HEICs from Apple and Huawei phones, or multi-frame MPF JPEGs.
+//!
+//! `exiftool -ee -g3` reports each embedded image as a sub-document group (`Doc1`, `Doc2`, ...)
+//! next to the `Main` document of the primary image. [`ExifData`] prefers the primary image's
+//! tags, so the capture details describe the image that is displayed.
+
+use crate::ExifData;
+use crate::features::metadata::{CameraSettings, get_camera_settings};
+use serde_json::Value;
+
+/// The `exiftool` arguments that report every image of a stack as its own document.
+pub const STACK_EXIFTOOL_ARGS: [&str; 3] = ["-n", "-ee", "-g3"];
+
+/// Tags of which at least one must be present for a sub-document to count as a stack member,
+/// so embedded thumbnails and gain maps without exposure data are left out.
+const EXPOSURE_TAGS: [&str; 3] = ["ExposureTime", "ISO", "FNumber"];
+
+/// The number of a sub-document group like `Doc2`, or `None` for other groups.
+pub fn sub_document_number(group: &str) -> Option<usize> {
+    group.strip_prefix("Doc")?.parse().ok()
+}
+
+/// The number of full-size images in an MPF list: the primary image and multi-frame images
+/// (panorama, disparity and multi-angle). Thumbnails, gain maps and depth maps don't count.
+fn mpf_frame_count(exif: &ExifData) -> usize {
+    let types: Vec<u64> = match exif.get_value("MPImageType") {
+        Some(Value::Array(values)) => values.iter().filter_map(Value::as_u64).collect(),
+        Some(value) => value.as_u64().into_iter().collect(),
+        None => Vec::new(),
+    };
+    types
+        .iter()
+        .filter(|image_type| {
+            let image_type = *image_type & 0x00FF_FFFF;
+            image_type == 0x03_0000 || image_type & 0x00FF_0000 == 0x02_0000
+        })
+        .count()
+}
+
+/// The number of sub-documents with exposure data, plus the main document.
+fn sub_document_count(exif: &ExifData) -> usize {
+    let Some(groups) = exif.inner().as_object() else {
+        return 0;
+    };
+    let members = groups
+        .iter()
+        .filter(|(group, tags)| sub_document_number(group).is_some() && has_exposure_data(tags))
+        .count();
+    if members == 0 { 0 } else { members + 1 }
+}
+
+fn has_exposure_data(tags: &Value) -> bool {
+    EXPOSURE_TAGS.iter().any(|tag| tags.get(tag).is_some())
+}
+
+/// The number of full-size images in a multi-image container, from the MPF image list,
+/// `ImageCount` or the sub-documents of `exiftool -ee` output. `None` for single images.
+///
+/// `ImageCount` is only read from HEIC and HEIF files, as Sony maker notes use the same name for
+/// the shutter count.
+pub fn stack_image_count(exif: &ExifData) -> Option<u32> {
+    let is_heif = exif
+        .get_str("MIMEType")
+        .is_some_and(|mime| mime.starts_with("image/hei"));
+    let image_count = exif
+        .get_u64("ImageCount")
+        .filter(|_| is_heif)
+        .and_then(|count| usize::try_from(count).ok())
+        .unwrap_or(0);
+    let count = mpf_frame_count(exif)
+        .max(image_count)
+        .max(sub_document_count(exif));
+    u32::try_from(count).ok().filter(|count| *count > 1)
+}
+
+/// The capture details of each image in `exiftool -ee -g3` output, in document order. The
+/// primary image, from the `Main` group, is member 0 and `DocN` is member N. Documents without
+/// exposure data are left out.
+pub fn stack_members(value: &Value) -> Vec<(usize, CameraSettings)> {
+    let Some(groups) = value.as_object() else {
+        return Vec::new();
+    };
+    let mut members: Vec<(usize, CameraSettings)> = groups
+        .iter()
+        .filter_map(|(group, tags)| {
+            let index = if group == "Main" {
+                0
+            } else {
+                sub_document_number(group)?
+            };
+            has_exposure_data(tags)
+                .then(|| (index, get_camera_settings(&ExifData::new(tags.clone()))))
+        })
+        .collect();
+    members.sort_by_key(|(index, _)| *index);
+    members
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    /// A Huawei HEIC with an edited image and the original, as `exiftool -n -ee -g3` reports it.
+    fn huawei_stack() -> Value {
+        json!({
+            "SourceFile": "IMG_20240601_181502.heic",
+            "Main": {
+                "MIMEType": "image/heic",
+                "Make": "HUAWEI",
+                "Model": "ELS-NX9",
+                "ExposureTime": 0.01,
+                "ISO": 64,
+                "FNumber": 1.8
+            },
+            "Doc1": {
+                "Make": "HUAWEI",
+                "Model": "ELS-NX9",
+                "ExposureTime": 0.04,
+                "ISO": 400,
+                "FNumber": 1.8
+            },
+            "Doc2": { "ImageWidth": 512, "ImageHeight": 384 }
+        })
+    }
+
+    #[test]
+    fn test_huawei_stack_prefers_primary_image() {
+        let exif = ExifData::new(huawei_stack());
+        assert_eq!(stack_image_count(&exif), Some(2));
+        // `Doc1` sorts before `Main`, but the displayed image's exposure is the one used.
+        let camera = get_camera_settings(&exif);
+        assert_eq!(camera.exposure_time, Some(0.01));
+        assert_eq!(camera.iso, Some(64));
+
+        let members = stack_members(&huawei_stack());
+        let exposures: Vec<(usize, Option<f64>, Option<u64>)> = members
+            .iter()
+            .map(|(index, camera)| (*index, camera.exposure_time, camera.iso))
+            .collect();
+        assert_eq!(
+            exposures,
+            vec![(0, Some(0.01), Some(64)), (1, Some(0.04), Some(400))]
+        );
+    }
+
+    #[test]
+    fn test_mpf_stack() {
+        // A multi-angle MPF JPEG of three frames and a large thumbnail, as `exiftool -n -g2`
+        // reports it.
+        let exif = ExifData::new(json!({
+            "Image": {
+                "MPFVersion": "0100",
+                "NumberOfImages": 4,
+                "MPImageType": [0x02_0003 | 0x2000_0000, 0x02_0003, 0x02_0003, 0x01_0001]
+            }
+        }));
+        assert_eq!(stack_image_count(&exif), Some(3));
+
+        // An Ultra HDR JPEG: the primary image and its gain map are not a stack.
+        let exif = ExifData::new(json!({ "Image": { "MPImageType": [0x03_0000, 0] } }));
+        assert_eq!(stack_image_count(&exif), None);
+        assert_eq!(stack_image_count(&ExifData::new(json!({}))), None);
+    }
+
+    #[test]
+    fn test_image_count_only_counts_for_heif() {
+        let heic = ExifData::new(
+            json!({ "File": { "MIMEType": "image/heic" }, "Image": { "ImageCount": 2 } }),
+        );
+        assert_eq!(stack_image_count(&heic), Some(2));
+        // Sony's shutter count.
+        let sony = ExifData::new(
+            json!({ "File": { "MIMEType": "image/jpeg" }, "Camera": { "ImageCount": 5231 } }),
+        );
+        assert_eq!(stack_image_count(&sony), None);
+    }
+}
@@ -0,0 +1,135 @@
+//! Detects files whose metadata was removed, like by a privacy stripper, leaving only the tags
+//! of the file system and the image data itself.
+
+use crate::ExifData;
+use crate::notes::{AnalysisNote, CODE_METADATA_STRIPPED, MODULE_METADATA};
+use crate::time::DEFAULT_TIME_TAG_PRIORITY;
+use crate::time::TimeSource;
+use crate::time::structs::TimeInfo;
+use serde_json::json;
+
+/// Formats that cameras write with capture metadata. Others, like PNG screenshots, often never
+/// had any, so they are never called stripped.
+const CAMERA_MIME_TYPES: [&str; 6] = [
+    "image/jpeg",
+    "image/heic",
+    "image/heif",
+    "image/tiff",
+    "video/mp4",
+    "video/quicktime",
+];
+
+/// Whether the file's metadata was stripped: it has no times but the file system's, no camera
+/// tags and no maker notes. `None` for formats cameras don't write, like PNG.
+pub fn is_metadata_stripped(exif: &ExifData, mime_type: &str) -> Option<bool> {
+    // Raw formats are "image/x-canon-cr2" and the like.
+    if !CAMERA_MIME_TYPES.contains(&mime_type) && !mime_type.starts_with("image/x-") {
+        return None;
+    }
+    let has_capture_time = exif
+        .group_tag_names("Time")
+        .iter()
+        .any(|tag| !tag.starts_with("File"))
+        || DEFAULT_TIME_TAG_PRIORITY
+            .iter()
+            .any(|tag| exif.get_value(tag).is_some());
+    let has_camera = !exif.group_tag_names("Camera").is_empty()
+        || exif.get_value("Make").is_some()
+        || exif.get_value("Model").is_some();
+    let has_maker_notes = exif
+        .tag_names()
+        .iter()
+        .any(|tag| tag.starts_with("MakerNote"));
+    Some(!has_capture_time && !has_camera && !has_maker_notes)
+}
+
+/// A note explaining the low confidence of the capture time of a stripped file.
+pub fn metadata_stripped_notes(stripped: Option<bool>, time: &TimeInfo) -> Vec<AnalysisNote> {
+    if stripped != Some(true) {
+        return Vec::new();
+    }
+    let source = time.source_details.time_source_display();
+    let message = match time.source_details.time_source {
+        TimeSource::FileModifyDate | TimeSource::FileCreateDate | TimeSource::FileAccessDate => {
+            format!("the metadata was stripped, the capture time is the file system's {source}")
+        }
+        _ => format!("the metadata was stripped, the capture time is read from {source}"),
+    };
+    vec![
+        AnalysisNote::warning(MODULE_METADATA, CODE_METADATA_STRIPPED, message)
+            .with_data(json!({ "timeSource": time.source_details.time_source })),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::time::get_time_info;
+    use crate::time::structs::TimeOptions;
+
+    #[test]
+    fn test_file_times_only_is_stripped() {
+        let exif = ExifData::new(json!({
+            "Time": {
+                "FileModifyDate": "2024:07:10 14:00:00+02:00",
+                "FileAccessDate": "2024:07:11 09:00:00+02:00"
+            },
+            "Image": { "ImageWidth": 640, "ImageHeight": 480, "JFIFVersion": "1 1" },
+            "Other": { "FileSize": 72786, "MIMEType": "image/jpeg" }
+        }));
+        assert_eq!(is_metadata_stripped(&exif, "image/jpeg"), Some(true));
+        assert_eq!(is_metadata_stripped(&exif, "image/x-canon-cr2"), Some(true));
+        assert_eq!(is_metadata_stripped(&exif, "image/png"), None);
+    }
+
+    #[test]
+    fn test_note_names_the_time_source() {
+        let time = |json: serde_json::Value| {
+            get_time_info(&ExifData::new(json), None, &TimeOptions::default()).unwrap()
+        };
+        let file_time = time(json!({ "Time": { "FileModifyDate": "2024:07:10 14:00:00+02:00" } }));
+        let notes = metadata_stripped_notes(Some(true), &file_time);
+        assert_eq!(
+            notes[0].message,
+            "the metadata was stripped, the capture time is the file system's FileModifyDate"
+        );
+
+        let file_name = time(json!({ "Other": { "FileName": "IMG_20240710_140000.jpg" } }));
+        let notes = metadata_stripped_notes(Some(true), &file_name);
+        assert_eq!(
+            notes[0].message,
+            "the metadata was stripped, the capture time is read from FileName"
+        );
+        assert!(metadata_stripped_notes(Some(false), &file_name).is_empty());
+    }
+
+    #[test]
+    fn test_partially_stripped_is_not_stripped() {
+        // The camera is still known, only the times were removed.
+        let camera_only = ExifData::new(json!({
+            "Time": { "FileModifyDate": "2024:07:10 14:00:00+02:00" },
+            "Camera": { "Make": "Canon", "Model": "Canon EOS 5D Mark IV" }
+        }));
+        assert_eq!(
+            is_metadata_stripped(&camera_only, "image/jpeg"),
+            Some(false)
+        );
+
+        let time_only = ExifData::new(json!({
+            "Time": {
+                "FileModifyDate": "2024:07:10 14:00:00+02:00",
+                "DateTimeOriginal": "2024:07:10 13:59:00"
+            }
+        }));
+        assert_eq!(is_metadata_stripped(&time_only, "image/jpeg"), Some(false));
+
+        let maker_notes = ExifData::new(json!({
+            "Time": { "FileModifyDate": "2024:07:10 14:00:00+02:00" },
+            "Other": { "MakerNoteVersion": "0100" }
+        }));
+        assert_eq!(
+            is_metadata_stripped(&maker_notes, "image/jpeg"),
+            Some(false)
+        );
+    }
+}
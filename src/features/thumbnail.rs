@@ -0,0 +1,137 @@
+use regex::Regex;
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
+
+// The pattern is a valid literal, so compiling it cannot fail.
+#[allow(clippy::unwrap_used)]
+static FRAME_FILENAME_PATTERN: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?i)^(?:frame|thumb|thumbnail|out|img)[_-]?(\d+)\.(?:jpe?g|png|webp|bmp)$")
+        .unwrap()
+});
+
+/// A frame extracted from a video, to be used as its thumbnail.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ThumbnailSource {
+    pub path: PathBuf,
+    /// Where in the video the frame was taken, in seconds. When `None`, it is inferred from
+    /// ffmpeg style frame filenames such as `frame_00012.jpg`.
+    pub timestamp_s: Option<f64>,
+}
+
+impl ThumbnailSource {
+    #[must_use]
+    pub fn new(path: impl Into<PathBuf>, timestamp_s: Option<f64>) -> Self {
+        Self {
+            path: path.into(),
+            timestamp_s,
+        }
+    }
+}
+
+impl From<PathBuf> for ThumbnailSource {
+    fn from(path: PathBuf) -> Self {
+        Self::new(path, None)
+    }
+}
+
+impl From<&Path> for ThumbnailSource {
+    fn from(path: &Path) -> Self {
+        Self::new(path, None)
+    }
+}
+
+/// Parses the frame number out of ffmpeg style output names (`frame_00012.jpg`, `out003.png`).
+fn frame_number_from_filename(path: &Path) -> Option<u64> {
+    let filename = path.file_name()?.to_str()?;
+    FRAME_FILENAME_PATTERN
+        .captures(filename)
+        .and_then(|caps| caps.get(1))
+        .and_then(|m| m.as_str().parse().ok())
+}
+
+/// Determines where in the video the thumbnail frame was taken, in seconds.
+///
+/// An explicit timestamp wins. Otherwise it is inferred from the frame number in the filename,
+/// which ffmpeg starts at 1 by default, so `frame_00001.jpg` is at 0s. Timestamps outside of
+/// `0..=duration` are clamped, with a warning.
+pub fn resolve_thumbnail_timestamp(
+    source: &ThumbnailSource,
+    video_fps: Option<f64>,
+    duration: Option<f64>,
+) -> Option<f64> {
+    #[allow(clippy::cast_precision_loss)]
+    let timestamp = source.timestamp_s.or_else(|| {
+        let fps = video_fps.filter(|fps| *fps > 0.0)?;
+        let frame = frame_number_from_filename(&source.path)?;
+        Some(frame.saturating_sub(1) as f64 / fps)
+    })?;
+
+    let max = duration.unwrap_or(f64::INFINITY).max(0.0);
+    let clamped = timestamp.clamp(0.0, max);
+    if clamped != timestamp {
+        log::warn!(
+            "Thumbnail timestamp {timestamp}s of {} is outside of the video duration, clamping to {clamped}s",
+            source.path.display()
+        );
+    }
+    Some(clamped)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_explicit_timestamp_wins() {
+        let source = ThumbnailSource::new("frame_00012.jpg", Some(3.5));
+        assert_eq!(
+            resolve_thumbnail_timestamp(&source, Some(30.0), Some(10.0)),
+            Some(3.5)
+        );
+    }
+
+    #[test]
+    fn test_timestamp_inferred_from_frame_name() {
+        let source = ThumbnailSource::from(Path::new("/tmp/frames/frame_00031.jpg"));
+        assert_eq!(
+            resolve_thumbnail_timestamp(&source, Some(30.0), Some(10.0)),
+            Some(1.0)
+        );
+        let source = ThumbnailSource::from(PathBuf::from("out001.png"));
+        assert_eq!(
+            resolve_thumbnail_timestamp(&source, Some(24.0), None),
+            Some(0.0)
+        );
+    }
+
+    #[test]
+    fn test_no_timestamp_without_fps_or_frame_name() {
+        let source = ThumbnailSource::from(Path::new("frame_00031.jpg"));
+        assert_eq!(resolve_thumbnail_timestamp(&source, None, Some(10.0)), None);
+        let source = ThumbnailSource::from(Path::new("poster.jpg"));
+        assert_eq!(
+            resolve_thumbnail_timestamp(&source, Some(30.0), Some(10.0)),
+            None
+        );
+    }
+
+    #[test]
+    fn test_out_of_range_timestamp_is_clamped() {
+        let source = ThumbnailSource::new("poster.jpg", Some(42.0));
+        assert_eq!(
+            resolve_thumbnail_timestamp(&source, Some(30.0), Some(10.0)),
+            Some(10.0)
+        );
+        let source = ThumbnailSource::new("poster.jpg", Some(-1.0));
+        assert_eq!(
+            resolve_thumbnail_timestamp(&source, None, Some(10.0)),
+            Some(0.0)
+        );
+        // A frame past the end of the video, e.g. when the fps was misreported.
+        let source = ThumbnailSource::from(Path::new("frame_01000.jpg"));
+        assert_eq!(
+            resolve_thumbnail_timestamp(&source, Some(30.0), Some(10.0)),
+            Some(10.0)
+        );
+    }
+}
@@ -0,0 +1,205 @@
+//! Assigns timezones to results without one, from photos with GPS taken around the same time.
+//!
+//! On a trip, phone photos usually have GPS while those of a dedicated camera don't. On its own,
+//! a camera photo gets a guessed offset or none at all, but a phone photo taken minutes earlier
+//! pins down the timezone.
+
+use crate::features::dedup::get_dedup_key;
+use crate::structs::MediaMetadata;
+use crate::time::GUESSED_ZONE_SOURCE_PREFIX;
+use crate::time::structs::{CONFIDENCE_HIGH, CONFIDENCE_MEDIUM, TimeZoneInfo};
+use chrono::{LocalResult, NaiveDateTime, Offset, TimeDelta, TimeZone, Utc};
+use chrono_tz::Tz;
+use std::str::FromStr;
+
+/// The timezone source of a zone taken from nearby photos.
+pub const INFERRED_ZONE_SOURCE: &str = "Inferred from nearby photos";
+
+/// The zone of a result whose timezone was determined from its GPS location.
+fn gps_zone(result: &MediaMetadata) -> Option<Tz> {
+    if result.gps.is_none() || result.time.source_details.confidence != CONFIDENCE_HIGH {
+        return None;
+    }
+    Tz::from_str(&result.time.timezone.as_ref()?.name).ok()
+}
+
+/// Whether the timezone of a result may be replaced: it is not `High` confidence, and it has
+/// no timezone or one guessed from a file system time. Offsets from a UTC tag or the itinerary
+/// are kept.
+fn needs_zone(result: &MediaMetadata) -> bool {
+    result.time.source_details.confidence != CONFIDENCE_HIGH
+        && result
+            .time
+            .timezone
+            .as_ref()
+            .is_none_or(|timezone| timezone.source.starts_with(GUESSED_ZONE_SOURCE_PREFIX))
+}
+
+/// Gives results without a reliable timezone the GPS derived zone of the nearest result within
+/// `max_gap`, from any camera. Returns the number of results that were changed.
+///
+/// Proximity is measured between local times, which don't depend on the zones being inferred.
+/// Changed results get the `Medium` confidence, a timezone with source
+/// [`INFERRED_ZONE_SOURCE`], and a new `datetime_utc` and `dedup_key`. `High` confidence
+/// results, offsets derived from a UTC tag and zones from the itinerary are never changed. The `completeness_score` is not
+/// updated, see [`score_completeness`](crate::score_completeness) to redo it.
+///
+/// ```rust
+/// use chrono::TimeDelta;
+/// use media_analyzer::infer_timezones;
+/// use media_analyzer::test_support::MediaMetadataFixture;
+///
+/// let mut results = vec![
+///     MediaMetadataFixture::sunset().build(),
+///     MediaMetadataFixture::photo()
+///         .without_offset()
+///         .with_local_time("2024-06-01T21:50:00")
+///         .build(),
+/// ];
+/// assert_eq!(infer_timezones(&mut results, TimeDelta::hours(1)), 1);
+/// assert_eq!(results[1].time.timezone.as_ref().unwrap().name, "Europe/Amsterdam");
+/// ```
+pub fn infer_timezones(results: &mut [MediaMetadata], max_gap: TimeDelta) -> usize {
+    let donors: Vec<(NaiveDateTime, Tz)> = results
+        .iter()
+        .filter_map(|result| Some((result.time.datetime_local, gps_zone(result)?)))
+        .collect();
+    let mut changed = 0;
+    for result in results.iter_mut().filter(|result| needs_zone(result)) {
+        let local = result.time.datetime_local;
+        let Some((_, tz)) = donors
+            .iter()
+            .map(|(donor_local, tz)| ((*donor_local - local).abs(), tz))
+            .filter(|(gap, _)| *gap <= max_gap)
+            .min_by_key(|(gap, _)| *gap)
+        else {
+            continue;
+        };
+        let (LocalResult::Single(zoned) | LocalResult::Ambiguous(zoned, _)) =
+            tz.from_local_datetime(&local)
+        else {
+            continue;
+        };
+        let time = &mut result.time;
+        time.datetime_utc = Some(zoned.with_timezone(&Utc));
+        time.timezone = Some(TimeZoneInfo {
+            name: tz.name().to_string(),
+            offset_seconds: zoned.offset().fix().into(),
+            source: INFERRED_ZONE_SOURCE.to_string(),
+            boundary_distance_km: None,
+            near_timezone_boundary: false,
+        });
+        time.datetime_local_iso = time.local_rfc3339();
+        time.source_details.confidence = CONFIDENCE_MEDIUM.to_string();
+        result.dedup_key = get_dedup_key(&result.camera, &result.time);
+        changed += 1;
+    }
+    changed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::MediaMetadataFixture;
+    use crate::time::structs::CONFIDENCE_LOW;
+    use crate::units::UtcOffset;
+
+    fn phone(time: &str) -> MediaMetadata {
+        MediaMetadataFixture::sunset()
+            .with_local_time(&format!("2024-06-01T{time}:00"))
+            .build()
+    }
+
+    fn dslr(local_time: &str) -> MediaMetadata {
+        MediaMetadataFixture::photo()
+            .without_offset()
+            .with_local_time(local_time)
+            .build()
+    }
+
+    #[test]
+    fn test_camera_photos_get_the_zone_of_nearby_phone_photos() {
+        let mut results = vec![
+            phone("10:00"),
+            dslr("2024-06-01T10:10:00"),
+            phone("10:30"),
+            dslr("2024-06-01T10:40:00"),
+            // A day later, with no phone photos around.
+            dslr("2024-06-02T18:00:00"),
+            // An explicit offset from another zone is kept.
+            MediaMetadataFixture::photo()
+                .with_local_time("2024-06-01T10:20:00")
+                .with_offset("-04:00")
+                .build(),
+        ];
+        assert_eq!(results[1].time.source_details.confidence, CONFIDENCE_LOW);
+
+        assert_eq!(infer_timezones(&mut results, TimeDelta::hours(2)), 2);
+
+        for dslr in [&results[1], &results[3]] {
+            let timezone = dslr.time.timezone.as_ref().unwrap();
+            assert_eq!(timezone.name, "Europe/Amsterdam");
+            assert_eq!(timezone.offset_seconds, UtcOffset(7200));
+            assert_eq!(timezone.source, INFERRED_ZONE_SOURCE);
+            assert_eq!(dslr.time.source_details.confidence, CONFIDENCE_MEDIUM);
+            assert!(dslr.dedup_key.is_some());
+        }
+        assert_eq!(
+            results[1].time.datetime_utc.unwrap().to_rfc3339(),
+            "2024-06-01T08:10:00+00:00"
+        );
+        assert_eq!(
+            results[1].time.datetime_local_iso.as_deref(),
+            Some("2024-06-01T10:10:00+02:00")
+        );
+
+        let outlier = &results[4];
+        assert!(outlier.time.timezone.is_none());
+        assert_eq!(outlier.time.source_details.confidence, CONFIDENCE_LOW);
+
+        let explicit = results[5].time.timezone.as_ref().unwrap();
+        assert_eq!(explicit.offset_seconds, UtcOffset(-4 * 3600));
+        assert_eq!(results[5].time.source_details.confidence, CONFIDENCE_HIGH);
+    }
+
+    #[test]
+    fn test_nearest_gps_photo_wins() {
+        let mut results = vec![
+            phone("10:00"),
+            MediaMetadataFixture::sunset()
+                .with_gps(40.7128, -74.0060)
+                .with_local_time("2024-06-01T12:00:00")
+                .build(),
+            dslr("2024-06-01T11:45:00"),
+        ];
+        assert_eq!(infer_timezones(&mut results, TimeDelta::hours(3)), 1);
+        assert_eq!(
+            results[2].time.timezone.as_ref().unwrap().name,
+            "America/New_York"
+        );
+    }
+
+    #[test]
+    fn test_offset_derived_from_a_utc_time_is_kept() {
+        // A naive capture time with a GPS UTC time, so a `Medium` confidence offset.
+        let derived = MediaMetadataFixture::photo()
+            .without_offset()
+            .with_local_time("2024-06-01T10:10:00")
+            .with_tag("Time", "GPSDateTime", "2024:06:01 13:10:00Z")
+            .build();
+        let mut results = vec![phone("10:00"), derived];
+        let before = results[1].time.timezone.clone().unwrap();
+        assert_eq!(results[1].time.source_details.confidence, CONFIDENCE_MEDIUM);
+
+        assert_eq!(infer_timezones(&mut results, TimeDelta::hours(1)), 0);
+        assert_eq!(results[1].time.timezone, Some(before));
+        assert_eq!(results[1].time.source_details.confidence, CONFIDENCE_MEDIUM);
+    }
+
+    #[test]
+    fn test_without_gps_photos_nothing_changes() {
+        let mut results = vec![dslr("2024-06-01T10:00:00"), dslr("2024-06-01T10:05:00")];
+        assert_eq!(infer_timezones(&mut results, TimeDelta::hours(1)), 0);
+        assert!(results.iter().all(|result| result.time.timezone.is_none()));
+    }
+}
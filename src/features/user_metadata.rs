@@ -0,0 +1,420 @@
+//! Captions, events and locations people wrote into their files, in IPTC and XMP or through
+//! Google Photos.
+
+use crate::ExifData;
+use crate::features::gps::LocationName;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Tags that may hold the description, in order of preference. Google Photos writes synced
+/// descriptions to `ImageDescription` and XMP `Description`.
+const DESCRIPTION_TAGS: [&str; 3] = ["Description", "ImageDescription", "Caption-Abstract"];
+/// Descriptions cameras fill in themselves, which say nothing about the photo.
+const PLACEHOLDER_DESCRIPTIONS: [&str; 6] = [
+    "OLYMPUS DIGITAL CAMERA",
+    "SONY DSC",
+    "DIGITAL CAMERA",
+    "SAMSUNG CAMERA PICTURES",
+    "KODAK Digital Still Camera",
+    "default",
+];
+/// The 8-byte character codes that start an EXIF `UserComment`, without their NUL padding.
+/// An all-NUL code means undefined.
+const CHARACTER_CODES: [&str; 3] = ["ASCII", "UNICODE", "JIS"];
+/// The length of the character code in front of an EXIF `UserComment`.
+const CHARACTER_CODE_LENGTH: usize = 8;
+/// Common country names that differ from their ISO 3166 short name, lowercased.
+const COUNTRY_ALIASES: [(&str, &str); 14] = [
+    ("usa", "US"),
+    ("united states", "US"),
+    ("uk", "GB"),
+    ("united kingdom", "GB"),
+    ("great britain", "GB"),
+    ("england", "GB"),
+    ("scotland", "GB"),
+    ("wales", "GB"),
+    ("holland", "NL"),
+    ("russia", "RU"),
+    ("south korea", "KR"),
+    ("north korea", "KP"),
+    ("vietnam", "VN"),
+    ("czech republic", "CZ"),
+];
+
+/// A location as written by a person or a photo manager, which is often more accurate than
+/// reverse geocoding the GPS coordinates.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WrittenLocation {
+    #[serde(default)]
+    pub city: Option<String>,
+    #[serde(default)]
+    pub state: Option<String>,
+    /// The country as written, a name or a code.
+    #[serde(default)]
+    pub country: Option<String>,
+    /// The ISO 3166 alpha-2 code of the written country code, or else of `country`.
+    #[serde(default)]
+    pub country_code: Option<String>,
+    /// A place within the city, like a venue or neighbourhood.
+    #[serde(default)]
+    pub sublocation: Option<String>,
+}
+
+/// Metadata written by people rather than by the camera.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UserMetadata {
+    /// The caption, without the placeholders some cameras write.
+    #[serde(default)]
+    pub description: Option<String>,
+    /// The IPTC extension `Event`, like "Anna's wedding".
+    #[serde(default)]
+    pub event: Option<String>,
+    #[serde(default)]
+    pub written_location: Option<WrittenLocation>,
+    /// The EXIF `UserComment`, decoded and without its character code.
+    #[serde(default)]
+    pub user_comment: Option<String>,
+    /// The XMP `DocumentID` editors give each saved version of a file.
+    #[serde(default)]
+    pub document_id: Option<String>,
+    /// The XMP `OriginalDocumentID`: the `DocumentID` of the file all versions were made from.
+    #[serde(default)]
+    pub original_document_id: Option<String>,
+    /// The `DocumentID`, or else the `InstanceID`, of the version this file was saved from,
+    /// from XMP `DerivedFrom`.
+    #[serde(default)]
+    pub derived_from: Option<String>,
+}
+
+/// Strips the character code `exiftool` leaves in front of some `UserComment` values, padded
+/// with NULs or spaces to 8 bytes. Text that only starts with a code name, like "ASCII art",
+/// is kept.
+fn strip_character_code(text: &str) -> &str {
+    if text.len() >= CHARACTER_CODE_LENGTH
+        && text.is_char_boundary(CHARACTER_CODE_LENGTH)
+        && text[..CHARACTER_CODE_LENGTH].trim_matches('\0').is_empty()
+    {
+        return &text[CHARACTER_CODE_LENGTH..];
+    }
+    CHARACTER_CODES
+        .iter()
+        .find_map(|code| {
+            let rest = text.strip_prefix(code)?;
+            let padding_length = rest.len().min(CHARACTER_CODE_LENGTH - code.len());
+            let (padding, payload) = rest.split_at_checked(padding_length)?;
+            padding
+                .chars()
+                .all(|c| matches!(c, '\0' | ' '))
+                .then_some(payload)
+        })
+        .unwrap_or(text)
+}
+
+/// Decodes UCS-2/UTF-16 that was read as single bytes, recognized by a byte order mark or by
+/// NULs in every other byte. `None` for other text.
+fn decode_utf16_bytes(text: &str) -> Option<String> {
+    let bytes: Vec<u8> = text
+        .chars()
+        .map(|c| u8::try_from(u32::from(c)).ok())
+        .collect::<Option<_>>()?;
+    let (bytes, little_endian) = match bytes.as_slice() {
+        [0xFF, 0xFE, rest @ ..] => (rest, true),
+        [0xFE, 0xFF, rest @ ..] => (rest, false),
+        bytes if bytes.len() < 2 || bytes.len() % 2 != 0 => return None,
+        bytes if bytes.iter().skip(1).step_by(2).all(|b| *b == 0) => (bytes, true),
+        bytes if bytes.iter().step_by(2).all(|b| *b == 0) => (bytes, false),
+        _ => return None,
+    };
+    let units: Vec<u16> = bytes
+        .chunks_exact(2)
+        .map(|pair| {
+            if little_endian {
+                u16::from_le_bytes([pair[0], pair[1]])
+            } else {
+                u16::from_be_bytes([pair[0], pair[1]])
+            }
+        })
+        .collect();
+    Some(String::from_utf16_lossy(&units))
+}
+
+/// Decodes an EXIF `UserComment` or similar text tag: strips the 8-byte character code
+/// (`ASCII`, `UNICODE`, `JIS` or undefined), decodes a UCS-2/UTF-16 payload, and trims NUL
+/// padding and whitespace. Byte arrays are read as UTF-8, or else as Latin-1. `None` when no
+/// text is left.
+#[must_use]
+pub fn decode_user_comment(value: &Value) -> Option<String> {
+    let text = match value {
+        Value::String(text) => text.clone(),
+        Value::Array(values) => {
+            let bytes: Vec<u8> = values
+                .iter()
+                .map(|value| value.as_u64().and_then(|byte| u8::try_from(byte).ok()))
+                .collect::<Option<_>>()?;
+            String::from_utf8(bytes.clone())
+                .unwrap_or_else(|_| bytes.into_iter().map(char::from).collect())
+        }
+        _ => return None,
+    };
+    let payload = strip_character_code(&text);
+    let decoded = decode_utf16_bytes(payload).unwrap_or_else(|| payload.to_string());
+    non_empty(decoded.trim_matches('\0'))
+}
+
+fn non_empty(text: &str) -> Option<String> {
+    let text = text.trim();
+    (!text.is_empty()).then(|| text.to_string())
+}
+
+/// The first of `tags` with text.
+fn first_text(exif: &ExifData, tags: &[&str]) -> Option<String> {
+    tags.iter()
+        .find_map(|tag| exif.get_str(tag).and_then(non_empty))
+}
+
+/// A tag named like a group, such as XMP `Location`, which a lookup from the root would find
+/// the group for.
+fn text_in_groups(exif: &ExifData, tag: &str) -> Option<String> {
+    exif.inner()
+        .as_object()?
+        .values()
+        .filter_map(|group| group.get(tag).and_then(Value::as_str))
+        .find_map(non_empty)
+}
+
+/// Strips what the ISO 3166 short names add, like "Netherlands (Kingdom of the)".
+fn normalize_country(name: &str) -> String {
+    let name = name.split_once(" (").map_or(name, |(name, _)| name);
+    let name = name.trim().to_lowercase();
+    name.strip_prefix("the ").unwrap_or(&name).to_string()
+}
+
+/// The ISO 3166 alpha-2 code of a country written as a code or a name.
+pub fn resolve_country_code(country: &str) -> Option<String> {
+    let country = country.trim();
+    let upper = country.to_uppercase();
+    let by_code = match upper.len() {
+        2 => rust_iso3166::from_alpha2(&upper),
+        3 => rust_iso3166::from_alpha3(&upper),
+        _ => None,
+    };
+    if let Some(code) = by_code {
+        return Some(code.alpha2.to_string());
+    }
+    let normalized = normalize_country(country);
+    COUNTRY_ALIASES
+        .iter()
+        .find(|(alias, _)| *alias == normalized)
+        .map(|(_, code)| (*code).to_string())
+        .or_else(|| {
+            rust_iso3166::ALL
+                .iter()
+                .find(|code| normalize_country(code.name) == normalized)
+                .map(|code| code.alpha2.to_string())
+        })
+}
+
+fn get_written_location(exif: &ExifData) -> Option<WrittenLocation> {
+    let country = first_text(exif, &["Country", "Country-PrimaryLocationName"]);
+    let written_code = first_text(exif, &["CountryCode", "Country-PrimaryLocationCode"]);
+    let location = WrittenLocation {
+        city: first_text(exif, &["City"]),
+        state: first_text(exif, &["State", "Province-State"]),
+        country_code: written_code
+            .as_deref()
+            .or(country.as_deref())
+            .and_then(resolve_country_code),
+        country,
+        sublocation: first_text(exif, &["Sub-location"])
+            .or_else(|| text_in_groups(exif, "Location")),
+    };
+    (location != WrittenLocation::default()).then_some(location)
+}
+
+pub fn get_user_metadata(exif: &ExifData) -> UserMetadata {
+    let description = DESCRIPTION_TAGS
+        .iter()
+        .filter_map(|tag| exif.get_value(tag).and_then(decode_user_comment))
+        .find(|description| {
+            !PLACEHOLDER_DESCRIPTIONS
+                .iter()
+                .any(|placeholder| description.eq_ignore_ascii_case(placeholder))
+        });
+    UserMetadata {
+        description,
+        event: first_text(exif, &["Event"]),
+        written_location: get_written_location(exif),
+        user_comment: exif.get_value("UserComment").and_then(decode_user_comment),
+        document_id: first_text(exif, &["DocumentID"]),
+        original_document_id: first_text(exif, &["OriginalDocumentID"]),
+        derived_from: first_text(exif, &["DerivedFromDocumentID", "DerivedFromInstanceID"]),
+    }
+}
+
+/// Whether the reverse-geocoded country differs from the written one, a strong sign that
+/// either the GPS or the caption is wrong. `None` when either country is unknown.
+pub fn location_conflict(written: &WrittenLocation, geocoded: &LocationName) -> Option<bool> {
+    let written_code = written.country_code.as_deref()?;
+    let geocoded_code = Some(geocoded.country_code.as_str()).filter(|code| !code.is_empty())?;
+    Some(!written_code.eq_ignore_ascii_case(geocoded_code))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::features::geocode::CachedGeocoder;
+    use crate::features::gps::get_gps_info;
+    use serde_json::json;
+
+    #[test]
+    fn test_resolve_country_code() {
+        assert_eq!(resolve_country_code("NL"), Some("NL".to_string()));
+        assert_eq!(resolve_country_code("nld"), Some("NL".to_string()));
+        assert_eq!(
+            resolve_country_code("The Netherlands"),
+            Some("NL".to_string())
+        );
+        assert_eq!(resolve_country_code("Holland"), Some("NL".to_string()));
+        assert_eq!(resolve_country_code("France"), Some("FR".to_string()));
+        assert_eq!(resolve_country_code("USA"), Some("US".to_string()));
+        assert_eq!(resolve_country_code("Atlantis"), None);
+    }
+
+    #[test]
+    fn test_written_location_and_event() {
+        let exif = ExifData::new(json!({
+            "Location": {
+                "GPSLatitude": 52.379_189,
+                "GPSLongitude": 4.899_431,
+                "City": "Amsterdam",
+                "State": "Noord-Holland",
+                "Country": "Netherlands",
+                "Location": "Centraal Station"
+            },
+            "Other": { "Event": " Anna's wedding ", "ImageDescription": "OLYMPUS DIGITAL CAMERA" }
+        }));
+        let user = get_user_metadata(&exif);
+        assert_eq!(user.event, Some("Anna's wedding".to_string()));
+        assert_eq!(user.description, None);
+        let written = user.written_location.unwrap();
+        assert_eq!(
+            written,
+            WrittenLocation {
+                city: Some("Amsterdam".to_string()),
+                state: Some("Noord-Holland".to_string()),
+                country: Some("Netherlands".to_string()),
+                country_code: Some("NL".to_string()),
+                sublocation: Some("Centraal Station".to_string()),
+            }
+        );
+
+        let gps = get_gps_info(&CachedGeocoder::coarse(0), &exif).unwrap();
+        assert_eq!(
+            location_conflict(&written, gps.location.as_ref().unwrap()),
+            Some(false)
+        );
+    }
+
+    #[test]
+    fn test_conflicting_location() {
+        // Captioned Paris, but the coordinates are in Amsterdam.
+        let exif = ExifData::new(json!({
+            "Location": {
+                "GPSLatitude": 52.379_189,
+                "GPSLongitude": 4.899_431,
+                "City": "Paris",
+                "Country-PrimaryLocationName": "France",
+                "Country-PrimaryLocationCode": "FRA"
+            }
+        }));
+        let written = get_user_metadata(&exif).written_location.unwrap();
+        assert_eq!(written.country_code, Some("FR".to_string()));
+        let gps = get_gps_info(&CachedGeocoder::coarse(0), &exif).unwrap();
+        assert_eq!(
+            location_conflict(&written, gps.location.as_ref().unwrap()),
+            Some(true)
+        );
+
+        let unknown = WrittenLocation {
+            city: Some("Paris".to_string()),
+            ..WrittenLocation::default()
+        };
+        assert_eq!(
+            location_conflict(&unknown, gps.location.as_ref().unwrap()),
+            None
+        );
+    }
+
+    #[test]
+    fn test_written_location_without_gps() {
+        let exif = ExifData::new(json!({
+            "Location": { "City": "Lisbon", "Country": "Portugal" },
+            "Other": { "Description": "Tram 28" }
+        }));
+        let user = get_user_metadata(&exif);
+        assert_eq!(user.description, Some("Tram 28".to_string()));
+        assert_eq!(
+            user.written_location.unwrap().country_code,
+            Some("PT".to_string())
+        );
+        // The written location is not turned into coordinates.
+        assert!(get_gps_info(&CachedGeocoder::coarse(0), &exif).is_none());
+    }
+
+    #[test]
+    fn test_no_user_metadata() {
+        let exif = ExifData::new(json!({ "Camera": { "Make": "Canon" } }));
+        assert_eq!(get_user_metadata(&exif), UserMetadata::default());
+    }
+
+    #[test]
+    fn test_ascii_prefixed_user_comment() {
+        let comment = json!("ASCII\u{0}\u{0}\u{0}Sunset at the pier");
+        assert_eq!(
+            decode_user_comment(&comment).as_deref(),
+            Some("Sunset at the pier")
+        );
+        let comment = json!("UNICODE Time-lapse");
+        assert_eq!(decode_user_comment(&comment).as_deref(), Some("Time-lapse"));
+        // Only a padded code is a character code.
+        let comment = json!("ASCII art of a cat");
+        assert_eq!(
+            decode_user_comment(&comment).as_deref(),
+            Some("ASCII art of a cat")
+        );
+    }
+
+    #[test]
+    fn test_unicode_prefixed_utf16_user_comment() {
+        let utf16le: String = "Zeitraffer über Köln"
+            .encode_utf16()
+            .flat_map(u16::to_le_bytes)
+            .map(char::from)
+            .collect();
+        let comment = json!(format!("UNICODE\u{0}{utf16le}"));
+        assert_eq!(
+            decode_user_comment(&comment).as_deref(),
+            Some("Zeitraffer über Köln")
+        );
+
+        let mut bytes = b"UNICODE\0".to_vec();
+        bytes.extend([0xFE, 0xFF, 0x00, b'H', 0x00, b'i']);
+        assert_eq!(decode_user_comment(&json!(bytes)).as_deref(), Some("Hi"));
+    }
+
+    #[test]
+    fn test_nul_padded_user_comment() {
+        let exif = ExifData::new(json!({
+            "Other": { "UserComment": "Birthday cake\u{0}\u{0}\u{0}\u{0}" }
+        }));
+        assert_eq!(
+            get_user_metadata(&exif).user_comment.as_deref(),
+            Some("Birthday cake")
+        );
+        // An undefined character code and only padding leave no comment.
+        let exif = ExifData::new(json!({ "Other": { "UserComment": "\u{0}".repeat(16) } }));
+        assert_eq!(get_user_metadata(&exif), UserMetadata::default());
+    }
+}
@@ -0,0 +1,463 @@
+//! Links originals in a batch of results to their edited copies, like `IMG_1234-edited.jpg`
+//! from Google Photos or an export from Lightroom.
+
+use crate::ExifData;
+use crate::features::ordering::filename;
+use crate::structs::MediaMetadata;
+use crate::time::file_modify_time;
+use crate::time::structs::CONFIDENCE_LOW;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::LazyLock;
+
+/// Lowercase substrings of the `Software` tag of editors. Cameras write their firmware version
+/// instead, like "HDR+ 1.0.345" or "17.4.1".
+const EDITOR_SOFTWARE: [&str; 15] = [
+    "adobe",
+    "photoshop",
+    "lightroom",
+    "gimp",
+    "snapseed",
+    "darktable",
+    "rawtherapee",
+    "capture one",
+    "affinity",
+    "pixelmator",
+    "luminar",
+    "picasa",
+    "google photos",
+    "vsco",
+    "paint.net",
+];
+
+/// The suffixes editors add to the file stem: Google Photos' `-edited`, the ` (1)` of a second
+/// download, and the `E` of iPhone edits, like `IMG_E1234`.
+// The patterns are valid literals, so compiling them cannot fail.
+#[allow(clippy::unwrap_used)]
+static EDITED_SUFFIX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?i)^(.+?)(?:[-_ ]edited|\s*\(\d+\))$").unwrap());
+#[allow(clippy::unwrap_used)]
+static APPLE_EDIT: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(?i)^IMG_E(\d{4})$").unwrap());
+
+/// How the members of a [`VersionChain`] were found to belong together.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum LinkageKind {
+    /// The XMP ids: a `DerivedFrom` or `OriginalDocumentID` pointing at another file's
+    /// `DocumentID`, or a shared `OriginalDocumentID`.
+    XmpDocumentId,
+    /// The same camera and capture time, down to the subseconds when known, with a different
+    /// `Software` tag for each file.
+    CaptureTime,
+    /// A filename with an edit suffix, like `IMG_1234-edited.jpg` next to `IMG_1234.jpg`.
+    Filename,
+}
+
+/// An original and its edited copies.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VersionChain {
+    /// The index of the likely original. `None` when only edits of it are in the batch.
+    pub original_index: Option<usize>,
+    /// The indices of the edits, ascending.
+    pub edit_indices: Vec<usize>,
+    pub linkage: LinkageKind,
+}
+
+/// What linking needs to know of one result.
+struct Version {
+    document_id: Option<String>,
+    original_document_id: Option<String>,
+    derived_from: Option<String>,
+    software: Option<String>,
+    /// Whether `software` is a known editor.
+    edited_by_software: bool,
+    file_modified: Option<i64>,
+    camera_key: Option<String>,
+    /// The local capture time, when it is not a low confidence guess.
+    capture_time: Option<String>,
+    /// The file stem without an edit suffix, lowercased.
+    base_name: String,
+    has_edit_suffix: bool,
+}
+
+impl Version {
+    fn new(result: &MediaMetadata) -> Self {
+        let exif = ExifData::new(result.exif.clone());
+        let software = exif.get_string("Software");
+        let camera = &result.camera;
+        let filename = filename(result);
+        let stem = filename
+            .rsplit_once('.')
+            .map_or(filename.as_str(), |(stem, _)| stem);
+        let (base_name, has_edit_suffix) = strip_edit_suffix(stem);
+        let user = &result.user_metadata;
+        Self {
+            document_id: user.document_id.clone(),
+            original_document_id: user.original_document_id.clone(),
+            derived_from: user.derived_from.clone(),
+            edited_by_software: software.as_deref().is_some_and(is_editor),
+            software,
+            file_modified: file_modify_time(&exif).map(|time| time.timestamp()),
+            camera_key: (camera.camera_make.is_some() || camera.camera_model.is_some()).then(
+                || {
+                    format!(
+                        "{}|{}|{}",
+                        camera.camera_make.as_deref().unwrap_or_default().trim(),
+                        camera.camera_model.as_deref().unwrap_or_default().trim(),
+                        camera
+                            .camera_serial_number
+                            .as_deref()
+                            .unwrap_or_default()
+                            .trim()
+                    )
+                },
+            ),
+            capture_time: (result.time.source_details.confidence != CONFIDENCE_LOW)
+                .then(|| result.time.datetime_local.to_string()),
+            base_name,
+            has_edit_suffix,
+        }
+    }
+
+    /// Sorts likely originals first: files not saved by an editor, then the oldest.
+    fn original_rank(&self, index: usize) -> (bool, bool, i64, usize) {
+        (
+            self.edited_by_software,
+            self.file_modified.is_none(),
+            self.file_modified.unwrap_or_default(),
+            index,
+        )
+    }
+}
+
+fn is_editor(software: &str) -> bool {
+    let software = software.to_lowercase();
+    EDITOR_SOFTWARE
+        .iter()
+        .any(|editor| software.contains(editor))
+}
+
+/// The lowercase stem without an edit suffix, and whether it had one.
+fn strip_edit_suffix(stem: &str) -> (String, bool) {
+    if let Some(captures) = APPLE_EDIT.captures(stem) {
+        return (format!("img_{}", &captures[1]), true);
+    }
+    EDITED_SUFFIX.captures(stem).map_or_else(
+        || (stem.to_lowercase(), false),
+        |captures| (captures[1].to_lowercase(), true),
+    )
+}
+
+/// A minimal union-find over result indices.
+struct Groups(Vec<usize>);
+
+impl Groups {
+    fn find(&mut self, index: usize) -> usize {
+        let mut root = index;
+        while self.0[root] != root {
+            root = self.0[root];
+        }
+        self.0[index] = root;
+        root
+    }
+
+    fn union(&mut self, a: usize, b: usize) {
+        let (a, b) = (self.find(a), self.find(b));
+        self.0[a.max(b)] = a.min(b);
+    }
+}
+
+/// Groups of at least two indices linked by their XMP ids.
+fn xmp_groups(versions: &[Version]) -> Vec<Vec<usize>> {
+    let mut by_document_id: BTreeMap<&str, usize> = BTreeMap::new();
+    for (index, version) in versions.iter().enumerate() {
+        if let Some(id) = &version.document_id {
+            by_document_id.entry(id).or_insert(index);
+        }
+    }
+    let mut groups = Groups((0..versions.len()).collect());
+    let mut by_original_id: BTreeMap<&str, usize> = BTreeMap::new();
+    for (index, version) in versions.iter().enumerate() {
+        for parent in [&version.derived_from, &version.original_document_id]
+            .into_iter()
+            .flatten()
+        {
+            if let Some(&parent_index) = by_document_id.get(parent.as_str()) {
+                groups.union(index, parent_index);
+            }
+        }
+        if let Some(original_id) = &version.original_document_id {
+            let first = *by_original_id.entry(original_id).or_insert(index);
+            groups.union(index, first);
+        }
+    }
+    let mut members: BTreeMap<usize, Vec<usize>> = BTreeMap::new();
+    for index in 0..versions.len() {
+        members.entry(groups.find(index)).or_default().push(index);
+    }
+    members
+        .into_values()
+        .filter(|group| group.len() > 1)
+        .collect()
+}
+
+/// The member the others were derived from that wasn't derived itself, or else the likely
+/// original by rank.
+fn xmp_original(versions: &[Version], group: &[usize]) -> usize {
+    let is_parent = |index: usize| {
+        versions[index].document_id.as_ref().is_some_and(|id| {
+            group.iter().any(|&other| {
+                other != index
+                    && (versions[other].derived_from.as_ref() == Some(id)
+                        || versions[other].original_document_id.as_ref() == Some(id))
+            })
+        })
+    };
+    let is_root = |index: usize| is_parent(index) && versions[index].derived_from.is_none();
+    group
+        .iter()
+        .copied()
+        .min_by_key(|&index| (!is_root(index), versions[index].original_rank(index)))
+        .unwrap_or(group[0])
+}
+
+/// Groups of unlinked results with the same camera and capture time and a different
+/// `Software` each. Bursts shot within the same second have the same `Software`, so they
+/// aren't linked.
+fn capture_time_groups(versions: &[Version], linked: &[bool]) -> Vec<Vec<usize>> {
+    let mut by_capture: BTreeMap<(&str, &str), Vec<usize>> = BTreeMap::new();
+    for (index, version) in versions.iter().enumerate() {
+        if let (false, Some(camera), Some(time)) =
+            (linked[index], &version.camera_key, &version.capture_time)
+        {
+            by_capture.entry((camera, time)).or_default().push(index);
+        }
+    }
+    by_capture
+        .into_values()
+        .filter(|group| {
+            let mut software: Vec<Option<&str>> = group
+                .iter()
+                .map(|&index| versions[index].software.as_deref())
+                .collect();
+            software.sort_unstable();
+            software.dedup();
+            group.len() > 1 && software.len() == group.len()
+        })
+        .collect()
+}
+
+/// Groups of unlinked results with the same file stem once edit suffixes are removed, of
+/// which at most one has no suffix. Edits from another camera than the original are left out.
+fn filename_groups(versions: &[Version], linked: &[bool]) -> Vec<(Option<usize>, Vec<usize>)> {
+    let mut by_name: BTreeMap<&str, Vec<usize>> = BTreeMap::new();
+    for (index, version) in versions.iter().enumerate() {
+        if !linked[index] && !version.base_name.is_empty() {
+            by_name.entry(&version.base_name).or_default().push(index);
+        }
+    }
+    by_name
+        .into_values()
+        .filter_map(|group| {
+            let (edits, originals): (Vec<usize>, Vec<usize>) = group
+                .into_iter()
+                .partition(|&index| versions[index].has_edit_suffix);
+            let original = match originals.as_slice() {
+                [] => None,
+                [original] => Some(*original),
+                _ => return None,
+            };
+            let same_camera = |index: &usize| {
+                let camera = |i: usize| versions[i].camera_key.as_deref();
+                original.is_none_or(|original| {
+                    camera(original).is_none()
+                        || camera(*index).is_none()
+                        || camera(original) == camera(*index)
+                })
+            };
+            let edits: Vec<usize> = edits.into_iter().filter(same_camera).collect();
+            let members = edits.len() + usize::from(original.is_some());
+            (!edits.is_empty() && members > 1).then_some((original, edits))
+        })
+        .collect()
+}
+
+/// Groups the originals in `results` with their edited copies. Each result is in at most one
+/// chain.
+///
+/// Results are linked by (in order of precedence) their XMP ids, the same camera and capture
+/// time with a different `Software` tag, and the `-edited`, ` (1)` and `IMG_E` filename
+/// conventions.
+///
+/// The likely original is the one the others were derived from, or else the one not saved by
+/// a known editor with the oldest file modification time.
+///
+/// The `Software` tag, file modification time and filename are read from `exif`, so results of
+/// analyzers with `keep_exif` off are only linked by their XMP ids.
+#[must_use]
+pub fn link_versions(results: &[MediaMetadata]) -> Vec<VersionChain> {
+    let versions: Vec<Version> = results.iter().map(Version::new).collect();
+    let mut linked = vec![false; versions.len()];
+    let mut chains = Vec::new();
+    let mut add = |linked: &mut Vec<bool>, original, mut edits: Vec<usize>, linkage| {
+        edits.retain(|&index| Some(index) != original);
+        edits.sort_unstable();
+        for &index in original.iter().chain(&edits) {
+            linked[index] = true;
+        }
+        chains.push(VersionChain {
+            original_index: original,
+            edit_indices: edits,
+            linkage,
+        });
+    };
+
+    for group in xmp_groups(&versions) {
+        let original = xmp_original(&versions, &group);
+        add(
+            &mut linked,
+            Some(original),
+            group,
+            LinkageKind::XmpDocumentId,
+        );
+    }
+    for group in capture_time_groups(&versions, &linked) {
+        let original = group
+            .iter()
+            .copied()
+            .min_by_key(|&index| versions[index].original_rank(index));
+        add(&mut linked, original, group, LinkageKind::CaptureTime);
+    }
+    for (original, edits) in filename_groups(&versions, &linked) {
+        add(&mut linked, original, edits, LinkageKind::Filename);
+    }
+
+    chains.sort_by_key(|chain| {
+        chain
+            .original_index
+            .into_iter()
+            .chain(chain.edit_indices.iter().copied())
+            .min()
+    });
+    chains
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::MediaMetadataFixture;
+
+    fn chain(original: Option<usize>, edits: &[usize], linkage: LinkageKind) -> VersionChain {
+        VersionChain {
+            original_index: original,
+            edit_indices: edits.to_vec(),
+            linkage,
+        }
+    }
+
+    #[test]
+    fn test_xmp_linked_versions() {
+        let original = MediaMetadataFixture::photo()
+            .with_filename("DSC_0001.NEF")
+            .with_tag("Other", "DocumentID", "xmp.did:AAA")
+            .with_tag("Other", "OriginalDocumentID", "xmp.did:AAA");
+        // A Lightroom export, renamed, with another time zone written.
+        let export = MediaMetadataFixture::photo()
+            .with_filename("wedding_final.jpg")
+            .with_offset("+00:00")
+            .with_tag(
+                "Other",
+                "Software",
+                "Adobe Photoshop Lightroom Classic 13.2",
+            )
+            .with_tag("Other", "DocumentID", "xmp.did:BBB")
+            .with_tag("Other", "OriginalDocumentID", "xmp.did:AAA")
+            .with_tag("Other", "DerivedFromDocumentID", "xmp.did:AAA");
+        let unrelated = MediaMetadataFixture::photo()
+            .with_filename("DSC_0002.NEF")
+            .with_tag("Other", "DocumentID", "xmp.did:CCC");
+        let results = [export.build(), unrelated.build(), original.build()];
+
+        assert_eq!(
+            results[0].user_metadata.derived_from.as_deref(),
+            Some("xmp.did:AAA")
+        );
+        assert_eq!(
+            link_versions(&results),
+            vec![chain(Some(2), &[0], LinkageKind::XmpDocumentId)]
+        );
+    }
+
+    #[test]
+    fn test_filename_linked_versions() {
+        let photo = |filename: &str| {
+            MediaMetadataFixture::photo()
+                .with_filename(filename)
+                .without_offset()
+                .build()
+        };
+        let results = [
+            photo("IMG_1234.JPG"),
+            photo("IMG_1234-edited.jpg"),
+            photo("IMG_1234 (1).JPG"),
+            photo("IMG_1235.JPG"),
+            photo("IMG_E1236.JPG"),
+        ];
+        assert_eq!(
+            link_versions(&results),
+            vec![chain(Some(0), &[1, 2], LinkageKind::Filename)]
+        );
+
+        // Only edits of an original that isn't in the batch.
+        let results = [photo("IMG_E1236.JPG"), photo("IMG_1236-edited.jpg")];
+        assert_eq!(
+            link_versions(&results),
+            vec![chain(None, &[0, 1], LinkageKind::Filename)]
+        );
+    }
+
+    #[test]
+    fn test_capture_time_linked_versions() {
+        let camera = MediaMetadataFixture::photo()
+            .with_filename("DSC_0001.JPG")
+            .with_tag("Other", "Software", "Ver.1.10")
+            .with_tag("Time", "FileModifyDate", "2024:06:01 10:00:05+02:00");
+        let edit = MediaMetadataFixture::photo()
+            .with_filename("beach.jpg")
+            .with_tag("Other", "Software", "GIMP 2.10.36")
+            .with_tag("Time", "FileModifyDate", "2024:06:03 19:12:00+02:00");
+        let results = [edit.build(), camera.build()];
+        assert_eq!(
+            link_versions(&results),
+            vec![chain(Some(1), &[0], LinkageKind::CaptureTime)]
+        );
+    }
+
+    #[test]
+    fn test_photos_in_the_same_second_are_not_linked() {
+        // Two frames of a burst: same camera, same second, same firmware.
+        let frame = |filename: &str| {
+            MediaMetadataFixture::photo()
+                .with_filename(filename)
+                .with_tag("Other", "Software", "Ver.1.10")
+                .build()
+        };
+        assert_eq!(
+            link_versions(&[frame("DSC_0001.JPG"), frame("DSC_0002.JPG")]),
+            Vec::new()
+        );
+
+        // The same name from two different cameras.
+        let other_camera = MediaMetadataFixture::photo()
+            .with_filename("IMG_0001-edited.jpg")
+            .with_tag("Camera", "Model", "EOS R6")
+            .with_local_time("2024-07-01T10:00:00")
+            .build();
+        let original = MediaMetadataFixture::photo()
+            .with_filename("IMG_0001.jpg")
+            .build();
+        assert_eq!(link_versions(&[original, other_camera]), Vec::new());
+    }
+}
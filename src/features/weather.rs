@@ -1,29 +1,342 @@
 use crate::GpsInfo;
+use crate::TimeZoneInfo;
 use crate::features::error::WeatherError;
-use chrono::{DateTime, Utc};
-use meteostat::{Hourly, LatLon, Meteostat, RequiredData};
+use crate::features::weather_provider::{WeatherObservation, WeatherProvider};
+use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
+use chrono_tz::Tz;
+use meteostat::WeatherCondition;
 use serde::{Deserialize, Serialize};
 use sunrise::{Coordinates, DawnType, SolarDay, SolarEvent};
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct WeatherInfo {
-    pub hourly: Option<Hourly>,
+    #[serde(default)]
+    pub hourly: Option<WeatherObservation>,
     pub sun_info: SunInfo,
+    /// The search radius of the last weather request, i.e. the radius the observation was
+    /// found in, or the largest radius tried when there was no data. 0 for results stored
+    /// before it was recorded.
+    #[serde(default)]
+    pub search_radius_used_km: f64,
+    /// The weather in the hours around the capture time, when the analyzer is configured with
+    /// `weather_context_hours`.
+    #[serde(default)]
+    pub context: Option<WeatherContext>,
+    /// The UTC date of the observation in `hourly`.
+    #[serde(default)]
+    pub weather_date_used: Option<NaiveDate>,
+    /// Whether `weather_date_used` is another day than the local capture date (the UTC one
+    /// without a timezone), as for a photo taken at 00:30 in a timezone east of UTC.
+    #[serde(default)]
+    pub crossed_day_boundary: bool,
+    /// Conditions that matter for photography, derived from `hourly` and the hours around it.
+    #[serde(default)]
+    pub context_flags: WeatherFlags,
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+/// Weather conditions that matter for photography. Each flag is `None` when the observations
+/// lack the data it needs.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WeatherFlags {
+    /// The condition was fog or freezing fog. Providers don't report visibility, so this only
+    /// depends on the condition.
+    #[serde(default)]
+    pub was_foggy: Option<bool>,
+    /// The wind speed was at least [`WINDY_THRESHOLD_KMH`], Beaufort 5, which shakes long
+    /// exposures and drones.
+    #[serde(default)]
+    pub was_windy: Option<bool>,
+    /// It snowed, or the snow depth grew, in the hours up to the capture hour. Daily data is
+    /// not fetched, so this only reaches back as far as `weather_context_hours`.
+    #[serde(default)]
+    pub fresh_snow_likely: Option<bool>,
+    /// The felt temperature (wind chill or heat index) was at most [`EXTREME_COLD_FELT_C`] or
+    /// at least [`EXTREME_HEAT_FELT_C`].
+    #[serde(default)]
+    pub temperature_felt_extreme: Option<bool>,
+}
+
+/// The lower bound of Beaufort 5 (fresh breeze), in km/h.
+pub const WINDY_THRESHOLD_KMH: f64 = 29.0;
+/// Felt temperatures at or below this, in °C, risk frostbite within half an hour.
+pub const EXTREME_COLD_FELT_C: f64 = -20.0;
+/// Felt temperatures at or above this, in °C, are in the heat index' danger range.
+pub const EXTREME_HEAT_FELT_C: f64 = 40.0;
+/// Wind chill is only defined at or below this temperature, in °C.
+const WIND_CHILL_MAX_C: f64 = 10.0;
+/// Wind chill is only defined at or above this wind speed, in km/h.
+const WIND_CHILL_MIN_KMH: f64 = 4.8;
+/// The heat index is only defined at or above this temperature, in °C.
+const HEAT_INDEX_MIN_C: f64 = 27.0;
+
+/// The temperature the air feels like: the wind chill in cold wind, the heat index in warm
+/// humid air, and otherwise the air temperature.
+fn felt_temperature(observation: &WeatherObservation) -> Option<f64> {
+    let t = observation.temperature?;
+    if t <= WIND_CHILL_MAX_C
+        && let Some(v) = observation.wind_speed.filter(|v| *v >= WIND_CHILL_MIN_KMH)
+    {
+        let v = v.powf(0.16);
+        return Some((0.3965 * t).mul_add(v, 11.37f64.mul_add(-v, 0.6215f64.mul_add(t, 13.12))));
+    }
+    if t >= HEAT_INDEX_MIN_C
+        && let Some(r) = observation.relative_humidity.map(f64::from)
+    {
+        // The Rothfusz regression with coefficients for °C, as a polynomial in the humidity.
+        let constant = (-0.012_308_094f64)
+            .mul_add(t, 1.611_394_11)
+            .mul_add(t, -8.784_695);
+        let linear = 2.211_732e-3f64
+            .mul_add(t, -0.146_116_05)
+            .mul_add(t, 2.338_549);
+        let quadratic = (-3.582e-6f64)
+            .mul_add(t, 7.2546e-4)
+            .mul_add(t, -0.016_424_828);
+        return Some(quadratic.mul_add(r, linear).mul_add(r, constant));
+    }
+    Some(t)
+}
+
+const fn is_snowfall(condition: WeatherCondition) -> bool {
+    matches!(
+        condition,
+        WeatherCondition::LightSnowfall
+            | WeatherCondition::Snowfall
+            | WeatherCondition::HeavySnowfall
+            | WeatherCondition::SnowShower
+            | WeatherCondition::HeavySnowShower
+    )
+}
+
+impl WeatherFlags {
+    /// Derives the flags from the observation at the capture hour `capture` and the
+    /// observations around it.
+    fn new(capture: &WeatherObservation, observations: &[WeatherObservation]) -> Self {
+        let before: Vec<&WeatherObservation> = observations
+            .iter()
+            .chain(std::iter::once(capture))
+            .filter(|observation| observation.datetime <= capture.datetime)
+            .collect();
+        let conditions: Vec<WeatherCondition> =
+            before.iter().filter_map(|hour| hour.condition).collect();
+        let snowed = (!conditions.is_empty()).then(|| conditions.iter().any(|c| is_snowfall(*c)));
+        let earliest_depth = before
+            .iter()
+            .filter(|hour| hour.snow.is_some())
+            .min_by_key(|hour| hour.datetime)
+            .and_then(|hour| hour.snow);
+        let depth_grew = earliest_depth
+            .zip(capture.snow)
+            .map(|(earliest, now)| now > earliest);
+        let fresh_snow_likely = match (snowed, depth_grew) {
+            (None, None) => None,
+            (snowed, grew) => Some(snowed.unwrap_or(false) || grew.unwrap_or(false)),
+        };
+
+        Self {
+            was_foggy: capture.condition.map(|condition| {
+                matches!(
+                    condition,
+                    WeatherCondition::Fog | WeatherCondition::FreezingFog
+                )
+            }),
+            was_windy: capture.wind_speed.map(|speed| speed >= WINDY_THRESHOLD_KMH),
+            fresh_snow_likely,
+            temperature_felt_extreme: felt_temperature(capture)
+                .map(|felt| felt <= EXTREME_COLD_FELT_C || felt >= EXTREME_HEAT_FELT_C),
+        }
+    }
+}
+
+/// A single hour around the capture time.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WeatherContextHour {
+    /// Hours relative to the capture hour, negative for hours before it.
+    pub offset_hours: i8,
+    #[serde(default)]
+    pub temperature: Option<f64>,
+    #[serde(default)]
+    pub precipitation: Option<f64>,
+    #[serde(default)]
+    pub condition: Option<WeatherCondition>,
+}
+
+/// The weather trend around the capture time, which a single hourly sample can misrepresent,
+/// e.g. a photo taken during a brief shower on a sunny day.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WeatherContext {
+    /// The hours that have data, ordered by offset. Hours the provider did not already fetch
+    /// are missing, so this can be shorter than requested.
+    #[serde(default)]
+    pub hours: Vec<WeatherContextHour>,
+    /// Whether there was any precipitation in the 3 hours before the capture hour, as far as
+    /// the context reaches. `None` when none of those hours report precipitation.
+    #[serde(default)]
+    pub was_raining_recently: Option<bool>,
+}
+
+/// The revision of the weather and sun logic. Bump it when a change can alter a
+/// [`WeatherInfo`].
+pub const WEATHER_LOGIC_REVISION: u32 = 2;
+
+/// How many hours before the capture hour count as "recently" for `was_raining_recently`.
+const RECENT_RAIN_HOURS: i8 = 3;
+
+impl WeatherContext {
+    /// Builds the context from the observations around the capture hour `capture`.
+    fn new(capture: &WeatherObservation, observations: &[WeatherObservation], hours: u8) -> Self {
+        let mut hours: Vec<WeatherContextHour> = observations
+            .iter()
+            .filter_map(|observation| {
+                let offset = (observation.datetime - capture.datetime).num_hours();
+                let offset_hours = i8::try_from(offset).ok()?;
+                (offset.unsigned_abs() <= u64::from(hours)).then_some(WeatherContextHour {
+                    offset_hours,
+                    temperature: observation.temperature,
+                    precipitation: observation.precipitation,
+                    condition: observation.condition,
+                })
+            })
+            .collect();
+        hours.sort_by_key(|hour| hour.offset_hours);
+        hours.dedup_by_key(|hour| hour.offset_hours);
+
+        let recent_precipitation: Vec<f64> = hours
+            .iter()
+            .filter(|hour| (-RECENT_RAIN_HOURS..0).contains(&hour.offset_hours))
+            .filter_map(|hour| hour.precipitation)
+            .collect();
+        let was_raining_recently = (!recent_precipitation.is_empty())
+            .then(|| recent_precipitation.iter().any(|p| *p > 0.0));
+
+        Self {
+            hours,
+            was_raining_recently,
+        }
+    }
+}
+
+/// How far around the media's location to look for weather data.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase", rename_all_fields = "camelCase")]
+pub enum RadiusStrategy {
+    /// Always search within this many kilometers.
+    Fixed(f64),
+    /// Start at `start_km` and, while there is no data, multiply the radius by `step_factor`
+    /// until `max_km` is reached. `start_km` must be positive and `step_factor` larger than 1.
+    Adaptive {
+        start_km: f64,
+        max_km: f64,
+        step_factor: f64,
+    },
+}
+
+/// The most radii an adaptive search tries, whatever its factors.
+const MAX_RADIUS_STEPS: usize = 32;
+
+impl RadiusStrategy {
+    /// Checks that an adaptive search widens towards its maximum.
+    pub fn validate(&self) -> Result<(), String> {
+        match *self {
+            Self::Fixed(_) => Ok(()),
+            Self::Adaptive {
+                start_km,
+                max_km,
+                step_factor,
+            } => {
+                if !(start_km.is_finite() && start_km > 0.0) {
+                    Err(format!(
+                        "adaptive radius start_km must be positive, got {start_km}"
+                    ))
+                } else if !(max_km.is_finite() && max_km >= start_km) {
+                    Err(format!(
+                        "adaptive radius max_km must be at least start_km ({start_km}), got {max_km}"
+                    ))
+                } else if !(step_factor.is_finite() && step_factor > 1.0) {
+                    Err(format!(
+                        "adaptive radius step_factor must be larger than 1, got {step_factor}"
+                    ))
+                } else {
+                    Ok(())
+                }
+            }
+        }
+    }
+
+    /// The radius to try after `radius_km` had no data, or `None` when the search is exhausted.
+    fn next_radius(&self, radius_km: f64) -> Option<f64> {
+        match *self {
+            Self::Fixed(_) => None,
+            Self::Adaptive {
+                max_km,
+                step_factor,
+                ..
+            } => {
+                let step_factor = if step_factor.is_finite() && step_factor > 1.0 {
+                    step_factor
+                } else {
+                    2.0
+                };
+                (radius_km < max_km).then(|| (radius_km * step_factor).min(max_km))
+            }
+        }
+    }
+
+    /// The radius that is searched first.
+    #[must_use]
+    pub const fn start_radius(&self) -> f64 {
+        match *self {
+            Self::Fixed(radius_km) => radius_km,
+            Self::Adaptive { start_km, .. } => start_km,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct SunInfo {
+    #[serde(default)]
     pub sunrise: Option<DateTime<Utc>>,
+    #[serde(default)]
     pub sunset: Option<DateTime<Utc>>,
+    #[serde(default)]
     pub dawn: Option<DateTime<Utc>>,
+    #[serde(default)]
     pub dusk: Option<DateTime<Utc>>,
     pub is_daytime: bool,
+    /// Sunrise in the timezone of the capture time. `None` when no timezone was resolved.
+    #[serde(default)]
+    pub sunrise_local: Option<NaiveDateTime>,
+    /// Sunset in the timezone of the capture time. `None` when no timezone was resolved.
+    #[serde(default)]
+    pub sunset_local: Option<NaiveDateTime>,
+    /// Seconds between sunrise and sunset, `None` when the sun doesn't rise or set that day.
+    #[serde(default)]
+    pub day_length_seconds: Option<i64>,
+}
+
+/// Converts a UTC instant to local time in the resolved timezone. IANA names respect DST on the
+/// given day, fixed offsets (e.g. from `OffsetTimeOriginal`) are applied as is.
+fn to_local(datetime: DateTime<Utc>, timezone: &TimeZoneInfo) -> Option<NaiveDateTime> {
+    if let Ok(tz) = timezone.name.parse::<Tz>() {
+        return Some(datetime.with_timezone(&tz).naive_local());
+    }
+    timezone
+        .offset_seconds
+        .fixed_offset()
+        .map(|offset| datetime.with_timezone(&offset).naive_local())
 }
 
-// This internal function can now return a Result
-fn compute_sun_info(datetime: DateTime<Utc>, gps_info: &GpsInfo) -> Result<SunInfo, WeatherError> {
+/// The position of the sun on the UTC day of `datetime` at the GPS location.
+pub fn compute_sun_info(
+    datetime: DateTime<Utc>,
+    gps_info: &GpsInfo,
+    timezone: Option<&TimeZoneInfo>,
+) -> Result<SunInfo, WeatherError> {
     let date = datetime.date_naive();
     let coord = Coordinates::new(gps_info.latitude, gps_info.longitude)
         .ok_or(WeatherError::SunCalculationError)?;
@@ -44,50 +357,184 @@ fn compute_sun_info(datetime: DateTime<Utc>, gps_info: &GpsInfo) -> Result<SunIn
     } else {
         true
     };
+    let day_length_seconds = sunrise.zip(sunset).map(|(sr, ss)| (ss - sr).num_seconds());
     Ok(SunInfo {
         sunrise,
         sunset,
         dawn,
         dusk,
         is_daytime,
+        sunrise_local: sunrise.zip(timezone).and_then(|(sr, tz)| to_local(sr, tz)),
+        sunset_local: sunset.zip(timezone).and_then(|(ss, tz)| to_local(ss, tz)),
+        day_length_seconds,
     })
 }
 
 pub async fn get_weather_info(
-    client: &Meteostat,
+    provider: &dyn WeatherProvider,
     gps_info: &GpsInfo,
     datetime: DateTime<Utc>,
-    weather_search_radius_km: f64,
+    timezone: Option<&TimeZoneInfo>,
+    radius_strategy: &RadiusStrategy,
+    context_hours: u8,
 ) -> Result<WeatherInfo, WeatherError> {
-    let hourly_frame = client
-        .hourly()
-        .location(LatLon(gps_info.latitude, gps_info.longitude))
-        .required_data(RequiredData::SpecificDate(datetime.date_naive()))
-        .max_distance_km(weather_search_radius_km)
-        .call()
-        .await?;
-
+    let mut radius_km = radius_strategy.start_radius();
+    let mut steps = 0;
     // Handle the case where there is data, but not for the specific hour requested
-    let weather_info = hourly_frame
-        .get_at(datetime)
-        .map_err(|_| WeatherError::NoDataAvailable)?
-        .collect_single_hourly();
-
-    let weather_info = weather_info.ok();
-    let sun_info = compute_sun_info(datetime, gps_info)?;
+    let weather_info = loop {
+        steps += 1;
+        match provider
+            .hourly_with_context(
+                gps_info.latitude,
+                gps_info.longitude,
+                datetime,
+                radius_km,
+                context_hours,
+            )
+            .await
+        {
+            Ok(observation) => break Some(observation),
+            Err(WeatherError::NoDataAvailable) => match radius_strategy.next_radius(radius_km) {
+                Some(next) if steps < MAX_RADIUS_STEPS => radius_km = next,
+                _ => break None,
+            },
+            Err(e) => return Err(e),
+        }
+    };
+    let sun_info = compute_sun_info(datetime, gps_info, timezone)?;
+    let context = weather_info
+        .as_ref()
+        .filter(|_| context_hours > 0)
+        .map(|(observation, context)| WeatherContext::new(observation, context, context_hours));
+    let context_flags = weather_info
+        .as_ref()
+        .map(|(observation, context)| WeatherFlags::new(observation, context))
+        .unwrap_or_default();
+    let weather_date_used = weather_info
+        .as_ref()
+        .map(|(observation, _)| observation.datetime.date_naive());
+    let capture_date = timezone
+        .and_then(|timezone| to_local(datetime, timezone))
+        .map_or_else(|| datetime.date_naive(), |local| local.date());
 
     Ok(WeatherInfo {
-        hourly: weather_info,
+        hourly: weather_info.map(|(observation, _)| observation),
         sun_info,
+        search_radius_used_km: radius_km,
+        context,
+        weather_date_used,
+        crossed_day_boundary: weather_date_used.is_some_and(|date| date != capture_date),
+        context_flags,
     })
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::features::gps::{GpsInfo, LocationName};
-    use chrono::TimeZone;
+    use crate::UtcOffset;
+    use crate::features::gps::{CoordinatePrecision, GpsInfo, LocationName};
+    use crate::features::weather_provider::tests::MockProvider;
+    use crate::features::weather_provider::tests::observation;
+    use crate::features::weather_provider::{MeteostatProvider, ObservationWithContext};
+    use async_trait::async_trait;
+    use chrono::{TimeZone, Timelike};
     use chrono_tz::Europe::Amsterdam;
+    use meteostat::Meteostat;
+    use std::sync::Mutex;
+
+    /// A provider that only has data when searching further than `min_radius_km`.
+    struct SparseStationProvider {
+        min_radius_km: f64,
+        radii: Mutex<Vec<f64>>,
+    }
+
+    #[async_trait]
+    impl WeatherProvider for SparseStationProvider {
+        async fn hourly_at(
+            &self,
+            _latitude: f64,
+            _longitude: f64,
+            _datetime: DateTime<Utc>,
+        ) -> Result<WeatherObservation, WeatherError> {
+            Err(WeatherError::NoDataAvailable)
+        }
+
+        async fn hourly_within(
+            &self,
+            _latitude: f64,
+            _longitude: f64,
+            datetime: DateTime<Utc>,
+            radius_km: f64,
+        ) -> Result<WeatherObservation, WeatherError> {
+            self.radii.lock().unwrap().push(radius_km);
+            if radius_km > self.min_radius_km {
+                Ok(observation(datetime, 5.0))
+            } else {
+                Err(WeatherError::NoDataAvailable)
+            }
+        }
+    }
+
+    /// A provider holding a fixed hourly frame, like a station's data file.
+    struct FrameProvider {
+        frame: Vec<WeatherObservation>,
+    }
+
+    #[async_trait]
+    impl WeatherProvider for FrameProvider {
+        async fn hourly_at(
+            &self,
+            _latitude: f64,
+            _longitude: f64,
+            datetime: DateTime<Utc>,
+        ) -> Result<WeatherObservation, WeatherError> {
+            self.frame
+                .iter()
+                .find(|o| o.datetime == datetime.with_minute(0).unwrap())
+                .cloned()
+                .ok_or(WeatherError::NoDataAvailable)
+        }
+
+        async fn hourly_with_context(
+            &self,
+            latitude: f64,
+            longitude: f64,
+            datetime: DateTime<Utc>,
+            _radius_km: f64,
+            context_hours: u8,
+        ) -> Result<ObservationWithContext, WeatherError> {
+            let observation = self.hourly_at(latitude, longitude, datetime).await?;
+            let window = chrono::TimeDelta::hours(i64::from(context_hours));
+            let context = self
+                .frame
+                .iter()
+                .filter(|o| (o.datetime - observation.datetime).abs() <= window)
+                .cloned()
+                .collect();
+            Ok((observation, context))
+        }
+    }
+
+    /// Hourly observations from 08:00 to 18:00 UTC, with rain only at 10:00.
+    fn rain_frame() -> Vec<WeatherObservation> {
+        (8..=18)
+            .map(|hour| {
+                let mut observation = observation(
+                    Utc.with_ymd_and_hms(2023, 10, 26, hour, 0, 0).unwrap(),
+                    f64::from(hour),
+                );
+                observation.precipitation = Some(if hour == 10 { 1.5 } else { 0.0 });
+                observation
+            })
+            .collect()
+    }
+
+    fn sparse_provider() -> SparseStationProvider {
+        SparseStationProvider {
+            min_radius_km: 250.0,
+            radii: Mutex::new(Vec::new()),
+        }
+    }
 
     /// Helper function to create a `GpsInfo` struct for a known location (Amsterdam, NL).
     fn amsterdam_gps_info() -> GpsInfo {
@@ -95,7 +542,7 @@ mod tests {
             latitude: 52.379_189,
             longitude: 4.899_431,
             altitude: Some(0.0),
-            location: LocationName {
+            location: Some(LocationName {
                 latitude: 52.379_189,
                 longitude: 4.899_431,
                 name: "Amsterdam".to_string(),
@@ -103,9 +550,17 @@ mod tests {
                 admin2: String::new(),
                 country_code: "NL".to_string(),
                 country_name: Some("Netherlands".to_string()),
-            },
+            }),
             image_direction: None,
             image_direction_ref: None,
+            coordinate_precision: CoordinatePrecision::Exact,
+            satellites: None,
+            fix_mode: None,
+            measurement_in_progress: None,
+            fix_quality: None,
+            location_conflict: None,
+            timezone: None,
+            movement_from_previous: None,
         }
     }
 
@@ -120,7 +575,7 @@ mod tests {
             .unwrap()
             .to_utc();
 
-        let sun_info = compute_sun_info(daytime, &gps_info).unwrap();
+        let sun_info = compute_sun_info(daytime, &gps_info, None).unwrap();
         assert!(sun_info.is_daytime, "14:00 in summer should be daytime");
     }
 
@@ -133,22 +588,472 @@ mod tests {
             .unwrap()
             .to_utc();
 
-        let sun_info = compute_sun_info(nighttime, &gps_info).unwrap();
+        let sun_info = compute_sun_info(nighttime, &gps_info, None).unwrap();
         assert!(!sun_info.is_daytime, "23:00 in summer should be nighttime");
     }
 
+    #[test]
+    fn test_day_length_and_local_times_on_dst_day() {
+        let gps_info = amsterdam_gps_info();
+        // Clocks in Amsterdam moved from +01:00 to +02:00 at 02:00 on this day.
+        let noon = Amsterdam
+            .with_ymd_and_hms(2024, 3, 31, 12, 0, 0)
+            .unwrap()
+            .to_utc();
+        let timezone = TimeZoneInfo {
+            name: "Europe/Amsterdam".to_string(),
+            offset_seconds: UtcOffset(7200),
+            source: "Test".to_string(),
+            boundary_distance_km: None,
+            near_timezone_boundary: false,
+        };
+
+        let sun_info = compute_sun_info(noon, &gps_info, Some(&timezone)).unwrap();
+        let (sunrise, sunset) = (sun_info.sunrise.unwrap(), sun_info.sunset.unwrap());
+        let day_length = sun_info.day_length_seconds.unwrap();
+        assert_eq!(day_length, (sunset - sunrise).num_seconds());
+        // Roughly 12h50m of daylight; the DST switch must not add or remove an hour.
+        assert!((12 * 3600..13 * 3600).contains(&day_length));
+
+        let sunrise_local = sun_info.sunrise_local.unwrap();
+        let sunset_local = sun_info.sunset_local.unwrap();
+        assert_eq!(
+            sunrise_local,
+            sunrise.with_timezone(&Amsterdam).naive_local()
+        );
+        assert_eq!(sunrise_local.hour(), 7);
+        assert_eq!(sunset_local.hour(), 20);
+    }
+
+    #[test]
+    fn test_sun_info_without_timezone_still_has_day_length() {
+        let gps_info = amsterdam_gps_info();
+        let time = Utc.with_ymd_and_hms(2024, 7, 10, 12, 0, 0).unwrap();
+
+        let sun_info = compute_sun_info(time, &gps_info, None).unwrap();
+        assert!(sun_info.sunrise_local.is_none());
+        assert!(sun_info.sunset_local.is_none());
+        assert!(sun_info.day_length_seconds.is_some_and(|d| d > 16 * 3600));
+    }
+
+    #[test]
+    fn test_local_times_with_fixed_offset() {
+        let gps_info = amsterdam_gps_info();
+        let time = Utc.with_ymd_and_hms(2024, 7, 10, 12, 0, 0).unwrap();
+        let timezone = TimeZoneInfo {
+            name: "+02:00".to_string(),
+            offset_seconds: UtcOffset(7200),
+            source: "OffsetTimeOriginal".to_string(),
+            boundary_distance_km: None,
+            near_timezone_boundary: false,
+        };
+
+        let sun_info = compute_sun_info(time, &gps_info, Some(&timezone)).unwrap();
+        let sunset = sun_info.sunset.unwrap();
+        assert_eq!(
+            sun_info.sunset_local.unwrap(),
+            sunset.naive_utc() + chrono::Duration::hours(2)
+        );
+    }
+
     #[test]
     fn test_compute_sun_info_fails_with_invalid_gps_coordinates() {
         let mut invalid_gps = amsterdam_gps_info();
         invalid_gps.latitude = 91.0; // Invalid latitude
         let time = Utc::now();
-        let result = compute_sun_info(time, &invalid_gps);
+        let result = compute_sun_info(time, &invalid_gps, None);
         assert!(matches!(
             result.unwrap_err(),
             WeatherError::SunCalculationError
         ));
     }
 
+    fn flags_at(edit: impl Fn(&mut WeatherObservation)) -> WeatherFlags {
+        let mut capture = observation(Utc.with_ymd_and_hms(2024, 1, 10, 12, 0, 0).unwrap(), 5.0);
+        capture.relative_humidity = None;
+        edit(&mut capture);
+        WeatherFlags::new(&capture, std::slice::from_ref(&capture))
+    }
+
+    #[test]
+    fn test_missing_inputs_give_no_flags() {
+        let flags = flags_at(|hour| hour.temperature = None);
+        assert_eq!(flags, WeatherFlags::default());
+    }
+
+    #[test]
+    fn test_fog_and_wind_flags() {
+        let flags = flags_at(|hour| hour.condition = Some(WeatherCondition::FreezingFog));
+        assert_eq!(flags.was_foggy, Some(true));
+        let flags = flags_at(|hour| hour.condition = Some(WeatherCondition::Overcast));
+        assert_eq!(flags.was_foggy, Some(false));
+
+        let windy = |speed| flags_at(|hour| hour.wind_speed = Some(speed)).was_windy;
+        assert_eq!(windy(28.9), Some(false));
+        assert_eq!(windy(WINDY_THRESHOLD_KMH), Some(true));
+    }
+
+    #[test]
+    fn test_felt_temperature_flags() {
+        let felt = |temperature, wind_speed, humidity| {
+            flags_at(|hour| {
+                hour.temperature = Some(temperature);
+                hour.wind_speed = wind_speed;
+                hour.relative_humidity = humidity;
+            })
+            .temperature_felt_extreme
+        };
+        assert_eq!(felt(-19.0, None, None), Some(false));
+        assert_eq!(felt(-20.0, None, None), Some(true));
+        // -10°C feels like -17.9°C at 20km/h, and like -20.8°C at 40km/h.
+        assert_eq!(felt(-10.0, Some(20.0), None), Some(false));
+        assert_eq!(felt(-10.0, Some(40.0), None), Some(true));
+        // 32°C feels like 35°C at 40% humidity, and like 41°C at 70%.
+        assert_eq!(felt(32.0, None, Some(40)), Some(false));
+        assert_eq!(felt(32.0, None, Some(70)), Some(true));
+        assert_eq!(felt(39.9, None, None), Some(false));
+    }
+
+    #[test]
+    fn test_fresh_snow_flags() {
+        let capture_time = Utc.with_ymd_and_hms(2024, 1, 10, 12, 0, 0).unwrap();
+        let hour = |offset: i64, snow: Option<i32>, condition| {
+            let mut hour = observation(capture_time + chrono::Duration::hours(offset), -2.0);
+            hour.snow = snow;
+            hour.condition = condition;
+            hour
+        };
+        let flags = |hours: &[WeatherObservation]| {
+            let capture = hours.iter().find(|h| h.datetime == capture_time).unwrap();
+            WeatherFlags::new(capture, hours).fresh_snow_likely
+        };
+
+        assert_eq!(flags(&[hour(0, None, None)]), None);
+        // The depth grew over the context hours.
+        assert_eq!(
+            flags(&[hour(-3, Some(40), None), hour(0, Some(60), None)]),
+            Some(true)
+        );
+        assert_eq!(
+            flags(&[hour(-3, Some(60), None), hour(0, Some(60), None)]),
+            Some(false)
+        );
+        // It snowed earlier; snow after the capture doesn't count.
+        let snow = Some(WeatherCondition::LightSnowfall);
+        let clear = Some(WeatherCondition::Clear);
+        assert_eq!(
+            flags(&[hour(-2, None, snow), hour(0, None, clear)]),
+            Some(true)
+        );
+        assert_eq!(
+            flags(&[hour(0, None, clear), hour(2, None, snow)]),
+            Some(false)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_get_weather_info_uses_provider_observation() {
+        let provider = MockProvider::new(Some(18.5));
+        let datetime = Utc.with_ymd_and_hms(2023, 10, 26, 12, 0, 0).unwrap();
+
+        let weather_info = get_weather_info(
+            &provider,
+            &amsterdam_gps_info(),
+            datetime,
+            None,
+            &RadiusStrategy::Fixed(100.0),
+            0,
+        )
+        .await
+        .unwrap();
+        assert_eq!(weather_info.hourly.unwrap().temperature, Some(18.5));
+        assert!(weather_info.sun_info.is_daytime);
+        assert_eq!(
+            weather_info.context_flags.temperature_felt_extreme,
+            Some(false)
+        );
+        assert_eq!(weather_info.context_flags.was_windy, None);
+    }
+
+    #[tokio::test]
+    async fn test_get_weather_info_keeps_sun_info_without_observation() {
+        let provider = MockProvider::new(None);
+        let datetime = Utc.with_ymd_and_hms(2023, 10, 26, 12, 0, 0).unwrap();
+
+        let weather_info = get_weather_info(
+            &provider,
+            &amsterdam_gps_info(),
+            datetime,
+            None,
+            &RadiusStrategy::Fixed(100.0),
+            0,
+        )
+        .await
+        .unwrap();
+        assert!(weather_info.hourly.is_none());
+        assert!(weather_info.sun_info.sunrise.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_adaptive_radius_expands_until_data_is_found() {
+        let provider = sparse_provider();
+        let datetime = Utc.with_ymd_and_hms(2023, 10, 26, 12, 0, 0).unwrap();
+        let strategy = RadiusStrategy::Adaptive {
+            start_km: 50.0,
+            max_km: 1000.0,
+            step_factor: 2.0,
+        };
+
+        let weather_info = get_weather_info(
+            &provider,
+            &amsterdam_gps_info(),
+            datetime,
+            None,
+            &strategy,
+            0,
+        )
+        .await
+        .unwrap();
+        assert_eq!(weather_info.hourly.unwrap().temperature, Some(5.0));
+        assert!((weather_info.search_radius_used_km - 400.0).abs() < f64::EPSILON);
+        assert_eq!(
+            *provider.radii.lock().unwrap(),
+            vec![50.0, 100.0, 200.0, 400.0]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_adaptive_radius_stops_at_max() {
+        let provider = sparse_provider();
+        let datetime = Utc.with_ymd_and_hms(2023, 10, 26, 12, 0, 0).unwrap();
+        let strategy = RadiusStrategy::Adaptive {
+            start_km: 50.0,
+            max_km: 150.0,
+            step_factor: 2.0,
+        };
+
+        let weather_info = get_weather_info(
+            &provider,
+            &amsterdam_gps_info(),
+            datetime,
+            None,
+            &strategy,
+            0,
+        )
+        .await
+        .unwrap();
+        assert!(weather_info.hourly.is_none());
+        assert!((weather_info.search_radius_used_km - 150.0).abs() < f64::EPSILON);
+        assert_eq!(*provider.radii.lock().unwrap(), vec![50.0, 100.0, 150.0]);
+    }
+
+    #[test]
+    fn test_adaptive_radius_that_cannot_widen_is_invalid() {
+        let adaptive = |start_km, max_km, step_factor| RadiusStrategy::Adaptive {
+            start_km,
+            max_km,
+            step_factor,
+        };
+        assert!(adaptive(50.0, 1000.0, 2.0).validate().is_ok());
+        assert!(adaptive(0.0, 1000.0, 2.0).validate().is_err());
+        assert!(adaptive(f64::NAN, 1000.0, 2.0).validate().is_err());
+        assert!(adaptive(50.0, f64::INFINITY, 2.0).validate().is_err());
+        assert!(adaptive(50.0, 10.0, 2.0).validate().is_err());
+        assert!(adaptive(50.0, 1000.0, 1.0).validate().is_err());
+        assert!(adaptive(50.0, 1000.0, f64::NAN).validate().is_err());
+        assert!(RadiusStrategy::Fixed(100.0).validate().is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_adaptive_radius_steps_are_capped() {
+        let provider = sparse_provider();
+        let datetime = Utc.with_ymd_and_hms(2023, 10, 26, 12, 0, 0).unwrap();
+        // Not validated, like a strategy set through `with_config`.
+        let strategy = RadiusStrategy::Adaptive {
+            start_km: 0.0,
+            max_km: 1000.0,
+            step_factor: 2.0,
+        };
+
+        let weather_info = get_weather_info(
+            &provider,
+            &amsterdam_gps_info(),
+            datetime,
+            None,
+            &strategy,
+            0,
+        )
+        .await
+        .unwrap();
+        assert!(weather_info.hourly.is_none());
+        assert_eq!(provider.radii.lock().unwrap().len(), MAX_RADIUS_STEPS);
+    }
+
+    #[tokio::test]
+    async fn test_fixed_radius_does_not_expand() {
+        let provider = sparse_provider();
+        let datetime = Utc.with_ymd_and_hms(2023, 10, 26, 12, 0, 0).unwrap();
+
+        let weather_info = get_weather_info(
+            &provider,
+            &amsterdam_gps_info(),
+            datetime,
+            None,
+            &RadiusStrategy::Fixed(100.0),
+            0,
+        )
+        .await
+        .unwrap();
+        assert!(weather_info.hourly.is_none());
+        assert_eq!(*provider.radii.lock().unwrap(), vec![100.0]);
+    }
+
+    #[tokio::test]
+    async fn test_weather_context_around_a_dry_hour_after_rain() {
+        // Shuffled, the context must still come out ordered.
+        let mut frame = rain_frame();
+        frame.reverse();
+        let provider = FrameProvider { frame };
+        let datetime = Utc.with_ymd_and_hms(2023, 10, 26, 12, 30, 0).unwrap();
+
+        let weather_info = get_weather_info(
+            &provider,
+            &amsterdam_gps_info(),
+            datetime,
+            None,
+            &RadiusStrategy::Fixed(100.0),
+            3,
+        )
+        .await
+        .unwrap();
+        let hourly = weather_info.hourly.unwrap();
+        assert_eq!(hourly.precipitation, Some(0.0));
+
+        let context = weather_info.context.unwrap();
+        let offsets: Vec<i8> = context.hours.iter().map(|h| h.offset_hours).collect();
+        assert_eq!(offsets, vec![-3, -2, -1, 0, 1, 2, 3]);
+        assert_eq!(context.hours[0].temperature, Some(9.0));
+        assert_eq!(context.hours[1].precipitation, Some(1.5));
+        assert_eq!(context.was_raining_recently, Some(true));
+    }
+
+    #[tokio::test]
+    async fn test_weather_context_rain_out_of_reach() {
+        let provider = FrameProvider {
+            frame: rain_frame(),
+        };
+        // Rain at 10:00 is 4 hours before 14:00, and only one hour is requested.
+        let datetime = Utc.with_ymd_and_hms(2023, 10, 26, 14, 0, 0).unwrap();
+        for context_hours in [1, 3] {
+            let context = get_weather_info(
+                &provider,
+                &amsterdam_gps_info(),
+                datetime,
+                None,
+                &RadiusStrategy::Fixed(100.0),
+                context_hours,
+            )
+            .await
+            .unwrap()
+            .context
+            .unwrap();
+            assert_eq!(context.was_raining_recently, Some(false));
+            assert_eq!(context.hours.len(), usize::from(context_hours) * 2 + 1);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_weather_context_is_off_by_default_and_limited_to_fetched_hours() {
+        let provider = MockProvider::new(Some(18.5));
+        let datetime = Utc.with_ymd_and_hms(2023, 10, 26, 12, 0, 0).unwrap();
+        let gps_info = amsterdam_gps_info();
+        let get = |context_hours| {
+            get_weather_info(
+                &provider,
+                &gps_info,
+                datetime,
+                None,
+                &RadiusStrategy::Fixed(100.0),
+                context_hours,
+            )
+        };
+
+        assert!(get(0).await.unwrap().context.is_none());
+        // The mock provider only fetches the capture hour, which has no precipitation.
+        let context = get(3).await.unwrap().context.unwrap();
+        assert_eq!(context.hours.len(), 1);
+        assert_eq!(context.hours[0].offset_hours, 0);
+        assert_eq!(context.was_raining_recently, None);
+        assert_eq!(provider.calls.load(std::sync::atomic::Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_capture_just_after_local_midnight() {
+        // Hourly observations from 20:00 to 04:00 UTC, spanning midnight.
+        let start = Utc.with_ymd_and_hms(2023, 10, 26, 20, 0, 0).unwrap();
+        let frame = (0..=8)
+            .map(|hour| {
+                let datetime = start + chrono::TimeDelta::hours(hour);
+                #[allow(clippy::cast_precision_loss)]
+                observation(datetime, hour as f64)
+            })
+            .collect();
+        let provider = FrameProvider { frame };
+        let amsterdam = TimeZoneInfo {
+            name: "Europe/Amsterdam".to_string(),
+            offset_seconds: UtcOffset(7200),
+            source: "GPS".to_string(),
+            boundary_distance_km: None,
+            near_timezone_boundary: false,
+        };
+        // 00:30 in Amsterdam (UTC+2) is 22:30 UTC on the day before.
+        let datetime = Amsterdam
+            .with_ymd_and_hms(2023, 10, 27, 0, 30, 0)
+            .unwrap()
+            .with_timezone(&Utc);
+
+        let weather_info = get_weather_info(
+            &provider,
+            &amsterdam_gps_info(),
+            datetime,
+            Some(&amsterdam),
+            &RadiusStrategy::Fixed(100.0),
+            0,
+        )
+        .await
+        .unwrap();
+        let hourly = weather_info.hourly.unwrap();
+        assert_eq!(
+            hourly.datetime,
+            Utc.with_ymd_and_hms(2023, 10, 26, 22, 0, 0).unwrap()
+        );
+        assert_eq!(hourly.temperature, Some(2.0));
+        assert_eq!(
+            weather_info.weather_date_used,
+            NaiveDate::from_ymd_opt(2023, 10, 26)
+        );
+        assert!(weather_info.crossed_day_boundary);
+
+        // 00:30 UTC is on the same day in UTC.
+        let datetime = Utc.with_ymd_and_hms(2023, 10, 27, 0, 30, 0).unwrap();
+        let weather_info = get_weather_info(
+            &provider,
+            &amsterdam_gps_info(),
+            datetime,
+            None,
+            &RadiusStrategy::Fixed(100.0),
+            0,
+        )
+        .await
+        .unwrap();
+        assert_eq!(weather_info.hourly.unwrap().temperature, Some(4.0));
+        assert_eq!(
+            weather_info.weather_date_used,
+            NaiveDate::from_ymd_opt(2023, 10, 27)
+        );
+        assert!(!weather_info.crossed_day_boundary);
+    }
+
     /// This is an integration test that makes a real network call to the Meteostat API.
     #[tokio::test(flavor = "multi_thread")]
     async fn test_get_weather_info_integration_success() {
@@ -159,10 +1064,18 @@ mod tests {
         let gps_info = amsterdam_gps_info();
         // A date in the past to ensure data is available
         let datetime = Utc.with_ymd_and_hms(2023, 10, 26, 12, 0, 0).unwrap();
-        let radius = 100.0;
+        let provider = MeteostatProvider::new(client, 100.0);
 
         // 2. Execute
-        let result = get_weather_info(&client, &gps_info, datetime, radius).await;
+        let result = get_weather_info(
+            &provider,
+            &gps_info,
+            datetime,
+            None,
+            &RadiusStrategy::Fixed(100.0),
+            0,
+        )
+        .await;
 
         // 3. Assert
         assert!(
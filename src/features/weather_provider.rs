@@ -0,0 +1,681 @@
+use crate::features::error::WeatherError;
+use async_trait::async_trait;
+use chrono::{DateTime, NaiveDate, TimeDelta, Utc};
+use meteostat::{Frequency, Hourly, InventoryRequest, LatLon, Meteostat, RequiredData};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// A single hourly weather observation, as returned by a [`WeatherProvider`].
+pub type WeatherObservation = Hourly;
+
+/// The observation at the capture hour, and the observations around it ordered by time
+/// (including the capture hour itself).
+pub type ObservationWithContext = (WeatherObservation, Vec<WeatherObservation>);
+
+/// How often a [`WeatherProvider`] found the data of a lookup in its cache, see
+/// [`WeatherProvider::cache_stats`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CacheStats {
+    /// Lookups whose station data was cached already.
+    pub hits: u64,
+    /// Lookups that downloaded the station data first.
+    pub misses: u64,
+    /// The size of the files in the cache folder.
+    pub bytes_on_disk: u64,
+}
+
+/// A station whose data [`WeatherProvider::prewarm`] couldn't fetch.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PrewarmFailure {
+    pub station: String,
+    pub error: String,
+}
+
+/// The stations [`WeatherProvider::prewarm`] fetched, in order of station ID.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PrewarmReport {
+    /// Stations whose hourly data is now cached.
+    pub fetched: Vec<String>,
+    pub failed: Vec<PrewarmFailure>,
+    /// Locations without a station within the radius that has data for any of the dates.
+    pub without_station: Vec<(f64, f64)>,
+}
+
+/// The folder `Meteostat::new` caches its data in.
+#[must_use]
+pub fn default_meteostat_cache_folder() -> Option<PathBuf> {
+    dirs::cache_dir().map(|folder| folder.join("meteostat_rs_cache"))
+}
+
+/// The first and last UTC date to fetch for the hour containing `datetime`: the dates of the
+/// hours up to `context_hours` (at least one) before and after it. Near midnight these are two
+/// days, so the hour is still found when the day of the capture has no data.
+#[must_use]
+pub fn required_dates(datetime: DateTime<Utc>, context_hours: u8) -> (NaiveDate, NaiveDate) {
+    let window = TimeDelta::hours(i64::from(context_hours.max(1)));
+    (
+        (datetime - window).date_naive(),
+        (datetime + window).date_naive(),
+    )
+}
+
+/// A source of historical hourly weather data.
+///
+/// Implementations should return [`WeatherError::NoDataAvailable`] when the source has no
+/// observation for the requested hour, so that a [`ChainedProvider`] can fall back to another
+/// source.
+#[async_trait]
+pub trait WeatherProvider: Send + Sync {
+    /// Fetches the observation for the hour containing `datetime` at the given coordinates.
+    async fn hourly_at(
+        &self,
+        latitude: f64,
+        longitude: f64,
+        datetime: DateTime<Utc>,
+    ) -> Result<WeatherObservation, WeatherError>;
+
+    /// Like [`WeatherProvider::hourly_at`], but limits the search to `radius_km` around the
+    /// coordinates. Providers without a notion of search radius ignore it.
+    async fn hourly_within(
+        &self,
+        latitude: f64,
+        longitude: f64,
+        datetime: DateTime<Utc>,
+        _radius_km: f64,
+    ) -> Result<WeatherObservation, WeatherError> {
+        self.hourly_at(latitude, longitude, datetime).await
+    }
+
+    /// Like [`WeatherProvider::hourly_within`], but also returns the observations up to
+    /// `context_hours` before and after `datetime`, when the provider already fetched them.
+    /// The default returns only the capture hour, so that context never costs extra requests.
+    async fn hourly_with_context(
+        &self,
+        latitude: f64,
+        longitude: f64,
+        datetime: DateTime<Utc>,
+        radius_km: f64,
+        _context_hours: u8,
+    ) -> Result<ObservationWithContext, WeatherError> {
+        let observation = self
+            .hourly_within(latitude, longitude, datetime, radius_km)
+            .await?;
+        Ok((observation.clone(), vec![observation]))
+    }
+
+    /// Fetches the data for `locations` on `dates` into the provider's cache, so that later
+    /// lookups there don't wait for downloads. Providers without a cache do nothing.
+    async fn prewarm(
+        &self,
+        _locations: &[(f64, f64)],
+        _dates: &[NaiveDate],
+        _radius_km: f64,
+    ) -> Result<PrewarmReport, WeatherError> {
+        Ok(PrewarmReport::default())
+    }
+
+    /// The cache hits and misses of the lookups so far, and the size of the cache. All zero
+    /// for providers without a cache.
+    fn cache_stats(&self) -> CacheStats {
+        CacheStats::default()
+    }
+}
+
+#[async_trait]
+impl<T: WeatherProvider + ?Sized> WeatherProvider for Arc<T> {
+    async fn hourly_at(
+        &self,
+        latitude: f64,
+        longitude: f64,
+        datetime: DateTime<Utc>,
+    ) -> Result<WeatherObservation, WeatherError> {
+        (**self).hourly_at(latitude, longitude, datetime).await
+    }
+
+    async fn hourly_within(
+        &self,
+        latitude: f64,
+        longitude: f64,
+        datetime: DateTime<Utc>,
+        radius_km: f64,
+    ) -> Result<WeatherObservation, WeatherError> {
+        (**self)
+            .hourly_within(latitude, longitude, datetime, radius_km)
+            .await
+    }
+
+    async fn hourly_with_context(
+        &self,
+        latitude: f64,
+        longitude: f64,
+        datetime: DateTime<Utc>,
+        radius_km: f64,
+        context_hours: u8,
+    ) -> Result<ObservationWithContext, WeatherError> {
+        (**self)
+            .hourly_with_context(latitude, longitude, datetime, radius_km, context_hours)
+            .await
+    }
+
+    async fn prewarm(
+        &self,
+        locations: &[(f64, f64)],
+        dates: &[NaiveDate],
+        radius_km: f64,
+    ) -> Result<PrewarmReport, WeatherError> {
+        (**self).prewarm(locations, dates, radius_km).await
+    }
+
+    fn cache_stats(&self) -> CacheStats {
+        (**self).cache_stats()
+    }
+}
+
+/// The default provider, backed by Meteostat weather station data.
+pub struct MeteostatProvider {
+    client: Arc<Meteostat>,
+    search_radius_km: f64,
+    cache_folder: Option<PathBuf>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+/// The total size of the files directly in `folder`, which is where Meteostat keeps its cache.
+fn folder_size(folder: &Path) -> u64 {
+    std::fs::read_dir(folder).map_or(0, |entries| {
+        entries
+            .filter_map(Result::ok)
+            .filter_map(|entry| entry.metadata().ok())
+            .filter(std::fs::Metadata::is_file)
+            .map(|metadata| metadata.len())
+            .sum()
+    })
+}
+
+impl MeteostatProvider {
+    /// Wraps a `Meteostat` client, searching for stations within `search_radius_km`.
+    #[must_use]
+    pub fn new(client: Meteostat, search_radius_km: f64) -> Self {
+        Self::shared(Arc::new(client), search_radius_km)
+    }
+
+    /// Like [`MeteostatProvider::new`], but with a client that is shared with other providers.
+    #[must_use]
+    pub const fn shared(client: Arc<Meteostat>, search_radius_km: f64) -> Self {
+        Self {
+            client,
+            search_radius_km,
+            cache_folder: None,
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+
+    /// Sets the cache folder of the client, which [`WeatherProvider::cache_stats`] needs to
+    /// tell hits from misses and to measure the cache. Without it, the stats stay zero.
+    #[must_use]
+    pub fn with_cache_folder(mut self, cache_folder: impl Into<PathBuf>) -> Self {
+        self.cache_folder = Some(cache_folder.into());
+        self
+    }
+
+    /// The ID of the station that Meteostat picks for a location: the nearest one within
+    /// `radius_km` whose inventory covers `required_data`.
+    fn nearest_station(
+        &self,
+        latitude: f64,
+        longitude: f64,
+        radius_km: f64,
+        required_data: RequiredData,
+    ) -> Option<String> {
+        self.client
+            .find_stations()
+            .location(LatLon(latitude, longitude))
+            .inventory_request(InventoryRequest::new(Frequency::Hourly, required_data))
+            .max_distance_km(radius_km)
+            .station_limit(1)
+            .call()
+            .into_iter()
+            .next()
+            .map(|found| found.station.id)
+    }
+
+    /// The observation at `datetime` and its context, from the nearest station with data on
+    /// `date`.
+    async fn observation_on(
+        &self,
+        date: NaiveDate,
+        latitude: f64,
+        longitude: f64,
+        datetime: DateTime<Utc>,
+        radius_km: f64,
+        context_hours: u8,
+    ) -> Result<ObservationWithContext, WeatherError> {
+        let required_data = RequiredData::SpecificDate(date);
+        if let Some(station) = self.nearest_station(latitude, longitude, radius_km, required_data) {
+            self.record_lookup(&station);
+        }
+        let hourly_frame = self
+            .client
+            .hourly()
+            .location(LatLon(latitude, longitude))
+            .required_data(required_data)
+            .max_distance_km(radius_km)
+            .call()
+            .await?;
+
+        // Handle the case where there is data, but not for the specific hour requested
+        let observation = hourly_frame
+            .get_at(datetime)
+            .map_err(|_| WeatherError::NoDataAvailable)?
+            .collect_single_hourly()
+            .map_err(|_| WeatherError::NoDataAvailable)?;
+        if context_hours == 0 {
+            return Ok((observation.clone(), vec![observation]));
+        }
+        let window = TimeDelta::hours(i64::from(context_hours));
+        let context = hourly_frame
+            .get_range(datetime - window, datetime + window)
+            .and_then(|frame| frame.collect_hourly())
+            .unwrap_or_else(|_| vec![observation.clone()]);
+        Ok((observation, context))
+    }
+
+    /// Counts a lookup at `station` as a hit when its hourly data is in the cache folder.
+    fn record_lookup(&self, station: &str) {
+        let Some(cache_folder) = &self.cache_folder else {
+            return;
+        };
+        let cached = cache_folder
+            .join(format!("hourly-{station}.parquet"))
+            .is_file();
+        let counter = if cached { &self.hits } else { &self.misses };
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+#[async_trait]
+impl WeatherProvider for MeteostatProvider {
+    async fn hourly_at(
+        &self,
+        latitude: f64,
+        longitude: f64,
+        datetime: DateTime<Utc>,
+    ) -> Result<WeatherObservation, WeatherError> {
+        self.hourly_within(latitude, longitude, datetime, self.search_radius_km)
+            .await
+    }
+
+    async fn hourly_within(
+        &self,
+        latitude: f64,
+        longitude: f64,
+        datetime: DateTime<Utc>,
+        radius_km: f64,
+    ) -> Result<WeatherObservation, WeatherError> {
+        self.hourly_with_context(latitude, longitude, datetime, radius_km, 0)
+            .await
+            .map(|(observation, _)| observation)
+    }
+
+    /// The station must have data for the UTC date of the capture hour or, when no station
+    /// does, for the other date of [`required_dates`]. Its frame holds more than that day, so
+    /// the context comes from it without another request, also across midnight.
+    async fn hourly_with_context(
+        &self,
+        latitude: f64,
+        longitude: f64,
+        datetime: DateTime<Utc>,
+        radius_km: f64,
+        context_hours: u8,
+    ) -> Result<ObservationWithContext, WeatherError> {
+        let capture_date = datetime.date_naive();
+        let (start, end) = required_dates(datetime, context_hours);
+        let result = self
+            .observation_on(
+                capture_date,
+                latitude,
+                longitude,
+                datetime,
+                radius_km,
+                context_hours,
+            )
+            .await;
+        let Some(adjacent) = [start, end].into_iter().find(|date| *date != capture_date) else {
+            return result;
+        };
+        match result {
+            Ok(found) => Ok(found),
+            Err(e) => self
+                .observation_on(
+                    adjacent,
+                    latitude,
+                    longitude,
+                    datetime,
+                    radius_km,
+                    context_hours,
+                )
+                .await
+                .map_err(|_| e),
+        }
+    }
+
+    /// Each location is matched to a station for each date, and each station is fetched once,
+    /// requiring data up to its latest date so that an older cache file is refreshed.
+    async fn prewarm(
+        &self,
+        locations: &[(f64, f64)],
+        dates: &[NaiveDate],
+        radius_km: f64,
+    ) -> Result<PrewarmReport, WeatherError> {
+        let mut report = PrewarmReport::default();
+        let mut latest_dates: BTreeMap<String, NaiveDate> = BTreeMap::new();
+        for &(latitude, longitude) in locations {
+            let mut found = false;
+            for &date in dates {
+                let required_data = RequiredData::SpecificDate(date);
+                if let Some(station) =
+                    self.nearest_station(latitude, longitude, radius_km, required_data)
+                {
+                    found = true;
+                    let latest = latest_dates.entry(station).or_insert(date);
+                    *latest = (*latest).max(date);
+                }
+            }
+            if !found && !dates.is_empty() {
+                report.without_station.push((latitude, longitude));
+            }
+        }
+        for (station, date) in latest_dates {
+            let fetched = self
+                .client
+                .hourly()
+                .station(&station)
+                .required_data(RequiredData::SpecificDate(date))
+                .call()
+                .await;
+            match fetched {
+                Ok(_) => report.fetched.push(station),
+                Err(error) => report.failed.push(PrewarmFailure {
+                    station,
+                    error: error.to_string(),
+                }),
+            }
+        }
+        Ok(report)
+    }
+
+    fn cache_stats(&self) -> CacheStats {
+        CacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            bytes_on_disk: self.cache_folder.as_deref().map_or(0, folder_size),
+        }
+    }
+}
+
+/// The provider of an analyzer built with `weather(false)`, which never looks the weather up.
+pub struct NoWeather;
+
+#[async_trait]
+impl WeatherProvider for NoWeather {
+    async fn hourly_at(
+        &self,
+        _latitude: f64,
+        _longitude: f64,
+        _datetime: DateTime<Utc>,
+    ) -> Result<WeatherObservation, WeatherError> {
+        Err(WeatherError::NoDataAvailable)
+    }
+}
+
+/// Queries `primary` first and falls back to `fallback` when it has no data for the hour.
+pub struct ChainedProvider<P, F> {
+    primary: P,
+    fallback: F,
+}
+
+impl<P: WeatherProvider, F: WeatherProvider> ChainedProvider<P, F> {
+    #[must_use]
+    pub const fn new(primary: P, fallback: F) -> Self {
+        Self { primary, fallback }
+    }
+}
+
+#[async_trait]
+impl<P: WeatherProvider, F: WeatherProvider> WeatherProvider for ChainedProvider<P, F> {
+    async fn hourly_at(
+        &self,
+        latitude: f64,
+        longitude: f64,
+        datetime: DateTime<Utc>,
+    ) -> Result<WeatherObservation, WeatherError> {
+        match self.primary.hourly_at(latitude, longitude, datetime).await {
+            Err(WeatherError::NoDataAvailable) => {
+                self.fallback.hourly_at(latitude, longitude, datetime).await
+            }
+            result => result,
+        }
+    }
+
+    async fn hourly_within(
+        &self,
+        latitude: f64,
+        longitude: f64,
+        datetime: DateTime<Utc>,
+        radius_km: f64,
+    ) -> Result<WeatherObservation, WeatherError> {
+        match self
+            .primary
+            .hourly_within(latitude, longitude, datetime, radius_km)
+            .await
+        {
+            Err(WeatherError::NoDataAvailable) => {
+                self.fallback
+                    .hourly_within(latitude, longitude, datetime, radius_km)
+                    .await
+            }
+            result => result,
+        }
+    }
+
+    async fn hourly_with_context(
+        &self,
+        latitude: f64,
+        longitude: f64,
+        datetime: DateTime<Utc>,
+        radius_km: f64,
+        context_hours: u8,
+    ) -> Result<ObservationWithContext, WeatherError> {
+        match self
+            .primary
+            .hourly_with_context(latitude, longitude, datetime, radius_km, context_hours)
+            .await
+        {
+            Err(WeatherError::NoDataAvailable) => {
+                self.fallback
+                    .hourly_with_context(latitude, longitude, datetime, radius_km, context_hours)
+                    .await
+            }
+            result => result,
+        }
+    }
+
+    /// Only the primary is prewarmed, as the fallback is only asked for hours it lacks.
+    async fn prewarm(
+        &self,
+        locations: &[(f64, f64)],
+        dates: &[NaiveDate],
+        radius_km: f64,
+    ) -> Result<PrewarmReport, WeatherError> {
+        self.primary.prewarm(locations, dates, radius_km).await
+    }
+
+    fn cache_stats(&self) -> CacheStats {
+        let (primary, fallback) = (self.primary.cache_stats(), self.fallback.cache_stats());
+        CacheStats {
+            hits: primary.hits + fallback.hits,
+            misses: primary.misses + fallback.misses,
+            bytes_on_disk: primary.bytes_on_disk + fallback.bytes_on_disk,
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// A provider returning a canned observation, or `NoDataAvailable` when `temperature` is `None`.
+    pub struct MockProvider {
+        pub temperature: Option<f64>,
+        pub calls: AtomicUsize,
+    }
+
+    impl MockProvider {
+        pub const fn new(temperature: Option<f64>) -> Self {
+            Self {
+                temperature,
+                calls: AtomicUsize::new(0),
+            }
+        }
+    }
+
+    pub fn observation(datetime: DateTime<Utc>, temperature: f64) -> WeatherObservation {
+        Hourly {
+            datetime,
+            temperature: Some(temperature),
+            dew_point: None,
+            relative_humidity: Some(70),
+            precipitation: None,
+            snow: None,
+            wind_direction: None,
+            wind_speed: None,
+            peak_wind_gust: None,
+            pressure: None,
+            sunshine_minutes: None,
+            condition: None,
+        }
+    }
+
+    #[async_trait]
+    impl WeatherProvider for MockProvider {
+        async fn hourly_at(
+            &self,
+            _latitude: f64,
+            _longitude: f64,
+            datetime: DateTime<Utc>,
+        ) -> Result<WeatherObservation, WeatherError> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            self.temperature
+                .map(|t| observation(datetime, t))
+                .ok_or(WeatherError::NoDataAvailable)
+        }
+    }
+
+    fn datetime() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2023, 10, 26, 12, 0, 0).unwrap()
+    }
+
+    #[test]
+    fn test_required_dates_span_midnight() {
+        let date = |day| NaiveDate::from_ymd_opt(2023, 10, day).unwrap();
+        let noon = Utc.with_ymd_and_hms(2023, 10, 26, 12, 0, 0).unwrap();
+        assert_eq!(required_dates(noon, 0), (date(26), date(26)));
+        assert_eq!(required_dates(noon, 12), (date(26), date(27)));
+        let after_midnight = Utc.with_ymd_and_hms(2023, 10, 27, 0, 30, 0).unwrap();
+        assert_eq!(required_dates(after_midnight, 0), (date(26), date(27)));
+        let before_midnight = Utc.with_ymd_and_hms(2023, 10, 26, 23, 30, 0).unwrap();
+        assert_eq!(required_dates(before_midnight, 0), (date(26), date(27)));
+    }
+
+    #[tokio::test]
+    async fn test_mock_provider_returns_canned_data() {
+        let provider = MockProvider::new(Some(21.5));
+        let observation = provider.hourly_at(52.0, 4.0, datetime()).await.unwrap();
+        assert_eq!(observation.temperature, Some(21.5));
+        assert_eq!(observation.datetime, datetime());
+    }
+
+    #[tokio::test]
+    async fn test_chained_provider_uses_primary_when_it_has_data() {
+        let chained =
+            ChainedProvider::new(MockProvider::new(Some(10.0)), MockProvider::new(Some(20.0)));
+        let observation = chained.hourly_at(52.0, 4.0, datetime()).await.unwrap();
+        assert_eq!(observation.temperature, Some(10.0));
+        assert_eq!(chained.fallback.calls.load(Ordering::SeqCst), 0);
+    }
+
+    #[tokio::test]
+    async fn test_chained_provider_falls_back_on_no_data() {
+        let chained = ChainedProvider::new(MockProvider::new(None), MockProvider::new(Some(20.0)));
+        let observation = chained.hourly_at(52.0, 4.0, datetime()).await.unwrap();
+        assert_eq!(observation.temperature, Some(20.0));
+        assert_eq!(chained.primary.calls.load(Ordering::SeqCst), 1);
+        assert_eq!(chained.fallback.calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_chained_provider_reports_no_data_when_both_fail() {
+        let chained = ChainedProvider::new(MockProvider::new(None), MockProvider::new(None));
+        let result = chained.hourly_at(52.0, 4.0, datetime()).await;
+        assert!(matches!(result, Err(WeatherError::NoDataAvailable)));
+    }
+
+    #[tokio::test]
+    async fn test_providers_without_cache_report_nothing() {
+        let chained = ChainedProvider::new(MockProvider::new(None), MockProvider::new(Some(20.0)));
+        let date = datetime().date_naive();
+        let report = chained
+            .prewarm(&[(52.0, 4.0)], &[date], 50.0)
+            .await
+            .unwrap();
+        assert_eq!(report, PrewarmReport::default());
+        chained.hourly_at(52.0, 4.0, datetime()).await.unwrap();
+        assert_eq!(chained.cache_stats(), CacheStats::default());
+    }
+
+    /// This is an integration test that downloads station data from Meteostat.
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_prewarm_fills_the_cache_folder() {
+        let cache_folder =
+            std::env::temp_dir().join(format!("media_analyzer_prewarm_{}", std::process::id()));
+        let client = Meteostat::with_cache_folder(cache_folder.clone())
+            .await
+            .expect("Failed to create Meteostat client");
+        let provider = MeteostatProvider::new(client, 50.0).with_cache_folder(&cache_folder);
+        let date = datetime().date_naive();
+
+        // Two spots in Amsterdam share a station, and the middle of the Atlantic has none.
+        let locations = [(52.373, 4.892), (52.374, 4.893), (40.0, -40.0)];
+        let report = provider.prewarm(&locations, &[date], 50.0).await.unwrap();
+        assert_eq!(report.fetched.len(), 1, "{report:?}");
+        assert!(report.failed.is_empty());
+        assert_eq!(report.without_station, vec![(40.0, -40.0)]);
+        let station = &report.fetched[0];
+        assert!(
+            cache_folder
+                .join(format!("hourly-{station}.parquet"))
+                .is_file()
+        );
+        let stats = provider.cache_stats();
+        assert_eq!((stats.hits, stats.misses), (0, 0));
+        assert!(stats.bytes_on_disk > 0);
+
+        // Lookups in Amsterdam find the prewarmed data, one in Paris has to download it.
+        provider.hourly_at(52.373, 4.892, datetime()).await.unwrap();
+        provider.hourly_at(48.857, 2.352, datetime()).await.unwrap();
+        let after = provider.cache_stats();
+        assert_eq!((after.hits, after.misses), (1, 1));
+        assert!(after.bytes_on_disk > stats.bytes_on_disk);
+
+        let _ = std::fs::remove_dir_all(&cache_folder);
+    }
+}
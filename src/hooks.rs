@@ -0,0 +1,180 @@
+//! User callbacks that run after the built-in analysis stages, to enrich or override their
+//! results.
+//!
+//! A hook that panics is reported with `log::warn!` and its changes are discarded, so one
+//! misbehaving hook can't fail the analysis or poison the analyzer.
+
+use crate::features::gps::GpsInfo;
+use crate::tags::structs::MediaFeatures;
+use crate::time::structs::TimeInfo;
+use std::panic::{AssertUnwindSafe, catch_unwind};
+use std::sync::Arc;
+
+/// Runs after GPS extraction and reverse geocoding.
+pub type GpsHook = Arc<dyn Fn(&mut Option<GpsInfo>) + Send + Sync>;
+/// Runs after the capture time is determined.
+pub type TimeHook = Arc<dyn Fn(&mut TimeInfo) + Send + Sync>;
+/// Runs after the feature flags are detected.
+pub type FeaturesHook = Arc<dyn Fn(&mut MediaFeatures) + Send + Sync>;
+/// Runs before the weather lookup; returning `false` skips it.
+pub type WeatherGate = Arc<dyn Fn(&GpsInfo, &TimeInfo) -> bool + Send + Sync>;
+
+#[derive(Clone, Default)]
+pub struct AnalysisHooks {
+    pub on_gps: Option<GpsHook>,
+    pub on_time: Option<TimeHook>,
+    pub on_features: Option<FeaturesHook>,
+    pub before_weather: Option<WeatherGate>,
+}
+
+/// Runs `hook` on a copy of `value`, and only keeps the changes when it returns normally.
+fn run_mutating_hook<T: Clone>(stage: &str, hook: &dyn Fn(&mut T), value: &mut T) {
+    let mut updated = value.clone();
+    match catch_unwind(AssertUnwindSafe(|| hook(&mut updated))) {
+        Ok(()) => *value = updated,
+        Err(_) => log::warn!("The {stage} hook panicked, its changes were discarded"),
+    }
+}
+
+impl AnalysisHooks {
+    pub fn run_gps(&self, gps: &mut Option<GpsInfo>) {
+        if let Some(hook) = &self.on_gps {
+            run_mutating_hook("on_gps", hook.as_ref(), gps);
+        }
+    }
+
+    /// Runs the `on_time` hook, then derives `datetime_local_iso` from what it left.
+    pub fn run_time(&self, time: &mut TimeInfo) {
+        if let Some(hook) = &self.on_time {
+            run_mutating_hook("on_time", hook.as_ref(), time);
+            time.datetime_local_iso = time.local_rfc3339();
+        }
+    }
+
+    pub fn run_features(&self, features: &mut MediaFeatures) {
+        if let Some(hook) = &self.on_features {
+            run_mutating_hook("on_features", hook.as_ref(), features);
+        }
+    }
+
+    /// Whether the weather lookup should run. A gate that panics doesn't veto the lookup.
+    pub fn allow_weather(&self, gps: &GpsInfo, time: &TimeInfo) -> bool {
+        self.before_weather.as_ref().is_none_or(|gate| {
+            catch_unwind(AssertUnwindSafe(|| gate(gps, time))).unwrap_or_else(|_| {
+                log::warn!("The before_weather hook panicked, the weather lookup continues");
+                true
+            })
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ExifData;
+    use crate::UtcOffset;
+    use crate::features::gps::{CoordinatePrecision, LocationName};
+    use crate::time::structs::{TimeOptions, TimeZoneInfo};
+    use crate::time::{TimeSource, get_time_info};
+    use serde_json::json;
+
+    fn time_info() -> TimeInfo {
+        let exif = ExifData::new(json!({ "Time": { "DateTimeOriginal": "2024:02:02 12:34:56" } }));
+        get_time_info(&exif, None, &TimeOptions::default()).unwrap()
+    }
+
+    #[test]
+    fn test_time_hook_changes_are_kept() {
+        let hooks = AnalysisHooks {
+            on_time: Some(Arc::new(|time: &mut TimeInfo| {
+                time.source_details
+                    .set_time_source(TimeSource::Other, Some("Private database".to_string()));
+            })),
+            ..AnalysisHooks::default()
+        };
+        let mut time = time_info();
+        hooks.run_time(&mut time);
+        assert_eq!(time.source_details.time_source, TimeSource::Other);
+        assert_eq!(
+            time.source_details.time_source_display(),
+            "Other (Private database)"
+        );
+    }
+
+    #[test]
+    fn test_local_iso_follows_the_time_hook() {
+        let hooks = AnalysisHooks {
+            on_time: Some(Arc::new(|time: &mut TimeInfo| {
+                time.datetime_local += chrono::TimeDelta::hours(1);
+                time.timezone = Some(TimeZoneInfo {
+                    name: "Europe/Amsterdam".to_string(),
+                    offset_seconds: UtcOffset(3600),
+                    source: "Private database".to_string(),
+                    boundary_distance_km: None,
+                    near_timezone_boundary: false,
+                });
+            })),
+            ..AnalysisHooks::default()
+        };
+        let mut time = time_info();
+        assert_eq!(time.datetime_local_iso, None);
+        hooks.run_time(&mut time);
+        assert_eq!(
+            time.datetime_local_iso.as_deref(),
+            Some("2024-02-02T13:34:56+01:00")
+        );
+    }
+
+    #[test]
+    fn test_panicking_hook_changes_are_discarded() {
+        let hooks = AnalysisHooks {
+            on_time: Some(Arc::new(|time: &mut TimeInfo| {
+                time.source_details.confidence = "half done".to_string();
+                panic!("hook failure");
+            })),
+            before_weather: Some(Arc::new(|_: &GpsInfo, _: &TimeInfo| panic!("gate failure"))),
+            ..AnalysisHooks::default()
+        };
+        let original = time_info();
+        let mut time = original.clone();
+        hooks.run_time(&mut time);
+        assert_eq!(time, original);
+
+        let gps = GpsInfo {
+            latitude: 52.0,
+            longitude: 5.0,
+            altitude: None,
+            location: Some(LocationName {
+                latitude: 52.0,
+                longitude: 5.0,
+                name: "Utrecht".to_string(),
+                admin1: "Utrecht".to_string(),
+                admin2: String::new(),
+                country_code: "NL".to_string(),
+                country_name: None,
+            }),
+            image_direction: None,
+            image_direction_ref: None,
+            coordinate_precision: CoordinatePrecision::Exact,
+            satellites: None,
+            fix_mode: None,
+            measurement_in_progress: None,
+            fix_quality: None,
+            location_conflict: None,
+            timezone: None,
+            movement_from_previous: None,
+        };
+        assert!(hooks.allow_weather(&gps, &time));
+        // The hooks still run after a panic.
+        hooks.run_time(&mut time);
+        assert_eq!(time, original);
+    }
+
+    #[test]
+    fn test_missing_hooks_change_nothing() {
+        let hooks = AnalysisHooks::default();
+        let mut gps = None;
+        hooks.run_gps(&mut gps);
+        assert!(gps.is_none());
+    }
+}
@@ -0,0 +1,297 @@
+//! Translations of the human-readable strings derived from an analysis, for display. Results
+//! don't depend on the locale: enums serialize to the same English values in every locale, only
+//! these display helpers are translated.
+
+use chrono::Weekday;
+use meteostat::WeatherCondition;
+use serde::{Deserialize, Serialize};
+
+/// A language for display strings, set with `MediaAnalyzer::builder().locale(...)`.
+///
+/// ```
+/// use chrono::Weekday;
+/// use media_analyzer::Locale;
+///
+/// assert_eq!(Locale::De.weekday_name(Weekday::Mon), "Montag");
+/// assert_eq!(Locale::Nl.code(), "nl");
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+#[non_exhaustive]
+pub enum Locale {
+    #[default]
+    En,
+    De,
+    Fr,
+    Es,
+    Nl,
+}
+
+/// The names of the weekdays, from Monday, in the order of [`Locale`].
+const WEEKDAY_NAMES: [[&str; 7]; 5] = [
+    [
+        "Monday",
+        "Tuesday",
+        "Wednesday",
+        "Thursday",
+        "Friday",
+        "Saturday",
+        "Sunday",
+    ],
+    [
+        "Montag",
+        "Dienstag",
+        "Mittwoch",
+        "Donnerstag",
+        "Freitag",
+        "Samstag",
+        "Sonntag",
+    ],
+    [
+        "lundi", "mardi", "mercredi", "jeudi", "vendredi", "samedi", "dimanche",
+    ],
+    [
+        "lunes",
+        "martes",
+        "miércoles",
+        "jueves",
+        "viernes",
+        "sábado",
+        "domingo",
+    ],
+    [
+        "maandag",
+        "dinsdag",
+        "woensdag",
+        "donderdag",
+        "vrijdag",
+        "zaterdag",
+        "zondag",
+    ],
+];
+
+impl Locale {
+    /// The ISO 639-1 code of the language, like "de".
+    #[must_use]
+    pub const fn code(self) -> &'static str {
+        match self {
+            Self::En => "en",
+            Self::De => "de",
+            Self::Fr => "fr",
+            Self::Es => "es",
+            Self::Nl => "nl",
+        }
+    }
+
+    /// The name of a weekday, like "Montag".
+    #[must_use]
+    pub const fn weekday_name(self, weekday: Weekday) -> &'static str {
+        WEEKDAY_NAMES[self as usize][weekday.num_days_from_monday() as usize]
+    }
+
+    /// The display name of a weather condition, like "Leichter Regen".
+    #[must_use]
+    pub const fn weather_condition_name(self, condition: WeatherCondition) -> &'static str {
+        // In the order of `Locale`.
+        let names = match condition {
+            WeatherCondition::Clear => ["Clear", "Klar", "Dégagé", "Despejado", "Helder"],
+            WeatherCondition::Fair => [
+                "Fair",
+                "Heiter",
+                "Beau temps",
+                "Poco nuboso",
+                "Licht bewolkt",
+            ],
+            WeatherCondition::Cloudy => ["Cloudy", "Bewölkt", "Nuageux", "Nublado", "Bewolkt"],
+            WeatherCondition::Overcast => [
+                "Overcast",
+                "Bedeckt",
+                "Couvert",
+                "Cubierto",
+                "Zwaar bewolkt",
+            ],
+            WeatherCondition::Fog => ["Fog", "Nebel", "Brouillard", "Niebla", "Mist"],
+            WeatherCondition::FreezingFog => [
+                "Freezing fog",
+                "Gefrierender Nebel",
+                "Brouillard givrant",
+                "Niebla helada",
+                "Aanvriezende mist",
+            ],
+            WeatherCondition::LightRain => [
+                "Light rain",
+                "Leichter Regen",
+                "Pluie faible",
+                "Lluvia ligera",
+                "Lichte regen",
+            ],
+            WeatherCondition::Rain => ["Rain", "Regen", "Pluie", "Lluvia", "Regen"],
+            WeatherCondition::HeavyRain => [
+                "Heavy rain",
+                "Starker Regen",
+                "Forte pluie",
+                "Lluvia intensa",
+                "Zware regen",
+            ],
+            WeatherCondition::FreezingRain => [
+                "Freezing rain",
+                "Gefrierender Regen",
+                "Pluie verglaçante",
+                "Lluvia helada",
+                "IJzel",
+            ],
+            WeatherCondition::HeavyFreezingRain => [
+                "Heavy freezing rain",
+                "Starker gefrierender Regen",
+                "Forte pluie verglaçante",
+                "Lluvia helada intensa",
+                "Zware ijzel",
+            ],
+            WeatherCondition::Sleet => [
+                "Sleet",
+                "Schneeregen",
+                "Neige fondue",
+                "Aguanieve",
+                "Natte sneeuw",
+            ],
+            WeatherCondition::HeavySleet => [
+                "Heavy sleet",
+                "Starker Schneeregen",
+                "Forte neige fondue",
+                "Aguanieve intensa",
+                "Zware natte sneeuw",
+            ],
+            WeatherCondition::LightSnowfall => [
+                "Light snowfall",
+                "Leichter Schneefall",
+                "Faibles chutes de neige",
+                "Nevada ligera",
+                "Lichte sneeuwval",
+            ],
+            WeatherCondition::Snowfall => [
+                "Snowfall",
+                "Schneefall",
+                "Chutes de neige",
+                "Nevada",
+                "Sneeuwval",
+            ],
+            WeatherCondition::HeavySnowfall => [
+                "Heavy snowfall",
+                "Starker Schneefall",
+                "Fortes chutes de neige",
+                "Nevada intensa",
+                "Zware sneeuwval",
+            ],
+            WeatherCondition::RainShower => [
+                "Rain shower",
+                "Regenschauer",
+                "Averse de pluie",
+                "Chubasco",
+                "Regenbui",
+            ],
+            WeatherCondition::HeavyRainShower => [
+                "Heavy rain shower",
+                "Starker Regenschauer",
+                "Forte averse de pluie",
+                "Chubasco fuerte",
+                "Zware regenbui",
+            ],
+            WeatherCondition::SleetShower => [
+                "Sleet shower",
+                "Schneeregenschauer",
+                "Averse de neige fondue",
+                "Chubasco de aguanieve",
+                "Natte sneeuwbui",
+            ],
+            WeatherCondition::HeavySleetShower => [
+                "Heavy sleet shower",
+                "Starker Schneeregenschauer",
+                "Forte averse de neige fondue",
+                "Chubasco de aguanieve fuerte",
+                "Zware natte sneeuwbui",
+            ],
+            WeatherCondition::SnowShower => [
+                "Snow shower",
+                "Schneeschauer",
+                "Averse de neige",
+                "Chubasco de nieve",
+                "Sneeuwbui",
+            ],
+            WeatherCondition::HeavySnowShower => [
+                "Heavy snow shower",
+                "Starker Schneeschauer",
+                "Forte averse de neige",
+                "Chubasco de nieve fuerte",
+                "Zware sneeuwbui",
+            ],
+            WeatherCondition::Lightning => {
+                ["Lightning", "Blitze", "Éclairs", "Relámpagos", "Bliksem"]
+            }
+            WeatherCondition::Hail => ["Hail", "Hagel", "Grêle", "Granizo", "Hagel"],
+            WeatherCondition::Thunderstorm => {
+                ["Thunderstorm", "Gewitter", "Orage", "Tormenta", "Onweer"]
+            }
+            WeatherCondition::HeavyThunderstorm => [
+                "Heavy thunderstorm",
+                "Schweres Gewitter",
+                "Orage violent",
+                "Tormenta fuerte",
+                "Zwaar onweer",
+            ],
+            WeatherCondition::Storm => ["Storm", "Sturm", "Tempête", "Temporal", "Storm"],
+        };
+        names[self as usize]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LOCALES: [Locale; 5] = [Locale::En, Locale::De, Locale::Fr, Locale::Es, Locale::Nl];
+
+    #[test]
+    fn test_german_names() {
+        assert_eq!(Locale::De.weekday_name(Weekday::Wed), "Mittwoch");
+        assert_eq!(Locale::De.weekday_name(Weekday::Sun), "Sonntag");
+        assert_eq!(
+            Locale::De.weather_condition_name(WeatherCondition::LightRain),
+            "Leichter Regen"
+        );
+        assert_eq!(
+            Locale::De.weather_condition_name(WeatherCondition::HeavyThunderstorm),
+            "Schweres Gewitter"
+        );
+        assert_eq!(Locale::default().weekday_name(Weekday::Wed), "Wednesday");
+    }
+
+    #[test]
+    fn test_tables_follow_the_locale_order() {
+        for (i, locale) in LOCALES.into_iter().enumerate() {
+            assert_eq!(locale as usize, i);
+        }
+        assert_eq!(
+            LOCALES.map(|locale| locale.weekday_name(Weekday::Sat)),
+            ["Saturday", "Samstag", "samedi", "sábado", "zaterdag"]
+        );
+    }
+
+    #[test]
+    fn test_every_condition_is_translated() {
+        for code in 1..=27 {
+            let condition = WeatherCondition::from_i64(code).unwrap();
+            let names = LOCALES.map(|locale| locale.weather_condition_name(condition));
+            assert!(names.iter().all(|name| !name.is_empty()), "{condition:?}");
+        }
+        assert_eq!(
+            LOCALES.map(|locale| locale.weather_condition_name(WeatherCondition::HeavySleet)),
+            [
+                "Heavy sleet",
+                "Starker Schneeregen",
+                "Forte neige fondue",
+                "Aguanieve intensa",
+                "Zware natte sneeuw"
+            ]
+        );
+    }
+}
@@ -0,0 +1,144 @@
+//! Helpers for persisting analysis results and reloading them without re-analyzing.
+
+use crate::MediaMetadata;
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+use std::io::{self, BufRead, Write};
+
+/// Writes the results as newline-delimited JSON, one result per line.
+///
+/// # Errors
+/// * If serializing a result or writing to `writer` fails.
+pub fn write_ndjson<'a, W: Write>(
+    results: impl IntoIterator<Item = &'a MediaMetadata>,
+    mut writer: W,
+) -> io::Result<()> {
+    for result in results {
+        serde_json::to_writer(&mut writer, result)?;
+        writer.write_all(b"\n")?;
+    }
+    writer.flush()
+}
+
+/// Reads results written by [`write_ndjson`]. Blank lines are skipped.
+pub fn read_ndjson<R: BufRead>(
+    reader: R,
+) -> impl Iterator<Item = serde_json::Result<MediaMetadata>> {
+    reader.lines().filter_map(|line| match line {
+        Ok(line) if line.trim().is_empty() => None,
+        Ok(line) => Some(serde_json::from_str(&line)),
+        Err(e) => Some(Err(serde_json::Error::io(e))),
+    })
+}
+
+impl MediaMetadata {
+    /// Flattens the result into dotted keys (`features.isHdr`, `time.datetimeUtc`,
+    /// `gps.location.countryCode`, ...), for Arrow/parquet writers or CSV.
+    ///
+    /// Arrays and the raw `exif` blob are stored as JSON encoded strings. Missing optional
+    /// sections are a single `null` entry (e.g. `gps`).
+    #[must_use]
+    pub fn flatten(&self) -> BTreeMap<String, Value> {
+        let mut flat = BTreeMap::new();
+        if let Ok(Value::Object(map)) = serde_json::to_value(self) {
+            flatten_into(&mut flat, "", map);
+        }
+        flat
+    }
+}
+
+fn flatten_into(flat: &mut BTreeMap<String, Value>, prefix: &str, map: Map<String, Value>) {
+    for (key, value) in map {
+        let key = if prefix.is_empty() {
+            key
+        } else {
+            format!("{prefix}.{key}")
+        };
+        match value {
+            Value::Object(nested) if key != "exif" => flatten_into(flat, &key, nested),
+            Value::Object(_) | Value::Array(_) => {
+                flat.insert(key, Value::String(value.to_string()));
+            }
+            value => {
+                flat.insert(key, value);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::MediaMetadataFixture;
+    use serde_json::json;
+
+    fn result(hash: &str, iso: u64) -> MediaMetadata {
+        MediaMetadataFixture::photo()
+            .with_hash(hash)
+            .with_tag("Camera", "ISO", iso)
+            .with_local_time("2024-02-02T12:34:56")
+            .with_offset("+01:00")
+            .build()
+    }
+
+    #[test]
+    fn test_ndjson_round_trip() {
+        let results = vec![result("a", 100), result("b", 6400)];
+        let mut buffer = Vec::new();
+        write_ndjson(&results, &mut buffer).unwrap();
+        assert_eq!(
+            String::from_utf8(buffer.clone()).unwrap().lines().count(),
+            2
+        );
+
+        let read: Vec<MediaMetadata> = read_ndjson(buffer.as_slice())
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(read.len(), 2);
+        for (original, read) in results.iter().zip(&read) {
+            assert_eq!(
+                serde_json::to_value(original).unwrap(),
+                serde_json::to_value(read).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn test_results_without_provenance_still_deserialize() {
+        let mut stored = serde_json::to_value(result("a", 100)).unwrap();
+        stored.as_object_mut().unwrap().remove("provenance");
+        let line = serde_json::to_string(&stored).unwrap();
+
+        let read: Vec<MediaMetadata> = read_ndjson(line.as_bytes())
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert!(read[0].provenance.is_none());
+    }
+
+    #[test]
+    fn test_read_ndjson_reports_bad_lines() {
+        let input = "\n{not json}\n";
+        let read: Vec<_> = read_ndjson(input.as_bytes()).collect();
+        assert_eq!(read.len(), 1);
+        assert!(read[0].is_err());
+    }
+
+    #[test]
+    fn test_flatten_key_names() {
+        let flat = result("a", 6400).flatten();
+
+        assert_eq!(flat["hash"], json!("a"));
+        assert_eq!(flat["features.isHdr"], json!(false));
+        assert_eq!(flat["camera.iso"], json!(6400));
+        assert_eq!(flat["time.sourceDetails.confidence"], json!("High"));
+        assert_eq!(flat["time.datetimeUtc"], json!("2024-02-02T11:34:56Z"));
+        assert_eq!(flat["gps"], Value::Null);
+        // Vectors and the raw exif are JSON encoded strings.
+        assert_eq!(
+            flat["qualityHints"],
+            json!(r#"[{"highIsoNoise":{"iso":6400}}]"#)
+        );
+        assert!(flat["exif"].as_str().unwrap().contains("\"ISO\":6400"));
+        assert!(flat.values().all(|v| !v.is_object() && !v.is_array()));
+    }
+}
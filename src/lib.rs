@@ -3,6 +3,7 @@
     clippy::cast_possible_truncation,
     clippy::float_cmp
 )]
+#![cfg_attr(not(test), deny(clippy::unwrap_used, clippy::expect_used))]
 //! # Media Analyzer
 //!
 //! A toolkit for extracting info from video and photo files.
@@ -33,11 +34,22 @@
 //! - **Geolocation & Weather**: Automatically performs reverse geocoding on GPS coordinates to find
 //!   human-readable location names ([`GpsInfo`]). If successful, it then fetches historical weather
 //!   and sun data (sunrise, sunset) for the precise time and place the media was captured,
-//!   populating the [`WeatherInfo`] struct.
+//!   populating the [`WeatherInfo`] struct. Meteostat is used by default; other sources can be
+//!   plugged in through the [`WeatherProvider`] trait (e.g. `OpenMeteoProvider` behind the
+//!   `weather-openmeteo` feature, or a [`ChainedProvider`] that falls back between two sources).
+//!
+//! - **HTTP Integration**: The `web` feature adds an `axum` router serving `POST /analyze` for
+//!   multipart uploads, with JSON responses and status codes for errors (see the
+//!   `http_server` example).
+//!
+//! - **Metrics**: The `metrics` feature records stage durations, time confidence, weather
+//!   outcomes and errors through the `metrics` crate, like `media_analyzer_errors_total` (see the
+//!   `prometheus_metrics` example).
 //!
 //! - **Rich Media Tagging**: Identifies a wide variety of special media characteristics, such as
 //!   `is_motion_photo`, `is_hdr`, `is_burst`, `is_slowmotion`, and `is_timelapse`, all available
-//!   in the [`MediaFeatures`] struct.
+//!   in the [`MediaFeatures`] struct. The detections are also available as pure functions over
+//!   `exiftool` JSON in [`detectors`], for metadata that was read elsewhere.
 //!
 //! - **Thumbnail Generation**: Creates a tiny, Base64-encoded JPEG data URL, for use as
 //!   a blurred placeholder in a UI while the full media loads.
@@ -55,13 +67,13 @@
 //!
 //! ```rust
 //! use std::path::Path;
-//! use media_analyzer::{MediaAnalyzer, MediaAnalyzerError};
+//! use media_analyzer::{Kilometers, MediaAnalyzer, MediaAnalyzerError};
 //!
 //! #[tokio::main]
 //! async fn main() -> Result<(), MediaAnalyzerError> {
 //!     // 1. Build the analyzer. The builder allows for custom configuration.
 //!     let analyzer = MediaAnalyzer::builder()
-//!         .weather_search_radius_km(50.0) // Optional: configure the analyzer
+//!         .weather_search_radius(Kilometers(50.0)) // Optional: configure the analyzer
 //!         .build()
 //!         .await?;
 //!
@@ -72,8 +84,8 @@
 //!     let result = analyzer.analyze_media(media_file).await?;
 //!
 //!     // 4. Access the structured data from the `MediaMetadata`.
-//!     if let Some(gps) = result.gps {
-//!         println!("Location: {}, {}", gps.location.name, gps.location.country_code);
+//!     if let Some(location) = result.gps.and_then(|gps| gps.location) {
+//!         println!("Location: {}, {}", location.name, location.country_code);
 //!     }
 //!
 //!     if let Some(model) = result.camera.camera_model {
@@ -88,26 +100,120 @@
 //! }
 //! ```
 
+mod cancel;
+pub mod detectors;
+#[cfg(feature = "http")]
+mod download;
 mod error;
 mod exif_data;
+mod exiftool_process;
 mod features;
+mod hooks;
+mod i18n;
+mod io;
+mod manifest;
 mod media_analyzer;
+mod notes;
+mod pool;
+mod preset;
+mod provenance;
+mod raw_exif;
+mod serde_time;
+mod skipped;
 mod structs;
 mod tags;
+mod telemetry;
+#[cfg(any(test, feature = "test-support"))]
+pub mod test_support;
 mod time;
+mod units;
+#[cfg(feature = "web")]
+pub mod web;
 
 // --- Public API Exports ---
-pub use exif_data::ExifData;
-pub use media_analyzer::MediaAnalyzer;
+pub use cancel::CancellationToken;
+pub use exif_data::{ExifData, ParseDiagnostic};
 pub use media_analyzer::MediaAnalyzerBuilder;
+pub use media_analyzer::{ConfigOverrides, MediaAnalyzer};
 
 // The primary error type
+#[cfg(feature = "http")]
+pub use download::DEFAULT_MAX_DOWNLOAD_BYTES;
 pub use error::MediaAnalyzerError;
+pub use features::error::WeatherError;
 
 // The main result struct and its components
-pub use features::gps::{GpsInfo, LocationName};
+pub use features::aux_images::AuxImageInfo;
+pub use features::calendar::{BundledHolidays, CalendarContext, HolidayProvider};
+pub use features::camera_module::{CameraModule, Facing, ModuleKind};
+pub use features::completeness::{CompletenessBreakdown, CompletenessWeights, score_completeness};
+pub use features::dedup::group_by_dedup_key;
+pub use features::device_motion::DeviceMotion;
+pub use features::file_health::FileHealth;
+pub use features::filename::FilenameTagPattern;
+pub use features::geo_export::{MapProvider, results_to_geojson_collection};
+pub use features::gps::{CoordinatePrecision, GpsFixMode, GpsFixQuality, GpsInfo, LocationName};
+pub use features::ingest_audit::IngestAudit;
 pub use features::metadata::{BasicMetadata, CameraSettings};
-pub use features::weather::{SunInfo, WeatherInfo};
+pub use features::mime::{MIME_TYPE_ALIASES, UNKNOWN_MIME_TYPE, normalize_mime_type};
+pub use features::movement::Movement;
+#[cfg(feature = "weather-openmeteo")]
+pub use features::open_meteo::OpenMeteoProvider;
+pub use features::ordering::sort_chronologically;
+pub use features::orientation::{InvalidOrientation, Orientation, OrientationName};
+pub use features::pano::PanoViewInfo;
+pub use features::probe::ProbeResult;
+pub use features::quality::QualityHint;
+pub use features::sessions::{
+    Session, SessionDetection, SessionOptions, detect_sessions, detect_sessions_with_options,
+};
+pub use features::thumbnail::ThumbnailSource;
+pub use features::time_inference::{INFERRED_ZONE_SOURCE, infer_timezones};
+pub use features::user_metadata::{UserMetadata, WrittenLocation};
+pub use features::versions::{LinkageKind, VersionChain, link_versions};
+pub use features::weather::{
+    EXTREME_COLD_FELT_C, EXTREME_HEAT_FELT_C, RadiusStrategy, SunInfo, WINDY_THRESHOLD_KMH,
+    WeatherContext, WeatherContextHour, WeatherFlags, WeatherInfo,
+};
+pub use features::weather_provider::{
+    CacheStats, ChainedProvider, MeteostatProvider, ObservationWithContext, PrewarmFailure,
+    PrewarmReport, WeatherObservation, WeatherProvider, default_meteostat_cache_folder,
+};
+pub use i18n::Locale;
+pub use io::{read_ndjson, write_ndjson};
+pub use manifest::{
+    AnalyzedFile, IncrementalOptions, IncrementalReport, Manifest, ManifestEntry, result_digest,
+};
+pub use meteostat::Meteostat;
+pub use notes::{
+    AnalysisNote, CODE_IMPLAUSIBLE_DURATION, CODE_LOOKUP_FAILED, CODE_MALFORMED_DURATION,
+    CODE_MISSING_MIME_TYPE, CODE_NO_PLACE_NAME, CODE_SKIPPED, MODULE_METADATA, NoteSeverity,
+};
+pub use pool::AnalyzerPool;
+pub use preset::{Preset, PresetOptions};
+pub use provenance::{
+    AnalysisModule, AnalysisProvenance, CRATE_VERSION, LogicRevisions, StalenessPolicy,
+};
+pub use raw_exif::RawExif;
+pub use reverse_geocoder::ReverseGeocoder;
+pub use serde_time::{SerializeOptions, TimestampPrecision};
+pub use skipped::{STAGE_GEOCODE, STAGE_TIMEZONE_FROM_GPS, STAGE_WEATHER, SkippedStage};
 pub use structs::MediaMetadata;
-pub use tags::structs::MediaFeatures;
-pub use time::structs::{SourceDetails, TimeInfo, TimeZoneInfo};
+pub use tags::aspect::AspectClass;
+pub use tags::hdr::{HdrStrictness, HdrType};
+pub use tags::motion::MotionPhotoVersion;
+pub use tags::solar::{NIGHT_SUN_ELEVATION_DEG, sun_elevation_deg};
+pub use tags::structs::{
+    DEFAULT_SLOWMOTION_RATIO_THRESHOLD, DEFAULT_SOLAR_EVENT_WINDOW, FeatureOptions, MediaFeatures,
+};
+#[cfg(feature = "metrics")]
+pub use telemetry::{
+    ERRORS_TOTAL, STAGE_DURATION_SECONDS, TIME_CONFIDENCE_TOTAL, WEATHER_RESULT_TOTAL,
+};
+pub use time::error::TimeError;
+pub use time::structs::{NumericDateOrder, SourceDetails, TimeInfo, TimeOptions, TimeZoneInfo};
+pub use time::{
+    DEFAULT_TIME_TAG_PRIORITY, ItineraryEntry, TIME_SOURCES, TimeCorrection, TimeSource,
+    nautical_timezone, parse_time_corrections_csv, timezone_at,
+};
+pub use units::{Kilometers, Microseconds, Seconds, UtcOffset};
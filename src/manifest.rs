@@ -0,0 +1,274 @@
+//! Change detection for folders that are analyzed again and again, like a nightly sync. See
+//! [`MediaAnalyzer::analyze_directory_incremental`](crate::MediaAnalyzer::analyze_directory_incremental).
+
+use crate::MediaAnalyzerError;
+use crate::features::hashing::hash_file;
+use crate::structs::MediaMetadata;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+/// What was known about a file when it was last analyzed.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ManifestEntry {
+    pub size_bytes: u64,
+    pub modified: DateTime<Utc>,
+    /// The BLAKE3 hash of the file, when the analysis compared hashes.
+    pub blake3: Option<String>,
+    /// A hash of the result's JSON without its provenance and raw tags, see [`result_digest`].
+    pub result_digest: String,
+}
+
+/// The files of earlier analyses, by path, to skip the ones that didn't change since.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Manifest {
+    pub entries: BTreeMap<PathBuf, ManifestEntry>,
+}
+
+impl Manifest {
+    /// Writes the manifest as pretty-printed JSON.
+    ///
+    /// # Errors
+    /// * If creating or writing the file fails.
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        serde_json::to_writer_pretty(&mut writer, self)?;
+        writer.flush()
+    }
+
+    /// Reads a manifest written by [`Manifest::save`], or an empty one when `path` doesn't
+    /// exist yet.
+    ///
+    /// # Errors
+    /// * If reading the file fails, or it doesn't hold a manifest.
+    pub fn load(path: &Path) -> io::Result<Self> {
+        match File::open(path) {
+            Ok(file) => Ok(serde_json::from_reader(BufReader::new(file))?),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e),
+        }
+    }
+}
+
+/// Settings for [`MediaAnalyzer::analyze_directory_incremental`](crate::MediaAnalyzer::analyze_directory_incremental).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct IncrementalOptions {
+    /// Also look in subdirectories.
+    pub recursive: bool,
+    /// Compare BLAKE3 hashes instead of modification times, so a file that was only touched is
+    /// skipped and an edit that kept the modification time is not. Reads every file on every
+    /// run.
+    pub compare_hashes: bool,
+}
+
+/// A file that was analyzed because it was new or changed.
+#[derive(Debug, Clone)]
+pub struct AnalyzedFile {
+    pub path: PathBuf,
+    pub result: MediaMetadata,
+    /// Whether the result differs from the one in the manifest, see [`result_digest`]. Always
+    /// `true` for new files, and `false` for a file that was only touched.
+    pub result_changed: bool,
+}
+
+/// The outcome of [`MediaAnalyzer::analyze_directory_incremental`](crate::MediaAnalyzer::analyze_directory_incremental).
+#[derive(Debug, Default)]
+pub struct IncrementalReport {
+    pub analyzed: Vec<AnalyzedFile>,
+    /// Files that match their manifest entry, and were not read by `exiftool`.
+    pub skipped_unchanged: Vec<PathBuf>,
+    /// Manifest entries in the directory whose file no longer exists. They are removed from
+    /// the manifest.
+    pub removed: Vec<PathBuf>,
+    /// Files whose analysis failed, like files that aren't media. They are not added to the
+    /// manifest, so they are tried again on the next run.
+    pub failed: Vec<(PathBuf, MediaAnalyzerError)>,
+}
+
+/// The size and modification time of a file, and its hash when asked for.
+pub struct FileStamp {
+    pub size_bytes: u64,
+    pub modified: DateTime<Utc>,
+    pub blake3: Option<String>,
+}
+
+impl FileStamp {
+    /// Reads the stamp of the file at `path`.
+    ///
+    /// # Errors
+    /// * If the file's metadata can't be read, or hashing it fails.
+    pub fn read(path: &Path, hash: bool) -> io::Result<Self> {
+        let metadata = fs::metadata(path)?;
+        Ok(Self {
+            size_bytes: metadata.len(),
+            modified: metadata.modified()?.into(),
+            blake3: hash.then(|| hash_file(path)).transpose()?,
+        })
+    }
+
+    /// Whether the file is the one `entry` describes: by size and hash when both sides have a
+    /// hash, so a file that was only touched still matches, otherwise by size and modification
+    /// time.
+    #[must_use]
+    pub fn matches(&self, entry: &ManifestEntry) -> bool {
+        self.size_bytes == entry.size_bytes
+            && match (&self.blake3, &entry.blake3) {
+                (Some(hash), Some(known)) => hash == known,
+                _ => self.modified == entry.modified,
+            }
+    }
+
+    /// Brings a matching `entry` up to date with this stamp, keeping its result digest.
+    pub fn refresh(self, entry: &mut ManifestEntry) {
+        entry.modified = self.modified;
+        if self.blake3.is_some() {
+            entry.blake3 = self.blake3;
+        }
+    }
+
+    #[must_use]
+    pub fn into_entry(self, result_digest: String) -> ManifestEntry {
+        ManifestEntry {
+            size_bytes: self.size_bytes,
+            modified: self.modified,
+            blake3: self.blake3,
+            result_digest,
+        }
+    }
+}
+
+/// A BLAKE3 hash of the result's JSON, without the provenance, which records when the analysis
+/// ran, the raw `exif`, whose file system tags like `FileAccessDate` change on every read, and
+/// `ingest_audit.capture_to_file_modify_seconds`, which changes when the file is touched. Two
+/// analyses of the same file with the same code and configuration have the same digest, also
+/// after a touch, unless the capture time or its offset is read from the file system times.
+#[must_use]
+pub fn result_digest(result: &MediaMetadata) -> String {
+    let mut value = serde_json::to_value(result).unwrap_or(Value::Null);
+    if let Value::Object(map) = &mut value {
+        map.remove("provenance");
+        map.remove("exif");
+        if let Some(Value::Object(audit)) = map.get_mut("ingestAudit") {
+            audit.remove("captureToFileModifySeconds");
+        }
+    }
+    blake3::hash(value.to_string().as_bytes())
+        .to_hex()
+        .to_string()
+}
+
+/// The files in `dir`, sorted, and those of its subdirectories when `recursive`.
+///
+/// # Errors
+/// * If a directory can't be read.
+pub fn list_files(dir: &Path, recursive: bool) -> io::Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    let mut pending = vec![dir.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        for entry in fs::read_dir(&dir)? {
+            let entry = entry?;
+            let file_type = entry.file_type()?;
+            if file_type.is_dir() {
+                if recursive {
+                    pending.push(entry.path());
+                }
+            } else if file_type.is_file() {
+                files.push(entry.path());
+            }
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::features::ingest_audit::IngestAudit;
+    use crate::media_analyzer::temp_media_path;
+    use crate::test_support::MediaMetadataFixture;
+
+    #[test]
+    fn test_save_load_round_trip() {
+        let (dir, path) = temp_media_path("manifest.json").unwrap();
+        assert_eq!(Manifest::load(&path).unwrap(), Manifest::default());
+
+        let mut manifest = Manifest::default();
+        manifest.entries.insert(
+            dir.join("a.jpg"),
+            ManifestEntry {
+                size_bytes: 12,
+                modified: DateTime::from_timestamp(1_700_000_000, 123_456_789).unwrap(),
+                blake3: Some("abc".to_string()),
+                result_digest: "def".to_string(),
+            },
+        );
+        manifest.save(&path).unwrap();
+        assert_eq!(Manifest::load(&path).unwrap(), manifest);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_result_digest_ignores_provenance_raw_tags_and_file_times() {
+        let mut result = MediaMetadataFixture::photo().build();
+        result.ingest_audit = Some(IngestAudit {
+            capture_to_file_modify_seconds: Some(60),
+            filename_matches_camera_scheme: None,
+            file_extension_matches_mime: true,
+        });
+        let mut later = result.clone();
+        later.provenance = None;
+        later.exif["Time"]["FileAccessDate"] = "2024:07:11 09:00:00+02:00".into();
+        // Touched a day later.
+        later.ingest_audit = Some(IngestAudit {
+            capture_to_file_modify_seconds: Some(86_460),
+            ..result.ingest_audit.unwrap()
+        });
+        assert_eq!(result_digest(&result), result_digest(&later));
+
+        later.basic.width += 1;
+        assert_ne!(result_digest(&result), result_digest(&later));
+    }
+
+    #[test]
+    fn test_stamp_matches_on_size_time_and_hash() {
+        let (dir, path) = temp_media_path("a.jpg").unwrap();
+        fs::write(&path, b"jpeg").unwrap();
+        let entry = FileStamp::read(&path, true)
+            .unwrap()
+            .into_entry(String::new());
+
+        assert!(FileStamp::read(&path, false).unwrap().matches(&entry));
+        let mut touched = FileStamp::read(&path, true).unwrap();
+        touched.modified += chrono::TimeDelta::seconds(1);
+        assert!(touched.matches(&entry));
+        touched.blake3 = None;
+        assert!(!touched.matches(&entry));
+        let mut edited = FileStamp::read(&path, true).unwrap();
+        edited.blake3 = Some("other".to_string());
+        assert!(!edited.matches(&entry));
+        fs::write(&path, b"jpeg!").unwrap();
+        assert!(!FileStamp::read(&path, false).unwrap().matches(&entry));
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_list_files_recursion() {
+        let (dir, _) = temp_media_path("").unwrap();
+        fs::create_dir_all(dir.join("nested")).unwrap();
+        fs::write(dir.join("b.jpg"), b"b").unwrap();
+        fs::write(dir.join("nested/a.jpg"), b"a").unwrap();
+
+        assert_eq!(list_files(&dir, false).unwrap(), vec![dir.join("b.jpg")]);
+        assert_eq!(
+            list_files(&dir, true).unwrap(),
+            vec![dir.join("b.jpg"), dir.join("nested/a.jpg")]
+        );
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -1,18 +1,123 @@
 use crate::ExifData;
 use crate::MediaAnalyzerError;
-use crate::features::gps::get_gps_info;
+use crate::cancel::CancellationToken;
+#[cfg(feature = "http")]
+use crate::download::{self, DEFAULT_MAX_DOWNLOAD_BYTES};
+use crate::exiftool_process::{ExifToolProcess, StayOpen};
+use crate::features::aux_images::{AuxImageInfo, copy_aux_image, get_aux_images};
+use crate::features::calendar::{BundledHolidays, HolidayProvider, get_calendar_context};
+use crate::features::completeness::{
+    CompletenessBreakdown, CompletenessWeights, score_completeness,
+};
+use crate::features::dedup::get_dedup_key;
+use crate::features::error::WeatherError;
+use crate::features::file_health::{check_file_health, decoded_dimension_mismatch};
+use crate::features::filename::{FilenameCandidates, FilenameTagPattern, FilenameTagger};
+use crate::features::geocode::{CachedGeocoder, DEFAULT_GEOCODE_CACHE_SIZE};
+use crate::features::gps::{
+    GpsInfo, LocationName, drop_2d_fix_altitude, get_gps_coordinates, get_gps_info,
+};
 use crate::features::hashing::hash_file;
-use crate::features::metadata::get_metadata;
-use crate::features::pano::should_use_pano_viewer;
-use crate::features::weather::get_weather_info;
+use crate::features::ingest_audit::get_ingest_audit;
+use crate::features::metadata::{CameraSettings, duration_notes, get_metadata};
+use crate::features::mime::mime_type_notes;
+use crate::features::movement::MovementTracker;
+use crate::features::pano::{get_pano_view_info, pano_viewer_evidence};
+use crate::features::probe::{PROBE_EXIFTOOL_ARGS, ProbeResult, get_probe_result};
+use crate::features::quality::get_quality_hints;
+use crate::features::stack::{STACK_EXIFTOOL_ARGS, stack_members};
+use crate::features::stripped::metadata_stripped_notes;
+use crate::features::thumbnail::{ThumbnailSource, resolve_thumbnail_timestamp};
+use crate::features::user_metadata::{get_user_metadata, location_conflict};
+use crate::features::weather::{RadiusStrategy, get_weather_info};
+use crate::features::weather_provider::{
+    CacheStats, MeteostatProvider, NoWeather, PrewarmReport, WeatherProvider,
+    default_meteostat_cache_folder,
+};
+use crate::hooks::{AnalysisHooks, FeaturesHook, GpsHook, TimeHook, WeatherGate};
+use crate::i18n::Locale;
+use crate::manifest::{
+    AnalyzedFile, FileStamp, IncrementalOptions, IncrementalReport, Manifest, list_files,
+    result_digest,
+};
+use crate::notes::{AnalysisNote, CODE_LOOKUP_FAILED, CODE_NO_PLACE_NAME, normalize_notes};
+use crate::preset::Preset;
+use crate::provenance::{
+    AnalysisProvenance, CRATE_VERSION, FingerprintedConfig, LogicRevisions, StalenessPolicy,
+    config_fingerprint,
+};
+use crate::raw_exif::RawExif;
+use crate::serde_time::{SerializeOptions, TimestampPrecision};
+use crate::skipped::{
+    STAGE_GEOCODE, STAGE_WEATHER, SkippedStage, skipped_before_weather, weather_prerequisites,
+};
 use crate::structs::MediaMetadata;
+use crate::tags::astro::apply_astrophotography;
+use crate::tags::hdr::HdrStrictness;
 use crate::tags::logic::extract_features;
-use crate::time::get_time_info;
+use crate::tags::solar::apply_solar_events;
+use crate::tags::structs::{
+    DEFAULT_SLOWMOTION_RATIO_THRESHOLD, DEFAULT_SOLAR_EVENT_WINDOW, FeatureOptions, MediaFeatures,
+};
+use crate::telemetry::{
+    StageTimer, WEATHER_ERROR, WEATHER_HIT, WEATHER_NO_DATA, WEATHER_SKIPPED, record_error,
+    record_time_confidence, record_weather_result,
+};
+use crate::time::structs::{
+    DEFAULT_FUTURE_TIMESTAMP_MARGIN, NumericDateOrder, TimeInfo, TimeOptions,
+};
+use crate::time::{ItineraryEntry, get_time_info, resolve_time_tag_priority, validate_itinerary};
+use crate::time::{TimeCorrection, lookup_gps_zone};
+use crate::units::Kilometers;
 use bon::bon;
-use exiftool::ExifTool;
+use chrono::{NaiveDate, TimeDelta, Utc};
 use meteostat::Meteostat;
 use reverse_geocoder::ReverseGeocoder;
+use serde_json::{Value, json};
+use std::collections::HashSet;
+use std::ffi::OsStr;
+use std::fs;
+use std::io::Write;
+use std::panic;
 use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::{Duration, Instant};
+
+/// The arguments of the `exiftool` call of an analysis: numeric values, grouped by category.
+const ANALYSIS_EXIFTOOL_ARGS: [&str; 2] = ["-n", "-g2"];
+
+// The stages reported in `MediaAnalyzerError::Timeout` and `MediaAnalyzerError::Cancelled`.
+#[cfg(feature = "http")]
+const STAGE_DOWNLOAD: &str = "download";
+const STAGE_EXIFTOOL: &str = "exiftool";
+const STAGE_METADATA: &str = "metadata";
+
+/// Creates a fresh temporary directory for media that isn't on disk yet.
+fn temp_media_dir() -> std::io::Result<PathBuf> {
+    let dir = std::env::temp_dir().join(format!(
+        "media_analyzer_upload_{:016x}",
+        rand::random::<u64>()
+    ));
+    fs::create_dir_all(&dir)?;
+    Ok(dir)
+}
+
+/// The path of `filename` in `dir`. Only the file name part of `filename` is used.
+fn temp_media_file(dir: &Path, filename: &str) -> PathBuf {
+    dir.join(
+        Path::new(filename)
+            .file_name()
+            .unwrap_or_else(|| OsStr::new("upload")),
+    )
+}
+
+/// Creates a fresh temporary directory for media that isn't on disk yet, returning it and the
+/// path of `filename` in it. Only the file name part of `filename` is used.
+pub(crate) fn temp_media_path(filename: &str) -> std::io::Result<(PathBuf, PathBuf)> {
+    let dir = temp_media_dir()?;
+    let path = temp_media_file(&dir, filename);
+    Ok((dir, path))
+}
 
 /// The main entry point for the media analysis pipeline.
 ///
@@ -29,11 +134,48 @@ use std::path::{Path, PathBuf};
 /// # Ok(())
 /// # }
 /// ```
+#[allow(clippy::struct_excessive_bools)]
 pub struct MediaAnalyzer {
-    geocoder: ReverseGeocoder,
-    exiftool: ExifTool,
-    meteostat: Meteostat,
-    weather_search_radius_km: f64,
+    geocoder: Arc<CachedGeocoder>,
+    exiftool: Arc<ExifToolProcess>,
+    weather_provider: Arc<dyn WeatherProvider>,
+    holiday_provider: Arc<dyn HolidayProvider>,
+    weather_radius_strategy: RadiusStrategy,
+    weather_context_hours: u8,
+    time_options: TimeOptions,
+    feature_options: FeatureOptions,
+    completeness_weights: CompletenessWeights,
+    filename_tagger: FilenameTagger,
+    locale: Locale,
+    movement: Option<MovementTracker>,
+    low_memory: bool,
+    strict_file_checks: bool,
+    collect_parse_diagnostics: bool,
+    trust_2d_altitude: bool,
+    reverse_geocode: bool,
+    weather: bool,
+    keep_exif: bool,
+    timestamp_precision: Option<TimestampPrecision>,
+    analysis_timeout: Option<Duration>,
+    exiftool_version: Option<String>,
+    hooks: AnalysisHooks,
+    #[cfg(feature = "http")]
+    http_client: reqwest::Client,
+    #[cfg(feature = "http")]
+    max_download_bytes: u64,
+}
+
+/// Lightweight settings to change in [`MediaAnalyzer::with_config`]. Settings left at `None`
+/// are copied from the original analyzer.
+#[derive(Debug, Clone, Default)]
+pub struct ConfigOverrides {
+    pub weather_radius_strategy: Option<RadiusStrategy>,
+    pub weather_context_hours: Option<u8>,
+    pub time_options: Option<TimeOptions>,
+    pub explain: Option<bool>,
+    pub hdr_detection: Option<HdrStrictness>,
+    pub night_mode_from_filename: Option<bool>,
+    pub slowmotion_ratio_threshold: Option<f64>,
 }
 
 #[bon]
@@ -45,9 +187,57 @@ impl MediaAnalyzer {
     ///
     /// # Builder Arguments
     ///
+    /// * `preset: Option<Preset>` - (Default: none) Seeds `weather`, `reverse_geocode`, `keep_exif`, `explain` and `collect_parse_diagnostics` with the values of a [`Preset`], see its docs for each. Options set explicitly win over the preset, in any order.
     /// * `exiftool_path: Option<PathBuf>` - An optional path to a specific `exiftool` executable. If `None`, `exiftool` will be searched for in the system's PATH.
     /// * `cache_folder: Option<PathBuf>` - An optional path to a directory for caching `Meteostat` data. Using a cache significantly speeds up repeated requests for the same location. If `None`, a default OS-specific cache location will be used.
-    /// * `weather_search_radius_km: f64` - (Default: `100.0`) The maximum distance in kilometers to search for a weather station from the media's GPS coordinates.
+    /// * `weather_search_radius: Kilometers` - (Default: `Kilometers(100.0)`) The maximum distance to search for a weather station from the media's GPS coordinates. `weather_search_radius_km(f64)` is a deprecated alias.
+    /// * `weather_provider: Option<Arc<dyn WeatherProvider>>` - An optional custom source of historical weather data. If `None`, a Meteostat-backed provider is created using `cache_folder` and `weather_search_radius`.
+    /// * `meteostat: Option<Arc<Meteostat>>` - A `Meteostat` client shared with other analyzers, which skips initializing a new one. Ignored when `weather_provider` is set. Pass the client's `cache_folder` too when it isn't the default, for [`MediaAnalyzer::weather_cache_stats`].
+    /// * `holiday_provider: Option<Arc<dyn HolidayProvider>>` - (Default: [`BundledHolidays`]) The source of the public holidays in `calendar_context.holiday`. The bundled one covers the national holidays of 29 countries, mostly in Europe and the Americas; photos from other countries get no holiday.
+    /// * `geocoder: Option<Arc<ReverseGeocoder>>` - A reverse geocoder shared with other analyzers, which skips loading the (large) geocoding dataset again.
+    /// * `weather_radius_strategy: Option<RadiusStrategy>` - How far to search for weather data. [`RadiusStrategy::Adaptive`] widens the search radius step by step in regions with sparse weather stations. If `None`, `RadiusStrategy::Fixed` with `weather_search_radius` is used. An adaptive strategy that cannot widen, like one with a `step_factor` of 1, is rejected with [`MediaAnalyzerError::InvalidConfiguration`].
+    /// * `weather_context_hours: u8` - (Default: `0`, off) Adds `weather.context` with the weather up to this many hours before and after the capture time, and whether it rained in the 3 hours before. Only hours the weather provider already fetched are included, so this makes no extra requests.
+    /// * `time_tag_priority: Option<Vec<String>>` - Replaces the order in which "Time" tags are tried for the local capture time of photos (see [`DEFAULT_TIME_TAG_PRIORITY`](crate::DEFAULT_TIME_TAG_PRIORITY)). Known tags are matched case-insensitively; unknown tags are used verbatim and may come from any group.
+    /// * `prepend_time_tag_priority: Option<Vec<String>>` - Tags to try before the (default or replaced) priority order, e.g. `DateTimeDigitized` for scanned slides where `CreateDate` is the scan time.
+    /// * `future_timestamp_margin: Option<TimeDelta>` - (Default: 48 hours) Capture times further than this in the future are flagged with `future_timestamp` and get a low confidence.
+    /// * `fall_back_on_future_timestamp: bool` - (Default: `false`) Replace a future capture time with the file modification time, when that is not in the future itself.
+    /// * `numeric_date_order: Option<NumericDateOrder>` - (Default: none) Whether numeric dates with the year last, like "03/07/2019" from some scanners, put the day or the month first. They are ambiguous, so without this option they are not parsed and another time source is used.
+    /// * `two_digit_years: bool` - (Default: `false`) Parse year-first dates with a two-digit year, like "04/03/07 09:05:02" from some old cameras, as years 1970-2069.
+    /// * `timezone_itinerary: Vec<ItineraryEntry>` - (Default: empty) Timezones by date range, for a trip with a camera without GPS. A naive capture time on a date in a range, without an offset tag or UTC time, is zoned in its timezone with Medium confidence. Overlapping ranges are rejected with [`MediaAnalyzerError::InvalidConfiguration`].
+    /// * `nautical_timezone_fallback: bool` - (Default: `false`) Zone capture times at GPS positions at sea where the timezone dataset has no zone, which happens on the borders between nautical zones, in the `Etc/GMT±N` zone of their longitude. See [`nautical_timezone`](crate::nautical_timezone).
+    /// * `geocode_cache_size: usize` - (Default: `10_000`) The number of reverse geocoded locations to cache, keyed by coordinates rounded to ~100m. Set to `0` to disable the cache.
+    /// * `explain: bool` - (Default: `false`) Record which detection rule set each feature flag, and on what evidence, in `features.explanations`.
+    /// * `hdr_detection: HdrStrictness` - (Default: `Strict`) With `Lenient`, a `Software` tag that mentions HDR is enough to set `is_hdr`. `hdr_type` is reported either way.
+    /// * `night_mode_from_filename: bool` - (Default: `false`) Set `is_night_mode` for any photo with "night" in its filename. By default a tag must back the filename up, like Pixel's HDR+ maker note or a long exposure, so `night_out.jpg` taken at noon doesn't count.
+    /// * `slowmotion_ratio_threshold: f64` - (Default: `1.05`) Videos captured at more than this many times their playback frame rate are slow motion. Lower it for cameras that conform clips to e.g. 1.04x. `features.speed_factor` reports the ratio either way.
+    /// * `solar_event_window: Option<TimeDelta>` - (Default: 25 minutes) Photos taken within this long of sunrise or sunset at their location get `features.is_sunrise_shot` or `features.is_sunset_shot`.
+    /// * `low_memory: bool` - (Default: `false`) Keeps the analyzer's memory use down, for small containers. See "Low-memory mode" below.
+    /// * `strict_file_checks: bool` - (Default: `false`) Fail files that are implausibly small for their photo or video extension with [`MediaAnalyzerError::UnsupportedFile`], instead of only setting `file_health.suspected_truncated`.
+    /// * `collect_parse_diagnostics: bool` - (Default: `false`) Record in `parse_diagnostics` each tag that was present but couldn't be converted to the type a detection expected, which otherwise only shows as a missing field.
+    /// * `trust_2d_altitude: bool` - (Default: `false`) Keep the altitude of GPS fixes that `GPSMeasureMode` marks as 2D. A 2D fix doesn't measure the altitude, so by default it is dropped. `gps.fix_mode` and `gps.fix_quality` are reported either way.
+    /// * `reverse_geocode: bool` - (Default: `true`) Look up the place name of GPS coordinates. With `false`, `gps.location` is `None`, but the coordinates are still used to find the timezone and the weather.
+    /// * `weather: bool` - (Default: `true`) Look up the historical weather. With `false`, no weather provider is created unless `weather_provider` is set, so analyses make no network requests, and `weather` is `None`.
+    /// * `completeness_weights: CompletenessWeights` - (Default: [`CompletenessWeights::default`]) The points each part of a result is worth in its `completeness_score`.
+    /// * `filename_tag_patterns: Vec<FilenameTagPattern>` - (Default: none) Regexes for naming conventions the built-in detections don't know. Each pattern is matched against the lowercased file name and the original file name, and sets its tag in `features.filename_tags` when it matches. A regex that doesn't compile fails the build with [`MediaAnalyzerError::InvalidConfiguration`].
+    /// * `locale: Locale` - (Default: [`Locale::En`]) The default language for callers' display helpers, like the weekday and weather condition names of [`Locale`], read back with [`MediaAnalyzer::locale`]. The analyzer itself doesn't use it, so results are the same in every locale.
+    /// * `track_movement: bool` - (Default: `false`) Fill `gps.movement_from_previous` with the distance, bearing and implied speed since the previously analyzed photo with GPS and a UTC time. Analyze files in capture order, one at a time: with concurrent analyses the previous photo is whichever finished last. See [`MediaAnalyzer::reset_movement_tracking`].
+    /// * `keep_exif: bool` - (Default: `true`) Keep the raw `exiftool` output in `exif`. With `false`, `exif` is `Value::Null`, like in low-memory mode.
+    /// * `timestamp_precision: Option<TimestampPrecision>` - (Default: none) The number of fractional second digits of the timestamps in [`MediaAnalyzer::to_json`], e.g. always 3 with `Millis`. By default chrono only writes the digits needed, so the precision varies between files with and without subseconds.
+    /// * `analysis_timeout: Option<Duration>` - (Default: none) Fails an analysis that takes longer than this with [`MediaAnalyzerError::Timeout`]. When `exiftool` was the stage that hung, it is replaced by a fresh process, so the next file doesn't wait behind it.
+    /// * `max_download_bytes: u64` - (Default: 2GiB) With the `http` feature, the largest file [`MediaAnalyzer::analyze_url`] downloads. Larger ones fail with [`MediaAnalyzerError::Download`] before they fill up the disk.
+    /// * `on_gps: impl Fn(&mut Option<GpsInfo>)` - Runs after GPS extraction and reverse geocoding, e.g. to override `location` from a private places database.
+    /// * `on_time: impl Fn(&mut TimeInfo)` - Runs after the capture time is determined.
+    /// * `on_features: impl Fn(&mut MediaFeatures)` - Runs after the feature flags are detected.
+    /// * `before_weather: impl Fn(&GpsInfo, &TimeInfo) -> bool` - Runs before the weather lookup, which is skipped when it returns `false`.
+    ///
+    /// Hooks run after the built-in logic of their stage. A hook that panics is logged as a warning and its changes are discarded.
+    ///
+    /// # Low-memory mode
+    ///
+    /// The timezone finder and the reverse geocoding dataset take ~100MB together. With `low_memory(true)` they take ~40MB, at these costs:
+    /// * GPS timezones are looked up without a tile index. The zones are the same, but each lookup is slower.
+    /// * Locations are reverse geocoded with a coarse dataset of one place per 1° cell per country, so `gps.location` is a town up to ~100km away and its `admin2` is empty. The country is still right, except close to a border. A shared `geocoder` is used as-is.
+    /// * The raw `exiftool` output is not kept: `exif` is `Value::Null` in the results.
     ///
     /// # Errors
     ///
@@ -58,12 +248,12 @@ impl MediaAnalyzer {
     /// # Example
     ///
     /// ```rust
-    /// # use media_analyzer::{MediaAnalyzer, MediaAnalyzerError};
+    /// # use media_analyzer::{Kilometers, MediaAnalyzer, MediaAnalyzerError};
     /// # #[tokio::main]
     /// # async fn main() -> Result<(), MediaAnalyzerError> {
     /// // Create an analyzer with a weather search radius.
     /// let analyzer = MediaAnalyzer::builder()
-    ///     .weather_search_radius_km(50.0)
+    ///     .weather_search_radius(Kilometers(50.0))
     ///     .build()
     ///     .await?;
     /// # Ok(())
@@ -71,24 +261,156 @@ impl MediaAnalyzer {
     /// ```
     #[builder]
     pub async fn new(
+        preset: Option<Preset>,
         exiftool_path: Option<&Path>,
         cache_folder: Option<PathBuf>,
-        #[builder(default = 100.0)] weather_search_radius_km: f64,
+        #[builder(default = Kilometers(100.0))] weather_search_radius: Kilometers,
+        weather_provider: Option<Arc<dyn WeatherProvider>>,
+        meteostat: Option<Arc<Meteostat>>,
+        holiday_provider: Option<Arc<dyn HolidayProvider>>,
+        geocoder: Option<Arc<ReverseGeocoder>>,
+        weather_radius_strategy: Option<RadiusStrategy>,
+        #[builder(default)] weather_context_hours: u8,
+        time_tag_priority: Option<Vec<String>>,
+        prepend_time_tag_priority: Option<Vec<String>>,
+        future_timestamp_margin: Option<TimeDelta>,
+        #[builder(default)] fall_back_on_future_timestamp: bool,
+        numeric_date_order: Option<NumericDateOrder>,
+        #[builder(default)] two_digit_years: bool,
+        #[builder(default)] timezone_itinerary: Vec<ItineraryEntry>,
+        #[builder(default)] nautical_timezone_fallback: bool,
+        #[builder(default = DEFAULT_GEOCODE_CACHE_SIZE)] geocode_cache_size: usize,
+        explain: Option<bool>,
+        #[builder(default)] hdr_detection: HdrStrictness,
+        #[builder(default)] night_mode_from_filename: bool,
+        #[builder(default = DEFAULT_SLOWMOTION_RATIO_THRESHOLD)] slowmotion_ratio_threshold: f64,
+        solar_event_window: Option<TimeDelta>,
+        #[builder(default)] low_memory: bool,
+        #[builder(default)] strict_file_checks: bool,
+        collect_parse_diagnostics: Option<bool>,
+        #[builder(default)] trust_2d_altitude: bool,
+        #[builder(default)] completeness_weights: CompletenessWeights,
+        #[builder(default)] filename_tag_patterns: Vec<FilenameTagPattern>,
+        #[builder(default)] locale: Locale,
+        #[builder(default)] track_movement: bool,
+        reverse_geocode: Option<bool>,
+        weather: Option<bool>,
+        keep_exif: Option<bool>,
+        timestamp_precision: Option<TimestampPrecision>,
+        analysis_timeout: Option<Duration>,
+        #[builder(with = |hook: impl Fn(&mut Option<GpsInfo>) + Send + Sync + 'static| Arc::new(hook) as GpsHook)]
+        on_gps: Option<GpsHook>,
+        #[builder(with = |hook: impl Fn(&mut TimeInfo) + Send + Sync + 'static| Arc::new(hook) as TimeHook)]
+        on_time: Option<TimeHook>,
+        #[builder(with = |hook: impl Fn(&mut MediaFeatures) + Send + Sync + 'static| Arc::new(hook) as FeaturesHook)]
+        on_features: Option<FeaturesHook>,
+        #[builder(with = |gate: impl Fn(&GpsInfo, &TimeInfo) -> bool + Send + Sync + 'static| Arc::new(gate) as WeatherGate)]
+        before_weather: Option<WeatherGate>,
+        #[cfg(feature = "http")]
+        #[builder(default = DEFAULT_MAX_DOWNLOAD_BYTES)]
+        max_download_bytes: u64,
     ) -> Result<Self, MediaAnalyzerError> {
-        let exiftool = match exiftool_path {
-            Some(path) => ExifTool::with_executable(path)?,
-            None => ExifTool::new()?,
+        validate_itinerary(&timezone_itinerary)
+            .map_err(MediaAnalyzerError::InvalidConfiguration)?;
+        let filename_tagger = FilenameTagger::new(filename_tag_patterns)
+            .map_err(MediaAnalyzerError::InvalidConfiguration)?;
+        if let Some(strategy) = &weather_radius_strategy {
+            strategy
+                .validate()
+                .map_err(MediaAnalyzerError::InvalidConfiguration)?;
+        }
+        let preset = preset.map(Preset::options).unwrap_or_default();
+        let explain = explain.unwrap_or(preset.explain);
+        let collect_parse_diagnostics =
+            collect_parse_diagnostics.unwrap_or(preset.collect_parse_diagnostics);
+        let reverse_geocode = reverse_geocode.unwrap_or(preset.reverse_geocode);
+        let weather = weather.unwrap_or(preset.weather);
+        let keep_exif = keep_exif.unwrap_or(preset.keep_exif);
+        let exiftool = ExifToolProcess::start(exiftool_path)?;
+        let exiftool_version = exiftool
+            .current()
+            .execute_lines(&["-ver"])
+            .ok()
+            .and_then(|lines| lines.into_iter().next())
+            .map(|version| version.trim().to_string());
+        let weather_provider: Arc<dyn WeatherProvider> = match (weather_provider, meteostat) {
+            (Some(provider), _) => provider,
+            (None, _) if !weather => Arc::new(NoWeather),
+            (None, meteostat) => {
+                let meteostat = match (meteostat, &cache_folder) {
+                    (Some(meteostat), _) => meteostat,
+                    (None, Some(path)) => {
+                        Arc::new(Meteostat::with_cache_folder(path.clone()).await?)
+                    }
+                    (None, None) => Arc::new(Meteostat::new().await?),
+                };
+                let provider = MeteostatProvider::shared(meteostat, weather_search_radius.into());
+                Arc::new(match cache_folder.or_else(default_meteostat_cache_folder) {
+                    Some(folder) => provider.with_cache_folder(folder),
+                    None => provider,
+                })
+            }
         };
-        let meteostat = match cache_folder {
-            Some(path) => Meteostat::with_cache_folder(path).await?,
-            None => Meteostat::new().await?,
+        let geocoder = match geocoder {
+            Some(geocoder) => CachedGeocoder::with_geocoder(geocoder, geocode_cache_size),
+            None if low_memory => CachedGeocoder::coarse(geocode_cache_size),
+            None => CachedGeocoder::new(geocode_cache_size),
         };
-        let geocoder = ReverseGeocoder::new();
         Ok(Self {
-            geocoder,
-            exiftool,
-            meteostat,
-            weather_search_radius_km,
+            geocoder: Arc::new(geocoder),
+            exiftool: Arc::new(exiftool),
+            weather_provider,
+            holiday_provider: holiday_provider.unwrap_or_else(|| Arc::new(BundledHolidays)),
+            weather_radius_strategy: weather_radius_strategy
+                .unwrap_or(RadiusStrategy::Fixed(weather_search_radius.into())),
+            weather_context_hours,
+            time_options: TimeOptions {
+                time_tag_priority: resolve_time_tag_priority(
+                    time_tag_priority,
+                    prepend_time_tag_priority,
+                ),
+                future_margin: future_timestamp_margin.unwrap_or(DEFAULT_FUTURE_TIMESTAMP_MARGIN),
+                fall_back_on_future_timestamp,
+                now: None,
+                corrections: Vec::new(),
+                low_memory,
+                numeric_date_order,
+                two_digit_years,
+                itinerary: timezone_itinerary,
+                nautical_fallback: nautical_timezone_fallback,
+            },
+            feature_options: FeatureOptions {
+                explain,
+                hdr_strictness: hdr_detection,
+                night_mode_from_filename,
+                slowmotion_ratio_threshold,
+                solar_event_window: solar_event_window.unwrap_or(DEFAULT_SOLAR_EVENT_WINDOW),
+            },
+            completeness_weights,
+            filename_tagger,
+            locale,
+            movement: track_movement.then(MovementTracker::default),
+            low_memory,
+            strict_file_checks,
+            collect_parse_diagnostics,
+            trust_2d_altitude,
+            reverse_geocode,
+            weather,
+            keep_exif,
+            timestamp_precision,
+            analysis_timeout,
+            exiftool_version,
+            hooks: AnalysisHooks {
+                on_gps,
+                on_time,
+                on_features,
+                before_weather,
+            },
+            #[cfg(feature = "http")]
+            http_client: download::client()
+                .map_err(|e| MediaAnalyzerError::InvalidConfiguration(e.to_string()))?,
+            #[cfg(feature = "http")]
+            max_download_bytes,
         })
     }
 
@@ -105,22 +427,36 @@ impl MediaAnalyzer {
     /// # Returns
     ///
     /// On success, returns a `Result` containing an [`MediaMetadata`] struct with the following fields:
-    /// * `exif`: The raw, grouped numeric (`-n -g2`) JSON output from `exiftool`.
+    /// * `exif`: The raw, grouped numeric (`-n -g2`) JSON output from `exiftool`. `Value::Null` in low-memory mode.
     /// * `metadata`: Core file properties like width, height, and duration.
     /// * `capture_details`: Photographic details like ISO, aperture, and camera model.
     /// * `tags`: Boolean flags for special media types (e.g., `is_motion_photo`, `is_slowmotion`).
     /// * `time_info`: Consolidated time information, including the best-guess UTC timestamp and timezone.
     /// * `pano_info`: Data related to panoramic images, including photospheres.
     /// * `gps_info`: GPS coordinates and reverse-geocoded location details.
+    /// * `quality_hints`: Metadata-based warnings about likely quality issues (high ISO, handheld long exposure, digital zoom, ...).
+    /// * `file_health`: Whether the file looks cut short: implausibly small, or a JPEG without an end marker.
+    /// * `parse_diagnostics`: With `collect_parse_diagnostics`, the tags that had an unexpected JSON type.
+    /// * `dedup_key`: A camera + capture time key shared by re-exports of the same shot. `None` when the time confidence is low or the camera is unknown.
+    /// * `thumbnail_timestamp_s`: Only set by [`MediaAnalyzer::analyze_media_with_thumbnail`].
+    /// * `provenance`: The crate and `exiftool` versions, the analysis time, and a fingerprint of the configuration.
+    /// * `aux_images`: Embedded gain maps, depth maps, portrait mattes and large thumbnails. Use [`MediaAnalyzer::extract_aux_image`] to read one.
+    /// * `user_metadata`: The description, IPTC event and written (IPTC/XMP) location. A written country that differs from the geocoded one sets `gps.location_conflict`. Also the XMP document ids, which [`link_versions`](crate::link_versions) uses to link edits to their original.
+    /// * `skipped_stages`: The optional stages that didn't run, with the reason: geocoding, the GPS timezone lookup and the weather lookup, which also lists a failed lookup.
+    /// * `ingest_audit`: Seconds from capture to the file's last modification, and whether the file name follows the camera make's naming scheme and the extension fits the file type.
+    /// * `notes`: Non-fatal information as typed [`AnalysisNote`](crate::AnalysisNote)s, sorted by module and code: the skipped stages, a failed weather lookup, a place the geocoder couldn't name and malformed duration tags. Empty when everything ran.
     /// * `weather_info`: Historical weather and sun information for the time and place of capture. This is a "best-effort" field and will be `None` if GPS or time data is missing, or if the weather service fails.
     ///
     /// # Errors
     ///
     /// This function will return an error if any of the critical analysis steps fail, such as:
     /// * [`MediaAnalyzerError::DataUrl`]: The provided `thumbnail` path is invalid or not an image.
+    /// * [`MediaAnalyzerError::EmptyFile`]: The `media_file` is zero bytes, checked before running `exiftool`.
+    /// * [`MediaAnalyzerError::UnsupportedFile`]: With `strict_file_checks`, the `media_file` is suspected to be truncated.
     /// * [`MediaAnalyzerError::Exiftool`]: `exiftool` fails to execute or read the `media_file`.
     /// * [`MediaAnalyzerError::Metadata`]: The `media_file` is missing essential metadata (e.g., `ImageWidth`).
     /// * [`MediaAnalyzerError::Time`]: No usable time information could be extracted from any source.
+    /// * [`MediaAnalyzerError::Timeout`]: The analysis took longer than `analysis_timeout`.
     ///
     /// # Example
     ///
@@ -144,52 +480,1122 @@ impl MediaAnalyzer {
         &self,
         media_file: &Path,
     ) -> Result<MediaMetadata, MediaAnalyzerError> {
-        let (hash, exif_value) = rayon::join(
-            || hash_file(media_file),
-            || self.exiftool.json(media_file, &["-n", "-g2"]),
-        );
-        let hash = hash?;
-        let exif_value = exif_value?;
-        let exif = ExifData::new(exif_value.clone());
-
-        let (basic, camera) = get_metadata(&exif)?;
-        let features = extract_features(media_file, &exif);
-        let gps = get_gps_info(&self.geocoder, &exif);
-        let use_panorama_viewer = should_use_pano_viewer(&exif);
-        let time = get_time_info(&exif, gps.as_ref())?;
-
-        let weather = if let (Some(gps), Some(utc_time)) = (gps.as_ref(), time.datetime_utc) {
-            get_weather_info(
-                &self.meteostat,
-                gps,
-                utc_time,
-                self.weather_search_radius_km,
-            )
+        self.analyze(media_file, None, None, None).await
+    }
+
+    /// Analyzes a media file like [`MediaAnalyzer::analyze_media`] or, with a `thumbnail`,
+    /// [`MediaAnalyzer::analyze_media_with_thumbnail`], until `token` is cancelled.
+    ///
+    /// Cancelling interrupts the analysis at its next await: while `exiftool` reads the file,
+    /// or during the weather lookup. When `exiftool` was interrupted, it is replaced by a fresh
+    /// process like after a timeout, so the analyzer stays usable.
+    ///
+    /// # Errors
+    ///
+    /// The same as [`MediaAnalyzer::analyze_media`], and [`MediaAnalyzerError::Cancelled`]
+    /// once `token` is cancelled.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use std::path::Path;
+    /// # use media_analyzer::{CancellationToken, MediaAnalyzer, MediaAnalyzerError};
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), MediaAnalyzerError> {
+    /// let analyzer = MediaAnalyzer::builder().build().await?;
+    /// let token = CancellationToken::new();
+    /// // Hand a clone to the cancel button.
+    /// let cancel_button = token.clone();
+    ///
+    /// let result = analyzer
+    ///     .analyze_media_cancellable(Path::new("assets/tent.jpg"), None, &token)
+    ///     .await;
+    /// # drop(cancel_button);
+    /// # result?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn analyze_media_cancellable(
+        &self,
+        media_file: &Path,
+        thumbnail: Option<ThumbnailSource>,
+        token: &CancellationToken,
+    ) -> Result<MediaMetadata, MediaAnalyzerError> {
+        self.analyze(media_file, thumbnail.as_ref(), None, Some(token))
             .await
-            .ok()
+    }
+
+    /// Analyzes a video like [`MediaAnalyzer::analyze_media`], and records where in the video the
+    /// thumbnail frame was taken in `thumbnail_timestamp_s`.
+    ///
+    /// The timestamp is taken from `thumbnail.timestamp_s`, or inferred from ffmpeg style frame
+    /// names (`frame_00012.jpg`) and the video frame rate. It is clamped to the video duration.
+    /// For photos, `thumbnail_timestamp_s` is always `None`.
+    ///
+    /// # Errors
+    ///
+    /// The same as [`MediaAnalyzer::analyze_media`].
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use std::path::Path;
+    /// # use media_analyzer::{MediaAnalyzer, MediaAnalyzerError, ThumbnailSource};
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), MediaAnalyzerError> {
+    /// let analyzer = MediaAnalyzer::builder().build().await?;
+    /// let thumbnail = ThumbnailSource::new("frames/frame_00031.jpg", None);
+    ///
+    /// let result = analyzer
+    ///     .analyze_media_with_thumbnail(Path::new("assets/video/car.webm"), thumbnail)
+    ///     .await?;
+    /// println!("Thumbnail taken at {:?}s", result.thumbnail_timestamp_s);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn analyze_media_with_thumbnail(
+        &self,
+        media_file: &Path,
+        thumbnail: impl Into<ThumbnailSource>,
+    ) -> Result<MediaMetadata, MediaAnalyzerError> {
+        self.analyze(media_file, Some(&thumbnail.into()), None, None)
+            .await
+    }
+
+    /// Analyzes a media file like [`MediaAnalyzer::analyze_media`], with `exiftool` output
+    /// that was read before, such as a test fixture saved with [`RawExif::save`].
+    ///
+    /// The file itself is still read for the hash, file health checks and embedded images.
+    ///
+    /// # Errors
+    ///
+    /// The same as [`MediaAnalyzer::analyze_media`], except for `exiftool` errors.
+    pub async fn analyze_from_exif(
+        &self,
+        media_file: &Path,
+        raw: RawExif,
+    ) -> Result<MediaMetadata, MediaAnalyzerError> {
+        self.analyze(media_file, None, Some(raw.grouped), None)
+            .await
+    }
+
+    /// Analyzes the files in `dir` that are new or changed since they were recorded in
+    /// `manifest`, and updates it, for folders that are synced again and again.
+    ///
+    /// A file is unchanged when its size and modification time match its entry, or its size
+    /// and BLAKE3 hash with `options.compare_hashes`. A file that was only touched is analyzed
+    /// again without `compare_hashes`, and reported with `result_changed` off unless its capture
+    /// time comes from the file system. Entries of files in `dir` that no longer exist are
+    /// removed. Keep the manifest file outside of `dir`, or it is reported as failed.
+    ///
+    /// # Errors
+    ///
+    /// * [`MediaAnalyzerError::Io`]: `dir` or one of its subdirectories can't be listed. Errors
+    ///   of single files are reported in `failed`.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// # use std::path::Path;
+    /// # use media_analyzer::{IncrementalOptions, Manifest, MediaAnalyzer, MediaAnalyzerError};
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), MediaAnalyzerError> {
+    /// let analyzer = MediaAnalyzer::builder().build().await?;
+    /// let manifest_path = Path::new("photos.manifest.json");
+    /// let mut manifest = Manifest::load(manifest_path)?;
+    ///
+    /// let options = IncrementalOptions::default();
+    /// let report = analyzer
+    ///     .analyze_directory_incremental(Path::new("photos"), &mut manifest, options)
+    ///     .await?;
+    /// println!("{} files analyzed", report.analyzed.len());
+    /// manifest.save(manifest_path)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn analyze_directory_incremental(
+        &self,
+        dir: &Path,
+        manifest: &mut Manifest,
+        options: IncrementalOptions,
+    ) -> Result<IncrementalReport, MediaAnalyzerError> {
+        let files = list_files(dir, options.recursive)?;
+        let mut report = IncrementalReport::default();
+        for path in &files {
+            let stamp = match FileStamp::read(path, options.compare_hashes) {
+                Ok(stamp) => stamp,
+                Err(e) => {
+                    report.failed.push((path.clone(), e.into()));
+                    continue;
+                }
+            };
+            if let Some(entry) = manifest.entries.get_mut(path)
+                && stamp.matches(entry)
+            {
+                stamp.refresh(entry);
+                report.skipped_unchanged.push(path.clone());
+                continue;
+            }
+            match self.analyze_media(path).await {
+                Ok(result) => {
+                    let digest = result_digest(&result);
+                    let result_changed = manifest
+                        .entries
+                        .get(path)
+                        .is_none_or(|entry| entry.result_digest != digest);
+                    manifest
+                        .entries
+                        .insert(path.clone(), stamp.into_entry(digest));
+                    report.analyzed.push(AnalyzedFile {
+                        path: path.clone(),
+                        result,
+                        result_changed,
+                    });
+                }
+                Err(e) => report.failed.push((path.clone(), e)),
+            }
+        }
+
+        let listed: HashSet<&PathBuf> = files.iter().collect();
+        manifest.entries.retain(|path, _| {
+            let in_dir = if options.recursive {
+                path.starts_with(dir)
+            } else {
+                path.parent() == Some(dir)
+            };
+            if in_dir && !listed.contains(path) {
+                report.removed.push(path.clone());
+                return false;
+            }
+            true
+        });
+        Ok(report)
+    }
+
+    /// Runs the same `exiftool` call as [`MediaAnalyzer::analyze_media`], with the analyzer's
+    /// `exiftool` instance, and returns its output. Use it to debug detections, or to create
+    /// fixtures for [`MediaAnalyzer::analyze_from_exif`].
+    ///
+    /// # Errors
+    ///
+    /// * [`MediaAnalyzerError::Exiftool`]: `exiftool` fails to execute or read the file.
+    pub fn raw_exif(&self, media_file: &Path) -> Result<RawExif, MediaAnalyzerError> {
+        let grouped = self
+            .exiftool
+            .current()
+            .json(media_file, &ANALYSIS_EXIFTOOL_ARGS)?;
+        Ok(RawExif {
+            grouped,
+            exiftool_args: ANALYSIS_EXIFTOOL_ARGS.map(str::to_string).to_vec(),
+        })
+    }
+
+    /// The capture details of each image in a multi-image file, such as an exposure stack or an
+    /// edit kept with its original (see `basic.stack_image_count`). This runs `exiftool`
+    /// again with sub-document extraction, so it is slower than the analysis itself.
+    ///
+    /// The primary image is member 0, and embedded images follow in file order. Images without
+    /// exposure data, like thumbnails and gain maps, are left out. A single image gives one
+    /// member, or none when it has no exposure data.
+    ///
+    /// # Errors
+    ///
+    /// * [`MediaAnalyzerError::Exiftool`]: `exiftool` fails to execute or read the file.
+    pub fn analyze_stack_members(
+        &self,
+        media_file: &Path,
+    ) -> Result<Vec<(usize, CameraSettings)>, MediaAnalyzerError> {
+        let documents = self
+            .exiftool
+            .current()
+            .json(media_file, &STACK_EXIFTOOL_ARGS)?;
+        Ok(stack_members(&documents))
+    }
+
+    /// Analyzes media that is only available in memory, such as an upload.
+    ///
+    /// `exiftool` reads from disk, so the bytes are written to a temporary file named after
+    /// `filename` (the file name matters for e.g. burst and Night Sight detection), which is
+    /// removed afterwards.
+    ///
+    /// # Errors
+    ///
+    /// The same as [`MediaAnalyzer::analyze_media`], and [`MediaAnalyzerError::Io`] when the
+    /// temporary file cannot be written.
+    pub async fn analyze_bytes(
+        &self,
+        bytes: &[u8],
+        filename: &str,
+    ) -> Result<MediaMetadata, MediaAnalyzerError> {
+        let (dir, path) = temp_media_path(filename)?;
+        let result = match fs::write(&path, bytes) {
+            Ok(()) => self.analyze_media(&path).await,
+            Err(e) => Err(e.into()),
+        };
+        // Best effort, a leftover file in the temp dir is not worth failing the analysis for.
+        let _ = fs::remove_dir_all(&dir);
+        result
+    }
+
+    /// Downloads media from a URL, such as a presigned object storage URL, and analyzes it.
+    ///
+    /// The body is streamed to a temporary file, like [`MediaAnalyzer::analyze_bytes`] does with
+    /// bytes, and removed afterwards. The file is named after `filename_hint`, or else the
+    /// response's `Content-Disposition` header, or else the last segment of the URL's path, as
+    /// the file name matters for e.g. burst and Night Sight detection. The `analysis_timeout`
+    /// includes the download.
+    ///
+    /// # Errors
+    ///
+    /// The same as [`MediaAnalyzer::analyze_media`], and [`MediaAnalyzerError::Download`] when
+    /// the request fails, the server responds with an error status, or the file is larger than
+    /// `max_download_bytes`. Its `url` is without the query string, which holds the signature
+    /// of a presigned URL.
+    #[cfg(feature = "http")]
+    pub async fn analyze_url(
+        &self,
+        url: &str,
+        filename_hint: Option<&str>,
+    ) -> Result<MediaMetadata, MediaAnalyzerError> {
+        let dir = temp_media_dir()?;
+        let exiftool = self.exiftool.current();
+        let stage = Mutex::new(Some(STAGE_DOWNLOAD));
+        let analysis = async {
+            let response = download::get(&self.http_client, url).await?;
+            let filename = filename_hint
+                .map_or_else(|| download::response_filename(&response), str::to_string);
+            let path = temp_media_file(&dir, &filename);
+            download::write_body(response, &path, self.max_download_bytes).await?;
+            self.run(&path, None, Arc::clone(&exiftool), None, &stage)
+                .await
+        };
+        let result = self.within_limits(analysis, &exiftool, &stage, None).await;
+        let _ = fs::remove_dir_all(&dir);
+        if let Err(e) = &result {
+            record_error(e);
+        }
+        result
+    }
+
+    /// Runs the analysis pipeline until it finishes, times out after `analysis_timeout`, or
+    /// `cancel` is cancelled, and counts a failure in the metrics.
+    async fn analyze(
+        &self,
+        media_file: &Path,
+        thumbnail: Option<&ThumbnailSource>,
+        raw_exif: Option<Value>,
+        cancel: Option<&CancellationToken>,
+    ) -> Result<MediaMetadata, MediaAnalyzerError> {
+        let result = self
+            .analyze_within_limits(media_file, thumbnail, raw_exif, cancel)
+            .await;
+        if let Err(e) = &result {
+            record_error(e);
+        }
+        result
+    }
+
+    async fn analyze_within_limits(
+        &self,
+        media_file: &Path,
+        thumbnail: Option<&ThumbnailSource>,
+        raw_exif: Option<Value>,
+        cancel: Option<&CancellationToken>,
+    ) -> Result<MediaMetadata, MediaAnalyzerError> {
+        if cancel.is_some_and(CancellationToken::is_cancelled) {
+            return Err(MediaAnalyzerError::Cancelled { stage: None });
+        }
+        let exiftool = self.exiftool.current();
+        let stage = Mutex::new(None);
+        let analysis = self.run(
+            media_file,
+            thumbnail,
+            Arc::clone(&exiftool),
+            raw_exif,
+            &stage,
+        );
+        self.within_limits(analysis, &exiftool, &stage, cancel)
+            .await
+    }
+
+    /// Runs `analysis` until it finishes, times out after `analysis_timeout`, or `cancel` is
+    /// cancelled. `exiftool` is restarted when it was interrupted, according to `stage`.
+    async fn within_limits(
+        &self,
+        analysis: impl Future<Output = Result<MediaMetadata, MediaAnalyzerError>>,
+        exiftool: &Arc<StayOpen>,
+        stage: &Mutex<Option<&'static str>>,
+        cancel: Option<&CancellationToken>,
+    ) -> Result<MediaMetadata, MediaAnalyzerError> {
+        if self.analysis_timeout.is_none() && cancel.is_none() {
+            return analysis.await;
+        }
+        let started = Instant::now();
+        let timeout = async {
+            match self.analysis_timeout {
+                Some(limit) => tokio::time::sleep(limit).await,
+                None => std::future::pending().await,
+            }
+        };
+        let cancelled = async {
+            match cancel {
+                Some(token) => token.cancelled().await,
+                None => std::future::pending().await,
+            }
+        };
+        let timed_out = tokio::select! {
+            result = analysis => return result,
+            () = timeout => true,
+            () = cancelled => false,
+        };
+
+        let stage = *stage.lock().unwrap_or_else(PoisonError::into_inner);
+        if stage == Some(STAGE_EXIFTOOL)
+            && let Err(e) = self.exiftool.restart(exiftool)
+        {
+            log::warn!("Could not restart exiftool after an interrupted analysis: {e}");
+        }
+        let stage = stage.map(str::to_string);
+        Err(if timed_out {
+            MediaAnalyzerError::Timeout {
+                elapsed: started.elapsed(),
+                stage,
+            }
+        } else {
+            MediaAnalyzerError::Cancelled { stage }
+        })
+    }
+
+    /// The analysis pipeline, recording the stage it is in in `stage`. `exiftool` is skipped
+    /// when its output is passed as `raw_exif`.
+    async fn run(
+        &self,
+        media_file: &Path,
+        thumbnail: Option<&ThumbnailSource>,
+        exiftool: Arc<StayOpen>,
+        raw_exif: Option<Value>,
+        stage: &Mutex<Option<&'static str>>,
+    ) -> Result<MediaMetadata, MediaAnalyzerError> {
+        let timer = StageTimer::default();
+        let enter = |name| {
+            *stage.lock().unwrap_or_else(PoisonError::into_inner) = Some(name);
+            timer.enter(name);
+        };
+        let mut file_health = check_file_health(media_file, self.strict_file_checks)?;
+
+        enter(STAGE_EXIFTOOL);
+        // On blocking threads, so a timeout can fire while `exiftool` is stuck. Not on the rayon
+        // pool, where a stuck call would hold up the hashing of later files.
+        let path = media_file.to_path_buf();
+        let hash = tokio::task::spawn_blocking({
+            let path = path.clone();
+            move || hash_file(&path)
+        });
+        let exif_value = tokio::task::spawn_blocking(move || {
+            raw_exif.map_or_else(|| exiftool.json(&path, &ANALYSIS_EXIFTOOL_ARGS), Ok)
+        });
+        let (hash, exif_value) = tokio::join!(hash, exif_value);
+        let hash = hash.unwrap_or_else(|e| panic::resume_unwind(e.into_panic()))?;
+        let exif_value = exif_value.unwrap_or_else(|e| panic::resume_unwind(e.into_panic()))?;
+        let mut exif = ExifData::new(exif_value);
+        if self.collect_parse_diagnostics {
+            exif = exif.with_parse_diagnostics();
+        }
+
+        enter(STAGE_METADATA);
+
+        let (mut basic, camera) = get_metadata(&exif)?;
+        let quality_hints = get_quality_hints(&basic, &camera, &exif);
+        let mut features = extract_features(media_file, &exif, &self.feature_options);
+        let filenames = FilenameCandidates::new(media_file, &exif);
+        for (tag_name, (id, kind)) in self.filename_tagger.tags(&filenames) {
+            if let Some(explanations) = features.explanations.as_mut() {
+                explanations.insert(
+                    format!("filenameTags.{tag_name}"),
+                    format!("{} matches the `{tag_name}` pattern", kind.describe()),
+                );
+            }
+            features.filename_tags.insert(tag_name, id);
+        }
+        let mut gps = if self.reverse_geocode {
+            get_gps_info(&self.geocoder, &exif)
         } else {
-            None
+            get_gps_coordinates(&exif)
+        };
+        if let Some(gps) = gps.as_mut()
+            && !self.trust_2d_altitude
+        {
+            drop_2d_fix_altitude(gps);
+        }
+        let user_metadata = get_user_metadata(&exif);
+        if let (Some(gps), Some(written)) = (gps.as_mut(), &user_metadata.written_location) {
+            gps.location_conflict = gps
+                .location
+                .as_ref()
+                .and_then(|location| location_conflict(written, location));
+        }
+        self.hooks.run_gps(&mut gps);
+        if let Some(gps) = gps.as_mut() {
+            gps.timezone = lookup_gps_zone(gps, &self.time_options).map(|tz| tz.name().to_string());
+        }
+        // Spherical videos are flagged in the features instead.
+        let pano_evidence = pano_viewer_evidence(&exif).filter(|_| !features.is_video);
+        let use_panorama_viewer = pano_evidence.is_some();
+        if let (Some(explanations), Some(evidence)) =
+            (features.explanations.as_mut(), pano_evidence)
+        {
+            explanations.insert("usePanoramaViewer".to_string(), evidence);
+        }
+        // Unstitched 360 degree videos keep theirs, to tell a stitching pipeline about them.
+        let pano =
+            get_pano_view_info(&exif).filter(|pano| !features.is_video || pano.needs_stitching);
+        let mut time = get_time_info(&exif, gps.as_ref(), &self.time_options)?;
+        self.hooks.run_time(&mut time);
+        if let (Some(tracker), Some(gps)) = (&self.movement, gps.as_mut()) {
+            gps.movement_from_previous = tracker.track(gps, time.datetime_utc);
+        }
+        let dedup_key = get_dedup_key(&camera, &time);
+        let ingest_audit = get_ingest_audit(media_file, &exif, &camera, &time);
+        let aux_images = get_aux_images(media_file, &exif);
+        let provenance = AnalysisProvenance {
+            crate_version: CRATE_VERSION.to_string(),
+            exiftool_version: self.exiftool_version.clone(),
+            analyzed_at: Utc::now(),
+            config_fingerprint: self.config_fingerprint(),
+            logic_revisions: LogicRevisions::current(),
+        };
+
+        let parse_diagnostics = exif.take_parse_diagnostics();
+
+        let mut notes = duration_notes(&exif);
+        notes.extend(mime_type_notes(&exif));
+        notes.extend(metadata_stripped_notes(basic.metadata_stripped, &time));
+        if let Some(location) = gps.as_ref().and_then(|gps| gps.location.as_ref())
+            && location.name.is_empty()
+        {
+            notes.push(
+                AnalysisNote::warning(
+                    STAGE_GEOCODE,
+                    CODE_NO_PLACE_NAME,
+                    "no place found near the coordinates, only the country",
+                )
+                .with_data(json!({
+                    "latitude": location.latitude,
+                    "longitude": location.longitude,
+                })),
+            );
+        }
+        let mut skipped_stages = skipped_before_weather(gps.as_ref(), &time, self.reverse_geocode);
+        notes.extend(skipped_stages.iter().map(AnalysisNote::skipped));
+        let weather = match weather_prerequisites(gps.as_ref(), &time, &self.hooks, self.weather) {
+            Ok((gps, utc_time)) => {
+                enter(STAGE_WEATHER);
+                let weather = get_weather_info(
+                    self.weather_provider.as_ref(),
+                    gps,
+                    utc_time,
+                    time.timezone.as_ref(),
+                    &self.weather_radius_strategy,
+                    self.weather_context_hours,
+                )
+                .await;
+                match weather {
+                    Ok(weather) => {
+                        record_weather_result(WEATHER_HIT);
+                        Some(weather)
+                    }
+                    Err(e) => {
+                        record_weather_result(match e {
+                            WeatherError::NoDataAvailable => WEATHER_NO_DATA,
+                            _ => WEATHER_ERROR,
+                        });
+                        let reason = format!("lookup failed: {e}");
+                        notes.push(AnalysisNote::warning(
+                            STAGE_WEATHER,
+                            CODE_LOOKUP_FAILED,
+                            reason.clone(),
+                        ));
+                        skipped_stages.push(SkippedStage::new(STAGE_WEATHER, reason));
+                        None
+                    }
+                }
+            }
+            Err(skipped) => {
+                record_weather_result(WEATHER_SKIPPED);
+                notes.push(AnalysisNote::skipped(&skipped));
+                skipped_stages.push(skipped);
+                None
+            }
         };
+        // The rest of the analysis is metadata work again.
+        timer.enter(STAGE_METADATA);
+        normalize_notes(&mut notes);
+
+        apply_astrophotography(
+            &mut features,
+            &exif,
+            &camera,
+            &time,
+            gps.as_ref(),
+            weather.as_ref(),
+        );
+        apply_solar_events(&mut features, &time, gps.as_ref(), &self.feature_options);
+        let calendar_context =
+            get_calendar_context(&time, gps.as_ref(), self.holiday_provider.as_ref());
+        self.hooks.run_features(&mut features);
+        // The video of a motion photo comes after the JPEG's EOI marker.
+        file_health.truncated_jpeg &= !features.is_motion_photo;
+        if !features.is_video
+            && let Some((width, height)) = decoded_dimension_mismatch(media_file, &exif)
+        {
+            file_health.dimension_mismatch = true;
+            basic.actual_width = Some(width);
+            basic.actual_height = Some(height);
+        }
+        let thumbnail_timestamp_s = thumbnail.filter(|_| features.is_video).and_then(|source| {
+            resolve_thumbnail_timestamp(source, features.video_fps, basic.duration.map(f64::from))
+        });
 
-        Ok(MediaMetadata {
+        let mut result = MediaMetadata {
             hash,
-            exif: exif_value,
+            exif: if self.low_memory || !self.keep_exif {
+                Value::Null
+            } else {
+                exif.into_inner()
+            },
             features,
             time,
             gps,
             use_panorama_viewer,
+            pano,
             basic,
             camera,
             weather,
+            calendar_context: Some(calendar_context),
+            quality_hints,
+            file_health,
+            parse_diagnostics,
+            dedup_key,
+            thumbnail_timestamp_s,
+            aux_images,
+            user_metadata,
+            skipped_stages,
+            notes,
+            ingest_audit: Some(ingest_audit),
+            completeness_score: 0,
+            completeness: CompletenessBreakdown::default(),
+            provenance: Some(provenance),
+        };
+        (result.completeness_score, result.completeness) =
+            score_completeness(&result, &self.completeness_weights);
+        record_time_confidence(&result.time.source_details.confidence);
+        Ok(result)
+    }
+
+    /// A stable hash of the options that affect analysis results: the weather radius strategy
+    /// and context hours, whether the weather is looked up, time tag priority, future timestamp handling, time corrections, explain mode, HDR
+    /// strictness, filename-only night mode, date parsing, nautical timezones, 2D fix altitudes, reverse geocoding, whether the exif is kept, completeness weights, movement tracking, filename tag patterns and low-memory mode. It is recorded in each result's
+    /// `provenance`, so results produced with a different configuration can be found and re-analyzed.
+    #[must_use]
+    pub fn config_fingerprint(&self) -> String {
+        let filename_tag_patterns = self.filename_tagger.patterns().cloned().collect::<Vec<_>>();
+        config_fingerprint(&FingerprintedConfig {
+            weather_radius_strategy: &self.weather_radius_strategy,
+            weather_context_hours: self.weather_context_hours,
+            time_options: &self.time_options,
+            feature_options: &self.feature_options,
+            trust_2d_altitude: self.trust_2d_altitude,
+            reverse_geocode: self.reverse_geocode,
+            weather: self.weather,
+            keep_exif: self.keep_exif,
+            track_movement: self.movement.is_some(),
+            completeness_weights: self.completeness_weights,
+            filename_tag_patterns: &filename_tag_patterns,
+        })
+    }
+
+    /// Serializes `result` to JSON with the analyzer's `timestamp_precision`, see
+    /// [`MediaMetadata::to_json_with_options`].
+    ///
+    /// # Errors
+    /// * If serializing the result fails.
+    pub fn to_json(&self, result: &MediaMetadata) -> serde_json::Result<Value> {
+        result.to_json_with_options(&SerializeOptions {
+            timestamp_precision: self.timestamp_precision,
+        })
+    }
+
+    /// The [`StalenessPolicy`] of this analyzer: results are stale when produced by older
+    /// detection logic or with another configuration.
+    #[must_use]
+    pub fn staleness_policy(&self) -> StalenessPolicy {
+        StalenessPolicy::current().with_config_fingerprint(self.config_fingerprint())
+    }
+
+    /// Writes an embedded image listed in [`MediaMetadata::aux_images`] to `out`, returning the
+    /// number of bytes written.
+    ///
+    /// The image is copied straight from its recorded offset when it is known, and otherwise
+    /// extracted with `exiftool -b -<exiftool_tag>`.
+    ///
+    /// # Errors
+    ///
+    /// * [`MediaAnalyzerError::AuxImageNotLocated`]: Neither the offset nor an `exiftool` tag is known, as for HEIC auxiliary images.
+    /// * [`MediaAnalyzerError::Exiftool`]: `exiftool` fails to extract the tag.
+    /// * [`MediaAnalyzerError::Io`]: The file can't be read, or `out` can't be written to.
+    pub fn extract_aux_image(
+        &self,
+        media_file: &Path,
+        info: &AuxImageInfo,
+        out: &mut impl Write,
+    ) -> Result<u64, MediaAnalyzerError> {
+        match (&info.offset_bytes, &info.length_bytes, &info.exiftool_tag) {
+            (Some(_), Some(_), _) => copy_aux_image(media_file, info, out),
+            (_, _, Some(tag)) => {
+                let bytes = self.exiftool.current().read_tag_binary(media_file, tag)?;
+                out.write_all(&bytes)?;
+                Ok(bytes.len() as u64)
+            }
+            _ => Err(MediaAnalyzerError::AuxImageNotLocated(
+                info.semantic.clone(),
+            )),
+        }
+    }
+
+    /// Creates an analyzer that shares this one's `exiftool` process, weather provider and
+    /// geocoder (including its cache), with some lightweight settings changed. This is much
+    /// cheaper than building a new analyzer.
+    ///
+    /// # Errors
+    ///
+    /// * [`MediaAnalyzerError::InvalidConfiguration`]: An override is rejected like the builder
+    ///   rejects it: an adaptive radius strategy that cannot widen, or overlapping itinerary
+    ///   entries.
+    pub fn with_config(&self, overrides: ConfigOverrides) -> Result<Self, MediaAnalyzerError> {
+        let weather_radius_strategy = overrides
+            .weather_radius_strategy
+            .unwrap_or(self.weather_radius_strategy);
+        weather_radius_strategy
+            .validate()
+            .map_err(MediaAnalyzerError::InvalidConfiguration)?;
+        let time_options = match overrides.time_options {
+            Some(time_options) => {
+                validate_itinerary(&time_options.itinerary)
+                    .map_err(MediaAnalyzerError::InvalidConfiguration)?;
+                TimeOptions {
+                    time_tag_priority: resolve_time_tag_priority(
+                        time_options.time_tag_priority,
+                        None,
+                    ),
+                    low_memory: self.low_memory,
+                    ..time_options
+                }
+            }
+            None => self.time_options.clone(),
+        };
+        Ok(Self {
+            geocoder: Arc::clone(&self.geocoder),
+            exiftool: Arc::clone(&self.exiftool),
+            weather_provider: Arc::clone(&self.weather_provider),
+            holiday_provider: Arc::clone(&self.holiday_provider),
+            weather_radius_strategy,
+            weather_context_hours: overrides
+                .weather_context_hours
+                .unwrap_or(self.weather_context_hours),
+            time_options,
+            feature_options: FeatureOptions {
+                explain: overrides.explain.unwrap_or(self.feature_options.explain),
+                hdr_strictness: overrides
+                    .hdr_detection
+                    .unwrap_or(self.feature_options.hdr_strictness),
+                night_mode_from_filename: overrides
+                    .night_mode_from_filename
+                    .unwrap_or(self.feature_options.night_mode_from_filename),
+                slowmotion_ratio_threshold: overrides
+                    .slowmotion_ratio_threshold
+                    .unwrap_or(self.feature_options.slowmotion_ratio_threshold),
+                solar_event_window: self.feature_options.solar_event_window,
+            },
+            completeness_weights: self.completeness_weights,
+            filename_tagger: self.filename_tagger.clone(),
+            locale: self.locale,
+            movement: self.movement.as_ref().map(|_| MovementTracker::default()),
+            low_memory: self.low_memory,
+            strict_file_checks: self.strict_file_checks,
+            collect_parse_diagnostics: self.collect_parse_diagnostics,
+            trust_2d_altitude: self.trust_2d_altitude,
+            reverse_geocode: self.reverse_geocode,
+            weather: self.weather,
+            keep_exif: self.keep_exif,
+            timestamp_precision: self.timestamp_precision,
+            analysis_timeout: self.analysis_timeout,
+            exiftool_version: self.exiftool_version.clone(),
+            hooks: self.hooks.clone(),
+            #[cfg(feature = "http")]
+            http_client: self.http_client.clone(),
+            #[cfg(feature = "http")]
+            max_download_bytes: self.max_download_bytes,
+        })
+    }
+
+    /// Creates an analyzer with the same configuration and shared services as this one, but
+    /// with its own `exiftool` process.
+    pub(crate) fn with_own_exiftool(&self) -> Result<Self, MediaAnalyzerError> {
+        Ok(Self {
+            exiftool: Arc::new(self.exiftool.start_another()?),
+            ..self.with_config(ConfigOverrides::default())?
         })
     }
+
+    /// Replaces this analyzer's `exiftool` process when it crashed. Returns whether it was
+    /// replaced.
+    pub(crate) fn restart_exiftool_if_dead(&self) -> Result<bool, MediaAnalyzerError> {
+        Ok(self.exiftool.restart_if_dead()?)
+    }
+
+    /// Makes this analyzer's `exiftool` process exit, as if it crashed.
+    #[cfg(test)]
+    pub(crate) fn crash_exiftool(&self) {
+        let _ = self
+            .exiftool
+            .current()
+            .execute_raw(&["-stay_open", "False"]);
+    }
+
+    /// Loads clock corrections to apply to the capture time of subsequently analyzed media,
+    /// replacing any previously loaded ones. See [`parse_time_corrections_csv`](crate::parse_time_corrections_csv)
+    /// for reading them from a CSV file.
+    ///
+    /// For each file, the most specific matching correction is used: one for the camera's serial
+    /// number wins over one for its model, and one limited to a date range wins over one that
+    /// isn't. The applied correction is noted in `time.source_details.time_source_detail`.
+    pub fn load_time_corrections(&mut self, corrections: Vec<TimeCorrection>) {
+        self.time_options.corrections = corrections;
+    }
+
+    /// Forgets the previously analyzed photo of `track_movement`, e.g. between albums, so the
+    /// next photo has no `movement_from_previous`.
+    pub fn reset_movement_tracking(&self) {
+        if let Some(tracker) = &self.movement {
+            tracker.reset();
+        }
+    }
+
+    /// Finds the nearest named place to the given coordinates.
+    ///
+    /// Shares its cache with [`MediaAnalyzer::analyze_media`], so looking up the location of
+    /// an already analyzed photo is free.
+    #[must_use]
+    pub fn reverse_geocode(&self, latitude: f64, longitude: f64) -> LocationName {
+        self.geocoder.reverse_geocode(latitude, longitude)
+    }
+
+    /// The number of locations in the reverse geocoding cache.
+    #[must_use]
+    pub fn geocode_cache_len(&self) -> usize {
+        self.geocoder.cached_len()
+    }
+
+    /// The default language for display strings, like [`Locale::weekday_name`], as set on the
+    /// builder. The analyzer doesn't use it, so results are the same in every locale.
+    #[must_use]
+    pub const fn locale(&self) -> Locale {
+        self.locale
+    }
+
+    /// Fetches the weather data of `locations` on `dates` into the weather provider's cache,
+    /// e.g. from a nightly job before a large import, so that those analyses don't wait for
+    /// downloads. Locations are matched to stations at the first radius of the weather radius
+    /// strategy, and each station is fetched once.
+    ///
+    /// # Errors
+    ///
+    /// * If the weather provider fails as a whole. Stations that fail are listed in the report.
+    pub async fn prewarm_weather(
+        &self,
+        locations: &[(f64, f64)],
+        dates: &[NaiveDate],
+    ) -> Result<PrewarmReport, MediaAnalyzerError> {
+        let radius_km = self.weather_radius_strategy.start_radius();
+        Ok(self
+            .weather_provider
+            .prewarm(locations, dates, radius_km)
+            .await?)
+    }
+
+    /// The weather cache hits and misses of the analyses so far, shared with analyzers made by
+    /// [`MediaAnalyzer::with_config`], and the size of the cache on disk.
+    #[must_use]
+    pub fn weather_cache_stats(&self) -> CacheStats {
+        self.weather_provider.cache_stats()
+    }
+
+    /// Reads just the MIME type, dimensions, duration, and file size of a media file.
+    ///
+    /// This runs a single `exiftool` call restricted to a handful of tags and skips hashing,
+    /// geocoding, time and weather lookups, which makes it suitable for routing files before
+    /// deciding whether to run [`MediaAnalyzer::analyze_media`]. It also succeeds for files
+    /// that are not media at all, such as text files.
+    ///
+    /// # Errors
+    ///
+    /// * [`MediaAnalyzerError::Exiftool`]: `exiftool` fails to execute or read the file.
+    /// * [`MediaAnalyzerError::Metadata`]: The file has no MIME type or file size.
+    pub fn probe(&self, media_file: &Path) -> Result<ProbeResult, MediaAnalyzerError> {
+        let exif = ExifData::new(
+            self.exiftool
+                .current()
+                .json(media_file, &PROBE_EXIFTOOL_ARGS)?,
+        );
+        Ok(get_probe_result(&exif)?)
+    }
+
+    /// Determines when a media file was captured, the cheap way.
+    ///
+    /// This runs the same `exiftool` call as [`MediaAnalyzer::analyze_media`] and reads the GPS
+    /// coordinates for the timezone, but skips hashing, reverse geocoding, feature detection and
+    /// weather lookups. The `on_time` hook runs, so the result equals the `time` of a full
+    /// analysis, unless an `on_gps` hook changes the coordinates there.
+    ///
+    /// # Errors
+    ///
+    /// * [`MediaAnalyzerError::Exiftool`]: `exiftool` fails to execute or read the file.
+    /// * [`MediaAnalyzerError::Time`]: No usable time information could be extracted from any source.
+    pub fn time_info(&self, media_file: &Path) -> Result<TimeInfo, MediaAnalyzerError> {
+        let exif = ExifData::new(
+            self.exiftool
+                .current()
+                .json(media_file, &ANALYSIS_EXIFTOOL_ARGS)?,
+        );
+        let gps = get_gps_coordinates(&exif);
+        let mut time = get_time_info(&exif, gps.as_ref(), &self.time_options)?;
+        self.hooks.run_time(&mut time);
+        Ok(time)
+    }
+}
+
+impl<'a, S: media_analyzer_builder::State> MediaAnalyzerBuilder<'a, S> {
+    /// Sets `weather_search_radius` to `km` kilometers.
+    #[deprecated(note = "use `weather_search_radius(Kilometers(km))`")]
+    pub fn weather_search_radius_km(
+        self,
+        km: f64,
+    ) -> MediaAnalyzerBuilder<'a, media_analyzer_builder::SetWeatherSearchRadius<S>>
+    where
+        S::WeatherSearchRadius: media_analyzer_builder::IsUnset,
+    {
+        self.weather_search_radius(Kilometers(km))
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::MediaAnalyzerError;
+    use crate::features::orientation::Orientation;
+    use crate::features::weather_provider::tests::MockProvider;
+    use crate::notes::{CODE_METADATA_STRIPPED, CODE_SKIPPED, NoteSeverity};
+    use crate::skipped::STAGE_TIMEZONE_FROM_GPS;
+    use crate::time::TimeSource;
+    use crate::time::structs::CONFIDENCE_LOW;
+    use crate::units::UtcOffset;
+    use serde_json::{Value, json};
     use std::path::{Path, PathBuf};
+    use std::sync::atomic::Ordering;
+
+    /// Every tag name the analysis pipeline reads, directly or through a priority list.
+    const FUZZ_TAGS: &[&str] = &[
+        "AEBBracketValue",
+        "AndroidCaptureFPS",
+        "AndroidMake",
+        "AndroidModel",
+        "Aperture",
+        "ApertureValue",
+        "AudioChannels",
+        "AudioFormat",
+        "AudioSampleRate",
+        "AvgFrameRate",
+        "BracketMode",
+        "BracketShotNumber",
+        "BurstId",
+        "BurstUUID",
+        "CompositeImage",
+        "CompressorID",
+        "ContinuousDrive",
+        "CreateDate",
+        "CreationTime",
+        "CroppedAreaImageWidthPixels",
+        "DateTimeDigitized",
+        "DateTimeOriginal",
+        "DateUTC",
+        "Description",
+        "DigitalZoomRatio",
+        "DirectoryItemSemantic",
+        "DriveMode",
+        "Duration",
+        "ExposureBiasValue",
+        "ExposureCompensation",
+        "ExposureTime",
+        "FNumber",
+        "FileAccessDate",
+        "FileCreateDate",
+        "FileModifyDate",
+        "FileName",
+        "FileSize",
+        "Flash",
+        "FocalLength",
+        "FocalLengthIn35mmFormat",
+        "FrameRate",
+        "FullPanoWidthPixels",
+        "GCamera:BurstId",
+        "GPSAltitude",
+        "GPSAltitudeRef",
+        "GPSCoordinates",
+        "GPSDateStamp",
+        "GPSDateTime",
+        "GPSImgDirection",
+        "GPSImgDirectionRef",
+        "GPSLatitude",
+        "GPSLongitude",
+        "GPSTimeStamp",
+        "GainMapImage",
+        "HDRImageType",
+        "ISO",
+        "ImageHeight",
+        "ImageWidth",
+        "LensMake",
+        "LensModel",
+        "MIMEType",
+        "Make",
+        "MaxApertureValue",
+        "MediaCreateDate",
+        "MediaDuration",
+        "MicroVideoOffset",
+        "Model",
+        "ModifyDate",
+        "MotionPhotoPresentationTimestampUs",
+        "OffsetTime",
+        "OffsetTimeDigitized",
+        "OffsetTimeOriginal",
+        "Orientation",
+        "ProjectionType",
+        "ReleaseMode",
+        "Rotation",
+        "SceneCaptureType",
+        "SceneMode",
+        "SequenceNumber",
+        "SerialNumber",
+        "ShootingMode",
+        "Software",
+        "SourceCount",
+        "SourceFrameRate",
+        "SpecialTypeID",
+        "Stitched",
+        "SubSecCreateDate",
+        "SubSecDateTimeOriginal",
+        "SubSecModifyDate",
+        "SubSecTimeDigitized",
+        "SubSecTimeOriginal",
+        "SubjectDistance",
+        "TrackCreateDate",
+        "TrackDuration",
+        "UsePanoramaViewer",
+        "UserComment",
+        "VideoFrameRate",
+    ];
+
+    const FUZZ_GROUPS: &[&str] = &[
+        "Time",
+        "Other",
+        "Location",
+        "Composite",
+        "Camera",
+        "Image",
+        "Video",
+        "Audio",
+        "Device",
+    ];
+
+    fn adversarial_values() -> Vec<Value> {
+        let mut nested = json!([]);
+        for _ in 0..100 {
+            nested = json!([nested]);
+        }
+        vec![
+            Value::Null,
+            json!(true),
+            json!(-1),
+            json!(0),
+            json!(i64::MIN),
+            json!(u64::MAX),
+            json!(f64::MAX),
+            json!(-f64::MAX),
+            json!(1e-300),
+            json!(""),
+            json!("garbage"),
+            json!("-1/0"),
+            json!("9999:99:99 99:99:99"),
+            json!("0000:00:00 00:00:00"),
+            json!("2024:01:01 10:00:00.99999999999999999999"),
+            json!("+99:99"),
+            json!("999999999:59:59"),
+            json!({ "nested": { "object": [1, 2, 3] } }),
+            json!([1, "two", null, {}]),
+            nested,
+        ]
+    }
+
+    #[test]
+    fn test_adversarial_exif_does_not_panic() {
+        let geocoder = CachedGeocoder::new(DEFAULT_GEOCODE_CACHE_SIZE);
+        let path = Path::new("does/not/exist.jpg");
+        let values = adversarial_values();
+
+        // Each value in every tag position, both at the root and inside every group.
+        let mut corpus: Vec<Value> = values
+            .iter()
+            .map(|value| {
+                let mut root = serde_json::Map::new();
+                for tag in FUZZ_TAGS {
+                    root.insert((*tag).to_string(), value.clone());
+                }
+                let flat = Value::Object(root.clone());
+                for group in FUZZ_GROUPS {
+                    root.insert((*group).to_string(), flat.clone());
+                }
+                Value::Object(root)
+            })
+            .collect();
+        // Mixed types: every tag gets a different adversarial value.
+        let mut mixed = serde_json::Map::new();
+        for (i, tag) in FUZZ_TAGS.iter().enumerate() {
+            mixed.insert((*tag).to_string(), values[i % values.len()].clone());
+        }
+        corpus.push(json!({ "Time": mixed.clone(), "Other": mixed.clone(), "Location": mixed }));
+        corpus.push(json!([]));
+        corpus.push(json!("not an object"));
+
+        for value in corpus {
+            let exif = ExifData::new(value);
+            let gps = get_gps_info(&geocoder, &exif);
+            let _ = get_time_info(&exif, gps.as_ref(), &TimeOptions::default());
+            if let Ok((basic, camera)) = get_metadata(&exif) {
+                let _ = get_quality_hints(&basic, &camera, &exif);
+            }
+            let _ = extract_features(
+                path,
+                &exif,
+                &FeatureOptions {
+                    explain: true,
+                    hdr_strictness: HdrStrictness::Lenient,
+                    night_mode_from_filename: true,
+                    slowmotion_ratio_threshold: DEFAULT_SLOWMOTION_RATIO_THRESHOLD,
+                    solar_event_window: DEFAULT_SOLAR_EVENT_WINDOW,
+                },
+            );
+            let _ = pano_viewer_evidence(&exif);
+        }
+    }
 
     /// A helper to get a specific asset path.
     fn asset_path(relative: &str) -> PathBuf {
@@ -198,6 +1604,121 @@ mod tests {
             .join(relative)
     }
 
+    /// A holiday provider that names every day after the country it was asked for.
+    struct EveryDayProvider;
+
+    impl HolidayProvider for EveryDayProvider {
+        fn holiday(&self, country_code: &str, _date: chrono::NaiveDate) -> Option<String> {
+            Some(format!("{country_code} day"))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_custom_holiday_provider_gets_the_geocoded_country()
+    -> Result<(), MediaAnalyzerError> {
+        let analyzer = MediaAnalyzer::builder()
+            .weather_provider(Arc::new(MockProvider::new(Some(20.0))))
+            .holiday_provider(Arc::new(EveryDayProvider))
+            .build()
+            .await?;
+        let result = analyzer.analyze_media(&asset_path("sunset.jpg")).await?;
+        let calendar_context = result.calendar_context.expect("is always set");
+        assert_eq!(calendar_context.holiday.as_deref(), Some("IT day"));
+
+        // Without a reverse geocoded country there is no holiday to look up.
+        let coordinates_only = MediaAnalyzer::builder()
+            .weather_provider(Arc::new(MockProvider::new(Some(20.0))))
+            .holiday_provider(Arc::new(EveryDayProvider))
+            .reverse_geocode(false)
+            .build()
+            .await?
+            .analyze_media(&asset_path("sunset.jpg"))
+            .await?;
+        let calendar_context = coordinates_only.calendar_context.expect("is always set");
+        assert_eq!(calendar_context.holiday, None);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_overlapping_itinerary_is_rejected() {
+        let date = |day| chrono::NaiveDate::from_ymd_opt(2024, 5, day).unwrap();
+        let result = MediaAnalyzer::builder()
+            .timezone_itinerary(vec![
+                ItineraryEntry {
+                    from: date(1),
+                    to: date(10),
+                    timezone: chrono_tz::Tz::Europe__Lisbon,
+                },
+                ItineraryEntry {
+                    from: date(10),
+                    to: date(20),
+                    timezone: chrono_tz::Tz::Europe__Athens,
+                },
+            ])
+            .build()
+            .await;
+        assert!(matches!(
+            result,
+            Err(MediaAnalyzerError::InvalidConfiguration(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_adaptive_radius_that_cannot_widen_is_rejected() {
+        let result = MediaAnalyzer::builder()
+            .weather_radius_strategy(RadiusStrategy::Adaptive {
+                start_km: 50.0,
+                max_km: 1000.0,
+                step_factor: 1.0,
+            })
+            .build()
+            .await;
+        assert!(matches!(
+            result,
+            Err(MediaAnalyzerError::InvalidConfiguration(message)) if message.contains("step_factor")
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_invalid_filename_tag_pattern_is_rejected() {
+        let result = MediaAnalyzer::builder()
+            .filename_tag_patterns(vec![FilenameTagPattern {
+                tag_name: "drone".to_string(),
+                pattern: "_aer(".to_string(),
+                capture_group_as_id: None,
+            }])
+            .build()
+            .await;
+        assert!(matches!(
+            result,
+            Err(MediaAnalyzerError::InvalidConfiguration(message)) if message.contains("`_aer(`")
+        ));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_probe_text_file() -> Result<(), MediaAnalyzerError> {
+        let analyzer = MediaAnalyzer::builder().build().await?;
+        let probe = analyzer.probe(&asset_path("text_file.txt"))?;
+
+        assert_eq!(probe.mime_type, "text/plain");
+        assert!(!probe.is_video);
+        assert!(probe.width.is_none());
+
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_probe_video() -> Result<(), MediaAnalyzerError> {
+        let analyzer = MediaAnalyzer::builder().build().await?;
+        let probe = analyzer.probe(&asset_path("video/car.webm"))?;
+
+        assert!(probe.is_video);
+        assert!(probe.duration.is_some_and(|d| d > 0.0));
+        assert!(probe.width.is_some());
+
+        Ok(())
+    }
+
     #[tokio::test(flavor = "multi_thread")]
     async fn test_full_analysis_on_standard_jpg() -> Result<(), MediaAnalyzerError> {
         let analyzer = MediaAnalyzer::builder().build().await?;
@@ -209,10 +1730,25 @@ mod tests {
         assert_eq!(result.basic.width, 5312);
         assert!(!result.features.is_video);
         assert!(!result.features.is_hdr, "sunset.jpg is not hdr");
+        assert_eq!(
+            result.features.is_sunset_shot,
+            Some(true),
+            "sunset.jpg is taken shortly after sunset"
+        );
+        assert_eq!(result.features.is_sunrise_shot, Some(false));
         assert!(result.gps.is_some(), "Should have GPS info");
         assert!(!result.features.is_burst);
         assert!(!result.use_panorama_viewer);
 
+        let gain_map = result
+            .aux_images
+            .iter()
+            .find(|image| image.semantic == "GainMap")
+            .expect("hdr.jpg embeds a gain map");
+        let mut bytes = Vec::new();
+        analyzer.extract_aux_image(&media_file, gain_map, &mut bytes)?;
+        assert_eq!(&bytes[..2], &[0xFF, 0xD8], "The gain map is a JPEG");
+
         Ok(())
     }
 
@@ -243,7 +1779,7 @@ mod tests {
         let result = analyzer.analyze_media(&media_file).await?;
 
         assert_eq!(result.basic.width, 3024);
-        assert_eq!(result.basic.orientation, Some(6));
+        assert_eq!(result.basic.orientation, Some(Orientation::Rotate90CW));
         assert!(!result.features.is_video);
         assert!(!result.features.is_hdr);
         assert!(result.gps.is_some(), "Should have GPS info");
@@ -323,7 +1859,7 @@ mod tests {
 
         let result = analyzer.analyze_media(&media_file).await?;
 
-        assert!(result.features.is_night_sight);
+        assert!(result.features.is_night_mode);
 
         Ok(())
     }
@@ -377,7 +1913,7 @@ mod tests {
         if let Some(tz) = &result.time.timezone {
             assert_ne!(tz.source, "CreateDate (Video UTC)");
             assert!(
-                tz.offset_seconds.abs() <= 15 * 3600,
+                tz.offset_seconds.0.abs() <= 15 * 3600,
                 "Offset should be sane"
             );
         }
@@ -463,10 +1999,11 @@ mod tests {
         assert!((gps_info.longitude - 14.422_816_666_666_7).abs() < 0.001);
 
         // Check reverse geocoded location data
-        assert_eq!(gps_info.location.name, "Massa di Somma");
-        assert_eq!(gps_info.location.admin1, "Campania");
-        assert_eq!(gps_info.location.country_code, "IT");
-        assert_eq!(gps_info.location.country_name, Some("Italy".to_string()));
+        let location = gps_info.location.as_ref().expect("sunset.jpg is geocoded");
+        assert_eq!(location.name, "Massa di Somma");
+        assert_eq!(location.admin1, "Campania");
+        assert_eq!(location.country_code, "IT");
+        assert_eq!(location.country_name, Some("Italy".to_string()));
 
         // --- 2. Time Info Assertions ---
         let time_info = result.time;
@@ -475,7 +2012,7 @@ mod tests {
         assert_eq!(time_info.source_details.confidence, "High");
         assert_eq!(
             time_info.source_details.time_source,
-            "SubSecDateTimeOriginal: Parsed SubSeconds"
+            TimeSource::SubSecDateTimeOriginal
         );
 
         // Check that UTC time and timezone were successfully calculated
@@ -491,7 +2028,8 @@ mod tests {
         let timezone = time_info.timezone.as_ref().unwrap();
         assert_eq!(timezone.name, "Europe/Rome");
         assert_eq!(
-            timezone.offset_seconds, 7200,
+            timezone.offset_seconds,
+            UtcOffset(7200),
             "Offset should be +2 hour for the photo's date"
         );
 
@@ -508,7 +2046,19 @@ mod tests {
             let time_from_sunset = time_info.datetime_utc.unwrap() - sunset;
             // The picture is taken less than an hour after sunset
             assert!(time_from_sunset.num_minutes() < 60);
+
+            // The local sunset uses the Europe/Rome time resolved for the photo (CEST).
+            let sunset_local = sun_info.sunset_local.expect("Timezone was resolved");
+            assert_eq!(
+                sunset_local,
+                sunset.with_timezone(&chrono_tz::Europe::Rome).naive_local()
+            );
+            assert_eq!(
+                sunset_local,
+                (sunset + chrono::Duration::hours(2)).naive_utc()
+            );
         }
+        assert!(sun_info.day_length_seconds.is_some());
 
         // Check hourly weather data. The API might not have data for every historical hour,
         // so checking for `is_some()` is often a sufficient integration test.
@@ -523,4 +2073,939 @@ mod tests {
 
         Ok(())
     }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_reverse_geocode_disabled_keeps_gps_timezone() -> Result<(), MediaAnalyzerError> {
+        let provider: Arc<dyn WeatherProvider> = Arc::new(MockProvider::new(Some(20.0)));
+        let geocoding = MediaAnalyzer::builder()
+            .weather_provider(Arc::clone(&provider))
+            .build()
+            .await?;
+        let coordinates_only = MediaAnalyzer::builder()
+            .weather_provider(provider)
+            .reverse_geocode(false)
+            .build()
+            .await?;
+        assert_ne!(
+            geocoding.config_fingerprint(),
+            coordinates_only.config_fingerprint()
+        );
+
+        let media_file = asset_path("sunset.jpg");
+        let geocoded = geocoding.analyze_media(&media_file).await?;
+        let result = coordinates_only.analyze_media(&media_file).await?;
+
+        let gps = result.gps.expect("sunset.jpg has GPS info");
+        let geocoded_gps = geocoded.gps.expect("sunset.jpg has GPS info");
+        assert_eq!(gps.location, None);
+        assert_eq!(
+            geocoded_gps.location.map(|location| location.name),
+            Some("Massa di Somma".to_string())
+        );
+        assert_eq!(
+            (gps.latitude, gps.longitude),
+            (geocoded_gps.latitude, geocoded_gps.longitude)
+        );
+
+        // The capture time is still zoned by the coordinates.
+        assert_eq!(result.time.source_details.confidence, "High");
+        assert_eq!(
+            result.time.timezone.map(|timezone| timezone.name),
+            Some("Europe/Rome".to_string())
+        );
+        assert_eq!(result.time.datetime_utc, geocoded.time.datetime_utc);
+        assert!(result.weather.is_some());
+        assert!(result.skipped_stages.contains(&SkippedStage::new(
+            STAGE_GEOCODE,
+            "disabled by configuration"
+        )));
+        assert!(
+            geocoded
+                .skipped_stages
+                .iter()
+                .all(|skipped| skipped.stage != STAGE_GEOCODE)
+        );
+        assert_eq!(
+            result.notes,
+            vec![AnalysisNote::skipped(&SkippedStage::new(
+                STAGE_GEOCODE,
+                "disabled by configuration"
+            ))]
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_presets_and_overrides() -> Result<(), MediaAnalyzerError> {
+        let fast = MediaAnalyzer::builder()
+            .preset(Preset::Fast)
+            .build()
+            .await?;
+        assert!(!fast.weather && !fast.reverse_geocode && !fast.keep_exif);
+        assert!(!fast.feature_options.explain && !fast.collect_parse_diagnostics);
+
+        let provider: Arc<dyn WeatherProvider> = Arc::new(MockProvider::new(Some(20.0)));
+        let full = MediaAnalyzer::builder()
+            .preset(Preset::Full)
+            .weather_provider(Arc::clone(&provider))
+            .build()
+            .await?;
+        assert!(full.weather && full.reverse_geocode && full.keep_exif);
+        assert!(full.feature_options.explain && full.collect_parse_diagnostics);
+
+        // An explicit option wins over the preset, set before or after it.
+        let before = MediaAnalyzer::builder()
+            .explain(false)
+            .preset(Preset::Full)
+            .weather_provider(Arc::clone(&provider))
+            .build()
+            .await?;
+        let after = MediaAnalyzer::builder()
+            .preset(Preset::Full)
+            .weather_provider(provider)
+            .explain(false)
+            .build()
+            .await?;
+        assert!(!before.feature_options.explain && !after.feature_options.explain);
+        assert!(before.collect_parse_diagnostics && after.collect_parse_diagnostics);
+        assert_eq!(before.config_fingerprint(), after.config_fingerprint());
+        assert_ne!(before.config_fingerprint(), full.config_fingerprint());
+
+        let offline = MediaAnalyzer::builder()
+            .preset(Preset::Offline)
+            .build()
+            .await?;
+        assert!(!offline.weather && offline.reverse_geocode && offline.keep_exif);
+        let result = offline.analyze_media(&asset_path("sunset.jpg")).await?;
+        assert!(result.weather.is_none());
+        assert!(result.gps.and_then(|gps| gps.location).is_some());
+        assert!(result.skipped_stages.contains(&SkippedStage::new(
+            STAGE_WEATHER,
+            "disabled by configuration"
+        )));
+
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_completeness_score() -> Result<(), MediaAnalyzerError> {
+        let analyzer = MediaAnalyzer::builder()
+            .weather_provider(Arc::new(MockProvider::new(Some(20.0))))
+            .build()
+            .await?;
+        let result = analyzer.analyze_media(&asset_path("sunset.jpg")).await?;
+        let breakdown = result.completeness;
+        assert!(breakdown.has_gps && breakdown.has_location && breakdown.has_weather);
+        assert!(breakdown.has_dimensions && breakdown.has_camera);
+        assert!(
+            result.completeness_score >= 85,
+            "{}",
+            result.completeness_score
+        );
+        assert_eq!(
+            score_completeness(&result, &CompletenessWeights::default()),
+            (result.completeness_score, breakdown)
+        );
+
+        let location_only = analyzer.with_config(ConfigOverrides::default())?;
+        let location_only = MediaAnalyzer {
+            completeness_weights: CompletenessWeights {
+                location: 100,
+                ..CompletenessWeights::default()
+            },
+            ..location_only
+        };
+        let weighted = location_only
+            .analyze_media(&asset_path("sunset.jpg"))
+            .await?;
+        assert!(weighted.completeness_score > result.completeness_score);
+        assert_ne!(
+            analyzer.config_fingerprint(),
+            location_only.config_fingerprint()
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_notes_of_healthy_and_degraded_analyses() -> Result<(), MediaAnalyzerError> {
+        let analyzer = MediaAnalyzer::builder()
+            .weather_provider(Arc::new(MockProvider::new(Some(20.0))))
+            .build()
+            .await?;
+        let healthy = analyzer.analyze_media(&asset_path("sunset.jpg")).await?;
+        assert!(healthy.weather.is_some());
+        assert_eq!(healthy.notes, Vec::new());
+
+        // Without GPS, geocoding and the lookups at the location are skipped, in module order.
+        let result = analyzer.analyze_media(&asset_path("png_image.png")).await?;
+        let notes: Vec<(&str, &str, NoteSeverity)> = result
+            .notes
+            .iter()
+            .map(|note| (note.module.as_str(), note.code.as_str(), note.severity))
+            .collect();
+        assert_eq!(
+            notes,
+            vec![
+                (STAGE_GEOCODE, CODE_SKIPPED, NoteSeverity::Info),
+                (STAGE_TIMEZONE_FROM_GPS, CODE_SKIPPED, NoteSeverity::Info),
+                (STAGE_WEATHER, CODE_SKIPPED, NoteSeverity::Info),
+            ]
+        );
+        let again = analyzer.analyze_media(&asset_path("png_image.png")).await?;
+        assert_eq!(result.notes, again.notes);
+
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_time_info_matches_full_analysis() -> Result<(), MediaAnalyzerError> {
+        let analyzer = MediaAnalyzer::builder().build().await?;
+
+        // GPS confirmed, from the coordinates alone.
+        let time = analyzer.time_info(&asset_path("sunset.jpg"))?;
+        assert_eq!(time.source_details.confidence, "High");
+        let result = analyzer.analyze_media(&asset_path("sunset.jpg")).await?;
+        assert_eq!(time, result.time);
+
+        let result = analyzer.analyze_media(&asset_path("png_image.png")).await?;
+        assert!(result.gps.is_none());
+        assert_eq!(
+            analyzer.time_info(&asset_path("png_image.png"))?,
+            result.time
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_analyzers_share_clients() -> Result<(), MediaAnalyzerError> {
+        let meteostat = Arc::new(Meteostat::new().await?);
+        let geocoder = Arc::new(ReverseGeocoder::new());
+        let build = || {
+            MediaAnalyzer::builder()
+                .meteostat(Arc::clone(&meteostat))
+                .geocoder(Arc::clone(&geocoder))
+                .build()
+        };
+        let first = build().await?;
+        let second = build().await?;
+        // Both analyzers hold the shared clients instead of initializing their own.
+        assert_eq!(Arc::strong_count(&meteostat), 3);
+        assert_eq!(Arc::strong_count(&geocoder), 3);
+
+        let media_file = asset_path("sunset.jpg");
+        let mut first_result = first.analyze_media(&media_file).await?;
+        let mut second_result = second.analyze_media(&media_file).await?;
+        // Only the analysis time may differ.
+        first_result.provenance = None;
+        second_result.provenance = None;
+        assert_eq!(
+            serde_json::to_value(first_result).unwrap(),
+            serde_json::to_value(second_result).unwrap()
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_with_config_shares_clients() -> Result<(), MediaAnalyzerError> {
+        let analyzer = MediaAnalyzer::builder().build().await?;
+        let explaining = analyzer.with_config(ConfigOverrides {
+            explain: Some(true),
+            ..ConfigOverrides::default()
+        })?;
+        assert!(Arc::ptr_eq(&analyzer.exiftool, &explaining.exiftool));
+        assert!(Arc::ptr_eq(&analyzer.geocoder, &explaining.geocoder));
+
+        let result = explaining.analyze_media(&asset_path("hdr.jpg")).await?;
+        assert!(result.features.explanations.is_some());
+        let result = analyzer.analyze_media(&asset_path("hdr.jpg")).await?;
+        assert!(result.features.explanations.is_none());
+
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_hooks_override_location_and_veto_weather() -> Result<(), MediaAnalyzerError> {
+        let provider = Arc::new(MockProvider::new(Some(20.0)));
+        let analyzer = MediaAnalyzer::builder()
+            .weather_provider(Arc::clone(&provider) as Arc<dyn WeatherProvider>)
+            .on_gps(|gps: &mut Option<GpsInfo>| {
+                if let Some(location) = gps.as_mut().and_then(|gps| gps.location.as_mut()) {
+                    location.name = "Grandma's garden".to_string();
+                }
+            })
+            // Veto the lookup for the overridden location, which the gate sees.
+            .before_weather(|gps: &GpsInfo, _: &TimeInfo| {
+                gps.location
+                    .as_ref()
+                    .is_none_or(|location| location.name != "Grandma's garden")
+            })
+            .on_features(|_: &mut MediaFeatures| panic!("a broken hook"))
+            .build()
+            .await?;
+
+        let result = analyzer.analyze_media(&asset_path("tent.jpg")).await?;
+        let gps = result.gps.expect("tent.jpg has GPS info");
+        assert_eq!(gps.location.unwrap().name, "Grandma's garden");
+        assert!(result.weather.is_none(), "The weather lookup was vetoed");
+        assert_eq!(provider.calls.load(Ordering::SeqCst), 0);
+
+        Ok(())
+    }
+
+    /// A stand-in for `exiftool -stay_open True -@ -` that hangs on files named `hang*`, and
+    /// reports a small photo taken in Amsterdam otherwise.
+    #[cfg(unix)]
+    const FAKE_EXIFTOOL: &str = r#"#!/bin/sh
+args=""
+while IFS= read -r line; do
+    if [ "$line" = "-execute" ]; then
+        case "$args" in
+            *-ver*) echo "13.00" ;;
+            *hang*) exec sleep 30 ;;
+            *) echo '[{"ImageWidth": 8, "ImageHeight": 8, "MIMEType": "image/jpeg", "FileSize": 4,
+                "GPSLatitude": 52.379189, "GPSLongitude": 4.899431,
+                "Time": {"DateTimeOriginal": "2024:06:01 10:00:00"}}]' ;;
+        esac
+        echo "{ready}"
+        args=""
+    else
+        args="$args $line"
+    fi
+done
+"#;
+
+    /// Writes [`FAKE_EXIFTOOL`] and two media files, `hang.jpg` and `photo.jpg`, to a new
+    /// temporary directory.
+    #[cfg(unix)]
+    fn fake_exiftool_dir() -> PathBuf {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = std::env::temp_dir().join(format!(
+            "media_analyzer_timeout_{:016x}",
+            rand::random::<u64>()
+        ));
+        fs::create_dir_all(&dir).unwrap();
+        let exiftool = dir.join("exiftool");
+        fs::write(&exiftool, FAKE_EXIFTOOL).unwrap();
+        fs::set_permissions(&exiftool, fs::Permissions::from_mode(0o755)).unwrap();
+        fs::write(dir.join("hang.jpg"), b"hang").unwrap();
+        fs::write(dir.join("photo.jpg"), b"jpeg").unwrap();
+        dir
+    }
+
+    /// A weather provider that never answers in time.
+    struct StalledProvider;
+
+    #[async_trait::async_trait]
+    impl WeatherProvider for StalledProvider {
+        async fn hourly_at(
+            &self,
+            _latitude: f64,
+            _longitude: f64,
+            _datetime: chrono::DateTime<Utc>,
+        ) -> Result<crate::WeatherObservation, crate::WeatherError> {
+            tokio::time::sleep(Duration::from_mins(1)).await;
+            Err(crate::WeatherError::NoDataAvailable)
+        }
+    }
+
+    #[cfg(unix)]
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_timeout_restarts_a_stuck_exiftool() -> Result<(), MediaAnalyzerError> {
+        let dir = fake_exiftool_dir();
+        let analyzer = MediaAnalyzer::builder()
+            .exiftool_path(&dir.join("exiftool"))
+            .weather_provider(Arc::new(MockProvider::new(Some(20.0))))
+            .analysis_timeout(Duration::from_secs(2))
+            .build()
+            .await?;
+        let stuck = analyzer.exiftool.current();
+
+        let started = Instant::now();
+        let error = analyzer
+            .analyze_media(&dir.join("hang.jpg"))
+            .await
+            .unwrap_err();
+        assert!(started.elapsed() < Duration::from_secs(10));
+        let MediaAnalyzerError::Timeout { elapsed, stage } = error else {
+            panic!("Expected a timeout, got {error:?}");
+        };
+        assert!(elapsed >= Duration::from_secs(2));
+        assert_eq!(stage.as_deref(), Some("exiftool"));
+        assert!(!Arc::ptr_eq(&stuck, &analyzer.exiftool.current()));
+        // The stuck process was killed, so its call in flight failed instead of waiting it out.
+        let started = Instant::now();
+        assert!(stuck.execute_lines(&["-ver"]).is_err());
+        assert!(started.elapsed() < Duration::from_secs(10));
+
+        // The stuck process would still hold up this analysis, the fresh one doesn't.
+        let result = analyzer.analyze_media(&dir.join("photo.jpg")).await?;
+        assert_eq!(result.basic.width, 8);
+        assert!(result.weather.is_some());
+
+        let _ = fs::remove_dir_all(&dir);
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_timeout_reports_the_weather_stage() -> Result<(), MediaAnalyzerError> {
+        let dir = fake_exiftool_dir();
+        let analyzer = MediaAnalyzer::builder()
+            .exiftool_path(&dir.join("exiftool"))
+            .weather_provider(Arc::new(StalledProvider))
+            .analysis_timeout(Duration::from_secs(2))
+            .build()
+            .await?;
+        let exiftool = analyzer.exiftool.current();
+
+        let error = analyzer
+            .analyze_media(&dir.join("photo.jpg"))
+            .await
+            .unwrap_err();
+        let MediaAnalyzerError::Timeout { stage, .. } = error else {
+            panic!("Expected a timeout, got {error:?}");
+        };
+        assert_eq!(stage.as_deref(), Some("weather"));
+        // `exiftool` was fine, so it is kept.
+        assert!(Arc::ptr_eq(&exiftool, &analyzer.exiftool.current()));
+
+        let _ = fs::remove_dir_all(&dir);
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_with_config_validates_like_the_builder() -> Result<(), MediaAnalyzerError> {
+        let dir = fake_exiftool_dir();
+        let analyzer = MediaAnalyzer::builder()
+            .exiftool_path(&dir.join("exiftool"))
+            .weather_provider(Arc::new(MockProvider::new(Some(20.0))))
+            .build()
+            .await?;
+
+        let stuck_radius = analyzer.with_config(ConfigOverrides {
+            weather_radius_strategy: Some(RadiusStrategy::Adaptive {
+                start_km: 50.0,
+                max_km: 1000.0,
+                step_factor: 1.0,
+            }),
+            ..ConfigOverrides::default()
+        });
+        assert!(matches!(
+            stuck_radius,
+            Err(MediaAnalyzerError::InvalidConfiguration(message)) if message.contains("step_factor")
+        ));
+
+        let date = |day| NaiveDate::from_ymd_opt(2024, 6, day).unwrap();
+        let overlapping = analyzer.with_config(ConfigOverrides {
+            time_options: Some(TimeOptions {
+                itinerary: vec![
+                    ItineraryEntry {
+                        from: date(1),
+                        to: date(10),
+                        timezone: chrono_tz::Europe::Lisbon,
+                    },
+                    ItineraryEntry {
+                        from: date(10),
+                        to: date(20),
+                        timezone: chrono_tz::Europe::Madrid,
+                    },
+                ],
+                ..TimeOptions::default()
+            }),
+            ..ConfigOverrides::default()
+        });
+        assert!(matches!(
+            overlapping,
+            Err(MediaAnalyzerError::InvalidConfiguration(message)) if message.contains("overlap")
+        ));
+
+        let prioritized = analyzer.with_config(ConfigOverrides {
+            time_options: Some(TimeOptions {
+                time_tag_priority: Some(vec![" createdate".to_string(), "CreateDate".to_string()]),
+                ..TimeOptions::default()
+            }),
+            ..ConfigOverrides::default()
+        })?;
+        assert_eq!(
+            prioritized.time_options.time_tag_priority,
+            Some(vec!["CreateDate".to_string()])
+        );
+
+        let _ = fs::remove_dir_all(&dir);
+        Ok(())
+    }
+
+    /// A weather provider that never answers its first lookup in time.
+    #[derive(Default)]
+    struct StalledOnceProvider {
+        stalled: std::sync::atomic::AtomicBool,
+    }
+
+    #[async_trait::async_trait]
+    impl WeatherProvider for StalledOnceProvider {
+        async fn hourly_at(
+            &self,
+            latitude: f64,
+            longitude: f64,
+            datetime: chrono::DateTime<Utc>,
+        ) -> Result<crate::WeatherObservation, crate::WeatherError> {
+            if !self.stalled.swap(true, Ordering::SeqCst) {
+                return StalledProvider
+                    .hourly_at(latitude, longitude, datetime)
+                    .await;
+            }
+            MockProvider::new(Some(20.0))
+                .hourly_at(latitude, longitude, datetime)
+                .await
+        }
+    }
+
+    /// Cancels `token` after `delay`, from another task.
+    fn cancel_after(token: &CancellationToken, delay: Duration) {
+        let token = token.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(delay).await;
+            token.cancel();
+        });
+    }
+
+    #[cfg(unix)]
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_cancel_during_weather_lookup() -> Result<(), MediaAnalyzerError> {
+        let dir = fake_exiftool_dir();
+        let analyzer = MediaAnalyzer::builder()
+            .exiftool_path(&dir.join("exiftool"))
+            .weather_provider(Arc::new(StalledOnceProvider::default()))
+            .build()
+            .await?;
+        let exiftool = analyzer.exiftool.current();
+
+        let token = CancellationToken::new();
+        cancel_after(&token, Duration::from_millis(500));
+        let started = Instant::now();
+        let error = analyzer
+            .analyze_media_cancellable(&dir.join("photo.jpg"), None, &token)
+            .await
+            .unwrap_err();
+        assert!(started.elapsed() < Duration::from_secs(5));
+        let MediaAnalyzerError::Cancelled { stage } = error else {
+            panic!("Expected a cancellation, got {error:?}");
+        };
+        assert_eq!(stage.as_deref(), Some("weather"));
+        assert!(Arc::ptr_eq(&exiftool, &analyzer.exiftool.current()));
+
+        // The same analyzer keeps working once the provider answers.
+        let result = analyzer.analyze_media(&dir.join("photo.jpg")).await?;
+        assert_eq!(result.basic.width, 8);
+        assert!(result.weather.is_some());
+
+        // A token that was cancelled before stops the analysis before it starts.
+        let error = analyzer
+            .analyze_media_cancellable(&dir.join("photo.jpg"), None, &token)
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            error,
+            MediaAnalyzerError::Cancelled { stage: None }
+        ));
+
+        let _ = fs::remove_dir_all(&dir);
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_cancel_restarts_a_busy_exiftool() -> Result<(), MediaAnalyzerError> {
+        let dir = fake_exiftool_dir();
+        let analyzer = MediaAnalyzer::builder()
+            .exiftool_path(&dir.join("exiftool"))
+            .weather_provider(Arc::new(MockProvider::new(Some(20.0))))
+            .build()
+            .await?;
+        let busy = analyzer.exiftool.current();
+
+        let token = CancellationToken::new();
+        cancel_after(&token, Duration::from_millis(500));
+        let started = Instant::now();
+        let error = analyzer
+            .analyze_media_cancellable(&dir.join("hang.jpg"), None, &token)
+            .await
+            .unwrap_err();
+        assert!(started.elapsed() < Duration::from_secs(5));
+        let MediaAnalyzerError::Cancelled { stage } = error else {
+            panic!("Expected a cancellation, got {error:?}");
+        };
+        assert_eq!(stage.as_deref(), Some("exiftool"));
+        assert!(!Arc::ptr_eq(&busy, &analyzer.exiftool.current()));
+
+        // The interrupted process would still be reading `hang.jpg`, the fresh one isn't.
+        let started = Instant::now();
+        let result = analyzer
+            .analyze_media_cancellable(&dir.join("photo.jpg"), None, &CancellationToken::new())
+            .await?;
+        assert!(started.elapsed() < Duration::from_secs(5));
+        assert_eq!(result.basic.width, 8);
+        assert!(result.weather.is_some());
+
+        let _ = fs::remove_dir_all(&dir);
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_analyze_from_raw_exif_matches_analyze_media() -> Result<(), MediaAnalyzerError> {
+        let analyzer = MediaAnalyzer::builder().build().await?;
+        let media_file = asset_path("sunset.jpg");
+
+        let raw = analyzer.raw_exif(&media_file)?;
+        assert_eq!(raw.exiftool_args, vec!["-n", "-g2"]);
+        let mut from_raw = analyzer.analyze_from_exif(&media_file, raw).await?;
+        let mut from_file = analyzer.analyze_media(&media_file).await?;
+        from_raw.provenance = None;
+        from_file.provenance = None;
+        assert_eq!(
+            serde_json::to_value(from_raw).unwrap(),
+            serde_json::to_value(from_file).unwrap()
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_single_image_is_its_own_stack_member() -> Result<(), MediaAnalyzerError> {
+        let analyzer = MediaAnalyzer::builder().build().await?;
+        let media_file = asset_path("sunset.jpg");
+
+        let result = analyzer.analyze_media(&media_file).await?;
+        assert_eq!(result.basic.stack_image_count, None);
+        let members = analyzer.analyze_stack_members(&media_file)?;
+        assert_eq!(members.len(), 1);
+        assert_eq!(members[0], (0, result.camera));
+
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_results_are_the_same_in_every_locale() -> Result<(), MediaAnalyzerError> {
+        let media_file = asset_path("sunset.jpg");
+        let mut results = Vec::new();
+        for locale in [Locale::En, Locale::De] {
+            let analyzer = MediaAnalyzer::builder()
+                .weather_provider(Arc::new(MockProvider::new(Some(20.0))))
+                .locale(locale)
+                .build()
+                .await?;
+            assert_eq!(analyzer.locale(), locale);
+            let mut result = analyzer.analyze_media(&media_file).await?;
+            result.provenance = None;
+            results.push(serde_json::to_value(result).unwrap());
+        }
+        // Only the display helpers are translated, not the serialized enum values.
+        assert_eq!(results[0], results[1]);
+        assert_eq!(
+            MediaAnalyzer::builder()
+                .locale(Locale::De)
+                .weather(false)
+                .build()
+                .await?
+                .locale()
+                .weekday_name(chrono::Weekday::Fri),
+            "Freitag"
+        );
+
+        Ok(())
+    }
+
+    /// Runs the real `exiftool` with the local time at +02:00, so file system times get that
+    /// offset on any machine.
+    #[cfg(unix)]
+    fn utc_plus_two_exiftool_dir() -> PathBuf {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = temp_media_dir().unwrap();
+        let exiftool = dir.join("exiftool");
+        fs::write(&exiftool, "#!/bin/sh\nTZ=UTC-2 exec exiftool \"$@\"\n").unwrap();
+        fs::set_permissions(&exiftool, fs::Permissions::from_mode(0o755)).unwrap();
+        dir
+    }
+
+    #[cfg(unix)]
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_stripped_metadata_falls_back_to_file_time() -> Result<(), MediaAnalyzerError> {
+        let dir = utc_plus_two_exiftool_dir();
+        let analyzer = MediaAnalyzer::builder()
+            .exiftool_path(&dir.join("exiftool"))
+            .build()
+            .await?;
+
+        // Only the image data is left, the capture time comes from the file system.
+        let stripped = asset_path("stripped/all_stripped.jpg");
+        let result = analyzer
+            .analyze_media_with_thumbnail(&stripped, stripped.as_path())
+            .await?;
+        assert_eq!(result.basic.metadata_stripped, Some(true));
+        assert_eq!(result.camera.camera_make, None);
+        assert_eq!(
+            result.time.source_details.time_source,
+            TimeSource::FileModifyDate
+        );
+        assert_eq!(result.time.source_details.confidence, CONFIDENCE_LOW);
+        // The offset of the file system's clock is the only timezone there is.
+        let timezone = result.time.timezone.as_ref().unwrap();
+        assert_eq!(timezone.name, "+02:00");
+        assert_eq!(timezone.offset_seconds, UtcOffset(7200));
+        assert!(
+            result
+                .notes
+                .iter()
+                .any(|note| note.code == CODE_METADATA_STRIPPED)
+        );
+
+        // The camera is still known, only the times were stripped.
+        let time_stripped = asset_path("stripped/time_stripped.jpg");
+        let result = analyzer
+            .analyze_media_with_thumbnail(&time_stripped, time_stripped.as_path())
+            .await?;
+        assert_eq!(result.basic.metadata_stripped, Some(false));
+        assert_eq!(result.camera.camera_make.as_deref(), Some("Canon"));
+        assert_eq!(
+            result.time.source_details.time_source,
+            TimeSource::FileModifyDate
+        );
+        assert!(
+            result
+                .notes
+                .iter()
+                .all(|note| note.code != CODE_METADATA_STRIPPED)
+        );
+
+        fs::remove_dir_all(dir)?;
+        Ok(())
+    }
+
+    #[cfg(feature = "http")]
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_analyze_url_matches_path_analysis() -> Result<(), MediaAnalyzerError> {
+        let media_file = asset_path("sunset.jpg");
+        let url = download::tests::serve(fs::read(&media_file)?, "", true);
+        let analyzer = MediaAnalyzer::builder().build().await?;
+
+        let mut from_url = analyzer
+            .analyze_url(
+                &format!("{url}/photos/sunset.jpg?X-Amz-Signature=abc"),
+                None,
+            )
+            .await?;
+        let mut from_path = analyzer.analyze_media(&media_file).await?;
+        // The raw tags include the path of the downloaded file.
+        for result in [&mut from_url, &mut from_path] {
+            result.exif = Value::Null;
+            result.provenance = None;
+        }
+        assert_eq!(
+            serde_json::to_value(from_url).unwrap(),
+            serde_json::to_value(from_path).unwrap()
+        );
+
+        Ok(())
+    }
+
+    #[cfg(all(unix, feature = "http"))]
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_analyze_url_times_out_in_the_download() -> Result<(), MediaAnalyzerError> {
+        let dir = fake_exiftool_dir();
+        // Accepts connections, but never responds.
+        let listener = std::net::TcpListener::bind("127.0.0.1:0")?;
+        let analyzer = MediaAnalyzer::builder()
+            .exiftool_path(&dir.join("exiftool"))
+            .weather_provider(Arc::new(MockProvider::new(Some(20.0))))
+            .analysis_timeout(Duration::from_secs(1))
+            .build()
+            .await?;
+
+        let error = analyzer
+            .analyze_url(
+                &format!("http://{}/photo.jpg", listener.local_addr()?),
+                None,
+            )
+            .await
+            .unwrap_err();
+        let MediaAnalyzerError::Timeout { stage, .. } = error else {
+            panic!("Expected a timeout, got {error:?}");
+        };
+        assert_eq!(stage.as_deref(), Some("download"));
+
+        let _ = fs::remove_dir_all(&dir);
+        Ok(())
+    }
+
+    #[cfg(feature = "http")]
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_analyze_url_rejects_large_files() -> Result<(), MediaAnalyzerError> {
+        let url = download::tests::serve(fs::read(asset_path("sunset.jpg"))?, "", true);
+        let analyzer = MediaAnalyzer::builder()
+            .max_download_bytes(1024)
+            .build()
+            .await?;
+
+        let error = analyzer
+            .analyze_url(&format!("{url}/sunset.jpg"), Some("sunset.jpg"))
+            .await
+            .unwrap_err();
+        assert!(
+            matches!(
+                error,
+                MediaAnalyzerError::Download {
+                    status: Some(200),
+                    ..
+                }
+            ),
+            "{error:?}"
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_analyze_directory_incremental() -> Result<(), MediaAnalyzerError> {
+        let (dir, _) = temp_media_path("")?;
+        for name in ["sunset.jpg", "tent.jpg", "hdr.jpg"] {
+            fs::copy(asset_path(name), dir.join(name))?;
+        }
+        let analyzer = MediaAnalyzer::builder()
+            .weather_provider(Arc::new(MockProvider::new(Some(20.0))))
+            .build()
+            .await?;
+        let mut manifest = Manifest::default();
+        let options = IncrementalOptions::default();
+
+        let first = analyzer
+            .analyze_directory_incremental(&dir, &mut manifest, options)
+            .await?;
+        assert_eq!(first.analyzed.len(), 3);
+        assert!(first.analyzed.iter().all(|file| file.result_changed));
+        assert_eq!(manifest.entries.len(), 3);
+
+        let second = analyzer
+            .analyze_directory_incremental(&dir, &mut manifest, options)
+            .await?;
+        assert!(second.analyzed.is_empty());
+        assert_eq!(second.skipped_unchanged.len(), 3);
+
+        // An entry that no longer matches, for a file that didn't change: the same result, though
+        // its `FileAccessDate` moved.
+        let unchanged = dir.join("sunset.jpg");
+        if let Some(entry) = manifest.entries.get_mut(&unchanged) {
+            entry.modified -= chrono::TimeDelta::seconds(1);
+        }
+        let reanalyzed = analyzer
+            .analyze_directory_incremental(&dir, &mut manifest, options)
+            .await?;
+        assert_eq!(reanalyzed.analyzed.len(), 1);
+        assert_eq!(reanalyzed.analyzed[0].path, unchanged);
+        assert!(!reanalyzed.analyzed[0].result_changed);
+
+        let touched = dir.join("tent.jpg");
+        fs::File::options()
+            .write(true)
+            .open(&touched)?
+            .set_modified(std::time::SystemTime::now() + Duration::from_secs(60))?;
+        let third = analyzer
+            .analyze_directory_incremental(&dir, &mut manifest, options)
+            .await?;
+        assert_eq!(third.analyzed.len(), 1);
+        assert_eq!(third.analyzed[0].path, touched);
+        assert!(!third.analyzed[0].result_changed);
+        assert_eq!(third.skipped_unchanged.len(), 2);
+
+        fs::remove_file(dir.join("hdr.jpg"))?;
+        let fourth = analyzer
+            .analyze_directory_incremental(&dir, &mut manifest, options)
+            .await?;
+        assert!(fourth.analyzed.is_empty());
+        assert_eq!(fourth.removed, vec![dir.join("hdr.jpg")]);
+        assert_eq!(manifest.entries.len(), 2);
+
+        let _ = fs::remove_dir_all(&dir);
+        Ok(())
+    }
+
+    #[cfg(feature = "metrics")]
+    #[test]
+    fn test_metrics_of_a_success_and_a_failure() {
+        use crate::telemetry::{
+            ERRORS_TOTAL, STAGE_DURATION_SECONDS, TIME_CONFIDENCE_TOTAL, WEATHER_RESULT_TOTAL,
+        };
+        use metrics_util::debugging::{DebugValue, DebuggingRecorder};
+
+        let recorder = DebuggingRecorder::new();
+        let snapshotter = recorder.snapshotter();
+        // The local recorder only sees metrics of this thread, so the analyses run on a
+        // current-thread runtime.
+        metrics::with_local_recorder(&recorder, || {
+            let runtime = tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+                .unwrap();
+            runtime.block_on(async {
+                let analyzer = MediaAnalyzer::builder()
+                    .weather_provider(Arc::new(MockProvider::new(Some(20.0))))
+                    .build()
+                    .await
+                    .unwrap();
+                analyzer
+                    .analyze_media(&asset_path("sunset.jpg"))
+                    .await
+                    .unwrap();
+                let failure = analyzer.analyze_media(&asset_path("text_file.txt")).await;
+                assert!(failure.is_err());
+            });
+        });
+
+        let metrics = snapshotter.snapshot().into_vec();
+        let value = |name: &str, label: &str, label_value: &str| {
+            metrics
+                .iter()
+                .find(|(key, ..)| {
+                    key.key().name() == name
+                        && key
+                            .key()
+                            .labels()
+                            .any(|pair| pair.key() == label && pair.value() == label_value)
+                })
+                .map(|(.., value)| value)
+        };
+        assert_eq!(
+            value(TIME_CONFIDENCE_TOTAL, "level", "high"),
+            Some(&DebugValue::Counter(1))
+        );
+        assert_eq!(
+            value(WEATHER_RESULT_TOTAL, "outcome", "hit"),
+            Some(&DebugValue::Counter(1))
+        );
+        assert_eq!(
+            value(ERRORS_TOTAL, "kind", "metadata"),
+            Some(&DebugValue::Counter(1))
+        );
+        // Both analyses ran exiftool, only the photo looked up the weather.
+        assert!(matches!(
+            value(STAGE_DURATION_SECONDS, "stage", "exiftool"),
+            Some(DebugValue::Histogram(samples)) if samples.len() == 2
+        ));
+        assert!(matches!(
+            value(STAGE_DURATION_SECONDS, "stage", "weather"),
+            Some(DebugValue::Histogram(samples)) if samples.len() == 1
+        ));
+        // No label holds a file path.
+        assert!(metrics.iter().all(|(key, ..)| {
+            key.key()
+                .labels()
+                .all(|label| !label.value().contains('/') && !label.value().contains('.'))
+        }));
+    }
 }
@@ -0,0 +1,157 @@
+//! Non-fatal information about an analysis, like a skipped weather lookup or a duration tag that
+//! couldn't be read, as typed notes that can be filtered on their module and code.
+
+use crate::skipped::SkippedStage;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::cmp::Ordering;
+
+/// Notes about reading the file's metadata, like its duration.
+pub const MODULE_METADATA: &str = "metadata";
+
+/// A stage that didn't run, with the reason as message.
+pub const CODE_SKIPPED: &str = "skipped";
+/// The weather lookup failed, e.g. because the provider was unreachable.
+pub const CODE_LOOKUP_FAILED: &str = "lookup_failed";
+/// Reverse geocoding found no place near the coordinates, only the country.
+pub const CODE_NO_PLACE_NAME: &str = "no_place_name";
+/// A duration tag could not be parsed.
+pub const CODE_MALFORMED_DURATION: &str = "malformed_duration";
+/// A duration tag was negative or longer than a week, and was ignored as corrupt.
+pub const CODE_IMPLAUSIBLE_DURATION: &str = "implausible_duration";
+/// exiftool reported no MIME type, but recognized the file type, so the MIME type is
+/// `application/octet-stream`.
+pub const CODE_MISSING_MIME_TYPE: &str = "missing_mime_type";
+/// The file's metadata was stripped, so the capture time is a file system time.
+pub const CODE_METADATA_STRIPPED: &str = "metadata_stripped";
+
+/// How much a note matters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum NoteSeverity {
+    /// Expected gaps, like no weather lookup for a photo without GPS.
+    Info,
+    /// Something went wrong, and a part of the result is missing or less precise.
+    Warning,
+}
+
+/// A piece of non-fatal information about an analysis.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AnalysisNote {
+    pub severity: NoteSeverity,
+    /// What the note is about, like [`CODE_SKIPPED`] or [`CODE_MALFORMED_DURATION`]. Stable
+    /// between releases, so it can be filtered on.
+    pub code: String,
+    /// The part of the analysis that wrote the note: a stage like
+    /// [`STAGE_WEATHER`](crate::STAGE_WEATHER), or [`MODULE_METADATA`].
+    pub module: String,
+    /// A description for people. Not stable.
+    pub message: String,
+    /// Details for programs, like the tag and value of a malformed duration.
+    #[serde(default)]
+    pub data: Option<Value>,
+}
+
+impl AnalysisNote {
+    fn new(severity: NoteSeverity, module: &str, code: &str, message: impl Into<String>) -> Self {
+        Self {
+            severity,
+            code: code.to_string(),
+            module: module.to_string(),
+            message: message.into(),
+            data: None,
+        }
+    }
+
+    #[must_use]
+    pub fn info(module: &str, code: &str, message: impl Into<String>) -> Self {
+        Self::new(NoteSeverity::Info, module, code, message)
+    }
+
+    #[must_use]
+    pub fn warning(module: &str, code: &str, message: impl Into<String>) -> Self {
+        Self::new(NoteSeverity::Warning, module, code, message)
+    }
+
+    #[must_use]
+    pub fn with_data(mut self, data: Value) -> Self {
+        self.data = Some(data);
+        self
+    }
+
+    /// The note for a stage that was skipped for an expected reason, like missing GPS.
+    #[must_use]
+    pub fn skipped(stage: &SkippedStage) -> Self {
+        Self::info(&stage.stage, CODE_SKIPPED, stage.reason.clone())
+    }
+
+    fn order(&self, other: &Self) -> Ordering {
+        let data = |note: &Self| note.data.as_ref().map(Value::to_string);
+        (&self.module, &self.code, &self.message, self.severity)
+            .cmp(&(&other.module, &other.code, &other.message, other.severity))
+            .then_with(|| data(self).cmp(&data(other)))
+    }
+}
+
+/// Sorts notes by module, then code, and removes duplicates, so the same file gives the same
+/// notes in every run.
+pub fn normalize_notes(notes: &mut Vec<AnalysisNote>) {
+    notes.sort_by(AnalysisNote::order);
+    notes.dedup();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::skipped::{STAGE_GEOCODE, STAGE_WEATHER};
+    use serde_json::json;
+
+    #[test]
+    fn test_notes_are_sorted_and_deduplicated() {
+        let duration = AnalysisNote::warning(
+            MODULE_METADATA,
+            CODE_MALFORMED_DURATION,
+            "`Duration` is not a duration",
+        )
+        .with_data(json!({ "tag": "Duration", "value": "n/a" }));
+        let lookup_failed =
+            AnalysisNote::warning(STAGE_WEATHER, CODE_LOOKUP_FAILED, "lookup failed: timeout");
+        let no_gps = AnalysisNote::skipped(&SkippedStage::new(STAGE_GEOCODE, "no GPS coordinates"));
+
+        let mut notes = vec![
+            lookup_failed.clone(),
+            duration.clone(),
+            no_gps.clone(),
+            duration.clone(),
+        ];
+        let mut reversed: Vec<AnalysisNote> = notes.iter().rev().cloned().collect();
+        normalize_notes(&mut notes);
+        normalize_notes(&mut reversed);
+
+        let expected = vec![no_gps, duration, lookup_failed];
+        assert_eq!(notes, expected);
+        assert_eq!(reversed, expected);
+    }
+
+    #[test]
+    fn test_notes_with_other_data_are_kept() {
+        let note = |value| {
+            AnalysisNote::warning(MODULE_METADATA, CODE_MALFORMED_DURATION, "malformed")
+                .with_data(json!({ "value": value }))
+        };
+        let mut notes = vec![note("b"), note("a"), note("b")];
+        normalize_notes(&mut notes);
+        assert_eq!(notes, vec![note("a"), note("b")]);
+        assert_eq!(
+            serde_json::to_value(&notes[0]).unwrap(),
+            json!({
+                "severity": "warning",
+                "code": "malformed_duration",
+                "module": "metadata",
+                "message": "malformed",
+                "data": { "value": "a" }
+            })
+        );
+    }
+}
@@ -0,0 +1,268 @@
+//! A set of analyzers with one `exiftool` process each, for batches that should use more than
+//! one core in the `exiftool` stage.
+
+use crate::structs::MediaMetadata;
+use crate::{MediaAnalyzer, MediaAnalyzerError};
+use std::panic;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use tokio::task::{JoinError, JoinSet};
+
+/// A result with the position of its file in the batch.
+type Indexed = (usize, Result<MediaMetadata, MediaAnalyzerError>);
+
+struct Member {
+    analyzer: MediaAnalyzer,
+    /// The number of files this member is analyzing.
+    in_flight: AtomicUsize,
+}
+
+/// Counts a file as in flight on a member until it is dropped.
+struct InFlight(Arc<Member>);
+
+impl InFlight {
+    fn new(member: &Arc<Member>) -> Self {
+        member.in_flight.fetch_add(1, Ordering::Relaxed);
+        Self(Arc::clone(member))
+    }
+}
+
+impl Drop for InFlight {
+    fn drop(&mut self) {
+        self.0.in_flight.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+/// Analyzers that share one configuration, geocoder and weather provider, but each have their
+/// own `exiftool` process.
+///
+/// A single [`MediaAnalyzer`] reads one file at a time with `exiftool`, however many analyses
+/// run concurrently; a pool reads as many as it has processes.
+///
+/// Dropping the pool stops its `exiftool` processes.
+///
+/// ```rust
+/// # use media_analyzer::{AnalyzerPool, MediaAnalyzer, MediaAnalyzerError};
+/// # use std::path::Path;
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), MediaAnalyzerError> {
+/// let analyzer = MediaAnalyzer::builder().build().await?;
+/// let pool = AnalyzerPool::new(4, analyzer)?;
+///
+/// let files = [Path::new("assets/tent.jpg"), Path::new("assets/sunset.jpg")];
+/// for result in pool.analyze_many(files, 8).await {
+///     println!("{:?}", result?.time.datetime_utc);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub struct AnalyzerPool {
+    members: Vec<Arc<Member>>,
+}
+
+impl AnalyzerPool {
+    /// Creates a pool of `size` analyzers with the configuration of `analyzer`, which becomes
+    /// the first of them. A `size` of 0 counts as 1.
+    ///
+    /// # Errors
+    ///
+    /// [`MediaAnalyzerError::Exiftool`] when one of the extra `exiftool` processes fails to
+    /// start. The processes started before it are stopped.
+    pub fn new(size: usize, analyzer: MediaAnalyzer) -> Result<Self, MediaAnalyzerError> {
+        let mut analyzers = Vec::with_capacity(size.max(1));
+        for _ in 1..size {
+            analyzers.push(analyzer.with_own_exiftool()?);
+        }
+        analyzers.insert(0, analyzer);
+        Ok(Self {
+            members: analyzers
+                .into_iter()
+                .map(|analyzer| {
+                    Arc::new(Member {
+                        analyzer,
+                        in_flight: AtomicUsize::new(0),
+                    })
+                })
+                .collect(),
+        })
+    }
+
+    /// The number of analyzers, and so of `exiftool` processes.
+    #[must_use]
+    pub const fn size(&self) -> usize {
+        self.members.len()
+    }
+
+    /// The first analyzer, for calls on a single file like [`MediaAnalyzer::probe`].
+    #[must_use]
+    pub fn analyzer(&self) -> &MediaAnalyzer {
+        &self.members[0].analyzer
+    }
+
+    fn least_busy(&self) -> &Arc<Member> {
+        self.members
+            .iter()
+            .min_by_key(|member| member.in_flight.load(Ordering::Relaxed))
+            .unwrap_or(&self.members[0])
+    }
+
+    /// Analyzes `files` like [`MediaAnalyzer::analyze_media`], at most `concurrency` at a time,
+    /// each on the analyzer with the fewest files in flight. The results are in the order of
+    /// `files`. A `concurrency` of 0 counts as 1.
+    ///
+    /// Set `concurrency` above the pool size to keep the `exiftool` processes busy while other
+    /// files wait for a weather lookup.
+    ///
+    /// When a file fails because its analyzer's `exiftool` process crashed, the process is
+    /// replaced and the file is analyzed once more. The other analyzers are not affected.
+    ///
+    /// # Panics
+    ///
+    /// When an analysis panics, like [`MediaAnalyzer::analyze_media`] would.
+    pub async fn analyze_many<I, P>(
+        &self,
+        files: I,
+        concurrency: usize,
+    ) -> Vec<Result<MediaMetadata, MediaAnalyzerError>>
+    where
+        I: IntoIterator<Item = P>,
+        P: AsRef<Path>,
+    {
+        let files: Vec<PathBuf> = files
+            .into_iter()
+            .map(|file| file.as_ref().to_path_buf())
+            .collect();
+        let mut results: Vec<Option<Result<MediaMetadata, MediaAnalyzerError>>> =
+            std::iter::repeat_with(|| None).take(files.len()).collect();
+        let mut tasks = JoinSet::new();
+        let mut collect = |joined: Result<Indexed, JoinError>| {
+            let (index, result) = match joined {
+                Ok(indexed) => indexed,
+                Err(e) => panic::resume_unwind(e.into_panic()),
+            };
+            results[index] = Some(result);
+        };
+
+        for (index, file) in files.into_iter().enumerate() {
+            if tasks.len() >= concurrency.max(1)
+                && let Some(joined) = tasks.join_next().await
+            {
+                collect(joined);
+            }
+            let in_flight = InFlight::new(self.least_busy());
+            tasks.spawn(async move {
+                let result = analyze_recovering(&in_flight.0, &file).await;
+                (index, result)
+            });
+        }
+        while let Some(joined) = tasks.join_next().await {
+            collect(joined);
+        }
+        results.into_iter().flatten().collect()
+    }
+}
+
+/// Analyzes `file`, and once more when it failed because `exiftool` crashed.
+async fn analyze_recovering(
+    member: &Arc<Member>,
+    file: &Path,
+) -> Result<MediaMetadata, MediaAnalyzerError> {
+    let result = member.analyzer.analyze_media(file).await;
+    if !matches!(result, Err(MediaAnalyzerError::Exiftool(_))) {
+        return result;
+    }
+    let restarted = tokio::task::spawn_blocking({
+        let member = Arc::clone(member);
+        move || member.analyzer.restart_exiftool_if_dead()
+    })
+    .await
+    .unwrap_or_else(|e| panic::resume_unwind(e.into_panic()));
+    match restarted {
+        Ok(true) => member.analyzer.analyze_media(file).await,
+        Ok(false) => result,
+        Err(e) => {
+            log::warn!("Could not restart a crashed exiftool: {e}");
+            result
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn asset(name: &str) -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("assets")
+            .join(name)
+    }
+
+    async fn pool(size: usize) -> AnalyzerPool {
+        let analyzer = MediaAnalyzer::builder()
+            .weather(false)
+            .build()
+            .await
+            .unwrap();
+        AnalyzerPool::new(size, analyzer).unwrap()
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_results_are_in_input_order() {
+        let pool = pool(3).await;
+        assert_eq!(pool.size(), 3);
+        let files = [
+            asset("tent.jpg"),
+            asset("sunset.jpg"),
+            asset("missing.jpg"),
+            asset("tent.jpg"),
+            asset("orientation-5.jpg"),
+        ];
+        let single: Vec<_> = {
+            let mut results = Vec::new();
+            for file in &files {
+                results.push(pool.analyzer().analyze_media(file).await);
+            }
+            results
+        };
+
+        let results = pool.analyze_many(&files, 4).await;
+        assert_eq!(results.len(), files.len());
+        for (pooled, single) in results.iter().zip(&single) {
+            match (pooled, single) {
+                (Ok(pooled), Ok(single)) => assert_eq!(pooled.hash, single.hash),
+                (Err(_), Err(_)) => {}
+                _ => panic!("pooled and single results differ: {pooled:?} vs {single:?}"),
+            }
+        }
+        assert!(results[2].is_err());
+        assert_eq!(
+            results[0].as_ref().unwrap().hash,
+            results[3].as_ref().unwrap().hash
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_crashed_exiftool_does_not_affect_the_others() {
+        let pool = pool(2).await;
+        pool.members[0].analyzer.crash_exiftool();
+
+        // The healthy analyzer still answers.
+        let tent = asset("tent.jpg");
+        assert!(pool.members[1].analyzer.analyze_media(&tent).await.is_ok());
+
+        // Files on the crashed analyzer are retried on a fresh process.
+        let results = pool.analyze_many(std::iter::repeat_n(&tent, 6), 2).await;
+        assert!(results.iter().all(Result::is_ok), "{results:?}");
+        assert!(pool.members[0].analyzer.analyze_media(&tent).await.is_ok());
+    }
+
+    #[test]
+    fn test_empty_batch() {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let pool = runtime.block_on(pool(0));
+        assert_eq!(pool.size(), 1);
+        let results = runtime.block_on(pool.analyze_many(Vec::<PathBuf>::new(), 0));
+        assert!(results.is_empty());
+    }
+}
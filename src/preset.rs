@@ -0,0 +1,98 @@
+//! Named combinations of builder options, for services that want a documented profile rather
+//! than picking each option.
+
+use serde::{Deserialize, Serialize};
+
+/// A profile of builder options, set with `MediaAnalyzer::builder().preset(...)`. Options set
+/// on the builder win over the preset, whether they are set before or after it.
+///
+/// | Option                      | Default | `Fast`  | `Full` | `Offline` |
+/// |-----------------------------|---------|---------|--------|-----------|
+/// | `weather`                   | `true`  | `false` | `true` | `false`   |
+/// | `reverse_geocode`           | `true`  | `false` | `true` | `true`    |
+/// | `keep_exif`                 | `true`  | `false` | `true` | `true`    |
+/// | `explain`                   | `false` | `false` | `true` | `false`   |
+/// | `collect_parse_diagnostics` | `false` | `false` | `true` | `false`   |
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Preset {
+    /// Only what is read from the file itself: no weather lookup, no reverse geocoding, and no
+    /// raw `exiftool` output in the results. GPS coordinates still zone the capture time.
+    Fast,
+    /// Everything on, with explanations of the feature flags and parse diagnostics.
+    Full,
+    /// Nothing that needs the network: no weather lookup, which downloads station data. Reverse
+    /// geocoding uses a bundled dataset, so it stays on.
+    Offline,
+}
+
+/// The values a preset gives the options it covers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[allow(clippy::struct_excessive_bools)]
+pub struct PresetOptions {
+    pub weather: bool,
+    pub reverse_geocode: bool,
+    pub keep_exif: bool,
+    pub explain: bool,
+    pub collect_parse_diagnostics: bool,
+}
+
+impl Default for PresetOptions {
+    /// The builder's defaults, without a preset.
+    fn default() -> Self {
+        Self {
+            weather: true,
+            reverse_geocode: true,
+            keep_exif: true,
+            explain: false,
+            collect_parse_diagnostics: false,
+        }
+    }
+}
+
+impl Preset {
+    #[must_use]
+    pub fn options(self) -> PresetOptions {
+        match self {
+            Self::Fast => PresetOptions {
+                weather: false,
+                reverse_geocode: false,
+                keep_exif: false,
+                ..PresetOptions::default()
+            },
+            Self::Full => PresetOptions {
+                explain: true,
+                collect_parse_diagnostics: true,
+                ..PresetOptions::default()
+            },
+            Self::Offline => PresetOptions {
+                weather: false,
+                ..PresetOptions::default()
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_preset_options() {
+        let fast = Preset::Fast.options();
+        assert!(!fast.weather && !fast.reverse_geocode && !fast.keep_exif);
+        assert!(!fast.explain && !fast.collect_parse_diagnostics);
+
+        let full = Preset::Full.options();
+        assert!(full.weather && full.reverse_geocode && full.keep_exif);
+        assert!(full.explain && full.collect_parse_diagnostics);
+
+        assert_eq!(
+            Preset::Offline.options(),
+            PresetOptions {
+                weather: false,
+                ..PresetOptions::default()
+            }
+        );
+    }
+}
@@ -0,0 +1,523 @@
+//! Which analyzer version and configuration produced a result, so stored results can be
+//! selectively re-analyzed later.
+
+use crate::features::completeness::CompletenessWeights;
+use crate::features::filename::FilenameTagPattern;
+use crate::features::gps::GPS_LOGIC_REVISION;
+use crate::features::weather::{RadiusStrategy, WEATHER_LOGIC_REVISION};
+use crate::structs::MediaMetadata;
+use crate::tags::hdr::HdrStrictness;
+use crate::tags::logic::TAGS_LOGIC_REVISION;
+use crate::tags::structs::{
+    DEFAULT_SLOWMOTION_RATIO_THRESHOLD, DEFAULT_SOLAR_EVENT_WINDOW, FeatureOptions,
+};
+use crate::time::structs::{NumericDateOrder, TimeOptions};
+use crate::time::{ItineraryEntry, TIME_LOGIC_REVISION, TimeCorrection};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// The version of this crate.
+pub const CRATE_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// How many hex characters of the BLAKE3 hash make up a config fingerprint.
+const FINGERPRINT_LENGTH: usize = 16;
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AnalysisProvenance {
+    pub crate_version: String,
+    /// `None` when `exiftool -ver` could not be read.
+    #[serde(default)]
+    pub exiftool_version: Option<String>,
+    pub analyzed_at: DateTime<Utc>,
+    /// A stable hash of the options that affect the result, see
+    /// [`MediaAnalyzer::config_fingerprint`](crate::MediaAnalyzer::config_fingerprint).
+    pub config_fingerprint: String,
+    /// The detection logic revisions this result was produced with. All zero for results
+    /// stored before revisions were recorded.
+    #[serde(default)]
+    pub logic_revisions: LogicRevisions,
+}
+
+/// The revision of the detection logic of each module, bumped whenever a change can alter
+/// what the module produces.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct LogicRevisions {
+    /// See [`TAGS_LOGIC_REVISION`].
+    pub tags: u32,
+    /// See [`TIME_LOGIC_REVISION`].
+    pub time: u32,
+    /// See [`GPS_LOGIC_REVISION`].
+    pub gps: u32,
+    /// See [`WEATHER_LOGIC_REVISION`].
+    pub weather: u32,
+}
+
+impl LogicRevisions {
+    /// The revisions of this build of the crate.
+    #[must_use]
+    pub const fn current() -> Self {
+        Self {
+            tags: TAGS_LOGIC_REVISION,
+            time: TIME_LOGIC_REVISION,
+            gps: GPS_LOGIC_REVISION,
+            weather: WEATHER_LOGIC_REVISION,
+        }
+    }
+
+    /// The revision of `module`.
+    #[must_use]
+    pub const fn get(&self, module: AnalysisModule) -> u32 {
+        match module {
+            AnalysisModule::Tags => self.tags,
+            AnalysisModule::Time => self.time,
+            AnalysisModule::Gps => self.gps,
+            AnalysisModule::Weather => self.weather,
+        }
+    }
+}
+
+/// A part of the analysis with its own logic revision.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum AnalysisModule {
+    /// [`MediaFeatures`](crate::MediaFeatures).
+    Tags,
+    /// [`TimeInfo`](crate::TimeInfo).
+    Time,
+    /// [`GpsInfo`](crate::GpsInfo).
+    Gps,
+    /// [`WeatherInfo`](crate::WeatherInfo).
+    Weather,
+}
+
+impl AnalysisModule {
+    pub const ALL: [Self; 4] = [Self::Tags, Self::Time, Self::Gps, Self::Weather];
+}
+
+/// Decides which stored results should be re-analyzed, e.g. after upgrading the crate.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StalenessPolicy {
+    /// The crate version, for reference. Upgrades that don't bump a logic revision don't make
+    /// results stale.
+    pub crate_version: String,
+    pub logic_revisions: LogicRevisions,
+    /// When set, results produced with another configuration are stale too. Get it from
+    /// [`MediaAnalyzer::config_fingerprint`](crate::MediaAnalyzer::config_fingerprint).
+    pub config_fingerprint: Option<String>,
+    /// The modules whose results are recomputed when their logic revision changes. Newer
+    /// revisions of the other modules are ignored.
+    pub recompute_on_change: Vec<AnalysisModule>,
+}
+
+impl StalenessPolicy {
+    /// The policy for this build of the crate, recomputing on a change to any module. The
+    /// configuration is not compared, see [`StalenessPolicy::with_config_fingerprint`].
+    #[must_use]
+    pub fn current() -> Self {
+        Self {
+            crate_version: CRATE_VERSION.to_string(),
+            logic_revisions: LogicRevisions::current(),
+            config_fingerprint: None,
+            recompute_on_change: AnalysisModule::ALL.to_vec(),
+        }
+    }
+
+    /// Also marks results produced with a configuration other than `fingerprint` as stale.
+    #[must_use]
+    pub fn with_config_fingerprint(mut self, fingerprint: impl Into<String>) -> Self {
+        self.config_fingerprint = Some(fingerprint.into());
+        self
+    }
+
+    /// Whether a result with `provenance` should be re-analyzed. Results without provenance
+    /// always are.
+    #[must_use]
+    pub fn is_stale(&self, provenance: Option<&AnalysisProvenance>) -> bool {
+        let Some(provenance) = provenance else {
+            return true;
+        };
+        let outdated_module = self.recompute_on_change.iter().any(|&module| {
+            provenance.logic_revisions.get(module) < self.logic_revisions.get(module)
+        });
+        let other_config = self
+            .config_fingerprint
+            .as_ref()
+            .is_some_and(|fingerprint| *fingerprint != provenance.config_fingerprint);
+        outdated_module || other_config
+    }
+}
+
+impl MediaMetadata {
+    /// Whether this result should be re-analyzed under `current`, see
+    /// [`StalenessPolicy::is_stale`].
+    #[must_use]
+    pub fn is_stale(&self, current: &StalenessPolicy) -> bool {
+        current.is_stale(self.provenance.as_ref())
+    }
+}
+
+/// The analyzer settings a fingerprint is computed from.
+#[derive(Debug, Clone, Copy)]
+#[allow(clippy::struct_excessive_bools)]
+pub struct FingerprintedConfig<'a> {
+    pub weather_radius_strategy: &'a RadiusStrategy,
+    pub weather_context_hours: u8,
+    pub time_options: &'a TimeOptions,
+    pub feature_options: &'a FeatureOptions,
+    pub trust_2d_altitude: bool,
+    pub reverse_geocode: bool,
+    pub weather: bool,
+    pub keep_exif: bool,
+    pub track_movement: bool,
+    pub completeness_weights: CompletenessWeights,
+    pub filename_tag_patterns: &'a [FilenameTagPattern],
+}
+
+/// The options that go into the fingerprint. Options that only affect performance, like the
+/// geocode cache size, are left out.
+///
+/// Fields added after the first fingerprinted release are left out at their default, so
+/// fingerprints from before they existed stay valid.
+#[derive(Serialize)]
+#[allow(clippy::struct_excessive_bools)]
+#[serde(rename_all = "camelCase")]
+struct FingerprintFields<'a> {
+    weather_radius_strategy: &'a RadiusStrategy,
+    #[serde(skip_serializing_if = "is_zero")]
+    weather_context_hours: u8,
+    time_tag_priority: Option<&'a [String]>,
+    future_margin_seconds: i64,
+    fall_back_on_future_timestamp: bool,
+    time_corrections: &'a [TimeCorrection],
+    explain: bool,
+    hdr_detection: HdrStrictness,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    night_mode_from_filename: bool,
+    #[serde(skip_serializing_if = "is_default_slowmotion_ratio")]
+    slowmotion_ratio_threshold: f64,
+    #[serde(skip_serializing_if = "is_default_solar_event_window")]
+    solar_event_window_seconds: i64,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    low_memory: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    numeric_date_order: Option<NumericDateOrder>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    two_digit_years: bool,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    trust_2d_altitude: bool,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    skip_reverse_geocode: bool,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    skip_weather: bool,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    skip_exif: bool,
+    #[serde(skip_serializing_if = "<[_]>::is_empty")]
+    timezone_itinerary: &'a [ItineraryEntry],
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    nautical_timezone_fallback: bool,
+    #[serde(skip_serializing_if = "is_default_completeness_weights")]
+    completeness_weights: CompletenessWeights,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    track_movement: bool,
+    #[serde(skip_serializing_if = "<[_]>::is_empty")]
+    filename_tag_patterns: &'a [FilenameTagPattern],
+}
+
+#[allow(clippy::trivially_copy_pass_by_ref)]
+const fn is_zero(value: &u8) -> bool {
+    *value == 0
+}
+
+#[allow(clippy::trivially_copy_pass_by_ref, clippy::float_cmp)]
+fn is_default_slowmotion_ratio(value: &f64) -> bool {
+    *value == DEFAULT_SLOWMOTION_RATIO_THRESHOLD
+}
+
+#[allow(clippy::trivially_copy_pass_by_ref)]
+const fn is_default_solar_event_window(value: &i64) -> bool {
+    *value == DEFAULT_SOLAR_EVENT_WINDOW.num_seconds()
+}
+
+fn is_default_completeness_weights(value: &CompletenessWeights) -> bool {
+    *value == CompletenessWeights::default()
+}
+
+/// Hashes the result-affecting options. Equal options always give the same fingerprint.
+pub fn config_fingerprint(config: &FingerprintedConfig<'_>) -> String {
+    let (time_options, feature_options) = (config.time_options, config.feature_options);
+    let fields = FingerprintFields {
+        weather_radius_strategy: config.weather_radius_strategy,
+        weather_context_hours: config.weather_context_hours,
+        time_tag_priority: time_options.time_tag_priority.as_deref(),
+        future_margin_seconds: time_options.future_margin.num_seconds(),
+        fall_back_on_future_timestamp: time_options.fall_back_on_future_timestamp,
+        time_corrections: &time_options.corrections,
+        explain: feature_options.explain,
+        hdr_detection: feature_options.hdr_strictness,
+        night_mode_from_filename: feature_options.night_mode_from_filename,
+        slowmotion_ratio_threshold: feature_options.slowmotion_ratio_threshold,
+        solar_event_window_seconds: feature_options.solar_event_window.num_seconds(),
+        low_memory: time_options.low_memory,
+        numeric_date_order: time_options.numeric_date_order,
+        two_digit_years: time_options.two_digit_years,
+        trust_2d_altitude: config.trust_2d_altitude,
+        skip_reverse_geocode: !config.reverse_geocode,
+        skip_weather: !config.weather,
+        skip_exif: !config.keep_exif,
+        timezone_itinerary: &time_options.itinerary,
+        nautical_timezone_fallback: time_options.nautical_fallback,
+        completeness_weights: config.completeness_weights,
+        track_movement: config.track_movement,
+        filename_tag_patterns: config.filename_tag_patterns,
+    };
+    // Serializing a struct of plain data to JSON cannot fail, and its field order is fixed.
+    let json = serde_json::to_vec(&fields).unwrap_or_default();
+    let mut fingerprint = blake3::hash(&json).to_hex().to_string();
+    fingerprint.truncate(FINGERPRINT_LENGTH);
+    fingerprint
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{NaiveDate, TimeDelta};
+
+    /// The default settings, with the weather radius of `strategy`.
+    fn base<'a>(
+        strategy: &'a RadiusStrategy,
+        time_options: &'a TimeOptions,
+        feature_options: &'a FeatureOptions,
+    ) -> FingerprintedConfig<'a> {
+        FingerprintedConfig {
+            weather_radius_strategy: strategy,
+            weather_context_hours: 0,
+            time_options,
+            feature_options,
+            trust_2d_altitude: false,
+            reverse_geocode: true,
+            weather: true,
+            keep_exif: true,
+            track_movement: false,
+            completeness_weights: CompletenessWeights::default(),
+            filename_tag_patterns: &[],
+        }
+    }
+
+    #[test]
+    fn test_fingerprint_is_deterministic() {
+        let (time_options, feature_options) = (TimeOptions::default(), FeatureOptions::default());
+        let first = config_fingerprint(&base(
+            &RadiusStrategy::Fixed(100.0),
+            &time_options,
+            &feature_options,
+        ));
+        let second = config_fingerprint(&base(
+            &RadiusStrategy::Fixed(100.0),
+            &TimeOptions::default(),
+            &FeatureOptions::default(),
+        ));
+        assert_eq!(first, second);
+        assert_eq!(first.len(), FINGERPRINT_LENGTH);
+    }
+
+    #[test]
+    fn test_fingerprint_changes_with_any_option() {
+        let strategy = RadiusStrategy::Fixed(100.0);
+        let (time_options, feature_options) = (TimeOptions::default(), FeatureOptions::default());
+        let base = base(&strategy, &time_options, &feature_options);
+        let with_features = |feature_options: FeatureOptions| {
+            config_fingerprint(&FingerprintedConfig {
+                feature_options: &feature_options,
+                ..base
+            })
+        };
+        let with_time = |time_options: TimeOptions| {
+            config_fingerprint(&FingerprintedConfig {
+                time_options: &time_options,
+                ..base
+            })
+        };
+
+        let variants = [
+            config_fingerprint(&FingerprintedConfig {
+                weather_radius_strategy: &RadiusStrategy::Fixed(50.0),
+                ..base
+            }),
+            config_fingerprint(&FingerprintedConfig {
+                weather_context_hours: 3,
+                ..base
+            }),
+            config_fingerprint(&FingerprintedConfig {
+                trust_2d_altitude: true,
+                ..base
+            }),
+            config_fingerprint(&FingerprintedConfig {
+                reverse_geocode: false,
+                ..base
+            }),
+            config_fingerprint(&FingerprintedConfig {
+                weather: false,
+                ..base
+            }),
+            config_fingerprint(&FingerprintedConfig {
+                keep_exif: false,
+                ..base
+            }),
+            config_fingerprint(&FingerprintedConfig {
+                track_movement: true,
+                ..base
+            }),
+            config_fingerprint(&FingerprintedConfig {
+                completeness_weights: CompletenessWeights {
+                    gps: 40,
+                    ..CompletenessWeights::default()
+                },
+                ..base
+            }),
+            config_fingerprint(&FingerprintedConfig {
+                filename_tag_patterns: &[FilenameTagPattern {
+                    tag_name: "drone".to_string(),
+                    pattern: "_aer\\.".to_string(),
+                    capture_group_as_id: None,
+                }],
+                ..base
+            }),
+            with_features(FeatureOptions {
+                hdr_strictness: HdrStrictness::Lenient,
+                ..FeatureOptions::default()
+            }),
+            with_features(FeatureOptions {
+                explain: true,
+                ..FeatureOptions::default()
+            }),
+            with_features(FeatureOptions {
+                night_mode_from_filename: true,
+                ..FeatureOptions::default()
+            }),
+            with_features(FeatureOptions {
+                slowmotion_ratio_threshold: 1.02,
+                ..FeatureOptions::default()
+            }),
+            with_features(FeatureOptions {
+                solar_event_window: TimeDelta::minutes(40),
+                ..FeatureOptions::default()
+            }),
+            with_time(TimeOptions {
+                time_tag_priority: Some(vec!["CreateDate".to_string()]),
+                ..TimeOptions::default()
+            }),
+            with_time(TimeOptions {
+                future_margin: TimeDelta::hours(1),
+                ..TimeOptions::default()
+            }),
+            with_time(TimeOptions {
+                low_memory: true,
+                ..TimeOptions::default()
+            }),
+            with_time(TimeOptions {
+                numeric_date_order: Some(NumericDateOrder::DayFirst),
+                ..TimeOptions::default()
+            }),
+            with_time(TimeOptions {
+                itinerary: vec![ItineraryEntry {
+                    from: NaiveDate::from_ymd_opt(2024, 5, 1).unwrap(),
+                    to: NaiveDate::from_ymd_opt(2024, 5, 10).unwrap(),
+                    timezone: chrono_tz::Tz::Europe__Lisbon,
+                }],
+                ..TimeOptions::default()
+            }),
+            with_time(TimeOptions {
+                two_digit_years: true,
+                ..TimeOptions::default()
+            }),
+            with_time(TimeOptions {
+                nautical_fallback: true,
+                ..TimeOptions::default()
+            }),
+            with_time(TimeOptions {
+                fall_back_on_future_timestamp: true,
+                ..TimeOptions::default()
+            }),
+        ];
+        let base = config_fingerprint(&base);
+        for (index, variant) in variants.iter().enumerate() {
+            assert_ne!(
+                &base, variant,
+                "variant {index} should change the fingerprint"
+            );
+        }
+    }
+
+    #[test]
+    fn test_reference_time_does_not_change_fingerprint() {
+        // `now` only pins the clock in tests, it is not configuration.
+        let strategy = RadiusStrategy::Fixed(100.0);
+        let feature_options = FeatureOptions::default();
+        let pinned = TimeOptions {
+            now: Some(DateTime::UNIX_EPOCH),
+            ..TimeOptions::default()
+        };
+        assert_eq!(
+            config_fingerprint(&base(&strategy, &pinned, &feature_options)),
+            config_fingerprint(&base(&strategy, &TimeOptions::default(), &feature_options))
+        );
+    }
+
+    fn provenance(logic_revisions: LogicRevisions) -> AnalysisProvenance {
+        AnalysisProvenance {
+            crate_version: CRATE_VERSION.to_string(),
+            exiftool_version: Some("13.10".to_string()),
+            analyzed_at: DateTime::UNIX_EPOCH,
+            config_fingerprint: "0123456789abcdef".to_string(),
+            logic_revisions,
+        }
+    }
+
+    #[test]
+    fn test_current_result_is_fresh() {
+        let policy = StalenessPolicy::current().with_config_fingerprint("0123456789abcdef");
+        assert!(!policy.is_stale(Some(&provenance(LogicRevisions::current()))));
+        assert!(policy.is_stale(None));
+    }
+
+    #[test]
+    fn test_older_revision_is_stale() {
+        let older_tags = LogicRevisions {
+            tags: TAGS_LOGIC_REVISION - 1,
+            ..LogicRevisions::current()
+        };
+        let policy = StalenessPolicy::current();
+        assert!(policy.is_stale(Some(&provenance(older_tags))));
+
+        // Only modules marked recompute-on-change count.
+        let time_only = StalenessPolicy {
+            recompute_on_change: vec![AnalysisModule::Time],
+            ..StalenessPolicy::current()
+        };
+        assert!(!time_only.is_stale(Some(&provenance(older_tags))));
+    }
+
+    #[test]
+    fn test_other_config_is_stale() {
+        let stored = provenance(LogicRevisions::current());
+        assert!(!StalenessPolicy::current().is_stale(Some(&stored)));
+        let policy = StalenessPolicy::current().with_config_fingerprint("fedcba9876543210");
+        assert!(policy.is_stale(Some(&stored)));
+    }
+
+    #[test]
+    fn test_provenance_without_revisions_is_stale() {
+        // Stored before logic revisions were recorded.
+        let stored: AnalysisProvenance = serde_json::from_value(serde_json::json!({
+            "crateVersion": "0.5.0",
+            "exiftoolVersion": "13.10",
+            "analyzedAt": "2025-01-01T00:00:00Z",
+            "configFingerprint": "0123456789abcdef"
+        }))
+        .unwrap();
+        assert_eq!(stored.logic_revisions, LogicRevisions::default());
+        assert!(StalenessPolicy::current().is_stale(Some(&stored)));
+    }
+}
@@ -0,0 +1,83 @@
+//! The raw `exiftool` output an analysis works on, for debugging detections and creating test
+//! fixtures.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Write};
+use std::path::Path;
+
+/// The `exiftool` JSON of one file, exactly as [`MediaAnalyzer::analyze_media`] sees it. Get it
+/// with [`MediaAnalyzer::raw_exif`] and analyze it with [`MediaAnalyzer::analyze_from_exif`].
+///
+/// [`MediaAnalyzer::analyze_media`]: crate::MediaAnalyzer::analyze_media
+/// [`MediaAnalyzer::raw_exif`]: crate::MediaAnalyzer::raw_exif
+/// [`MediaAnalyzer::analyze_from_exif`]: crate::MediaAnalyzer::analyze_from_exif
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RawExif {
+    /// The tags grouped by category, with numeric values: the output of a single call with
+    /// `exiftool_args`.
+    pub grouped: Value,
+    /// The arguments `exiftool` was called with, to tell fixtures from an older pipeline apart.
+    pub exiftool_args: Vec<String>,
+}
+
+impl RawExif {
+    /// Writes the output as pretty-printed JSON, e.g. to create a test fixture.
+    ///
+    /// # Errors
+    /// * If creating or writing the file fails.
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        serde_json::to_writer_pretty(&mut writer, self)?;
+        writer.flush()
+    }
+
+    /// Reads output written by [`RawExif::save`].
+    ///
+    /// # Errors
+    /// * If reading the file fails, or it doesn't hold saved output.
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let reader = BufReader::new(File::open(path)?);
+        Ok(serde_json::from_reader(reader)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_save_load_round_trip() {
+        let raw = RawExif {
+            grouped: json!({
+                "Camera": { "Make": "Google", "ExposureTime": 0.002 },
+                "Time": { "DateTimeOriginal": "2024:06:01 12:00:00" }
+            }),
+            exiftool_args: vec!["-n".to_string(), "-g2".to_string()],
+        };
+        let path = std::env::temp_dir().join(format!(
+            "media_analyzer_raw_exif_{:016x}.json",
+            rand::random::<u64>()
+        ));
+
+        raw.save(&path).unwrap();
+        let loaded = RawExif::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(loaded, raw);
+    }
+
+    #[test]
+    fn test_load_rejects_other_json() {
+        let path = std::env::temp_dir().join(format!(
+            "media_analyzer_raw_exif_{:016x}.json",
+            rand::random::<u64>()
+        ));
+        std::fs::write(&path, r#"{"Camera": {}}"#).unwrap();
+        let result = RawExif::load(&path);
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(result.unwrap_err().kind(), io::ErrorKind::InvalidData);
+    }
+}
@@ -0,0 +1,187 @@
+//! Serializes the timestamps of results with a fixed number of fractional digits, so that
+//! consumers comparing them as text see the same precision for every file.
+
+use crate::structs::MediaMetadata;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::sync::LazyLock;
+
+// The pattern is a valid literal, so compiling it cannot fail.
+#[allow(clippy::unwrap_used)]
+static TIMESTAMP_PATTERN: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^(\d{4}-\d{2}-\d{2}T\d{2}:\d{2}:\d{2})(?:\.(\d{1,9}))?(Z|[+-]\d{2}:\d{2})?$")
+        .unwrap()
+});
+
+/// How many fractional second digits timestamps are written with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum TimestampPrecision {
+    /// 9 digits, which loses nothing.
+    Nanos,
+    /// 6 digits.
+    Micros,
+    /// 3 digits.
+    Millis,
+    /// Whole seconds.
+    Seconds,
+}
+
+impl TimestampPrecision {
+    const fn digits(self) -> usize {
+        match self {
+            Self::Nanos => 9,
+            Self::Micros => 6,
+            Self::Millis => 3,
+            Self::Seconds => 0,
+        }
+    }
+}
+
+/// Options for [`MediaMetadata::to_json_with_options`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SerializeOptions {
+    /// Writes every timestamp with this many fractional digits, truncating extra ones and
+    /// padding missing ones with zeros. `None` keeps chrono's format, which only writes the
+    /// digits needed.
+    pub timestamp_precision: Option<TimestampPrecision>,
+}
+
+/// Rewrites a timestamp like "2017-11-06T11:03:20.123953Z" to `precision`, or returns `None`
+/// for other text.
+fn with_precision(text: &str, precision: TimestampPrecision) -> Option<String> {
+    let captures = TIMESTAMP_PATTERN.captures(text)?;
+    let seconds = captures.get(1)?.as_str();
+    let fraction = captures.get(2).map_or("", |fraction| fraction.as_str());
+    let offset = captures.get(3).map_or("", |offset| offset.as_str());
+    let digits = precision.digits();
+    if digits == 0 {
+        return Some(format!("{seconds}{offset}"));
+    }
+    let fraction = &fraction[..fraction.len().min(digits)];
+    Some(format!("{seconds}.{fraction:0<digits$}{offset}"))
+}
+
+fn apply_precision(value: &mut Value, precision: TimestampPrecision) {
+    match value {
+        Value::String(text) => {
+            if let Some(rewritten) = with_precision(text, precision) {
+                *text = rewritten;
+            }
+        }
+        Value::Array(items) => {
+            for item in items {
+                apply_precision(item, precision);
+            }
+        }
+        Value::Object(map) => {
+            for item in map.values_mut() {
+                apply_precision(item, precision);
+            }
+        }
+        _ => {}
+    }
+}
+
+impl MediaMetadata {
+    /// Serializes the result to JSON like `serde_json::to_value`, with `options` applied. The
+    /// raw `exif` is left as it is.
+    ///
+    /// Deserializing accepts timestamps of any precision, so results written with any options
+    /// can be read back.
+    ///
+    /// # Errors
+    /// * If serializing the result fails.
+    pub fn to_json_with_options(&self, options: &SerializeOptions) -> serde_json::Result<Value> {
+        let mut value = serde_json::to_value(self)?;
+        if let (Some(precision), Value::Object(map)) = (options.timestamp_precision, &mut value) {
+            for (key, item) in map.iter_mut() {
+                if key != "exif" {
+                    apply_precision(item, precision);
+                }
+            }
+        }
+        Ok(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ExifData;
+    use crate::time::get_time_info;
+    use crate::time::structs::{TimeInfo, TimeOptions};
+    use serde_json::json;
+
+    #[test]
+    fn test_precision_levels() {
+        let cases = [
+            (TimestampPrecision::Nanos, "2017-11-06T11:03:20.123953000"),
+            (TimestampPrecision::Micros, "2017-11-06T11:03:20.123953"),
+            (TimestampPrecision::Millis, "2017-11-06T11:03:20.123"),
+            (TimestampPrecision::Seconds, "2017-11-06T11:03:20"),
+        ];
+        for (precision, expected) in cases {
+            assert_eq!(
+                with_precision("2017-11-06T11:03:20.123953", precision).as_deref(),
+                Some(expected)
+            );
+        }
+        // Timestamps without subseconds get the same width as those with.
+        assert_eq!(
+            with_precision("2017-11-06T11:03:20Z", TimestampPrecision::Millis).as_deref(),
+            Some("2017-11-06T11:03:20.000Z")
+        );
+        assert_eq!(
+            with_precision("2017-11-06T11:03:20.5+02:00", TimestampPrecision::Micros).as_deref(),
+            Some("2017-11-06T11:03:20.500000+02:00")
+        );
+        assert_eq!(
+            with_precision("2017-11-06", TimestampPrecision::Seconds),
+            None
+        );
+        assert_eq!(
+            with_precision("Amsterdam", TimestampPrecision::Seconds),
+            None
+        );
+    }
+
+    fn time_info() -> TimeInfo {
+        let exif = ExifData::new(json!({ "Time": {
+            "DateTimeOriginal": "2017:11:06 11:03:20.123953789",
+            "OffsetTimeOriginal": "+01:00"
+        } }));
+        get_time_info(&exif, None, &TimeOptions::default()).unwrap()
+    }
+
+    #[test]
+    fn test_nanos_round_trip() {
+        let time = time_info();
+        let mut value = serde_json::to_value(&time).unwrap();
+        apply_precision(&mut value, TimestampPrecision::Nanos);
+        assert_eq!(value["datetimeUtc"], "2017-11-06T10:03:20.123953789Z");
+        // The ISO text is padded like the other timestamps, but names the same instant.
+        assert_eq!(
+            value["datetimeLocalIso"],
+            "2017-11-06T11:03:20.123000000+01:00"
+        );
+        let read_back: TimeInfo = serde_json::from_value(value).unwrap();
+        assert_eq!(read_back.datetime_utc, time.datetime_utc);
+        assert_eq!(read_back.datetime_local, time.datetime_local);
+        assert_eq!(read_back.timezone, time.timezone);
+    }
+
+    #[test]
+    fn test_any_precision_can_be_read_back() {
+        let time = time_info();
+        let mut value = serde_json::to_value(&time).unwrap();
+        apply_precision(&mut value, TimestampPrecision::Millis);
+        assert_eq!(value["datetimeLocal"], "2017-11-06T11:03:20.123");
+        let read_back: TimeInfo = serde_json::from_value(value).unwrap();
+        assert_eq!(
+            read_back.datetime_local.to_string(),
+            "2017-11-06 11:03:20.123"
+        );
+    }
+}
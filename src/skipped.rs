@@ -0,0 +1,200 @@
+//! Why optional stages of an analysis didn't run, to tell "there is no weather station nearby"
+//! apart from "there was no GPS, so the weather was never looked up".
+
+use crate::features::gps::{CoordinatePrecision, GpsInfo};
+use crate::hooks::AnalysisHooks;
+use crate::time::structs::TimeInfo;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Reverse geocoding the GPS coordinates to a place name.
+pub const STAGE_GEOCODE: &str = "geocode";
+/// Looking up the timezone of a naive capture time at the GPS location.
+pub const STAGE_TIMEZONE_FROM_GPS: &str = "timezone_from_gps";
+/// Looking up the historical weather.
+pub const STAGE_WEATHER: &str = "weather";
+
+const NO_GPS: &str = "no GPS coordinates";
+const DISABLED: &str = "disabled by configuration";
+
+/// An optional stage of the analysis that was skipped, or that failed without failing the
+/// analysis.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SkippedStage {
+    /// [`STAGE_GEOCODE`], [`STAGE_TIMEZONE_FROM_GPS`] or [`STAGE_WEATHER`].
+    pub stage: String,
+    /// Why it was skipped, like "no GPS coordinates".
+    pub reason: String,
+}
+
+impl SkippedStage {
+    pub fn new(stage: &str, reason: impl Into<String>) -> Self {
+        Self {
+            stage: stage.to_string(),
+            reason: reason.into(),
+        }
+    }
+}
+
+/// The stages skipped before the weather lookup, as far as they depend on GPS or on
+/// `reverse_geocode` being turned off.
+pub fn skipped_before_weather(
+    gps: Option<&GpsInfo>,
+    time: &TimeInfo,
+    reverse_geocode: bool,
+) -> Vec<SkippedStage> {
+    let mut skipped = Vec::new();
+    match gps {
+        None => skipped.push(SkippedStage::new(STAGE_GEOCODE, NO_GPS)),
+        Some(_) if !reverse_geocode => {
+            skipped.push(SkippedStage::new(STAGE_GEOCODE, DISABLED));
+        }
+        Some(gps) if gps.coordinate_precision == CoordinatePrecision::VeryCoarse => {
+            skipped.push(SkippedStage::new(
+                STAGE_GEOCODE,
+                "coordinates are too coarse to name a place",
+            ));
+        }
+        Some(_) => {}
+    }
+    if time.timezone.is_none() {
+        let reason = if gps.is_some() {
+            "no timezone found at the GPS location"
+        } else {
+            NO_GPS
+        };
+        skipped.push(SkippedStage::new(STAGE_TIMEZONE_FROM_GPS, reason));
+    }
+    skipped
+}
+
+/// The location and UTC time to look the weather up for, or why the lookup is skipped. A
+/// lookup turned off with `weather(false)` is skipped regardless of GPS.
+pub fn weather_prerequisites<'a>(
+    gps: Option<&'a GpsInfo>,
+    time: &TimeInfo,
+    hooks: &AnalysisHooks,
+    enabled: bool,
+) -> Result<(&'a GpsInfo, DateTime<Utc>), SkippedStage> {
+    if !enabled {
+        return Err(SkippedStage::new(STAGE_WEATHER, DISABLED));
+    }
+    let gps = gps.ok_or_else(|| SkippedStage::new(STAGE_WEATHER, NO_GPS))?;
+    let utc_time = time
+        .datetime_utc
+        .ok_or_else(|| SkippedStage::new(STAGE_WEATHER, "no UTC timestamp"))?;
+    if !hooks.allow_weather(gps, time) {
+        return Err(SkippedStage::new(
+            STAGE_WEATHER,
+            "disabled by the before_weather hook",
+        ));
+    }
+    Ok((gps, utc_time))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ExifData;
+    use crate::features::gps::get_gps_coordinates;
+    use crate::time::get_time_info;
+    use crate::time::structs::{CONFIDENCE_LOW, TimeOptions};
+    use serde_json::{Value, json};
+    use std::sync::Arc;
+
+    fn gps_and_time(exif: Value) -> (Option<GpsInfo>, TimeInfo) {
+        let exif = ExifData::new(exif);
+        let gps = get_gps_coordinates(&exif);
+        let time = get_time_info(&exif, gps.as_ref(), &TimeOptions::default()).unwrap();
+        (gps, time)
+    }
+
+    #[test]
+    fn test_no_gps() {
+        let (gps, time) =
+            gps_and_time(json!({ "Time": { "DateTimeOriginal": "2024:06:01 12:00:00" } }));
+        assert_eq!(
+            skipped_before_weather(gps.as_ref(), &time, true),
+            vec![
+                SkippedStage::new(STAGE_GEOCODE, "no GPS coordinates"),
+                SkippedStage::new(STAGE_TIMEZONE_FROM_GPS, "no GPS coordinates"),
+            ]
+        );
+        assert_eq!(
+            weather_prerequisites(gps.as_ref(), &time, &AnalysisHooks::default(), true)
+                .unwrap_err(),
+            SkippedStage::new(STAGE_WEATHER, "no GPS coordinates")
+        );
+    }
+
+    #[test]
+    fn test_gps_with_low_confidence_time() {
+        // Only the file date is known, but the weather is still looked up.
+        let (gps, time) = gps_and_time(json!({
+            "Location": { "GPSLatitude": 52.379_189, "GPSLongitude": 4.899_431 },
+            "Time": { "FileModifyDate": "2024:06:01 12:00:00+02:00" }
+        }));
+        assert_eq!(time.source_details.confidence, CONFIDENCE_LOW);
+        assert_eq!(
+            skipped_before_weather(gps.as_ref(), &time, true),
+            Vec::new()
+        );
+        // Turning geocoding off leaves the timezone lookup alone.
+        assert_eq!(
+            skipped_before_weather(gps.as_ref(), &time, false),
+            vec![SkippedStage::new(
+                STAGE_GEOCODE,
+                "disabled by configuration"
+            )]
+        );
+        let (_, utc_time) =
+            weather_prerequisites(gps.as_ref(), &time, &AnalysisHooks::default(), true).unwrap();
+        assert_eq!(utc_time.to_rfc3339(), "2024-06-01T10:00:00+00:00");
+
+        // Without a UTC time, there is nothing to look the weather up for.
+        let (_, naive_time) =
+            gps_and_time(json!({ "Time": { "DateTimeOriginal": "2024:06:01 12:00:00" } }));
+        assert_eq!(
+            weather_prerequisites(gps.as_ref(), &naive_time, &AnalysisHooks::default(), true)
+                .unwrap_err(),
+            SkippedStage::new(STAGE_WEATHER, "no UTC timestamp")
+        );
+    }
+
+    #[test]
+    fn test_weather_disabled() {
+        let (gps, time) = gps_and_time(json!({
+            "Location": { "GPSLatitude": 52.379_189, "GPSLongitude": 4.899_431 },
+            "Time": { "DateTimeOriginal": "2024:06:01 12:00:00" }
+        }));
+        let hooks = AnalysisHooks {
+            before_weather: Some(Arc::new(|_: &GpsInfo, _: &TimeInfo| false)),
+            ..AnalysisHooks::default()
+        };
+        assert_eq!(
+            weather_prerequisites(gps.as_ref(), &time, &hooks, true).unwrap_err(),
+            SkippedStage::new(STAGE_WEATHER, "disabled by the before_weather hook")
+        );
+        assert_eq!(
+            weather_prerequisites(gps.as_ref(), &time, &AnalysisHooks::default(), false)
+                .unwrap_err(),
+            SkippedStage::new(STAGE_WEATHER, "disabled by configuration")
+        );
+    }
+
+    #[test]
+    fn test_very_coarse_gps_is_not_geocoded() {
+        let (gps, time) = gps_and_time(json!({
+            "Location": { "GPSLatitude": 52, "GPSLongitude": 5 },
+            "Time": { "DateTimeOriginal": "2024:06:01 12:00:00" }
+        }));
+        assert_eq!(
+            skipped_before_weather(gps.as_ref(), &time, true),
+            vec![SkippedStage::new(
+                STAGE_GEOCODE,
+                "coordinates are too coarse to name a place"
+            )]
+        );
+    }
+}
@@ -1,19 +1,85 @@
+use crate::exif_data::ParseDiagnostic;
+use crate::features::aux_images::AuxImageInfo;
+use crate::features::calendar::CalendarContext;
+use crate::features::completeness::CompletenessBreakdown;
+use crate::features::file_health::FileHealth;
+use crate::features::ingest_audit::IngestAudit;
+use crate::features::pano::PanoViewInfo;
+use crate::features::quality::QualityHint;
+use crate::features::user_metadata::UserMetadata;
+use crate::notes::AnalysisNote;
+use crate::provenance::AnalysisProvenance;
+use crate::skipped::SkippedStage;
 use crate::tags::structs::MediaFeatures;
 use crate::time::structs::TimeInfo;
 use crate::{BasicMetadata, CameraSettings, GpsInfo, WeatherInfo};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct MediaMetadata {
     pub hash: String,
     pub exif: Value,
     pub use_panorama_viewer: bool,
+    /// The GPano view of a photo sphere or panorama, `None` for other images and for videos
+    /// other than the unstitched lens images of a 360 degree camera.
+    #[serde(default)]
+    pub pano: Option<PanoViewInfo>,
     pub basic: BasicMetadata,
     pub camera: CameraSettings,
     pub features: MediaFeatures,
     pub time: TimeInfo,
+    #[serde(default)]
     pub gps: Option<GpsInfo>,
+    #[serde(default)]
     pub weather: Option<WeatherInfo>,
+    /// Whether the photo was taken on a weekend or a public holiday, by its local date.
+    #[serde(default)]
+    pub calendar_context: Option<CalendarContext>,
+    #[serde(default)]
+    pub quality_hints: Vec<QualityHint>,
+    /// Signs that the file was cut short or doesn't match its metadata. Default for results
+    /// stored before they were checked.
+    #[serde(default)]
+    pub file_health: FileHealth,
+    /// Tags that were present but had an unexpected type. `None` unless the analyzer was built
+    /// with `collect_parse_diagnostics`.
+    #[serde(default)]
+    pub parse_diagnostics: Option<Vec<ParseDiagnostic>>,
+    #[serde(default)]
+    pub dedup_key: Option<String>,
+    /// For videos analyzed with a [`ThumbnailSource`](crate::ThumbnailSource), where in the video
+    /// the thumbnail frame was taken, in seconds.
+    #[serde(default)]
+    pub thumbnail_timestamp_s: Option<f64>,
+    /// Gain maps, depth maps and other images embedded next to the primary image.
+    #[serde(default)]
+    pub aux_images: Vec<AuxImageInfo>,
+    /// Captions, events and locations written into the file by people.
+    #[serde(default)]
+    pub user_metadata: UserMetadata,
+    /// The optional stages that didn't run, like the weather lookup without GPS, and why.
+    #[serde(default)]
+    pub skipped_stages: Vec<SkippedStage>,
+    /// Non-fatal information about the analysis, like skipped stages, a failed weather lookup
+    /// or a malformed duration tag, sorted by module and code. Empty for a file that was
+    /// analyzed completely.
+    #[serde(default)]
+    pub notes: Vec<AnalysisNote>,
+    /// How long after capture the file was last written, and whether its name and extension
+    /// are still the camera's. `None` for results stored before it was audited.
+    #[serde(default)]
+    pub ingest_audit: Option<IngestAudit>,
+    /// How much is known about the file, from 0 to 100, weighted by the analyzer's
+    /// `completeness_weights`. See [`score_completeness`](crate::score_completeness).
+    #[serde(default)]
+    pub completeness_score: u8,
+    /// What `completeness_score` is made of.
+    #[serde(default)]
+    pub completeness: CompletenessBreakdown,
+    /// The analyzer version and configuration that produced this result. `None` for results
+    /// stored before provenance was recorded.
+    #[serde(default)]
+    pub provenance: Option<AnalysisProvenance>,
 }
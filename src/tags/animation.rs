@@ -0,0 +1,50 @@
+use crate::ExifData;
+
+/// Decides whether the file is an animated image rather than a photo or a video, explaining why.
+///
+/// Image sequences (`image/heic-sequence`, `image/avif-sequence`, ...) always count, GIFs only
+/// when they have more than one frame.
+pub fn animation_evidence(exif: &ExifData) -> Option<String> {
+    let mime_type = exif
+        .group_str("Other", "MIMEType")
+        .or_else(|| exif.get_str("MIMEType"))?;
+    if mime_type.starts_with("image/") && mime_type.ends_with("-sequence") {
+        return Some(format!("MIMEType is {mime_type}"));
+    }
+    if mime_type == "image/gif"
+        && let Some(frame_count) = exif.get_u64("FrameCount").filter(|count| *count > 1)
+    {
+        return Some(format!("GIF with FrameCount {frame_count}"));
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_image_sequences_are_animations() {
+        for mime_type in ["image/heic-sequence", "image/avif-sequence"] {
+            let exif = ExifData::new(json!({ "MIMEType": mime_type }));
+            assert_eq!(
+                animation_evidence(&exif),
+                Some(format!("MIMEType is {mime_type}"))
+            );
+        }
+    }
+
+    #[test]
+    fn test_gif_needs_multiple_frames() {
+        let animated = ExifData::new(json!({ "MIMEType": "image/gif", "FrameCount": 12 }));
+        assert_eq!(
+            animation_evidence(&animated).as_deref(),
+            Some("GIF with FrameCount 12")
+        );
+        let still = ExifData::new(json!({ "MIMEType": "image/gif", "FrameCount": 1 }));
+        assert!(animation_evidence(&still).is_none());
+        let jpeg = ExifData::new(json!({ "MIMEType": "image/jpeg" }));
+        assert!(animation_evidence(&jpeg).is_none());
+    }
+}
@@ -0,0 +1,89 @@
+//! Classifies the displayed aspect ratio into the common social media formats.
+
+use serde::{Deserialize, Serialize};
+
+/// How far, relatively, a width/height ratio may be off its format's ratio, to allow for
+/// e.g. 1080x1350 crops that were resized with rounding.
+pub const ASPECT_TOLERANCE: f64 = 0.02;
+/// Width/height ratios from this one up are ultrawide, such as 21:9 video and panoramas.
+pub const ULTRAWIDE_MIN_RATIO: f64 = 2.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum AspectClass {
+    /// 9:16, as in stories and short-form vertical video.
+    Vertical9x16,
+    /// 4:5, the tallest portrait post format.
+    Portrait4x5,
+    Square,
+    Landscape16x9,
+    Landscape4x3,
+    Ultrawide,
+    /// Also for classes written by later versions.
+    #[serde(other)]
+    Other,
+}
+
+impl AspectClass {
+    /// Classifies displayed dimensions, `None` when either of them is zero.
+    #[must_use]
+    pub fn classify(width: u64, height: u64) -> Option<Self> {
+        if width == 0 || height == 0 {
+            return None;
+        }
+        #[allow(clippy::cast_precision_loss)]
+        let ratio = width as f64 / height as f64;
+        if ratio >= ULTRAWIDE_MIN_RATIO {
+            return Some(Self::Ultrawide);
+        }
+        let formats = [
+            (Self::Vertical9x16, 9.0 / 16.0),
+            (Self::Portrait4x5, 4.0 / 5.0),
+            (Self::Square, 1.0),
+            (Self::Landscape4x3, 4.0 / 3.0),
+            (Self::Landscape16x9, 16.0 / 9.0),
+        ];
+        Some(
+            formats
+                .into_iter()
+                .find(|(_, target)| (ratio / target - 1.0).abs() <= ASPECT_TOLERANCE)
+                .map_or(Self::Other, |(class, _)| class),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classify() {
+        assert_eq!(
+            AspectClass::classify(1080, 1920),
+            Some(AspectClass::Vertical9x16)
+        );
+        assert_eq!(
+            AspectClass::classify(1080, 1350),
+            Some(AspectClass::Portrait4x5)
+        );
+        assert_eq!(AspectClass::classify(3000, 3000), Some(AspectClass::Square));
+        // 1.5% off square still counts.
+        assert_eq!(AspectClass::classify(1015, 1000), Some(AspectClass::Square));
+        assert_eq!(
+            AspectClass::classify(4000, 3000),
+            Some(AspectClass::Landscape4x3)
+        );
+        assert_eq!(
+            AspectClass::classify(1920, 1080),
+            Some(AspectClass::Landscape16x9)
+        );
+        assert_eq!(
+            AspectClass::classify(2560, 1080),
+            Some(AspectClass::Ultrawide)
+        );
+        // 3:2, as from most DSLRs, and 3:4 portrait photos.
+        assert_eq!(AspectClass::classify(6000, 4000), Some(AspectClass::Other));
+        assert_eq!(AspectClass::classify(3000, 4000), Some(AspectClass::Other));
+        assert_eq!(AspectClass::classify(0, 1080), None);
+    }
+}
@@ -0,0 +1,223 @@
+//! Detects astrophotography: star fields and long-exposure night landscapes. This needs the
+//! position of the sun, so it runs after the weather stage rather than in
+//! [`extract_features`](crate::tags::logic::extract_features).
+
+use crate::ExifData;
+use crate::features::gps::GpsInfo;
+use crate::features::metadata::CameraSettings;
+use crate::features::weather::{SunInfo, WeatherInfo, compute_sun_info};
+use crate::tags::night_mode::special_type_containing;
+use crate::tags::structs::MediaFeatures;
+use crate::time::structs::TimeInfo;
+use chrono::{DateTime, TimeDelta, Utc};
+
+/// Exposure times from this long up can be astrophotography, in seconds.
+pub const ASTRO_MIN_EXPOSURE_S: f64 = 5.0;
+/// ISO values from this high up can be astrophotography.
+pub const ASTRO_MIN_ISO: u64 = 800;
+/// How long after civil dusk (or before civil dawn) the sky counts as dark enough for stars.
+const DARKNESS_MARGIN: TimeDelta = TimeDelta::hours(1);
+
+/// The rule that marked a photo as astrophotography, with the evidence found.
+#[derive(Debug, Clone, PartialEq)]
+pub enum AstroEvidence {
+    /// Pixel's XMP `SpecialTypeID` for Astrophotography mode.
+    SpecialType(String),
+    /// A long, high-ISO exposure without flash, taken well after dusk.
+    LongNightExposure { exposure_time: f64, iso: u64 },
+}
+
+impl AstroEvidence {
+    pub fn explanation(&self) -> String {
+        match self {
+            Self::SpecialType(value) => {
+                format!("SpecialTypeID contains 'astro' (value: '{value}')")
+            }
+            Self::LongNightExposure { exposure_time, iso } => format!(
+                "ExposureTime {exposure_time}s >= {ASTRO_MIN_EXPOSURE_S}s and ISO {iso} >= {ASTRO_MIN_ISO} without flash, well after dusk"
+            ),
+        }
+    }
+}
+
+/// The exposure time and ISO of a long, high-ISO exposure with focal length data and without
+/// flash.
+fn long_exposure(camera: &CameraSettings) -> Option<(f64, u64)> {
+    let exposure_time = camera
+        .exposure_time
+        .filter(|exposure_time| *exposure_time >= ASTRO_MIN_EXPOSURE_S)?;
+    let iso = camera.iso.filter(|iso| *iso >= ASTRO_MIN_ISO)?;
+    let flash_fired = camera.flash.as_ref().is_some_and(|flash| flash.fired);
+    (camera.focal_length.is_some() && !flash_fired).then_some((exposure_time, iso))
+}
+
+/// Whether `datetime` is well after dusk or well before dawn. Without a dusk or dawn, as in
+/// polar summer and winter, it is dark whenever the sun is down.
+fn is_dark(sun: &SunInfo, datetime: DateTime<Utc>) -> bool {
+    if sun.is_daytime {
+        return false;
+    }
+    let before_dawn = sun
+        .dawn
+        .is_none_or(|dawn| datetime < dawn - DARKNESS_MARGIN);
+    let after_dusk = sun
+        .dusk
+        .is_none_or(|dusk| datetime > dusk + DARKNESS_MARGIN);
+    match (sun.dawn, sun.dusk) {
+        (Some(_), Some(_)) => before_dawn || after_dusk,
+        _ => before_dawn && after_dusk,
+    }
+}
+
+/// Decides whether the photo is astrophotography, explaining the rule that fired.
+///
+/// Pixel's Astrophotography mode marker is enough on its own. Otherwise, every condition of
+/// [`AstroEvidence::LongNightExposure`] must hold. The result is `None` when the exposure
+/// qualifies but there is no capture time or sun position to tell whether it was dark.
+pub fn detect_astrophotography(
+    exif: &ExifData,
+    camera: &CameraSettings,
+    capture_utc: Option<DateTime<Utc>>,
+    sun: Option<&SunInfo>,
+) -> (Option<bool>, Option<AstroEvidence>) {
+    if let Some(special_type) = special_type_containing(exif, "astro") {
+        return (Some(true), Some(AstroEvidence::SpecialType(special_type)));
+    }
+    let Some((exposure_time, iso)) = long_exposure(camera) else {
+        return (Some(false), None);
+    };
+    match capture_utc.zip(sun) {
+        None => (None, None),
+        Some((datetime, sun)) if is_dark(sun, datetime) => (
+            Some(true),
+            Some(AstroEvidence::LongNightExposure { exposure_time, iso }),
+        ),
+        Some(_) => (Some(false), None),
+    }
+}
+
+/// Sets `is_astrophotography` and its explanation, using the sun position of the weather
+/// lookup, or computing it when the weather was skipped.
+pub fn apply_astrophotography(
+    features: &mut MediaFeatures,
+    exif: &ExifData,
+    camera: &CameraSettings,
+    time: &TimeInfo,
+    gps: Option<&GpsInfo>,
+    weather: Option<&WeatherInfo>,
+) {
+    let computed_sun = match (weather, gps, time.datetime_utc) {
+        (None, Some(gps), Some(datetime)) => {
+            compute_sun_info(datetime, gps, time.timezone.as_ref()).ok()
+        }
+        _ => None,
+    };
+    let sun = weather
+        .map(|weather| &weather.sun_info)
+        .or(computed_sun.as_ref());
+    let (is_astrophotography, evidence) =
+        detect_astrophotography(exif, camera, time.datetime_utc, sun);
+    features.is_astrophotography = is_astrophotography;
+    if let (Some(explanations), Some(evidence)) = (features.explanations.as_mut(), evidence) {
+        explanations.insert("isAstrophotography".to_string(), evidence.explanation());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::features::gps::get_gps_coordinates;
+    use crate::features::metadata::get_metadata;
+    use crate::tags::logic::extract_features;
+    use crate::tags::structs::FeatureOptions;
+    use crate::time::get_time_info;
+    use crate::time::structs::TimeOptions;
+    use serde_json::{Value, json};
+    use std::path::Path;
+
+    fn features_for(exif: Value) -> MediaFeatures {
+        let exif = ExifData::new(exif);
+        let options = FeatureOptions {
+            explain: true,
+            ..FeatureOptions::default()
+        };
+        let mut features = extract_features(Path::new("photo.jpg"), &exif, &options);
+        let (_, camera) = get_metadata(&exif).unwrap();
+        let gps = get_gps_coordinates(&exif);
+        let time = get_time_info(&exif, gps.as_ref(), &TimeOptions::default()).unwrap();
+        apply_astrophotography(&mut features, &exif, &camera, &time, gps.as_ref(), None);
+        features
+    }
+
+    /// A tripod shot in the Dutch countryside at `time` local summer time.
+    fn long_exposure_exif(time: &str, exposure_time: f64, iso: u64) -> Value {
+        json!({
+            "File": { "MIMEType": "image/jpeg", "FileSize": 1000, "ImageWidth": 6000, "ImageHeight": 4000 },
+            "Camera": {
+                "Make": "NIKON CORPORATION",
+                "Model": "NIKON Z 6",
+                "ExposureTime": exposure_time,
+                "ISO": iso,
+                "FocalLength": 14.0,
+                "Flash": 16
+            },
+            "Location": { "GPSLatitude": 52.7, "GPSLongitude": 6.4 },
+            "Time": { "DateTimeOriginal": time, "OffsetTimeOriginal": "+02:00" }
+        })
+    }
+
+    #[test]
+    fn test_pixel_astro_mode() {
+        // No capture settings or location, the marker is enough.
+        let features = features_for(json!({
+            "File": { "MIMEType": "image/jpeg", "FileSize": 1000, "ImageWidth": 4032, "ImageHeight": 3024 },
+            "XMP": {
+                "SpecialTypeID": "com.google.android.apps.camera.gallery.specialtype.SpecialType-ASTRO"
+            },
+            "Time": { "DateTimeOriginal": "2024:03:10 23:40:00" }
+        }));
+        assert_eq!(features.is_astrophotography, Some(true));
+        assert!(!features.is_night_mode);
+        assert!(features.explanations.unwrap()["isAstrophotography"].contains("SpecialType-ASTRO"));
+    }
+
+    #[test]
+    fn test_long_exposure_at_night() {
+        let features = features_for(long_exposure_exif("2024:08:12 02:00:00", 20.0, 3200));
+        assert_eq!(features.is_astrophotography, Some(true));
+        assert_eq!(
+            features.explanations.unwrap()["isAstrophotography"],
+            "ExposureTime 20s >= 5s and ISO 3200 >= 800 without flash, well after dusk"
+        );
+    }
+
+    #[test]
+    fn test_daytime_long_exposure() {
+        // A waterfall smoothed with an ND filter: long enough, but the sun is up.
+        let features = features_for(long_exposure_exif("2024:08:12 14:00:00", 5.0, 800));
+        assert_eq!(features.is_astrophotography, Some(false));
+        assert!(
+            !features
+                .explanations
+                .unwrap()
+                .contains_key("isAstrophotography")
+        );
+    }
+
+    #[test]
+    fn test_twilight_and_unknown_darkness() {
+        // Sunset in the Netherlands is around 21:00 on this day, so 21:30 is still twilight.
+        let features = features_for(long_exposure_exif("2024:08:12 21:30:00", 20.0, 3200));
+        assert_eq!(features.is_astrophotography, Some(false));
+
+        // Without a location there is no sun position to check.
+        let mut exif = long_exposure_exif("2024:08:12 02:00:00", 20.0, 3200);
+        exif.as_object_mut().unwrap().remove("Location");
+        assert_eq!(features_for(exif).is_astrophotography, None);
+
+        // A fired flash rules it out, whatever the time.
+        let mut exif = long_exposure_exif("2024:08:12 02:00:00", 20.0, 3200);
+        exif["Camera"]["Flash"] = json!(1);
+        assert_eq!(features_for(exif).is_astrophotography, Some(false));
+    }
+}
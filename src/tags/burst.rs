@@ -1,8 +1,12 @@
 use crate::ExifData;
+use crate::features::filename::{FilenameCandidates, FilenameKind};
+use crate::tags::drive_mode::{DriveModeInfo, synthesize_burst_id};
 use regex::Regex;
 use std::sync::LazyLock;
 
 // Captures the common filename prefix before "_burst".
+// The pattern is a valid literal, so compiling it cannot fail.
+#[allow(clippy::unwrap_used)]
 static BURST_ID_PATTERN: LazyLock<Regex> =
     LazyLock::new(|| Regex::new(r"(?i)(.*?)_burst.*").unwrap());
 
@@ -22,24 +26,84 @@ pub fn detect_burst_from_filename(filename_lower: &str) -> (bool, Option<String>
     (false, None)
 }
 
+/// The rule that marked a photo as part of a burst, with the evidence found.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum BurstEvidence {
+    ExifTag {
+        tag: &'static str,
+        id: String,
+    },
+    ContinuousDrive {
+        sequence_number: u32,
+        id: Option<String>,
+    },
+    Filename {
+        id: String,
+        kind: FilenameKind,
+    },
+}
+
+impl BurstEvidence {
+    #[must_use]
+    pub fn burst_id(&self) -> Option<String> {
+        match self {
+            Self::ExifTag { id, .. } | Self::Filename { id, .. } => Some(id.clone()),
+            Self::ContinuousDrive { id, .. } => id.clone(),
+        }
+    }
+
+    #[must_use]
+    pub fn explanation(&self) -> String {
+        match self {
+            Self::ExifTag { tag, id } => format!("{tag} is set (value: '{id}')"),
+            Self::ContinuousDrive {
+                sequence_number, ..
+            } => format!("continuous drive mode with SequenceNumber {sequence_number}"),
+            Self::Filename { id, kind } => {
+                format!("{} contains '_burst' (burst id: '{id}')", kind.describe())
+            }
+        }
+    }
+}
+
 /// Orchestrates burst detection using a multi-layered approach for maximum compatibility.
-pub fn find_burst_info(exif: &ExifData, filename_lower: &str) -> (bool, Option<String>) {
+pub fn burst_evidence(
+    exif: &ExifData,
+    drive_mode: &DriveModeInfo,
+    filenames: &FilenameCandidates,
+) -> Option<BurstEvidence> {
     // Layer 1: Check for explicit EXIF burst tags (most reliable method).
     // - BurstUUID is the standard for Apple devices.
     // - GCamera:BurstId is a specific XMP tag used by Google Camera.
-    let exif_burst_id = exif
-        .get_string("BurstUUID")
-        .or_else(|| exif.get_string("GCamera:BurstId"))
-        .or_else(|| exif.get_string("BurstId"));
+    for tag in ["BurstUUID", "GCamera:BurstId", "BurstId"] {
+        if let Some(id) = exif.get_string(tag) {
+            if id.is_empty() {
+                break;
+            }
+            return Some(BurstEvidence::ExifTag { tag, id });
+        }
+    }
 
-    if let Some(id) = exif_burst_id
-        && !id.is_empty()
+    // Layer 2: A continuous-drive sequence recorded in the maker notes of dedicated cameras.
+    if drive_mode.is_continuous
+        && let Some(sequence_number) = drive_mode.sequence_number.filter(|n| *n > 0)
     {
-        return (true, Some(id));
+        return Some(BurstEvidence::ContinuousDrive {
+            sequence_number,
+            id: synthesize_burst_id(exif),
+        });
     }
 
-    // Layer 2: Fallback to filename-based detection for other devices (e.g., Samsung).
-    detect_burst_from_filename(filename_lower)
+    // Layer 3: Fallback to filename-based detection for other devices (e.g., Samsung).
+    filenames
+        .find_map(
+            |filename_lower| match detect_burst_from_filename(filename_lower) {
+                (true, Some(id)) => Some(id),
+                _ => None,
+            },
+        )
+        .map(|(id, kind)| BurstEvidence::Filename { id, kind })
 }
 
 #[cfg(test)]
@@ -47,8 +111,19 @@ mod tests {
     use super::*;
     use crate::ExifData;
     use serde_json::json;
+    use std::path::Path;
 
-    // --- Tests for the orchestrator `find_burst_info` function ---
+    fn find_burst_info(
+        exif: &ExifData,
+        drive_mode: &DriveModeInfo,
+        filename_lower: &str,
+    ) -> (bool, Option<String>) {
+        let filenames = FilenameCandidates::new(Path::new(filename_lower), exif);
+        burst_evidence(exif, drive_mode, &filenames)
+            .map_or((false, None), |evidence| (true, evidence.burst_id()))
+    }
+
+    // --- Tests for the orchestrator `burst_evidence` function ---
 
     #[test]
     fn test_find_burst_prefers_exif_burstuuid() {
@@ -60,7 +135,7 @@ mod tests {
         }));
         let filename = "some_burst_filename.jpg";
 
-        let (is_burst, burst_id) = find_burst_info(&exif_data, filename);
+        let (is_burst, burst_id) = find_burst_info(&exif_data, &DriveModeInfo::default(), filename);
 
         assert!(is_burst);
         assert_eq!(burst_id, Some("APPLE-BURST-ID-123".to_string()));
@@ -75,7 +150,7 @@ mod tests {
         }));
         let filename = "some_burst_filename.jpg";
 
-        let (is_burst, burst_id) = find_burst_info(&exif_data, filename);
+        let (is_burst, burst_id) = find_burst_info(&exif_data, &DriveModeInfo::default(), filename);
 
         assert!(is_burst);
         assert_eq!(burst_id, Some("GOOGLE-BURST-ID-456".to_string()));
@@ -89,7 +164,7 @@ mod tests {
         }));
         let filename = "some_burst_filename.jpg";
 
-        let (is_burst, burst_id) = find_burst_info(&exif_data, filename);
+        let (is_burst, burst_id) = find_burst_info(&exif_data, &DriveModeInfo::default(), filename);
 
         assert!(is_burst);
         assert_eq!(burst_id, Some("GENERIC-BURST-ID-789".to_string()));
@@ -101,7 +176,7 @@ mod tests {
         let exif_data = ExifData::new(json!({})); // No burst tags
         let filename = "20150813_160421_burst01.jpg";
 
-        let (is_burst, burst_id) = find_burst_info(&exif_data, filename);
+        let (is_burst, burst_id) = find_burst_info(&exif_data, &DriveModeInfo::default(), filename);
 
         assert!(is_burst);
         assert_eq!(burst_id, Some("20150813_160421".to_string()));
@@ -113,7 +188,7 @@ mod tests {
         let exif_data = ExifData::new(json!({ "BurstUUID": "" }));
         let filename = "google_burst_abc.jpg";
 
-        let (is_burst, burst_id) = find_burst_info(&exif_data, filename);
+        let (is_burst, burst_id) = find_burst_info(&exif_data, &DriveModeInfo::default(), filename);
 
         assert!(is_burst);
         assert_eq!(burst_id, Some("google".to_string()));
@@ -125,12 +200,30 @@ mod tests {
         let exif_data = ExifData::new(json!({}));
         let filename = "a_regular_photo.jpg";
 
-        let (is_burst, burst_id) = find_burst_info(&exif_data, filename);
+        let (is_burst, burst_id) = find_burst_info(&exif_data, &DriveModeInfo::default(), filename);
 
         assert!(!is_burst);
         assert!(burst_id.is_none());
     }
 
+    #[test]
+    fn test_find_burst_from_continuous_drive_mode() {
+        let exif_data = ExifData::new(json!({
+            "SerialNumber": "4012345",
+            "DateTimeOriginal": "2024:05:06 07:08:09"
+        }));
+        let drive_mode = DriveModeInfo {
+            is_continuous: true,
+            is_exposure_bracket: false,
+            sequence_number: Some(2),
+        };
+
+        let (is_burst, burst_id) = find_burst_info(&exif_data, &drive_mode, "dsc01234.jpg");
+
+        assert!(is_burst);
+        assert_eq!(burst_id, Some("4012345_20240506070809".to_string()));
+    }
+
     // --- Unit tests for the helper `detect_burst_from_filename` function ---
 
     #[test]
@@ -0,0 +1,207 @@
+//! Drive mode (continuous shooting and exposure bracketing) from camera maker notes.
+//!
+//! Mirrorless and DSLR cameras don't write burst ids like phones do, but record the drive mode
+//! and the position of the shot within a sequence in their maker notes.
+
+use crate::ExifData;
+use serde_json::Value;
+
+/// Continuous-drive and bracketing information of a single shot.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DriveModeInfo {
+    pub is_continuous: bool,
+    pub is_exposure_bracket: bool,
+    /// Position of the shot in its continuous or bracketed sequence, when recorded.
+    pub sequence_number: Option<u32>,
+}
+
+/// Reads the first number of a value, numeric or a space separated list ("1 0 0" for Olympus).
+fn first_number(value: &Value) -> Option<u64> {
+    value.as_u64().or_else(|| {
+        value
+            .as_str()
+            .and_then(|s| s.split_whitespace().next())
+            .and_then(|s| s.parse().ok())
+    })
+}
+
+fn description_contains(exif: &ExifData, tag: &str, needle: &str) -> bool {
+    exif.get_str(tag)
+        .is_some_and(|s| s.to_lowercase().contains(needle))
+}
+
+fn read_u32(exif: &ExifData, tag: &str) -> Option<u32> {
+    exif.get_value(tag)
+        .and_then(first_number)
+        .and_then(|n| u32::try_from(n).ok())
+}
+
+/// Sony `ReleaseMode`: 2 = Continuous, 5 = Exposure Bracketing.
+fn sony(exif: &ExifData) -> (bool, bool) {
+    let release_mode = exif.get_value("ReleaseMode").and_then(first_number);
+    (release_mode == Some(2), release_mode == Some(5))
+}
+
+/// Canon `ContinuousDrive` (1, 3, 4, 5, 8, 10 are continuous variants) and `BracketMode`
+/// (1 = AEB) or a non-zero `AEBBracketValue`.
+fn canon(exif: &ExifData) -> (bool, bool) {
+    let continuous = exif
+        .get_value("ContinuousDrive")
+        .and_then(first_number)
+        .is_some_and(|mode| matches!(mode, 1 | 3 | 4 | 5 | 8 | 10));
+    let bracket = exif.get_value("BracketMode").and_then(first_number) == Some(1)
+        || exif.get_f64("AEBBracketValue").is_some_and(|v| v != 0.0);
+    (continuous, bracket)
+}
+
+/// Nikon `ShootingMode` is a bit mask: bit 0 = Continuous, bit 4 = Exposure Bracketing.
+fn nikon(exif: &ExifData) -> (bool, bool) {
+    exif.get_value("ShootingMode")
+        .and_then(first_number)
+        .map_or((false, false), |mode| (mode & 0x1 != 0, mode & 0x10 != 0))
+}
+
+/// Olympus `DriveMode` starts with the mode: 1 = Continuous, 2 = Exposure Bracketing.
+fn olympus(exif: &ExifData) -> (bool, bool) {
+    let drive_mode = exif.get_value("DriveMode").and_then(first_number);
+    (drive_mode == Some(1), drive_mode == Some(2))
+}
+
+/// Decodes the drive mode from the maker notes of Sony, Canon, Nikon and Olympus/OM cameras.
+/// Textual values (exiftool without `-n`) are recognized for any make.
+pub fn get_drive_mode(exif: &ExifData) -> DriveModeInfo {
+    let make = exif.get_str("Make").unwrap_or_default().to_lowercase();
+    let (mut is_continuous, mut is_exposure_bracket) = if make.contains("sony") {
+        sony(exif)
+    } else if make.contains("canon") {
+        canon(exif)
+    } else if make.contains("nikon") {
+        nikon(exif)
+    } else if make.contains("olympus") || make.contains("om digital") {
+        olympus(exif)
+    } else {
+        (false, false)
+    };
+
+    for tag in [
+        "DriveMode",
+        "ReleaseMode",
+        "ContinuousDrive",
+        "ShootingMode",
+    ] {
+        is_continuous |= description_contains(exif, tag, "continuous");
+        is_exposure_bracket |= description_contains(exif, tag, "exposure bracket")
+            || description_contains(exif, tag, "aeb");
+    }
+
+    let sequence_number =
+        read_u32(exif, "BracketShotNumber").or_else(|| read_u32(exif, "SequenceNumber"));
+    DriveModeInfo {
+        is_continuous,
+        is_exposure_bracket,
+        sequence_number,
+    }
+}
+
+/// Builds a burst id for a continuous-drive sequence: the camera serial and the capture time
+/// truncated to the second.
+pub fn synthesize_burst_id(exif: &ExifData) -> Option<String> {
+    let datetime = exif.get_str("DateTimeOriginal")?;
+    let second: String = datetime
+        .chars()
+        .filter(char::is_ascii_digit)
+        .take(14)
+        .collect();
+    if second.len() < 14 {
+        return None;
+    }
+    let serial = exif
+        .get_value("SerialNumber")
+        .and_then(|v| {
+            v.as_str()
+                .map(str::to_owned)
+                .or_else(|| v.as_u64().map(|n| n.to_string()))
+        })
+        .unwrap_or_default();
+    Some(format!("{}_{second}", serial.trim()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_sony_continuous_sequence() {
+        let exif = ExifData::new(json!({
+            "Make": "SONY",
+            "ReleaseMode": 2,
+            "SequenceNumber": 3,
+            "SerialNumber": "4012345",
+            "DateTimeOriginal": "2024:05:06 07:08:09"
+        }));
+        let drive_mode = get_drive_mode(&exif);
+        assert!(drive_mode.is_continuous);
+        assert!(!drive_mode.is_exposure_bracket);
+        assert_eq!(drive_mode.sequence_number, Some(3));
+        assert_eq!(
+            synthesize_burst_id(&exif),
+            Some("4012345_20240506070809".to_string())
+        );
+    }
+
+    #[test]
+    fn test_canon_aeb_triple() {
+        for shot in 1..=3 {
+            let exif = ExifData::new(json!({
+                "Make": "Canon",
+                "ContinuousDrive": 0,
+                "BracketMode": 1,
+                "AEBBracketValue": -1.0,
+                "BracketShotNumber": shot
+            }));
+            let drive_mode = get_drive_mode(&exif);
+            assert!(drive_mode.is_exposure_bracket);
+            assert!(!drive_mode.is_continuous);
+            assert_eq!(drive_mode.sequence_number, Some(shot));
+        }
+    }
+
+    #[test]
+    fn test_nikon_shooting_mode_bits() {
+        let exif = ExifData::new(json!({ "Make": "NIKON CORPORATION", "ShootingMode": 0x11 }));
+        let drive_mode = get_drive_mode(&exif);
+        assert!(drive_mode.is_continuous);
+        assert!(drive_mode.is_exposure_bracket);
+    }
+
+    #[test]
+    fn test_olympus_drive_mode_list() {
+        let exif = ExifData::new(json!({ "Make": "OLYMPUS CORPORATION", "DriveMode": "1 2 0 0" }));
+        assert!(get_drive_mode(&exif).is_continuous);
+    }
+
+    #[test]
+    fn test_textual_drive_mode() {
+        let exif = ExifData::new(json!({ "Make": "FUJIFILM", "DriveMode": "Continuous High" }));
+        assert!(get_drive_mode(&exif).is_continuous);
+    }
+
+    #[test]
+    fn test_single_shot() {
+        let exif = ExifData::new(json!({
+            "Make": "SONY",
+            "ReleaseMode": 0,
+            "SequenceNumber": 0,
+            "DateTimeOriginal": "2024:05:06 07:08:09"
+        }));
+        assert_eq!(
+            get_drive_mode(&exif),
+            DriveModeInfo {
+                is_continuous: false,
+                is_exposure_bracket: false,
+                sequence_number: Some(0),
+            }
+        );
+    }
+}
@@ -1,45 +1,246 @@
 use crate::ExifData;
+use serde::{Deserialize, Serialize};
 
-pub fn detect_hdr(exif: &ExifData) -> bool {
-    // 1. Pixel: CompositeImage == 3
-    if exif.get_i64("CompositeImage").is_some_and(|x| x == 3) {
-        return true;
+/// The kind of HDR image, from most to least reliable signal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum HdrType {
+    /// An Ultra HDR (or Adobe/ISO 21496) JPEG with a gain map, which has to be tone-mapped to
+    /// be displayed in HDR.
+    UltraHdrGainMap,
+    /// An iPhone Smart HDR capture, marked with `CompositeImage` or `SceneCaptureType`.
+    AppleSmartHdr,
+    /// An HDR composite from another camera, marked with `CompositeImage`, `SceneCaptureType`
+    /// or `HDRImageType`.
+    VendorComposite,
+    /// Only the `Software` tag mentions "hdr". This is unreliable: an app that removes HDR, like
+    /// "HDR Eraser", matches too.
+    SoftwareTagOnly,
+    /// A video with an HDR transfer function: HLG or PQ, usually with BT.2020 color primaries.
+    HdrVideo,
+}
+
+/// Which signals count towards `is_hdr`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum HdrStrictness {
+    /// Only gain maps and HDR composite tags count.
+    #[default]
+    Strict,
+    /// A `Software` tag that mentions "hdr" counts as well.
+    Lenient,
+}
+
+/// The rule that marked an image as HDR, with the evidence found.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum HdrEvidence {
+    CompositeImage,
+    SceneCaptureType,
+    HdrImageType(String),
+    Software(String),
+    GainMapImage,
+    GainMapDirectoryItem,
+    /// The transfer function, with the color primaries when they are BT.2020.
+    TransferCharacteristics {
+        transfer: VideoTransfer,
+        bt2020: bool,
+    },
+}
+
+/// An HDR transfer function of a video, from its `TransferCharacteristics` tag.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VideoTransfer {
+    /// Perceptual Quantizer (SMPTE ST 2084), used by HDR10 and Dolby Vision.
+    Pq,
+    /// Hybrid Log-Gamma (ARIB STD-B67), used by phones like iPhones and Pixels.
+    Hlg,
+}
+
+impl VideoTransfer {
+    /// Reads the numeric H.273 code `exiftool -n` writes, or the printed description.
+    fn from_value(value: &serde_json::Value) -> Option<Self> {
+        if let Some(code) = value.as_i64() {
+            return match code {
+                16 => Some(Self::Pq),
+                18 => Some(Self::Hlg),
+                _ => None,
+            };
+        }
+        let description = value.as_str()?.to_lowercase();
+        if description.contains("2084") || description.contains("pq") {
+            Some(Self::Pq)
+        } else if description.contains("b67") || description.contains("hlg") {
+            Some(Self::Hlg)
+        } else {
+            None
+        }
     }
 
-    // 2. SceneCaptureType == 3 (some DSLRs / iPhones)
-    if exif.get_i64("SceneCaptureType").is_some_and(|x| x == 3) {
-        return true;
+    const fn name(self) -> &'static str {
+        match self {
+            Self::Pq => "PQ",
+            Self::Hlg => "HLG",
+        }
     }
+}
 
-    // 3. Explicit HDR tag
-    if exif.get_value("HDRImageType").is_some() {
-        return true;
+impl HdrEvidence {
+    #[must_use]
+    pub fn explanation(&self) -> String {
+        match self {
+            Self::CompositeImage => {
+                "CompositeImage is 3 (composite image captured while shooting)".to_string()
+            }
+            Self::SceneCaptureType => "SceneCaptureType is 3 (HDR)".to_string(),
+            Self::HdrImageType(value) => format!("HDRImageType is present (value: '{value}')"),
+            Self::Software(value) => format!("Software contains 'hdr' (value: '{value}')"),
+            Self::GainMapImage => "GainMapImage is present".to_string(),
+            Self::GainMapDirectoryItem => "DirectoryItemSemantic contains 'GainMap'".to_string(),
+            Self::TransferCharacteristics { transfer, bt2020 } => format!(
+                "TransferCharacteristics is {}{}",
+                transfer.name(),
+                if *bt2020 {
+                    " with BT.2020 ColorPrimaries"
+                } else {
+                    ""
+                }
+            ),
+        }
     }
+}
+
+/// The HDR classification of an image, with the evidence for it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct HdrDetection {
+    pub hdr_type: HdrType,
+    pub evidence: HdrEvidence,
+}
 
-    // 4. Software string contains "hdr"
-    if exif
-        .get_str("Software")
-        .is_some_and(|s| s.to_lowercase().contains("hdr"))
-    {
-        return true;
+impl HdrDetection {
+    /// Whether this detection makes the image HDR at the given strictness.
+    #[must_use]
+    pub fn is_hdr(&self, strictness: HdrStrictness) -> bool {
+        self.hdr_type != HdrType::SoftwareTagOnly || strictness == HdrStrictness::Lenient
     }
+}
 
-    // 5. XMP / gain map detection
-    if exif.get_value("GainMapImage").is_some()
-        || exif
-            .get_value("DirectoryItemSemantic")
-            .and_then(|x| x.as_array())
-            .is_some_and(|arr| {
-                arr.iter().any(|s| {
-                    s.as_str()
-                        .is_some_and(|s| s.eq_ignore_ascii_case("GainMap"))
-                })
+/// A gain map, either as an embedded `GainMapImage` or as a Google container directory item.
+pub fn gain_map_evidence(exif: &ExifData) -> Option<HdrEvidence> {
+    if exif.get_value("GainMapImage").is_some() {
+        return Some(HdrEvidence::GainMapImage);
+    }
+    exif.get_value("DirectoryItemSemantic")
+        .and_then(|x| x.as_array())
+        .is_some_and(|arr| {
+            arr.iter().any(|s| {
+                s.as_str()
+                    .is_some_and(|s| s.eq_ignore_ascii_case("GainMap"))
             })
-    {
-        return true;
+        })
+        .then_some(HdrEvidence::GainMapDirectoryItem)
+}
+
+/// Tags that mark an HDR composite: `CompositeImage` 3 (Pixel, iPhone), `SceneCaptureType` 3
+/// (some DSLRs, iPhone) or an `HDRImageType` tag (Sony, Pentax).
+fn composite_evidence(exif: &ExifData) -> Option<HdrEvidence> {
+    if exif.get_i64("CompositeImage").is_some_and(|x| x == 3) {
+        return Some(HdrEvidence::CompositeImage);
     }
+    if exif.get_i64("SceneCaptureType").is_some_and(|x| x == 3) {
+        return Some(HdrEvidence::SceneCaptureType);
+    }
+    exif.get_value("HDRImageType").map(|value| {
+        HdrEvidence::HdrImageType(
+            value
+                .as_str()
+                .map_or_else(|| value.to_string(), str::to_owned),
+        )
+    })
+}
+
+fn is_apple(exif: &ExifData) -> bool {
+    exif.get_str("Make")
+        .is_some_and(|make| make.trim().eq_ignore_ascii_case("apple"))
+}
+
+/// An iPhone Smart HDR composite.
+pub fn apple_smart_hdr_evidence(exif: &ExifData) -> Option<HdrEvidence> {
+    composite_evidence(exif).filter(|_| is_apple(exif))
+}
 
-    false
+/// An HDR composite from any camera other than an iPhone.
+pub fn vendor_composite_evidence(exif: &ExifData) -> Option<HdrEvidence> {
+    composite_evidence(exif).filter(|_| !is_apple(exif))
+}
+
+/// The `Software` tag mentions "hdr".
+pub fn software_hdr_evidence(exif: &ExifData) -> Option<HdrEvidence> {
+    exif.get_str("Software")
+        .filter(|software| software.to_lowercase().contains("hdr"))
+        .map(|software| HdrEvidence::Software(software.to_string()))
+}
+
+/// Whether `ColorPrimaries` is BT.2020, as H.273 code 9 or its printed description.
+fn has_bt2020_primaries(exif: &ExifData) -> bool {
+    exif.get_value("ColorPrimaries").is_some_and(|value| {
+        value.as_i64() == Some(9)
+            || value
+                .as_str()
+                .is_some_and(|description| description.contains("2020"))
+    })
+}
+
+/// An HLG or PQ transfer function. BT.2020 primaries alone don't count: with an SDR transfer
+/// function they are a wide gamut SDR video.
+pub fn hdr_video_evidence(exif: &ExifData) -> Option<HdrEvidence> {
+    let transfer = exif
+        .get_value("TransferCharacteristics")
+        .and_then(VideoTransfer::from_value)?;
+    Some(HdrEvidence::TransferCharacteristics {
+        transfer,
+        bt2020: has_bt2020_primaries(exif),
+    })
+}
+
+type HdrSignal = fn(&ExifData) -> Option<HdrEvidence>;
+
+/// Classifies the image by its most reliable HDR signal. A `SoftwareTagOnly` detection is
+/// returned regardless of strictness; use [`HdrDetection::is_hdr`] to decide if it counts.
+///
+/// Videos are only classified by their transfer function, as the photo tags don't describe
+/// them: a `Software` tag naming an HDR-capable app says nothing about the video's format.
+///
+/// ```
+/// use media_analyzer::detectors::{HdrEvidence, detect_hdr_type};
+/// use media_analyzer::{ExifData, HdrStrictness, HdrType};
+/// use serde_json::json;
+///
+/// let exif = ExifData::new(json!({ "Image": { "Software": "HDR+ 1.0.345" } }));
+/// let detection = detect_hdr_type(&exif).unwrap();
+/// assert_eq!(detection.hdr_type, HdrType::SoftwareTagOnly);
+/// assert_eq!(detection.evidence, HdrEvidence::Software("HDR+ 1.0.345".to_string()));
+/// assert!(!detection.is_hdr(HdrStrictness::Strict));
+/// assert!(detection.is_hdr(HdrStrictness::Lenient));
+/// ```
+#[must_use]
+pub fn detect_hdr_type(exif: &ExifData) -> Option<HdrDetection> {
+    if exif.is_video() {
+        return hdr_video_evidence(exif).map(|evidence| HdrDetection {
+            hdr_type: HdrType::HdrVideo,
+            evidence,
+        });
+    }
+    let signals: [(HdrType, HdrSignal); 4] = [
+        (HdrType::UltraHdrGainMap, gain_map_evidence),
+        (HdrType::AppleSmartHdr, apple_smart_hdr_evidence),
+        (HdrType::VendorComposite, vendor_composite_evidence),
+        (HdrType::SoftwareTagOnly, software_hdr_evidence),
+    ];
+    signals.into_iter().find_map(|(hdr_type, signal)| {
+        signal(exif).map(|evidence| HdrDetection { hdr_type, evidence })
+    })
 }
 
 #[cfg(test)]
@@ -48,6 +249,14 @@ mod tests {
     use crate::ExifData;
     use serde_json::json;
 
+    fn detect_hdr(exif: &ExifData) -> bool {
+        detect_hdr_type(exif).is_some_and(|d| d.is_hdr(HdrStrictness::Lenient))
+    }
+
+    fn hdr_type(exif: &serde_json::Value) -> Option<HdrType> {
+        detect_hdr_type(&ExifData::new(exif.clone())).map(|d| d.hdr_type)
+    }
+
     #[test]
     fn test_detects_hdr_from_composite_image() {
         let exif = ExifData::new(json!({ "CompositeImage": 3 }));
@@ -151,6 +360,15 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_hdr_evidence_explanation() {
+        let exif = ExifData::new(json!({ "Software": "HDR+ 1.0.345" }));
+        assert_eq!(
+            detect_hdr_type(&exif).unwrap().evidence.explanation(),
+            "Software contains 'hdr' (value: 'HDR+ 1.0.345')"
+        );
+    }
+
     #[test]
     fn test_returns_false_for_standard_image_exif() {
         let exif = ExifData::new(json!({
@@ -172,4 +390,89 @@ mod tests {
             "Should return false for an empty EXIF object"
         );
     }
+
+    #[test]
+    fn test_hdr_type_per_signal() {
+        assert_eq!(
+            hdr_type(&json!({ "DirectoryItemSemantic": ["Primary", "GainMap"] })),
+            Some(HdrType::UltraHdrGainMap)
+        );
+        assert_eq!(
+            hdr_type(&json!({ "Make": "Apple", "CompositeImage": 3 })),
+            Some(HdrType::AppleSmartHdr)
+        );
+        assert_eq!(
+            hdr_type(&json!({ "Make": "SONY", "HDRImageType": 1 })),
+            Some(HdrType::VendorComposite)
+        );
+        assert_eq!(
+            hdr_type(&json!({ "Software": "HDR Eraser 2.1" })),
+            Some(HdrType::SoftwareTagOnly)
+        );
+        assert_eq!(hdr_type(&json!({ "Software": "Adobe Photoshop" })), None);
+    }
+
+    #[test]
+    fn test_gain_map_wins_over_composite() {
+        // A Pixel Ultra HDR photo is also an HDR+ composite, but it has to be tone-mapped.
+        let detection = detect_hdr_type(&ExifData::new(json!({
+            "Make": "Google",
+            "CompositeImage": 3,
+            "DirectoryItemSemantic": ["Primary", "GainMap"]
+        })))
+        .unwrap();
+        assert_eq!(detection.hdr_type, HdrType::UltraHdrGainMap);
+        assert_eq!(detection.evidence, HdrEvidence::GainMapDirectoryItem);
+    }
+
+    #[test]
+    fn test_software_tag_only_counts_when_lenient() {
+        let detection =
+            detect_hdr_type(&ExifData::new(json!({ "Software": "HDR Eraser 2.1" }))).unwrap();
+        assert!(!detection.is_hdr(HdrStrictness::Strict));
+        assert!(detection.is_hdr(HdrStrictness::Lenient));
+        assert!(!detection.is_hdr(HdrStrictness::default()));
+
+        let composite = detect_hdr_type(&ExifData::new(json!({ "SceneCaptureType": 3 }))).unwrap();
+        assert!(composite.is_hdr(HdrStrictness::Strict));
+    }
+
+    #[test]
+    fn test_hdr_video_transfer_functions() {
+        let video = |transfer: serde_json::Value, primaries: serde_json::Value| {
+            detect_hdr_type(&ExifData::new(json!({
+                "MIMEType": "video/quicktime",
+                "TransferCharacteristics": transfer,
+                "ColorPrimaries": primaries
+            })))
+        };
+
+        let pq = video(json!(16), json!(9)).unwrap();
+        assert_eq!(pq.hdr_type, HdrType::HdrVideo);
+        assert_eq!(
+            pq.evidence,
+            HdrEvidence::TransferCharacteristics {
+                transfer: VideoTransfer::Pq,
+                bt2020: true
+            }
+        );
+        assert!(pq.is_hdr(HdrStrictness::Strict));
+
+        // Without `-n`, `exiftool` prints the description.
+        let hlg = video(json!("ARIB STD-B67 (HLG)"), json!("BT.2020, BT.2100")).unwrap();
+        assert_eq!(
+            hlg.evidence.explanation(),
+            "TransferCharacteristics is HLG with BT.2020 ColorPrimaries"
+        );
+
+        // Wide gamut SDR, and the photo tags, don't make a video HDR.
+        assert_eq!(video(json!(1), json!(9)), None);
+        assert_eq!(
+            detect_hdr_type(&ExifData::new(json!({
+                "MIMEType": "video/mp4",
+                "CompositeImage": 3
+            }))),
+            None
+        );
+    }
 }
@@ -1,81 +1,242 @@
 use crate::ExifData;
-use crate::tags::burst::find_burst_info;
+use crate::features::filename::{FilenameCandidates, FilenameKind};
+use crate::features::metadata::display_dimensions;
+use crate::features::pano::{spherical_video_evidence, stereo_pano_evidence};
+use crate::features::user_metadata::decode_user_comment;
+use crate::tags::animation::animation_evidence;
+use crate::tags::aspect::AspectClass;
+use crate::tags::burst::{BurstEvidence, burst_evidence};
+use crate::tags::drive_mode::get_drive_mode;
 use crate::tags::fps::get_fps;
-use crate::tags::hdr::detect_hdr;
-use crate::tags::motion::detect_motion_photo;
-use crate::tags::structs::MediaFeatures;
+use crate::tags::hdr::detect_hdr_type;
+use crate::tags::motion::{MotionPhotoEvidence, motion_photo_evidence, motion_photo_version};
+use crate::tags::night_mode::{NightModeEvidence, night_mode_evidence};
+use crate::tags::structs::{FeatureOptions, MediaFeatures};
+use crate::units::Microseconds;
+use std::collections::BTreeMap;
 use std::path::Path;
 
-/// Extracts tags from a file's path and its EXIF metadata.
-pub fn extract_features(path: &Path, exif: &ExifData) -> MediaFeatures {
-    let filename_lower = path
-        .file_name()
-        .unwrap_or_default()
-        .to_string_lossy()
-        .to_lowercase();
+/// The revision of the feature detection logic. Bump it when a change can alter the detected
+/// features of a file, so stored results can be found stale, see
+/// [`StalenessPolicy`](crate::StalenessPolicy).
+pub const TAGS_LOGIC_REVISION: u32 = 6;
+
+const TIMELAPSE_MAX_FPS: f64 = 10.0;
+
+/// Decides whether the video is a timelapse, explaining the rule that fired.
+/// The first tag that is present decides: `UserComment`, `Description`, `SpecialTypeID`, and
+/// finally a low frame rate.
+fn timelapse_evidence(exif: &ExifData, video_fps: Option<f64>) -> Option<String> {
+    for tag in ["UserComment", "Description"] {
+        if let Some(text) = exif.get_value(tag).and_then(decode_user_comment) {
+            let lower = text.to_lowercase();
+            return ["time-lapse", "hyperlapse"]
+                .into_iter()
+                .find(|needle| lower.contains(needle))
+                .map(|needle| format!("{tag} contains '{needle}' (value: '{text}')"));
+        }
+    }
+    if let Some(special_type) = exif.get_str("SpecialTypeID") {
+        return special_type
+            .to_lowercase()
+            .contains("timelapse")
+            .then(|| format!("SpecialTypeID contains 'timelapse' (value: '{special_type}')"));
+    }
+    video_fps
+        .filter(|v_fps| *v_fps < TIMELAPSE_MAX_FPS)
+        .map(|v_fps| format!("video_fps {v_fps:.1} < {TIMELAPSE_MAX_FPS:.1} fallback"))
+}
 
-    // --- Multi-layered Burst Detection ---
-    let (is_burst, burst_id) = find_burst_info(exif, &filename_lower);
+/// How many times slower than real time a video plays.
+fn speed_factor(capture_fps: Option<f64>, video_fps: Option<f64>) -> Option<f64> {
+    match (capture_fps, video_fps) {
+        (Some(c_fps), Some(v_fps)) if v_fps > 0.0 => Some(c_fps / v_fps),
+        _ => None,
+    }
+}
 
-    // --- Other Tags from Filename ---
-    let is_night_sight = filename_lower.contains("night");
+fn slowmotion_evidence(
+    capture_fps: Option<f64>,
+    video_fps: Option<f64>,
+    ratio_threshold: f64,
+) -> Option<String> {
+    match (capture_fps, video_fps, speed_factor(capture_fps, video_fps)) {
+        (Some(c_fps), Some(v_fps), Some(ratio)) if ratio > ratio_threshold => Some(format!(
+            "capture_fps {c_fps:.1} / video_fps {v_fps:.1} = {ratio:.2} > {ratio_threshold}"
+        )),
+        _ => None,
+    }
+}
 
-    // --- Tags from EXIF / Carving / Companion ---
-    let is_motion_photo = detect_motion_photo(path, exif);
-    let motion_photo_presentation_timestamp = exif.get_i64("MotionPhotoPresentationTimestampUs");
+/// Extracts tags from a file's path and its EXIF metadata.
+///
+/// With `options.explain`, the rule that set each flag and its evidence are recorded in
+/// `explanations`, keyed by the camelCase field name. See [`FeatureOptions`] for the other
+/// settings. `is_astrophotography` and the sunrise, sunset and night shot flags need the
+/// position of the sun, so they are left `None` here.
+///
+/// ```
+/// use media_analyzer::detectors::extract_features;
+/// use media_analyzer::{ExifData, FeatureOptions};
+/// use serde_json::json;
+/// use std::path::Path;
+///
+/// let exif = ExifData::new(json!({
+///     "Image": { "ImageWidth": 1080, "ImageHeight": 1920, "Rotation": 0 },
+///     "Video": { "VideoFrameRate": 30.0, "AndroidCaptureFPS": 240.0 },
+///     "Other": { "MIMEType": "video/mp4" }
+/// }));
+/// let options = FeatureOptions {
+///     explain: true,
+///     ..FeatureOptions::default()
+/// };
+/// let features = extract_features(Path::new("PXL_20240601_120000000.mp4"), &exif, &options);
+/// assert!(features.is_video && features.is_slowmotion && features.is_vertical_video);
+/// assert_eq!(features.speed_factor, Some(8.0));
+/// assert!(features.explanations.unwrap().contains_key("isSlowmotion"));
+/// ```
+pub fn extract_features(path: &Path, exif: &ExifData, options: &FeatureOptions) -> MediaFeatures {
+    let filenames = FilenameCandidates::new(path, exif);
+    let mut explanations = BTreeMap::new();
+    let mut record = |key: &str, explanation: Option<String>| {
+        if let Some(explanation) = explanation {
+            explanations.insert(key.to_string(), explanation);
+        }
+    };
+
+    // --- Multi-layered Burst Detection ---
+    let drive_mode = get_drive_mode(exif);
+    let burst = burst_evidence(exif, &drive_mode, &filenames);
+    let is_burst = burst.is_some();
+    let burst_id = burst.as_ref().and_then(BurstEvidence::burst_id);
+    record("isBurst", burst.as_ref().map(BurstEvidence::explanation));
 
     // --- Video Detection ---
     let is_video = exif.is_video();
 
-    let is_hdr = detect_hdr(exif);
+    // --- Night Mode ---
+    // Night modes are photo modes, a "night" in the name of a video is just its subject.
+    let night_mode = (!is_video)
+        .then(|| night_mode_evidence(exif, &filenames, options.night_mode_from_filename))
+        .flatten();
+    let is_night_mode = night_mode.is_some();
+    record(
+        "isNightMode",
+        night_mode.as_ref().map(NightModeEvidence::explanation),
+    );
+    let matched_original_filename = night_mode
+        .as_ref()
+        .and_then(NightModeEvidence::filename_kind)
+        == Some(FilenameKind::Original)
+        || matches!(
+            burst,
+            Some(BurstEvidence::Filename {
+                kind: FilenameKind::Original,
+                ..
+            })
+        );
 
-    // --- Video Metadata ---
-    let (video_fps, capture_fps) = get_fps(exif);
+    // --- Tags from EXIF / Carving / Companion ---
+    let motion_photo = motion_photo_evidence(path, exif);
+    let is_motion_photo = motion_photo.is_some();
+    record(
+        "isMotionPhoto",
+        motion_photo.as_ref().map(MotionPhotoEvidence::explanation),
+    );
+    let motion_photo_version = motion_photo_version(exif).filter(|_| is_motion_photo);
+    let motion_photo_presentation_timestamp = exif
+        .get_i64("MotionPhotoPresentationTimestampUs")
+        .map(Microseconds);
+
+    let hdr = detect_hdr_type(exif);
+    let hdr_type = hdr.as_ref().map(|detection| detection.hdr_type);
+    let hdr = hdr.filter(|detection| detection.is_hdr(options.hdr_strictness));
+    let is_hdr = hdr.is_some();
+    record(
+        "isHdr",
+        hdr.as_ref()
+            .map(|detection| detection.evidence.explanation()),
+    );
 
-    // --- Slow Motion and Time-lapse ---
-    let is_slowmotion = match (capture_fps, video_fps) {
-        (Some(c_fps), Some(v_fps)) if v_fps > 0.0 => (c_fps / v_fps) > 1.05,
-        _ => false,
+    let animation = animation_evidence(exif);
+    let is_animated = animation.is_some();
+    record("isAnimated", animation);
+
+    // --- Video Metadata ---
+    // The frame rate tags of animations describe their playback, not a capture.
+    let (video_fps, capture_fps) = if is_animated {
+        (None, None)
+    } else {
+        get_fps(exif)
     };
 
-    let is_timelapse = exif.get_str("UserComment").map_or_else(
-        || {
-            exif.get_str("Description").map_or_else(
-                || {
-                    exif.get_str("SpecialTypeID").map_or(
-                        matches!(video_fps, Some(v_fps) if v_fps < 10.0),
-                        |special_type| special_type.to_lowercase().contains("timelapse"),
-                    )
-                },
-                |description| {
-                    let desc = description.to_lowercase();
-                    desc.contains("time-lapse") || desc.contains("hyperlapse")
-                },
-            )
-        },
-        |user_comment| {
-            let comment = user_comment.to_lowercase();
-            comment.contains("time-lapse") || comment.contains("hyperlapse")
-        },
-    );
+    // --- Slow Motion and Time-lapse ---
+    let speed_factor = speed_factor(capture_fps, video_fps);
+    let slowmotion =
+        slowmotion_evidence(capture_fps, video_fps, options.slowmotion_ratio_threshold);
+    let is_slowmotion = slowmotion.is_some();
+    record("isSlowmotion", slowmotion);
+
+    let timelapse = (!is_animated)
+        .then(|| timelapse_evidence(exif, video_fps))
+        .flatten();
+    let is_timelapse = timelapse.is_some();
+    record("isTimelapse", timelapse);
+
+    // --- Aspect Ratio ---
+    let display_size = exif
+        .get_u64("ImageWidth")
+        .zip(exif.get_u64("ImageHeight"))
+        .map(|(width, height)| display_dimensions(width, height, exif));
+    let aspect_class =
+        display_size.and_then(|(width, height)| AspectClass::classify(width, height));
+    let is_vertical_video = is_video && display_size.is_some_and(|(width, height)| height > width);
+
+    let spherical_video = is_video.then(|| spherical_video_evidence(exif)).flatten();
+    let is_spherical_video = spherical_video.is_some();
+    record("isSphericalVideo", spherical_video);
+    let stereo_pano = (!is_video).then(|| stereo_pano_evidence(exif)).flatten();
+    let is_stereo_pano = stereo_pano.is_some();
+    record("isStereoPano", stereo_pano);
 
     // --- Construct and return the final struct ---
     MediaFeatures {
         is_motion_photo,
+        motion_photo_version,
         motion_photo_presentation_timestamp,
-        is_night_sight,
+        is_night_mode,
+        // Set after the weather stage, which knows the position of the sun.
+        is_astrophotography: None,
+        is_sunrise_shot: None,
+        is_sunset_shot: None,
+        is_night_shot: None,
+        matched_original_filename,
         is_hdr,
+        hdr_type,
         is_burst,
         burst_id,
+        is_exposure_bracket: drive_mode.is_exposure_bracket,
+        bracket_sequence: drive_mode
+            .sequence_number
+            .filter(|_| drive_mode.is_exposure_bracket),
         is_timelapse,
         is_slowmotion,
         is_video,
+        is_spherical_video,
+        is_stereo_pano,
+        is_animated,
+        aspect_class,
+        is_vertical_video,
         capture_fps,
+        speed_factor,
         video_fps,
         compressor_id: exif.get_string("CompressorID"),
         audio_format: exif.get_string("AudioFormat"),
         audio_channels: exif.get_u64("AudioChannels"),
         audio_sample_rate: exif.get_u64("AudioSampleRate"),
+        // Set by the analyzer, which holds the compiled patterns.
+        filename_tags: BTreeMap::new(),
+        explanations: options.explain.then_some(explanations),
     }
 }
 
@@ -84,12 +245,23 @@ mod tests {
     use super::*;
     use crate::ExifData;
     use crate::MediaAnalyzerError;
+    use crate::tags::hdr::HdrType;
+    use crate::tags::motion::MotionPhotoVersion;
+    use crate::tags::structs::DEFAULT_SLOWMOTION_RATIO_THRESHOLD;
+    use crate::units::Seconds;
     use exiftool::ExifTool;
     use std::path::Path;
 
     /// Helper function to reduce boilerplate in tests.
     /// It takes a relative path to an asset, runs exiftool, and returns the extracted tags.
     fn get_tags_for_asset(relative_path: &str) -> Result<MediaFeatures, MediaAnalyzerError> {
+        get_tags_for_asset_with(relative_path, false)
+    }
+
+    fn get_tags_for_asset_with(
+        relative_path: &str,
+        explain: bool,
+    ) -> Result<MediaFeatures, MediaAnalyzerError> {
         let path = Path::new(env!("CARGO_MANIFEST_DIR"))
             .join("assets")
             .join(relative_path);
@@ -99,7 +271,21 @@ mod tests {
         let et = ExifTool::new()?;
         let exif_data = ExifData::new(et.json(&path, &["-n", "-g2"])?);
 
-        Ok(extract_features(&path, &exif_data))
+        Ok(extract_features(
+            &path,
+            &exif_data,
+            &FeatureOptions {
+                explain,
+                ..FeatureOptions::default()
+            },
+        ))
+    }
+
+    fn explained() -> FeatureOptions {
+        FeatureOptions {
+            explain: true,
+            ..FeatureOptions::default()
+        }
     }
 
     #[test]
@@ -107,8 +293,8 @@ mod tests {
         let tags = get_tags_for_asset("night_sight/PXL_20250104_170020532.NIGHT.jpg").unwrap();
 
         assert!(
-            tags.is_night_sight,
-            "Should be detected as Night Sight from filename"
+            tags.is_night_mode,
+            "Should be detected as Night Sight from its filename and HDR+ maker note"
         );
 
         // Ensure other boolean tags are false
@@ -124,6 +310,10 @@ mod tests {
         let tags = get_tags_for_asset("motion/PXL_20250103_180944831.MP.jpg").unwrap();
 
         assert!(tags.is_motion_photo, "Should be detected as a Motion Photo");
+        assert_eq!(
+            tags.motion_photo_version,
+            Some(MotionPhotoVersion::V2MotionPhoto)
+        );
         assert!(
             !tags.is_video,
             "Motion photos are not considered primary videos"
@@ -134,6 +324,7 @@ mod tests {
     fn test_hdr_photo() {
         let tags = get_tags_for_asset("hdr.jpg").unwrap();
         assert!(tags.is_hdr, "Should be detected as HDR from EXIF tag");
+        assert_eq!(tags.hdr_type, Some(HdrType::UltraHdrGainMap));
     }
 
     #[test]
@@ -169,6 +360,8 @@ mod tests {
             (capture_fps / video_fps) > 1.05,
             "Slow motion ratio should be > 1.05"
         );
+        let speed_factor = tags.speed_factor.expect("Should have a speed factor");
+        assert!((speed_factor - capture_fps / video_fps).abs() < 1e-9);
     }
 
     #[test]
@@ -194,7 +387,34 @@ mod tests {
         assert!(!tags.is_timelapse);
         assert!(!tags.is_motion_photo);
         assert!(!tags.is_burst);
-        assert!(!tags.is_night_sight);
+        assert!(!tags.is_night_mode);
+        assert!(!tags.is_hdr);
+        assert!(!tags.is_spherical_video);
+    }
+
+    #[test]
+    fn test_standard_video_is_unchanged_by_photo_options() {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("assets/video/car.webm");
+        let exif = ExifData::new(
+            ExifTool::new()
+                .unwrap()
+                .json(&path, &["-n", "-g2"])
+                .unwrap(),
+        );
+        let photo_options = FeatureOptions {
+            night_mode_from_filename: true,
+            hdr_strictness: crate::HdrStrictness::Lenient,
+            ..FeatureOptions::default()
+        };
+
+        let tags = extract_features(&path, &exif, &photo_options);
+        assert_eq!(
+            tags,
+            extract_features(&path, &exif, &FeatureOptions::default())
+        );
+        assert_eq!(tags.hdr_type, None);
+        assert!(!tags.is_night_mode);
+        assert!(!tags.is_spherical_video);
     }
 
     #[test]
@@ -204,7 +424,7 @@ mod tests {
         // Assert all boolean flags are correctly false for a standard image
         assert!(!tags.is_video);
         assert!(!tags.is_burst);
-        assert!(!tags.is_night_sight);
+        assert!(!tags.is_night_mode);
         assert!(!tags.is_motion_photo);
         assert!(!tags.is_slowmotion);
         assert!(!tags.is_timelapse);
@@ -223,7 +443,7 @@ mod tests {
         assert!(!tags.is_burst);
         assert!(!tags.is_hdr);
         assert!(!tags.is_motion_photo);
-        assert!(!tags.is_night_sight);
+        assert!(!tags.is_night_mode);
         assert!(!tags.is_slowmotion);
         assert!(!tags.is_timelapse);
 
@@ -232,4 +452,264 @@ mod tests {
         assert!(tags.capture_fps.is_none());
         assert!(tags.video_fps.is_none());
     }
+
+    #[test]
+    fn test_explanations_are_off_by_default() {
+        let tags = get_tags_for_asset("hdr.jpg").unwrap();
+        assert!(tags.explanations.is_none());
+    }
+
+    #[test]
+    fn test_explain_hdr_photo() {
+        let tags = get_tags_for_asset_with("hdr.jpg", true).unwrap();
+        let explanations = tags.explanations.unwrap();
+        assert_eq!(
+            explanations.get("isHdr").map(String::as_str),
+            Some("DirectoryItemSemantic contains 'GainMap'")
+        );
+        assert!(!explanations.contains_key("isBurst"));
+    }
+
+    #[test]
+    fn test_explain_timelapse_video() {
+        let tags = get_tags_for_asset_with("timelapse.mp4", true).unwrap();
+        let explanation = &tags.explanations.unwrap()["isTimelapse"];
+        assert!(
+            explanation.starts_with("SpecialTypeID contains 'timelapse'"),
+            "unexpected explanation: {explanation}"
+        );
+    }
+
+    #[test]
+    fn test_timelapse_evidence_order() {
+        let exif = ExifData::new(serde_json::json!({
+            "Description": "Hyperlapse by phone",
+            "SpecialTypeID": "SpecialType-TIMELAPSE"
+        }));
+        assert_eq!(
+            timelapse_evidence(&exif, Some(30.0)).as_deref(),
+            Some("Description contains 'hyperlapse' (value: 'Hyperlapse by phone')")
+        );
+
+        // A comment that doesn't mention a timelapse decides before the frame rate fallback.
+        let exif = ExifData::new(serde_json::json!({ "UserComment": "Holiday" }));
+        assert!(timelapse_evidence(&exif, Some(5.0)).is_none());
+
+        // The character code is stripped from the comment.
+        let exif = ExifData::new(serde_json::json!({ "UserComment": "UNICODE Time-lapse" }));
+        assert_eq!(
+            timelapse_evidence(&exif, None).as_deref(),
+            Some("UserComment contains 'time-lapse' (value: 'Time-lapse')")
+        );
+
+        let exif = ExifData::new(serde_json::json!({}));
+        assert_eq!(
+            timelapse_evidence(&exif, Some(5.0)).as_deref(),
+            Some("video_fps 5.0 < 10.0 fallback")
+        );
+    }
+
+    #[test]
+    fn test_slowmotion_evidence() {
+        let threshold = DEFAULT_SLOWMOTION_RATIO_THRESHOLD;
+        assert_eq!(
+            slowmotion_evidence(Some(240.0), Some(30.0), threshold).as_deref(),
+            Some("capture_fps 240.0 / video_fps 30.0 = 8.00 > 1.05")
+        );
+        assert!(slowmotion_evidence(Some(30.0), Some(30.0), threshold).is_none());
+        // A lightly conformed clip only counts with a lowered threshold.
+        assert!(slowmotion_evidence(Some(31.2), Some(30.0), threshold).is_none());
+        assert_eq!(
+            slowmotion_evidence(Some(31.2), Some(30.0), 1.02).as_deref(),
+            Some("capture_fps 31.2 / video_fps 30.0 = 1.04 > 1.02")
+        );
+    }
+
+    #[test]
+    fn test_speed_factor_and_slowmotion_threshold() {
+        let exif = ExifData::new(serde_json::json!({
+            "Other": { "MIMEType": "video/mp4" },
+            "Video": { "VideoFrameRate": 30.0, "AndroidCaptureFPS": 31.2 }
+        }));
+        let path = Path::new("VID_0001.mp4");
+        let tags = extract_features(path, &exif, &FeatureOptions::default());
+        assert!(!tags.is_slowmotion);
+        assert!((tags.speed_factor.unwrap() - 1.04).abs() < 1e-9);
+
+        let lowered = FeatureOptions {
+            slowmotion_ratio_threshold: 1.02,
+            ..FeatureOptions::default()
+        };
+        assert!(extract_features(path, &exif, &lowered).is_slowmotion);
+
+        let tags = extract_features(path, &ExifData::new(serde_json::json!({})), &lowered);
+        assert_eq!(tags.speed_factor, None);
+    }
+
+    fn image_sequence_exif(mime_type: &str) -> ExifData {
+        // Trimmed `exiftool -n -g2` output of an iPhone HEIC burst sequence / animated AVIF.
+        ExifData::new(serde_json::json!({
+            "Other": {
+                "FileName": "IMG_0042.heics",
+                "FileSize": 1_843_200,
+                "MIMEType": mime_type,
+                "MajorBrand": "msf1"
+            },
+            "Image": { "ImageWidth": 1440, "ImageHeight": 1080 },
+            "Video": { "FrameRate": 3.0, "Duration": 2.4, "CompressorID": "hvc1" },
+            "Time": { "CreateDate": "2024:05:01 12:00:00" }
+        }))
+    }
+
+    #[test]
+    fn test_image_sequences_are_animations() {
+        for mime_type in ["image/heic-sequence", "image/avif-sequence"] {
+            let exif = image_sequence_exif(mime_type);
+            let tags = extract_features(Path::new("IMG_0042.heics"), &exif, &explained());
+
+            assert!(tags.is_animated, "{mime_type} should be animated");
+            assert!(!tags.is_video);
+            // A 3 fps FrameRate must not trigger the low frame rate timelapse fallback.
+            assert!(!tags.is_timelapse);
+            assert!(!tags.is_slowmotion);
+            assert!(tags.video_fps.is_none());
+            assert_eq!(
+                tags.explanations.unwrap()["isAnimated"],
+                format!("MIMEType is {mime_type}")
+            );
+
+            let (basic, _) = crate::features::metadata::get_metadata(&exif).unwrap();
+            assert_eq!(basic.duration, Some(Seconds(2.4)));
+        }
+    }
+
+    #[test]
+    fn test_renamed_burst_uses_preserved_filename() {
+        let exif = ExifData::new(serde_json::json!({
+            "Other": { "FileName": "IMG_0001 (1).jpg", "MIMEType": "image/jpeg" },
+            "Author": { "PreservedFileName": "20150813_160421_BURST01.jpg" }
+        }));
+        let tags = extract_features(Path::new("IMG_0001 (1).jpg"), &exif, &explained());
+
+        assert!(tags.is_burst, "The original filename marks a burst");
+        assert_eq!(tags.burst_id.as_deref(), Some("20150813_160421"));
+        assert!(tags.matched_original_filename);
+        assert_eq!(
+            tags.explanations.unwrap()["isBurst"],
+            "original filename contains '_burst' (burst id: '20150813_160421')"
+        );
+    }
+
+    #[test]
+    fn test_motion_photo_flag_as_string() {
+        let exif = ExifData::new(serde_json::json!({
+            "XMP": { "MotionPhoto": "1" },
+            "Other": { "MIMEType": "image/jpeg" }
+        }))
+        .with_parse_diagnostics();
+        let tags = extract_features(Path::new("does_not_exist.jpg"), &exif, &explained());
+        assert!(tags.is_motion_photo);
+        assert_eq!(
+            tags.explanations.unwrap()["isMotionPhoto"],
+            "MotionPhoto is 1"
+        );
+        assert_eq!(exif.take_parse_diagnostics(), Some(Vec::new()));
+    }
+
+    #[test]
+    fn test_aspect_class_uses_displayed_dimensions() {
+        let tags_for = |exif: serde_json::Value| {
+            extract_features(
+                Path::new("VID_0001.mp4"),
+                &ExifData::new(exif),
+                &FeatureOptions::default(),
+            )
+        };
+
+        let upright = tags_for(serde_json::json!({
+            "ImageWidth": 1080, "ImageHeight": 1920, "Rotation": 0, "MIMEType": "video/mp4"
+        }));
+        assert!(upright.is_vertical_video);
+        assert_eq!(upright.aspect_class, Some(AspectClass::Vertical9x16));
+
+        // Phones often store upright video as landscape frames with a rotation.
+        let rotated = tags_for(serde_json::json!({
+            "ImageWidth": 1920, "ImageHeight": 1080, "Rotation": 90, "MIMEType": "video/mp4"
+        }));
+        assert!(rotated.is_vertical_video);
+        assert_eq!(rotated.aspect_class, Some(AspectClass::Vertical9x16));
+
+        let landscape = tags_for(serde_json::json!({
+            "ImageWidth": 1920, "ImageHeight": 1080, "MIMEType": "video/mp4"
+        }));
+        assert!(!landscape.is_vertical_video);
+        assert_eq!(landscape.aspect_class, Some(AspectClass::Landscape16x9));
+
+        let photo = tags_for(serde_json::json!({
+            "ImageWidth": 3000, "ImageHeight": 4000, "Orientation": 6, "MIMEType": "image/jpeg"
+        }));
+        assert!(!photo.is_vertical_video);
+        assert_eq!(photo.aspect_class, Some(AspectClass::Landscape4x3));
+
+        let unknown = tags_for(serde_json::json!({ "MIMEType": "video/mp4" }));
+        assert_eq!(unknown.aspect_class, None);
+        assert!(!unknown.is_vertical_video);
+    }
+
+    #[test]
+    fn test_photo_detectors_skip_videos() {
+        let options = FeatureOptions {
+            night_mode_from_filename: true,
+            ..explained()
+        };
+        // A 360 degree dashcam clip, written by an app that advertises HDR.
+        let exif = ExifData::new(serde_json::json!({
+            "Other": { "MIMEType": "video/mp4" },
+            "Image": { "Software": "HDR Video Pro" },
+            "Video": { "Spherical": true, "ProjectionType": "equirectangular" }
+        }));
+        let options_lenient = FeatureOptions {
+            hdr_strictness: crate::HdrStrictness::Lenient,
+            ..options
+        };
+        let tags = extract_features(Path::new("night_drive.mp4"), &exif, &options_lenient);
+        assert!(tags.is_video);
+        assert!(!tags.is_night_mode);
+        assert!(!tags.is_hdr);
+        assert_eq!(tags.hdr_type, None);
+        assert!(tags.is_spherical_video);
+        assert_eq!(
+            tags.explanations
+                .unwrap()
+                .get("isSphericalVideo")
+                .map(String::as_str),
+            Some("Spherical is true")
+        );
+
+        // The same name on a photo is still night mode when the filename rule is on.
+        let photo = ExifData::new(serde_json::json!({ "MIMEType": "image/jpeg" }));
+        let tags = extract_features(Path::new("night_drive.jpg"), &photo, &options);
+        assert!(tags.is_night_mode);
+        assert!(!tags.is_spherical_video);
+    }
+
+    #[test]
+    fn test_hlg_video_is_hdr() {
+        // A Pixel 8 HDR video as `exiftool -n -g2` reports it.
+        let exif = ExifData::new(serde_json::json!({
+            "Other": { "MIMEType": "video/mp4" },
+            "Video": {
+                "ColorPrimaries": 9,
+                "TransferCharacteristics": 18,
+                "MatrixCoefficients": 9
+            }
+        }));
+        let tags = extract_features(Path::new("PXL_20240601_161502123.mp4"), &exif, &explained());
+        assert!(tags.is_hdr);
+        assert_eq!(tags.hdr_type, Some(HdrType::HdrVideo));
+        assert_eq!(
+            tags.explanations.unwrap().get("isHdr").map(String::as_str),
+            Some("TransferCharacteristics is HLG with BT.2020 ColorPrimaries")
+        );
+    }
 }
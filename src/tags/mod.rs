@@ -1,6 +1,12 @@
+pub mod animation;
+pub mod aspect;
+pub mod astro;
 pub mod burst;
+pub mod drive_mode;
 pub mod fps;
 pub mod hdr;
 pub mod logic;
 pub mod motion;
+pub mod night_mode;
+pub mod solar;
 pub mod structs;
@@ -1,4 +1,5 @@
 use crate::ExifData;
+use serde::{Deserialize, Serialize};
 use std::fs::File;
 use std::io::{Read, Seek, SeekFrom};
 use std::path::Path;
@@ -63,18 +64,100 @@ fn find_embedded_mp4_start(data: &[u8]) -> Option<usize> {
     None
 }
 
-/// Determines if the file has an embedded motion photo video.
-pub fn detect_motion_photo(input_file: &Path, exif: &ExifData) -> bool {
+/// The format of a motion photo with an embedded video, which determines where the video bytes
+/// are.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum MotionPhotoVersion {
+    /// Google's original format (Pixel 2 and 3): `MicroVideo` is 1 and `MicroVideoOffset` is
+    /// the video's distance from the end of the file.
+    V1MicroVideo,
+    /// Google's current format: `MotionPhoto` is 1 and the XMP container directory lists the
+    /// video's length.
+    V2MotionPhoto,
+    /// Samsung's format, with the video in a trailer after the JPEG.
+    SamsungTrailer,
+}
+
+/// Tags that only Samsung's trailer has.
+const SAMSUNG_TRAILER_TAGS: [&str; 2] = ["EmbeddedVideoFile", "EmbeddedVideoType"];
+
+/// Determines the motion photo format from the tag set that is present.
+pub fn motion_photo_version(exif: &ExifData) -> Option<MotionPhotoVersion> {
+    let has_container_directory = ["DirectoryItemSemantic", "DirectoryItemMime"]
+        .iter()
+        .any(|tag| exif.get_ignoring_case(tag).is_some());
+    let motion_photo_flag = exif.get_bool_ignoring_case("MotionPhoto") == Some(true);
+    if motion_photo_flag && has_container_directory {
+        return Some(MotionPhotoVersion::V2MotionPhoto);
+    }
+    if exif.get_bool_ignoring_case("MicroVideo") == Some(true)
+        || exif
+            .get_u64_ignoring_case("MicroVideoOffset")
+            .is_some_and(|offset| offset > 0)
+    {
+        return Some(MotionPhotoVersion::V1MicroVideo);
+    }
+    let is_samsung = exif
+        .get_str("Make")
+        .is_some_and(|make| make.trim().eq_ignore_ascii_case("samsung"));
+    if SAMSUNG_TRAILER_TAGS
+        .iter()
+        .any(|tag| exif.get_ignoring_case(tag).is_some())
+        || (is_samsung
+            && (motion_photo_flag || exif.get_ignoring_case("MotionPhotoVideo").is_some()))
+    {
+        return Some(MotionPhotoVersion::SamsungTrailer);
+    }
+    (motion_photo_flag || exif.get_ignoring_case("MotionPhotoVideo").is_some())
+        .then_some(MotionPhotoVersion::V2MotionPhoto)
+}
+
+/// The rule that marked a photo as a motion photo, with the evidence found.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MotionPhotoEvidence {
+    CompanionFile,
+    EmbeddedVideoTag(&'static str),
+    MotionPhotoFlag,
+    MicroVideoFlag,
+    MicroVideoOffset(u64),
+    EmbeddedMp4(usize),
+}
+
+impl MotionPhotoEvidence {
+    pub fn explanation(&self) -> String {
+        match self {
+            Self::CompanionFile => "companion video file (.mov/.mp4) next to the photo".to_string(),
+            Self::EmbeddedVideoTag(tag) => format!("{tag} is present"),
+            Self::MotionPhotoFlag => "MotionPhoto is 1".to_string(),
+            Self::MicroVideoFlag => "MicroVideo is 1".to_string(),
+            Self::MicroVideoOffset(offset) => {
+                format!("MicroVideoOffset {offset} points at a valid video")
+            }
+            Self::EmbeddedMp4(offset) => format!("embedded MP4 found at byte offset {offset}"),
+        }
+    }
+}
+
+/// Determines if the file has an embedded motion photo video, and on what evidence.
+pub fn motion_photo_evidence(input_file: &Path, exif: &ExifData) -> Option<MotionPhotoEvidence> {
     if exif.is_video() {
-        return false;
+        return None;
     }
     if check_companion_files(input_file) {
-        return true;
+        return Some(MotionPhotoEvidence::CompanionFile);
     }
-    if exif.get_ignoring_case("MotionPhotoVideo").is_some()
-        || exif.get_ignoring_case("EmbeddedVideoFile").is_some()
-    {
-        return true;
+    for tag in ["MotionPhotoVideo", "EmbeddedVideoFile"] {
+        if exif.get_ignoring_case(tag).is_some() {
+            return Some(MotionPhotoEvidence::EmbeddedVideoTag(tag));
+        }
+    }
+    // Samsung writes the flag as the string "1".
+    if exif.get_bool_ignoring_case("MotionPhoto") == Some(true) {
+        return Some(MotionPhotoEvidence::MotionPhotoFlag);
+    }
+    if exif.get_bool_ignoring_case("MicroVideo") == Some(true) {
+        return Some(MotionPhotoEvidence::MicroVideoFlag);
     }
     if let Some(offset_val) = exif.get_u64_ignoring_case("MicroVideoOffset")
         && offset_val > 0
@@ -88,7 +171,7 @@ pub fn detect_motion_photo(input_file: &Path, exif: &ExifData) -> bool {
             if f.seek(SeekFrom::Start(start_offset)).is_ok() {
                 let mut buf = [0u8; 64];
                 if f.read_exact(&mut buf).is_ok() && is_valid_video(&buf, false) {
-                    return true;
+                    return Some(MotionPhotoEvidence::MicroVideoOffset(offset_val));
                 }
             }
         }
@@ -117,7 +200,7 @@ pub fn detect_motion_photo(input_file: &Path, exif: &ExifData) -> bool {
                     // If EOI marker is within the last 32 bytes of the file,
                     // there is definitely no trailing MP4 appended.
                     if pos >= 96 {
-                        return false;
+                        return None;
                     }
                 }
             }
@@ -129,10 +212,82 @@ pub fn detect_motion_photo(input_file: &Path, exif: &ExifData) -> bool {
         {
             let video_bytes = &data[mp4_start_offset..];
             if is_valid_video(video_bytes, true) {
-                return true;
+                return Some(MotionPhotoEvidence::EmbeddedMp4(mp4_start_offset));
             }
         }
     }
 
-    false
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{Value, json};
+
+    /// The version and evidence for a photo that doesn't exist on disk, so only tags count.
+    fn detect(exif: Value) -> (Option<MotionPhotoVersion>, Option<MotionPhotoEvidence>) {
+        let exif = ExifData::new(exif);
+        let path = Path::new("/nonexistent/motion_photo_fixture.jpg");
+        (
+            motion_photo_version(&exif),
+            motion_photo_evidence(path, &exif),
+        )
+    }
+
+    #[test]
+    fn test_v1_micro_video() {
+        let (version, evidence) = detect(json!({
+            "Camera": { "Make": "Google", "Model": "Pixel 3" },
+            "Other": { "MicroVideo": 1, "MicroVideoVersion": 1, "MicroVideoOffset": 2_465_532 }
+        }));
+        assert_eq!(version, Some(MotionPhotoVersion::V1MicroVideo));
+        assert_eq!(evidence, Some(MotionPhotoEvidence::MicroVideoFlag));
+    }
+
+    #[test]
+    fn test_v2_motion_photo() {
+        let (version, evidence) = detect(json!({
+            "Camera": { "Make": "Google", "Model": "Pixel 8" },
+            "Other": {
+                "MotionPhoto": 1,
+                "MotionPhotoVersion": 1,
+                "DirectoryItemMime": ["image/jpeg", "video/mp4"],
+                "DirectoryItemSemantic": ["Primary", "MotionPhoto"],
+                "DirectoryItemLength": [0, 3_012_345]
+            }
+        }));
+        assert_eq!(version, Some(MotionPhotoVersion::V2MotionPhoto));
+        assert_eq!(evidence, Some(MotionPhotoEvidence::MotionPhotoFlag));
+    }
+
+    #[test]
+    fn test_samsung_trailer() {
+        let (version, evidence) = detect(json!({
+            "Camera": { "Make": "samsung", "Model": "SM-S911B" },
+            "Other": { "MotionPhoto": "1", "EmbeddedVideoType": "MotionPhoto_Data" }
+        }));
+        assert_eq!(version, Some(MotionPhotoVersion::SamsungTrailer));
+        assert_eq!(evidence, Some(MotionPhotoEvidence::MotionPhotoFlag));
+
+        let (version, evidence) = detect(json!({
+            "Camera": { "Make": "samsung", "Model": "SM-G991B" },
+            "Other": { "MotionPhotoVideo": "(Binary data 2834560 bytes)" }
+        }));
+        assert_eq!(version, Some(MotionPhotoVersion::SamsungTrailer));
+        assert_eq!(
+            evidence,
+            Some(MotionPhotoEvidence::EmbeddedVideoTag("MotionPhotoVideo"))
+        );
+    }
+
+    #[test]
+    fn test_still_photo() {
+        let (version, evidence) = detect(json!({
+            "Camera": { "Make": "Google", "Model": "Pixel 8" },
+            "Other": { "MicroVideo": 0, "MotionPhoto": 0 }
+        }));
+        assert_eq!(version, None);
+        assert_eq!(evidence, None);
+    }
 }
@@ -0,0 +1,212 @@
+//! Detects night modes, which merge a burst of long exposures into one low-light photo: Pixel
+//! Night Sight, Apple Night Mode and Samsung Bright Night.
+
+use crate::ExifData;
+use crate::features::filename::{FilenameCandidates, FilenameKind};
+use serde_json::Value;
+
+/// Exposure times from this long up count as a long exposure, in seconds.
+pub const NIGHT_MODE_MIN_EXPOSURE_S: f64 = 1.0;
+/// The EXIF `CompositeImage` value of an image composited while shooting.
+const COMPOSITE_CAPTURED_WHILE_SHOOTING: u64 = 3;
+
+/// The rule that marked a photo as taken in a night mode, with the evidence found.
+#[derive(Debug, Clone, PartialEq)]
+pub enum NightModeEvidence {
+    /// Pixel's XMP `SpecialTypeID`.
+    SpecialType(String),
+    /// An Apple composite with a long exposure.
+    AppleComposite { exposure_time: f64 },
+    /// A night `SceneMode`, as written by Samsung Bright Night.
+    SceneMode(String),
+    /// "night" in the filename, backed by a tag unless filename hints alone are allowed.
+    Filename {
+        kind: FilenameKind,
+        corroboration: Option<String>,
+    },
+}
+
+impl NightModeEvidence {
+    pub fn explanation(&self) -> String {
+        match self {
+            Self::SpecialType(value) => {
+                format!("SpecialTypeID contains 'night' (value: '{value}')")
+            }
+            Self::AppleComposite { exposure_time } => format!(
+                "Apple composite image with ExposureTime {exposure_time}s >= {NIGHT_MODE_MIN_EXPOSURE_S}s"
+            ),
+            Self::SceneMode(value) => format!("SceneMode contains 'night' (value: '{value}')"),
+            Self::Filename {
+                kind,
+                corroboration: Some(corroboration),
+            } => format!("{} contains 'night' and {corroboration}", kind.describe()),
+            Self::Filename {
+                kind,
+                corroboration: None,
+            } => format!("{} contains 'night'", kind.describe()),
+        }
+    }
+
+    /// The filename the detection matched, when it was filename-based.
+    pub const fn filename_kind(&self) -> Option<FilenameKind> {
+        match self {
+            Self::Filename { kind, .. } => Some(*kind),
+            _ => None,
+        }
+    }
+}
+
+/// The `SpecialTypeID` value that contains `needle` (lowercase), which may be one of a list.
+pub fn special_type_containing(exif: &ExifData, needle: &str) -> Option<String> {
+    let matches = |text: &str| text.to_lowercase().contains(needle);
+    match exif.get_ignoring_case("SpecialTypeID")? {
+        Value::String(text) => matches(text).then(|| text.clone()),
+        Value::Array(items) => items
+            .iter()
+            .filter_map(Value::as_str)
+            .find(|text| matches(text))
+            .map(str::to_string),
+        _ => None,
+    }
+}
+
+fn apple_night_exposure(exif: &ExifData) -> Option<f64> {
+    let is_apple = exif
+        .get_str("Make")
+        .is_some_and(|make| make.eq_ignore_ascii_case("apple"));
+    let is_composite = exif.get_u64("CompositeImage") == Some(COMPOSITE_CAPTURED_WHILE_SHOOTING);
+    if !is_apple || !is_composite {
+        return None;
+    }
+    exif.get_f64("ExposureTime")
+        .filter(|exposure_time| *exposure_time >= NIGHT_MODE_MIN_EXPOSURE_S)
+}
+
+/// A tag that backs up a "night" filename: Pixel's HDR+ maker note, or a long exposure.
+fn filename_corroboration(exif: &ExifData) -> Option<String> {
+    if exif.get_ignoring_case("HdrPlusMakernote").is_some() {
+        return Some("HdrPlusMakernote is present".to_string());
+    }
+    exif.get_f64("ExposureTime")
+        .filter(|exposure_time| *exposure_time >= NIGHT_MODE_MIN_EXPOSURE_S)
+        .map(|exposure_time| {
+            format!("ExposureTime {exposure_time}s >= {NIGHT_MODE_MIN_EXPOSURE_S}s")
+        })
+}
+
+/// Decides whether the photo was taken in a night mode, explaining the rule that fired.
+///
+/// "night" in the filename only counts when a tag backs it up, or with `filename_alone`, as
+/// people name photos `night_out.jpg` too.
+pub fn night_mode_evidence(
+    exif: &ExifData,
+    filenames: &FilenameCandidates,
+    filename_alone: bool,
+) -> Option<NightModeEvidence> {
+    if let Some(special_type) = special_type_containing(exif, "night") {
+        return Some(NightModeEvidence::SpecialType(special_type));
+    }
+    if let Some(exposure_time) = apple_night_exposure(exif) {
+        return Some(NightModeEvidence::AppleComposite { exposure_time });
+    }
+    if let Some(scene_mode) = exif
+        .get_str("SceneMode")
+        .filter(|scene_mode| scene_mode.to_lowercase().contains("night"))
+    {
+        return Some(NightModeEvidence::SceneMode(scene_mode.to_string()));
+    }
+    let ((), kind) =
+        filenames.find_map(|filename_lower| filename_lower.contains("night").then_some(()))?;
+    let corroboration = filename_corroboration(exif);
+    (corroboration.is_some() || filename_alone).then_some(NightModeEvidence::Filename {
+        kind,
+        corroboration,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::path::Path;
+
+    fn evidence_for(
+        filename: &str,
+        exif: &Value,
+        filename_alone: bool,
+    ) -> Option<NightModeEvidence> {
+        let exif = ExifData::new(exif.clone());
+        let filenames = FilenameCandidates::new(Path::new(filename), &exif);
+        night_mode_evidence(&exif, &filenames, filename_alone)
+    }
+
+    #[test]
+    fn test_pixel_night_sight() {
+        let special_type = json!({ "XMP": { "SpecialTypeID": ["com.google.android.apps.camera.gallery.specialtype.SpecialType-NIGHT"] } });
+        assert!(matches!(
+            evidence_for("PXL_20250104_170020532.jpg", &special_type, false),
+            Some(NightModeEvidence::SpecialType(_))
+        ));
+
+        let maker_note = json!({ "XMP": { "HdrPlusMakernote": "SERSUAPvZDVtXnAe" } });
+        assert_eq!(
+            evidence_for("PXL_20250104_170020532.NIGHT.jpg", &maker_note, false),
+            Some(NightModeEvidence::Filename {
+                kind: FilenameKind::Current,
+                corroboration: Some("HdrPlusMakernote is present".to_string()),
+            })
+        );
+        // The maker note is on all HDR+ shots, not only Night Sight ones.
+        assert_eq!(
+            evidence_for("PXL_20250104_170020532.jpg", &maker_note, false),
+            None
+        );
+    }
+
+    #[test]
+    fn test_apple_night_mode() {
+        let exif = json!({
+            "Camera": { "Make": "Apple", "ExposureTime": 3.0 },
+            "Image": { "CompositeImage": 3 }
+        });
+        assert_eq!(
+            evidence_for("IMG_0001.HEIC", &exif, false),
+            Some(NightModeEvidence::AppleComposite { exposure_time: 3.0 })
+        );
+
+        // Smart HDR composites are short exposures.
+        let exif = json!({
+            "Camera": { "Make": "Apple", "ExposureTime": 0.01 },
+            "Image": { "CompositeImage": 3 }
+        });
+        assert_eq!(evidence_for("IMG_0001.HEIC", &exif, false), None);
+    }
+
+    #[test]
+    fn test_samsung_bright_night() {
+        let exif = json!({ "Camera": { "Make": "samsung", "SceneMode": "Night" } });
+        assert_eq!(
+            evidence_for("20240101_220000.jpg", &exif, false),
+            Some(NightModeEvidence::SceneMode("Night".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_night_out_at_noon_is_not_night_mode() {
+        let exif = json!({
+            "Camera": { "Make": "Google", "Model": "Pixel 8", "ExposureTime": 0.002 },
+            "Time": { "DateTimeOriginal": "2024:06:01 12:00:00" }
+        });
+        assert_eq!(
+            evidence_for("night_out_with_friends.jpg", &exif, false),
+            None
+        );
+        assert_eq!(
+            evidence_for("night_out_with_friends.jpg", &exif, true),
+            Some(NightModeEvidence::Filename {
+                kind: FilenameKind::Current,
+                corroboration: None,
+            })
+        );
+    }
+}
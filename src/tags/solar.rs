@@ -0,0 +1,274 @@
+//! Tags photos taken around sunrise or sunset, or at night. Like
+//! [`astro`](crate::tags::astro), this needs the capture time in UTC and the location, so it
+//! runs after the time and GPS stages rather than in
+//! [`extract_features`](crate::tags::logic::extract_features).
+
+use crate::features::gps::GpsInfo;
+use crate::tags::structs::{FeatureOptions, MediaFeatures};
+use crate::time::structs::TimeInfo;
+use chrono::{DateTime, TimeDelta, Utc};
+use sunrise::{Coordinates, SolarDay, SolarEvent};
+
+/// The sun is further below the horizon than this at night, in degrees. Civil dusk ends here.
+pub const NIGHT_SUN_ELEVATION_DEG: f64 = -6.0;
+
+/// The Julian date of the Unix epoch.
+const UNIX_EPOCH_JULIAN_DATE: f64 = 2_440_587.5;
+/// The Julian date of the J2000 epoch.
+const J2000_JULIAN_DATE: f64 = 2_451_545.0;
+
+/// The time of `event` closest to `datetime`, looking at the UTC day of `datetime` and the
+/// days around it. West of Greenwich the sun sets on the next UTC day, so the UTC day alone
+/// misses evening shots.
+fn nearest_event(
+    datetime: DateTime<Utc>,
+    coordinates: Coordinates,
+    event: SolarEvent,
+) -> Option<DateTime<Utc>> {
+    let date = datetime.date_naive();
+    [date.pred_opt(), Some(date), date.succ_opt()]
+        .into_iter()
+        .flatten()
+        .filter_map(|day| SolarDay::new(coordinates, day).event_time(event))
+        .min_by_key(|time| (*time - datetime).abs())
+}
+
+/// Whether `event` happens within `window` of `datetime`. `false` where the sun doesn't rise
+/// or set around that day, like in polar summer.
+fn is_near_event(
+    datetime: DateTime<Utc>,
+    coordinates: Coordinates,
+    event: SolarEvent,
+    window: TimeDelta,
+) -> bool {
+    nearest_event(datetime, coordinates, event)
+        .is_some_and(|time| (time - datetime).abs() <= window)
+}
+
+/// The elevation of the center of the sun above the horizon at `datetime`, in degrees, without
+/// refraction. Uses the low-precision solar coordinates of the Astronomical Almanac, which
+/// are accurate to about 0.01° for years close to 2000.
+#[must_use]
+#[allow(clippy::cast_precision_loss)]
+pub fn sun_elevation_deg(datetime: DateTime<Utc>, latitude: f64, longitude: f64) -> f64 {
+    let julian_date = datetime.timestamp_millis() as f64 / 86_400_000.0 + UNIX_EPOCH_JULIAN_DATE;
+    let days = julian_date - J2000_JULIAN_DATE;
+
+    let mean_longitude = 0.985_647_4f64.mul_add(days, 280.460).rem_euclid(360.0);
+    let mean_anomaly = 0.985_600_3f64
+        .mul_add(days, 357.528)
+        .rem_euclid(360.0)
+        .to_radians();
+    let equation_of_center =
+        1.915f64.mul_add(mean_anomaly.sin(), 0.020 * (2.0 * mean_anomaly).sin());
+    let ecliptic_longitude = (mean_longitude + equation_of_center).to_radians();
+    let obliquity = (-0.000_000_4f64).mul_add(days, 23.439).to_radians();
+
+    let right_ascension =
+        (obliquity.cos() * ecliptic_longitude.sin()).atan2(ecliptic_longitude.cos());
+    let declination = (obliquity.sin() * ecliptic_longitude.sin()).asin();
+    let sidereal_time_deg = 360.985_647_366_29f64.mul_add(days, 280.460_618_37);
+    let hour_angle = (sidereal_time_deg + longitude).to_radians() - right_ascension;
+
+    let latitude = latitude.to_radians();
+    latitude
+        .sin()
+        .mul_add(
+            declination.sin(),
+            latitude.cos() * declination.cos() * hour_angle.cos(),
+        )
+        .clamp(-1.0, 1.0)
+        .asin()
+        .to_degrees()
+}
+
+/// Sets `is_sunrise_shot`, `is_sunset_shot` and `is_night_shot`. They stay `None` without a
+/// UTC capture time or GPS location.
+pub fn apply_solar_events(
+    features: &mut MediaFeatures,
+    time: &TimeInfo,
+    gps: Option<&GpsInfo>,
+    options: &FeatureOptions,
+) {
+    let Some((datetime, gps)) = time.datetime_utc.zip(gps) else {
+        return;
+    };
+    let Some(coordinates) = Coordinates::new(gps.latitude, gps.longitude) else {
+        return;
+    };
+    let window = options.solar_event_window;
+    let is_sunrise = is_near_event(datetime, coordinates, SolarEvent::Sunrise, window);
+    let is_sunset = is_near_event(datetime, coordinates, SolarEvent::Sunset, window);
+    let elevation = sun_elevation_deg(datetime, gps.latitude, gps.longitude);
+    let is_night = elevation < NIGHT_SUN_ELEVATION_DEG;
+
+    features.is_sunrise_shot = Some(is_sunrise);
+    features.is_sunset_shot = Some(is_sunset);
+    features.is_night_shot = Some(is_night);
+    if let Some(explanations) = features.explanations.as_mut() {
+        let minutes = window.num_minutes();
+        if is_sunrise {
+            explanations.insert(
+                "isSunriseShot".to_string(),
+                format!("Captured within {minutes} minutes of sunrise"),
+            );
+        }
+        if is_sunset {
+            explanations.insert(
+                "isSunsetShot".to_string(),
+                format!("Captured within {minutes} minutes of sunset"),
+            );
+        }
+        if is_night {
+            explanations.insert(
+                "isNightShot".to_string(),
+                format!("Sun elevation {elevation:.1}° < {NIGHT_SUN_ELEVATION_DEG}° at capture"),
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ExifData;
+    use crate::features::gps::get_gps_coordinates;
+    use crate::tags::logic::extract_features;
+    use crate::tags::structs::DEFAULT_SOLAR_EVENT_WINDOW;
+    use crate::time::get_time_info;
+    use crate::time::structs::TimeOptions;
+    use serde_json::{Value, json};
+    use std::path::Path;
+
+    /// Amsterdam, where the sun sets at 20:07 UTC (22:07 local time) on 2024-06-21.
+    const LATITUDE: f64 = 52.37;
+    const LONGITUDE: f64 = 4.9;
+
+    /// A photo taken at `time` local summer time, which the GPS location zones.
+    fn exif_at(time: &str, with_gps: bool) -> Value {
+        let mut exif = json!({
+            "File": { "MIMEType": "image/jpeg", "FileSize": 1000, "ImageWidth": 4000, "ImageHeight": 3000 },
+            "Location": { "GPSLatitude": LATITUDE, "GPSLongitude": LONGITUDE },
+            "Time": { "DateTimeOriginal": time, "OffsetTimeOriginal": "+02:00" }
+        });
+        if !with_gps {
+            exif.as_object_mut().unwrap().remove("Location");
+        }
+        exif
+    }
+
+    fn features_for(exif: Value) -> MediaFeatures {
+        let exif = ExifData::new(exif);
+        let options = FeatureOptions {
+            explain: true,
+            ..FeatureOptions::default()
+        };
+        let mut features = extract_features(Path::new("photo.jpg"), &exif, &options);
+        let gps = get_gps_coordinates(&exif);
+        let time = get_time_info(&exif, gps.as_ref(), &TimeOptions::default()).unwrap();
+        apply_solar_events(&mut features, &time, gps.as_ref(), &options);
+        features
+    }
+
+    fn sunset() -> DateTime<Utc> {
+        let datetime = DateTime::parse_from_rfc3339("2024-06-21T20:00:00Z")
+            .unwrap()
+            .to_utc();
+        let coordinates = Coordinates::new(LATITUDE, LONGITUDE).unwrap();
+        nearest_event(datetime, coordinates, SolarEvent::Sunset).unwrap()
+    }
+
+    fn exif_time(datetime: DateTime<Utc>) -> String {
+        (datetime + TimeDelta::hours(2))
+            .format("%Y:%m:%d %H:%M:%S")
+            .to_string()
+    }
+
+    #[test]
+    fn test_sunset_window_boundaries() {
+        let sunset = sunset();
+        assert_eq!(sunset.format("%H").to_string(), "20");
+
+        let inside = features_for(exif_at(&exif_time(sunset + TimeDelta::minutes(24)), true));
+        assert_eq!(inside.is_sunset_shot, Some(true));
+        assert_eq!(inside.is_sunrise_shot, Some(false));
+        assert_eq!(
+            inside.explanations.unwrap()["isSunsetShot"],
+            "Captured within 25 minutes of sunset"
+        );
+
+        let before = features_for(exif_at(&exif_time(sunset - TimeDelta::minutes(24)), true));
+        assert_eq!(before.is_sunset_shot, Some(true));
+
+        let outside = features_for(exif_at(&exif_time(sunset + TimeDelta::minutes(26)), true));
+        assert_eq!(outside.is_sunset_shot, Some(false));
+        let outside = features_for(exif_at(&exif_time(sunset - TimeDelta::minutes(26)), true));
+        assert_eq!(outside.is_sunset_shot, Some(false));
+    }
+
+    #[test]
+    fn test_configurable_window() {
+        let exif = ExifData::new(exif_at(&exif_time(sunset() + TimeDelta::minutes(40)), true));
+        let options = FeatureOptions {
+            solar_event_window: TimeDelta::hours(1),
+            ..FeatureOptions::default()
+        };
+        let mut features = extract_features(Path::new("photo.jpg"), &exif, &options);
+        let gps = get_gps_coordinates(&exif);
+        let time = get_time_info(&exif, gps.as_ref(), &TimeOptions::default()).unwrap();
+        apply_solar_events(&mut features, &time, gps.as_ref(), &options);
+        assert_eq!(features.is_sunset_shot, Some(true));
+    }
+
+    #[test]
+    fn test_sunset_on_the_next_utc_day() {
+        // In San Francisco the sun sets at 03:35 UTC on the next UTC day in midsummer.
+        let datetime = DateTime::parse_from_rfc3339("2024-06-22T03:30:00Z")
+            .unwrap()
+            .to_utc();
+        let coordinates = Coordinates::new(37.77, -122.42).unwrap();
+        assert!(is_near_event(
+            datetime,
+            coordinates,
+            SolarEvent::Sunset,
+            DEFAULT_SOLAR_EVENT_WINDOW
+        ));
+    }
+
+    #[test]
+    fn test_sun_elevation() {
+        let at = |time: &str| {
+            let datetime = DateTime::parse_from_rfc3339(time).unwrap().to_utc();
+            sun_elevation_deg(datetime, LATITUDE, LONGITUDE)
+        };
+        // At solar noon on the solstice the sun is 90° - 52.37° + 23.44° high, at solar
+        // midnight as far below the horizon as 90° - 52.37° - 23.44°.
+        assert!((at("2024-06-21T11:40:00Z") - 61.07).abs() < 0.5);
+        assert!((at("2024-06-21T23:40:00Z") + 14.19).abs() < 0.5);
+
+        let night = features_for(exif_at("2024:06:22 01:40:00", true));
+        assert_eq!(night.is_night_shot, Some(true));
+        assert!(night.explanations.unwrap()["isNightShot"].contains("< -6°"));
+        let day = features_for(exif_at("2024:06:21 13:40:00", true));
+        assert_eq!(day.is_night_shot, Some(false));
+        assert_eq!(day.is_sunrise_shot, Some(false));
+        assert_eq!(day.is_sunset_shot, Some(false));
+    }
+
+    #[test]
+    fn test_unknown_without_gps_or_utc_time() {
+        let features = features_for(exif_at("2024:06:21 22:10:00", false));
+        assert_eq!(features.is_sunset_shot, None);
+        assert_eq!(features.is_sunrise_shot, None);
+        assert_eq!(features.is_night_shot, None);
+
+        // Without an offset or a location, the naive capture time has no UTC time.
+        let mut exif = exif_at("2024:06:21 22:10:00", false);
+        exif["Time"]
+            .as_object_mut()
+            .unwrap()
+            .remove("OffsetTimeOriginal");
+        let features = features_for(exif);
+        assert_eq!(features.is_night_shot, None);
+    }
+}
@@ -1,22 +1,127 @@
+use crate::tags::aspect::AspectClass;
+use crate::tags::hdr::{HdrStrictness, HdrType};
+use crate::tags::motion::MotionPhotoVersion;
+use crate::units::Microseconds;
+use chrono::TimeDelta;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 
-#[derive(Debug, PartialEq, Clone, Deserialize, Serialize)]
-#[serde(rename_all = "camelCase")]
+/// The default [`FeatureOptions::slowmotion_ratio_threshold`].
+pub const DEFAULT_SLOWMOTION_RATIO_THRESHOLD: f64 = 1.05;
+/// The default [`FeatureOptions::solar_event_window`].
+pub const DEFAULT_SOLAR_EVENT_WINDOW: TimeDelta = TimeDelta::minutes(25);
+
+/// Settings for feature detection.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FeatureOptions {
+    /// Record the rule and evidence behind each flag that was set in `explanations`.
+    pub explain: bool,
+    /// Whether a `Software` tag mentioning HDR is enough for `is_hdr`.
+    pub hdr_strictness: HdrStrictness,
+    /// Whether "night" in the filename is enough for `is_night_mode`.
+    pub night_mode_from_filename: bool,
+    /// Videos whose capture frame rate is more than this many times their playback frame rate
+    /// are slow motion.
+    pub slowmotion_ratio_threshold: f64,
+    /// Photos taken within this long before or after sunrise or sunset are sunrise or sunset
+    /// shots.
+    pub solar_event_window: TimeDelta,
+}
+
+impl Default for FeatureOptions {
+    fn default() -> Self {
+        Self {
+            explain: false,
+            hdr_strictness: HdrStrictness::default(),
+            night_mode_from_filename: false,
+            slowmotion_ratio_threshold: DEFAULT_SLOWMOTION_RATIO_THRESHOLD,
+            solar_event_window: DEFAULT_SOLAR_EVENT_WINDOW,
+        }
+    }
+}
+
+/// The default has every flag off and everything else `None`, as for a plain photo. Fields
+/// missing from stored results, like flags added in later versions, take their default.
+#[derive(Debug, Default, PartialEq, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase", default)]
 #[allow(clippy::struct_excessive_bools)]
+#[non_exhaustive]
 pub struct MediaFeatures {
     pub is_motion_photo: bool,
-    pub motion_photo_presentation_timestamp: Option<i64>,
-    pub is_night_sight: bool,
+    /// The format of the embedded video, `None` for e.g. Live Photos with a companion video
+    /// file.
+    #[serde(default)]
+    pub motion_photo_version: Option<MotionPhotoVersion>,
+    /// The time in the embedded video that the photo was taken at.
+    pub motion_photo_presentation_timestamp: Option<Microseconds>,
+    /// Taken in a night mode: Pixel Night Sight, Apple Night Mode or Samsung Bright Night.
+    /// Always `false` for videos.
+    #[serde(alias = "isNightSight")]
+    pub is_night_mode: bool,
+    /// A star field or long-exposure night landscape: Pixel Astrophotography mode, or a long,
+    /// high-ISO exposure without flash well after dusk. `None` when the exposure qualifies but
+    /// whether it was dark is unknown, for lack of a location or UTC capture time.
+    #[serde(default)]
+    pub is_astrophotography: Option<bool>,
+    /// Taken within `solar_event_window` (25 minutes by default) of sunrise at the location.
+    /// `None` without a location or UTC capture time.
+    #[serde(default)]
+    pub is_sunrise_shot: Option<bool>,
+    /// Taken within `solar_event_window` of sunset, like `is_sunrise_shot`.
+    #[serde(default)]
+    pub is_sunset_shot: Option<bool>,
+    /// Taken with the sun more than 6° below the horizon, after civil dusk and before civil
+    /// dawn. `None` without a location or UTC capture time.
+    #[serde(default)]
+    pub is_night_shot: Option<bool>,
+    /// A filename-based detection (burst, night mode) matched the original filename preserved in
+    /// the metadata rather than the current one, e.g. because a sync tool renamed the file.
+    pub matched_original_filename: bool,
+    /// Set for gain map and HDR composite images, HLG and PQ videos, and with [`HdrStrictness::Lenient`](crate::HdrStrictness::Lenient)
+    /// also when only the `Software` tag mentions HDR.
     pub is_hdr: bool,
+    /// The kind of HDR image. Also set to `SoftwareTagOnly` when that doesn't count for `is_hdr`.
+    pub hdr_type: Option<HdrType>,
     pub is_burst: bool,
     pub burst_id: Option<String>,
+    /// Part of an automatic exposure bracketing (AEB) sequence.
+    pub is_exposure_bracket: bool,
+    /// Position of the shot within its exposure bracket.
+    pub bracket_sequence: Option<u32>,
     pub is_timelapse: bool,
     pub is_slowmotion: bool,
     pub is_video: bool,
+    /// A 360 degree video. These aren't shown in the panorama viewer, so
+    /// `use_panorama_viewer` is `false` for them.
+    #[serde(default)]
+    pub is_spherical_video: bool,
+    /// A stereoscopic panorama, like those of Cardboard Camera, with the right-eye image
+    /// embedded in the file.
+    #[serde(default)]
+    pub is_stereo_pano: bool,
+    /// An animated image, like an image sequence (HEIC/AVIF) or an animated GIF. These have a
+    /// duration, but are not videos, and are never slow motion or timelapse.
+    pub is_animated: bool,
+    /// The social media format closest to the displayed aspect ratio, after rotation.
+    #[serde(default)]
+    pub aspect_class: Option<AspectClass>,
+    /// A video that is taller than it is wide as displayed, like a phone video shot upright.
+    #[serde(default)]
+    pub is_vertical_video: bool,
     pub capture_fps: Option<f64>,
+    /// How many times slower than real time the video plays: `capture_fps / video_fps`. Above
+    /// 1 for slow motion, below 1 for timelapses recorded at a known capture rate.
+    #[serde(default)]
+    pub speed_factor: Option<f64>,
     pub video_fps: Option<f64>,
     pub audio_format: Option<String>,
     pub audio_channels: Option<u64>,
     pub audio_sample_rate: Option<u64>,
     pub compressor_id: Option<String>,
+    /// The analyzer's `filename_tag_patterns` that matched the file name, with the captured id
+    /// when the pattern has one.
+    pub filename_tags: BTreeMap<String, Option<String>>,
+    /// With explain mode on, the rule and evidence behind each flag that was set, keyed by the
+    /// field name (e.g. "isHdr" → "Software contains 'hdr' (value: 'HDR+ 1.0.345')").
+    pub explanations: Option<BTreeMap<String, String>>,
 }
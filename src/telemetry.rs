@@ -0,0 +1,125 @@
+//! Fleet-level metrics of analyses through the [`metrics`](https://docs.rs/metrics) crate, with
+//! the `metrics` feature. Without it, recording does nothing. Labels only take values from small
+//! fixed sets, never file paths or other per-file values.
+#![cfg_attr(not(feature = "metrics"), allow(dead_code))]
+
+use crate::MediaAnalyzerError;
+#[cfg(feature = "metrics")]
+use crate::time::structs::{CONFIDENCE_HIGH, CONFIDENCE_LOW, CONFIDENCE_MEDIUM};
+use std::time::Duration;
+#[cfg(feature = "metrics")]
+use std::{
+    sync::{Mutex, PoisonError},
+    time::Instant,
+};
+
+/// A histogram of the seconds spent in each stage of an analysis, labeled `stage`: `exiftool`,
+/// `metadata` or `weather`. Recorded once per stage an analysis reached, also when it failed.
+pub const STAGE_DURATION_SECONDS: &str = "media_analyzer_stage_duration_seconds";
+/// A counter of successful analyses by the confidence of their capture time, labeled `level`:
+/// `high`, `medium` or `low`.
+pub const TIME_CONFIDENCE_TOTAL: &str = "media_analyzer_time_confidence_total";
+/// A counter of weather lookups, labeled `outcome`: `hit`, `no_data` when the provider had no
+/// data, `error` when it failed, or `skipped`, e.g. for photos without GPS.
+pub const WEATHER_RESULT_TOTAL: &str = "media_analyzer_weather_result_total";
+/// A counter of failed analyses, labeled `kind`, see [`MediaAnalyzerError::kind`].
+pub const ERRORS_TOTAL: &str = "media_analyzer_errors_total";
+
+pub const WEATHER_HIT: &str = "hit";
+pub const WEATHER_NO_DATA: &str = "no_data";
+pub const WEATHER_ERROR: &str = "error";
+pub const WEATHER_SKIPPED: &str = "skipped";
+
+/// Adds up the time spent in each stage of an analysis, and records the totals when it is
+/// dropped, so a stage that is entered twice is still one sample.
+#[derive(Debug, Default)]
+pub struct StageTimer {
+    #[cfg(feature = "metrics")]
+    state: Mutex<TimerState>,
+}
+
+#[cfg(feature = "metrics")]
+#[derive(Debug, Default)]
+struct TimerState {
+    current: Option<(&'static str, Instant)>,
+    totals: Vec<(&'static str, Duration)>,
+}
+
+impl StageTimer {
+    /// Ends the current stage, if any, and starts timing `stage`.
+    #[cfg_attr(not(feature = "metrics"), allow(clippy::unused_self))]
+    pub fn enter(&self, stage: &'static str) {
+        #[cfg(feature = "metrics")]
+        {
+            let now = Instant::now();
+            let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+            if let Some((previous, started)) = state.current.replace((stage, now)) {
+                state.add(previous, now - started);
+            }
+        }
+        #[cfg(not(feature = "metrics"))]
+        let _ = stage;
+    }
+}
+
+#[cfg(feature = "metrics")]
+impl TimerState {
+    fn add(&mut self, stage: &'static str, elapsed: Duration) {
+        match self.totals.iter_mut().find(|(name, _)| *name == stage) {
+            Some((_, total)) => *total += elapsed,
+            None => self.totals.push((stage, elapsed)),
+        }
+    }
+}
+
+#[cfg(feature = "metrics")]
+impl Drop for StageTimer {
+    fn drop(&mut self) {
+        let state = self.state.get_mut().unwrap_or_else(PoisonError::into_inner);
+        if let Some((stage, started)) = state.current.take() {
+            state.add(stage, started.elapsed());
+        }
+        for (stage, total) in state.totals.drain(..) {
+            record_stage_duration(stage, total);
+        }
+    }
+}
+
+fn record_stage_duration(stage: &'static str, elapsed: Duration) {
+    #[cfg(feature = "metrics")]
+    ::metrics::histogram!(STAGE_DURATION_SECONDS, "stage" => stage).record(elapsed.as_secs_f64());
+    #[cfg(not(feature = "metrics"))]
+    let _ = (stage, elapsed);
+}
+
+/// Counts the confidence of a capture time, like "High".
+pub fn record_time_confidence(confidence: &str) {
+    #[cfg(feature = "metrics")]
+    {
+        let level = match confidence {
+            CONFIDENCE_HIGH => "high",
+            CONFIDENCE_MEDIUM => "medium",
+            CONFIDENCE_LOW => "low",
+            _ => "other",
+        };
+        ::metrics::counter!(TIME_CONFIDENCE_TOTAL, "level" => level).increment(1);
+    }
+    #[cfg(not(feature = "metrics"))]
+    let _ = confidence;
+}
+
+/// Counts a weather lookup with an outcome like [`WEATHER_HIT`].
+pub fn record_weather_result(outcome: &'static str) {
+    #[cfg(feature = "metrics")]
+    ::metrics::counter!(WEATHER_RESULT_TOTAL, "outcome" => outcome).increment(1);
+    #[cfg(not(feature = "metrics"))]
+    let _ = outcome;
+}
+
+/// Counts a failed analysis by the kind of its error.
+pub fn record_error(error: &MediaAnalyzerError) {
+    #[cfg(feature = "metrics")]
+    ::metrics::counter!(ERRORS_TOTAL, "kind" => error.kind()).increment(1);
+    #[cfg(not(feature = "metrics"))]
+    let _ = error;
+}
@@ -0,0 +1,376 @@
+//! Builders for realistic [`MediaMetadata`] values, for tests of code that consumes analysis
+//! results. Enabled with the `test-support` feature.
+//!
+//! A fixture is `exiftool -j -n -g2` style JSON that is run through the same extraction as a
+//! real analysis, without `exiftool` or a file. So the features, time and camera settings of
+//! the result agree with each other and with its `exif`.
+//!
+//! ```
+//! use media_analyzer::test_support::MediaMetadataFixture;
+//!
+//! let result = MediaMetadataFixture::photo()
+//!     .with_gps(52.37, 4.89)
+//!     .with_local_time("2024-05-01T12:00:00")
+//!     .with_hdr()
+//!     .build();
+//!
+//! assert!(result.features.is_hdr);
+//! assert_eq!(result.time.timezone.unwrap().name, "Europe/Amsterdam");
+//! assert_eq!(result.time.datetime_utc.unwrap().to_rfc3339(), "2024-05-01T10:00:00+00:00");
+//! ```
+//!
+//! Coordinates are not reverse geocoded, so `gps.location` is `None`. Set it on the built
+//! result when a test needs a place name.
+
+// Fixtures are built in tests, where a panic on bad input is the clearest failure.
+#![allow(clippy::expect_used)]
+
+use crate::features::calendar::{BundledHolidays, get_calendar_context};
+use crate::features::completeness::{
+    CompletenessBreakdown, CompletenessWeights, score_completeness,
+};
+use crate::features::dedup::get_dedup_key;
+use crate::features::file_health::FileHealth;
+use crate::features::gps::get_gps_coordinates;
+use crate::features::ingest_audit::get_ingest_audit;
+use crate::features::metadata::get_metadata;
+use crate::features::pano::{get_pano_view_info, pano_viewer_evidence};
+use crate::features::quality::get_quality_hints;
+use crate::features::user_metadata::get_user_metadata;
+use crate::tags::logic::extract_features;
+use crate::tags::structs::FeatureOptions;
+use crate::time::structs::TimeOptions;
+use crate::time::{get_time_info, lookup_gps_zone};
+use crate::{ExifData, MediaMetadata};
+use chrono::NaiveDateTime;
+use serde_json::{Map, Value, json};
+use std::path::Path;
+
+/// The file name of fixtures that don't set one.
+const DEFAULT_FILENAME: &str = "IMG_0001.jpg";
+
+/// A builder for a [`MediaMetadata`], starting from a kind of file and adjusted with the `with_`
+/// methods, which write the tags `exiftool` would report.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MediaMetadataFixture {
+    exif: Value,
+    hash: Option<String>,
+}
+
+impl MediaMetadataFixture {
+    /// Any `exiftool` JSON. It needs at least `ImageWidth`, `ImageHeight`, `MIMEType` and a
+    /// capture time, like a `DateTimeOriginal`.
+    #[must_use]
+    pub const fn from_exif(exif: Value) -> Self {
+        Self { exif, hash: None }
+    }
+
+    /// A JPEG from a Canon EOS R5, taken on 2024-06-01 at 10:00 in UTC+02:00.
+    #[must_use]
+    pub fn photo() -> Self {
+        Self::from_exif(json!({
+            "Other": {
+                "FileName": DEFAULT_FILENAME,
+                "MIMEType": "image/jpeg",
+                "FileSize": 6_291_456
+            },
+            "Image": { "ImageWidth": 4000, "ImageHeight": 3000, "Orientation": 1 },
+            "Camera": {
+                "Make": "Canon",
+                "Model": "EOS R5",
+                "ISO": 100,
+                "ExposureTime": 0.004,
+                "FNumber": 5.6,
+                "FocalLength": 35.0
+            },
+            "Time": {
+                "DateTimeOriginal": "2024:06:01 10:00:00",
+                "OffsetTimeOriginal": "+02:00"
+            }
+        }))
+    }
+
+    /// A Pixel photo of a sunset over Amsterdam, like `assets/sunset.jpg`.
+    #[must_use]
+    pub fn sunset() -> Self {
+        Self::from_exif(json!({
+            "Other": {
+                "FileName": "PXL_20240601_194012345.jpg",
+                "MIMEType": "image/jpeg",
+                "FileSize": 3_145_728
+            },
+            "Image": { "ImageWidth": 4080, "ImageHeight": 3072, "Orientation": 1 },
+            "Camera": {
+                "Make": "Google",
+                "Model": "Pixel 8",
+                "ISO": 50,
+                "ExposureTime": 0.001,
+                "FNumber": 1.68,
+                "FocalLength": 6.9
+            },
+            "Location": { "GPSLatitude": 52.379_189, "GPSLongitude": 4.899_431 },
+            "Time": {
+                "DateTimeOriginal": "2024:06:01 21:40:12",
+                "SubSecTimeOriginal": "345",
+                "OffsetTimeOriginal": "+02:00"
+            }
+        }))
+    }
+
+    /// A Pixel motion photo: a JPEG with an embedded video, like the photos in
+    /// `assets/motion`.
+    #[must_use]
+    pub fn motion_photo() -> Self {
+        Self::from_exif(json!({
+            "Other": {
+                "FileName": "PXL_20240601_100000123.MP.jpg",
+                "MIMEType": "image/jpeg",
+                "FileSize": 5_242_880
+            },
+            "Image": {
+                "ImageWidth": 4080,
+                "ImageHeight": 3072,
+                "Orientation": 1,
+                "MotionPhoto": 1,
+                "MotionPhotoVersion": 1,
+                "MotionPhotoPresentationTimestampUs": 1_234_567,
+                "DirectoryItemSemantic": ["Primary", "MotionPhoto"],
+                "DirectoryItemMime": ["image/jpeg", "video/mp4"],
+                "DirectoryItemLength": [0, 2_097_152]
+            },
+            "Camera": { "Make": "Google", "Model": "Pixel 8" },
+            "Time": {
+                "DateTimeOriginal": "2024:06:01 10:00:00",
+                "SubSecTimeOriginal": "123",
+                "OffsetTimeOriginal": "+02:00"
+            }
+        }))
+    }
+
+    /// A 12.5 second 1080p Pixel video at 30 fps, like `assets/video`. Its capture time is
+    /// the UTC `CreateDate` of the `QuickTime` header.
+    #[must_use]
+    pub fn video() -> Self {
+        Self::from_exif(json!({
+            "Other": {
+                "FileName": "PXL_20240601_080000123.mp4",
+                "MIMEType": "video/mp4",
+                "FileSize": 31_457_280
+            },
+            "Video": {
+                "ImageWidth": 1920,
+                "ImageHeight": 1080,
+                "Rotation": 0,
+                "VideoFrameRate": 30.0,
+                "Duration": 12.5,
+                "CompressorID": "hvc1"
+            },
+            "Audio": { "AudioFormat": "mp4a", "AudioChannels": 2, "AudioSampleRate": 48000 },
+            "Camera": { "Make": "Google", "Model": "Pixel 8" },
+            "Time": { "CreateDate": "2024:06:01 08:00:00" }
+        }))
+    }
+
+    /// Sets a tag in a group of the `exiftool -g2` output, like `("Camera", "ISO")`.
+    ///
+    /// # Panics
+    ///
+    /// When the JSON of [`Self::from_exif`] is not an object.
+    #[must_use]
+    pub fn with_tag(mut self, group: &str, tag: &str, value: impl Into<Value>) -> Self {
+        let root = self.exif.as_object_mut().expect("exif should be an object");
+        let group = root
+            .entry(group)
+            .or_insert_with(|| Value::Object(Map::new()));
+        if !group.is_object() {
+            *group = Value::Object(Map::new());
+        }
+        group
+            .as_object_mut()
+            .expect("group was made an object")
+            .insert(tag.to_string(), value.into());
+        self
+    }
+
+    #[must_use]
+    pub fn with_filename(self, filename: &str) -> Self {
+        self.with_tag("Other", "FileName", filename)
+    }
+
+    /// The hash of the file. Without one, it is a hash of the fixture's `exif`, so different
+    /// fixtures get different hashes.
+    #[must_use]
+    pub fn with_hash(mut self, hash: &str) -> Self {
+        self.hash = Some(hash.to_string());
+        self
+    }
+
+    /// GPS coordinates in degrees. These also set the timezone of the capture time when the
+    /// fixture has no offset.
+    #[must_use]
+    pub fn with_gps(self, latitude: f64, longitude: f64) -> Self {
+        self.with_tag("Location", "GPSLatitude", latitude).with_tag(
+            "Location",
+            "GPSLongitude",
+            longitude,
+        )
+    }
+
+    /// The local capture time, as "YYYY-MM-DDTHH:MM:SS". Keeps the fixture's UTC offset, if any.
+    ///
+    /// # Panics
+    ///
+    /// When `local_time` is not in that format.
+    #[must_use]
+    pub fn with_local_time(self, local_time: &str) -> Self {
+        let local_time = NaiveDateTime::parse_from_str(local_time, "%Y-%m-%dT%H:%M:%S")
+            .expect("local time should be formatted as YYYY-MM-DDTHH:MM:SS");
+        self.with_tag(
+            "Time",
+            "DateTimeOriginal",
+            local_time.format("%Y:%m:%d %H:%M:%S").to_string(),
+        )
+    }
+
+    /// The UTC offset of the capture time, like "+02:00".
+    #[must_use]
+    pub fn with_offset(self, offset: &str) -> Self {
+        self.with_tag("Time", "OffsetTimeOriginal", offset)
+    }
+
+    /// Removes the UTC offset of the capture time, so its timezone comes from the GPS
+    /// coordinates, or is unknown without them.
+    #[must_use]
+    pub fn without_offset(mut self) -> Self {
+        if let Some(time) = self.exif.get_mut("Time").and_then(Value::as_object_mut) {
+            time.remove("OffsetTimeOriginal");
+        }
+        self
+    }
+
+    /// Marks the capture as an HDR composite, with `CompositeImage` 3.
+    #[must_use]
+    pub fn with_hdr(self) -> Self {
+        self.with_tag("Image", "CompositeImage", 3)
+    }
+
+    /// Runs the extraction of an analysis on the fixture's tags, with the default options and
+    /// without weather.
+    ///
+    /// # Panics
+    ///
+    /// When the tags lack `ImageWidth`, `ImageHeight`, `MIMEType` or a capture time.
+    #[must_use]
+    pub fn build(self) -> MediaMetadata {
+        let exif = ExifData::new(self.exif.clone());
+        let path = Path::new(exif.get_str("FileName").unwrap_or(DEFAULT_FILENAME)).to_path_buf();
+        let (basic, camera) = get_metadata(&exif).expect("fixture should have basic metadata");
+        let features = extract_features(&path, &exif, &FeatureOptions::default());
+        let mut gps = get_gps_coordinates(&exif);
+        if let Some(gps) = gps.as_mut() {
+            gps.timezone =
+                lookup_gps_zone(gps, &TimeOptions::default()).map(|tz| tz.name().to_string());
+        }
+        let time = get_time_info(&exif, gps.as_ref(), &TimeOptions::default())
+            .expect("fixture should have a capture time");
+        let mut result = MediaMetadata {
+            hash: self
+                .hash
+                .unwrap_or_else(|| blake3::hash(self.exif.to_string().as_bytes()).to_string()),
+            use_panorama_viewer: !features.is_video && pano_viewer_evidence(&exif).is_some(),
+            pano: get_pano_view_info(&exif)
+                .filter(|pano| !features.is_video || pano.needs_stitching),
+            quality_hints: get_quality_hints(&basic, &camera, &exif),
+            dedup_key: get_dedup_key(&camera, &time),
+            ingest_audit: Some(get_ingest_audit(&path, &exif, &camera, &time)),
+            user_metadata: get_user_metadata(&exif),
+            file_health: FileHealth::default(),
+            parse_diagnostics: None,
+            weather: None,
+            calendar_context: Some(get_calendar_context(&time, gps.as_ref(), &BundledHolidays)),
+            thumbnail_timestamp_s: None,
+            aux_images: Vec::new(),
+            skipped_stages: Vec::new(),
+            notes: Vec::new(),
+            provenance: None,
+            features,
+            time,
+            gps,
+            basic,
+            camera,
+            exif: self.exif,
+            completeness_score: 0,
+            completeness: CompletenessBreakdown::default(),
+        };
+        (result.completeness_score, result.completeness) =
+            score_completeness(&result, &CompletenessWeights::default());
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tags::motion::MotionPhotoVersion;
+
+    #[test]
+    fn test_ready_made_fixtures() {
+        let photo = MediaMetadataFixture::photo().build();
+        assert_eq!(photo.basic.width, 4000);
+        assert_eq!(photo.camera.camera_model.as_deref(), Some("EOS R5"));
+        assert_eq!(
+            photo.time.datetime_utc.unwrap().to_rfc3339(),
+            "2024-06-01T08:00:00+00:00"
+        );
+        assert!(photo.gps.is_none());
+        assert!(!photo.features.is_hdr);
+
+        let sunset = MediaMetadataFixture::sunset().build();
+        let gps = sunset.gps.unwrap();
+        assert!(gps.location.is_none());
+        assert_eq!(gps.timezone.as_deref(), Some("Europe/Amsterdam"));
+        assert_eq!(sunset.time.timezone.unwrap().name, "Europe/Amsterdam");
+
+        let motion = MediaMetadataFixture::motion_photo().build();
+        assert!(motion.features.is_motion_photo);
+        assert_eq!(
+            motion.features.motion_photo_version,
+            Some(MotionPhotoVersion::V2MotionPhoto)
+        );
+
+        let video = MediaMetadataFixture::video().build();
+        assert!(video.features.is_video);
+        assert_eq!(video.features.video_fps, Some(30.0));
+        assert_eq!(
+            video.time.datetime_utc.unwrap().to_rfc3339(),
+            "2024-06-01T08:00:00+00:00"
+        );
+    }
+
+    #[test]
+    fn test_builders() {
+        let result = MediaMetadataFixture::photo()
+            .with_filename("DSC_0042.JPG")
+            .with_hash("abc")
+            .without_offset()
+            .with_gps(-33.8568, 151.2153)
+            .with_local_time("2024-12-24T18:30:00")
+            .build();
+        assert_eq!(result.hash, "abc");
+        assert_eq!(result.time.timezone.unwrap().name, "Australia/Sydney");
+        assert_eq!(
+            result.time.datetime_utc.unwrap().to_rfc3339(),
+            "2024-12-24T07:30:00+00:00"
+        );
+        assert_eq!(result.exif["Other"]["FileName"], "DSC_0042.JPG");
+
+        // Distinct fixtures get distinct hashes.
+        assert_ne!(
+            MediaMetadataFixture::photo().build().hash,
+            MediaMetadataFixture::photo()
+                .with_offset("+01:00")
+                .build()
+                .hash
+        );
+    }
+}
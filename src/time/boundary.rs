@@ -0,0 +1,95 @@
+//! Detects GPS-zoned results that lie close to a timezone border, where GPS error may have
+//! picked the zone on the wrong side.
+
+/// Timezone borders within this distance make the zone of a GPS location fragile.
+pub const TIMEZONE_BOUNDARY_THRESHOLD_KM: f64 = 5.0;
+/// The number of points probed on the ring around the location.
+const RING_SAMPLES: u32 = 8;
+/// Halvings of the search interval when locating the border, ~5m precision at 5km.
+const BISECTION_STEPS: u32 = 10;
+const KM_PER_DEGREE_LATITUDE: f64 = 111.32;
+
+/// The point `distance_km` away from the coordinate in the direction of `bearing` (radians,
+/// clockwise from north). An equirectangular approximation is accurate enough at these scales.
+fn offset_point(latitude: f64, longitude: f64, bearing: f64, distance_km: f64) -> (f64, f64) {
+    let delta_latitude = distance_km * bearing.cos() / KM_PER_DEGREE_LATITUDE;
+    let km_per_degree_longitude = KM_PER_DEGREE_LATITUDE * latitude.to_radians().cos().max(0.01);
+    let delta_longitude = distance_km * bearing.sin() / km_per_degree_longitude;
+    (
+        (latitude + delta_latitude).clamp(-90.0, 90.0),
+        longitude + delta_longitude,
+    )
+}
+
+/// Estimates the distance to the nearest timezone border, when one is within
+/// [`TIMEZONE_BOUNDARY_THRESHOLD_KM`].
+///
+/// Probes a ring of points at the threshold distance. For every direction that lands in
+/// another zone, the crossing is located by bisection, and the closest crossing is returned.
+/// `tz_name` looks up the zone name at a latitude and longitude.
+pub fn boundary_distance_km<'a>(
+    tz_name: impl Fn(f64, f64) -> &'a str,
+    latitude: f64,
+    longitude: f64,
+) -> Option<f64> {
+    let zone = tz_name(latitude, longitude);
+    let zone_at = |bearing: f64, distance_km: f64| {
+        let (lat, lon) = offset_point(latitude, longitude, bearing, distance_km);
+        tz_name(lat, lon)
+    };
+
+    (0..RING_SAMPLES)
+        .map(|i| f64::from(i) * std::f64::consts::TAU / f64::from(RING_SAMPLES))
+        .filter(|bearing| zone_at(*bearing, TIMEZONE_BOUNDARY_THRESHOLD_KM) != zone)
+        .map(|bearing| {
+            let (mut inside, mut outside) = (0.0, TIMEZONE_BOUNDARY_THRESHOLD_KM);
+            for _ in 0..BISECTION_STEPS {
+                let middle = f64::midpoint(inside, outside);
+                if zone_at(bearing, middle) == zone {
+                    inside = middle;
+                } else {
+                    outside = middle;
+                }
+            }
+            outside
+        })
+        .reduce(f64::min)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::LazyLock;
+
+    use tzf_rs::DefaultFinder;
+
+    static FINDER: LazyLock<DefaultFinder> = LazyLock::new(DefaultFinder::new);
+
+    fn tz_name(latitude: f64, longitude: f64) -> &'static str {
+        FINDER.get_tz_name(longitude, latitude)
+    }
+
+    #[test]
+    fn test_point_deep_inside_a_zone() {
+        // Madrid, hundreds of kilometers from the Portuguese border.
+        assert_eq!(boundary_distance_km(tz_name, 40.4168, -3.7038), None);
+    }
+
+    #[test]
+    fn test_point_on_the_dutch_german_border() {
+        // Kerkrade, where the Nieuwstraat is the border with Herzogenrath.
+        assert_eq!(FINDER.get_tz_name(6.0710, 50.8660), "Europe/Amsterdam");
+        let distance = boundary_distance_km(tz_name, 50.8660, 6.0710).unwrap();
+        assert!(
+            distance < 2.0,
+            "the border is a few hundred meters away, got {distance}km"
+        );
+    }
+
+    #[test]
+    fn test_offset_point_distance() {
+        let (lat, lon) = offset_point(52.0, 5.0, 0.0, KM_PER_DEGREE_LATITUDE);
+        assert!((lat - 53.0).abs() < 1e-9);
+        assert!((lon - 5.0).abs() < 1e-9);
+    }
+}
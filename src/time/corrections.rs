@@ -0,0 +1,243 @@
+//! Clock corrections for whole batches of media, e.g. a camera whose clock was an hour off
+//! during a trip.
+
+use super::error::TimeError;
+use crate::ExifData;
+use crate::features::dedup::get_serial_number;
+use chrono::{NaiveDate, NaiveDateTime, TimeDelta};
+use serde::{Deserialize, Serialize};
+
+/// Shifts the capture time of media from a specific camera, optionally within a date range.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TimeCorrection {
+    /// Only applies to the camera body with this serial number.
+    pub camera_serial: Option<String>,
+    /// Only applies to cameras of this model, compared case-insensitively.
+    pub camera_model: Option<String>,
+    /// The first capture date (inclusive) the correction applies to.
+    pub applies_from: Option<NaiveDate>,
+    /// The last capture date (inclusive) the correction applies to.
+    pub applies_to: Option<NaiveDate>,
+    /// Seconds to add to the camera's clock time, negative for a clock that was ahead.
+    pub offset_seconds: i64,
+}
+
+impl TimeCorrection {
+    fn matches(&self, serial: Option<&str>, model: Option<&str>, date: NaiveDate) -> bool {
+        let serial_matches = self
+            .camera_serial
+            .as_deref()
+            .is_none_or(|s| serial.is_some_and(|serial| serial.trim() == s.trim()));
+        let model_matches = self
+            .camera_model
+            .as_deref()
+            .is_none_or(|m| model.is_some_and(|model| model.trim().eq_ignore_ascii_case(m.trim())));
+        let in_range = self.applies_from.is_none_or(|from| date >= from)
+            && self.applies_to.is_none_or(|to| date <= to);
+        serial_matches && model_matches && in_range
+    }
+
+    /// Serial numbers are more specific than models, which are more specific than date ranges.
+    fn specificity(&self) -> u8 {
+        u8::from(self.camera_serial.is_some()) * 4
+            + u8::from(self.camera_model.is_some()) * 2
+            + u8::from(self.applies_from.is_some() || self.applies_to.is_some())
+    }
+
+    #[must_use]
+    pub const fn offset(&self) -> TimeDelta {
+        TimeDelta::seconds(self.offset_seconds)
+    }
+
+    /// Describes the correction for `SourceDetails.time_source_detail`, e.g. "corrected -3600s".
+    #[must_use]
+    pub fn description(&self) -> String {
+        format!("corrected {:+}s", self.offset_seconds)
+    }
+}
+
+/// Finds the most specific correction for media from the camera in `exif`, captured at
+/// `capture_time` according to the (uncorrected) camera clock. The first one wins on a tie.
+pub fn find_correction<'a>(
+    corrections: &'a [TimeCorrection],
+    exif: &ExifData,
+    capture_time: NaiveDateTime,
+) -> Option<&'a TimeCorrection> {
+    if corrections.is_empty() {
+        return None;
+    }
+    let serial = get_serial_number(exif);
+    let model = exif.get_str("Model");
+    corrections
+        .iter()
+        .filter(|c| c.matches(serial.as_deref(), model, capture_time.date()))
+        .rev()
+        .max_by_key(|c| c.specificity())
+}
+
+const CSV_COLUMNS: usize = 5;
+
+fn optional_field(field: &str) -> Option<String> {
+    let field = field.trim();
+    (!field.is_empty()).then(|| field.to_string())
+}
+
+fn parse_csv_line(line: &str, line_number: usize) -> Result<TimeCorrection, TimeError> {
+    let invalid = |reason: String| TimeError::InvalidCorrection {
+        line: line_number,
+        reason,
+    };
+    let fields: Vec<&str> = line.split(',').collect();
+    if fields.len() != CSV_COLUMNS {
+        return Err(invalid(format!(
+            "expected {CSV_COLUMNS} columns, found {}",
+            fields.len()
+        )));
+    }
+    let parse_date = |field: &str| {
+        optional_field(field)
+            .map(|date| {
+                NaiveDate::parse_from_str(&date, "%Y-%m-%d")
+                    .map_err(|_| invalid(format!("invalid date '{date}', expected YYYY-MM-DD")))
+            })
+            .transpose()
+    };
+    let offset = fields[4].trim();
+    Ok(TimeCorrection {
+        camera_serial: optional_field(fields[0]),
+        camera_model: optional_field(fields[1]),
+        applies_from: parse_date(fields[2])?,
+        applies_to: parse_date(fields[3])?,
+        offset_seconds: offset
+            .parse()
+            .map_err(|_| invalid(format!("invalid offset '{offset}'")))?,
+    })
+}
+
+/// Parses a corrections table in CSV form, with the columns
+/// `camera_serial,camera_model,applies_from,applies_to,offset_seconds`.
+///
+/// Empty fields are left unset, dates are formatted as `YYYY-MM-DD`. A header row, blank lines
+/// and lines starting with `#` are skipped. Fields can't contain commas.
+///
+/// # Errors
+///
+/// Returns [`TimeError::InvalidCorrection`] for the first line that can't be parsed.
+pub fn parse_time_corrections_csv(input: &str) -> Result<Vec<TimeCorrection>, TimeError> {
+    input
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| {
+            let line = line.trim();
+            !line.is_empty() && !line.starts_with('#') && !line.starts_with("camera_serial")
+        })
+        .map(|(index, line)| parse_csv_line(line, index + 1))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn capture_time() -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2024, 7, 10)
+            .unwrap()
+            .and_hms_opt(14, 0, 0)
+            .unwrap()
+    }
+
+    fn exif() -> ExifData {
+        ExifData::new(json!({
+            "Camera": { "Make": "Canon", "Model": "EOS R5", "SerialNumber": "123456" }
+        }))
+    }
+
+    fn correction(
+        serial: Option<&str>,
+        model: Option<&str>,
+        offset_seconds: i64,
+    ) -> TimeCorrection {
+        TimeCorrection {
+            camera_serial: serial.map(str::to_string),
+            camera_model: model.map(str::to_string),
+            applies_from: None,
+            applies_to: None,
+            offset_seconds,
+        }
+    }
+
+    #[test]
+    fn test_serial_wins_over_model() {
+        let corrections = vec![
+            correction(None, Some("eos r5"), 60),
+            correction(Some("123456"), None, -3600),
+        ];
+        let found = find_correction(&corrections, &exif(), capture_time()).unwrap();
+        assert_eq!(found.offset_seconds, -3600);
+        assert_eq!(found.description(), "corrected -3600s");
+    }
+
+    #[test]
+    fn test_model_match_with_date_range() {
+        let in_range = TimeCorrection {
+            applies_from: NaiveDate::from_ymd_opt(2024, 7, 1),
+            applies_to: NaiveDate::from_ymd_opt(2024, 7, 10),
+            ..correction(None, Some("EOS R5"), 7200)
+        };
+        let before = TimeCorrection {
+            applies_to: NaiveDate::from_ymd_opt(2024, 6, 30),
+            ..correction(None, Some("EOS R5"), 60)
+        };
+        let corrections = vec![correction(None, Some("EOS R5"), 30), before, in_range];
+        let found = find_correction(&corrections, &exif(), capture_time()).unwrap();
+        assert_eq!(found.offset_seconds, 7200);
+    }
+
+    #[test]
+    fn test_no_match() {
+        let corrections = vec![
+            correction(Some("999"), None, 60),
+            correction(None, Some("Pixel 8"), 60),
+            TimeCorrection {
+                applies_from: NaiveDate::from_ymd_opt(2025, 1, 1),
+                ..correction(None, Some("EOS R5"), 60)
+            },
+        ];
+        assert!(find_correction(&corrections, &exif(), capture_time()).is_none());
+    }
+
+    #[test]
+    fn test_parse_csv() {
+        let csv = "camera_serial,camera_model,applies_from,applies_to,offset_seconds\n\
+                   # Holiday in Italy\n\
+                   123456,,2024-07-01,2024-07-14,-3600\n\
+                   \n\
+                   ,EOS R5,,,60\n";
+        let corrections = parse_time_corrections_csv(csv).unwrap();
+        assert_eq!(
+            corrections,
+            vec![
+                TimeCorrection {
+                    applies_from: NaiveDate::from_ymd_opt(2024, 7, 1),
+                    applies_to: NaiveDate::from_ymd_opt(2024, 7, 14),
+                    ..correction(Some("123456"), None, -3600)
+                },
+                correction(None, Some("EOS R5"), 60),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_csv_reports_line() {
+        let csv = ",EOS R5,,,60\n,EOS R5,2024-13-01,,60";
+        assert_eq!(
+            parse_time_corrections_csv(csv),
+            Err(TimeError::InvalidCorrection {
+                line: 2,
+                reason: "invalid date '2024-13-01', expected YYYY-MM-DD".to_string()
+            })
+        );
+    }
+}
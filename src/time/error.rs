@@ -4,4 +4,7 @@ use thiserror::Error;
 pub enum TimeError {
     #[error("Could not extract any usable time metadata from the file")]
     Extraction,
+
+    #[error("Invalid time correction on line {line}: {reason}")]
+    InvalidCorrection { line: usize, reason: String },
 }
@@ -1,64 +1,285 @@
 //! Functions for extracting raw time-related string/number values from EXIF JSON.
 
 use super::parsing::{
-    add_subseconds_from_number, parse_datetime_offset, parse_datetime_utc_z, parse_naive,
-    parse_offset_string,
+    ParseOptions, add_subseconds_from_number, parse_datetime_offset, parse_datetime_utc_z,
+    parse_matroska_utc, parse_naive, parse_offset_string,
 };
 use crate::ExifData;
+use crate::features::filename::get_original_filename;
 use crate::time::filename_parsing::parse_datetime_from_filename;
+use crate::time::source::{TimeSource, TimeSourceDetail};
+use crate::time::structs::{TimeOptions, format_offset};
 use chrono::{DateTime, FixedOffset, NaiveDateTime, Utc};
+use serde_json::Value;
 
 #[derive(Debug)]
 /// Intermediate data structure
 pub struct ExtractedTimeComponents {
-    pub best_local: Option<(NaiveDateTime, String)>, // (DateTime, Source Tag Name)
-    pub potential_utc: Option<(DateTime<Utc>, String)>, // (DateTime, Source Tag Name)
+    pub best_local: Option<(NaiveDateTime, TimeSourceDetail)>,
+    pub potential_utc: Option<(DateTime<Utc>, TimeSourceDetail)>,
     pub potential_explicit_offset: Option<(i32, String, String)>, // (Offset Seconds, Offset String, Source Tag Name)
-    pub potential_file_dt: Option<(DateTime<FixedOffset>, String)>, // (DateTime, Source Tag Name)
+    pub potential_file_dt: Option<(DateTime<FixedOffset>, TimeSourceDetail)>,
     pub is_video: bool,
 }
 
-/// Parses a datetime from a filename string.
-fn parse_filename_to_naive(exif: &ExifData) -> Option<(NaiveDateTime, String)> {
-    if let Some(filename) = exif.group_str("Other", "FileName") {
-        let result = parse_datetime_from_filename(filename);
-        return result.map(|datetime| (datetime, "FileName".to_string()));
+/// MIME types of Matroska containers, whose dates are UTC.
+const MATROSKA_MIME_TYPES: [&str; 3] = ["video/webm", "video/x-matroska", "audio/x-matroska"];
+/// Tags that hold a Matroska segment's date, in order of preference.
+const MATROSKA_DATE_TAGS: [&str; 3] = ["DateTimeOriginal", "DateUTC", "CreationTime"];
+/// exiftool puts Matroska dates in one of these groups, depending on its version.
+const MATROSKA_DATE_GROUPS: [&str; 3] = ["Time", "Video", "Other"];
+
+/// The UTC date of a Matroska segment.
+fn matroska_utc(exif: &ExifData) -> Option<DateTime<Utc>> {
+    MATROSKA_DATE_TAGS.iter().find_map(|tag| {
+        MATROSKA_DATE_GROUPS
+            .iter()
+            .find_map(|group| parse_matroska_utc(exif.group_str(group, tag)?))
+    })
+}
+
+/// File system times, in order of preference when there is no capture time to compare to.
+const FILE_TIME_TAGS: [&str; 3] = ["FileModifyDate", "FileCreateDate", "FileAccessDate"];
+
+/// The file's modification time, as the file system reported it to `exiftool`.
+pub fn file_modify_time(exif: &ExifData) -> Option<DateTime<FixedOffset>> {
+    parse_datetime_offset(exif.group_str("Time", "FileModifyDate")?)
+}
+
+/// Picks the file system time to guess the offset from: the one closest to the capture time
+/// `local`, as a backup restore or a copy resets some of them to the day of the copy, and which
+/// ones depends on the file system. Without a capture time, the first in [`FILE_TIME_TAGS`].
+///
+/// When a choice was made, the source notes the distance to the capture time.
+fn select_file_time(
+    exif: &ExifData,
+    local: Option<NaiveDateTime>,
+) -> Option<(DateTime<FixedOffset>, TimeSourceDetail)> {
+    let file_times: Vec<(DateTime<FixedOffset>, &str)> = FILE_TIME_TAGS
+        .iter()
+        .filter_map(|field| {
+            Some((
+                parse_datetime_offset(exif.group_str("Time", field)?)?,
+                *field,
+            ))
+        })
+        .collect();
+    let Some(local) = local.filter(|_| file_times.len() > 1) else {
+        return file_times
+            .first()
+            .map(|(dt, field)| (*dt, TimeSourceDetail::from_tag(field)));
+    };
+    // `min_by_key` keeps the first of equally close times, so ties follow the preference order.
+    let (dt, field) = file_times
+        .into_iter()
+        .min_by_key(|(dt, _)| (dt.naive_local() - local).abs())?;
+    let days = (dt.naive_local() - local).num_days().abs();
+    let unit = if days == 1 { "day" } else { "days" };
+    Some((
+        dt,
+        TimeSourceDetail::from_tag(field).with_note(format!("{days} {unit} from the capture time")),
+    ))
+}
+
+/// Parses a datetime from the file name, or else from the original file name preserved in the
+/// metadata, which survives renames by sync tools.
+fn parse_filename_to_naive(exif: &ExifData) -> Option<(NaiveDateTime, TimeSourceDetail)> {
+    let current = exif
+        .group_str("Other", "FileName")
+        .map(|filename| (TimeSourceDetail::new(TimeSource::FileName), filename));
+    let original = get_original_filename(exif).map(|(tag, filename)| {
+        (
+            TimeSourceDetail::new(TimeSource::OriginalFileName).with_note(tag),
+            filename,
+        )
+    });
+    current
+        .into_iter()
+        .chain(original)
+        .find_map(|(source, filename)| {
+            parse_datetime_from_filename(filename).map(|datetime| (datetime, source))
+        })
+}
+
+/// The default order in which "Time" group tags are tried for the local capture time of a photo.
+pub const DEFAULT_TIME_TAG_PRIORITY: [&str; 8] = [
+    "SubSecDateTimeOriginal",
+    "SubSecCreateDate",
+    "SubSecTimeDigitized",
+    "DateTimeOriginal",
+    "CreateDate",
+    "DateTimeDigitized",
+    "SubSecModifyDate",
+    "ModifyDate",
+];
+
+/// Time tags that a custom priority list may refer to, used to normalize their casing.
+const KNOWN_TIME_TAGS: [&str; 12] = [
+    "SubSecDateTimeOriginal",
+    "SubSecCreateDate",
+    "SubSecTimeDigitized",
+    "DateTimeOriginal",
+    "CreateDate",
+    "DateTimeDigitized",
+    "SubSecModifyDate",
+    "ModifyDate",
+    "DateTimeCreated",
+    "DigitalCreationDateTime",
+    "MediaCreateDate",
+    "TrackCreateDate",
+];
+
+/// The order in which offset tags are tried when the chosen local time tag has no paired offset.
+const OFFSET_TAG_PRIORITY: [&str; 3] = ["OffsetTimeOriginal", "OffsetTimeDigitized", "OffsetTime"];
+
+/// The largest offset from UTC in use, in hours.
+const MAX_OFFSET_HOURS: f64 = 14.0;
+
+/// An offset in hours, like the legacy `TimeZoneOffset` tag, in seconds. Halves like 5.5 are
+/// kept.
+#[allow(clippy::cast_possible_truncation)]
+fn offset_from_hours(hours: f64) -> Option<(i32, String)> {
+    if !hours.is_finite() || hours.abs() > MAX_OFFSET_HOURS {
+        return None;
+    }
+    let secs = (hours * 3600.0).round() as i32;
+    Some((secs, format_offset(secs)))
+}
+
+/// The legacy `TimeZoneOffset` tag of older Nikons: integer hours, or two of them for
+/// `DateTimeOriginal` and `ModifyDate`. The second is only used when the local time came from
+/// `ModifyDate`.
+fn time_zone_offset(exif: &ExifData, chosen_field: Option<&str>) -> Option<(i32, String, String)> {
+    let hours: Vec<f64> = match exif.get_value("TimeZoneOffset")? {
+        Value::Array(values) => values.iter().filter_map(Value::as_f64).collect(),
+        Value::String(text) => text
+            .split_whitespace()
+            .map(str::parse)
+            .collect::<Result<_, _>>()
+            .ok()?,
+        value => vec![value.as_f64()?],
+    };
+    let (index, source) = match (hours.len(), chosen_field) {
+        (2.., Some("ModifyDate" | "SubSecModifyDate")) => {
+            (1, "TimeZoneOffset (paired with ModifyDate)")
+        }
+        (2.., _) => (0, "TimeZoneOffset (paired with DateTimeOriginal)"),
+        _ => (0, "TimeZoneOffset"),
+    };
+    let (secs, parsed_str) = offset_from_hours(*hours.get(index)?)?;
+    Some((secs, parsed_str, source.to_string()))
+}
+
+/// The `TimeZone` tag of action cameras with `Make` "`GoPro`": a `±HH:MM` string, or minutes
+/// with `-n`. Other makes write a `TimeZone` tag without daylight saving time, so it is not
+/// read for them.
+fn gopro_time_zone(exif: &ExifData) -> Option<(i32, String, String)> {
+    if !exif
+        .get_str("Make")
+        .is_some_and(|make| make.to_lowercase().contains("gopro"))
+    {
+        return None;
     }
-    None
+    let (secs, parsed_str) = match exif.get_value("TimeZone")? {
+        Value::String(text) => parse_offset_string(text.trim())?,
+        value => offset_from_hours(value.as_f64()? / 60.0)?,
+    };
+    Some((secs, parsed_str, "TimeZone (GoPro)".to_string()))
+}
+
+/// The offset tag that EXIF 2.32 pairs with a datetime tag. exiftool calls the EXIF
+/// `DateTimeDigitized` tag `CreateDate`, and the EXIF `DateTime` tag `ModifyDate`.
+fn paired_offset_tag(time_tag: &str) -> Option<&'static str> {
+    match time_tag {
+        "DateTimeOriginal" | "SubSecDateTimeOriginal" => Some("OffsetTimeOriginal"),
+        "CreateDate" | "SubSecCreateDate" | "DateTimeDigitized" | "SubSecTimeDigitized" => {
+            Some("OffsetTimeDigitized")
+        }
+        "ModifyDate" | "SubSecModifyDate" => Some("OffsetTime"),
+        _ => None,
+    }
+}
+
+/// Normalizes a user supplied time tag to its canonical exiftool name. Known tags are matched
+/// case-insensitively, unknown tags are passed through verbatim so newer exiftool tags work.
+fn normalize_time_tag(tag: &str) -> String {
+    let tag = tag.trim();
+    KNOWN_TIME_TAGS
+        .iter()
+        .find(|known| known.eq_ignore_ascii_case(tag))
+        .map_or_else(|| tag.to_string(), |known| (*known).to_string())
+}
+
+/// Builds the time tag priority list from an optional replacement of the default order and
+/// optional tags to try before it. Returns `None` when neither is given, meaning the default.
+pub fn resolve_time_tag_priority(
+    replace: Option<Vec<String>>,
+    prepend: Option<Vec<String>>,
+) -> Option<Vec<String>> {
+    if replace.is_none() && prepend.is_none() {
+        return None;
+    }
+    let base = replace.unwrap_or_else(|| {
+        DEFAULT_TIME_TAG_PRIORITY
+            .iter()
+            .map(ToString::to_string)
+            .collect()
+    });
+    let mut priority: Vec<String> = Vec::new();
+    for tag in prepend.into_iter().flatten().chain(base) {
+        let tag = normalize_time_tag(&tag);
+        if !tag.is_empty() && !priority.contains(&tag) {
+            priority.push(tag);
+        }
+    }
+    Some(priority)
+}
+
+/// Looks up a local time tag in the "Time" group. Unknown (custom) tags may live in any group.
+fn local_time_source<'a>(exif: &'a ExifData, field: &str) -> Option<&'a str> {
+    exif.group_str("Time", field).or_else(|| {
+        if KNOWN_TIME_TAGS.contains(&field) {
+            None
+        } else {
+            exif.get_str(field)
+        }
+    })
 }
 
-pub fn extract_time_components(exif: &ExifData) -> ExtractedTimeComponents {
-    let mut potential_utc: Option<(DateTime<Utc>, String)> = None;
-    let mut potential_explicit_offset: Option<(i32, String, String)> = None;
-    let mut potential_file_dt: Option<(DateTime<FixedOffset>, String)> = None;
+/// Extracts the raw time components from the EXIF data.
+///
+/// `options.time_tag_priority` overrides the order of [`DEFAULT_TIME_TAG_PRIORITY`] for photos.
+/// Videos always use `DateTimeOriginal`, because their `CreateDate` is UTC rather than local
+/// time. The date parsing options are passed on to [`parse_naive`].
+pub fn extract_time_components(exif: &ExifData, options: &TimeOptions) -> ExtractedTimeComponents {
+    let mut potential_utc: Option<(DateTime<Utc>, TimeSourceDetail)> = None;
 
     let mime = exif.group_str("Other", "MIMEType").unwrap_or("");
     let is_video = mime.contains("video");
+    let is_matroska = MATROSKA_MIME_TYPES.contains(&mime);
 
     // --- Best Naive Time (DateTimeOriginal, CreateDate, etc.) with SubSeconds ---
     // Video CreateDate is UTC, not local time. We exclude it from local sources for videos.
-    let local_datetime_sources_priority = if is_video {
-        vec![("Time", "DateTimeOriginal", false)]
+    // Matroska's DateTimeOriginal is UTC too, and is used as such below.
+    let local_datetime_sources_priority: Vec<&str> = if is_matroska {
+        Vec::new()
+    } else if is_video {
+        vec!["DateTimeOriginal"]
     } else {
-        vec![
-            ("Time", "SubSecDateTimeOriginal", true),
-            ("Time", "SubSecCreateDate", true),
-            ("Time", "SubSecTimeDigitized", true),
-            ("Time", "DateTimeOriginal", false),
-            ("Time", "CreateDate", false),
-            ("Time", "DateTimeDigitized", false),
-            ("Time", "SubSecModifyDate", true),
-            ("Time", "ModifyDate", false),
-        ]
+        options.time_tag_priority.as_deref().map_or_else(
+            || DEFAULT_TIME_TAG_PRIORITY.to_vec(),
+            |priority| priority.iter().map(String::as_str).collect(),
+        )
     };
 
     let mut primary_naive_candidate: Option<(NaiveDateTime, String)> = None;
     let mut found_subsecond_number_source: Option<(String, u32)> = None;
 
-    for (group, field, _is_subsec_field) in &local_datetime_sources_priority {
+    let group = "Time";
+    for field in &local_datetime_sources_priority {
         if primary_naive_candidate.is_none()
-            && let Some(dt_str) = exif.group_str(group, field)
-            && let Some((dt, parsed_subsec)) = parse_naive(dt_str)
+            && let Some(dt_str) = local_time_source(exif, field)
+            && let Some((dt, parsed_subsec)) = parse_naive(dt_str, ParseOptions::from(options))
         {
             let source_name = field.to_string();
             primary_naive_candidate = Some((dt, source_name));
@@ -102,30 +323,36 @@ pub fn extract_time_components(exif: &ExifData) -> ExtractedTimeComponents {
             break;
         }
         if primary_naive_candidate.is_some()
-            && *field == local_datetime_sources_priority.last().unwrap().1
+            && local_datetime_sources_priority
+                .last()
+                .is_some_and(|last_field| field == last_field)
         {
             break;
         }
     }
 
-    if let (Some((local_dt, source_name)), Some((subsec_source, subsec_num))) = (
-        primary_naive_candidate.as_mut(),
-        found_subsecond_number_source.as_ref(),
-    ) {
-        if subsec_source == "_ParsedFromString_" {
-            *source_name = format!("{source_name}: Parsed SubSeconds");
-        } else {
-            *local_dt = add_subseconds_from_number(*local_dt, *subsec_num);
-            *source_name = format!("{source_name} + {subsec_source}");
+    let chosen_local_field = primary_naive_candidate
+        .as_ref()
+        .map(|(_, source_name)| source_name.clone());
+    let best_local_from_exif = primary_naive_candidate.map(|(local_dt, source_name)| {
+        let source = TimeSourceDetail::from_tag(&source_name);
+        match &found_subsecond_number_source {
+            Some((subsec_source, _)) if subsec_source == "_ParsedFromString_" => {
+                (local_dt, source.with_note("parsed subseconds"))
+            }
+            Some((subsec_source, subsec_num)) => (
+                add_subseconds_from_number(local_dt, *subsec_num),
+                source.with_note(format!("with {subsec_source}")),
+            ),
+            None => (local_dt, source),
         }
-    }
-    let best_local_from_exif = primary_naive_candidate;
+    });
 
     // --- Potential UTC Time ---
     if let Some(gps_dt_str) = exif.group_str("Time", "GPSDateTime")
         && let Some(dt_utc) = parse_datetime_utc_z(gps_dt_str)
     {
-        potential_utc = Some((dt_utc, "GPSDateTime".to_string()));
+        potential_utc = Some((dt_utc, TimeSourceDetail::new(TimeSource::GpsDateTime)));
     }
 
     if potential_utc.is_none()
@@ -136,23 +363,39 @@ pub fn extract_time_components(exif: &ExifData) -> ExtractedTimeComponents {
     {
         let combined_str = format!("{date_str} {time_str}Z");
         if let Some(dt_utc) = parse_datetime_utc_z(&combined_str) {
-            potential_utc = Some((dt_utc, "GPSDateStamp/GPSTimeStamp".to_string()));
+            potential_utc = Some((dt_utc, TimeSourceDetail::new(TimeSource::GpsDateStamp)));
         }
     }
 
     // --- Potential Explicit Offset ---
-    let offset_sources_priority = [
-        ("Time", "OffsetTimeOriginal"),
-        ("Time", "OffsetTimeDigitized"),
-        ("Time", "OffsetTime"),
-    ];
-    for (group, field) in offset_sources_priority {
-        if let Some(offset_str) = exif.group_str(group, field)
-            && let Some((secs, parsed_str)) = parse_offset_string(offset_str)
-        {
-            potential_explicit_offset = Some((secs, parsed_str, field.to_string()));
-            break;
-        }
+    // EXIF 2.32 pairs each offset tag with one datetime tag, so the offset belonging to the
+    // chosen local time wins. Other offsets may be stale, and are only used when it is missing.
+    let paired_offset = chosen_local_field
+        .as_deref()
+        .and_then(|field| Some((field, paired_offset_tag(field)?)))
+        .and_then(|(field, offset_field)| {
+            let (secs, parsed_str) = parse_offset_string(exif.group_str("Time", offset_field)?)?;
+            Some((
+                secs,
+                parsed_str,
+                format!("{offset_field} (paired with {field})"),
+            ))
+        });
+    // Legacy and maker offset tags come after all standard ones.
+    let potential_explicit_offset = paired_offset
+        .or_else(|| {
+            OFFSET_TAG_PRIORITY.iter().find_map(|field| {
+                let (secs, parsed_str) = parse_offset_string(exif.group_str("Time", field)?)?;
+                Some((secs, parsed_str, (*field).to_string()))
+            })
+        })
+        .or_else(|| time_zone_offset(exif, chosen_local_field.as_deref()))
+        .or_else(|| gopro_time_zone(exif));
+
+    // --- Potential UTC from Matroska Tags ---
+    if is_matroska && potential_utc.is_none() {
+        potential_utc = matroska_utc(exif)
+            .map(|dt_utc| (dt_utc, TimeSourceDetail::new(TimeSource::MatroskaDateUtc)));
     }
 
     // --- Potential UTC from Video Tags ---
@@ -172,31 +415,22 @@ pub fn extract_time_components(exif: &ExifData) -> ExtractedTimeComponents {
                     format!("{dt_str}Z")
                 };
                 if let Some(dt_utc) = parse_datetime_utc_z(&utc_str) {
-                    potential_utc = Some((dt_utc, format!("{field} (Video UTC)")));
+                    potential_utc = Some((
+                        dt_utc,
+                        TimeSourceDetail::from_tag(field).with_note("Video UTC"),
+                    ));
                     break;
                 }
             }
         }
     }
 
-    // --- Potential File Time ---
-    let file_time_sources_priority = [
-        ("Time", "FileModifyDate"),
-        ("Time", "FileCreateDate"),
-        ("Time", "FileAccessDate"),
-    ];
-    for (group, field) in file_time_sources_priority {
-        if let Some(dt_str) = exif.group_str(group, field)
-            && let Some(dt) = parse_datetime_offset(dt_str)
-        {
-            potential_file_dt = Some((dt, field.to_string()));
-            break;
-        }
-    }
-
     // The filename is now the final fallback for best_local within the extraction step.
     let best_local = best_local_from_exif.or_else(|| parse_filename_to_naive(exif));
 
+    // --- Potential File Time ---
+    let potential_file_dt = select_file_time(exif, best_local.as_ref().map(|(local, _)| *local));
+
     ExtractedTimeComponents {
         best_local,
         potential_utc,
@@ -216,7 +450,7 @@ mod tests {
     #[test]
     fn test_extracts_nothing_from_empty_json() {
         let exif = ExifData::new(json!({}));
-        let components = extract_time_components(&exif);
+        let components = extract_time_components(&exif, &TimeOptions::default());
 
         assert!(components.best_local.is_none());
         assert!(components.potential_utc.is_none());
@@ -232,11 +466,11 @@ mod tests {
                 "FileName": "IMG_20240101_123000.jpg"
             }
         }));
-        let components = extract_time_components(&exif);
+        let components = extract_time_components(&exif, &TimeOptions::default());
 
         assert!(components.best_local.is_some());
         let (local_dt, source) = components.best_local.unwrap();
-        assert_eq!(source, "FileName");
+        assert_eq!(source.source, TimeSource::FileName);
         assert_eq!(
             local_dt,
             NaiveDate::from_ymd_opt(2024, 1, 1)
@@ -254,11 +488,11 @@ mod tests {
                 "FileName": "1597948682906.jpg"
             }
         }));
-        let components = extract_time_components(&exif);
+        let components = extract_time_components(&exif, &TimeOptions::default());
 
         assert!(components.best_local.is_some());
         let (local_dt, source) = components.best_local.unwrap();
-        assert_eq!(source, "FileName");
+        assert_eq!(source.source, TimeSource::FileName);
         assert_eq!(
             local_dt,
             NaiveDate::from_ymd_opt(2020, 8, 20)
@@ -279,11 +513,11 @@ mod tests {
                 "FileName": "IMG_20240101_123000.jpg"
             }
         }));
-        let components = extract_time_components(&exif);
+        let components = extract_time_components(&exif, &TimeOptions::default());
 
         assert!(components.best_local.is_some());
         let (local_dt, source) = components.best_local.unwrap();
-        assert_eq!(source, "DateTimeOriginal"); // Verifies EXIF was preferred
+        assert_eq!(source.source, TimeSource::DateTimeOriginal); // Verifies EXIF was preferred
         assert_eq!(
             local_dt,
             NaiveDate::from_ymd_opt(2025, 2, 2)
@@ -303,11 +537,11 @@ mod tests {
             }
         }));
 
-        let components = extract_time_components(&exif);
+        let components = extract_time_components(&exif, &TimeOptions::default());
         assert!(components.best_local.is_some());
 
         let (local_dt, source) = components.best_local.unwrap();
-        assert_eq!(source, "DateTimeOriginal");
+        assert_eq!(source.source, TimeSource::DateTimeOriginal);
         assert_eq!(
             local_dt,
             NaiveDate::from_ymd_opt(2024, 2, 2)
@@ -317,6 +551,66 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_custom_time_tag_priority() {
+        let exif = ExifData::new(json!({
+            "Time": {
+                "CreateDate": "2023:01:01 10:00:00",
+                "DateTimeDigitized": "1987:06:05 00:00:00"
+            }
+        }));
+
+        let (_, default_source) = extract_time_components(&exif, &TimeOptions::default())
+            .best_local
+            .unwrap();
+        assert_eq!(default_source.source, TimeSource::CreateDate);
+
+        let priority =
+            resolve_time_tag_priority(None, Some(vec!["datetimedigitized".to_string()])).unwrap();
+        assert_eq!(priority[0], "DateTimeDigitized");
+        assert_eq!(priority.len(), DEFAULT_TIME_TAG_PRIORITY.len());
+
+        let options = TimeOptions {
+            time_tag_priority: Some(priority),
+            ..TimeOptions::default()
+        };
+        let (local_dt, source) = extract_time_components(&exif, &options).best_local.unwrap();
+        assert_eq!(source.source, TimeSource::DateTimeDigitized);
+        assert_eq!(
+            local_dt,
+            NaiveDate::from_ymd_opt(1987, 6, 5)
+                .unwrap()
+                .and_hms_opt(0, 0, 0)
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_resolve_time_tag_priority() {
+        assert!(resolve_time_tag_priority(None, None).is_none());
+
+        let replaced = resolve_time_tag_priority(
+            Some(vec!["ModifyDate".to_string(), "XMP:MyScanDate".to_string()]),
+            None,
+        )
+        .unwrap();
+        assert_eq!(replaced, vec!["ModifyDate", "XMP:MyScanDate"]);
+    }
+
+    #[test]
+    fn test_unknown_custom_tag_is_found_in_any_group() {
+        let exif = ExifData::new(json!({
+            "Other": { "ScanDate": "1975:03:04 05:06:07" },
+            "Time": { "CreateDate": "2023:01:01 10:00:00" }
+        }));
+        let options = TimeOptions {
+            time_tag_priority: resolve_time_tag_priority(None, Some(vec!["ScanDate".to_string()])),
+            ..TimeOptions::default()
+        };
+        let (_, source) = extract_time_components(&exif, &options).best_local.unwrap();
+        assert_eq!(source.describe(), "CustomTag (ScanDate)");
+    }
+
     #[test]
     fn test_naive_time_with_parsed_subseconds() {
         // Subseconds are part of the string itself
@@ -326,10 +620,11 @@ mod tests {
             }
         }));
 
-        let components = extract_time_components(&exif);
+        let components = extract_time_components(&exif, &TimeOptions::default());
         let (local_dt, source) = components.best_local.unwrap();
 
-        assert_eq!(source, "SubSecDateTimeOriginal: Parsed SubSeconds");
+        assert_eq!(source.source, TimeSource::SubSecDateTimeOriginal);
+        assert_eq!(source.detail.as_deref(), Some("parsed subseconds"));
         assert_eq!(
             local_dt,
             NaiveDate::from_ymd_opt(2024, 3, 3)
@@ -349,11 +644,14 @@ mod tests {
             }
         }));
 
-        let components = extract_time_components(&exif);
+        let components = extract_time_components(&exif, &TimeOptions::default());
         let (local_dt, source) = components.best_local.unwrap();
 
         // Check that the source name was correctly combined
-        assert_eq!(source, "DateTimeOriginal + SubSecTimeOriginal");
+        assert_eq!(
+            source.describe(),
+            "DateTimeOriginal (with SubSecTimeOriginal)"
+        );
         assert_eq!(
             local_dt,
             NaiveDate::from_ymd_opt(2024, 4, 4)
@@ -369,9 +667,9 @@ mod tests {
         let exif_gps_dt = ExifData::new(json!({
             "Time": { "GPSDateTime": "2024:05:05 10:00:00Z" }
         }));
-        let components_1 = extract_time_components(&exif_gps_dt);
+        let components_1 = extract_time_components(&exif_gps_dt, &TimeOptions::default());
         let (utc_dt_1, source_1) = components_1.potential_utc.unwrap();
-        assert_eq!(source_1, "GPSDateTime");
+        assert_eq!(source_1.source, TimeSource::GpsDateTime);
         assert_eq!(utc_dt_1.to_rfc3339(), "2024-05-05T10:00:00+00:00");
 
         // Fallback case: GPSDateStamp + GPSTimeStamp
@@ -381,9 +679,9 @@ mod tests {
                 "GPSTimeStamp": "11:22:33"
             }
         }));
-        let components_2 = extract_time_components(&exif_gps_stamps);
+        let components_2 = extract_time_components(&exif_gps_stamps, &TimeOptions::default());
         let (utc_dt_2, source_2) = components_2.potential_utc.unwrap();
-        assert_eq!(source_2, "GPSDateStamp/GPSTimeStamp");
+        assert_eq!(source_2.source, TimeSource::GpsDateStamp);
         assert_eq!(utc_dt_2.to_rfc3339(), "2024-06-06T11:22:33+00:00");
     }
 
@@ -401,7 +699,7 @@ mod tests {
             }
         }));
 
-        let components = extract_time_components(&exif);
+        let components = extract_time_components(&exif, &TimeOptions::default());
 
         // Verify Offset Time
         assert!(components.potential_explicit_offset.is_some());
@@ -413,10 +711,60 @@ mod tests {
         // Verify File Time
         assert!(components.potential_file_dt.is_some());
         let (file_dt, file_source) = components.potential_file_dt.unwrap();
-        assert_eq!(file_source, "FileModifyDate");
+        assert_eq!(file_source.describe(), "FileModifyDate");
         assert_eq!(file_dt.to_rfc3339(), "2024-07-07T15:00:00-07:00");
     }
 
+    #[test]
+    fn test_file_time_closest_to_the_capture_time_is_used() {
+        // Restored from a backup: the create date is the day of the restore.
+        let exif = ExifData::new(json!({
+            "Time": {
+                "DateTimeOriginal": "2019:08:14 18:30:00",
+                "FileModifyDate": "2019:08:14 18:30:02+02:00",
+                "FileCreateDate": "2025:03:01 09:12:00+01:00",
+                "FileAccessDate": "2025:03:02 10:00:00+01:00"
+            }
+        }));
+        let (file_dt, file_source) = extract_time_components(&exif, &TimeOptions::default())
+            .potential_file_dt
+            .unwrap();
+        assert_eq!(
+            file_source.describe(),
+            "FileModifyDate (0 days from the capture time)"
+        );
+        assert_eq!(file_dt.offset().local_minus_utc(), 2 * 3600);
+
+        // Copied on a file system that resets the modify date, keeping the create date.
+        let exif = ExifData::new(json!({
+            "Time": {
+                "DateTimeOriginal": "2019:08:14 18:30:00",
+                "FileModifyDate": "2025:03:01 09:12:00+01:00",
+                "FileCreateDate": "2019:08:16 08:00:00+02:00"
+            }
+        }));
+        let (file_dt, file_source) = extract_time_components(&exif, &TimeOptions::default())
+            .potential_file_dt
+            .unwrap();
+        assert_eq!(
+            file_source.describe(),
+            "FileCreateDate (1 day from the capture time)"
+        );
+        assert_eq!(file_dt.offset().local_minus_utc(), 2 * 3600);
+
+        // Without a capture time, the modify date is preferred.
+        let exif = ExifData::new(json!({
+            "Time": {
+                "FileModifyDate": "2025:03:01 09:12:00+01:00",
+                "FileCreateDate": "2019:08:16 08:00:00+02:00"
+            }
+        }));
+        let (_, file_source) = extract_time_components(&exif, &TimeOptions::default())
+            .potential_file_dt
+            .unwrap();
+        assert_eq!(file_source.describe(), "FileModifyDate");
+    }
+
     #[test]
     fn test_video_create_date_is_treated_as_utc() {
         let exif = ExifData::new(json!({
@@ -431,12 +779,12 @@ mod tests {
             },
         }));
 
-        let components = extract_time_components(&exif);
+        let components = extract_time_components(&exif, &TimeOptions::default());
 
         assert!(components.is_video, "Should be identified as a video");
         assert!(components.potential_utc.is_some());
         let (utc_dt, utc_source) = components.potential_utc.unwrap();
-        assert_eq!(utc_source, "CreateDate (Video UTC)");
+        assert_eq!(utc_source.describe(), "CreateDate (Video UTC)");
         assert_eq!(utc_dt.to_rfc3339(), "2026-04-12T19:28:01+00:00");
 
         // Check Local Extraction
@@ -445,7 +793,7 @@ mod tests {
         assert!(components.best_local.is_some());
         let (local_dt, local_source) = components.best_local.unwrap();
 
-        assert_eq!(local_source, "FileName");
+        assert_eq!(local_source.source, TimeSource::FileName);
         // Verify it picked up the 19:24:36 from the PXL filename, not 19:28:01 from EXIF
         assert_eq!(local_dt.time().to_string(), "19:24:36");
 
@@ -455,4 +803,211 @@ mod tests {
             "CreateDate should not have been used as local time for a video"
         );
     }
+
+    #[test]
+    fn test_matroska_date_is_utc() {
+        for (group, tag, date) in [
+            ("Other", "DateUTC", "2023-08-14T17:05:09.000000Z"),
+            ("Video", "CreationTime", "2023-08-14 17:05:09"),
+            ("Time", "DateTimeOriginal", "2023:08:14 17:05:09Z"),
+        ] {
+            let exif = ExifData::new(json!({
+                "Other": { "MIMEType": "video/webm", "FileName": "car.webm" },
+                "Time": { "FileModifyDate": "2025:01:01 10:00:00+01:00" },
+            }));
+            let mut value = exif.into_inner();
+            value[group][tag] = json!(date);
+            let components =
+                extract_time_components(&ExifData::new(value), &TimeOptions::default());
+
+            let (utc_dt, utc_source) = components.potential_utc.unwrap();
+            assert_eq!(
+                utc_source.source,
+                TimeSource::MatroskaDateUtc,
+                "{tag} in {group}"
+            );
+            assert_eq!(utc_dt.to_rfc3339(), "2023-08-14T17:05:09+00:00");
+            assert!(
+                components.best_local.is_none(),
+                "A Matroska date is not local time"
+            );
+        }
+    }
+
+    #[test]
+    fn test_matroska_date_is_only_read_from_matroska() {
+        let exif = ExifData::new(json!({
+            "Other": { "MIMEType": "video/mp4", "DateUTC": "2023-08-14T17:05:09Z" },
+        }));
+        let components = extract_time_components(&exif, &TimeOptions::default());
+        assert!(components.potential_utc.is_none());
+    }
+
+    fn offset_for(time: &serde_json::Value) -> (i32, String) {
+        let exif = ExifData::new(json!({ "Time": time }));
+        let (secs, _, source) = extract_time_components(&exif, &TimeOptions::default())
+            .potential_explicit_offset
+            .unwrap();
+        (secs, source)
+    }
+
+    #[test]
+    fn test_offset_is_paired_with_chosen_time_tag() {
+        let (secs, source) = offset_for(&json!({
+            "DateTimeOriginal": "2024:03:01 10:00:00",
+            "OffsetTimeOriginal": "+09:00"
+        }));
+        assert_eq!(secs, 9 * 3600);
+        assert_eq!(source, "OffsetTimeOriginal (paired with DateTimeOriginal)");
+
+        let (secs, source) = offset_for(&json!({
+            "ModifyDate": "2024:03:01 10:00:00",
+            "OffsetTime": "-03:00"
+        }));
+        assert_eq!(secs, -3 * 3600);
+        assert_eq!(source, "OffsetTime (paired with ModifyDate)");
+    }
+
+    #[test]
+    fn test_stale_offset_of_blank_time_tag_is_ignored() {
+        // DateTimeOriginal is blank, so CreateDate wins and its own offset applies, not the
+        // higher priority but stale OffsetTimeOriginal.
+        let (secs, source) = offset_for(&json!({
+            "DateTimeOriginal": "0000:00:00 00:00:00",
+            "OffsetTimeOriginal": "+01:00",
+            "CreateDate": "2024:03:01 10:00:00",
+            "OffsetTimeDigitized": "+02:00",
+            "OffsetTime": "+03:00"
+        }));
+        assert_eq!(secs, 2 * 3600);
+        assert_eq!(source, "OffsetTimeDigitized (paired with CreateDate)");
+    }
+
+    #[test]
+    fn test_unpaired_offset_falls_back_to_priority_order() {
+        // CreateDate has no OffsetTimeDigitized, so the generic order picks OffsetTimeOriginal.
+        let (secs, source) = offset_for(&json!({
+            "CreateDate": "2024:03:01 10:00:00",
+            "OffsetTimeOriginal": "+01:00",
+            "OffsetTime": "+03:00"
+        }));
+        assert_eq!(secs, 3600);
+        assert_eq!(source, "OffsetTimeOriginal");
+
+        // A paired offset that doesn't parse counts as missing.
+        let (secs, source) = offset_for(&json!({
+            "DateTimeOriginal": "2024:03:01 10:00:00",
+            "OffsetTimeOriginal": "garbage",
+            "OffsetTime": "+03:00"
+        }));
+        assert_eq!(secs, 3 * 3600);
+        assert_eq!(source, "OffsetTime");
+    }
+
+    #[test]
+    fn test_legacy_time_zone_offset() {
+        let (secs, source) = offset_for(&json!({
+            "DateTimeOriginal": "2024:03:01 10:00:00",
+            "TimeZoneOffset": 9
+        }));
+        assert_eq!(secs, 9 * 3600);
+        assert_eq!(source, "TimeZoneOffset");
+
+        let (secs, _) = offset_for(&json!({
+            "DateTimeOriginal": "2024:03:01 10:00:00",
+            "TimeZoneOffset": -5
+        }));
+        assert_eq!(secs, -5 * 3600);
+
+        let exif = ExifData::new(json!({ "Time": {
+            "DateTimeOriginal": "2024:03:01 10:00:00",
+            "TimeZoneOffset": 5.5
+        }}));
+        let (secs, parsed_str, _) = extract_time_components(&exif, &TimeOptions::default())
+            .potential_explicit_offset
+            .unwrap();
+        assert_eq!(secs, 5 * 3600 + 1800);
+        assert_eq!(parsed_str, "+05:30");
+    }
+
+    #[test]
+    fn test_legacy_time_zone_offset_pair() {
+        // exiftool writes the two values separated by a space with `-n`.
+        for offsets in [json!("1 2"), json!([1, 2])] {
+            let (secs, source) = offset_for(&json!({
+                "DateTimeOriginal": "2024:03:01 10:00:00",
+                "TimeZoneOffset": offsets
+            }));
+            assert_eq!(secs, 3600);
+            assert_eq!(source, "TimeZoneOffset (paired with DateTimeOriginal)");
+
+            let (secs, source) = offset_for(&json!({
+                "ModifyDate": "2024:03:01 10:00:00",
+                "TimeZoneOffset": offsets
+            }));
+            assert_eq!(secs, 7200);
+            assert_eq!(source, "TimeZoneOffset (paired with ModifyDate)");
+        }
+    }
+
+    #[test]
+    fn test_standard_offset_wins_over_legacy_tags() {
+        let (secs, source) = offset_for(&json!({
+            "DateTimeOriginal": "2024:03:01 10:00:00",
+            "OffsetTimeOriginal": "-04:00",
+            "TimeZoneOffset": 9
+        }));
+        assert_eq!(secs, -4 * 3600);
+        assert_eq!(source, "OffsetTimeOriginal (paired with DateTimeOriginal)");
+
+        // An implausible legacy offset is ignored.
+        let exif = ExifData::new(json!({ "Time": {
+            "DateTimeOriginal": "2024:03:01 10:00:00",
+            "TimeZoneOffset": 20
+        }}));
+        assert!(
+            extract_time_components(&exif, &TimeOptions::default())
+                .potential_explicit_offset
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_gopro_time_zone() {
+        for time_zone in [json!("+02:00"), json!(120)] {
+            let (secs, source) = offset_for(&json!({
+                "Make": "GoPro",
+                "DateTimeOriginal": "2024:03:01 10:00:00",
+                "TimeZone": time_zone
+            }));
+            assert_eq!(secs, 7200);
+            assert_eq!(source, "TimeZone (GoPro)");
+        }
+
+        // Other makes' TimeZone tags leave out daylight saving time.
+        let exif = ExifData::new(json!({ "Time": {
+            "Make": "Canon",
+            "DateTimeOriginal": "2024:03:01 10:00:00",
+            "TimeZone": 60
+        }}));
+        assert!(
+            extract_time_components(&exif, &TimeOptions::default())
+                .potential_explicit_offset
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_best_local_falls_back_to_preserved_filename() {
+        let exif = ExifData::new(json!({
+            "Other": { "FileName": "IMG_0001-2.jpg" },
+            "Author": { "PreservedFileName": "IMG_20240101_123000.jpg" }
+        }));
+
+        let (local_dt, source) = extract_time_components(&exif, &TimeOptions::default())
+            .best_local
+            .unwrap();
+        assert_eq!(source.describe(), "OriginalFileName (PreservedFileName)");
+        assert_eq!(local_dt.to_string(), "2024-01-01 12:30:00");
+    }
 }
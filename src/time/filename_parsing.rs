@@ -6,6 +6,8 @@ static RE_YYYYMMDD_HHMMSS: OnceLock<Regex> = OnceLock::new();
 static RE_YYYY_MM_DD_HH_MM_SS: OnceLock<Regex> = OnceLock::new();
 static RE_UNIX_MS: OnceLock<Regex> = OnceLock::new();
 
+// The patterns are valid literals, so compiling them cannot fail.
+#[allow(clippy::unwrap_used)]
 pub fn parse_datetime_from_filename(filename: &str) -> Option<NaiveDateTime> {
     // --- Attempt 1: Standard YYYYMMDD_HHMMSS format ---
     // The `get_or_init` method ensures the Regex is compiled exactly once on its first use.
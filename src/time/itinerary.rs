@@ -0,0 +1,85 @@
+//! Timezones by date for media without GPS or offset tags, e.g. from a camera carried through
+//! several countries on one trip.
+
+use chrono::NaiveDate;
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+
+/// The timezone someone was in from `from` up to and including `to`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ItineraryEntry {
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    pub timezone: Tz,
+}
+
+impl ItineraryEntry {
+    fn contains(&self, date: NaiveDate) -> bool {
+        self.from <= date && date <= self.to
+    }
+}
+
+/// Checks that every entry ends after it starts and that no two entries overlap, as a date in
+/// both would have two timezones.
+pub fn validate_itinerary(itinerary: &[ItineraryEntry]) -> Result<(), String> {
+    if let Some(entry) = itinerary.iter().find(|entry| entry.to < entry.from) {
+        return Err(format!(
+            "itinerary entry for {} ends ({}) before it starts ({})",
+            entry.timezone, entry.to, entry.from
+        ));
+    }
+    let mut sorted: Vec<&ItineraryEntry> = itinerary.iter().collect();
+    sorted.sort_by_key(|entry| entry.from);
+    if let Some(pair) = sorted.windows(2).find(|pair| pair[1].from <= pair[0].to) {
+        return Err(format!(
+            "itinerary entries for {} ({} to {}) and {} ({} to {}) overlap",
+            pair[0].timezone, pair[0].from, pair[0].to, pair[1].timezone, pair[1].from, pair[1].to
+        ));
+    }
+    Ok(())
+}
+
+/// The timezone of the entry whose range contains `date`.
+pub fn itinerary_zone(itinerary: &[ItineraryEntry], date: NaiveDate) -> Option<Tz> {
+    itinerary
+        .iter()
+        .find(|entry| entry.contains(date))
+        .map(|entry| entry.timezone)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(from: (u32, u32), to: (u32, u32), timezone: Tz) -> ItineraryEntry {
+        ItineraryEntry {
+            from: NaiveDate::from_ymd_opt(2024, from.0, from.1).unwrap(),
+            to: NaiveDate::from_ymd_opt(2024, to.0, to.1).unwrap(),
+            timezone,
+        }
+    }
+
+    #[test]
+    fn test_validate_itinerary() {
+        let trip = [
+            entry((5, 1), (5, 10), Tz::Europe__Lisbon),
+            entry((5, 11), (5, 20), Tz::Europe__Madrid),
+        ];
+        assert_eq!(validate_itinerary(&trip), Ok(()));
+        assert_eq!(validate_itinerary(&[]), Ok(()));
+
+        // Travel days are in one place or the other, not both.
+        let overlapping = [
+            entry((5, 11), (5, 20), Tz::Europe__Madrid),
+            entry((5, 1), (5, 11), Tz::Europe__Lisbon),
+        ];
+        assert_eq!(
+            validate_itinerary(&overlapping),
+            Err("itinerary entries for Europe/Lisbon (2024-05-01 to 2024-05-11) and Europe/Madrid (2024-05-11 to 2024-05-20) overlap".to_string())
+        );
+
+        let backwards = [entry((5, 10), (5, 1), Tz::Europe__Lisbon)];
+        assert!(validate_itinerary(&backwards).is_err());
+    }
+}
@@ -1,35 +1,289 @@
 //! Core logic for determining the best time representation based on extracted components.
 
+use super::boundary::boundary_distance_km;
+use super::corrections::{TimeCorrection, find_correction};
 use super::error::TimeError;
 use super::extraction::{ExtractedTimeComponents, extract_time_components};
+use super::itinerary::itinerary_zone;
+use super::source::TimeSourceDetail;
 use crate::ExifData;
 use crate::GpsInfo;
+use crate::features::gps::CoordinatePrecision;
 use crate::time::structs::{
-    CONFIDENCE_HIGH, CONFIDENCE_LOW, CONFIDENCE_MEDIUM, SourceDetails, TimeInfo, TimeZoneInfo,
+    CONFIDENCE_HIGH, CONFIDENCE_LOW, CONFIDENCE_MEDIUM, SourceDetails, TimeInfo, TimeOptions,
+    TimeZoneInfo, format_offset,
 };
-use chrono::{FixedOffset, LocalResult, Offset, TimeZone, Utc};
+use crate::units::UtcOffset;
+use chrono::{DateTime, FixedOffset, LocalResult, NaiveDateTime, Offset, TimeZone, Utc};
 use chrono_tz::Tz;
 use std::str::FromStr;
-use tzf_rs::DefaultFinder;
+use tzf_rs::{DefaultFinder, Finder};
 
 // --- Constants specific to the logic ---
+/// The revision of the time resolution logic. Bump it when a change can alter a
+/// [`TimeInfo`](crate::TimeInfo).
+pub const TIME_LOGIC_REVISION: u32 = 7;
 const MAX_NAIVE_GPS_DIFF_SECONDS: i64 = 10;
 const MAX_SANE_TZ_OFFSET_SECONDS: i32 = 15 * 3600;
+/// The timezone source of a naive time zoned at the GPS location.
+const GPS_ZONE_SOURCE: &str = "IANA from GPS";
+/// The timezone source of a naive time zoned by the configured travel itinerary.
+pub const ITINERARY_ZONE_SOURCE: &str = "Itinerary timezone";
+/// The start of the timezone source of an offset guessed from a file system time.
+pub const GUESSED_ZONE_SOURCE_PREFIX: &str = "Guessed from ";
+/// The timezone source of a fixed offset derived from a GPS UTC time, at a location without a
+/// zone in the dataset.
+pub const DERIVED_OFFSET_SOURCE: &str = "Offset derived from GPS UTC ↔ local difference";
+/// UTC offsets are whole quarter hours.
+const OFFSET_STEP_SECONDS: i64 = 15 * 60;
 
-// --- Global Timezone Finder ---
+// --- Global Timezone Finders ---
 static FINDER: std::sync::LazyLock<DefaultFinder> = std::sync::LazyLock::new(DefaultFinder::new);
+/// Without the tile index `DefaultFinder` puts in front, which saves ~20MB but makes each
+/// lookup a polygon search.
+static LIGHT_FINDER: std::sync::LazyLock<Finder> = std::sync::LazyLock::new(Finder::new);
 
-pub fn get_time_info(exif: &ExifData, gps_info: Option<&GpsInfo>) -> Result<TimeInfo, TimeError> {
-    let components = extract_time_components(exif);
-    let time_result = apply_priority_logic(components, gps_info);
-    time_result.ok_or(TimeError::Extraction)
+pub fn get_time_info(
+    exif: &ExifData,
+    gps_info: Option<&GpsInfo>,
+    options: &TimeOptions,
+) -> Result<TimeInfo, TimeError> {
+    let mut components = extract_time_components(exif, options);
+    apply_correction(&mut components, exif, &options.corrections);
+    let now = options.now.unwrap_or_else(Utc::now);
+    let mut time_info =
+        apply_priority_logic(components, gps_info, options, now).ok_or(TimeError::Extraction)?;
+    time_info.datetime_local_iso = time_info.local_rfc3339();
+    Ok(time_info)
+}
+
+/// Shifts the times read from the camera's clock by the matching correction, if any. GPS times
+/// come from satellites, so they are left alone.
+fn apply_correction(
+    components: &mut ExtractedTimeComponents,
+    exif: &ExifData,
+    corrections: &[TimeCorrection],
+) {
+    let capture_time = components
+        .best_local
+        .as_ref()
+        .map(|(local, _)| *local)
+        .or_else(|| {
+            components
+                .potential_utc
+                .as_ref()
+                .map(|(utc, _)| utc.naive_utc())
+        });
+    let Some(correction) =
+        capture_time.and_then(|capture_time| find_correction(corrections, exif, capture_time))
+    else {
+        return;
+    };
+    if let Some((local, source)) = components.best_local.as_mut() {
+        *local += correction.offset();
+        *source = source.clone().with_note(correction.description());
+    }
+    if let Some((utc, source)) = components.potential_utc.as_mut()
+        && !source.source.is_gps()
+    {
+        *utc += correction.offset();
+        *source = source.clone().with_note(correction.description());
+    }
 }
 
 /// Applies the priority logic to extracted components and constructs the final `TimeInfo`.
+///
+/// A resulting time more than `options.future_margin` after `now` is flagged as a future
+/// timestamp with low confidence, or replaced by the file time when configured to.
 fn apply_priority_logic(
     components: ExtractedTimeComponents,
     gps_info: Option<&GpsInfo>,
+    options: &TimeOptions,
+    now: DateTime<Utc>,
 ) -> Option<TimeInfo> {
+    let potential_file_dt = components.potential_file_dt.clone();
+    let gps_zone = gps_info.and_then(|gps| gps_zone(gps, options));
+    let mut time_info = select_time_info(components, gps_info, gps_zone, options)?;
+    cap_imprecise_gps_confidence(&mut time_info, gps_info);
+    time_info.canonicalize_timezone(gps_zone);
+
+    // Naive times without a known timezone are compared as if they were UTC.
+    let instant = time_info
+        .datetime_utc
+        .unwrap_or_else(|| time_info.datetime_local.and_utc());
+    let latest_sane = now + options.future_margin;
+    if instant <= latest_sane {
+        return Some(time_info);
+    }
+
+    if options.fall_back_on_future_timestamp
+        && let Some((file_dt, file_source)) = potential_file_dt
+        && file_dt.with_timezone(&Utc) <= latest_sane
+    {
+        let mut file_time_info = file_time_info(file_dt, file_source.clone());
+        file_time_info.canonicalize_timezone(gps_zone);
+        let file_source = file_source.with_note(format!(
+            "future {} ignored",
+            time_info.source_details.time_source_display()
+        ));
+        file_time_info
+            .source_details
+            .set_time_source(file_source.source, file_source.detail);
+        file_time_info.source_details.future_timestamp = true;
+        return Some(file_time_info);
+    }
+
+    time_info.source_details.confidence = CONFIDENCE_LOW.to_string();
+    time_info.source_details.future_timestamp = true;
+    Some(time_info)
+}
+
+/// Lowers a `High` confidence to `Medium` when the timezone was only looked up at coordinates
+/// that were rounded, e.g. to whole degrees, and may lie across a timezone border. A GPS UTC
+/// time that confirmed the zone keeps the confidence high.
+fn cap_imprecise_gps_confidence(time_info: &mut TimeInfo, gps_info: Option<&GpsInfo>) {
+    let imprecise =
+        gps_info.is_some_and(|gps| gps.coordinate_precision != CoordinatePrecision::Exact);
+    let zoned_at_gps = time_info
+        .timezone
+        .as_ref()
+        .is_some_and(|timezone| timezone.source == GPS_ZONE_SOURCE);
+    if imprecise && zoned_at_gps && time_info.source_details.confidence == CONFIDENCE_HIGH {
+        time_info.source_details.confidence = CONFIDENCE_MEDIUM.to_string();
+    }
+}
+
+/// The IANA timezone name at a location, from the light finder in low-memory mode.
+fn tz_name(low_memory: bool, latitude: f64, longitude: f64) -> &'static str {
+    if low_memory {
+        LIGHT_FINDER.get_tz_name(longitude, latitude)
+    } else {
+        FINDER.get_tz_name(longitude, latitude)
+    }
+}
+
+/// The IANA timezone at a location, from the same dataset and finder the analyzer zones capture
+/// times with under `options`, so `low_memory` picks the light finder.
+///
+/// `None` where the dataset has no zone, which happens on the borders between the nautical zones
+/// of the open sea.
+///
+/// ```
+/// use chrono_tz::Tz;
+/// use media_analyzer::{TimeOptions, timezone_at};
+///
+/// let options = TimeOptions::default();
+/// assert_eq!(timezone_at(52.37, 4.89, &options), Some(Tz::Europe__Amsterdam));
+/// ```
+#[must_use]
+pub fn timezone_at(latitude: f64, longitude: f64, options: &TimeOptions) -> Option<Tz> {
+    Tz::from_str(tz_name(options.low_memory, latitude, longitude)).ok()
+}
+
+/// The nautical timezone at sea for a longitude.
+///
+/// Zones are whole hours of 15 degrees, from `Etc/GMT+12` in the far west to `Etc/GMT-12` in
+/// the far east. The `Etc` zones have inverted signs, so `Etc/GMT-1` is UTC+1.
+#[must_use]
+pub fn nautical_timezone(longitude: f64) -> Tz {
+    #[allow(clippy::cast_possible_truncation)]
+    let hours = (longitude / 15.0).round().clamp(-12.0, 12.0) as i32;
+    let name = match hours {
+        0 => "Etc/GMT".to_string(),
+        hours => format!("Etc/GMT{:+}", -hours),
+    };
+    Tz::from_str(&name).unwrap_or(Tz::Etc__GMT)
+}
+
+/// The IANA timezone at the GPS location, or the nautical zone where the dataset has none and
+/// the fallback is on. Looked up once per analysis and kept in `gps.timezone`.
+pub fn lookup_gps_zone(gps: &GpsInfo, options: &TimeOptions) -> Option<Tz> {
+    Tz::from_str(tz_name(options.low_memory, gps.latitude, gps.longitude))
+        .ok()
+        .or_else(|| {
+            options
+                .nautical_fallback
+                .then(|| nautical_timezone(gps.longitude))
+        })
+}
+
+/// The zone in `gps.timezone`, or else the one looked up at the coordinates.
+fn gps_zone(gps: &GpsInfo, options: &TimeOptions) -> Option<Tz> {
+    gps.timezone
+        .as_deref()
+        .and_then(|name| Tz::from_str(name).ok())
+        .or_else(|| lookup_gps_zone(gps, options))
+}
+
+/// Describes a zone found at the GPS location, noting how close a border with another zone is.
+fn gps_zone_info(
+    gps: &GpsInfo,
+    low_memory: bool,
+    tz: Tz,
+    offset_seconds: i32,
+    source: String,
+) -> TimeZoneInfo {
+    let boundary_distance_km = boundary_distance_km(
+        |latitude, longitude| tz_name(low_memory, latitude, longitude),
+        gps.latitude,
+        gps.longitude,
+    );
+    TimeZoneInfo {
+        name: tz.name().to_string(),
+        offset_seconds: UtcOffset(offset_seconds),
+        source,
+        boundary_distance_km,
+        near_timezone_boundary: boundary_distance_km.is_some(),
+    }
+}
+
+fn source_details(source: TimeSourceDetail, confidence: &str) -> SourceDetails {
+    SourceDetails::new(source.source, source.detail, confidence)
+}
+
+/// Builds a low confidence `TimeInfo` from only the file system time.
+fn file_time_info(file_dt: DateTime<FixedOffset>, file_source: TimeSourceDetail) -> TimeInfo {
+    let offset = file_dt.offset().fix();
+    TimeInfo {
+        datetime_utc: Some(file_dt.with_timezone(&Utc)),
+        datetime_local: file_dt.naive_local(),
+        datetime_local_iso: None,
+        timezone: Some(TimeZoneInfo {
+            name: offset.to_string(),
+            offset_seconds: offset.into(),
+            source: file_source.describe(),
+            boundary_distance_km: None,
+            near_timezone_boundary: false,
+        }),
+        source_details: source_details(file_source, CONFIDENCE_LOW),
+    }
+}
+
+/// The UTC offset between a local time and a UTC time of the same moment, when it is within
+/// `MAX_NAIVE_GPS_DIFF_SECONDS` of a whole quarter hour and a sane offset.
+fn derived_offset(local_dt: NaiveDateTime, utc_dt: DateTime<Utc>) -> Option<FixedOffset> {
+    let difference = (local_dt - utc_dt.naive_utc()).num_seconds();
+    let offset = (difference + OFFSET_STEP_SECONDS / 2).div_euclid(OFFSET_STEP_SECONDS)
+        * OFFSET_STEP_SECONDS;
+    if (difference - offset).abs() > MAX_NAIVE_GPS_DIFF_SECONDS {
+        return None;
+    }
+    i32::try_from(offset)
+        .ok()
+        .filter(|offset| offset.abs() <= MAX_SANE_TZ_OFFSET_SECONDS)
+        .and_then(FixedOffset::east_opt)
+}
+
+/// Picks the most trustworthy combination of the extracted time components. `gps_zone` is the
+/// timezone at the GPS location, `None` when it couldn't be resolved.
+fn select_time_info(
+    components: ExtractedTimeComponents,
+    gps: Option<&GpsInfo>,
+    gps_zone: Option<Tz>,
+    options: &TimeOptions,
+) -> Option<TimeInfo> {
+    let has_gps = gps.is_some();
+    let gps_zone = gps.zip(gps_zone);
     let ExtractedTimeComponents {
         best_local,
         potential_utc,
@@ -39,9 +293,8 @@ fn apply_priority_logic(
     } = components;
 
     // --- Priority 1: Confirmed UTC (Highest confidence) ---
-    if let (Some((local_dt, naive_source)), Some((gps_utc_dt, utc_source)), Some(gps)) =
-        (&best_local, &potential_utc, gps_info)
-        && let Ok(tz) = Tz::from_str(FINDER.get_tz_name(gps.longitude, gps.latitude))
+    if let (Some((local_dt, naive_source)), Some((gps_utc_dt, utc_source)), Some((gps, tz))) =
+        (&best_local, &potential_utc, gps_zone)
         && let LocalResult::Single(zoned_dt) | LocalResult::Ambiguous(zoned_dt, _) =
             tz.from_local_datetime(local_dt)
     {
@@ -52,11 +305,17 @@ fn apply_priority_logic(
         // The UTC is more trustable for video so we ignore the `diff` possibly being high in this case
         if is_video || diff.num_seconds().abs() <= MAX_NAIVE_GPS_DIFF_SECONDS {
             let offset_secs = zoned_dt.offset().fix().local_minus_utc();
-            let tz_info = TimeZoneInfo {
-                name: tz.name().to_string(),
-                offset_seconds: offset_secs,
-                source: format!("{utc_source} confirmed by {naive_source} @ GPS location"),
-            };
+            let tz_info = gps_zone_info(
+                gps,
+                options.low_memory,
+                tz,
+                offset_secs,
+                format!(
+                    "{} confirmed by {} @ GPS location",
+                    utc_source.describe(),
+                    naive_source.describe()
+                ),
+            );
             // Discard `local_dt` if it comes from video
             let datetime_local = if is_video {
                 gps_utc_dt.with_timezone(&tz).naive_local()
@@ -66,34 +325,61 @@ fn apply_priority_logic(
             return Some(TimeInfo {
                 datetime_utc: Some(*gps_utc_dt),
                 datetime_local,
+                datetime_local_iso: None,
                 timezone: Some(tz_info),
-                source_details: SourceDetails {
-                    time_source: naive_source.clone(),
-                    confidence: CONFIDENCE_HIGH.to_string(),
-                },
+                source_details: source_details(naive_source.clone(), CONFIDENCE_HIGH),
             });
         }
     }
 
+    // --- Priority 1b: Confirmed UTC at a GPS Location Without a Zone ---
+    // The zone dataset has gaps, like the borders between nautical zones. A GPS UTC time that
+    // matches the naive time up to a whole offset confirms it just as well.
+    if has_gps
+        && gps_zone.is_none()
+        && let (Some((local_dt, naive_source)), Some((gps_utc_dt, utc_source))) =
+            (&best_local, &potential_utc)
+        && utc_source.source.is_gps()
+        && let Some(offset) = derived_offset(*local_dt, *gps_utc_dt)
+    {
+        return Some(TimeInfo {
+            datetime_utc: Some(*gps_utc_dt),
+            datetime_local: *local_dt,
+            datetime_local_iso: None,
+            timezone: Some(TimeZoneInfo {
+                name: format_offset(offset.local_minus_utc()),
+                offset_seconds: offset.into(),
+                source: DERIVED_OFFSET_SOURCE.to_string(),
+                boundary_distance_km: None,
+                near_timezone_boundary: false,
+            }),
+            source_details: source_details(
+                naive_source
+                    .clone()
+                    .with_note(format!("offset from {}", utc_source.source.name())),
+                CONFIDENCE_HIGH,
+            ),
+        });
+    }
+
     if let Some((local_dt, naive_source)) = best_local {
         // --- Priority 2: Zoned Time (Naive + GPS Location) ---
-        if let Some(gps) = gps_info
-            && let Ok(tz) = Tz::from_str(FINDER.get_tz_name(gps.longitude, gps.latitude))
+        if let Some((gps, tz)) = gps_zone
             && let LocalResult::Single(zoned_dt) | LocalResult::Ambiguous(zoned_dt, _) =
                 tz.from_local_datetime(&local_dt)
         {
             return Some(TimeInfo {
                 datetime_utc: Some(zoned_dt.with_timezone(&Utc)),
                 datetime_local: local_dt,
-                timezone: Some(TimeZoneInfo {
-                    name: tz.name().to_string(),
-                    offset_seconds: zoned_dt.offset().fix().local_minus_utc(),
-                    source: "IANA from GPS".to_string(),
-                }),
-                source_details: SourceDetails {
-                    time_source: naive_source,
-                    confidence: CONFIDENCE_HIGH.to_string(),
-                },
+                datetime_local_iso: None,
+                timezone: Some(gps_zone_info(
+                    gps,
+                    options.low_memory,
+                    tz,
+                    zoned_dt.offset().fix().local_minus_utc(),
+                    GPS_ZONE_SOURCE.to_string(),
+                )),
+                source_details: source_details(naive_source, CONFIDENCE_HIGH),
             });
         }
 
@@ -106,26 +392,27 @@ fn apply_priority_logic(
             return Some(TimeInfo {
                 datetime_utc: Some(dt_with_offset.with_timezone(&Utc)),
                 datetime_local: local_dt,
+                datetime_local_iso: None,
                 timezone: Some(TimeZoneInfo {
                     name: offset_str,
-                    offset_seconds: offset_secs,
+                    offset_seconds: UtcOffset(offset_secs),
                     source: offset_source,
+                    boundary_distance_km: None,
+                    near_timezone_boundary: false,
                 }),
-                source_details: SourceDetails {
-                    time_source: naive_source,
-                    confidence: CONFIDENCE_HIGH.to_string(),
-                },
+                source_details: source_details(naive_source, CONFIDENCE_HIGH),
             });
         }
 
         // --- Priority 4: Hybrid (Local Time + Unconfirmed UTC Time) ---
         if let Some((utc_dt, utc_source)) = potential_utc {
             // Calculate offset in seconds between local and UTC datetimes
-            let offset_seconds = (local_dt - utc_dt.naive_utc()).num_seconds() as i32;
+            let offset_seconds =
+                i32::try_from((local_dt - utc_dt.naive_utc()).num_seconds()).unwrap_or(i32::MAX);
 
             // Format offset as ±HH:MM
             let sign = if offset_seconds >= 0 { '+' } else { '-' };
-            let abs_offset = offset_seconds.abs();
+            let abs_offset = offset_seconds.saturating_abs();
 
             if abs_offset <= MAX_SANE_TZ_OFFSET_SECONDS {
                 let hours = abs_offset / 3600;
@@ -135,20 +422,43 @@ fn apply_priority_logic(
                 return Some(TimeInfo {
                     datetime_utc: Some(utc_dt),
                     datetime_local: local_dt,
+                    datetime_local_iso: None,
                     timezone: Some(TimeZoneInfo {
                         name: tz_name,
-                        offset_seconds,
-                        source: utc_source.clone(),
+                        offset_seconds: UtcOffset(offset_seconds),
+                        source: utc_source.describe(),
+                        boundary_distance_km: None,
+                        near_timezone_boundary: false,
                     }),
-                    source_details: SourceDetails {
-                        time_source: format!("{naive_source} + {utc_source}"),
-                        confidence: CONFIDENCE_MEDIUM.to_string(),
-                    },
+                    source_details: source_details(
+                        naive_source.with_note(format!("offset from {}", utc_source.source.name())),
+                        CONFIDENCE_MEDIUM,
+                    ),
                 });
             }
         }
 
-        // --- Priority 5: Naive With Guessed Offset ---
+        // --- Priority 5: Naive + Itinerary Timezone (Trip Without GPS) ---
+        if let Some(tz) = itinerary_zone(&options.itinerary, local_dt.date())
+            && let LocalResult::Single(zoned_dt) | LocalResult::Ambiguous(zoned_dt, _) =
+                tz.from_local_datetime(&local_dt)
+        {
+            return Some(TimeInfo {
+                datetime_utc: Some(zoned_dt.with_timezone(&Utc)),
+                datetime_local: local_dt,
+                datetime_local_iso: None,
+                timezone: Some(TimeZoneInfo {
+                    name: tz.name().to_string(),
+                    offset_seconds: zoned_dt.offset().fix().into(),
+                    source: ITINERARY_ZONE_SOURCE.to_string(),
+                    boundary_distance_km: None,
+                    near_timezone_boundary: false,
+                }),
+                source_details: source_details(naive_source, CONFIDENCE_MEDIUM),
+            });
+        }
+
+        // --- Priority 6: Naive With Guessed Offset ---
         if let Some((file_dt, file_source)) = potential_file_dt {
             let guessed_offset = file_dt.offset().fix();
             let iso_utc = guessed_offset
@@ -159,15 +469,15 @@ fn apply_priority_logic(
             return Some(TimeInfo {
                 datetime_utc: iso_utc,
                 datetime_local: local_dt,
+                datetime_local_iso: None,
                 timezone: Some(TimeZoneInfo {
                     name: guessed_offset.to_string(),
-                    offset_seconds: guessed_offset.local_minus_utc(),
-                    source: format!("Guessed from {file_source}"),
+                    offset_seconds: guessed_offset.into(),
+                    source: format!("{GUESSED_ZONE_SOURCE_PREFIX}{}", file_source.describe()),
+                    boundary_distance_km: None,
+                    near_timezone_boundary: false,
                 }),
-                source_details: SourceDetails {
-                    time_source: naive_source,
-                    confidence: CONFIDENCE_MEDIUM.to_string(),
-                },
+                source_details: source_details(naive_source, CONFIDENCE_MEDIUM),
             });
         }
 
@@ -175,11 +485,9 @@ fn apply_priority_logic(
         return Some(TimeInfo {
             datetime_utc: None,
             datetime_local: local_dt,
+            datetime_local_iso: None,
             timezone: None,
-            source_details: SourceDetails {
-                time_source: naive_source,
-                confidence: CONFIDENCE_LOW.to_string(),
-            },
+            source_details: source_details(naive_source, CONFIDENCE_LOW),
         });
     }
 
@@ -191,30 +499,15 @@ fn apply_priority_logic(
         return Some(TimeInfo {
             datetime_utc: Some(utc_dt),
             datetime_local: utc_dt.naive_utc(),
+            datetime_local_iso: None,
             timezone: None,
-            source_details: SourceDetails {
-                time_source: utc_source,
-                confidence: CONFIDENCE_HIGH.to_string(),
-            },
+            source_details: source_details(utc_source, CONFIDENCE_HIGH),
         });
     }
 
     // --- Priority 8: File Metadata Time Only ---
     if let Some((file_dt, file_source)) = potential_file_dt {
-        let offset = file_dt.offset().fix();
-        return Some(TimeInfo {
-            datetime_utc: Some(file_dt.with_timezone(&Utc)),
-            datetime_local: file_dt.naive_local(),
-            timezone: Some(TimeZoneInfo {
-                name: offset.to_string(),
-                offset_seconds: offset.local_minus_utc(),
-                source: file_source.clone(),
-            }),
-            source_details: SourceDetails {
-                time_source: file_source,
-                confidence: CONFIDENCE_LOW.to_string(),
-            },
-        });
+        return Some(file_time_info(file_dt, file_source));
     }
 
     None
@@ -224,11 +517,13 @@ fn apply_priority_logic(
 mod tests {
     use super::*;
     use crate::ExifData;
+    use crate::features::geocode::{CachedGeocoder, DEFAULT_GEOCODE_CACHE_SIZE};
     use crate::features::gps::get_gps_info;
-    use crate::{LocationName, MediaAnalyzerError};
+    use crate::time::ItineraryEntry;
+    use crate::time::source::TimeSource;
+    use crate::{CoordinatePrecision, LocationName, MediaAnalyzerError};
     use chrono::NaiveDate;
     use exiftool::ExifTool;
-    use reverse_geocoder::ReverseGeocoder;
     use serde_json::from_str;
     use std::path::Path;
 
@@ -247,7 +542,15 @@ mod tests {
                 altitude: None,
                 image_direction: None,
                 image_direction_ref: None,
-                location: LocationName {
+                coordinate_precision: CoordinatePrecision::Exact,
+                satellites: None,
+                fix_mode: None,
+                measurement_in_progress: None,
+                fix_quality: None,
+                location_conflict: None,
+                timezone: None,
+                movement_from_previous: None,
+                location: Some(LocationName {
                     latitude: 0.0,
                     name: String::new(),
                     admin1: String::new(),
@@ -255,7 +558,7 @@ mod tests {
                     country_code: String::new(),
                     longitude: 0.,
                     country_name: None,
-                },
+                }),
             }
         }
     }
@@ -299,11 +602,11 @@ mod tests {
         let image = Path::new("assets/tz-offset-bug/IMG_20170904_101507.jpg");
         let et = ExifTool::new()?;
         let exif = ExifData::new(et.json(image, &["-n", "-g2"])?);
-        let geocoder = ReverseGeocoder::new();
+        let geocoder = CachedGeocoder::new(DEFAULT_GEOCODE_CACHE_SIZE);
         let gps_info = get_gps_info(&geocoder, &exif);
 
         // Act
-        let time_info = get_time_info(&exif, gps_info.as_ref())?;
+        let time_info = get_time_info(&exif, gps_info.as_ref(), &TimeOptions::default())?;
 
         // Assert
         println!("{time_info:?}");
@@ -320,7 +623,7 @@ mod tests {
             longitude: 6.563_036,
         };
 
-        let info = get_time_info(&exif, Some(&gps.into())).unwrap();
+        let info = get_time_info(&exif, Some(&gps.into()), &TimeOptions::default()).unwrap();
 
         // UTC time should come directly from GPSDateTime because it's confirmed.
         assert_eq!(
@@ -337,17 +640,32 @@ mod tests {
         );
         // Timezone should be identified from GPS and used for confirmation.
         assert_eq!(info.timezone.as_ref().unwrap().name, "Europe/Amsterdam");
-        assert_eq!(info.timezone.as_ref().unwrap().offset_seconds, 3600);
+        assert_eq!(
+            info.timezone.as_ref().unwrap().offset_seconds,
+            UtcOffset(3600)
+        );
         // Confidence should be high.
         assert_eq!(info.source_details.confidence, CONFIDENCE_HIGH);
+        assert_eq!(
+            info.datetime_local_iso.as_deref(),
+            Some("2017-11-06T11:03:20.123+01:00")
+        );
         assert!(info.timezone.unwrap().source.contains("confirmed by"));
+        assert_eq!(
+            info.source_details.time_source,
+            TimeSource::SubSecDateTimeOriginal
+        );
+        assert_eq!(
+            info.source_details.time_source_detail.as_deref(),
+            Some("parsed subseconds")
+        );
     }
 
     #[test]
     fn test_priority5_guessed_offset_from_pict0017() {
         let exif = get_basic_exif();
         // No GPS, no fallback timezone.
-        let info = get_time_info(&exif, None).unwrap();
+        let info = get_time_info(&exif, None, &TimeOptions::default()).unwrap();
 
         // `best_local` comes from `ModifyDate` since `DateTimeOriginal` is blank.
         assert_eq!(
@@ -359,7 +677,10 @@ mod tests {
         );
         // The offset is "guessed" from the `FileModifyDate`.
         assert_eq!(info.timezone.as_ref().unwrap().name, "+01:00");
-        assert_eq!(info.timezone.as_ref().unwrap().offset_seconds, 3600);
+        assert_eq!(
+            info.timezone.as_ref().unwrap().offset_seconds,
+            UtcOffset(3600)
+        );
         assert_eq!(info.timezone.unwrap().source, "Guessed from FileModifyDate");
         // The UTC time is calculated from the local time + guessed offset.
         assert_eq!(
@@ -368,12 +689,13 @@ mod tests {
         );
         // Confidence is Medium because the offset is a guess.
         assert_eq!(info.source_details.confidence, CONFIDENCE_MEDIUM);
+        assert_eq!(info.source_details.time_source, TimeSource::ModifyDate);
     }
 
     #[test]
     fn test_priority6_naive_with_fallback_timezone() {
         let exif = get_basic_exif();
-        let info = get_time_info(&exif, None).unwrap();
+        let info = get_time_info(&exif, None, &TimeOptions::default()).unwrap();
 
         assert_eq!(
             info.datetime_local,
@@ -399,7 +721,7 @@ mod tests {
         let exif = ExifData::new(
             from_str(r#"{ "Time": { "DateTimeOriginal": "2023-05-10 10:00:00" } }"#).unwrap(),
         );
-        let info = get_time_info(&exif, None).unwrap();
+        let info = get_time_info(&exif, None, &TimeOptions::default()).unwrap();
 
         assert_eq!(
             info.datetime_local,
@@ -413,6 +735,10 @@ mod tests {
         assert!(info.timezone.is_none());
         // Confidence is Low.
         assert_eq!(info.source_details.confidence, CONFIDENCE_LOW);
+        assert_eq!(
+            info.source_details.time_source,
+            TimeSource::DateTimeOriginal
+        );
     }
 
     #[test]
@@ -420,7 +746,7 @@ mod tests {
         let exif = ExifData::new(
             from_str(r#"{ "Time": { "GPSDateTime": "2022-08-15T18:00:00Z" } }"#).unwrap(),
         );
-        let info = get_time_info(&exif, None).unwrap();
+        let info = get_time_info(&exif, None, &TimeOptions::default()).unwrap();
 
         // UTC time is known and accurate.
         assert_eq!(
@@ -435,5 +761,593 @@ mod tests {
                 .and_hms_opt(18, 0, 0)
                 .unwrap()
         );
+        assert_eq!(info.source_details.time_source, TimeSource::GpsDateTime);
+    }
+
+    #[test]
+    fn test_priority8_stripped_file_times_only() {
+        // What exiftool reports for a file whose metadata was stripped.
+        let exif = ExifData::new(serde_json::json!({
+            "Time": {
+                "FileModifyDate": "2024:07:10 14:00:00+02:00",
+                "FileAccessDate": "2024:07:11 09:30:00+02:00",
+                "FileInodeChangeDate": "2024:07:10 14:00:05+02:00"
+            },
+            "Other": { "FileName": "export.jpg" }
+        }));
+        let info = get_time_info(&exif, None, &TimeOptions::default()).unwrap();
+
+        assert_eq!(
+            info.datetime_utc.unwrap().to_rfc3339(),
+            "2024-07-10T12:00:00+00:00"
+        );
+        assert_eq!(
+            info.datetime_local,
+            NaiveDate::from_ymd_opt(2024, 7, 10)
+                .unwrap()
+                .and_hms_opt(14, 0, 0)
+                .unwrap()
+        );
+        // The offset of the file system's clock is the only timezone there is.
+        let timezone = info.timezone.unwrap();
+        assert_eq!(timezone.name, "+02:00");
+        assert_eq!(timezone.offset_seconds, UtcOffset(7200));
+        assert_eq!(timezone.source, "FileModifyDate");
+        assert!(!timezone.near_timezone_boundary);
+        assert_eq!(info.source_details.confidence, CONFIDENCE_LOW);
+        assert_eq!(info.source_details.time_source, TimeSource::FileModifyDate);
+    }
+
+    #[test]
+    fn test_time_source_of_each_priority() {
+        let groningen: GpsInfo = MockGpsInfo {
+            latitude: 53.212_688,
+            longitude: 6.563_036,
+        }
+        .into();
+        let cases = [
+            // Priority 2: a naive time zoned at the GPS location.
+            (
+                r#"{ "Time": { "DateTimeOriginal": "2024:07:10 14:00:00" } }"#,
+                Some(&groningen),
+                TimeSource::DateTimeOriginal,
+                "DateTimeOriginal",
+            ),
+            // Priority 3: a naive time with an offset tag.
+            (
+                r#"{ "Time": { "CreateDate": "2024:07:10 14:00:00", "OffsetTimeDigitized": "+02:00" } }"#,
+                None,
+                TimeSource::CreateDate,
+                "CreateDate",
+            ),
+            // Priority 4: a naive time with an unconfirmed UTC time.
+            (
+                r#"{ "Time": { "DateTimeOriginal": "2024:07:10 14:00:00", "GPSDateStamp": "2024:07:10", "GPSTimeStamp": "12:00:00" } }"#,
+                None,
+                TimeSource::DateTimeOriginal,
+                "DateTimeOriginal (offset from GPSDateStamp)",
+            ),
+            // Priority 7: only a UTC time.
+            (
+                r#"{ "Time": { "GPSDateStamp": "2024:07:10", "GPSTimeStamp": "12:00:00" } }"#,
+                None,
+                TimeSource::GpsDateStamp,
+                "GPSDateStamp",
+            ),
+            // Priority 8: only the file time.
+            (
+                r#"{ "Time": { "FileModifyDate": "2024:07:10 14:00:00+02:00" } }"#,
+                None,
+                TimeSource::FileModifyDate,
+                "FileModifyDate",
+            ),
+            // The file name as the naive time.
+            (
+                r#"{ "Other": { "FileName": "IMG_20240710_140000.jpg" } }"#,
+                None,
+                TimeSource::FileName,
+                "FileName",
+            ),
+        ];
+        for (json, gps, source, display) in cases {
+            let exif = ExifData::new(from_str(json).unwrap());
+            let info = get_time_info(&exif, gps, &TimeOptions::default()).unwrap();
+            assert_eq!(info.source_details.time_source, source, "{json}");
+            assert_eq!(info.source_details.time_source_display(), display, "{json}");
+        }
+    }
+
+    fn future_options(fall_back: bool) -> TimeOptions {
+        TimeOptions {
+            now: Some(Utc.with_ymd_and_hms(2025, 3, 1, 12, 0, 0).unwrap()),
+            fall_back_on_future_timestamp: fall_back,
+            ..TimeOptions::default()
+        }
+    }
+
+    fn future_exif(date_time_original: &str) -> ExifData {
+        ExifData::new(serde_json::json!({
+            "Time": {
+                "DateTimeOriginal": date_time_original,
+                "OffsetTimeOriginal": "+01:00",
+                "FileModifyDate": "2025:02:26 19:14:06+01:00"
+            }
+        }))
+    }
+
+    #[test]
+    fn test_future_timestamp_is_flagged() {
+        let exif = future_exif("2035:02:26 19:14:06");
+        let info = get_time_info(&exif, None, &future_options(false)).unwrap();
+
+        assert!(info.source_details.future_timestamp);
+        assert_eq!(info.source_details.confidence, CONFIDENCE_LOW);
+        assert_eq!(
+            info.source_details.time_source,
+            TimeSource::DateTimeOriginal
+        );
+        assert_eq!(info.datetime_local.to_string(), "2035-02-26 19:14:06");
+    }
+
+    #[test]
+    fn test_future_timestamp_falls_back_to_file_time() {
+        let exif = future_exif("2035:02:26 19:14:06");
+        let info = get_time_info(&exif, None, &future_options(true)).unwrap();
+
+        assert!(info.source_details.future_timestamp);
+        assert_eq!(info.source_details.confidence, CONFIDENCE_LOW);
+        assert_eq!(info.source_details.time_source, TimeSource::FileModifyDate);
+        assert_eq!(
+            info.source_details.time_source_display(),
+            "FileModifyDate (future DateTimeOriginal ignored)"
+        );
+        assert_eq!(
+            info.datetime_utc.unwrap().to_rfc3339(),
+            "2025-02-26T18:14:06+00:00"
+        );
+    }
+
+    #[test]
+    fn test_recent_photo_is_not_a_future_timestamp() {
+        // One day after "now" is within the default 48 hour margin.
+        let exif = future_exif("2025:03:02 12:00:00");
+        let info = get_time_info(&exif, None, &future_options(true)).unwrap();
+
+        assert!(!info.source_details.future_timestamp);
+        assert_eq!(info.source_details.confidence, CONFIDENCE_HIGH);
+        assert_eq!(
+            info.source_details.time_source,
+            TimeSource::DateTimeOriginal
+        );
+    }
+
+    fn assert_utc_timezone(json: &str, gps: Option<MockGpsInfo>) -> TimeZoneInfo {
+        let exif = ExifData::new(from_str(json).unwrap());
+        let gps = gps.map(GpsInfo::from);
+        let info = get_time_info(&exif, gps.as_ref(), &TimeOptions::default()).unwrap();
+        let timezone = info.timezone.unwrap();
+        assert_eq!(timezone.name, "UTC", "unexpected timezone for {json}");
+        assert_eq!(timezone.offset_seconds, UtcOffset(0));
+        timezone
+    }
+
+    #[test]
+    fn test_zero_offset_paths_are_named_utc() {
+        // Priority 3: explicit offset tags, as "Z" and as "+00:00".
+        for offset in ["Z", "+00:00", "-0000"] {
+            let timezone = assert_utc_timezone(
+                &format!(
+                    r#"{{ "Time": {{ "DateTimeOriginal": "2023:05:10 10:00:00", "OffsetTimeOriginal": "{offset}" }} }}"#
+                ),
+                None,
+            );
+            assert_eq!(
+                timezone.source,
+                format!(
+                    "OffsetTimeOriginal (paired with DateTimeOriginal) (originally '{offset}')"
+                )
+            );
+        }
+        // Priority 4: local time equal to the unconfirmed UTC time.
+        assert_utc_timezone(
+            r#"{ "Time": { "DateTimeOriginal": "2023:05:10 10:00:00", "GPSDateTime": "2023:05:10 10:00:00Z" } }"#,
+            None,
+        );
+        // Priority 5: offset guessed from a file time in UTC.
+        assert_utc_timezone(
+            r#"{ "Time": { "DateTimeOriginal": "2023:05:10 10:00:00", "FileModifyDate": "2023:05:10 10:00:00+00:00" } }"#,
+            None,
+        );
+        // Priority 8: only the file time.
+        assert_utc_timezone(
+            r#"{ "Time": { "FileModifyDate": "2023:05:10 10:00:00+00:00" } }"#,
+            None,
+        );
+    }
+
+    #[test]
+    fn test_zero_offset_gps_zone_is_named_utc() {
+        // The open sea around 0,0 has a fixed "Etc/GMT" zone.
+        let timezone = assert_utc_timezone(
+            r#"{ "Time": { "DateTimeOriginal": "2023:05:10 10:00:00" } }"#,
+            Some(MockGpsInfo {
+                latitude: 0.0,
+                longitude: 0.0,
+            }),
+        );
+        assert!(
+            timezone
+                .source
+                .starts_with("IANA from GPS (originally 'Etc/")
+        );
+    }
+
+    #[test]
+    fn test_iana_zone_name_is_kept() {
+        // London is at UTC in winter, but is a real zone with DST rules.
+        let exif = ExifData::new(
+            from_str(r#"{ "Time": { "DateTimeOriginal": "2023:01:10 10:00:00" } }"#).unwrap(),
+        );
+        let gps = MockGpsInfo {
+            latitude: 51.5,
+            longitude: -0.12,
+        };
+        let info = get_time_info(&exif, Some(&gps.into()), &TimeOptions::default()).unwrap();
+        assert_eq!(info.timezone.unwrap().name, "Europe/London");
+    }
+
+    #[test]
+    fn test_looked_up_gps_zone_is_reused() {
+        let exif = ExifData::new(
+            from_str(r#"{ "Time": { "DateTimeOriginal": "2023:06:10 10:00:00" } }"#).unwrap(),
+        );
+        let mut gps: GpsInfo = MockGpsInfo {
+            latitude: 52.37,
+            longitude: 4.89,
+        }
+        .into();
+        let tz = lookup_gps_zone(&gps, &TimeOptions::default()).unwrap();
+        assert_eq!(tz, Tz::Europe__Amsterdam);
+
+        // The zone in `gps.timezone` wins over a new lookup at the coordinates.
+        gps.timezone = Some("Europe/Lisbon".to_string());
+        let info = get_time_info(&exif, Some(&gps), &TimeOptions::default()).unwrap();
+        let timezone = info.timezone.unwrap();
+        assert_eq!(timezone.name, "Europe/Lisbon");
+        assert_eq!(timezone.offset_seconds, UtcOffset(3600));
+    }
+
+    #[test]
+    fn test_gps_zone_near_border_is_flagged() {
+        let exif = ExifData::new(
+            from_str(r#"{ "Time": { "DateTimeOriginal": "2023:06:10 10:00:00" } }"#).unwrap(),
+        );
+        let zone_at = |latitude, longitude| {
+            let gps = MockGpsInfo {
+                latitude,
+                longitude,
+            };
+            get_time_info(&exif, Some(&gps.into()), &TimeOptions::default())
+                .unwrap()
+                .timezone
+                .unwrap()
+        };
+
+        // Kerkrade, on the Dutch-German border.
+        let border = zone_at(50.8660, 6.0710);
+        assert_eq!(border.name, "Europe/Amsterdam");
+        assert!(border.near_timezone_boundary);
+        assert!(border.boundary_distance_km.is_some_and(|d| d < 2.0));
+
+        // Utrecht, in the middle of the Netherlands.
+        let inland = zone_at(52.0907, 5.1214);
+        assert!(!inland.near_timezone_boundary);
+        assert_eq!(inland.boundary_distance_km, None);
+    }
+
+    #[test]
+    fn test_imprecise_coordinates_cap_the_zone_confidence() {
+        let exif = ExifData::new(
+            from_str(r#"{ "Time": { "DateTimeOriginal": "2023:06:10 10:00:00" } }"#).unwrap(),
+        );
+        let confidence = |coordinate_precision| {
+            let gps = GpsInfo {
+                coordinate_precision,
+                ..MockGpsInfo {
+                    latitude: 52.0,
+                    longitude: 5.0,
+                }
+                .into()
+            };
+            let info = get_time_info(&exif, Some(&gps), &TimeOptions::default()).unwrap();
+            assert_eq!(info.timezone.unwrap().name, "Europe/Amsterdam");
+            info.source_details.confidence
+        };
+
+        assert_eq!(confidence(CoordinatePrecision::Exact), CONFIDENCE_HIGH);
+        assert_eq!(
+            confidence(CoordinatePrecision::Approximate),
+            CONFIDENCE_MEDIUM
+        );
+        assert_eq!(
+            confidence(CoordinatePrecision::VeryCoarse),
+            CONFIDENCE_MEDIUM
+        );
+
+        // A GPS time confirms the zone, whatever the precision.
+        let exif = ExifData::new(
+            from_str(
+                r#"{ "Time": {
+                    "DateTimeOriginal": "2023:06:10 10:00:00",
+                    "GPSDateTime": "2023:06:10 08:00:00Z"
+                } }"#,
+            )
+            .unwrap(),
+        );
+        let gps = GpsInfo {
+            coordinate_precision: CoordinatePrecision::VeryCoarse,
+            ..MockGpsInfo {
+                latitude: 52.0,
+                longitude: 5.0,
+            }
+            .into()
+        };
+        let info = get_time_info(&exif, Some(&gps), &TimeOptions::default()).unwrap();
+        assert_eq!(info.source_details.confidence, CONFIDENCE_HIGH);
+    }
+
+    #[test]
+    fn test_low_memory_finder_gives_the_same_time() {
+        let exif = ExifData::new(
+            from_str(r#"{ "Time": { "DateTimeOriginal": "2023:06:10 10:00:00" } }"#).unwrap(),
+        );
+        let low_memory = TimeOptions {
+            low_memory: true,
+            ..TimeOptions::default()
+        };
+        // Kerkrade, Utrecht, London, New York, Sydney and the open sea.
+        for (latitude, longitude) in [
+            (50.8660, 6.0710),
+            (52.0907, 5.1214),
+            (51.5, -0.12),
+            (40.7128, -74.0060),
+            (-33.8688, 151.2093),
+            (0.0, 0.0),
+        ] {
+            let gps: GpsInfo = MockGpsInfo {
+                latitude,
+                longitude,
+            }
+            .into();
+            let default = get_time_info(&exif, Some(&gps), &TimeOptions::default()).unwrap();
+            let light = get_time_info(&exif, Some(&gps), &low_memory).unwrap();
+            assert_eq!(default, light, "at {latitude}, {longitude}");
+        }
+    }
+
+    #[test]
+    fn test_timezone_at() {
+        let options = TimeOptions::default();
+        assert_eq!(
+            timezone_at(52.3676, 4.9041, &options),
+            Some(Tz::Europe__Amsterdam)
+        );
+        // The open sea has nautical zones in the dataset, except on their borders.
+        assert_eq!(
+            timezone_at(-30.0, -140.0, &options),
+            Some(Tz::Etc__GMTPlus9)
+        );
+        assert_eq!(timezone_at(0.0, 7.5, &options), None);
+        let low_memory = TimeOptions {
+            low_memory: true,
+            ..TimeOptions::default()
+        };
+        assert_eq!(
+            timezone_at(52.3676, 4.9041, &low_memory),
+            Some(Tz::Europe__Amsterdam)
+        );
+        assert_eq!(nautical_timezone(7.5), Tz::Etc__GMTMinus1);
+        assert_eq!(nautical_timezone(-179.9), Tz::Etc__GMTPlus12);
+        assert_eq!(nautical_timezone(0.0), Tz::Etc__GMT);
+    }
+
+    #[test]
+    fn test_nautical_fallback_zones_times_at_sea() {
+        let exif = ExifData::new(
+            from_str(r#"{ "Time": { "DateTimeOriginal": "2024:07:10 14:00:00" } }"#).unwrap(),
+        );
+        // On the border between the UTC and UTC+1 nautical zones in the Gulf of Guinea.
+        let gps: GpsInfo = MockGpsInfo {
+            latitude: 0.0,
+            longitude: 7.5,
+        }
+        .into();
+        let info = get_time_info(&exif, Some(&gps), &TimeOptions::default()).unwrap();
+        assert_eq!(info.datetime_utc, None);
+        assert_eq!(info.timezone, None);
+
+        let options = TimeOptions {
+            nautical_fallback: true,
+            ..TimeOptions::default()
+        };
+        let info = get_time_info(&exif, Some(&gps), &options).unwrap();
+        assert_eq!(
+            info.datetime_utc.unwrap().to_rfc3339(),
+            "2024-07-10T13:00:00+00:00"
+        );
+        assert_eq!(info.timezone.unwrap().name, "Etc/GMT-1");
+    }
+
+    #[test]
+    fn test_offset_derived_from_gps_utc_without_zone() {
+        // The same spot in the Gulf of Guinea, without a zone, but with a GPS UTC time 2 hours
+        // and 3 seconds behind the naive time.
+        let gps: GpsInfo = MockGpsInfo {
+            latitude: 0.0,
+            longitude: 7.5,
+        }
+        .into();
+        let exif = ExifData::new(
+            from_str(
+                r#"{ "Time": { "DateTimeOriginal": "2024:07:10 14:00:00", "GPSDateTime": "2024:07:10 12:00:03Z" } }"#,
+            )
+            .unwrap(),
+        );
+        let info = get_time_info(&exif, Some(&gps), &TimeOptions::default()).unwrap();
+        assert_eq!(info.source_details.confidence, CONFIDENCE_HIGH);
+        assert_eq!(
+            info.datetime_utc.unwrap().to_rfc3339(),
+            "2024-07-10T12:00:03+00:00"
+        );
+        let timezone = info.timezone.unwrap();
+        assert_eq!(timezone.name, "+02:00");
+        assert_eq!(timezone.offset_seconds, UtcOffset(7200));
+        assert_eq!(timezone.source, DERIVED_OFFSET_SOURCE);
+        assert_eq!(
+            info.source_details.time_source,
+            TimeSource::DateTimeOriginal
+        );
+        assert_eq!(
+            info.source_details.time_source_detail.as_deref(),
+            Some("offset from GPSDateTime")
+        );
+
+        // A difference that isn't close to a whole offset doesn't confirm anything.
+        let exif = ExifData::new(
+            from_str(
+                r#"{ "Time": { "DateTimeOriginal": "2024:07:10 14:00:00", "GPSDateTime": "2024:07:10 12:07:00Z" } }"#,
+            )
+            .unwrap(),
+        );
+        let info = get_time_info(&exif, Some(&gps), &TimeOptions::default()).unwrap();
+        assert_eq!(info.source_details.confidence, CONFIDENCE_MEDIUM);
+        assert_ne!(info.timezone.unwrap().source, DERIVED_OFFSET_SOURCE);
+    }
+
+    #[test]
+    fn test_derived_offset_rounds_to_quarter_hours() {
+        let local = NaiveDate::from_ymd_opt(2024, 7, 10)
+            .unwrap()
+            .and_hms_opt(14, 0, 0)
+            .unwrap();
+        let utc = |h, m, s| local.date().and_hms_opt(h, m, s).unwrap().and_utc();
+        let seconds = |offset: Option<FixedOffset>| offset.map(|o| o.local_minus_utc());
+        assert_eq!(seconds(derived_offset(local, utc(8, 15, 5))), Some(20_700));
+        assert_eq!(
+            seconds(derived_offset(local, utc(16, 59, 55))),
+            Some(-10_800)
+        );
+        assert_eq!(seconds(derived_offset(local, utc(14, 0, 0))), Some(0));
+        assert_eq!(derived_offset(local, utc(12, 7, 30)), None);
+    }
+
+    #[test]
+    fn test_time_correction_shifts_local_time_before_timezone() {
+        let exif = ExifData::new(
+            from_str(
+                r#"{
+                "Camera": { "Model": "EOS R5", "SerialNumber": "123456" },
+                "Time": { "DateTimeOriginal": "2024:07:10 14:00:00", "OffsetTimeOriginal": "+02:00" }
+            }"#,
+            )
+            .unwrap(),
+        );
+        let options = TimeOptions {
+            corrections: vec![TimeCorrection {
+                camera_serial: Some("123456".to_string()),
+                camera_model: None,
+                applies_from: None,
+                applies_to: None,
+                offset_seconds: -3600,
+            }],
+            ..TimeOptions::default()
+        };
+        let info = get_time_info(&exif, None, &options).unwrap();
+
+        assert_eq!(
+            info.datetime_local,
+            NaiveDate::from_ymd_opt(2024, 7, 10)
+                .unwrap()
+                .and_hms_opt(13, 0, 0)
+                .unwrap()
+        );
+        assert_eq!(
+            info.datetime_utc.unwrap().to_rfc3339(),
+            "2024-07-10T11:00:00+00:00"
+        );
+        assert_eq!(
+            info.source_details.time_source_display(),
+            "DateTimeOriginal (corrected -3600s)"
+        );
+    }
+
+    #[test]
+    fn test_itinerary_zones_naive_times() {
+        let date = |month, day| NaiveDate::from_ymd_opt(2024, month, day).unwrap();
+        let options = TimeOptions {
+            itinerary: vec![
+                ItineraryEntry {
+                    from: date(5, 1),
+                    to: date(5, 10),
+                    timezone: Tz::Europe__Lisbon,
+                },
+                ItineraryEntry {
+                    from: date(5, 11),
+                    to: date(5, 20),
+                    timezone: Tz::Europe__Athens,
+                },
+                ItineraryEntry {
+                    from: date(5, 21),
+                    to: date(5, 31),
+                    timezone: Tz::Asia__Tokyo,
+                },
+            ],
+            ..TimeOptions::default()
+        };
+        let time_at = |date_time_original: &str| {
+            let exif = ExifData::new(serde_json::json!({
+                "Time": { "DateTimeOriginal": date_time_original }
+            }));
+            get_time_info(&exif, None, &options).unwrap()
+        };
+
+        for (date_time_original, zone, utc) in [
+            (
+                "2024:05:01 12:00:00",
+                "Europe/Lisbon",
+                "2024-05-01T11:00:00+00:00",
+            ),
+            (
+                "2024:05:15 12:00:00",
+                "Europe/Athens",
+                "2024-05-15T09:00:00+00:00",
+            ),
+            (
+                "2024:05:31 23:30:00",
+                "Asia/Tokyo",
+                "2024-05-31T14:30:00+00:00",
+            ),
+        ] {
+            let info = time_at(date_time_original);
+            let timezone = info.timezone.unwrap();
+            assert_eq!(timezone.name, zone);
+            assert_eq!(timezone.source, "Itinerary timezone");
+            assert_eq!(info.datetime_utc.unwrap().to_rfc3339(), utc);
+            assert_eq!(info.source_details.confidence, CONFIDENCE_MEDIUM);
+        }
+
+        // After the trip, the naive time stays naive.
+        let info = time_at("2024:06:01 12:00:00");
+        assert!(info.timezone.is_none());
+        assert!(info.datetime_utc.is_none());
+        assert_eq!(info.source_details.confidence, CONFIDENCE_LOW);
+
+        // An offset tag is more specific than the itinerary.
+        let exif = ExifData::new(serde_json::json!({
+            "Time": { "DateTimeOriginal": "2024:05:15 12:00:00", "OffsetTimeOriginal": "+02:00" }
+        }));
+        let info = get_time_info(&exif, None, &options).unwrap();
+        assert_eq!(info.timezone.unwrap().offset_seconds, UtcOffset(7200));
+        assert_eq!(info.source_details.confidence, CONFIDENCE_HIGH);
     }
 }
@@ -1,9 +1,20 @@
 //! Module for extracting and consolidating time information from media metadata.
+mod boundary;
+mod corrections;
 pub mod error;
 mod extraction;
 mod filename_parsing;
+mod itinerary;
 mod logic;
 mod parsing;
+mod source;
 pub mod structs;
 
-pub use logic::get_time_info;
+pub use corrections::{TimeCorrection, parse_time_corrections_csv};
+pub use extraction::{DEFAULT_TIME_TAG_PRIORITY, file_modify_time, resolve_time_tag_priority};
+pub use itinerary::{ItineraryEntry, validate_itinerary};
+pub use logic::lookup_gps_zone;
+pub use logic::{
+    GUESSED_ZONE_SOURCE_PREFIX, TIME_LOGIC_REVISION, get_time_info, nautical_timezone, timezone_at,
+};
+pub use source::{TIME_SOURCES, TimeSource};
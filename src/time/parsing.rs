@@ -1,28 +1,126 @@
 //! Utility functions for parsing time/date/offset strings into chrono types.
 
-use chrono::{DateTime, FixedOffset, NaiveDateTime, Timelike};
+use crate::time::structs::{NumericDateOrder, TimeOptions};
+use chrono::{DateTime, Datelike, FixedOffset, NaiveDateTime, Timelike};
 use regex::Regex;
-// Only if needed directly here, which it is for parse_offset_string
+use std::sync::LazyLock;
+
+// The pattern is a valid literal, so compiling it cannot fail.
+#[allow(clippy::unwrap_used)]
+static OFFSET_PATTERN: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^([+-])(\d{2}):?(\d{2})$").unwrap());
 
 // Make functions `pub` so they can be used by `extraction.rs` and `logic.rs`.
 
-/// Parses a naive datetime string commonly found in EXIF (YYYY:MM:DD HH:MM:SS[.fff]).
-/// Returns the `NaiveDateTime` and a boolean indicating if subseconds were present in the string.
-pub fn parse_naive(s: &str) -> Option<(NaiveDateTime, bool)> {
-    let formats = [
-        ("%Y:%m:%d %H:%M:%S%.f", true),
-        ("%Y-%m-%d %H:%M:%S%.f", true),
-        ("%Y:%m:%d %H:%M:%S", false),
-        ("%Y-%m-%d %H:%M:%S", false),
-    ];
-
-    for (fmt, has_subsecs_in_fmt) in formats {
-        if let Ok(dt) = NaiveDateTime::parse_from_str(s, fmt) {
-            let parsed_subsecs = has_subsecs_in_fmt && dt.nanosecond() != 0;
-            return Some((dt, parsed_subsecs));
+/// Naive datetime formats that can't be misread: year first, or with a month name. The flag
+/// tells whether the format has subseconds.
+const UNAMBIGUOUS_FORMATS: [(&str, bool); 16] = [
+    // EXIF, and what some tools write instead.
+    ("%Y:%m:%d %H:%M:%S%.f", true),
+    ("%Y-%m-%d %H:%M:%S%.f", true),
+    ("%Y/%m/%d %H:%M:%S%.f", true),
+    ("%Y-%m-%dT%H:%M:%S%.f", true),
+    ("%Y:%m:%d %H:%M:%S", false),
+    ("%Y-%m-%d %H:%M:%S", false),
+    ("%Y/%m/%d %H:%M:%S", false),
+    ("%Y-%m-%dT%H:%M:%S", false),
+    // Scanner software, with a 12-hour clock.
+    ("%Y:%m:%d %I:%M:%S %p", false),
+    ("%Y-%m-%d %I:%M:%S %p", false),
+    ("%Y/%m/%d %I:%M:%S %p", false),
+    // Old phones, with a day first and an English month name ("07-Mar-2019").
+    ("%d-%b-%Y %H:%M:%S", false),
+    ("%d %b %Y %H:%M:%S", false),
+    ("%d/%b/%Y %H:%M:%S", false),
+    ("%d-%b-%Y %I:%M:%S %p", false),
+    ("%d %b %Y %I:%M:%S %p", false),
+];
+
+const DAY_FIRST_FORMATS: [(&str, bool); 2] = [
+    ("%d/%m/%Y %H:%M:%S", false),
+    ("%d/%m/%Y %I:%M:%S %p", false),
+];
+
+/// Two-digit years up to this one are read as 20xx, later ones as 19xx: 1970-2069.
+const TWO_DIGIT_YEAR_PIVOT: u32 = 69;
+/// `%Y` also reads years written with fewer digits, so "04/03/07" would be the year 4. Earlier
+/// years are left to the two-digit year parsing.
+const MIN_FOUR_DIGIT_YEAR: i32 = 1000;
+
+const MONTH_FIRST_FORMATS: [(&str, bool); 2] = [
+    ("%m/%d/%Y %H:%M:%S", false),
+    ("%m/%d/%Y %I:%M:%S %p", false),
+];
+
+/// Expands the two-digit year of a year-first date ("04/03/07 09:05:02") to four digits.
+fn expand_two_digit_year(s: &str) -> Option<String> {
+    let (year, rest) = s.split_at_checked(2)?;
+    if !year.bytes().all(|b| b.is_ascii_digit()) || !rest.starts_with([':', '/', '-']) {
+        return None;
+    }
+    let year: u32 = year.parse().ok()?;
+    let century = if year <= TWO_DIGIT_YEAR_PIVOT {
+        2000
+    } else {
+        1900
+    };
+    Some(format!("{}{rest}", century + year))
+}
+
+fn parse_with_formats<'a>(
+    s: &str,
+    formats: impl IntoIterator<Item = &'a (&'a str, bool)>,
+) -> Option<(NaiveDateTime, bool)> {
+    formats.into_iter().find_map(|&(fmt, has_subsecs_in_fmt)| {
+        let dt = NaiveDateTime::parse_from_str(s, fmt)
+            .ok()
+            .filter(|dt| dt.year() >= MIN_FOUR_DIGIT_YEAR)?;
+        Some((dt, has_subsecs_in_fmt && dt.nanosecond() != 0))
+    })
+}
+
+/// How [`parse_naive`] treats ambiguous dates. The default only parses unambiguous ones.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ParseOptions {
+    /// See [`TimeOptions::numeric_date_order`].
+    pub numeric_date_order: Option<NumericDateOrder>,
+    /// See [`TimeOptions::two_digit_years`].
+    pub two_digit_years: bool,
+}
+
+impl From<&TimeOptions> for ParseOptions {
+    fn from(options: &TimeOptions) -> Self {
+        Self {
+            numeric_date_order: options.numeric_date_order,
+            two_digit_years: options.two_digit_years,
         }
     }
-    None
+}
+
+/// Parses a naive datetime string commonly found in EXIF (YYYY:MM:DD HH:MM:SS[.fff]), or one
+/// of the other [`UNAMBIGUOUS_FORMATS`]. Components without zero padding ("2004:3:7 9:5:2"),
+/// as written by some early digital cameras, are accepted too.
+/// Returns the `NaiveDateTime` and a boolean indicating if subseconds were present in the string.
+///
+/// Numeric dates with the year last ("03/07/2019") are only parsed when
+/// `options.numeric_date_order` says which comes first, as misreading them is worse than falling
+/// back to another source. Likewise, year-first dates with a two-digit year ("04/03/07") are
+/// only parsed with `options.two_digit_years`, as years 1970-2069.
+pub fn parse_naive(s: &str, options: ParseOptions) -> Option<(NaiveDateTime, bool)> {
+    let s = s.trim();
+    let numeric_formats: &[(&str, bool)] = match options.numeric_date_order {
+        Some(NumericDateOrder::DayFirst) => &DAY_FIRST_FORMATS,
+        Some(NumericDateOrder::MonthFirst) => &MONTH_FIRST_FORMATS,
+        None => &[],
+    };
+
+    parse_with_formats(s, UNAMBIGUOUS_FORMATS.iter().chain(numeric_formats)).or_else(|| {
+        let expanded = options
+            .two_digit_years
+            .then(|| expand_two_digit_year(s))
+            .flatten()?;
+        parse_with_formats(&expanded, &UNAMBIGUOUS_FORMATS)
+    })
 }
 
 /// Parses a datetime string with a timezone offset (e.g., file modification date).
@@ -53,13 +151,30 @@ pub fn parse_datetime_utc_z(s: &str) -> Option<DateTime<chrono::Utc>> {
         .map(|dt| dt.with_timezone(&chrono::Utc))
 }
 
+/// Parses a Matroska date, which is always UTC: RFC3339 (`2021-03-04T12:34:56.000000Z`), or
+/// space-separated with `-` or `:` date separators and an optional `Z` or `UTC` suffix.
+pub fn parse_matroska_utc(s: &str) -> Option<DateTime<chrono::Utc>> {
+    let s = s.trim();
+    if let Some(dt) = parse_datetime_utc_z(s) {
+        return Some(dt);
+    }
+    let naive = s
+        .strip_suffix("UTC")
+        .or_else(|| s.strip_suffix('Z'))
+        .unwrap_or(s)
+        .trim_end();
+    ["%Y-%m-%d %H:%M:%S%.f", "%Y:%m:%d %H:%M:%S%.f"]
+        .into_iter()
+        .find_map(|fmt| NaiveDateTime::parse_from_str(naive, fmt).ok())
+        .map(|dt| DateTime::<chrono::Utc>::from_naive_utc_and_offset(dt, chrono::Utc))
+}
+
 /// Parses an offset string like "+02:00", "-0500", or "Z" into offset seconds and the original string.
 pub fn parse_offset_string(offset_str: &str) -> Option<(i32, String)> {
     if offset_str == "Z" {
         return Some((0, "Z".to_string()));
     }
-    let re_offset = Regex::new(r"^([+-])(\d{2}):?(\d{2})$").ok()?;
-    if let Some(caps) = re_offset.captures(offset_str) {
+    if let Some(caps) = OFFSET_PATTERN.captures(offset_str) {
         let sign = if caps.get(1)?.as_str() == "-" { -1 } else { 1 };
         let hours = caps.get(2)?.as_str().parse::<i32>().ok()?;
         let minutes = caps.get(3)?.as_str().parse::<i32>().ok()?;
@@ -94,13 +209,36 @@ mod tests {
     use super::*;
     use chrono::NaiveDate;
 
+    #[test]
+    fn test_parse_matroska_utc() {
+        let expected = "2023-08-14T17:05:09+00:00";
+        for date in [
+            "2023-08-14T17:05:09Z",
+            "2023-08-14T17:05:09.000000Z",
+            "2023-08-14 17:05:09",
+            "2023-08-14 17:05:09 UTC",
+            "2023:08:14 17:05:09Z",
+            "2023:08:14 17:05:09",
+        ] {
+            let parsed = parse_matroska_utc(date).map(|dt| dt.to_rfc3339());
+            assert_eq!(parsed.as_deref(), Some(expected), "{date}");
+        }
+        assert!(parse_matroska_utc("not a date").is_none());
+    }
+
     // --- Tests for `parse_naive` ---
     mod parse_naive_tests {
         use super::*;
 
+        const TWO_DIGIT_YEARS: ParseOptions = ParseOptions {
+            numeric_date_order: None,
+            two_digit_years: true,
+        };
+
         #[test]
         fn parses_colon_separated_date() {
-            let (dt, has_subsec) = parse_naive("2024:01:01 10:30:00").unwrap();
+            let (dt, has_subsec) =
+                parse_naive("2024:01:01 10:30:00", ParseOptions::default()).unwrap();
             assert_eq!(
                 dt,
                 NaiveDate::from_ymd_opt(2024, 1, 1)
@@ -113,7 +251,8 @@ mod tests {
 
         #[test]
         fn parses_hyphen_separated_date() {
-            let (dt, has_subsec) = parse_naive("2024-02-02 11:00:00").unwrap();
+            let (dt, has_subsec) =
+                parse_naive("2024-02-02 11:00:00", ParseOptions::default()).unwrap();
             assert_eq!(
                 dt,
                 NaiveDate::from_ymd_opt(2024, 2, 2)
@@ -126,7 +265,8 @@ mod tests {
 
         #[test]
         fn parses_with_subseconds() {
-            let (dt, has_subsec) = parse_naive("2024:03:03 12:00:00.123").unwrap();
+            let (dt, has_subsec) =
+                parse_naive("2024:03:03 12:00:00.123", ParseOptions::default()).unwrap();
             assert_eq!(
                 dt,
                 NaiveDate::from_ymd_opt(2024, 3, 3)
@@ -137,10 +277,123 @@ mod tests {
             assert!(has_subsec);
         }
 
+        fn datetime(year: i32, month: u32, day: u32, hour: u32, min: u32) -> NaiveDateTime {
+            NaiveDate::from_ymd_opt(year, month, day)
+                .unwrap()
+                .and_hms_opt(hour, min, 0)
+                .unwrap()
+        }
+
+        #[test]
+        fn parses_slash_separated_date() {
+            let (dt, _) = parse_naive("2024/01/01 10:30:00", ParseOptions::default()).unwrap();
+            assert_eq!(dt, datetime(2024, 1, 1, 10, 30));
+        }
+
+        #[test]
+        fn parses_iso_t_separated_date() {
+            let (dt, has_subsec) =
+                parse_naive("2019-03-07T14:15:00", ParseOptions::default()).unwrap();
+            assert_eq!(dt, datetime(2019, 3, 7, 14, 15));
+            assert!(!has_subsec);
+            let (_, has_subsec) =
+                parse_naive("2019-03-07T14:15:00.5", ParseOptions::default()).unwrap();
+            assert!(has_subsec);
+        }
+
+        #[test]
+        fn parses_12_hour_clock() {
+            let parse = |s| parse_naive(s, ParseOptions::default()).unwrap().0;
+            assert_eq!(
+                parse("2019/03/07 02:15:00 PM"),
+                datetime(2019, 3, 7, 14, 15)
+            );
+            assert_eq!(parse("2019:03:07 02:15:00 am"), datetime(2019, 3, 7, 2, 15));
+            // Noon and midnight.
+            assert_eq!(parse("2019/03/07 12:00:00 PM"), datetime(2019, 3, 7, 12, 0));
+            assert_eq!(parse("2019/03/07 12:00:00 AM"), datetime(2019, 3, 7, 0, 0));
+            assert!(parse_naive("2019/03/07 13:00:00 PM", ParseOptions::default()).is_none());
+        }
+
+        #[test]
+        fn parses_day_first_month_names() {
+            let parse = |s| parse_naive(s, ParseOptions::default()).unwrap().0;
+            assert_eq!(parse("07-Mar-2019 14:15:00"), datetime(2019, 3, 7, 14, 15));
+            assert_eq!(parse("07 mar 2019 14:15:00"), datetime(2019, 3, 7, 14, 15));
+            assert_eq!(
+                parse("7-Dec-2019 02:15:00 PM"),
+                datetime(2019, 12, 7, 14, 15)
+            );
+        }
+
+        #[test]
+        fn numeric_day_month_order_is_opt_in() {
+            assert!(parse_naive("03/07/2019 10:00:00", ParseOptions::default()).is_none());
+            let (dt, _) = parse_naive(
+                "03/07/2019 10:00:00",
+                ParseOptions {
+                    numeric_date_order: Some(NumericDateOrder::DayFirst),
+                    two_digit_years: false,
+                },
+            )
+            .unwrap();
+            assert_eq!(dt, datetime(2019, 7, 3, 10, 0));
+            let (dt, _) = parse_naive(
+                "03/07/2019 10:00:00 PM",
+                ParseOptions {
+                    numeric_date_order: Some(NumericDateOrder::MonthFirst),
+                    two_digit_years: false,
+                },
+            )
+            .unwrap();
+            assert_eq!(dt, datetime(2019, 3, 7, 22, 0));
+        }
+
+        #[test]
+        fn parses_unpadded_components() {
+            let (dt, has_subsec) = parse_naive("2004:3:7 9:5:2", ParseOptions::default()).unwrap();
+            assert_eq!(
+                dt,
+                datetime(2004, 3, 7, 9, 5) + chrono::TimeDelta::seconds(2)
+            );
+            assert!(!has_subsec);
+            let (dt, has_subsec) =
+                parse_naive("2004:3:7 9:5:2.05 ", ParseOptions::default()).unwrap();
+            assert_eq!(dt.nanosecond(), 50_000_000);
+            assert!(has_subsec);
+            // Padded strings and their subseconds are read as before.
+            let (dt, _) =
+                parse_naive("2004:03:07 09:05:02.123456789", ParseOptions::default()).unwrap();
+            assert_eq!(dt.second(), 2);
+            assert_eq!(dt.nanosecond(), 123_456_789);
+            // Month 13 is invalid, padded or not.
+            assert!(parse_naive("2004:13:7 9:5:2", ParseOptions::default()).is_none());
+            assert!(parse_naive("04:13:07 09:05:02", TWO_DIGIT_YEARS).is_none());
+        }
+
+        #[test]
+        fn two_digit_years_are_opt_in() {
+            assert!(parse_naive("04/03/07 09:05:02", ParseOptions::default()).is_none());
+            let (dt, _) = parse_naive("04/03/07 09:05:02", TWO_DIGIT_YEARS).unwrap();
+            assert_eq!(
+                dt,
+                datetime(2004, 3, 7, 9, 5) + chrono::TimeDelta::seconds(2)
+            );
+            let (dt, _) = parse_naive("69:12:31 23:59:00", TWO_DIGIT_YEARS).unwrap();
+            assert_eq!(dt.year(), 2069);
+            let (dt, _) = parse_naive("70-01-01 00:00:00", TWO_DIGIT_YEARS).unwrap();
+            assert_eq!(dt.year(), 1970);
+            // Four-digit years are not mistaken for two-digit ones.
+            let (dt, _) = parse_naive("2004:03:07 09:05:02", TWO_DIGIT_YEARS).unwrap();
+            assert_eq!(dt.year(), 2004);
+            // Neither are dates with the year last.
+            assert!(parse_naive("03/07/2019 10:00:00", TWO_DIGIT_YEARS).is_none());
+        }
+
         #[test]
         fn returns_none_for_invalid_format() {
-            assert!(parse_naive("not a date").is_none());
-            assert!(parse_naive("2024/01/01 10:30:00").is_none());
+            assert!(parse_naive("not a date", ParseOptions::default()).is_none());
+            assert!(parse_naive("2024.01.01 10:30:00", ParseOptions::default()).is_none());
         }
     }
 
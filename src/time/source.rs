@@ -0,0 +1,309 @@
+//! Where a capture time came from, with codes that stay the same between versions.
+
+use serde::{Deserialize, Serialize};
+
+/// The tag or other source a capture time was read from.
+///
+/// Each source has a [`code`](TimeSource::code) that never changes, to index and filter stored
+/// results on. It serializes as its name, like "`DateTimeOriginal`". Deserializing also accepts
+/// the free-form time sources of older versions, like "`DateTimeOriginal` + `SubSecTimeOriginal`",
+/// by their leading tag.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[serde(from = "String", into = "&'static str")]
+#[repr(u16)]
+#[non_exhaustive]
+pub enum TimeSource {
+    /// Set by a hook, or a source written by a later version.
+    Other = 0,
+    DateTimeOriginal = 1,
+    SubSecDateTimeOriginal = 2,
+    CreateDate = 3,
+    SubSecCreateDate = 4,
+    DateTimeDigitized = 5,
+    SubSecTimeDigitized = 6,
+    ModifyDate = 7,
+    SubSecModifyDate = 8,
+    DateTimeCreated = 9,
+    DigitalCreationDateTime = 10,
+    MediaCreateDate = 11,
+    TrackCreateDate = 12,
+    /// A tag of a custom `time_tag_priority` that isn't one of the above. The detail names it.
+    CustomTag = 13,
+    GpsDateTime = 14,
+    /// `GPSDateStamp` and `GPSTimeStamp` combined.
+    GpsDateStamp = 15,
+    /// The date of a Matroska segment, which is UTC.
+    MatroskaDateUtc = 16,
+    /// A date in the file name.
+    FileName = 17,
+    /// A date in the original file name preserved in the metadata. The detail names the tag.
+    OriginalFileName = 18,
+    FileModifyDate = 19,
+    FileCreateDate = 20,
+    FileAccessDate = 21,
+}
+
+/// Every [`TimeSource`], in the order of their codes.
+pub const TIME_SOURCES: [TimeSource; 22] = [
+    TimeSource::Other,
+    TimeSource::DateTimeOriginal,
+    TimeSource::SubSecDateTimeOriginal,
+    TimeSource::CreateDate,
+    TimeSource::SubSecCreateDate,
+    TimeSource::DateTimeDigitized,
+    TimeSource::SubSecTimeDigitized,
+    TimeSource::ModifyDate,
+    TimeSource::SubSecModifyDate,
+    TimeSource::DateTimeCreated,
+    TimeSource::DigitalCreationDateTime,
+    TimeSource::MediaCreateDate,
+    TimeSource::TrackCreateDate,
+    TimeSource::CustomTag,
+    TimeSource::GpsDateTime,
+    TimeSource::GpsDateStamp,
+    TimeSource::MatroskaDateUtc,
+    TimeSource::FileName,
+    TimeSource::OriginalFileName,
+    TimeSource::FileModifyDate,
+    TimeSource::FileCreateDate,
+    TimeSource::FileAccessDate,
+];
+
+impl TimeSource {
+    /// The stable number of the source, for database columns and indexes.
+    ///
+    /// ```
+    /// use media_analyzer::TimeSource;
+    ///
+    /// assert_eq!(TimeSource::DateTimeOriginal.code(), 1);
+    /// assert_eq!(TimeSource::from_code(14), Some(TimeSource::GpsDateTime));
+    /// ```
+    #[must_use]
+    pub const fn code(self) -> u16 {
+        self as u16
+    }
+
+    /// The source with a [`code`](TimeSource::code), `None` for codes of later versions.
+    #[must_use]
+    pub fn from_code(code: u16) -> Option<Self> {
+        TIME_SOURCES
+            .iter()
+            .copied()
+            .find(|source| source.code() == code)
+    }
+
+    /// The exiftool name of the tag, like "`GPSDateTime`", or a name in the same style for the
+    /// sources that aren't one tag.
+    #[must_use]
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Other => "Other",
+            Self::DateTimeOriginal => "DateTimeOriginal",
+            Self::SubSecDateTimeOriginal => "SubSecDateTimeOriginal",
+            Self::CreateDate => "CreateDate",
+            Self::SubSecCreateDate => "SubSecCreateDate",
+            Self::DateTimeDigitized => "DateTimeDigitized",
+            Self::SubSecTimeDigitized => "SubSecTimeDigitized",
+            Self::ModifyDate => "ModifyDate",
+            Self::SubSecModifyDate => "SubSecModifyDate",
+            Self::DateTimeCreated => "DateTimeCreated",
+            Self::DigitalCreationDateTime => "DigitalCreationDateTime",
+            Self::MediaCreateDate => "MediaCreateDate",
+            Self::TrackCreateDate => "TrackCreateDate",
+            Self::CustomTag => "CustomTag",
+            Self::GpsDateTime => "GPSDateTime",
+            Self::GpsDateStamp => "GPSDateStamp",
+            Self::MatroskaDateUtc => "MatroskaDateUTC",
+            Self::FileName => "FileName",
+            Self::OriginalFileName => "OriginalFileName",
+            Self::FileModifyDate => "FileModifyDate",
+            Self::FileCreateDate => "FileCreateDate",
+            Self::FileAccessDate => "FileAccessDate",
+        }
+    }
+
+    /// The source of a tag name, case-insensitively. `None` for tags that aren't a source.
+    #[must_use]
+    pub fn from_name(name: &str) -> Option<Self> {
+        TIME_SOURCES
+            .iter()
+            .copied()
+            .find(|source| source.name().eq_ignore_ascii_case(name))
+    }
+
+    /// Whether the time came from the GPS receiver, whose clock is not the camera's.
+    #[must_use]
+    pub const fn is_gps(self) -> bool {
+        matches!(self, Self::GpsDateTime | Self::GpsDateStamp)
+    }
+
+    /// The name, followed by the detail in parentheses when there is one, like
+    /// "`DateTimeOriginal` (with `SubSecTimeOriginal`)".
+    #[must_use]
+    pub fn describe(self, detail: Option<&str>) -> String {
+        match detail {
+            Some(detail) => format!("{} ({detail})", self.name()),
+            None => self.name().to_string(),
+        }
+    }
+}
+
+impl From<TimeSource> for &'static str {
+    fn from(source: TimeSource) -> Self {
+        source.name()
+    }
+}
+
+impl From<String> for TimeSource {
+    /// Reads a name, or the leading tag of an older version's time source, like "`FileName`" in
+    /// "`FileName` + `CreateDate` (Video UTC)".
+    fn from(name: String) -> Self {
+        let tag = name
+            .trim()
+            .split([' ', ':', '/', '('])
+            .next()
+            .unwrap_or_default();
+        Self::from_name(tag).unwrap_or(Self::Other)
+    }
+}
+
+/// A [`TimeSource`] and notes on how the time was read from it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TimeSourceDetail {
+    pub source: TimeSource,
+    pub detail: Option<String>,
+}
+
+impl TimeSourceDetail {
+    #[must_use]
+    pub const fn new(source: TimeSource) -> Self {
+        Self {
+            source,
+            detail: None,
+        }
+    }
+
+    /// A tag read as a time source: its [`TimeSource`], or [`TimeSource::CustomTag`] naming it.
+    #[must_use]
+    pub fn from_tag(tag: &str) -> Self {
+        TimeSource::from_name(tag)
+            .filter(|source| *source != TimeSource::Other && *source != TimeSource::CustomTag)
+            .map_or_else(
+                || Self::new(TimeSource::CustomTag).with_note(tag),
+                Self::new,
+            )
+    }
+
+    /// Adds a note to the detail, after the notes already there.
+    #[must_use]
+    pub fn with_note(mut self, note: impl AsRef<str>) -> Self {
+        self.detail = Some(match self.detail {
+            Some(detail) => format!("{detail}, {}", note.as_ref()),
+            None => note.as_ref().to_string(),
+        });
+        self
+    }
+
+    #[must_use]
+    pub fn describe(&self) -> String {
+        self.source.describe(self.detail.as_deref())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_codes_never_change() {
+        // Stored results and database indexes rely on these. Add new sources at the end, never
+        // renumber or reuse a code.
+        let table: Vec<(u16, &str)> = TIME_SOURCES
+            .iter()
+            .map(|source| (source.code(), source.name()))
+            .collect();
+        assert_eq!(
+            table,
+            [
+                (0, "Other"),
+                (1, "DateTimeOriginal"),
+                (2, "SubSecDateTimeOriginal"),
+                (3, "CreateDate"),
+                (4, "SubSecCreateDate"),
+                (5, "DateTimeDigitized"),
+                (6, "SubSecTimeDigitized"),
+                (7, "ModifyDate"),
+                (8, "SubSecModifyDate"),
+                (9, "DateTimeCreated"),
+                (10, "DigitalCreationDateTime"),
+                (11, "MediaCreateDate"),
+                (12, "TrackCreateDate"),
+                (13, "CustomTag"),
+                (14, "GPSDateTime"),
+                (15, "GPSDateStamp"),
+                (16, "MatroskaDateUTC"),
+                (17, "FileName"),
+                (18, "OriginalFileName"),
+                (19, "FileModifyDate"),
+                (20, "FileCreateDate"),
+                (21, "FileAccessDate"),
+            ]
+        );
+        for source in TIME_SOURCES {
+            assert_eq!(TimeSource::from_code(source.code()), Some(source));
+        }
+        assert_eq!(TimeSource::from_code(1000), None);
+    }
+
+    #[test]
+    fn test_serializes_as_name() {
+        assert_eq!(
+            serde_json::to_value(TimeSource::GpsDateTime).unwrap(),
+            "GPSDateTime"
+        );
+        for source in TIME_SOURCES {
+            let json = serde_json::to_string(&source).unwrap();
+            assert_eq!(serde_json::from_str::<TimeSource>(&json).unwrap(), source);
+        }
+    }
+
+    #[test]
+    fn test_reads_old_time_sources() {
+        for (old, source) in [
+            (
+                "SubSecDateTimeOriginal: Parsed SubSeconds",
+                TimeSource::SubSecDateTimeOriginal,
+            ),
+            (
+                "DateTimeOriginal + SubSecTimeOriginal",
+                TimeSource::DateTimeOriginal,
+            ),
+            ("GPSDateStamp/GPSTimeStamp", TimeSource::GpsDateStamp),
+            (
+                "FileModifyDate (future DateTimeOriginal ignored)",
+                TimeSource::FileModifyDate,
+            ),
+            ("Private database", TimeSource::Other),
+        ] {
+            assert_eq!(TimeSource::from(old.to_string()), source, "{old}");
+        }
+    }
+
+    #[test]
+    fn test_unknown_tags_are_custom() {
+        let custom = TimeSourceDetail::from_tag("ScanDate");
+        assert_eq!(custom.source, TimeSource::CustomTag);
+        assert_eq!(custom.describe(), "CustomTag (ScanDate)");
+        assert_eq!(
+            TimeSourceDetail::from_tag("DateTimeOriginal").describe(),
+            "DateTimeOriginal"
+        );
+        assert_eq!(
+            TimeSourceDetail::from_tag("DateTimeOriginal")
+                .with_note("with SubSecTimeOriginal")
+                .with_note("corrected -3600s")
+                .describe(),
+            "DateTimeOriginal (with SubSecTimeOriginal, corrected -3600s)"
+        );
+    }
+}
@@ -1,20 +1,33 @@
-use chrono::{DateTime, NaiveDateTime, Utc};
+use crate::time::corrections::TimeCorrection;
+use crate::time::itinerary::ItineraryEntry;
+use crate::time::parsing::parse_offset_string;
+use crate::time::source::TimeSource;
+use crate::units::UtcOffset;
+use chrono::{DateTime, NaiveDateTime, Offset, TimeDelta, Timelike, Utc};
+use chrono_tz::Tz;
 use serde::{Deserialize, Serialize};
 
 /// Represents the extracted and consolidated time information for a media file.
-#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct TimeInfo {
     /// Timestamp guaranteed to be in UTC (ISO 8601 format with 'Z').
     /// This is the primary field for reliable date/time comparisons and storage.
     /// It's `None` if UTC could not be confidently determined.
+    #[serde(default)]
     pub datetime_utc: Option<DateTime<Utc>>,
 
     /// The best available "naive" timestamp (without timezone context) found in the metadata.
     /// This often corresponds to the camera's local time setting when the picture was taken.
     pub datetime_local: NaiveDateTime,
 
+    /// `datetime_local` with the timezone offset applied, as an RFC 3339 string
+    /// (e.g. "2017-11-06T11:03:20.123+01:00"). `None` when the timezone is unknown.
+    #[serde(default)]
+    pub datetime_local_iso: Option<String>,
+
     /// Details about the timezone context associated with `datetime_local`, if determined.
+    #[serde(default)]
     pub timezone: Option<TimeZoneInfo>,
 
     /// Information about how the time components were derived
@@ -22,8 +35,113 @@ pub struct TimeInfo {
     pub source_details: SourceDetails,
 }
 
+impl TimeInfo {
+    /// The local time with the stored offset applied, e.g. "2017-11-06T11:03:20.123+01:00".
+    /// Returns `None` when no timezone was determined.
+    #[must_use]
+    pub fn local_rfc3339(&self) -> Option<String> {
+        let timezone = self.timezone.as_ref()?;
+        Some(format!(
+            "{}{}",
+            format_naive_iso(&self.datetime_local),
+            format_offset(timezone.offset_seconds.0)
+        ))
+    }
+
+    /// The UTC time, e.g. "2017-11-06T10:03:20.123Z".
+    #[must_use]
+    pub fn utc_rfc3339(&self) -> Option<String> {
+        self.datetime_utc
+            .map(|utc| format!("{}Z", format_naive_iso(&utc.naive_utc())))
+    }
+
+    /// The most informative time string available: the local time with its offset when the
+    /// timezone is known, otherwise the naive local time without an offset.
+    #[must_use]
+    pub fn best_display_time(&self) -> String {
+        self.local_rfc3339()
+            .unwrap_or_else(|| format_naive_iso(&self.datetime_local))
+    }
+
+    /// Gives the same timezone the same name, whichever branch produced it.
+    ///
+    /// Fixed offsets ("Z", "+00:00", "-0500") and fixed UTC zones ("Etc/UTC") are renamed: to
+    /// the IANA zone at the GPS location (`gps_zone`) when its offset matches exactly, otherwise
+    /// to "UTC" for a zero offset, or "±HH:MM". The original name is kept in the `source`.
+    pub fn canonicalize_timezone(&mut self, gps_zone: Option<Tz>) {
+        let Some(timezone) = self.timezone.as_mut() else {
+            return;
+        };
+        if !is_utc_alias(&timezone.name) && parse_offset_string(&timezone.name).is_none() {
+            return;
+        }
+        let matching_zone = gps_zone.filter(|tz| {
+            self.datetime_utc.is_some_and(|utc| {
+                utc.with_timezone(tz).offset().fix().local_minus_utc() == timezone.offset_seconds.0
+            })
+        });
+        let name = match matching_zone {
+            Some(tz) if !is_utc_alias(tz.name()) => tz.name().to_string(),
+            _ if timezone.offset_seconds == UtcOffset(0) => UTC_ZONE_NAME.to_string(),
+            _ => format_offset(timezone.offset_seconds.0),
+        };
+        if name != timezone.name {
+            timezone.source = format!("{} (originally '{}')", timezone.source, timezone.name);
+            timezone.name = name;
+        }
+    }
+}
+
+/// The name every zero offset timezone is reported under.
+pub const UTC_ZONE_NAME: &str = "UTC";
+
+/// Names of zones that are always at UTC, compared case-insensitively.
+const UTC_ALIASES: [&str; 14] = [
+    "utc",
+    "z",
+    "zulu",
+    "uct",
+    "universal",
+    "gmt",
+    "gmt0",
+    "etc/utc",
+    "etc/uct",
+    "etc/universal",
+    "etc/zulu",
+    "etc/gmt",
+    "etc/gmt0",
+    "etc/greenwich",
+];
+
+fn is_utc_alias(name: &str) -> bool {
+    UTC_ALIASES.contains(&name.to_lowercase().as_str())
+}
+
+/// Formats a naive datetime as ISO 8601, with sub-seconds truncated to milliseconds and trailing
+/// zeros trimmed (".120" becomes ".12", ".000" is omitted).
+fn format_naive_iso(datetime: &NaiveDateTime) -> String {
+    let base = datetime.format("%Y-%m-%dT%H:%M:%S").to_string();
+    let millis = datetime.nanosecond() % 1_000_000_000 / 1_000_000;
+    if millis == 0 {
+        return base;
+    }
+    let fraction = format!("{millis:03}");
+    format!("{base}.{}", fraction.trim_end_matches('0'))
+}
+
+/// Formats an offset in seconds as `±HH:MM`.
+pub fn format_offset(offset_seconds: i32) -> String {
+    let sign = if offset_seconds < 0 { '-' } else { '+' };
+    let abs_offset = offset_seconds.unsigned_abs();
+    format!(
+        "{sign}{:02}:{:02}",
+        abs_offset / 3600,
+        (abs_offset % 3600) / 60
+    )
+}
+
 /// Contains details about the timezone determination.
-#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct TimeZoneInfo {
     /// The name or representation of the timezone.
@@ -32,22 +150,300 @@ pub struct TimeZoneInfo {
     pub name: String,
     /// The offset from UTC in seconds.
     /// For IANA zones, this accounts for DST at that time.
-    pub offset_seconds: i32,
+    pub offset_seconds: UtcOffset,
     /// Describes how the timezone information was obtained (e.g., "IANA from GPS", "`OffsetTimeOriginal`").
     pub source: String,
+    /// For zones derived from the GPS location, the estimated distance to a border with another
+    /// zone, when one is within 5km. GPS error may have put the location in the wrong zone.
+    #[serde(default)]
+    pub boundary_distance_km: Option<f64>,
+    /// Whether `boundary_distance_km` is set.
+    #[serde(default)]
+    pub near_timezone_boundary: bool,
 }
 
 /// Provides context on the origin and reliability of the extracted time information.
+///
+/// Serialized with a `timeSourceDisplay` as well, see [`SourceDetails::time_source_display`].
 #[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
-#[serde(rename_all = "camelCase")]
+#[serde(from = "RawSourceDetails", into = "SerializedSourceDetails")]
 pub struct SourceDetails {
-    pub time_source: String,
+    /// The tag the capture time was read from. See [`TimeSource::code`] for a number to index.
+    pub time_source: TimeSource,
+    /// How the time was read from `time_source`, like the subsecond tag it was combined with,
+    /// or the name of a custom tag.
+    pub time_source_detail: Option<String>,
     /// An indicator of the overall reliability of the `TimeInfo` structure,
     /// especially the `datetime_utc` and `timezone` fields.
     pub confidence: String, // e.g., "High", "Medium", "Low"
+    /// The metadata claimed a capture time in the future (e.g. a camera with a wrongly set
+    /// clock). The confidence is lowered, or the time was replaced by the file time.
+    pub future_timestamp: bool,
+}
+
+impl SourceDetails {
+    #[must_use]
+    pub fn new(time_source: TimeSource, detail: Option<String>, confidence: &str) -> Self {
+        Self {
+            time_source,
+            time_source_detail: detail,
+            confidence: confidence.to_string(),
+            future_timestamp: false,
+        }
+    }
+
+    /// Replaces the time source and its detail.
+    pub fn set_time_source(&mut self, time_source: TimeSource, detail: Option<String>) {
+        self.time_source = time_source;
+        self.time_source_detail = detail;
+    }
+
+    /// `time_source` with its detail, for humans, like "`DateTimeOriginal` (with
+    /// `SubSecTimeOriginal`)".
+    #[must_use]
+    pub fn time_source_display(&self) -> String {
+        self.time_source
+            .describe(self.time_source_detail.as_deref())
+    }
+}
+
+/// [`SourceDetails`] as stored. Results of older versions have only a free-form `timeSource`,
+/// whose text after the leading tag becomes the detail.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawSourceDetails {
+    time_source: String,
+    #[serde(default)]
+    time_source_detail: Option<String>,
+    confidence: String,
+    #[serde(default)]
+    future_timestamp: bool,
+}
+
+impl From<RawSourceDetails> for SourceDetails {
+    fn from(raw: RawSourceDetails) -> Self {
+        let time_source = TimeSource::from(raw.time_source.clone());
+        let legacy_detail = || {
+            let text = raw.time_source.trim();
+            let tag_end = text.find([' ', ':', '/', '(']).unwrap_or(text.len());
+            // An unknown tag is kept whole.
+            let rest = match TimeSource::from_name(&text[..tag_end]) {
+                Some(_) => &text[tag_end..],
+                None => text,
+            };
+            let rest = rest.trim_start_matches([' ', ':', '/', '+']).trim();
+            (!rest.is_empty()).then(|| rest.to_string())
+        };
+        Self {
+            time_source_detail: raw.time_source_detail.or_else(legacy_detail),
+            time_source,
+            confidence: raw.confidence,
+            future_timestamp: raw.future_timestamp,
+        }
+    }
+}
+
+/// [`SourceDetails`] as written, with the display text derived from the source.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SerializedSourceDetails {
+    time_source: TimeSource,
+    time_source_detail: Option<String>,
+    time_source_display: String,
+    confidence: String,
+    future_timestamp: bool,
+}
+
+impl From<SourceDetails> for SerializedSourceDetails {
+    fn from(details: SourceDetails) -> Self {
+        Self {
+            time_source_display: details.time_source_display(),
+            time_source: details.time_source,
+            time_source_detail: details.time_source_detail,
+            confidence: details.confidence,
+            future_timestamp: details.future_timestamp,
+        }
+    }
+}
+
+/// Default margin before a capture time counts as "in the future", to allow for timezone
+/// mistakes and slightly fast camera clocks.
+pub const DEFAULT_FUTURE_TIMESTAMP_MARGIN: TimeDelta = TimeDelta::hours(48);
+
+/// Which comes first in numeric dates with the year last, like "03/07/2019".
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum NumericDateOrder {
+    /// 3 July, as in most of the world.
+    DayFirst,
+    /// March 7, as in the US.
+    MonthFirst,
+}
+
+/// Configuration for how the capture time is determined.
+#[derive(Debug, Clone)]
+#[allow(clippy::struct_excessive_bools)]
+pub struct TimeOptions {
+    /// Overrides the order of the local time tags, see
+    /// [`DEFAULT_TIME_TAG_PRIORITY`](crate::DEFAULT_TIME_TAG_PRIORITY).
+    pub time_tag_priority: Option<Vec<String>>,
+    /// Capture times later than `now + future_margin` are flagged as future timestamps.
+    pub future_margin: TimeDelta,
+    /// Replace a future capture time with the file time, when that is not in the future itself.
+    pub fall_back_on_future_timestamp: bool,
+    /// The reference time for the future timestamp check. `None` means the current time.
+    pub now: Option<DateTime<Utc>>,
+    /// Clock corrections; the most specific matching one shifts the camera's clock time.
+    pub corrections: Vec<TimeCorrection>,
+    /// Look up GPS timezones with a finder that uses ~20MB less memory, but is slower. Zones
+    /// found are the same.
+    pub low_memory: bool,
+    /// How to read numeric dates with the year last. `None` leaves them unparsed, as they are
+    /// ambiguous.
+    pub numeric_date_order: Option<NumericDateOrder>,
+    /// Read year-first dates with a two-digit year, like "04/03/07", as years 1970-2069. Off by
+    /// default, as they are ambiguous.
+    pub two_digit_years: bool,
+    /// Timezones by date range, for naive times without a GPS location, offset tag or UTC
+    /// time. Ranges must not overlap.
+    pub itinerary: Vec<ItineraryEntry>,
+    /// Zone times at sea positions without a timezone in the dataset in the nautical zone of
+    /// their longitude, see [`nautical_timezone`](crate::nautical_timezone).
+    pub nautical_fallback: bool,
+}
+
+impl Default for TimeOptions {
+    fn default() -> Self {
+        Self {
+            time_tag_priority: None,
+            future_margin: DEFAULT_FUTURE_TIMESTAMP_MARGIN,
+            fall_back_on_future_timestamp: false,
+            now: None,
+            corrections: Vec::new(),
+            low_memory: false,
+            numeric_date_order: None,
+            two_digit_years: false,
+            itinerary: Vec::new(),
+            nautical_fallback: false,
+        }
+    }
 }
 
 // Confidence level constants
 pub const CONFIDENCE_HIGH: &str = "High"; // GPS UTC, Confirmed UTC, Zoned, Explicit Fixed Offset
-pub const CONFIDENCE_MEDIUM: &str = "Medium"; // Naive + Guessed Offset
+pub const CONFIDENCE_MEDIUM: &str = "Medium"; // Naive + Guessed Offset or Itinerary Zone
 pub const CONFIDENCE_LOW: &str = "Low"; // Naive Only, Filename
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn time_info(offset_seconds: Option<i32>, micros: u32) -> TimeInfo {
+        let datetime_local = NaiveDate::from_ymd_opt(2017, 11, 6)
+            .unwrap()
+            .and_hms_micro_opt(11, 3, 20, micros)
+            .unwrap();
+        TimeInfo {
+            datetime_utc: offset_seconds.map(|secs| {
+                (datetime_local - chrono::Duration::seconds(i64::from(secs))).and_utc()
+            }),
+            datetime_local,
+            datetime_local_iso: None,
+            timezone: offset_seconds.map(|offset_seconds| TimeZoneInfo {
+                name: "Test".to_string(),
+                offset_seconds: UtcOffset(offset_seconds),
+                source: "Test".to_string(),
+                boundary_distance_km: None,
+                near_timezone_boundary: false,
+            }),
+            source_details: SourceDetails::new(TimeSource::Other, None, CONFIDENCE_HIGH),
+        }
+    }
+
+    #[test]
+    fn test_positive_offset() {
+        let info = time_info(Some(3600), 123_953);
+        assert_eq!(
+            info.local_rfc3339().unwrap(),
+            "2017-11-06T11:03:20.123+01:00"
+        );
+        assert_eq!(info.utc_rfc3339().unwrap(), "2017-11-06T10:03:20.123Z");
+        assert_eq!(info.best_display_time(), "2017-11-06T11:03:20.123+01:00");
+    }
+
+    #[test]
+    fn test_negative_offset_with_minutes() {
+        let info = time_info(Some(-(3 * 3600 + 30 * 60)), 0);
+        assert_eq!(info.local_rfc3339().unwrap(), "2017-11-06T11:03:20-03:30");
+        assert_eq!(info.utc_rfc3339().unwrap(), "2017-11-06T14:33:20Z");
+    }
+
+    #[test]
+    fn test_no_timezone() {
+        let info = time_info(None, 0);
+        assert!(info.local_rfc3339().is_none());
+        assert!(info.utc_rfc3339().is_none());
+        assert_eq!(info.best_display_time(), "2017-11-06T11:03:20");
+    }
+
+    #[test]
+    fn test_subsecond_trimming() {
+        assert_eq!(
+            time_info(None, 500_000).best_display_time(),
+            "2017-11-06T11:03:20.5"
+        );
+        assert_eq!(
+            time_info(None, 120_999).best_display_time(),
+            "2017-11-06T11:03:20.12"
+        );
+        assert_eq!(
+            time_info(None, 999).best_display_time(),
+            "2017-11-06T11:03:20"
+        );
+    }
+
+    fn with_timezone(name: &str, offset_seconds: i32) -> TimeInfo {
+        let mut info = time_info(Some(offset_seconds), 0);
+        info.timezone.as_mut().unwrap().name = name.to_string();
+        info
+    }
+
+    #[test]
+    fn test_canonicalize_zero_offset_names() {
+        for name in ["UTC", "Z", "+00:00", "-0000", "Etc/UTC", "etc/gmt"] {
+            let mut info = with_timezone(name, 0);
+            info.canonicalize_timezone(None);
+            let timezone = info.timezone.unwrap();
+            assert_eq!(timezone.name, UTC_ZONE_NAME);
+            assert_eq!(timezone.offset_seconds, UtcOffset(0));
+            if name != UTC_ZONE_NAME {
+                assert_eq!(timezone.source, format!("Test (originally '{name}')"));
+            }
+        }
+    }
+
+    #[test]
+    fn test_canonicalize_fixed_offset_format() {
+        let mut info = with_timezone("+0530", 19_800);
+        info.canonicalize_timezone(None);
+        assert_eq!(info.timezone.unwrap().name, "+05:30");
+    }
+
+    #[test]
+    fn test_canonicalize_uses_matching_gps_zone() {
+        let mut info = with_timezone("+01:00", 3600);
+        info.canonicalize_timezone(Some(chrono_tz::Europe::Amsterdam));
+        assert_eq!(info.timezone.unwrap().name, "Europe/Amsterdam");
+
+        // In winter, Amsterdam is not at +02:00.
+        let mut info = with_timezone("+02:00", 7200);
+        info.canonicalize_timezone(Some(chrono_tz::Europe::Amsterdam));
+        assert_eq!(info.timezone.unwrap().name, "+02:00");
+
+        // IANA names are left alone.
+        let mut info = with_timezone("Europe/London", 0);
+        info.canonicalize_timezone(Some(chrono_tz::Europe::London));
+        assert_eq!(info.timezone.unwrap().name, "Europe/London");
+    }
+}
@@ -0,0 +1,156 @@
+//! Newtypes for the numbers in the public API that carry a unit, so a radius in meters or a
+//! `Duration` can't be passed where kilometers are expected.
+//!
+//! Each serializes as its inner number, so the JSON of a result is the same as with a plain
+//! number.
+//!
+//! ```compile_fail
+//! # use media_analyzer::MediaAnalyzer;
+//! # use std::time::Duration;
+//! let builder = MediaAnalyzer::builder().weather_search_radius(Duration::from_secs(50));
+//! ```
+
+use chrono::{FixedOffset, TimeDelta};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// A distance in kilometers.
+#[derive(Debug, Clone, Copy, Default, PartialEq, PartialOrd, Deserialize, Serialize)]
+#[serde(transparent)]
+pub struct Kilometers(pub f64);
+
+impl From<f64> for Kilometers {
+    fn from(km: f64) -> Self {
+        Self(km)
+    }
+}
+
+impl From<Kilometers> for f64 {
+    fn from(km: Kilometers) -> Self {
+        km.0
+    }
+}
+
+/// A length of time in seconds, with a fraction.
+#[derive(Debug, Clone, Copy, Default, PartialEq, PartialOrd, Deserialize, Serialize)]
+#[serde(transparent)]
+pub struct Seconds(pub f64);
+
+impl Seconds {
+    /// As a `Duration`, or `None` when negative or not finite.
+    #[must_use]
+    pub fn to_duration(self) -> Option<Duration> {
+        Duration::try_from_secs_f64(self.0).ok()
+    }
+}
+
+impl From<f64> for Seconds {
+    fn from(seconds: f64) -> Self {
+        Self(seconds)
+    }
+}
+
+impl From<Seconds> for f64 {
+    fn from(seconds: Seconds) -> Self {
+        seconds.0
+    }
+}
+
+impl From<Duration> for Seconds {
+    fn from(duration: Duration) -> Self {
+        Self(duration.as_secs_f64())
+    }
+}
+
+/// An offset from UTC in seconds, positive east of Greenwich.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize, Serialize,
+)]
+#[serde(transparent)]
+pub struct UtcOffset(pub i32);
+
+impl UtcOffset {
+    /// As a chrono offset, or `None` when it is a day or more.
+    #[must_use]
+    pub fn fixed_offset(self) -> Option<FixedOffset> {
+        FixedOffset::east_opt(self.0)
+    }
+}
+
+impl From<i32> for UtcOffset {
+    fn from(seconds: i32) -> Self {
+        Self(seconds)
+    }
+}
+
+impl From<UtcOffset> for i32 {
+    fn from(offset: UtcOffset) -> Self {
+        offset.0
+    }
+}
+
+impl From<FixedOffset> for UtcOffset {
+    fn from(offset: FixedOffset) -> Self {
+        Self(offset.local_minus_utc())
+    }
+}
+
+/// A length of time in microseconds.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize, Serialize,
+)]
+#[serde(transparent)]
+pub struct Microseconds(pub i64);
+
+impl Microseconds {
+    /// As a chrono `TimeDelta`.
+    #[must_use]
+    pub const fn to_time_delta(self) -> TimeDelta {
+        TimeDelta::microseconds(self.0)
+    }
+}
+
+impl From<i64> for Microseconds {
+    fn from(micros: i64) -> Self {
+        Self(micros)
+    }
+}
+
+impl From<Microseconds> for i64 {
+    fn from(micros: Microseconds) -> Self {
+        micros.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_serializes_as_inner_number() {
+        assert_eq!(serde_json::to_value(Kilometers(50.0)).unwrap(), json!(50.0));
+        assert_eq!(
+            serde_json::to_value(UtcOffset(-3600)).unwrap(),
+            json!(-3600)
+        );
+        let seconds: Seconds = serde_json::from_value(json!(5.5)).unwrap();
+        assert_eq!(seconds, Seconds(5.5));
+        let micros: Microseconds = serde_json::from_value(json!(1_500_000)).unwrap();
+        assert_eq!(micros.to_time_delta(), TimeDelta::milliseconds(1500));
+    }
+
+    #[test]
+    fn test_conversions() {
+        assert_eq!(
+            Seconds(1.5).to_duration(),
+            Some(Duration::from_millis(1500))
+        );
+        assert_eq!(Seconds(-1.0).to_duration(), None);
+        assert_eq!(Seconds::from(Duration::from_secs(3)), Seconds(3.0));
+        let offset = FixedOffset::east_opt(7200).unwrap();
+        assert_eq!(UtcOffset::from(offset).fixed_offset(), Some(offset));
+        assert_eq!(UtcOffset(86_400).fixed_offset(), None);
+        assert_eq!(f64::from(Kilometers::from(12.5)), 12.5);
+    }
+}
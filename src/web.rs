@@ -0,0 +1,217 @@
+//! Glue for serving the analyzer over HTTP with `axum`, behind the `web` feature.
+//!
+//! [`router`] exposes `POST /analyze`, which takes a multipart upload with a `file` field and
+//! responds with the [`MediaMetadata`] as JSON, see [`MediaAnalyzer::to_json`]. Errors map to status codes through the
+//! [`IntoResponse`] impl of [`MediaAnalyzerError`]:
+//!
+//! * 404: the file doesn't exist.
+//! * 413: the upload is larger than the limit of the router, see [`DEFAULT_UPLOAD_LIMIT`].
+//! * 415: `exiftool` can't read the file, or its type is unsupported.
+//! * 422: the file is empty or lacks essential metadata, or no capture time could be found.
+//! * 504: the weather service failed, or the analysis timed out.
+
+use crate::MediaAnalyzer;
+use crate::MediaAnalyzerError;
+use crate::features::error::{DataUrlError, WeatherError};
+use crate::structs::MediaMetadata;
+use axum::body::Bytes;
+use axum::extract::{DefaultBodyLimit, Multipart, State};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::routing::post;
+use axum::{Json, Router};
+use exiftool::ExifToolError;
+use serde_json::json;
+use std::io::ErrorKind;
+use std::sync::Arc;
+
+/// The multipart field holding the uploaded file.
+pub const UPLOAD_FIELD: &str = "file";
+
+/// The largest request body [`router`] accepts, 512 MiB. Uploads are held in memory while they
+/// are analyzed.
+pub const DEFAULT_UPLOAD_LIMIT: usize = 512 * 1024 * 1024;
+
+impl IntoResponse for MediaMetadata {
+    fn into_response(self) -> Response {
+        Json(self).into_response()
+    }
+}
+
+impl MediaAnalyzerError {
+    /// The HTTP status code that best describes this error.
+    #[must_use]
+    pub fn status_code(&self) -> StatusCode {
+        match self {
+            Self::Io(e) if matches!(e.kind(), ErrorKind::NotFound) => StatusCode::NOT_FOUND,
+            Self::Exiftool(ExifToolError::FileNotFound { .. }) => StatusCode::NOT_FOUND,
+            Self::Exiftool(ExifToolError::ExifToolProcess { .. })
+            | Self::DataUrl(DataUrlError::UnsupportedFileType(_))
+            | Self::UnsupportedFile { .. } => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            Self::Metadata(_) | Self::Time(_) | Self::EmptyFile(_) => {
+                StatusCode::UNPROCESSABLE_ENTITY
+            }
+            Self::Weather(WeatherError::ApiError(_) | WeatherError::Provider(_))
+            | Self::Meteostat(_)
+            | Self::Timeout { .. } => StatusCode::GATEWAY_TIMEOUT,
+            Self::NoThumbnail => StatusCode::BAD_REQUEST,
+            Self::Download { .. } => StatusCode::BAD_GATEWAY,
+            _ => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+impl IntoResponse for MediaAnalyzerError {
+    fn into_response(self) -> Response {
+        let body = Json(json!({ "error": self.to_string() }));
+        (self.status_code(), body).into_response()
+    }
+}
+
+/// Analyzes an uploaded file. See [`MediaAnalyzer::analyze_bytes`].
+///
+/// # Errors
+///
+/// The same as [`MediaAnalyzer::analyze_bytes`].
+pub async fn analyze_upload(
+    analyzer: &MediaAnalyzer,
+    bytes: Bytes,
+    filename: &str,
+) -> Result<MediaMetadata, MediaAnalyzerError> {
+    analyzer.analyze_bytes(&bytes, filename).await
+}
+
+async fn analyze_handler(
+    State(analyzer): State<Arc<MediaAnalyzer>>,
+    mut multipart: Multipart,
+) -> Response {
+    loop {
+        let field = match multipart.next_field().await {
+            Ok(Some(field)) => field,
+            Ok(None) => break,
+            Err(e) => return (e.status(), e.body_text()).into_response(),
+        };
+        if field.name() != Some(UPLOAD_FIELD) {
+            continue;
+        }
+        let filename = field.file_name().unwrap_or("upload").to_string();
+        return match field.bytes().await {
+            Ok(bytes) => match analyze_upload(&analyzer, bytes, &filename).await {
+                Ok(result) => match analyzer.to_json(&result) {
+                    Ok(json) => Json(json).into_response(),
+                    Err(e) => {
+                        let body = Json(json!({ "error": e.to_string() }));
+                        (StatusCode::INTERNAL_SERVER_ERROR, body).into_response()
+                    }
+                },
+                Err(e) => e.into_response(),
+            },
+            Err(e) => (e.status(), e.body_text()).into_response(),
+        };
+    }
+    (
+        StatusCode::BAD_REQUEST,
+        format!("Missing multipart field '{UPLOAD_FIELD}'"),
+    )
+        .into_response()
+}
+
+/// A router exposing `POST /analyze` for multipart uploads of up to [`DEFAULT_UPLOAD_LIMIT`]
+/// bytes. Larger requests get a 413. This replaces the 2 MB default of `axum`, which is too
+/// small for most photos.
+pub fn router(analyzer: Arc<MediaAnalyzer>) -> Router {
+    router_with_upload_limit(analyzer, DEFAULT_UPLOAD_LIMIT)
+}
+
+/// Like [`router`], accepting request bodies of up to `max_bytes`.
+pub fn router_with_upload_limit(analyzer: Arc<MediaAnalyzer>, max_bytes: usize) -> Router {
+    Router::new()
+        .route("/analyze", post(analyze_handler))
+        .layer(DefaultBodyLimit::max(max_bytes))
+        .with_state(analyzer)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+    use axum::http::Request;
+    use http_body_util::BodyExt;
+    use serde_json::Value;
+    use tower::ServiceExt;
+
+    const BOUNDARY: &str = "media-analyzer-test-boundary";
+
+    fn multipart_body(filename: &str, bytes: &[u8]) -> Vec<u8> {
+        let mut body = format!(
+            "--{BOUNDARY}\r\nContent-Disposition: form-data; name=\"{UPLOAD_FIELD}\"; filename=\"{filename}\"\r\nContent-Type: application/octet-stream\r\n\r\n"
+        )
+        .into_bytes();
+        body.extend_from_slice(bytes);
+        body.extend_from_slice(format!("\r\n--{BOUNDARY}--\r\n").as_bytes());
+        body
+    }
+
+    #[test]
+    fn test_status_codes() {
+        let not_found = MediaAnalyzerError::Io(std::io::Error::from(ErrorKind::NotFound));
+        assert_eq!(not_found.status_code(), StatusCode::NOT_FOUND);
+        let missing_field = MediaAnalyzerError::Metadata(
+            crate::features::error::MetadataError::MissingRequiredField("ImageWidth".to_string()),
+        );
+        assert_eq!(
+            missing_field.status_code(),
+            StatusCode::UNPROCESSABLE_ENTITY
+        );
+        let weather = MediaAnalyzerError::Weather(WeatherError::Provider("timeout".to_string()));
+        assert_eq!(weather.status_code(), StatusCode::GATEWAY_TIMEOUT);
+        let download = MediaAnalyzerError::Download {
+            url: "https://example.com/photo.jpg".to_string(),
+            status: Some(403),
+            reason: "403 Forbidden".to_string(),
+        };
+        assert_eq!(download.status_code(), StatusCode::BAD_GATEWAY);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_post_analyze_sunset() {
+        let analyzer = MediaAnalyzer::builder().build().await.unwrap();
+        let bytes = std::fs::read("assets/sunset.jpg").unwrap();
+        // Larger than the 2 MB default limit of `axum`.
+        assert!(bytes.len() > 2 * 1024 * 1024);
+        let request = Request::post("/analyze")
+            .header(
+                "content-type",
+                format!("multipart/form-data; boundary={BOUNDARY}"),
+            )
+            .body(Body::from(multipart_body("sunset.jpg", &bytes)))
+            .unwrap();
+
+        let response = router(Arc::new(analyzer)).oneshot(request).await.unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let result: Value = serde_json::from_slice(&body).unwrap();
+        assert!(result["gps"].is_object(), "gps should be present");
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_post_analyze_over_the_upload_limit() {
+        let analyzer = MediaAnalyzer::builder().build().await.unwrap();
+        let bytes = std::fs::read("assets/sunset.jpg").unwrap();
+        let request = Request::post("/analyze")
+            .header(
+                "content-type",
+                format!("multipart/form-data; boundary={BOUNDARY}"),
+            )
+            .body(Body::from(multipart_body("sunset.jpg", &bytes)))
+            .unwrap();
+
+        let response = router_with_upload_limit(Arc::new(analyzer), 1024 * 1024)
+            .oneshot(request)
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+    }
+}
@@ -0,0 +1,76 @@
+//! Measures the memory use of low-memory mode. This test gets its own process, because the
+//! timezone finders and geocoding datasets are loaded once per process and never freed.
+//!
+//! Run with `cargo test --features memory-tests --test low_memory`.
+#![cfg(feature = "memory-tests")]
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use media_analyzer::{MediaAnalyzer, WeatherError, WeatherObservation, WeatherProvider};
+use std::path::PathBuf;
+use std::sync::Arc;
+
+/// Keeps Meteostat, which has its own memory use, out of the measurement.
+struct NoWeather;
+
+#[async_trait]
+impl WeatherProvider for NoWeather {
+    async fn hourly_at(
+        &self,
+        _latitude: f64,
+        _longitude: f64,
+        _datetime: DateTime<Utc>,
+    ) -> Result<WeatherObservation, WeatherError> {
+        Err(WeatherError::NoDataAvailable)
+    }
+}
+
+fn fixtures() -> Vec<PathBuf> {
+    [
+        "sunset.jpg",
+        "tent.jpg",
+        "cluster.jpg",
+        "hdr.jpg",
+        "iphone.HEIC",
+    ]
+    .iter()
+    .map(|name| PathBuf::from("assets").join(name))
+    .collect()
+}
+
+fn physical_mem_bytes() -> usize {
+    memory_stats::memory_stats()
+        .expect("memory stats are supported on this platform")
+        .physical_mem
+}
+
+/// Builds an analyzer and analyzes all fixtures, returning by how much the resident memory grew.
+async fn memory_growth(low_memory: bool) -> usize {
+    let before = physical_mem_bytes();
+    let analyzer = MediaAnalyzer::builder()
+        .weather_provider(Arc::new(NoWeather))
+        .low_memory(low_memory)
+        .build()
+        .await
+        .unwrap();
+    let mut results = Vec::new();
+    for path in fixtures() {
+        results.push(analyzer.analyze_media(&path).await.unwrap());
+    }
+    assert!(results.iter().any(|result| result.gps.is_some()));
+    physical_mem_bytes().saturating_sub(before)
+}
+
+#[tokio::test]
+async fn test_low_memory_mode_uses_materially_less_memory() {
+    // Low-memory mode goes first: the default mode's finder and dataset are separate, so they
+    // are still loaded afresh afterwards.
+    let low_memory = memory_growth(true).await;
+    let default = memory_growth(false).await;
+    assert!(
+        low_memory * 2 < default,
+        "low-memory mode grew by {}MB, the default by {}MB",
+        low_memory >> 20,
+        default >> 20
+    );
+}
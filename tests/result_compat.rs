@@ -0,0 +1,80 @@
+//! Stored results must keep loading after an upgrade. `tests/result_snapshots` holds a result
+//! of the current version with every field set, and one trimmed to the fields 0.10 wrote.
+//!
+//! When a field is added to a result struct, add it to the current snapshot. When an older
+//! snapshot fails to load, the new field needs a `#[serde(default)]`.
+
+use media_analyzer::{
+    AspectClass, CameraSettings, MediaFeatures, MediaMetadata, TimeSource, UtcOffset,
+};
+use serde_json::Value;
+use std::fs;
+use std::path::Path;
+
+fn snapshot(version: &str) -> String {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/result_snapshots")
+        .join(format!("{version}.json"));
+    fs::read_to_string(path).unwrap()
+}
+
+#[test]
+fn test_current_snapshot_round_trips() {
+    let json = snapshot("0.11");
+    let result: MediaMetadata = serde_json::from_str(&json).unwrap();
+
+    // Every field of the snapshot is read, and written back the same.
+    let expected: Value = serde_json::from_str(&json).unwrap();
+    assert_eq!(serde_json::to_value(&result).unwrap(), expected);
+
+    let reloaded: MediaMetadata =
+        serde_json::from_str(&serde_json::to_string(&result).unwrap()).unwrap();
+    assert_eq!(reloaded, result);
+}
+
+#[test]
+fn test_old_snapshot_loads_with_defaults() {
+    let result: MediaMetadata = serde_json::from_str(&snapshot("0.10")).unwrap();
+
+    assert_eq!(result.basic.width, 4080);
+    assert_eq!(result.basic.original_filename, None);
+    assert!(!result.features.is_night_mode);
+    assert!(!result.features.is_animated);
+    assert_eq!(result.features.aspect_class, None);
+    assert_eq!(result.camera.camera_module, None);
+    assert_eq!(result.time.datetime_local_iso, None);
+    let timezone = result.time.timezone.unwrap();
+    assert_eq!(timezone.offset_seconds, UtcOffset(7200));
+    assert!(!result.time.source_details.future_timestamp);
+    // The free-form time source of older versions is kept as the detail.
+    assert_eq!(
+        result.time.source_details.time_source,
+        TimeSource::DateTimeOriginal
+    );
+    assert_eq!(
+        result.time.source_details.time_source_display(),
+        "DateTimeOriginal (IANA from GPS)"
+    );
+    let gps = result.gps.unwrap();
+    assert_eq!(gps.location.unwrap().admin1, "");
+    assert_eq!(gps.timezone, None);
+    let weather = result.weather.unwrap();
+    assert_eq!(weather.search_radius_used_km, 0.0);
+    assert_eq!(weather.sun_info.day_length_seconds, None);
+    assert!(result.quality_hints.is_empty());
+    assert!(result.aux_images.is_empty());
+    assert!(result.notes.is_empty());
+    assert_eq!(result.provenance, None);
+}
+
+#[test]
+fn test_defaults_and_unknown_variants() {
+    let features: MediaFeatures = serde_json::from_str("{}").unwrap();
+    assert_eq!(features, MediaFeatures::default());
+    let camera: CameraSettings = serde_json::from_str("{}").unwrap();
+    assert_eq!(camera, CameraSettings::default());
+
+    // Values written by a later version fall back instead of failing the whole result.
+    let aspect: AspectClass = serde_json::from_str(r#""landscape21x9""#).unwrap();
+    assert_eq!(aspect, AspectClass::Other);
+}
@@ -0,0 +1,82 @@
+//! Reads the cases in `tests/time_corpus`, for its test and the `regenerate_time_corpus`
+//! example.
+//!
+//! A case is a `<name>.json` with a `description`, the `exif` JSON of a file and optionally the
+//! `now` to check for future timestamps against. Its `<name>.expected.json` holds the
+//! `TimeInfo` it resolves to, or `{ "error": ... }`.
+
+use chrono::{DateTime, Utc};
+use media_analyzer::detectors::resolve_time;
+use media_analyzer::{ExifData, TimeOptions};
+use serde::Deserialize;
+use serde_json::{Value, json};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+pub const CORPUS_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/time_corpus");
+
+const EXPECTED_SUFFIX: &str = ".expected.json";
+
+/// The `now` of cases without one, so that their results don't change over time.
+const DEFAULT_NOW: &str = "2025-01-01T00:00:00Z";
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Case {
+    #[serde(skip)]
+    pub name: String,
+    pub description: String,
+    pub now: Option<DateTime<Utc>>,
+    pub exif: Value,
+}
+
+impl Case {
+    pub fn expected_path(&self) -> PathBuf {
+        Path::new(CORPUS_DIR).join(format!("{}{EXPECTED_SUFFIX}", self.name))
+    }
+
+    /// The `TimeInfo` the case resolves to as JSON, or the error.
+    pub fn resolve(&self) -> Value {
+        let options = TimeOptions {
+            now: Some(self.now.unwrap_or_else(|| DEFAULT_NOW.parse().unwrap())),
+            ..TimeOptions::default()
+        };
+        match resolve_time(&ExifData::new(self.exif.clone()), &options) {
+            Ok(time) => serde_json::to_value(time).unwrap(),
+            Err(e) => json!({ "error": e.to_string() }),
+        }
+    }
+}
+
+/// All cases, ordered by name.
+pub fn load_cases() -> Vec<Case> {
+    let mut cases: Vec<Case> = fs::read_dir(CORPUS_DIR)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| {
+            let name = path.file_name().unwrap().to_string_lossy();
+            name.ends_with(".json") && !name.ends_with(EXPECTED_SUFFIX)
+        })
+        .map(|path| {
+            let text = fs::read_to_string(&path).unwrap();
+            let mut case: Case = serde_json::from_str(&text)
+                .unwrap_or_else(|e| panic!("{} is not a valid case: {e}", path.display()));
+            case.name = path.file_stem().unwrap().to_string_lossy().into_owned();
+            case
+        })
+        .collect();
+    cases.sort_by(|a, b| a.name.cmp(&b.name));
+    cases
+}
+
+/// The names of expectation files without a case.
+pub fn orphaned_expectations(cases: &[Case]) -> Vec<String> {
+    fs::read_dir(CORPUS_DIR)
+        .unwrap()
+        .filter_map(|entry| {
+            let name = entry.unwrap().file_name().to_string_lossy().into_owned();
+            let case = name.strip_suffix(EXPECTED_SUFFIX)?.to_string();
+            (!cases.iter().any(|c| c.name == case)).then_some(name)
+        })
+        .collect()
+}
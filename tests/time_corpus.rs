@@ -0,0 +1,95 @@
+//! Golden-file tests of capture time resolution over the cases in `tests/time_corpus`, see
+//! `tests/support/time_corpus.rs` for their format.
+//!
+//! After a deliberate change of the time logic, regenerate the expectations with
+//! `cargo run --example regenerate_time_corpus -- --write` and review the diff.
+
+#[path = "support/time_corpus.rs"]
+mod time_corpus;
+
+use serde_json::Value;
+use std::fs;
+use time_corpus::{load_cases, orphaned_expectations};
+
+/// Numbers closer than this are equal, for floats like `boundaryDistanceKm`.
+const TOLERANCE: f64 = 1e-6;
+
+/// Collects the differences between `expected` and `actual` as `path: expected …, got …`.
+/// A missing field equals `null`.
+fn diff(path: &str, expected: &Value, actual: &Value, differences: &mut Vec<String>) {
+    match (expected, actual) {
+        (Value::Object(expected), Value::Object(actual)) => {
+            let mut keys: Vec<&String> = expected.keys().chain(actual.keys()).collect();
+            keys.sort();
+            keys.dedup();
+            for key in keys {
+                diff(
+                    &format!("{path}.{key}"),
+                    expected.get(key).unwrap_or(&Value::Null),
+                    actual.get(key).unwrap_or(&Value::Null),
+                    differences,
+                );
+            }
+        }
+        (Value::Array(expected), Value::Array(actual)) if expected.len() == actual.len() => {
+            for (index, (expected, actual)) in expected.iter().zip(actual).enumerate() {
+                diff(&format!("{path}[{index}]"), expected, actual, differences);
+            }
+        }
+        (Value::Number(expected_number), Value::Number(actual_number))
+            if (expected_number.as_f64().unwrap() - actual_number.as_f64().unwrap()).abs()
+                <= TOLERANCE => {}
+        _ if expected == actual => {}
+        _ => differences.push(format!("{path}: expected {expected}, got {actual}")),
+    }
+}
+
+#[test]
+fn test_time_corpus() {
+    let cases = load_cases();
+    assert!(cases.len() >= 15, "only {} cases", cases.len());
+    assert_eq!(orphaned_expectations(&cases), Vec::<String>::new());
+
+    let mut failures = Vec::new();
+    for case in &cases {
+        let Ok(expected) = fs::read_to_string(case.expected_path()) else {
+            failures.push(format!(
+                "{}: no {}",
+                case.name,
+                case.expected_path().display()
+            ));
+            continue;
+        };
+        let expected: Value = serde_json::from_str(&expected).unwrap();
+        let mut differences = Vec::new();
+        diff("$", &expected, &case.resolve(), &mut differences);
+        if !differences.is_empty() {
+            failures.push(format!(
+                "{} ({}):\n    {}",
+                case.name,
+                case.description,
+                differences.join("\n    ")
+            ));
+        }
+    }
+    assert!(
+        failures.is_empty(),
+        "{} of {} time corpus cases differ. Regenerate the expectations with \
+         `cargo run --example regenerate_time_corpus -- --write` if this is deliberate.\n\n{}",
+        failures.len(),
+        cases.len(),
+        failures.join("\n\n")
+    );
+}
+
+#[test]
+fn test_diff_is_tolerant() {
+    let mut differences = Vec::new();
+    diff(
+        "$",
+        &serde_json::json!({ "a": 1.0, "b": null, "c": [1, 2] }),
+        &serde_json::json!({ "a": 1.000_000_1, "c": [1, 3] }),
+        &mut differences,
+    );
+    assert_eq!(differences, vec!["$.c[1]: expected 2, got 3"]);
+}